@@ -6,10 +6,14 @@ use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> Result<(), rquest::Error> {
-    // Build a client to impersonate Chrome130
+    // Build a client to impersonate Chrome130, querying specific nameservers
+    // with custom search domains
     let client = rquest::Client::builder()
         .impersonate(Impersonate::Chrome130)
         .hickory_dns_strategy(LookupIpStrategy::Ipv4Only)
+        .hickory_dns_nameservers(["1.1.1.1:53".parse().unwrap(), "8.8.8.8:53".parse().unwrap()])
+        .hickory_dns_ndots(1)
+        .hickory_dns_search(["peet.ws"])
         .build()?;
 
     // Use the API you're already familiar with
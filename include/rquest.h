@@ -0,0 +1,50 @@
+/*
+ * C API of rquest, built with the `ffi` feature.
+ *
+ * Strings are NUL-terminated UTF-8. Functions failing return NULL, and
+ * rquest_last_error() then describes the error. They must not be called
+ * from a thread running a Tokio runtime.
+ */
+#ifndef RQUEST_H
+#define RQUEST_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct RquestClient RquestClient;
+typedef struct RquestResponse RquestResponse;
+
+/* The error of the last call on this thread, or NULL if it succeeded. */
+const char *rquest_last_error(void);
+
+/* A client impersonating a profile such as "chrome_131", or none if NULL. */
+RquestClient *rquest_client_new(const char *impersonate);
+void rquest_client_free(RquestClient *client);
+
+/*
+ * Send a request and read its response whole. `headers` holds one
+ * "Name: value" header per line, or is NULL; `body` is NULL for no body.
+ */
+RquestResponse *rquest_request(const RquestClient *client,
+                               const char *method,
+                               const char *url,
+                               const char *headers,
+                               const uint8_t *body,
+                               size_t body_len);
+
+/* The pointers returned below are valid until the response is freed. */
+uint16_t rquest_response_status(const RquestResponse *res);
+const char *rquest_response_url(const RquestResponse *res);
+const char *rquest_response_headers(const RquestResponse *res);
+const uint8_t *rquest_response_body(const RquestResponse *res, size_t *len);
+void rquest_response_free(RquestResponse *res);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RQUEST_H */
@@ -0,0 +1,260 @@
+//! A small command line client for trying out impersonation profiles.
+//!
+//! ```text
+//! rquest-cli -p firefox_133 --os windows -i https://example.com
+//! rquest-cli -p chrome_131 -d '{"a":1}' -H 'content-type: application/json' https://httpbin.org/post
+//! rquest-cli -p safari_18 --self-test
+//! ```
+use std::process::ExitCode;
+use std::time::Duration;
+
+use rquest::header::{HeaderName, HeaderValue};
+use rquest::{redirect, Client, Impersonate, ImpersonateOS, ImpersonateOption, Method, Proxy};
+
+const SELF_TEST_URL: &str = "https://tls.peet.ws/api/all";
+
+const USAGE: &str = "\
+Usage: rquest-cli [OPTIONS] [URL]
+
+Options:
+  -X, --request <METHOD>      Request method (default: GET, or POST with --data)
+  -d, --data <DATA>           Request body
+  -H, --header <NAME: VALUE>  Add a request header; may be repeated
+  -p, --impersonate <NAME>    Profile to impersonate, e.g. chrome_131, firefox_133, safari_18
+      --os <OS>               Operating system: windows, macos, linux, android, ios
+  -x, --proxy <URL>           Send requests through a proxy
+  -L, --location              Follow redirects
+  -k, --insecure              Accept invalid certificates
+  -m, --max-time <SECONDS>    Total timeout
+  -i, --include               Print the status line and headers, in received order
+  -v, --verbose               Also print the headers of the last request, in sent order
+      --self-test             Query a fingerprinting service and print what it saw
+  -h, --help                  Print this help
+";
+
+#[derive(Default)]
+struct Args {
+    method: Option<Method>,
+    data: Option<String>,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    impersonate: Option<Impersonate>,
+    os: Option<ImpersonateOS>,
+    proxy: Option<String>,
+    location: bool,
+    insecure: bool,
+    max_time: Option<Duration>,
+    include: bool,
+    verbose: bool,
+    self_test: bool,
+    url: Option<String>,
+}
+
+fn parse_args() -> Result<Option<Args>, String> {
+    let mut args = Args::default();
+    let mut iter = std::env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .ok_or_else(|| format!("missing value for {}", name))
+        };
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-X" | "--request" => {
+                let method = value(&arg)?;
+                args.method = Some(
+                    Method::from_bytes(method.to_uppercase().as_bytes())
+                        .map_err(|_| format!("invalid method: {}", method))?,
+                );
+            }
+            "-d" | "--data" => args.data = Some(value(&arg)?),
+            "-H" | "--header" => {
+                let header = value(&arg)?;
+                let (name, val) = header
+                    .split_once(':')
+                    .ok_or_else(|| format!("invalid header: {}", header))?;
+                let name = HeaderName::from_bytes(name.trim().as_bytes())
+                    .map_err(|_| format!("invalid header name: {}", name))?;
+                let val = HeaderValue::from_str(val.trim())
+                    .map_err(|_| format!("invalid header value: {}", val))?;
+                args.headers.push((name, val));
+            }
+            "-p" | "--impersonate" => {
+                let name = value(&arg)?;
+                args.impersonate = Some(
+                    serde_json::from_value(serde_json::Value::String(name.clone()))
+                        .map_err(|_| format!("unknown profile: {}", name))?,
+                );
+            }
+            "--os" => {
+                let name = value(&arg)?;
+                args.os = Some(
+                    serde_json::from_value(serde_json::Value::String(name.clone()))
+                        .map_err(|_| format!("unknown operating system: {}", name))?,
+                );
+            }
+            "-x" | "--proxy" => args.proxy = Some(value(&arg)?),
+            "-L" | "--location" => args.location = true,
+            "-k" | "--insecure" => args.insecure = true,
+            "-m" | "--max-time" => {
+                let secs = value(&arg)?;
+                let max_time = secs
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                    .ok_or_else(|| format!("invalid timeout: {}", secs))?;
+                args.max_time = Some(max_time);
+            }
+            "-i" | "--include" => args.include = true,
+            "-v" | "--verbose" => args.verbose = true,
+            "--self-test" => args.self_test = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ if args.url.is_none() => args.url = Some(arg),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+
+    if args.url.is_none() && !args.self_test {
+        return Err("missing URL".to_owned());
+    }
+
+    Ok(Some(args))
+}
+
+fn build_client(args: &Args) -> Result<Client, rquest::Error> {
+    let mut builder = Client::builder()
+        .debug_capture(args.verbose)
+        .danger_accept_invalid_certs(args.insecure)
+        .redirect(if args.location {
+            redirect::Policy::default()
+        } else {
+            redirect::Policy::none()
+        });
+
+    if args.impersonate.is_some() || args.os.is_some() {
+        builder = builder.impersonate(
+            ImpersonateOption::builder()
+                .impersonate(args.impersonate.unwrap_or_default())
+                .impersonate_os(args.os.unwrap_or_default())
+                .build(),
+        );
+    }
+
+    if let Some(ref proxy) = args.proxy {
+        builder = builder.proxy(Proxy::all(proxy)?);
+    }
+
+    if let Some(max_time) = args.max_time {
+        builder = builder.timeout(max_time);
+    }
+
+    builder.build()
+}
+
+async fn run(args: Args) -> Result<(), rquest::Error> {
+    let client = build_client(&args)?;
+
+    if args.self_test {
+        return self_test(&client).await;
+    }
+
+    let url = args.url.as_deref().expect("checked by parse_args");
+    let method = match (args.method, &args.data) {
+        (Some(method), _) => method,
+        (None, Some(_)) => Method::POST,
+        (None, None) => Method::GET,
+    };
+
+    let mut request = client.request(method.clone(), url);
+    for (name, value) in args.headers {
+        request = request.header(name, value);
+    }
+    if let Some(data) = args.data {
+        request = request.body(data);
+    }
+
+    let response = request.send().await?;
+
+    // the headers the connection wrote, once the client ordered them
+    if let Some(capture) = response.debug_capture() {
+        eprintln!("> {} {} {:?}", method, response.url(), capture.version());
+        for (name, value) in capture.headers() {
+            eprintln!("> {}: {}", name, String::from_utf8_lossy(value.as_bytes()));
+        }
+        eprintln!(">");
+    }
+
+    if args.include || args.verbose {
+        println!("{:?} {}", response.version(), response.status());
+        for (name, value) in response.headers() {
+            println!("{}: {}", name, String::from_utf8_lossy(value.as_bytes()));
+        }
+        println!();
+    }
+
+    println!("{}", response.text().await?);
+    Ok(())
+}
+
+async fn self_test(client: &Client) -> Result<(), rquest::Error> {
+    let report: serde_json::Value = client.get(SELF_TEST_URL).send().await?.json().await?;
+
+    let fields = [
+        ("http version", "/http_version"),
+        ("user agent", "/user_agent"),
+        ("ja3", "/tls/ja3"),
+        ("ja3 hash", "/tls/ja3_hash"),
+        ("ja4", "/tls/ja4"),
+        ("peetprint hash", "/tls/peetprint_hash"),
+        ("akamai", "/http2/akamai_fingerprint"),
+        ("akamai hash", "/http2/akamai_fingerprint_hash"),
+    ];
+    for (label, pointer) in fields {
+        if let Some(value) = report.pointer(pointer).and_then(|v| v.as_str()) {
+            println!("{:<16}{}", label, value);
+        }
+    }
+
+    // The headers in the order the server received them
+    let frames = report
+        .pointer("/http2/sent_frames")
+        .and_then(|v| v.as_array());
+    let headers = frames
+        .into_iter()
+        .flatten()
+        .find(|frame| frame["frame_type"] == "HEADERS")
+        .and_then(|frame| frame["headers"].as_array())
+        .or_else(|| report.pointer("/http1/headers").and_then(|v| v.as_array()));
+    if let Some(headers) = headers {
+        println!("header order");
+        for header in headers.iter().filter_map(|h| h.as_str()) {
+            println!("  {}", header);
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! A cache store in files, and the encoding of its entries.
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use boring2::hash::{hash, MessageDigest};
+use bytes::Bytes;
+use http::header::{HeaderName, HeaderValue};
+use http::{HeaderMap, StatusCode, Version};
+use log::debug;
+
+use super::{CacheEntry, CacheStore};
+use crate::util::fast_random;
+
+/// The first line of an encoded entry, telling its format.
+const MAGIC: &[u8] = b"RQUEST-CACHE/1\r\n";
+
+/// A [`CacheStore`] keeping each response in a file of a directory.
+///
+/// The files are written whole then renamed, so several clients, even in
+/// other processes, may share a directory: they see each other's
+/// responses, and never a partly written one. The files are read and
+/// written on the blocking threads of the runtime.
+///
+/// # Example
+///
+/// ```no_run
+/// use rquest::cache::{DiskStore, HttpCache};
+///
+/// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let store = DiskStore::open("/var/cache/crawler")?;
+/// let client = rquest::Client::builder()
+///     .http_cache(HttpCache::with_store(store))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DiskStore {
+    dir: PathBuf,
+}
+
+impl DiskStore {
+    /// Open the store in `dir`, creating the directory if it is missing.
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<DiskStore> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(DiskStore { dir })
+    }
+
+    /// Remove every entry of the store.
+    pub fn clear(&self) -> io::Result<()> {
+        for file in fs::read_dir(&self.dir)? {
+            let path = file?.path();
+            if path.extension().is_some_and(|ext| ext == "entry") {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        let digest =
+            hash(MessageDigest::sha256(), key.as_bytes()).expect("hashing in memory does not fail");
+        let name = digest.iter().fold(String::new(), |mut hex, b| {
+            let _ = write!(hex, "{b:02x}");
+            hex
+        });
+        self.dir.join(name).with_extension("entry")
+    }
+}
+
+impl CacheStore for DiskStore {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        let path = self.path(key);
+        let data = fs::read(&path).ok()?;
+        match CacheEntry::decode(&data) {
+            // two keys with the same digest would only be another's entry
+            Ok(entry) if super::cache_key(&entry.url) == key => Some(entry),
+            Ok(_) => None,
+            Err(err) => {
+                debug!(
+                    "removing unreadable cache entry {}: {}",
+                    path.display(),
+                    err
+                );
+                let _ = fs::remove_file(path);
+                None
+            }
+        }
+    }
+
+    fn put(&self, key: &str, entry: CacheEntry) {
+        let path = self.path(key);
+        let temp = path.with_extension(format!("{:016x}.tmp", fast_random()));
+        let result = fs::write(&temp, entry.encode()).and_then(|()| fs::rename(&temp, &path));
+        if let Err(err) = result {
+            debug!("failed to store cache entry {}: {}", path.display(), err);
+            let _ = fs::remove_file(temp);
+        }
+    }
+
+    fn delete(&self, key: &str) {
+        let _ = fs::remove_file(self.path(key));
+    }
+}
+
+// ===== encoding =====
+
+impl CacheEntry {
+    /// Encode the entry, metadata and body, to keep it out of memory, such
+    /// as in the files of a [`DiskStore`] or in a shared database.
+    ///
+    /// The encoding is an HTTP/1.1 message with a line of metadata before
+    /// it, and the request headers it varies on after its headers.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.body.len() + 512);
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(
+            format!(
+                "{} {} {}\r\n",
+                millis(self.request_time),
+                millis(self.response_time),
+                self.url
+            )
+            .as_bytes(),
+        );
+        buf.extend_from_slice(
+            format!("{} {}\r\n", version_str(self.version), self.status.as_u16()).as_bytes(),
+        );
+        for headers in [&self.headers, &self.vary] {
+            for (name, value) in headers {
+                buf.extend_from_slice(name.as_str().as_bytes());
+                buf.extend_from_slice(b": ");
+                buf.extend_from_slice(value.as_bytes());
+                buf.extend_from_slice(b"\r\n");
+            }
+            buf.extend_from_slice(b"\r\n");
+        }
+        buf.extend_from_slice(&self.body);
+        buf
+    }
+
+    /// Decode an entry encoded by [`CacheEntry::encode`].
+    pub fn decode(data: &[u8]) -> io::Result<CacheEntry> {
+        let data = data
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| invalid("not a cache entry"))?;
+        let mut lines = Lines { data };
+
+        let meta = lines.line_str()?;
+        let mut meta = meta.splitn(3, ' ');
+        let request_time = parse_millis(meta.next())?;
+        let response_time = parse_millis(meta.next())?;
+        let url = meta
+            .next()
+            .and_then(|url| url.parse().ok())
+            .ok_or_else(|| invalid("invalid URL"))?;
+
+        let status_line = lines.line_str()?;
+        let (version, status) = status_line
+            .split_once(' ')
+            .ok_or_else(|| invalid("invalid status line"))?;
+        let version = match version {
+            "HTTP/0.9" => Version::HTTP_09,
+            "HTTP/1.0" => Version::HTTP_10,
+            "HTTP/1.1" => Version::HTTP_11,
+            "HTTP/2.0" => Version::HTTP_2,
+            "HTTP/3.0" => Version::HTTP_3,
+            _ => return Err(invalid("invalid version")),
+        };
+        let status = status
+            .parse()
+            .ok()
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .ok_or_else(|| invalid("invalid status"))?;
+
+        let headers = lines.headers()?;
+        let vary = lines.headers()?;
+        Ok(CacheEntry {
+            url,
+            status,
+            version,
+            headers,
+            body: Bytes::copy_from_slice(lines.data),
+            vary,
+            request_time,
+            response_time,
+        })
+    }
+}
+
+struct Lines<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Lines<'a> {
+    fn line(&mut self) -> io::Result<&'a [u8]> {
+        let end = self
+            .data
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(|| invalid("truncated entry"))?;
+        let line = &self.data[..end];
+        self.data = &self.data[end + 2..];
+        Ok(line)
+    }
+
+    fn line_str(&mut self) -> io::Result<&'a str> {
+        std::str::from_utf8(self.line()?).map_err(|_| invalid("invalid line"))
+    }
+
+    /// Header lines, up to an empty line.
+    fn headers(&mut self) -> io::Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        loop {
+            let line = self.line()?;
+            if line.is_empty() {
+                return Ok(headers);
+            }
+            let colon = line
+                .iter()
+                .position(|b| *b == b':')
+                .ok_or_else(|| invalid("invalid header"))?;
+            let name = HeaderName::from_bytes(&line[..colon]).map_err(invalid)?;
+            let value = line[colon + 1..]
+                .strip_prefix(b" ")
+                .unwrap_or(&line[colon + 1..]);
+            headers.append(name, HeaderValue::from_bytes(value).map_err(invalid)?);
+        }
+    }
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+fn millis(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+fn parse_millis(field: Option<&str>) -> io::Result<SystemTime> {
+    field
+        .and_then(|field| field.parse().ok())
+        .map(|millis| UNIX_EPOCH + Duration::from_millis(millis))
+        .ok_or_else(|| invalid("invalid time"))
+}
+
+fn version_str(version: Version) -> &'static str {
+    match version {
+        Version::HTTP_09 => "HTTP/0.9",
+        Version::HTTP_10 => "HTTP/1.0",
+        Version::HTTP_2 => "HTTP/2.0",
+        Version::HTTP_3 => "HTTP/3.0",
+        _ => "HTTP/1.1",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_encoding_round_trips() {
+        let mut headers = HeaderMap::new();
+        headers.insert("cache-control", HeaderValue::from_static("max-age=60"));
+        headers.append("set-cookie", HeaderValue::from_static("a=1"));
+        headers.append("set-cookie", HeaderValue::from_static("b=2"));
+        let mut vary = HeaderMap::new();
+        vary.insert("accept-language", HeaderValue::from_static("en"));
+        let entry = CacheEntry {
+            url: "https://example.com/a?b=c".parse().unwrap(),
+            status: StatusCode::NOT_FOUND,
+            version: Version::HTTP_2,
+            headers,
+            body: Bytes::from_static(b"\r\n\r\nbinary\0body"),
+            vary,
+            request_time: UNIX_EPOCH + Duration::from_millis(1_700_000_000_123),
+            response_time: UNIX_EPOCH + Duration::from_millis(1_700_000_000_456),
+        };
+
+        let decoded = CacheEntry::decode(&entry.encode()).unwrap();
+        assert_eq!(decoded.url, entry.url);
+        assert_eq!(decoded.status, entry.status);
+        assert_eq!(decoded.version, entry.version);
+        assert_eq!(decoded.headers, entry.headers);
+        assert_eq!(decoded.vary, entry.vary);
+        assert_eq!(decoded.body, entry.body);
+        assert_eq!(decoded.request_time, entry.request_time);
+        assert_eq!(decoded.response_time, entry.response_time);
+
+        assert!(CacheEntry::decode(b"HTTP/1.1 200 OK\r\n\r\n").is_err());
+    }
+}
@@ -0,0 +1,544 @@
+//! HTTP caching, as of [RFC 9111].
+//!
+//! A client built with [`ClientBuilder::http_cache`] keeps the responses to
+//! its `GET` requests, and answers the same requests with them while they
+//! are fresh, without a round trip. Stale responses are revalidated with
+//! `If-None-Match` and `If-Modified-Since`, so an unchanged resource costs
+//! a `304 Not Modified` instead of its body.
+//!
+//! The cache is private: it follows the `Cache-Control` directives of a
+//! browser's cache, `private` responses included, and `s-maxage` ignored.
+//! Where the responses are kept is up to a [`CacheStore`]: in memory by
+//! default, or in files with a [`DiskStore`], to keep them across runs and
+//! share them between processes.
+//!
+//! # Example
+//!
+//! ```
+//! use rquest::cache::{CacheStatus, HttpCache};
+//!
+//! # async fn run() -> Result<(), rquest::Error> {
+//! let client = rquest::Client::builder()
+//!     .http_cache(HttpCache::new())
+//!     .build()?;
+//!
+//! let res = client.get("https://example.com/feed").send().await?;
+//! if res.cache_status() == Some(CacheStatus::Hit) {
+//!     println!("no request sent");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [RFC 9111]: https://datatracker.ietf.org/doc/html/rfc9111
+//! [`ClientBuilder::http_cache`]: crate::ClientBuilder::http_cache
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use antidote::Mutex;
+use bytes::Bytes;
+use http::header::{
+    HeaderName, HeaderValue, AGE, CONTENT_LENGTH, CONTENT_LOCATION, ETAG, IF_MATCH,
+    IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_UNMODIFIED_SINCE, LAST_MODIFIED, LOCATION, RANGE, VARY,
+};
+use http::{HeaderMap, Method, StatusCode, Version};
+use log::{debug, trace};
+use lru::LruCache;
+use url::Url;
+
+pub use self::disk::DiskStore;
+
+use self::policy::CacheControl;
+use crate::{Client, Request, Response, ResponseBuilderExt};
+
+mod disk;
+mod policy;
+
+/// The storage of the responses of an [`HttpCache`].
+///
+/// The entries are keyed by the URL of their request. A store may drop
+/// any entry at any time, such as to bound its size. Stores out of memory
+/// can save the entries with [`CacheEntry::encode`].
+///
+/// The methods are called on the blocking threads of the runtime, so they
+/// may block on files or a database.
+pub trait CacheStore: Send + Sync + 'static {
+    /// Get the entry stored under `key`.
+    fn get(&self, key: &str) -> Option<CacheEntry>;
+    /// Store `entry` under `key`, replacing any other.
+    fn put(&self, key: &str, entry: CacheEntry);
+    /// Remove the entry stored under `key`.
+    fn delete(&self, key: &str);
+}
+
+/// A [`CacheStore`] in memory, dropping the least recently used responses
+/// past a total size of their bodies.
+pub struct MemoryStore {
+    inner: Mutex<MemoryInner>,
+}
+
+struct MemoryInner {
+    entries: LruCache<String, CacheEntry>,
+    size: usize,
+    max_size: usize,
+}
+
+/// A response kept by an [`HttpCache`], with what is needed to tell its age
+/// and to match it with the requests it answers.
+#[derive(Clone, Debug)]
+pub struct CacheEntry {
+    url: Url,
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    body: Bytes,
+    /// The headers of the request named by the `Vary` of the response.
+    vary: HeaderMap,
+    request_time: SystemTime,
+    response_time: SystemTime,
+}
+
+/// How an [`HttpCache`] answered a request, as told by
+/// [`Response::cache_status`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheStatus {
+    /// The response was fetched from the server.
+    Miss,
+    /// A fresh stored response answered, without a request.
+    Hit,
+    /// A stale stored response answered, as allowed by
+    /// `stale-while-revalidate`, `stale-if-error` or `max-stale`.
+    Stale,
+    /// The server confirmed a stored response with `304 Not Modified`.
+    Revalidated,
+}
+
+/// An HTTP cache for the responses of a client.
+///
+/// It is shared by its clones, so one cache can serve several clients.
+///
+/// To use one, refer to [`ClientBuilder::http_cache`].
+///
+/// [`ClientBuilder::http_cache`]: crate::ClientBuilder::http_cache
+#[derive(Clone)]
+pub struct HttpCache {
+    store: Arc<dyn CacheStore>,
+    max_body_size: u64,
+}
+
+// ===== impl MemoryStore =====
+
+impl MemoryStore {
+    /// Create a store keeping up to `max_size` bytes of bodies.
+    pub fn new(max_size: usize) -> MemoryStore {
+        MemoryStore {
+            inner: Mutex::new(MemoryInner {
+                entries: LruCache::unbounded(),
+                size: 0,
+                max_size,
+            }),
+        }
+    }
+}
+
+impl Default for MemoryStore {
+    /// A store keeping up to 64 MiB of bodies.
+    fn default() -> MemoryStore {
+        MemoryStore::new(64 * 1024 * 1024)
+    }
+}
+
+impl CacheStore for MemoryStore {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        self.inner.lock().entries.get(key).cloned()
+    }
+
+    fn put(&self, key: &str, entry: CacheEntry) {
+        let mut inner = self.inner.lock();
+        inner.size += entry.body.len();
+        if let Some(old) = inner.entries.put(key.to_owned(), entry) {
+            inner.size -= old.body.len();
+        }
+        while inner.size > inner.max_size {
+            match inner.entries.pop_lru() {
+                Some((_, old)) => inner.size -= old.body.len(),
+                None => break,
+            }
+        }
+    }
+
+    fn delete(&self, key: &str) {
+        let mut inner = self.inner.lock();
+        if let Some(old) = inner.entries.pop(key) {
+            inner.size -= old.body.len();
+        }
+    }
+}
+
+impl fmt::Debug for MemoryStore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let inner = self.inner.lock();
+        f.debug_struct("MemoryStore")
+            .field("entries", &inner.entries.len())
+            .field("size", &inner.size)
+            .field("max_size", &inner.max_size)
+            .finish()
+    }
+}
+
+// ===== impl CacheEntry =====
+
+impl CacheEntry {
+    /// The URL of the response.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// The status of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// The headers of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The body of the response, decompressed.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// When the request was sent.
+    pub fn request_time(&self) -> SystemTime {
+        self.request_time
+    }
+
+    /// When the response was received.
+    pub fn response_time(&self) -> SystemTime {
+        self.response_time
+    }
+
+    /// Update the stored headers with those of a `304 Not Modified`
+    /// ([RFC 9111, section 3.2]).
+    ///
+    /// [RFC 9111, section 3.2]: https://datatracker.ietf.org/doc/html/rfc9111#section-3.2
+    fn freshen(&mut self, headers: &HeaderMap, request_time: SystemTime) {
+        for name in headers.keys() {
+            if name == CONTENT_LENGTH {
+                continue;
+            }
+            self.headers.remove(name);
+            for value in headers.get_all(name) {
+                self.headers.append(name, value.clone());
+            }
+        }
+        self.request_time = request_time;
+        self.response_time = SystemTime::now();
+    }
+
+    fn to_response(&self, age: Duration, status: CacheStatus) -> crate::Result<Response> {
+        let mut builder = http::Response::builder()
+            .status(self.status)
+            .version(self.version)
+            .url(self.url.clone())
+            .extension(status);
+        if let Some(headers) = builder.headers_mut() {
+            *headers = self.headers.clone();
+            headers.insert(AGE, HeaderValue::from(age.as_secs()));
+        }
+        let res = builder
+            .body(self.body.clone())
+            .map_err(crate::error::decode)?;
+        Ok(Response::from(res))
+    }
+}
+
+// ===== impl HttpCache =====
+
+impl HttpCache {
+    /// Create a cache in memory, keeping up to 64 MiB of bodies.
+    pub fn new() -> HttpCache {
+        HttpCache::with_store(MemoryStore::default())
+    }
+
+    /// Create a cache keeping the responses in `store`.
+    pub fn with_store<S: CacheStore>(store: S) -> HttpCache {
+        HttpCache {
+            store: Arc::new(store),
+            max_body_size: 8 * 1024 * 1024,
+        }
+    }
+
+    /// Set the largest body of a response to store.
+    ///
+    /// Responses are read whole before they are returned, to be stored;
+    /// larger ones announced by `Content-Length` are streamed instead, and
+    /// not stored. Default is 8 MiB.
+    pub fn max_body_size(mut self, max_body_size: u64) -> HttpCache {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Answer `req` from the cache, or send it and store the response.
+    pub(crate) async fn send(self, client: Client, req: Request) -> crate::Result<Response> {
+        let method = req.method().clone();
+        if method != Method::GET {
+            let url = req.url().clone();
+            let res = client.send_or_replay(req).await?;
+            if !method.is_safe() && (res.status().is_success() || res.status().is_redirection()) {
+                self.invalidate(&url, res.headers()).await;
+            }
+            return Ok(res);
+        }
+
+        // the caller revalidates or asks for a part on its own
+        let headers = req.headers();
+        if [
+            RANGE,
+            IF_NONE_MATCH,
+            IF_MODIFIED_SINCE,
+            IF_MATCH,
+            IF_UNMODIFIED_SINCE,
+        ]
+        .iter()
+        .any(|name| headers.contains_key(name))
+        {
+            return client.send_or_replay(req).await;
+        }
+
+        let request_cc = CacheControl::parse(req.headers());
+        if request_cc.no_store {
+            return client.send_or_replay(req).await;
+        }
+
+        let key = cache_key(req.url());
+        let stored = self
+            .get(key.clone())
+            .await
+            .filter(|entry| entry.vary_matches(req.headers()));
+        let Some(entry) = stored else {
+            if request_cc.only_if_cached {
+                return gateway_timeout(req.url());
+            }
+            return self.fetch(client, req, key).await;
+        };
+
+        let response_cc = CacheControl::parse(&entry.headers);
+        let age = entry.current_age(SystemTime::now());
+        let lifetime = entry.freshness_lifetime(&response_cc);
+        let no_cache = request_cc.no_cache || response_cc.no_cache;
+
+        let max_age = request_cc.max_age.unwrap_or(Duration::MAX);
+        let min_fresh = request_cc.min_fresh.unwrap_or_default();
+        if !no_cache && age <= max_age && lifetime > age.saturating_add(min_fresh) {
+            trace!("fresh response for {} in cache", key);
+            return entry.to_response(age, CacheStatus::Hit);
+        }
+
+        let staleness = age.saturating_sub(lifetime);
+        if !no_cache && !response_cc.must_revalidate {
+            let max_stale = match request_cc.max_stale {
+                Some(max_stale) => max_stale.unwrap_or(Duration::MAX),
+                None => Duration::ZERO,
+            };
+            if staleness <= max_stale && age <= max_age {
+                return entry.to_response(age, CacheStatus::Stale);
+            }
+
+            let revalidation = req.try_clone();
+            if let (Some(window), Some(revalidation)) =
+                (response_cc.stale_while_revalidate, revalidation)
+            {
+                if staleness <= window {
+                    debug!("revalidating stale response for {} in background", key);
+                    let response = entry.to_response(age, CacheStatus::Stale);
+                    tokio::spawn(self.clone().revalidate(client, revalidation, key, entry));
+                    return response;
+                }
+            }
+        }
+
+        if request_cc.only_if_cached {
+            return gateway_timeout(req.url());
+        }
+
+        let stale_if_error = request_cc
+            .stale_if_error
+            .or(response_cc.stale_if_error)
+            .filter(|window| !response_cc.must_revalidate && staleness <= *window);
+        match self
+            .clone()
+            .revalidate(client, req, key, entry.clone())
+            .await
+        {
+            Ok(res) if res.status().is_server_error() && stale_if_error.is_some() => {
+                debug!("serving stale response after {}", res.status());
+                entry.to_response(age, CacheStatus::Stale)
+            }
+            Err(err) if stale_if_error.is_some() => {
+                debug!("serving stale response after error: {}", err);
+                entry.to_response(age, CacheStatus::Stale)
+            }
+            result => result,
+        }
+    }
+
+    /// Send `req` with the validators of `entry`, and answer with it if the
+    /// server confirms it.
+    async fn revalidate(
+        self,
+        client: Client,
+        mut req: Request,
+        key: String,
+        mut entry: CacheEntry,
+    ) -> crate::Result<Response> {
+        let validators = [(ETAG, IF_NONE_MATCH), (LAST_MODIFIED, IF_MODIFIED_SINCE)];
+        for (validator, condition) in validators {
+            if let Some(value) = entry.headers.get(validator) {
+                req.headers_mut().insert(condition, value.clone());
+            }
+        }
+
+        let request_headers = req.headers().clone();
+        let request_time = SystemTime::now();
+        let res = client.send_or_replay(req).await?;
+        if res.status() != StatusCode::NOT_MODIFIED {
+            return self.store(key, request_headers, request_time, res).await;
+        }
+
+        trace!("stored response for {} revalidated", key);
+        entry.freshen(res.headers(), request_time);
+        self.put(key, entry.clone()).await;
+        entry.to_response(Duration::ZERO, CacheStatus::Revalidated)
+    }
+
+    /// Send `req`, and store the response if it may be.
+    async fn fetch(self, client: Client, req: Request, key: String) -> crate::Result<Response> {
+        let request_headers = req.headers().clone();
+        let request_time = SystemTime::now();
+        let res = client.send_or_replay(req).await?;
+        self.store(key, request_headers, request_time, res).await
+    }
+
+    async fn store(
+        self,
+        key: String,
+        request_headers: HeaderMap,
+        request_time: SystemTime,
+        mut res: Response,
+    ) -> crate::Result<Response> {
+        res.extensions_mut().insert(CacheStatus::Miss);
+        let cc = CacheControl::parse(res.headers());
+        if !policy::is_storable(res.status(), res.headers(), &cc)
+            || cache_key(res.url()) != key
+            || res
+                .content_length()
+                .is_some_and(|len| len > self.max_body_size)
+        {
+            self.delete(key).await;
+            return Ok(res);
+        }
+
+        let mut entry = CacheEntry {
+            url: res.url().clone(),
+            status: res.status(),
+            version: res.version(),
+            headers: res.headers().clone(),
+            body: Bytes::new(),
+            vary: HeaderMap::new(),
+            request_time,
+            response_time: SystemTime::now(),
+        };
+        for value in entry.headers.get_all(VARY) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            for name in value.split(',') {
+                let Ok(name) = HeaderName::from_bytes(name.trim().as_bytes()) else {
+                    continue;
+                };
+                for value in request_headers.get_all(&name) {
+                    entry.vary.append(name.clone(), value.clone());
+                }
+            }
+        }
+
+        entry.body = res.bytes().await?;
+        if entry.body.len() as u64 <= self.max_body_size {
+            trace!("storing response for {}", key);
+            self.put(key, entry.clone()).await;
+        }
+        entry.to_response(Duration::ZERO, CacheStatus::Miss)
+    }
+
+    /// Drop the responses an unsafe request may have changed
+    /// ([RFC 9111, section 4.4]).
+    ///
+    /// [RFC 9111, section 4.4]: https://datatracker.ietf.org/doc/html/rfc9111#section-4.4
+    async fn invalidate(&self, url: &Url, headers: &HeaderMap) {
+        self.delete(cache_key(url)).await;
+        for name in [LOCATION, CONTENT_LOCATION] {
+            let target = headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| url.join(value).ok());
+            if let Some(target) = target.filter(|target| target.origin() == url.origin()) {
+                self.delete(cache_key(&target)).await;
+            }
+        }
+    }
+
+    /// Get the entry stored under `key`, off the threads of the runtime.
+    async fn get(&self, key: String) -> Option<CacheEntry> {
+        let store = self.store.clone();
+        tokio::task::spawn_blocking(move || store.get(&key))
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Store `entry` under `key`, off the threads of the runtime.
+    async fn put(&self, key: String, entry: CacheEntry) {
+        let store = self.store.clone();
+        let _ = tokio::task::spawn_blocking(move || store.put(&key, entry)).await;
+    }
+
+    /// Remove the entry stored under `key`, off the threads of the runtime.
+    async fn delete(&self, key: String) {
+        let store = self.store.clone();
+        let _ = tokio::task::spawn_blocking(move || store.delete(&key)).await;
+    }
+}
+
+impl Default for HttpCache {
+    fn default() -> HttpCache {
+        HttpCache::new()
+    }
+}
+
+impl fmt::Debug for HttpCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HttpCache")
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+/// The key of the responses to a URL, without its fragment.
+fn cache_key(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.into()
+}
+
+/// The answer to an `only-if-cached` request without a usable response.
+fn gateway_timeout(url: &Url) -> crate::Result<Response> {
+    let res = http::Response::builder()
+        .status(StatusCode::GATEWAY_TIMEOUT)
+        .url(url.clone())
+        .body(Bytes::new())
+        .map_err(crate::error::decode)?;
+    Ok(Response::from(res))
+}
@@ -0,0 +1,280 @@
+//! The rules of RFC 9111 deciding what is stored, and for how long it is
+//! fresh.
+use std::time::{Duration, SystemTime};
+
+use http::header::{AGE, CACHE_CONTROL, DATE, EXPIRES, LAST_MODIFIED, PRAGMA, VARY};
+use http::{HeaderMap, StatusCode};
+
+use super::CacheEntry;
+use crate::util::parse_http_date;
+
+/// The longest freshness guessed from `Last-Modified`.
+const MAX_HEURISTIC_FRESHNESS: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The directives of the `Cache-Control` headers of a request or response.
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct CacheControl {
+    pub(super) no_store: bool,
+    pub(super) no_cache: bool,
+    pub(super) must_revalidate: bool,
+    pub(super) public: bool,
+    pub(super) only_if_cached: bool,
+    pub(super) max_age: Option<Duration>,
+    pub(super) min_fresh: Option<Duration>,
+    /// `Some(None)` for a `max-stale` without a limit.
+    pub(super) max_stale: Option<Option<Duration>>,
+    pub(super) stale_while_revalidate: Option<Duration>,
+    pub(super) stale_if_error: Option<Duration>,
+}
+
+impl CacheControl {
+    pub(super) fn parse(headers: &HeaderMap) -> CacheControl {
+        let mut cc = CacheControl::default();
+        let mut any = false;
+        for value in headers.get_all(CACHE_CONTROL) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            for directive in value.split(',') {
+                any = true;
+                let (name, arg) = match directive.split_once('=') {
+                    Some((name, arg)) => (name.trim(), Some(arg.trim().trim_matches('"'))),
+                    None => (directive.trim(), None),
+                };
+                let secs = || {
+                    arg.and_then(|arg| arg.parse().ok())
+                        .map(Duration::from_secs)
+                };
+                match name.to_ascii_lowercase().as_str() {
+                    "no-store" => cc.no_store = true,
+                    "no-cache" => cc.no_cache = true,
+                    "must-revalidate" => cc.must_revalidate = true,
+                    "public" => cc.public = true,
+                    "only-if-cached" => cc.only_if_cached = true,
+                    // a client's cache is private, so s-maxage is not for it
+                    "max-age" => cc.max_age = secs(),
+                    "min-fresh" => cc.min_fresh = secs(),
+                    "max-stale" => cc.max_stale = Some(secs()),
+                    "stale-while-revalidate" => cc.stale_while_revalidate = secs(),
+                    "stale-if-error" => cc.stale_if_error = secs(),
+                    _ => {}
+                }
+            }
+        }
+
+        // HTTP/1.0 caches, when no Cache-Control tells otherwise
+        if !any
+            && headers
+                .get_all(PRAGMA)
+                .iter()
+                .any(|value| value.as_bytes().eq_ignore_ascii_case(b"no-cache"))
+        {
+            cc.no_cache = true;
+        }
+        cc
+    }
+}
+
+/// Whether the status may be stored without explicit freshness, fresh for
+/// a time guessed from `Last-Modified`.
+fn heuristically_cacheable(status: StatusCode) -> bool {
+    matches!(
+        status.as_u16(),
+        200 | 203 | 204 | 300 | 301 | 308 | 404 | 405 | 410 | 414 | 501
+    )
+}
+
+/// Whether a response to a `GET` may be stored.
+pub(super) fn is_storable(status: StatusCode, headers: &HeaderMap, cc: &CacheControl) -> bool {
+    if cc.no_store || status == StatusCode::PARTIAL_CONTENT {
+        return false;
+    }
+    if headers
+        .get_all(VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .any(|value| value.split(',').any(|name| name.trim() == "*"))
+    {
+        return false;
+    }
+    cc.max_age.is_some()
+        || cc.public
+        || headers.contains_key(EXPIRES)
+        || heuristically_cacheable(status)
+}
+
+fn header_date(headers: &HeaderMap, name: http::HeaderName) -> Option<SystemTime> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_http_date)
+}
+
+impl CacheEntry {
+    /// How long the response is fresh after it was generated
+    /// ([RFC 9111, section 4.2.1]).
+    ///
+    /// [RFC 9111, section 4.2.1]: https://datatracker.ietf.org/doc/html/rfc9111#section-4.2.1
+    pub(super) fn freshness_lifetime(&self, cc: &CacheControl) -> Duration {
+        if let Some(max_age) = cc.max_age {
+            return max_age;
+        }
+        let date = header_date(&self.headers, DATE).unwrap_or(self.response_time);
+        if self.headers.contains_key(EXPIRES) {
+            // an invalid date, such as "0", is in the past
+            return header_date(&self.headers, EXPIRES)
+                .and_then(|expires| expires.duration_since(date).ok())
+                .unwrap_or_default();
+        }
+        if heuristically_cacheable(self.status) {
+            if let Some(last_modified) = header_date(&self.headers, LAST_MODIFIED) {
+                let since = date.duration_since(last_modified).unwrap_or_default();
+                return (since / 10).min(MAX_HEURISTIC_FRESHNESS);
+            }
+        }
+        Duration::ZERO
+    }
+
+    /// How old the response is at `now` ([RFC 9111, section 4.2.3]).
+    ///
+    /// [RFC 9111, section 4.2.3]: https://datatracker.ietf.org/doc/html/rfc9111#section-4.2.3
+    pub(super) fn current_age(&self, now: SystemTime) -> Duration {
+        let age_value = self
+            .headers
+            .get(AGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or_default();
+        let apparent_age = header_date(&self.headers, DATE)
+            .and_then(|date| self.response_time.duration_since(date).ok())
+            .unwrap_or_default();
+        let response_delay = self
+            .response_time
+            .duration_since(self.request_time)
+            .unwrap_or_default();
+        let corrected_initial_age = apparent_age.max(age_value.saturating_add(response_delay));
+        let resident_time = now.duration_since(self.response_time).unwrap_or_default();
+        corrected_initial_age.saturating_add(resident_time)
+    }
+
+    /// Whether the request headers named by `Vary` are the same in
+    /// `headers` as in the request the response was stored for.
+    pub(super) fn vary_matches(&self, headers: &HeaderMap) -> bool {
+        self.headers
+            .get_all(VARY)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .all(|name| {
+                let stored = self.vary.get_all(name).iter();
+                stored.eq(headers.get_all(name).iter())
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+    use std::time::UNIX_EPOCH;
+
+    fn entry(headers: &[(&'static str, &'static str)]) -> CacheEntry {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(*name, HeaderValue::from_static(value));
+        }
+        CacheEntry {
+            url: "http://example.com/".parse().unwrap(),
+            status: StatusCode::OK,
+            version: http::Version::HTTP_11,
+            headers: map,
+            body: Default::default(),
+            vary: HeaderMap::new(),
+            request_time: UNIX_EPOCH + Duration::from_secs(784_111_776),
+            response_time: UNIX_EPOCH + Duration::from_secs(784_111_777),
+        }
+    }
+
+    #[test]
+    fn parses_http_dates() {
+        let expected = Some(UNIX_EPOCH + Duration::from_secs(784_111_777));
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), expected);
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), expected);
+        assert_eq!(parse_http_date("Sun Nov  6 08:49:37 1994"), expected);
+        assert_eq!(parse_http_date("0"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 25:49:37 GMT"), None);
+    }
+
+    #[test]
+    fn parses_cache_control() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            CACHE_CONTROL,
+            "max-age=60, Must-Revalidate".parse().unwrap(),
+        );
+        headers.append(
+            CACHE_CONTROL,
+            "stale-if-error=\"30\", max-stale".parse().unwrap(),
+        );
+        let cc = CacheControl::parse(&headers);
+        assert_eq!(cc.max_age, Some(Duration::from_secs(60)));
+        assert!(cc.must_revalidate);
+        assert_eq!(cc.stale_if_error, Some(Duration::from_secs(30)));
+        assert_eq!(cc.max_stale, Some(None));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(PRAGMA, "no-cache".parse().unwrap());
+        assert!(CacheControl::parse(&headers).no_cache);
+    }
+
+    #[test]
+    fn freshness_from_max_age_expires_or_last_modified() {
+        let lifetime = |stored: CacheEntry| {
+            let cc = CacheControl::parse(&stored.headers);
+            stored.freshness_lifetime(&cc)
+        };
+        assert_eq!(
+            lifetime(entry(&[("cache-control", "max-age=60")])),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            lifetime(entry(&[
+                ("date", "Sun, 06 Nov 1994 08:49:37 GMT"),
+                ("expires", "Sun, 06 Nov 1994 09:49:37 GMT"),
+            ])),
+            Duration::from_secs(3600)
+        );
+        assert_eq!(lifetime(entry(&[("expires", "0")])), Duration::ZERO);
+        // a tenth of the time since the last modification
+        assert_eq!(
+            lifetime(entry(&[
+                ("date", "Sun, 06 Nov 1994 08:49:37 GMT"),
+                ("last-modified", "Sun, 06 Nov 1994 06:49:37 GMT"),
+            ])),
+            Duration::from_secs(720)
+        );
+    }
+
+    #[test]
+    fn age_counts_delay_and_residence() {
+        let stored = entry(&[("date", "Sun, 06 Nov 1994 08:49:37 GMT"), ("age", "10")]);
+        let now = stored.response_time + Duration::from_secs(5);
+        // 10 seconds of Age, 1 of response delay, 5 in the cache
+        assert_eq!(stored.current_age(now), Duration::from_secs(16));
+    }
+
+    #[test]
+    fn vary_compares_request_headers() {
+        let mut stored = entry(&[("vary", "Accept-Language")]);
+        stored.vary.insert("accept-language", "en".parse().unwrap());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("accept-language", "en".parse().unwrap());
+        assert!(stored.vary_matches(&headers));
+        headers.insert("accept-language", "fr".parse().unwrap());
+        assert!(!stored.vary_matches(&headers));
+    }
+}
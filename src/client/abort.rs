@@ -0,0 +1,97 @@
+//! Cancelling requests from another task.
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use futures_util::future::{self, Either};
+use tokio::sync::Notify;
+
+use super::response::Response;
+use crate::{error, Url};
+
+/// A future completing once its handle is aborted.
+pub(crate) type Aborted = Pin<Box<dyn Future<Output = ()> + Send + Sync>>;
+
+/// A handle to cancel requests from another task.
+///
+/// A request given the handle with [`RequestBuilder::abort_handle`] fails
+/// once the handle is aborted, whether it is still waiting for its response
+/// or its body is being read, with an error for which
+/// [`Error::is_canceled`] is true. A handle may be given to many requests,
+/// to cancel them all at once.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn run() -> Result<(), rquest::Error> {
+/// let handle = rquest::AbortHandle::new();
+/// let request = rquest::Client::new()
+///     .get("https://example.com/large.iso")
+///     .abort_handle(&handle)
+///     .send();
+///
+/// // from another task
+/// handle.abort();
+///
+/// let err = request.await.unwrap_err();
+/// assert!(err.is_canceled());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`RequestBuilder::abort_handle`]: crate::RequestBuilder::abort_handle
+/// [`Error::is_canceled`]: crate::Error::is_canceled
+#[derive(Clone, Debug, Default)]
+pub struct AbortHandle {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    aborted: AtomicBool,
+    notify: Notify,
+}
+
+impl AbortHandle {
+    /// Create a handle, not aborted yet.
+    pub fn new() -> AbortHandle {
+        AbortHandle::default()
+    }
+
+    /// Cancel the requests given this handle, and any given it later.
+    pub fn abort(&self) {
+        self.inner.aborted.store(true, Ordering::Release);
+        self.inner.notify.notify_waiters();
+    }
+
+    /// Whether the handle was aborted.
+    pub fn is_aborted(&self) -> bool {
+        self.inner.aborted.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn aborted(&self) -> Aborted {
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            // listen before checking, not to miss an abort in between
+            let mut notified = std::pin::pin!(inner.notify.notified());
+            notified.as_mut().enable();
+            if !inner.aborted.load(Ordering::Acquire) {
+                notified.await;
+            }
+        })
+    }
+}
+
+/// Send a request with `pending`, unless `handle` is aborted first, and
+/// read its body until then.
+pub(crate) async fn send<F>(handle: AbortHandle, url: Url, pending: F) -> crate::Result<Response>
+where
+    F: Future<Output = crate::Result<Response>>,
+{
+    let pending = std::pin::pin!(pending);
+    match future::select(handle.aborted(), pending).await {
+        Either::Left(((), _)) => Err(error::request(error::Canceled).with_url(url)),
+        Either::Right((res, _)) => res.map(|res| res.abort_on(&handle)),
+    }
+}
@@ -0,0 +1,228 @@
+//! Alternative services, the endpoints an origin advertises with `Alt-Svc`
+//! headers to be reached at instead.
+//!
+//! See [RFC 7838](https://www.rfc-editor.org/rfc/rfc7838).
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+use antidote::Mutex;
+use http::header::ALT_SVC;
+use http::uri::Authority;
+use http::HeaderMap;
+use log::trace;
+use lru::LruCache;
+use url::Url;
+
+/// How many origins a client remembers the alternative services of.
+const MAX_ENTRIES: usize = 1024;
+
+/// How long an alternative service is fresh without a `ma` parameter.
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The protocols an alternative service can be switched to, over TCP.
+const SUPPORTED: &[&str] = &["h2", "http/1.1"];
+
+/// The alternative services of the origins, by origin.
+pub(crate) struct AltSvcCache {
+    entries: Mutex<LruCache<String, Vec<Alternative>>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Alternative {
+    /// The ALPN protocol id, such as `h2` or `h3`.
+    protocol: String,
+    /// The host, or `None` for the host of the origin.
+    host: Option<String>,
+    port: u16,
+    expires: Instant,
+}
+
+impl AltSvcCache {
+    pub(crate) fn new() -> AltSvcCache {
+        AltSvcCache {
+            entries: Mutex::new(LruCache::new(
+                NonZeroUsize::new(MAX_ENTRIES).expect("non-zero"),
+            )),
+        }
+    }
+
+    /// Remember the alternative services advertised in a response from `url`.
+    ///
+    /// Only secure responses are trusted. The services replace the ones
+    /// known for the origin, and `clear` forgets them.
+    pub(crate) fn observe(&self, url: &Url, headers: &HeaderMap) {
+        if url.scheme() != "https" || !headers.contains_key(ALT_SVC) {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut alternatives = Vec::new();
+        for value in headers.get_all(ALT_SVC) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            if value.trim() == "clear" {
+                alternatives.clear();
+                break;
+            }
+            alternatives.extend(parse(value, now));
+        }
+
+        let origin = url.origin().ascii_serialization();
+        let mut entries = self.entries.lock();
+        if alternatives.is_empty() {
+            entries.pop(&origin);
+        } else {
+            trace!("alternative services of {}: {:?}", origin, alternatives);
+            entries.put(origin, alternatives);
+        }
+    }
+
+    /// The endpoint to connect to for `url`, if its origin advertised one
+    /// that can be reached over TCP, in the order of preference of the
+    /// origin.
+    pub(crate) fn endpoint(&self, url: &Url) -> Option<Authority> {
+        if url.scheme() != "https" {
+            return None;
+        }
+        let host = url.host_str()?;
+        let port = url.port_or_known_default()?;
+
+        let now = Instant::now();
+        let origin = url.origin().ascii_serialization();
+        let mut entries = self.entries.lock();
+        let alternatives = entries.get_mut(&origin)?;
+        alternatives.retain(|alternative| alternative.expires > now);
+
+        let alternative = alternatives
+            .iter()
+            .find(|alternative| SUPPORTED.contains(&alternative.protocol.as_str()))?;
+        let alt_host = alternative.host.as_deref().unwrap_or(host);
+        if alt_host.eq_ignore_ascii_case(host) && alternative.port == port {
+            return None;
+        }
+        format!("{}:{}", alt_host, alternative.port).parse().ok()
+    }
+}
+
+/// Parse the alternatives of an `Alt-Svc` value, skipping the invalid ones.
+fn parse(value: &str, now: Instant) -> impl Iterator<Item = Alternative> + '_ {
+    value.split(',').filter_map(move |alternative| {
+        let mut params = alternative.split(';');
+        let (protocol, authority) = params.next()?.trim().split_once('=')?;
+        let protocol = percent_encoding::percent_decode_str(protocol.trim())
+            .decode_utf8()
+            .ok()?
+            .into_owned();
+        let authority = authority.trim().strip_prefix('"')?.strip_suffix('"')?;
+        let (host, port) = authority.rsplit_once(':')?;
+        let port = port.parse().ok()?;
+
+        let mut max_age = DEFAULT_MAX_AGE;
+        for param in params {
+            if let Some((name, value)) = param.split_once('=') {
+                if name.trim().eq_ignore_ascii_case("ma") {
+                    let seconds = value.trim().trim_matches('"').parse::<u64>().ok()?;
+                    // cap the max-age, so its expiry is representable
+                    max_age = Duration::from_secs(seconds.min(u32::MAX as u64));
+                }
+            }
+        }
+
+        Some(Alternative {
+            protocol,
+            host: (!host.is_empty()).then(|| host.to_ascii_lowercase()),
+            port,
+            expires: now + max_age,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn alt_svc(values: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(ALT_SVC, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn parse_alternatives() {
+        let now = Instant::now();
+        let alternatives: Vec<_> = parse(
+            "h3=\":443\"; ma=86400, h2=\"Alt.example.com:8443\"; ma=60; persist=1, http%2F1.1=\"b:80\", bad",
+            now,
+        )
+        .collect();
+
+        assert_eq!(
+            alternatives,
+            [
+                Alternative {
+                    protocol: "h3".into(),
+                    host: None,
+                    port: 443,
+                    expires: now + Duration::from_secs(86400),
+                },
+                Alternative {
+                    protocol: "h2".into(),
+                    host: Some("alt.example.com".into()),
+                    port: 8443,
+                    expires: now + Duration::from_secs(60),
+                },
+                Alternative {
+                    protocol: "http/1.1".into(),
+                    host: Some("b".into()),
+                    port: 80,
+                    expires: now + DEFAULT_MAX_AGE,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn switches_to_the_first_supported_alternative() {
+        let cache = AltSvcCache::new();
+        let url = Url::parse("https://example.com/a").unwrap();
+        cache.observe(
+            &url,
+            &alt_svc(&["h3=\":443\"", "h2=\"alt.example.com:8443\""]),
+        );
+
+        let other = Url::parse("https://example.com/b").unwrap();
+        assert_eq!(
+            cache.endpoint(&other),
+            Some(Authority::from_static("alt.example.com:8443"))
+        );
+
+        // h3 alone can't be reached, and the origin itself needs no switch
+        cache.observe(&url, &alt_svc(&["h3=\":443\"", "h2=\":443\""]));
+        assert_eq!(cache.endpoint(&url), None);
+
+        cache.observe(&url, &alt_svc(&["h2=\":8443\""]));
+        assert_eq!(
+            cache.endpoint(&url),
+            Some(Authority::from_static("example.com:8443"))
+        );
+
+        cache.observe(&url, &alt_svc(&["clear"]));
+        assert_eq!(cache.endpoint(&url), None);
+    }
+
+    #[test]
+    fn ignores_plain_http() {
+        let cache = AltSvcCache::new();
+        let url = Url::parse("http://example.com/").unwrap();
+        cache.observe(&url, &alt_svc(&["h2=\"alt.example.com:443\""]));
+        assert_eq!(cache.endpoint(&url), None);
+
+        let url = Url::parse("https://example.com/").unwrap();
+        assert_eq!(cache.endpoint(&url), None);
+    }
+}
@@ -0,0 +1,195 @@
+//! Authorization added to every request of a client, for APIs with
+//! short-lived tokens.
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use antidote::Mutex;
+use http::header::{HeaderValue, AUTHORIZATION};
+use log::{debug, trace};
+
+use super::http::Client;
+use super::request::Request;
+use super::response::Response;
+use crate::{error, StatusCode};
+
+type TokenFuture = Pin<Box<dyn Future<Output = crate::Result<AuthToken>> + Send>>;
+
+/// The token an [`AuthProvider`] returns.
+#[derive(Clone)]
+pub struct AuthToken {
+    header: HeaderValue,
+    expires_at: Option<Instant>,
+}
+
+impl AuthToken {
+    /// A token for `Bearer` authentication.
+    ///
+    /// # Errors
+    ///
+    /// This fails if the token contains characters not allowed in a header.
+    pub fn bearer(token: &str) -> crate::Result<AuthToken> {
+        HeaderValue::try_from(format!("Bearer {token}"))
+            .map(AuthToken::header)
+            .map_err(error::builder)
+    }
+
+    /// A token sent as is in the `Authorization` header.
+    pub fn header(mut value: HeaderValue) -> AuthToken {
+        value.set_sensitive(true);
+        AuthToken {
+            header: value,
+            expires_at: None,
+        }
+    }
+
+    /// Fetch a new token before the next request once `ttl` has passed.
+    ///
+    /// Tokens without expiry are only fetched again when the server answers
+    /// `401 Unauthorized`.
+    pub fn expires_in(mut self, ttl: Duration) -> AuthToken {
+        self.expires_at = Instant::now().checked_add(ttl);
+        self
+    }
+
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Instant::now())
+    }
+}
+
+impl fmt::Debug for AuthToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthToken")
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+/// A source of tokens for the `Authorization` header of every request of a
+/// client.
+///
+/// The last token is kept for the next requests, until it expires or the
+/// server answers `401 Unauthorized`. Then a new token is fetched, and a
+/// request refused with the old one is sent again once, if its body can be
+/// sent again.
+///
+/// To use one, refer to [`ClientBuilder::default_auth`](super::ClientBuilder::default_auth).
+#[derive(Clone)]
+pub struct AuthProvider {
+    callback: Arc<dyn Fn() -> TokenFuture + Send + Sync>,
+    current: Arc<Mutex<Option<AuthToken>>>,
+}
+
+impl AuthProvider {
+    /// A provider calling `callback` whenever it needs a new token.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use rquest::{AuthProvider, AuthToken};
+    /// # async fn fetch_token() -> rquest::Result<String> { Ok(String::new()) }
+    ///
+    /// let auth = AuthProvider::new(|| async {
+    ///     let token = fetch_token().await?;
+    ///     Ok(AuthToken::bearer(&token)?.expires_in(Duration::from_secs(300)))
+    /// });
+    /// ```
+    pub fn new<F, Fut>(callback: F) -> AuthProvider
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = crate::Result<AuthToken>> + Send + 'static,
+    {
+        AuthProvider {
+            callback: Arc::new(move || Box::pin(callback()) as TokenFuture),
+            current: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// A provider always returning the same token.
+    pub fn fixed(token: AuthToken) -> AuthProvider {
+        let current = Some(token.clone());
+        let provider = AuthProvider::new(move || {
+            let token = token.clone();
+            async move { Ok(token) }
+        });
+        *provider.current.lock() = current;
+        provider
+    }
+
+    /// The header of the last token, or of a new one if it expired.
+    async fn authorization(&self) -> crate::Result<HeaderValue> {
+        let current = self
+            .current
+            .lock()
+            .as_ref()
+            .filter(|token| !token.is_expired())
+            .map(|token| token.header.clone());
+        if let Some(header) = current {
+            return Ok(header);
+        }
+
+        trace!("fetching new authorization token");
+        let token = (self.callback)().await?;
+        let header = token.header.clone();
+        *self.current.lock() = Some(token);
+        Ok(header)
+    }
+
+    /// Forget the token the server refused, unless another request already
+    /// replaced it.
+    fn invalidate(&self, refused: &HeaderValue) {
+        let mut current = self.current.lock();
+        if current
+            .as_ref()
+            .is_some_and(|token| token.header == *refused)
+        {
+            *current = None;
+        }
+    }
+}
+
+impl fmt::Debug for AuthProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthProvider")
+            .field("current", &*self.current.lock())
+            .finish()
+    }
+}
+
+/// Send `req` with a token from `provider`, and once more with a new token
+/// if the server refuses it.
+pub(super) async fn send(
+    client: Client,
+    provider: AuthProvider,
+    mut req: Request,
+) -> crate::Result<Response> {
+    let retry = req.try_clone();
+
+    let header = provider.authorization().await?;
+    req.headers_mut().insert(AUTHORIZATION, header.clone());
+    let res = client.execute_request(req).await?;
+    if res.status() != StatusCode::UNAUTHORIZED {
+        return Ok(res);
+    }
+
+    let mut retry = match retry {
+        Some(retry) => retry,
+        None => {
+            debug!("authorization refused, but body not reusable");
+            return Ok(res);
+        }
+    };
+    provider.invalidate(&header);
+    let refreshed = provider.authorization().await?;
+    if refreshed == header {
+        return Ok(res);
+    }
+
+    trace!("sending again with a new authorization token");
+    retry.headers_mut().insert(AUTHORIZATION, refreshed);
+    client.execute_request(retry).await
+}
@@ -1,6 +1,13 @@
 use std::fmt;
 use std::future::Future;
+#[cfg(feature = "stream")]
+use std::io;
+#[cfg(feature = "stream")]
+use std::path::Path;
 use std::pin::Pin;
+#[cfg(feature = "stream")]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
 
@@ -10,6 +17,8 @@ use http_body_util::combinators::BoxBody;
 use pin_project_lite::pin_project;
 #[cfg(feature = "stream")]
 use tokio::fs::File;
+#[cfg(feature = "stream")]
+use tokio::sync::mpsc;
 use tokio::time::Sleep;
 #[cfg(feature = "stream")]
 use tokio_util::io::ReaderStream;
@@ -17,6 +26,34 @@ use tokio_util::io::ReaderStream;
 /// An asynchronous request body.
 pub struct Body {
     inner: Inner,
+    factory: Option<Factory>,
+}
+
+/// The chunks a [`BodySender`] buffers before it waits.
+#[cfg(feature = "stream")]
+const CHANNEL_CAPACITY: usize = 8;
+
+/// The sending half of a [`Body::channel`].
+///
+/// # Optional
+///
+/// This requires the `stream` feature to be enabled.
+#[cfg(feature = "stream")]
+#[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+#[derive(Debug)]
+pub struct BodySender {
+    tx: mpsc::Sender<Bytes>,
+    aborted: Arc<AtomicBool>,
+}
+
+/// Makes the stream of a body again, to send it more than once.
+type Factory = Arc<dyn Fn() -> Body + Send + Sync>;
+
+/// What is needed to send a body again.
+#[derive(Clone)]
+pub(crate) enum Replay {
+    Bytes(Bytes),
+    Factory(Factory),
 }
 
 enum Inner {
@@ -95,7 +132,14 @@ impl Body {
         Body::stream(stream)
     }
 
-    #[cfg(any(feature = "stream", feature = "multipart"))]
+    #[cfg(any(
+        feature = "stream",
+        feature = "multipart",
+        feature = "gzip",
+        feature = "zstd",
+        feature = "brotli",
+        feature = "deflate"
+    ))]
     pub(crate) fn stream<S>(stream: S) -> Body
     where
         S: futures_util::stream::TryStream + Send + 'static,
@@ -113,9 +157,191 @@ impl Body {
         )));
         Body {
             inner: Inner::Streaming(body),
+            factory: None,
+        }
+    }
+
+    /// Make a streaming body from a factory, called again each time the
+    /// body has to be sent again.
+    ///
+    /// Unlike other streaming bodies, requests with such a body can be
+    /// cloned with [`Request::try_clone`](crate::Request::try_clone), and
+    /// sent again on redirects and retries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use rquest::Body;
+    /// # fn main() {
+    /// let body = Body::from_fn(|| {
+    ///     let chunks: Vec<Result<_, std::io::Error>> = vec![Ok("hello"), Ok(" world")];
+    ///     futures_util::stream::iter(chunks)
+    /// });
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the `stream` feature to be enabled.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+    pub fn from_fn<F, S>(factory: F) -> Body
+    where
+        F: Fn() -> S + Send + Sync + 'static,
+        S: futures_util::stream::TryStream + Send + 'static,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
+        Body::replay(&(Arc::new(move || Body::stream(factory())) as Factory))
+    }
+
+    /// Make a streaming body from an `AsyncRead`, read as it is sent.
+    ///
+    /// With a `len`, the request has a `Content-Length`, and the reader must
+    /// give exactly `len` bytes; otherwise the body is sent chunked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use rquest::Body;
+    /// # fn main() {
+    /// let data = std::io::Cursor::new(b"hello world".to_vec());
+    /// let body = Body::from_async_read(data, Some(11));
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the `stream` feature to be enabled.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+    pub fn from_async_read<R>(reader: R, len: Option<u64>) -> Body
+    where
+        R: tokio::io::AsyncRead + Send + 'static,
+    {
+        let body = Body::stream(ReaderStream::new(reader));
+        match len {
+            Some(len) => body.sized(len),
+            None => body,
+        }
+    }
+
+    /// Make a streaming body from the file at `path`, with its length as
+    /// `Content-Length`.
+    ///
+    /// The file is opened when the body is sent, and opened again each time
+    /// the body has to be sent again, such as on redirects and retries, so
+    /// it is never read in memory whole.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let body = rquest::Body::from_file("backup.tar")?;
+    /// let res = rquest::Client::new()
+    ///     .put("https://storage.example.com/backups/latest.tar")
+    ///     .body(body)
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This fails if `path` is not a file whose metadata can be read.
+    ///
+    /// # Optional
+    ///
+    /// This requires the `stream` feature to be enabled.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Body> {
+        use futures_util::TryStreamExt;
+
+        let metadata = std::fs::metadata(&path)?;
+        if !metadata.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "body path is not a file",
+            ));
+        }
+        let len = metadata.len();
+        let path: Arc<Path> = path.as_ref().into();
+        let factory: Factory = Arc::new(move || {
+            let path = path.clone();
+            let stream = futures_util::stream::once(async move { File::open(path).await })
+                .map_ok(ReaderStream::new)
+                .try_flatten();
+            Body::stream(stream).sized(len)
+        });
+        Ok(Body::replay(&factory))
+    }
+
+    /// Make a streaming body sent from a [`BodySender`], for chunks made
+    /// while the request is sent, such as lines of generated NDJSON.
+    ///
+    /// The body ends when the sender is dropped. Up to a few chunks are
+    /// buffered, after which sending waits for the body to be written.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let (mut sender, body) = rquest::Body::channel();
+    /// let request = rquest::Client::new()
+    ///     .post("http://httpbin.org/post")
+    ///     .body(body)
+    ///     .send();
+    /// let response = tokio::spawn(request);
+    ///
+    /// for i in 0..3 {
+    ///     sender.send_data(format!("{{\"n\":{i}}}\n")).await?;
+    /// }
+    /// drop(sender);
+    /// let res = response.await.unwrap()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the `stream` feature to be enabled.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+    pub fn channel() -> (BodySender, Body) {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let aborted = Arc::new(AtomicBool::new(false));
+        let stream = ChannelStream {
+            rx,
+            aborted: aborted.clone(),
+        };
+        (BodySender { tx, aborted }, Body::stream(stream))
+    }
+
+    /// Tell the exact length of a streaming body.
+    #[cfg(feature = "stream")]
+    fn sized(self, len: u64) -> Body {
+        use http_body_util::BodyExt;
+
+        match self.inner {
+            Inner::Streaming(inner) => Body {
+                inner: Inner::Streaming(SizedBody { inner, len }.boxed()),
+                factory: self.factory,
+            },
+            inner @ Inner::Reusable(_) => Body {
+                inner,
+                factory: self.factory,
+            },
         }
     }
 
+    fn replay(factory: &Factory) -> Body {
+        let mut body = factory();
+        body.factory = Some(factory.clone());
+        body
+    }
+
     pub(crate) fn empty() -> Body {
         Body::reusable(Bytes::new())
     }
@@ -123,6 +349,7 @@ impl Body {
     pub(crate) fn reusable(chunk: Bytes) -> Body {
         Body {
             inner: Inner::Reusable(chunk),
+            factory: None,
         }
     }
 
@@ -151,22 +378,25 @@ impl Body {
 
         Body {
             inner: Inner::Streaming(boxed),
+            factory: None,
         }
     }
 
-    pub(crate) fn try_reuse(self) -> (Option<Bytes>, Self) {
-        let reuse = match self.inner {
-            Inner::Reusable(ref chunk) => Some(chunk.clone()),
-            Inner::Streaming { .. } => None,
+    pub(crate) fn try_reuse(self) -> (Option<Replay>, Self) {
+        let reuse = match (&self.inner, &self.factory) {
+            (Inner::Reusable(chunk), _) => Some(Replay::Bytes(chunk.clone())),
+            (Inner::Streaming { .. }, Some(factory)) => Some(Replay::Factory(factory.clone())),
+            (Inner::Streaming { .. }, None) => None,
         };
 
         (reuse, self)
     }
 
     pub(crate) fn try_clone(&self) -> Option<Body> {
-        match self.inner {
-            Inner::Reusable(ref chunk) => Some(Body::reusable(chunk.clone())),
-            Inner::Streaming { .. } => None,
+        match (&self.inner, &self.factory) {
+            (Inner::Reusable(chunk), _) => Some(Body::reusable(chunk.clone())),
+            (Inner::Streaming { .. }, Some(factory)) => Some(Body::replay(factory)),
+            (Inner::Streaming { .. }, None) => None,
         }
     }
 
@@ -196,6 +426,7 @@ impl From<BoxBody<Bytes, Box<dyn std::error::Error + Send + Sync>>> for Body {
     fn from(body: BoxBody<Bytes, Box<dyn std::error::Error + Send + Sync>>) -> Self {
         Self {
             inner: Inner::Streaming(body),
+            factory: None,
         }
     }
 }
@@ -244,6 +475,24 @@ impl From<File> for Body {
     }
 }
 
+impl Replay {
+    /// The content of a body of bytes.
+    pub(crate) fn as_bytes(&self) -> Option<&Bytes> {
+        match self {
+            Replay::Bytes(chunk) => Some(chunk),
+            Replay::Factory(_) => None,
+        }
+    }
+
+    /// A new body with the same content.
+    pub(crate) fn body(&self) -> Body {
+        match self {
+            Replay::Bytes(chunk) => Body::reusable(chunk.clone()),
+            Replay::Factory(factory) => Body::replay(factory),
+        }
+    }
+}
+
 impl fmt::Debug for Body {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Body").finish()
@@ -453,6 +702,110 @@ where
     }
 }
 
+// ===== impl BodySender =====
+
+#[cfg(feature = "stream")]
+impl BodySender {
+    /// Send a chunk of the body, waiting while the buffered chunks are
+    /// written.
+    ///
+    /// # Errors
+    ///
+    /// This fails if the body is no longer sent, such as when the request
+    /// failed or was dropped.
+    pub async fn send_data<B: Into<Bytes>>(&mut self, chunk: B) -> crate::Result<()> {
+        self.tx
+            .send(chunk.into())
+            .await
+            .map_err(|_| crate::error::body("request body was dropped"))
+    }
+
+    /// Try to send a chunk of the body without waiting, giving it back if
+    /// the buffer is full or the body is no longer sent.
+    pub fn try_send_data<B: Into<Bytes>>(&mut self, chunk: B) -> Result<(), Bytes> {
+        self.tx
+            .try_send(chunk.into())
+            .map_err(mpsc::error::TrySendError::into_inner)
+    }
+
+    /// End the body with an error, so the request fails instead of being
+    /// sent with a truncated body.
+    pub fn abort(self) {
+        self.aborted.store(true, Ordering::Release);
+    }
+
+    /// Whether the body is no longer sent.
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+
+    /// Wait until the body is no longer sent.
+    pub async fn closed(&self) {
+        self.tx.closed().await
+    }
+}
+
+#[cfg(feature = "stream")]
+struct ChannelStream {
+    rx: mpsc::Receiver<Bytes>,
+    aborted: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "stream")]
+impl futures_util::Stream for ChannelStream {
+    type Item = Result<Bytes, crate::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        match futures_util::ready!(self.rx.poll_recv(cx)) {
+            Some(chunk) => Poll::Ready(Some(Ok(chunk))),
+            // the sender is gone, after sending its chunks
+            None if self.aborted.swap(false, Ordering::Acquire) => {
+                Poll::Ready(Some(Err(crate::error::body("request body was aborted"))))
+            }
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+// ===== impl SizedBody =====
+
+#[cfg(feature = "stream")]
+pin_project! {
+    /// A streaming body of a length known beforehand.
+    struct SizedBody<B> {
+        #[pin]
+        inner: B,
+        len: u64,
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<B> HttpBody for SizedBody<B>
+where
+    B: HttpBody,
+{
+    type Data = B::Data;
+    type Error = B::Error;
+
+    #[inline]
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<hyper2::body::Frame<Self::Data>, Self::Error>>> {
+        self.project().inner.poll_frame(cx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        http_body::SizeHint::with_exact(self.len)
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.len == 0 || self.inner.is_end_stream()
+    }
+}
+
 // ===== impl IntoBytesBody =====
 pin_project! {
     struct IntoBytesBody<B> {
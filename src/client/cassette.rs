@@ -0,0 +1,409 @@
+//! Recording of responses to disk, to replay them in tests.
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use antidote::Mutex;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use http::StatusCode;
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use super::http::Client;
+use super::request::Request;
+use super::response::Response;
+use crate::response::ResponseBuilderExt;
+use crate::{error, Url};
+
+/// When a [`Cassette`] sends requests for real.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CassetteMode {
+    /// Replay the recorded responses, and record the requests matching none.
+    #[default]
+    Auto,
+    /// Send every request, and record all the responses anew.
+    Record,
+    /// Only replay the recorded responses, and fail the requests matching
+    /// none.
+    Replay,
+}
+
+/// A file of recorded responses, replayed instead of sending the requests
+/// that match them.
+///
+/// On the first run of a test, the requests are sent and their responses
+/// recorded in the file. The next runs replay them, without touching the
+/// network, so tests of scrapers are fast and deterministic.
+///
+/// Requests match a recording by method, URL and body, and by the headers
+/// set with [`Cassette::match_headers`]. Identical requests replay their
+/// responses in the order they were recorded. Only those headers of the
+/// requests are recorded, so credentials such as `Authorization` or
+/// `Cookie` stay out of the file unless they are matched on.
+///
+/// In [`CassetteMode::Auto`], a request matching no recording is sent and
+/// its response appended to the file. Use [`CassetteMode::Replay`] where
+/// tests must never touch the network, such as in CI.
+///
+/// Clones of a cassette share its recordings, but each one keeps its own
+/// matching settings.
+///
+/// Responses are recorded decompressed and after redirects, whole, so
+/// cassettes are meant for tests rather than large downloads.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// use rquest::Cassette;
+///
+/// let client = rquest::Client::builder()
+///     .cassette(Cassette::open("tests/cassettes/search.json")?)
+///     .build()?;
+///
+/// // sent on the first run, replayed on the next ones
+/// let body = client.get("https://example.com/search?q=rust").send().await?.text().await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Optional
+///
+/// This requires the optional `json` feature enabled.
+#[derive(Clone)]
+pub struct Cassette {
+    inner: Arc<Inner>,
+    match_headers: Vec<HeaderName>,
+    match_body: bool,
+}
+
+struct Inner {
+    path: PathBuf,
+    mode: CassetteMode,
+    tape: Mutex<Tape>,
+    /// Held while the file is written, so the writes land in order.
+    writing: tokio::sync::Mutex<()>,
+}
+
+struct Tape {
+    interactions: Vec<Interaction>,
+    played: Vec<bool>,
+}
+
+impl Cassette {
+    /// Open the cassette at `path`, in [`CassetteMode::Auto`].
+    ///
+    /// The file does not have to exist yet; it is created on the first
+    /// recording.
+    ///
+    /// # Errors
+    ///
+    /// This fails if the file exists and can not be read as a cassette.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Cassette> {
+        Cassette::with_mode(path, CassetteMode::Auto)
+    }
+
+    /// Open the cassette at `path`, in `mode`.
+    ///
+    /// In [`CassetteMode::Record`], the recordings already in the file are
+    /// replaced.
+    ///
+    /// # Errors
+    ///
+    /// This fails if the file has to be replayed and can not be read as a
+    /// cassette.
+    pub fn with_mode<P: AsRef<Path>>(path: P, mode: CassetteMode) -> io::Result<Cassette> {
+        let path = path.as_ref().to_owned();
+        let interactions = match mode {
+            CassetteMode::Record => Vec::new(),
+            CassetteMode::Auto if !path.exists() => Vec::new(),
+            CassetteMode::Auto | CassetteMode::Replay => {
+                let file: File = serde_json::from_slice(&std::fs::read(&path)?)?;
+                file.interactions
+            }
+        };
+        Ok(Cassette {
+            inner: Arc::new(Inner {
+                path,
+                mode,
+                tape: Mutex::new(Tape {
+                    played: vec![false; interactions.len()],
+                    interactions,
+                }),
+                writing: tokio::sync::Mutex::new(()),
+            }),
+            match_headers: Vec::new(),
+            match_body: true,
+        })
+    }
+
+    /// Also match requests by the values of the headers `names`.
+    pub fn match_headers<I>(mut self, names: I) -> Cassette
+    where
+        I: IntoIterator,
+        I::Item: Into<HeaderName>,
+    {
+        self.match_headers = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether to match requests by their body.
+    ///
+    /// Default is `true`.
+    pub fn match_body(mut self, enable: bool) -> Cassette {
+        self.match_body = enable;
+        self
+    }
+
+    /// The number of recorded responses.
+    pub fn len(&self) -> usize {
+        self.inner.tape.lock().interactions.len()
+    }
+
+    /// Whether no response is recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Replay the response recorded for `req`, or send it and record the
+    /// response.
+    pub(super) async fn play(self, client: Client, req: Request) -> crate::Result<Response> {
+        let recorded = RecordedRequest::new(&req, &self.match_headers);
+
+        if self.inner.mode != CassetteMode::Record {
+            if let Some(res) = self.replay(&recorded, self.inner.mode == CassetteMode::Replay) {
+                debug!(
+                    "replaying {} {} from cassette",
+                    recorded.method, recorded.url
+                );
+                return res.into_response();
+            }
+            if self.inner.mode == CassetteMode::Replay {
+                return Err(error::request(format!(
+                    "no response recorded in {} for {} {}",
+                    self.inner.path.display(),
+                    recorded.method,
+                    recorded.url
+                ))
+                .with_url(req.url().clone()));
+            }
+        }
+
+        let url = req.url().clone();
+        let res = client.send_request(req).await?;
+        let response = RecordedResponse {
+            status: res.status().as_u16(),
+            url: res.url().to_string(),
+            headers: pairs(res.headers()),
+            body: None,
+        };
+        let body = res.bytes().await?;
+        let response = RecordedResponse {
+            body: (!body.is_empty()).then(|| StoredBody::new(&body)),
+            ..response
+        };
+
+        self.record(Interaction {
+            request: recorded,
+            response: response.clone(),
+        })
+        .await
+        .map_err(|err| error::request(err).with_url(url))?;
+        response.into_response()
+    }
+
+    fn replay(&self, req: &RecordedRequest, repeat: bool) -> Option<RecordedResponse> {
+        let mut tape = self.inner.tape.lock();
+        let Tape {
+            interactions,
+            played,
+        } = &mut *tape;
+
+        let matching: Vec<usize> = (0..interactions.len())
+            .filter(|&i| self.matches(&interactions[i].request, req))
+            .collect();
+        // identical requests replay their responses in order; once all are
+        // played, only a replaying cassette plays the last one again, others
+        // record a new one
+        let i = matching
+            .iter()
+            .copied()
+            .find(|&i| !played[i])
+            .or_else(|| matching.last().copied().filter(|_| repeat))?;
+        played[i] = true;
+        Some(interactions[i].response.clone())
+    }
+
+    fn matches(&self, recorded: &RecordedRequest, req: &RecordedRequest) -> bool {
+        recorded.method == req.method
+            && recorded.url == req.url
+            && (!self.match_body || recorded.body == req.body)
+            && self.match_headers.iter().all(|name| {
+                let value = |headers: &[(String, String)]| {
+                    headers
+                        .iter()
+                        .filter(|(n, _)| name.as_str().eq_ignore_ascii_case(n))
+                        .map(|(_, v)| v.clone())
+                        .collect::<Vec<_>>()
+                };
+                value(&recorded.headers) == value(&req.headers)
+            })
+    }
+
+    async fn record(&self, interaction: Interaction) -> io::Result<()> {
+        {
+            let mut tape = self.inner.tape.lock();
+            tape.interactions.push(interaction);
+            tape.played.push(true);
+        }
+
+        let _writing = self.inner.writing.lock().await;
+        let contents = {
+            let tape = self.inner.tape.lock();
+            let file = FileRef {
+                interactions: &tape.interactions,
+            };
+            serde_json::to_vec_pretty(&file)?
+        };
+        let path = self.inner.path.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&path, contents)
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+}
+
+impl fmt::Debug for Cassette {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Cassette")
+            .field("path", &self.inner.path)
+            .field("mode", &self.inner.mode)
+            .field("recorded", &self.len())
+            .finish()
+    }
+}
+
+#[derive(Deserialize)]
+struct File {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Serialize)]
+struct FileRef<'a> {
+    interactions: &'a [Interaction],
+}
+
+#[derive(Serialize, Deserialize)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordedRequest {
+    method: String,
+    url: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<StoredBody>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    url: String,
+    headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<StoredBody>,
+}
+
+/// A body, as text if it is UTF-8, or else in base64.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum StoredBody {
+    Text(String),
+    Base64(String),
+}
+
+impl RecordedRequest {
+    /// The request, with only the `match_headers` among its headers.
+    fn new(req: &Request, match_headers: &[HeaderName]) -> RecordedRequest {
+        let headers = pairs(req.headers())
+            .into_iter()
+            .filter(|(name, _)| match_headers.iter().any(|m| m.as_str() == name))
+            .collect();
+        RecordedRequest {
+            method: req.method().to_string(),
+            url: req.url().to_string(),
+            headers,
+            body: req
+                .body()
+                .and_then(|body| body.as_bytes())
+                .filter(|body| !body.is_empty())
+                .map(StoredBody::new),
+        }
+    }
+}
+
+impl RecordedResponse {
+    fn into_response(self) -> crate::Result<Response> {
+        let url = Url::parse(&self.url).map_err(error::decode)?;
+        let status = StatusCode::from_u16(self.status).map_err(error::decode)?;
+        let mut builder = http::Response::builder().status(status).url(url);
+        if let Some(headers) = builder.headers_mut() {
+            *headers = header_map(&self.headers)?;
+        }
+        let body = match self.body {
+            Some(body) => body.into_bytes()?,
+            None => Vec::new(),
+        };
+        let res = builder.body(body).map_err(error::decode)?;
+        Ok(Response::from(res))
+    }
+}
+
+impl StoredBody {
+    fn new(body: &[u8]) -> StoredBody {
+        match std::str::from_utf8(body) {
+            Ok(text) => StoredBody::Text(text.to_owned()),
+            Err(_) => StoredBody::Base64(BASE64_STANDARD.encode(body)),
+        }
+    }
+
+    fn into_bytes(self) -> crate::Result<Vec<u8>> {
+        match self {
+            StoredBody::Text(text) => Ok(text.into_bytes()),
+            StoredBody::Base64(data) => BASE64_STANDARD.decode(data).map_err(error::decode),
+        }
+    }
+}
+
+fn pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect()
+}
+
+fn header_map(pairs: &[(String, String)]) -> crate::Result<HeaderMap> {
+    let mut headers = HeaderMap::with_capacity(pairs.len());
+    for (name, value) in pairs {
+        let name = HeaderName::from_bytes(name.as_bytes()).map_err(error::decode)?;
+        let value = HeaderValue::from_bytes(value.as_bytes()).map_err(error::decode)?;
+        headers.append(name, value);
+    }
+    Ok(headers)
+}
@@ -0,0 +1,313 @@
+//! Detection of the encoding of text responses.
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use futures_util::{Stream, StreamExt};
+use http::header::{HeaderMap, CONTENT_TYPE};
+use mime::Mime;
+
+use super::response::Response;
+
+type BoxStream<T> = Pin<Box<dyn Stream<Item = crate::Result<T>> + Send>>;
+
+/// How many bytes of a document browsers look through for a `<meta>`
+/// declaring its encoding.
+const PRESCAN_LEN: usize = 1024;
+
+/// The encoding the `Content-Type` of a response declares, and whether the
+/// body may declare one itself, being HTML.
+pub(super) fn declared(headers: &HeaderMap) -> (Option<&'static Encoding>, bool) {
+    let Some(value) = headers.get(CONTENT_TYPE) else {
+        return (None, true);
+    };
+    let Some(mime) = value.to_str().ok().and_then(|v| v.parse::<Mime>().ok()) else {
+        return (None, false);
+    };
+    let encoding = mime
+        .get_param("charset")
+        .map(|charset| Encoding::for_label(charset.as_str().as_bytes()).unwrap_or(UTF_8));
+    let html = mime.essence_str() == "text/html" || mime.essence_str() == "application/xhtml+xml";
+    (encoding, html)
+}
+
+/// The encoding a `<meta>` tag at the start of an HTML document declares,
+/// found like browsers prescan a document.
+pub(super) fn prescan(bytes: &[u8]) -> Option<&'static Encoding> {
+    let bytes = &bytes[..bytes.len().min(PRESCAN_LEN)];
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        if rest.starts_with(b"<!--") {
+            i += 4 + find(&rest[4..], b"-->")? + 3;
+        } else if starts_with_ignore_case(rest, b"<meta")
+            && rest.get(5).is_some_and(|b| is_space(*b) || *b == b'/')
+        {
+            let (encoding, len) = meta(&rest[5..]);
+            if encoding.is_some() {
+                return encoding;
+            }
+            i += 5 + len;
+        } else if rest.starts_with(b"<!") || rest.starts_with(b"</") || rest.starts_with(b"<?") {
+            i += find(rest, b">")? + 1;
+        } else if rest.starts_with(b"<") && rest.get(1).is_some_and(u8::is_ascii_alphabetic) {
+            i += find(rest, b">")? + 1;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// The encoding the attributes of a `<meta>` tag declare, and their length.
+fn meta(bytes: &[u8]) -> (Option<&'static Encoding>, usize) {
+    let mut charset = None;
+    let mut http_equiv = false;
+    let mut content = None;
+
+    let mut i = 0;
+    loop {
+        while bytes.get(i).is_some_and(|b| is_space(*b) || *b == b'/') {
+            i += 1;
+        }
+        match bytes.get(i) {
+            None => return (None, i),
+            Some(b'>') => break,
+            Some(_) => {}
+        }
+
+        let start = i;
+        while bytes
+            .get(i)
+            .is_some_and(|b| !is_space(*b) && !matches!(b, b'=' | b'>' | b'/'))
+        {
+            i += 1;
+        }
+        let name = &bytes[start..i];
+        while bytes.get(i).is_some_and(|b| is_space(*b)) {
+            i += 1;
+        }
+        let mut value: &[u8] = &[];
+        if bytes.get(i) == Some(&b'=') {
+            i += 1;
+            while bytes.get(i).is_some_and(|b| is_space(*b)) {
+                i += 1;
+            }
+            match bytes.get(i) {
+                Some(&quote @ (b'"' | b'\'')) => {
+                    let start = i + 1;
+                    let Some(len) = find(&bytes[start..], &[quote]) else {
+                        return (None, bytes.len());
+                    };
+                    value = &bytes[start..start + len];
+                    i = start + len + 1;
+                }
+                _ => {
+                    let start = i;
+                    while bytes.get(i).is_some_and(|b| !is_space(*b) && *b != b'>') {
+                        i += 1;
+                    }
+                    value = &bytes[start..i];
+                }
+            }
+        }
+
+        if name.eq_ignore_ascii_case(b"charset") && charset.is_none() {
+            charset = Some(value);
+        } else if name.eq_ignore_ascii_case(b"http-equiv") {
+            http_equiv |= value.eq_ignore_ascii_case(b"content-type");
+        } else if name.eq_ignore_ascii_case(b"content") && content.is_none() {
+            content = Some(value);
+        }
+    }
+
+    let label = charset.or_else(|| {
+        content
+            .filter(|_| http_equiv)
+            .and_then(charset_from_content)
+    });
+    let encoding = label
+        .and_then(|label| Encoding::for_label(label))
+        .map(|encoding| {
+            // a document declaring itself UTF-16 would not have been readable
+            // as ASCII to find the declaration
+            if encoding == encoding_rs::UTF_16BE || encoding == encoding_rs::UTF_16LE {
+                UTF_8
+            } else if encoding == encoding_rs::X_USER_DEFINED {
+                WINDOWS_1252
+            } else {
+                encoding
+            }
+        });
+    (encoding, i)
+}
+
+/// The `charset=` value in the `content` of a `<meta http-equiv>`.
+fn charset_from_content(content: &[u8]) -> Option<&[u8]> {
+    let mut rest = content;
+    loop {
+        let at = rest
+            .windows(7)
+            .position(|w| w.eq_ignore_ascii_case(b"charset"))?;
+        rest = &rest[at + 7..];
+        let after = rest.iter().position(|b| !is_space(*b))?;
+        if rest[after] != b'=' {
+            continue;
+        }
+        rest = &rest[after + 1..];
+        let start = rest.iter().position(|b| !is_space(*b))?;
+        rest = &rest[start..];
+        return match rest[0] {
+            quote @ (b'"' | b'\'') => {
+                let len = find(&rest[1..], &[quote])?;
+                Some(&rest[1..1 + len])
+            }
+            _ => {
+                let len = rest
+                    .iter()
+                    .position(|b| is_space(*b) || *b == b';')
+                    .unwrap_or(rest.len());
+                Some(&rest[..len])
+            }
+        };
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn starts_with_ignore_case(bytes: &[u8], prefix: &[u8]) -> bool {
+    bytes
+        .get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+fn is_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\x0c' | b'\r')
+}
+
+/// The text of a response, decoded as it arrives.
+///
+/// To construct a `TextStream`, refer to [`Response::text_lossy_stream`].
+pub struct TextStream {
+    body: BoxStream<Bytes>,
+    sniff: Option<(Vec<u8>, &'static Encoding)>,
+    decoder: Option<encoding_rs::Decoder>,
+    done: bool,
+}
+
+impl TextStream {
+    pub(super) fn new(res: Response, default_encoding: &'static Encoding) -> TextStream {
+        let (declared, html) = declared(res.headers());
+        let body = futures_util::stream::unfold(res, |mut res| async move {
+            res.chunk().await.transpose().map(|chunk| (chunk, res))
+        });
+        let (sniff, decoder) = match declared {
+            Some(encoding) => (None, Some(encoding.new_decoder())),
+            None if html => (Some((Vec::new(), default_encoding)), None),
+            None => (None, Some(default_encoding.new_decoder())),
+        };
+        TextStream {
+            body: Box::pin(body),
+            sniff,
+            decoder,
+            done: false,
+        }
+    }
+
+    /// Decode `bytes`, once the encoding is known.
+    fn decode(&mut self, bytes: &[u8], last: bool) -> Option<String> {
+        if self.decoder.is_none() {
+            let (buffered, default) = self.sniff.as_mut()?;
+            buffered.extend_from_slice(bytes);
+            if buffered.len() < PRESCAN_LEN && !last {
+                return None;
+            }
+            let encoding = prescan(buffered).unwrap_or(*default);
+            let buffered = std::mem::take(buffered);
+            self.sniff = None;
+            let decoder = self.decoder.insert(encoding.new_decoder());
+            return Some(decode(decoder, &buffered, last));
+        }
+        let decoder = self.decoder.as_mut()?;
+        Some(decode(decoder, bytes, last))
+    }
+}
+
+fn decode(decoder: &mut encoding_rs::Decoder, bytes: &[u8], last: bool) -> String {
+    let len = decoder
+        .max_utf8_buffer_length(bytes.len())
+        .unwrap_or(bytes.len() * 3 + 16);
+    let mut text = String::with_capacity(len);
+    let _ = decoder.decode_to_string(bytes, &mut text, last);
+    text
+}
+
+impl Stream for TextStream {
+    type Item = crate::Result<String>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.done {
+                return Poll::Ready(None);
+            }
+            let text = match futures_util::ready!(self.body.poll_next_unpin(cx)) {
+                Some(Ok(chunk)) => self.decode(&chunk, false),
+                Some(Err(err)) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(err)));
+                }
+                None => {
+                    self.done = true;
+                    self.decode(&[], true)
+                }
+            };
+            match text {
+                Some(text) if !text.is_empty() => return Poll::Ready(Some(Ok(text))),
+                _ => continue,
+            }
+        }
+    }
+}
+
+impl fmt::Debug for TextStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TextStream")
+            .field(
+                "encoding",
+                &self.decoder.as_ref().map(|d| d.encoding().name()),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_meta_charset() {
+        assert_eq!(
+            prescan(b"<!doctype html><html><head><meta charset=\"Shift_JIS\">"),
+            Some(encoding_rs::SHIFT_JIS)
+        );
+        assert_eq!(
+            prescan(b"<meta http-equiv=Content-Type content='text/html; charset=koi8-r'>"),
+            Some(encoding_rs::KOI8_R)
+        );
+        assert_eq!(prescan(b"<META CHARSET=utf-16>"), Some(UTF_8));
+    }
+
+    #[test]
+    fn skips_comments_and_other_tags() {
+        assert_eq!(prescan(b"<!-- <meta charset=gbk> --><p>x</p>"), None);
+        assert_eq!(prescan(b"<meta content='charset=gbk'>"), None);
+        assert_eq!(
+            prescan(b"<title a='>'>t</title><meta charset=gbk>"),
+            Some(encoding_rs::GBK)
+        );
+    }
+}
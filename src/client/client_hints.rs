@@ -0,0 +1,361 @@
+//! User-agent client hints, the high entropy details of the browser and its
+//! platform that origins ask for with `Accept-CH`.
+//!
+//! See [User-Agent Client Hints](https://wicg.github.io/ua-client-hints/).
+use std::num::NonZeroUsize;
+
+use antidote::Mutex;
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use lru::LruCache;
+use url::Url;
+
+use super::fetch;
+
+/// How many origins a client remembers the hints asked for by.
+const MAX_ENTRIES: usize = 1024;
+
+pub(super) const ACCEPT_CH: HeaderName = HeaderName::from_static("accept-ch");
+pub(super) const CRITICAL_CH: HeaderName = HeaderName::from_static("critical-ch");
+
+const SEC_CH_UA: HeaderName = HeaderName::from_static("sec-ch-ua");
+const SEC_CH_UA_ARCH: HeaderName = HeaderName::from_static("sec-ch-ua-arch");
+const SEC_CH_UA_BITNESS: HeaderName = HeaderName::from_static("sec-ch-ua-bitness");
+const SEC_CH_UA_FULL_VERSION: HeaderName = HeaderName::from_static("sec-ch-ua-full-version");
+const SEC_CH_UA_FULL_VERSION_LIST: HeaderName =
+    HeaderName::from_static("sec-ch-ua-full-version-list");
+const SEC_CH_UA_MODEL: HeaderName = HeaderName::from_static("sec-ch-ua-model");
+const SEC_CH_UA_PLATFORM_VERSION: HeaderName =
+    HeaderName::from_static("sec-ch-ua-platform-version");
+const SEC_CH_UA_WOW64: HeaderName = HeaderName::from_static("sec-ch-ua-wow64");
+
+/// The high entropy client hints a client sends to the origins asking for
+/// them.
+///
+/// Only the details are set here: the hint headers are generated from them
+/// in the structured field syntax browsers use. The brands of
+/// `Sec-CH-UA-Full-Version-List` are the ones of the `Sec-CH-UA` header of
+/// the request, as set by [`Impersonate`](crate::Impersonate), so the two
+/// always agree, with the [`full_version`](ClientHints::full_version) for
+/// the brands of its major version.
+///
+/// # Example
+///
+/// ```
+/// let hints = rquest::ClientHints::new()
+///     .platform_version("15.0.0")
+///     .arch("x86")
+///     .bitness("64")
+///     .full_version("124.0.6367.60");
+///
+/// let client = rquest::Client::builder()
+///     .client_hints(hints)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ClientHints {
+    platform_version: Option<String>,
+    arch: Option<String>,
+    bitness: Option<String>,
+    model: Option<String>,
+    full_version: Option<String>,
+    full_version_list: Option<Vec<(String, String)>>,
+    wow64: Option<bool>,
+}
+
+impl ClientHints {
+    /// Hints with no details set, sending no headers.
+    pub fn new() -> ClientHints {
+        ClientHints::default()
+    }
+
+    /// Set the version of the platform, for `Sec-CH-UA-Platform-Version`,
+    /// such as `"15.0.0"` for Windows 11.
+    pub fn platform_version<S: Into<String>>(mut self, version: S) -> ClientHints {
+        self.platform_version = Some(version.into());
+        self
+    }
+
+    /// Set the CPU architecture, for `Sec-CH-UA-Arch`, such as `"x86"` or
+    /// `"arm"`.
+    pub fn arch<S: Into<String>>(mut self, arch: S) -> ClientHints {
+        self.arch = Some(arch.into());
+        self
+    }
+
+    /// Set the CPU bitness, for `Sec-CH-UA-Bitness`, such as `"64"`.
+    pub fn bitness<S: Into<String>>(mut self, bitness: S) -> ClientHints {
+        self.bitness = Some(bitness.into());
+        self
+    }
+
+    /// Set the device model, for `Sec-CH-UA-Model`, empty on desktops.
+    pub fn model<S: Into<String>>(mut self, model: S) -> ClientHints {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Set the full version of the browser, for `Sec-CH-UA-Full-Version`
+    /// and the brands of `Sec-CH-UA-Full-Version-List` sharing its major
+    /// version.
+    pub fn full_version<S: Into<String>>(mut self, version: S) -> ClientHints {
+        self.full_version = Some(version.into());
+        self
+    }
+
+    /// Set the brands and full versions of `Sec-CH-UA-Full-Version-List`,
+    /// instead of generating them from the `Sec-CH-UA` header.
+    pub fn full_version_list<I, B, V>(mut self, brands: I) -> ClientHints
+    where
+        I: IntoIterator<Item = (B, V)>,
+        B: Into<String>,
+        V: Into<String>,
+    {
+        self.full_version_list = Some(
+            brands
+                .into_iter()
+                .map(|(brand, version)| (brand.into(), version.into()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Set whether the browser is a 32-bit one on 64-bit Windows, for
+    /// `Sec-CH-UA-WoW64`.
+    pub fn wow64(mut self, enabled: bool) -> ClientHints {
+        self.wow64 = Some(enabled);
+        self
+    }
+
+    /// The value of the hint `name`, for a request with the `sec_ch_ua`
+    /// header, if it is set.
+    fn value(&self, name: &HeaderName, sec_ch_ua: Option<&HeaderValue>) -> Option<HeaderValue> {
+        let value = if name == SEC_CH_UA_PLATFORM_VERSION {
+            sf_string(self.platform_version.as_ref()?)
+        } else if name == SEC_CH_UA_ARCH {
+            sf_string(self.arch.as_ref()?)
+        } else if name == SEC_CH_UA_BITNESS {
+            sf_string(self.bitness.as_ref()?)
+        } else if name == SEC_CH_UA_MODEL {
+            sf_string(self.model.as_ref()?)
+        } else if name == SEC_CH_UA_FULL_VERSION {
+            sf_string(self.full_version.as_ref()?)
+        } else if name == SEC_CH_UA_WOW64 {
+            (if self.wow64? { "?1" } else { "?0" }).to_owned()
+        } else if name == SEC_CH_UA_FULL_VERSION_LIST {
+            self.full_version_list(sec_ch_ua)?
+        } else {
+            return None;
+        };
+        HeaderValue::try_from(value).ok()
+    }
+
+    fn full_version_list(&self, sec_ch_ua: Option<&HeaderValue>) -> Option<String> {
+        let brands = match self.full_version_list {
+            Some(ref brands) => brands.clone(),
+            None => {
+                let full_version = self.full_version.as_deref()?;
+                let major = full_version.split('.').next()?;
+                parse_brands(sec_ch_ua?.to_str().ok()?)
+                    .into_iter()
+                    .map(|(brand, version)| {
+                        let version = if version == major {
+                            full_version.to_owned()
+                        } else {
+                            format!("{}.0.0.0", version)
+                        };
+                        (brand, version)
+                    })
+                    .collect()
+            }
+        };
+        if brands.is_empty() {
+            return None;
+        }
+
+        let list = brands
+            .iter()
+            .map(|(brand, version)| format!("{};v={}", sf_string(brand), sf_string(version)))
+            .collect::<Vec<_>>();
+        Some(list.join(", "))
+    }
+}
+
+/// The origins that asked for client hints, and the hints sent to them.
+pub(crate) struct HintStore {
+    hints: ClientHints,
+    accepted: Mutex<LruCache<String, Vec<HeaderName>>>,
+}
+
+impl HintStore {
+    pub(crate) fn new(hints: ClientHints) -> HintStore {
+        HintStore {
+            hints,
+            accepted: Mutex::new(LruCache::new(
+                NonZeroUsize::new(MAX_ENTRIES).expect("non-zero"),
+            )),
+        }
+    }
+
+    /// Add the hints the origin of `url` asked for, and that are set, to
+    /// `headers`, keeping the ones already there.
+    pub(crate) fn add(&self, url: &Url, headers: &mut HeaderMap) {
+        let origin = url.origin().ascii_serialization();
+        let Some(accepted) = self.accepted.lock().get(&origin).cloned() else {
+            return;
+        };
+        for name in accepted {
+            if headers.contains_key(&name) {
+                continue;
+            }
+            if let Some(value) = self.hints.value(&name, headers.get(SEC_CH_UA)) {
+                headers.insert(name, value);
+            }
+        }
+    }
+
+    /// Remember the hints a response from `url` asks for with `Accept-CH`.
+    ///
+    /// Like browsers, only trustworthy origins are listened to, and a new
+    /// `Accept-CH` replaces the hints an origin asked for before.
+    pub(crate) fn observe(&self, url: &Url, headers: &HeaderMap) {
+        if !fetch::is_trustworthy(url) || !headers.contains_key(ACCEPT_CH) {
+            return;
+        }
+
+        let hints = parse_hints(headers, ACCEPT_CH);
+        let origin = url.origin().ascii_serialization();
+        let mut accepted = self.accepted.lock();
+        if hints.is_empty() {
+            accepted.pop(&origin);
+        } else {
+            accepted.put(origin, hints);
+        }
+    }
+}
+
+/// Parse the header names listed in the `name` headers of `headers`.
+pub(super) fn parse_hints(headers: &HeaderMap, name: HeaderName) -> Vec<HeaderName> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|hint| HeaderName::from_bytes(hint.trim().as_bytes()).ok())
+        .collect()
+}
+
+/// Parse the brands and versions of a `Sec-CH-UA` value.
+fn parse_brands(value: &str) -> Vec<(String, String)> {
+    value
+        .split(',')
+        .filter_map(|brand| {
+            let (brand, params) = brand.split_once(';')?;
+            let version = params
+                .split(';')
+                .find_map(|param| param.trim().strip_prefix("v="))?;
+            Some((
+                brand.trim().trim_matches('"').to_owned(),
+                version.trim_matches('"').to_owned(),
+            ))
+        })
+        .collect()
+}
+
+/// Serialize `s` as a structured field string.
+fn sf_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHROME: &str =
+        "\"Chromium\";v=\"124\", \"Google Chrome\";v=\"124\", \"Not-A.Brand\";v=\"99\"";
+
+    #[test]
+    fn parse_hint_lists() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            ACCEPT_CH,
+            HeaderValue::from_static("Sec-CH-UA-Platform-Version, sec-ch-ua-arch"),
+        );
+        headers.append(ACCEPT_CH, HeaderValue::from_static("DPR,,bad hint"));
+
+        assert_eq!(
+            parse_hints(&headers, ACCEPT_CH),
+            [
+                HeaderName::from_static("sec-ch-ua-platform-version"),
+                HeaderName::from_static("sec-ch-ua-arch"),
+                HeaderName::from_static("dpr"),
+            ]
+        );
+        assert!(parse_hints(&headers, CRITICAL_CH).is_empty());
+    }
+
+    #[test]
+    fn full_version_list_follows_sec_ch_ua() {
+        let hints = ClientHints::new().full_version("124.0.6367.60");
+        let sec_ch_ua = HeaderValue::from_static(CHROME);
+
+        assert_eq!(
+            hints.value(&SEC_CH_UA_FULL_VERSION_LIST, Some(&sec_ch_ua)),
+            Some(HeaderValue::from_static(
+                "\"Chromium\";v=\"124.0.6367.60\", \"Google Chrome\";v=\"124.0.6367.60\", \"Not-A.Brand\";v=\"99.0.0.0\""
+            ))
+        );
+        assert_eq!(hints.value(&SEC_CH_UA_FULL_VERSION_LIST, None), None);
+
+        let hints = hints.full_version_list([("Brand", "1.2")]);
+        assert_eq!(
+            hints.value(&SEC_CH_UA_FULL_VERSION_LIST, None),
+            Some(HeaderValue::from_static("\"Brand\";v=\"1.2\""))
+        );
+    }
+
+    #[test]
+    fn sends_the_accepted_hints() {
+        let store = HintStore::new(
+            ClientHints::new()
+                .platform_version("15.0.0")
+                .model("")
+                .wow64(false),
+        );
+        let url = Url::parse("https://example.com/").unwrap();
+
+        let mut headers = HeaderMap::new();
+        store.add(&url, &mut headers);
+        assert!(headers.is_empty());
+
+        let mut response = HeaderMap::new();
+        response.insert(
+            ACCEPT_CH,
+            HeaderValue::from_static(
+                "Sec-CH-UA-Platform-Version, Sec-CH-UA-Model, Sec-CH-UA-WoW64, Sec-CH-UA-Arch",
+            ),
+        );
+        store.observe(&url, &response);
+        // plain HTTP origins are ignored
+        store.observe(&Url::parse("http://example.org/").unwrap(), &response);
+
+        store.add(&url, &mut headers);
+        assert_eq!(headers[SEC_CH_UA_PLATFORM_VERSION], "\"15.0.0\"");
+        assert_eq!(headers[SEC_CH_UA_MODEL], "\"\"");
+        assert_eq!(headers[SEC_CH_UA_WOW64], "?0");
+        assert!(!headers.contains_key(SEC_CH_UA_ARCH));
+
+        let mut headers = HeaderMap::new();
+        store.add(&Url::parse("http://example.org/").unwrap(), &mut headers);
+        assert!(headers.is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+//! Compression of request bodies.
+#[cfg(feature = "brotli")]
+use async_compression::tokio::bufread::BrotliEncoder;
+#[cfg(feature = "gzip")]
+use async_compression::tokio::bufread::GzipEncoder;
+#[cfg(feature = "deflate")]
+use async_compression::tokio::bufread::ZlibEncoder;
+#[cfg(feature = "zstd")]
+use async_compression::tokio::bufread::ZstdEncoder;
+#[cfg(any(
+    feature = "gzip",
+    feature = "zstd",
+    feature = "brotli",
+    feature = "deflate"
+))]
+use bytes::Bytes;
+#[cfg(any(
+    feature = "gzip",
+    feature = "zstd",
+    feature = "brotli",
+    feature = "deflate"
+))]
+use tokio_util::io::{ReaderStream, StreamReader};
+
+use super::body::Body;
+#[cfg(any(
+    feature = "gzip",
+    feature = "zstd",
+    feature = "brotli",
+    feature = "deflate"
+))]
+use super::{body::Replay, decoder::IoStream};
+
+/// A content coding to compress request bodies with.
+///
+/// Each coding requires the feature of the same name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// `gzip`
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// `br`
+    #[cfg(feature = "brotli")]
+    Brotli,
+    /// `zstd`
+    #[cfg(feature = "zstd")]
+    Zstd,
+    /// `deflate`, a zlib stream
+    #[cfg(feature = "deflate")]
+    Deflate,
+}
+
+impl Encoding {
+    /// The `Content-Encoding` value of the coding.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            #[cfg(feature = "gzip")]
+            Encoding::Gzip => "gzip",
+            #[cfg(feature = "brotli")]
+            Encoding::Brotli => "br",
+            #[cfg(feature = "zstd")]
+            Encoding::Zstd => "zstd",
+            #[cfg(feature = "deflate")]
+            Encoding::Deflate => "deflate",
+        }
+    }
+}
+
+/// Compress `body`, up front on a blocking thread when it is in memory so
+/// it stays reusable and keeps an exact length, or as it streams otherwise.
+pub(crate) async fn compress(body: Body, encoding: Encoding) -> Body {
+    #[cfg(any(
+        feature = "gzip",
+        feature = "zstd",
+        feature = "brotli",
+        feature = "deflate"
+    ))]
+    {
+        match body.try_reuse() {
+            (Some(Replay::Bytes(bytes)), _) => {
+                match tokio::task::spawn_blocking(move || compress_bytes(&bytes, encoding)).await {
+                    Ok(bytes) => Body::reusable(bytes),
+                    Err(err) => std::panic::resume_unwind(err.into_panic()),
+                }
+            }
+            (_, body) => compress_stream(body, encoding),
+        }
+    }
+
+    #[cfg(not(any(
+        feature = "gzip",
+        feature = "zstd",
+        feature = "brotli",
+        feature = "deflate"
+    )))]
+    {
+        let _ = body;
+        match encoding {}
+    }
+}
+
+#[cfg(any(
+    feature = "gzip",
+    feature = "zstd",
+    feature = "brotli",
+    feature = "deflate"
+))]
+fn compress_bytes(bytes: &[u8], encoding: Encoding) -> Bytes {
+    use futures_util::FutureExt;
+    use tokio::io::AsyncReadExt;
+
+    let mut out = Vec::new();
+    let read = match encoding {
+        #[cfg(feature = "gzip")]
+        Encoding::Gzip => GzipEncoder::new(bytes).read_to_end(&mut out).now_or_never(),
+        #[cfg(feature = "brotli")]
+        Encoding::Brotli => BrotliEncoder::new(bytes)
+            .read_to_end(&mut out)
+            .now_or_never(),
+        #[cfg(feature = "zstd")]
+        Encoding::Zstd => ZstdEncoder::new(bytes).read_to_end(&mut out).now_or_never(),
+        #[cfg(feature = "deflate")]
+        Encoding::Deflate => ZlibEncoder::new(bytes).read_to_end(&mut out).now_or_never(),
+    };
+
+    // Reading from memory never waits, nor fails
+    read.expect("in-memory encoder is always ready")
+        .expect("in-memory encoder never fails");
+    Bytes::from(out)
+}
+
+#[cfg(any(
+    feature = "gzip",
+    feature = "zstd",
+    feature = "brotli",
+    feature = "deflate"
+))]
+fn compress_stream(body: Body, encoding: Encoding) -> Body {
+    let reader = StreamReader::new(IoStream(body));
+    match encoding {
+        #[cfg(feature = "gzip")]
+        Encoding::Gzip => Body::stream(ReaderStream::new(GzipEncoder::new(reader))),
+        #[cfg(feature = "brotli")]
+        Encoding::Brotli => Body::stream(ReaderStream::new(BrotliEncoder::new(reader))),
+        #[cfg(feature = "zstd")]
+        Encoding::Zstd => Body::stream(ReaderStream::new(ZstdEncoder::new(reader))),
+        #[cfg(feature = "deflate")]
+        Encoding::Deflate => Body::stream(ReaderStream::new(ZlibEncoder::new(reader))),
+    }
+}
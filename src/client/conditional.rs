@@ -0,0 +1,170 @@
+//! Conditional requests, revalidating a resource with the validators of the
+//! last response to it.
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use antidote::Mutex;
+use http::header::{HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use http::{Method, StatusCode};
+use log::trace;
+use lru::LruCache;
+
+use super::http::Client;
+use super::request::Request;
+use super::response::Response;
+
+/// How many URLs a client remembers the validators of.
+const MAX_ENTRIES: usize = 1024;
+
+/// The `ETag` and `Last-Modified` of the last successful responses, by URL.
+#[derive(Clone)]
+pub(crate) struct ValidatorCache {
+    entries: Arc<Mutex<LruCache<String, Validators>>>,
+}
+
+#[derive(Clone)]
+struct Validators {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+}
+
+impl ValidatorCache {
+    pub(crate) fn new() -> ValidatorCache {
+        ValidatorCache {
+            entries: Arc::new(Mutex::new(LruCache::new(
+                NonZeroUsize::new(MAX_ENTRIES).expect("non-zero"),
+            ))),
+        }
+    }
+
+    fn get(&self, url: &str) -> Option<Validators> {
+        self.entries.lock().get(url).cloned()
+    }
+
+    fn store(&self, url: &str, res: &Response) {
+        let validators = Validators {
+            etag: res.headers().get(ETAG).cloned(),
+            last_modified: res.headers().get(LAST_MODIFIED).cloned(),
+        };
+        let mut entries = self.entries.lock();
+        if validators.etag.is_none() && validators.last_modified.is_none() {
+            entries.pop(url);
+        } else {
+            entries.put(url.to_owned(), validators);
+        }
+    }
+}
+
+/// A `GET` or `HEAD` request made conditional on the resource having
+/// changed since the last response to it.
+///
+/// The client remembers the `ETag` and `Last-Modified` of the successful
+/// responses sent through conditional requests, and sends them back in
+/// `If-None-Match` and `If-Modified-Since`. A `304 Not Modified` answer is
+/// told apart from a new representation by [`Revalidation`].
+///
+/// To construct a `ConditionalRequest`, refer to
+/// [`RequestBuilder::conditional`].
+///
+/// [`RequestBuilder::conditional`]: super::RequestBuilder::conditional
+///
+/// # Example
+///
+/// ```
+/// # async fn run() -> Result<(), rquest::Error> {
+/// let client = rquest::Client::new();
+/// match client.get("https://example.com/feed").conditional().send().await? {
+///     rquest::Revalidation::Modified(res) => println!("{}", res.text().await?),
+///     rquest::Revalidation::NotModified(_) => println!("still the same"),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "ConditionalRequest does nothing until it is sent"]
+pub struct ConditionalRequest {
+    client: Client,
+    request: crate::Result<Request>,
+}
+
+/// The outcome of a [`ConditionalRequest`].
+#[derive(Debug)]
+pub enum Revalidation {
+    /// The resource changed, or was not known yet; the response holds it.
+    Modified(Response),
+    /// The server answered `304 Not Modified`, so the last representation
+    /// received is still current.
+    NotModified(Response),
+}
+
+impl ConditionalRequest {
+    pub(super) fn new(client: Client, request: crate::Result<Request>) -> ConditionalRequest {
+        ConditionalRequest { client, request }
+    }
+
+    /// Send the request, with the validators of the last response to its
+    /// URL unless it sets `If-None-Match` or `If-Modified-Since` itself.
+    ///
+    /// Other methods than `GET` and `HEAD` are sent as they are.
+    pub async fn send(self) -> crate::Result<Revalidation> {
+        let mut req = self.request?;
+        let cache = self.client.validators();
+        let url = req.url().as_str().to_owned();
+        let cacheable = matches!(*req.method(), Method::GET | Method::HEAD);
+
+        if cacheable {
+            if let Some(validators) = cache.get(&url) {
+                let headers = req.headers_mut();
+                if !headers.contains_key(IF_NONE_MATCH) && !headers.contains_key(IF_MODIFIED_SINCE)
+                {
+                    trace!("revalidating {}", url);
+                    if let Some(etag) = validators.etag {
+                        headers.insert(IF_NONE_MATCH, etag);
+                    }
+                    if let Some(last_modified) = validators.last_modified {
+                        headers.insert(IF_MODIFIED_SINCE, last_modified);
+                    }
+                }
+            }
+        }
+
+        let res = self.client.execute_request(req).await?;
+        if res.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Revalidation::NotModified(res));
+        }
+        if cacheable && res.status().is_success() {
+            cache.store(&url, &res);
+        }
+        Ok(Revalidation::Modified(res))
+    }
+}
+
+impl fmt::Debug for ConditionalRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ConditionalRequest")
+            .field("request", &self.request)
+            .finish()
+    }
+}
+
+impl Revalidation {
+    /// Whether the server sent a new representation.
+    pub fn is_modified(&self) -> bool {
+        matches!(self, Revalidation::Modified(_))
+    }
+
+    /// The response, whichever it is.
+    pub fn into_response(self) -> Response {
+        match self {
+            Revalidation::Modified(res) | Revalidation::NotModified(res) => res,
+        }
+    }
+
+    /// The new representation, or `None` if it was not modified.
+    pub fn modified(self) -> Option<Response> {
+        match self {
+            Revalidation::Modified(res) => Some(res),
+            Revalidation::NotModified(_) => None,
+        }
+    }
+}
@@ -45,6 +45,31 @@ pub struct HttpContext {
     /// Header ordering for requests
     #[builder(default, setter(strip_option, into))]
     pub(crate) headers_order: Option<Cow<'static, [HeaderName]>>,
+
+    /// Multipart form formatting
+    #[builder(default, setter(strip_option))]
+    pub(crate) multipart_style: Option<MultipartStyle>,
+}
+
+/// How multipart forms are formatted.
+///
+/// Browsers and HTTP libraries each pick their own boundaries and escape
+/// field names their own way, which makes multipart bodies another surface
+/// to fingerprint a client with. Impersonation picks the style of the
+/// impersonated client.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MultipartStyle {
+    /// Random hexadecimal boundaries, and field names percent-encoded as
+    /// `name*=` when they are not plain ASCII.
+    #[default]
+    Default,
+    /// Chrome and Safari: `----WebKitFormBoundary` boundaries.
+    WebKit,
+    /// Firefox: `----geckoformboundary` boundaries.
+    Gecko,
+    /// OkHttp: UUID boundaries.
+    OkHttp,
 }
 
 /// Implement `HttpContextProvider` for `HttpContext`.
@@ -0,0 +1,164 @@
+//! Rendering of requests as curl commands.
+use std::fmt::Write;
+
+use http::header::{HeaderMap, ACCEPT_ENCODING, CONTENT_LENGTH};
+use http::{Method, Version};
+
+use super::request::Request;
+use crate::proxy::ProxyScheme;
+
+/// Render `req` with `headers` and going through `proxy` as a curl command.
+pub(super) fn command(req: &Request, headers: &HeaderMap, proxy: Option<&ProxyScheme>) -> String {
+    let mut args = vec!["curl".to_owned()];
+
+    let body = req.body();
+    match *req.method() {
+        Method::GET if body.is_none() => {}
+        Method::HEAD => args.push("--head".to_owned()),
+        ref method => args.push(format!("-X {}", quote(method.as_str()))),
+    }
+
+    match req.raw_path_and_query() {
+        Some(path_and_query) => {
+            let url = req.url();
+            let authority = &url[url::Position::BeforeHost..url::Position::AfterPort];
+            args.push("--path-as-is".to_owned());
+            args.push(quote(&format!(
+                "{}://{}{}",
+                url.scheme(),
+                authority,
+                path_and_query
+            )));
+        }
+        None => args.push(quote(req.url().as_str())),
+    }
+
+    match req.version() {
+        Some(Version::HTTP_10) => args.push("--http1.0".to_owned()),
+        Some(Version::HTTP_11) => args.push("--http1.1".to_owned()),
+        Some(Version::HTTP_2) => args.push("--http2".to_owned()),
+        Some(Version::HTTP_3) => args.push("--http3".to_owned()),
+        _ => {}
+    }
+
+    for (name, value) in headers {
+        // curl counts the body itself
+        if *name == CONTENT_LENGTH {
+            continue;
+        }
+        let value = String::from_utf8_lossy(value.as_bytes());
+        // an empty value needs a `;` for curl to send the header at all
+        if value.is_empty() {
+            args.push(format!("-H {}", quote(&format!("{name};"))));
+        } else {
+            args.push(format!("-H {}", quote(&format!("{name}: {value}"))));
+        }
+    }
+    if headers.contains_key(ACCEPT_ENCODING) {
+        args.push("--compressed".to_owned());
+    }
+
+    if let Some(proxy) = proxy {
+        push_proxy(&mut args, proxy);
+    }
+
+    if let Some(body) = body {
+        match body.as_bytes() {
+            Some(bytes) => args.push(format!("--data-binary {}", quote_bytes(bytes))),
+            // a stream can not be shown, it is read from stdin instead
+            None => args.push("--data-binary @-".to_owned()),
+        }
+    }
+
+    args.join(" \\\n  ")
+}
+
+fn push_proxy(args: &mut Vec<String>, proxy: &ProxyScheme) {
+    match proxy {
+        ProxyScheme::Http { auth, host, .. } | ProxyScheme::Https { auth, host, .. } => {
+            let scheme = match proxy {
+                ProxyScheme::Https { .. } => "https",
+                _ => "http",
+            };
+            args.push(format!("--proxy {}", quote(&format!("{scheme}://{host}"))));
+            if let Some(auth) = auth {
+                let auth = String::from_utf8_lossy(auth.as_bytes());
+                args.push(format!(
+                    "--proxy-header {}",
+                    quote(&format!("Proxy-Authorization: {auth}"))
+                ));
+            }
+        }
+        #[cfg(feature = "socks")]
+        ProxyScheme::Socks4 {
+            addr, remote_dns, ..
+        } => {
+            let scheme = if *remote_dns { "socks4a" } else { "socks4" };
+            args.push(format!("--proxy {}", quote(&format!("{scheme}://{addr}"))));
+        }
+        #[cfg(feature = "socks")]
+        ProxyScheme::Socks5 {
+            addr,
+            auth,
+            remote_dns,
+        } => {
+            let scheme = if *remote_dns { "socks5h" } else { "socks5" };
+            args.push(format!("--proxy {}", quote(&format!("{scheme}://{addr}"))));
+            if let Some((username, password)) = auth {
+                args.push(format!(
+                    "--proxy-user {}",
+                    quote(&format!("{username}:{password}"))
+                ));
+            }
+        }
+    }
+}
+
+/// Quote `s` as a single shell word.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Quote `bytes` as a single shell word, escaping them if they are not text.
+fn quote_bytes(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) if !s.contains('\0') => quote(s),
+        _ => {
+            let mut quoted = String::from("$'");
+            for &b in bytes {
+                let _ = write!(quoted, "\\x{b:02x}");
+            }
+            quoted.push('\'');
+            quoted
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_shell_words() {
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(quote_bytes(b"a b"), "'a b'");
+        assert_eq!(quote_bytes(&[0, 0xff]), r"$'\x00\xff'");
+    }
+
+    #[test]
+    fn renders_request() {
+        let mut req = Request::new(Method::POST, "https://a.test/p?q=1".parse().unwrap());
+        *req.body_mut() = Some("{\"a\":1}".into());
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json".parse().unwrap());
+        headers.insert("content-length", "7".parse().unwrap());
+        headers.insert("x-empty", "".parse().unwrap());
+
+        assert_eq!(
+            command(&req, &headers, None),
+            "curl \\\n  -X 'POST' \\\n  'https://a.test/p?q=1' \\\n  \
+             -H 'content-type: application/json' \\\n  -H 'x-empty;' \\\n  \
+             --data-binary '{\"a\":1}'"
+        );
+    }
+}
@@ -7,6 +7,8 @@ use std::fmt;
 ))]
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 #[cfg(any(
@@ -57,7 +59,9 @@ use tokio_util::codec::{BytesCodec, FramedRead};
 ))]
 use tokio_util::io::StreamReader;
 
+use super::abort::{AbortHandle, Aborted};
 use super::body::ResponseBody;
+use super::stats::{Transfer, TransferStats};
 
 #[derive(Clone, Copy, Debug)]
 pub(super) struct Accepts {
@@ -76,8 +80,23 @@ pub(super) struct Accepts {
 /// The inner decoder may be constructed asynchronously.
 pub(crate) struct Decoder {
     inner: Inner,
+    max_size: Option<u64>,
+    max_ratio: Option<u32>,
+    received: u64,
+    /// The bytes read before decompression, for compressed bodies.
+    compressed: Option<Arc<AtomicU64>>,
+    #[cfg(feature = "json")]
+    recording: Option<super::har::Recording>,
+    /// Fails the body once its request is canceled.
+    aborted: Option<Aborted>,
+    /// Counts the decoded bytes.
+    transfer: Option<Transfer>,
 }
 
+/// The output a compressed body may reach before its ratio is checked, as
+/// the first bytes of legitimate bodies often expand a lot.
+const RATIO_GRACE: u64 = 64 * 1024;
+
 #[cfg(any(
     feature = "gzip",
     feature = "zstd",
@@ -140,7 +159,7 @@ struct Pending(PeekableIoStream, DecoderType);
     feature = "brotli",
     feature = "deflate",
 ))]
-pub(crate) struct IoStream<B = ResponseBody>(B);
+pub(crate) struct IoStream<B = ResponseBody>(pub(super) B);
 
 #[cfg(any(
     feature = "gzip",
@@ -166,13 +185,90 @@ impl fmt::Debug for Decoder {
 }
 
 impl Decoder {
+    fn new(inner: Inner) -> Decoder {
+        Decoder {
+            inner,
+            max_size: None,
+            max_ratio: None,
+            received: 0,
+            compressed: None,
+            #[cfg(feature = "json")]
+            recording: None,
+            aborted: None,
+            transfer: None,
+        }
+    }
+
+    /// A decoder for a compressed body, counting its bytes.
+    #[cfg(any(
+        feature = "gzip",
+        feature = "zstd",
+        feature = "brotli",
+        feature = "deflate"
+    ))]
+    fn compressed(body: ResponseBody, decoder_type: DecoderType) -> Decoder {
+        use futures_util::StreamExt;
+        use http_body_util::BodyExt;
+
+        let compressed = Arc::new(AtomicU64::new(0));
+        let body = {
+            let compressed = compressed.clone();
+            body.map_frame(move |frame| {
+                if let Some(data) = frame.data_ref() {
+                    compressed.fetch_add(data.len() as u64, Ordering::Relaxed);
+                }
+                frame
+            })
+            .boxed()
+        };
+
+        Decoder {
+            compressed: Some(compressed),
+            ..Decoder::new(Inner::Pending(Box::pin(Pending(
+                IoStream(body).peekable(),
+                decoder_type,
+            ))))
+        }
+    }
+
+    /// Fail once the decoded body grows past `max_size` bytes.
+    pub(super) fn max_size(mut self, max_size: Option<u64>) -> Decoder {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Fail once a compressed body expands more than `max_ratio` times.
+    pub(super) fn max_ratio(mut self, max_ratio: Option<u32>) -> Decoder {
+        self.max_ratio = max_ratio;
+        self
+    }
+
+    /// Record the decoded body in a HAR entry.
+    #[cfg(feature = "json")]
+    pub(super) fn record(&mut self, recording: super::har::Recording) {
+        self.recording = Some(recording);
+    }
+
+    /// Fail the body once `handle` is aborted.
+    pub(super) fn abort_on(&mut self, handle: &AbortHandle) {
+        self.aborted = Some(handle.aborted());
+    }
+
+    /// Count the decoded bytes in the counters of `transfer`.
+    pub(super) fn count(&mut self, transfer: Transfer) {
+        self.transfer = Some(transfer);
+    }
+
+    /// The counters of the request the body answers.
+    pub(super) fn stats(&self) -> Option<TransferStats> {
+        self.transfer.as_ref().map(Transfer::stats)
+    }
+
     /// A plain text decoder.
     ///
     /// This decoder will emit the underlying chunks as-is.
     fn plain_text(body: ResponseBody) -> Decoder {
-        Decoder {
-            inner: Inner::PlainText(body),
-        }
+        Decoder::new(Inner::PlainText(body))
     }
 
     /// A gzip decoder.
@@ -180,14 +276,7 @@ impl Decoder {
     /// This decoder will buffer and decompress chunks that are gzipped.
     #[cfg(feature = "gzip")]
     fn gzip(body: ResponseBody) -> Decoder {
-        use futures_util::StreamExt;
-
-        Decoder {
-            inner: Inner::Pending(Box::pin(Pending(
-                IoStream(body).peekable(),
-                DecoderType::Gzip,
-            ))),
-        }
+        Decoder::compressed(body, DecoderType::Gzip)
     }
 
     /// A brotli decoder.
@@ -195,14 +284,7 @@ impl Decoder {
     /// This decoder will buffer and decompress chunks that are brotlied.
     #[cfg(feature = "brotli")]
     fn brotli(body: ResponseBody) -> Decoder {
-        use futures_util::StreamExt;
-
-        Decoder {
-            inner: Inner::Pending(Box::pin(Pending(
-                IoStream(body).peekable(),
-                DecoderType::Brotli,
-            ))),
-        }
+        Decoder::compressed(body, DecoderType::Brotli)
     }
 
     /// A zstd decoder.
@@ -210,14 +292,7 @@ impl Decoder {
     /// This decoder will buffer and decompress chunks that are zstd compressed.
     #[cfg(feature = "zstd")]
     fn zstd(body: ResponseBody) -> Decoder {
-        use futures_util::StreamExt;
-
-        Decoder {
-            inner: Inner::Pending(Box::pin(Pending(
-                IoStream(body).peekable(),
-                DecoderType::Zstd,
-            ))),
-        }
+        Decoder::compressed(body, DecoderType::Zstd)
     }
 
     /// A deflate decoder.
@@ -225,14 +300,7 @@ impl Decoder {
     /// This decoder will buffer and decompress chunks that are deflated.
     #[cfg(feature = "deflate")]
     fn deflate(body: ResponseBody) -> Decoder {
-        use futures_util::StreamExt;
-
-        Decoder {
-            inner: Inner::Pending(Box::pin(Pending(
-                IoStream(body).peekable(),
-                DecoderType::Deflate,
-            ))),
-        }
+        Decoder::compressed(body, DecoderType::Deflate)
     }
 
     #[cfg(any(
@@ -321,6 +389,113 @@ impl HttpBody for Decoder {
         mut self: Pin<&mut Self>,
         cx: &mut Context,
     ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if let Some(aborted) = self.aborted.as_mut() {
+            if aborted.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Some(Err(crate::error::body(crate::error::Canceled))));
+            }
+        }
+
+        #[cfg(feature = "json")]
+        if self.recording.is_some() {
+            let frame = futures_util::ready!(self.as_mut().poll_counted(cx));
+            match frame {
+                Some(Ok(ref frame)) => {
+                    if let (Some(data), Some(recording)) = (frame.data_ref(), &mut self.recording) {
+                        recording.data(data);
+                    }
+                }
+                // the entry is complete
+                None => self.recording = None,
+                Some(Err(_)) => {}
+            }
+            return Poll::Ready(frame);
+        }
+
+        self.poll_counted(cx)
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        match self.inner {
+            Inner::PlainText(ref body) => HttpBody::size_hint(body),
+            // the rest are "unknown", so default
+            #[cfg(any(
+                feature = "brotli",
+                feature = "zstd",
+                feature = "gzip",
+                feature = "deflate"
+            ))]
+            _ => http_body::SizeHint::default(),
+        }
+    }
+}
+
+impl Decoder {
+    /// Poll the decoded body within its limits, counting its bytes.
+    fn poll_counted(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Bytes>, crate::Error>>> {
+        let frame = futures_util::ready!(self.as_mut().poll_limited(cx));
+        if let (Some(transfer), Some(data)) = (
+            &self.transfer,
+            frame
+                .as_ref()
+                .and_then(|frame| frame.as_ref().ok())
+                .and_then(Frame::data_ref),
+        ) {
+            transfer.decoded(data.len());
+        }
+        Poll::Ready(frame)
+    }
+
+    /// Poll the decoded body, within the size and ratio limits.
+    fn poll_limited(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Bytes>, crate::Error>>> {
+        if self.max_size.is_none() && self.max_ratio.is_none() {
+            return self.poll_decoded(cx);
+        }
+
+        // Bodies announcing their length fail before it is read
+        if let Some(max_size) = self.max_size {
+            if self.received + self.size_hint().lower() > max_size {
+                return Poll::Ready(Some(Err(crate::error::body(crate::error::BodyTooLarge(
+                    max_size,
+                )))));
+            }
+        }
+
+        let frame = futures_util::ready!(self.as_mut().poll_decoded(cx));
+        if let Some(data) = frame
+            .as_ref()
+            .and_then(|frame| frame.as_ref().ok())
+            .and_then(Frame::data_ref)
+        {
+            self.received += data.len() as u64;
+            if let Some(max_size) = self.max_size.filter(|max| self.received > *max) {
+                return Poll::Ready(Some(Err(crate::error::body(crate::error::BodyTooLarge(
+                    max_size,
+                )))));
+            }
+            if let (Some(max_ratio), Some(compressed)) = (self.max_ratio, &self.compressed) {
+                let compressed = compressed.load(Ordering::Relaxed);
+                if self.received > RATIO_GRACE
+                    && self.received > compressed.saturating_mul(max_ratio.into())
+                {
+                    return Poll::Ready(Some(Err(crate::error::decode(
+                        crate::error::DecompressionLimit(max_ratio),
+                    ))));
+                }
+            }
+        }
+        Poll::Ready(frame)
+    }
+
+    fn poll_decoded(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Bytes>, crate::Error>>> {
         match self.inner {
             #[cfg(any(
                 feature = "brotli",
@@ -331,7 +506,7 @@ impl HttpBody for Decoder {
             Inner::Pending(ref mut future) => match Pin::new(future).poll(cx) {
                 Poll::Ready(Ok(inner)) => {
                     self.inner = inner;
-                    self.poll_frame(cx)
+                    self.poll_decoded(cx)
                 }
                 Poll::Ready(Err(e)) => Poll::Ready(Some(Err(crate::error::decode_io(e)))),
                 Poll::Pending => Poll::Pending,
@@ -401,20 +576,6 @@ impl HttpBody for Decoder {
             }
         }
     }
-
-    fn size_hint(&self) -> http_body::SizeHint {
-        match self.inner {
-            Inner::PlainText(ref body) => HttpBody::size_hint(body),
-            // the rest are "unknown", so default
-            #[cfg(any(
-                feature = "brotli",
-                feature = "zstd",
-                feature = "gzip",
-                feature = "deflate"
-            ))]
-            _ => http_body::SizeHint::default(),
-        }
-    }
 }
 
 #[cfg(any(
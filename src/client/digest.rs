@@ -0,0 +1,332 @@
+//! HTTP Digest access authentication ([RFC 7616]).
+//!
+//! [RFC 7616]: https://datatracker.ietf.org/doc/html/rfc7616
+use std::fmt::{self, Write};
+
+use boring2::hash::{hash, MessageDigest};
+use http::header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use http::Method;
+use log::debug;
+
+use super::http::Client;
+use super::request::Request;
+use super::response::Response;
+use crate::{error, StatusCode, Url};
+
+/// The credentials of a request authenticating with Digest.
+#[derive(Clone)]
+pub(crate) struct DigestAuth {
+    username: String,
+    password: String,
+}
+
+impl DigestAuth {
+    pub(crate) fn new(username: String, password: String) -> DigestAuth {
+        DigestAuth { username, password }
+    }
+}
+
+impl fmt::Debug for DigestAuth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DigestAuth")
+            .field("username", &self.username)
+            .finish()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Algorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+}
+
+impl Algorithm {
+    fn parse(name: &str) -> Option<Algorithm> {
+        match name.to_ascii_uppercase().as_str() {
+            "MD5" => Some(Algorithm::Md5),
+            "MD5-SESS" => Some(Algorithm::Md5Sess),
+            "SHA-256" => Some(Algorithm::Sha256),
+            "SHA-256-SESS" => Some(Algorithm::Sha256Sess),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "MD5",
+            Algorithm::Md5Sess => "MD5-sess",
+            Algorithm::Sha256 => "SHA-256",
+            Algorithm::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    fn is_sess(self) -> bool {
+        matches!(self, Algorithm::Md5Sess | Algorithm::Sha256Sess)
+    }
+
+    fn hash(self, data: &str) -> String {
+        let digest = match self {
+            Algorithm::Md5 | Algorithm::Md5Sess => MessageDigest::md5(),
+            Algorithm::Sha256 | Algorithm::Sha256Sess => MessageDigest::sha256(),
+        };
+        let bytes = hash(digest, data.as_bytes()).expect("hashing in memory does not fail");
+        bytes.iter().fold(String::new(), |mut hex, b| {
+            let _ = write!(hex, "{b:02x}");
+            hex
+        })
+    }
+}
+
+/// A `Digest` challenge of a `WWW-Authenticate` header.
+#[derive(Debug, PartialEq, Eq)]
+struct Challenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: Algorithm,
+    /// Whether the server supports `qop=auth`, or is an RFC 2069 one
+    /// without `qop` at all.
+    qop_auth: bool,
+}
+
+impl Challenge {
+    /// The strongest supported challenge in the `WWW-Authenticate` headers
+    /// of `res`.
+    fn from_response(res: &Response) -> Option<Challenge> {
+        res.headers()
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(challenges)
+            .max_by_key(|challenge| {
+                matches!(
+                    challenge.algorithm,
+                    Algorithm::Sha256 | Algorithm::Sha256Sess
+                )
+            })
+    }
+
+    fn parse(params: &[(String, String)]) -> Option<Challenge> {
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        };
+        let algorithm = match param("algorithm") {
+            Some(name) => Algorithm::parse(name)?,
+            None => Algorithm::Md5,
+        };
+        let qop_auth = match param("qop") {
+            Some(qop) => {
+                let qop_auth = qop.split(',').any(|qop| qop.trim() == "auth");
+                // only `auth-int` is offered, which needs the whole body
+                if !qop_auth {
+                    return None;
+                }
+                true
+            }
+            None => false,
+        };
+        Some(Challenge {
+            realm: param("realm")?.to_owned(),
+            nonce: param("nonce")?.to_owned(),
+            opaque: param("opaque").map(ToOwned::to_owned),
+            algorithm,
+            qop_auth,
+        })
+    }
+
+    /// The `Authorization` header answering the challenge for a request of
+    /// `method` to `uri`.
+    fn authorize(&self, auth: &DigestAuth, method: &Method, uri: &str, cnonce: &str) -> String {
+        const NC: &str = "00000001";
+
+        let algorithm = self.algorithm;
+        let mut ha1 = algorithm.hash(&format!(
+            "{}:{}:{}",
+            auth.username, self.realm, auth.password
+        ));
+        if algorithm.is_sess() {
+            ha1 = algorithm.hash(&format!("{ha1}:{}:{cnonce}", self.nonce));
+        }
+        let ha2 = algorithm.hash(&format!("{method}:{uri}"));
+        let response = if self.qop_auth {
+            algorithm.hash(&format!("{ha1}:{}:{NC}:{cnonce}:auth:{ha2}", self.nonce))
+        } else {
+            algorithm.hash(&format!("{ha1}:{}:{ha2}", self.nonce))
+        };
+
+        let mut header = format!(
+            "Digest username=\"{}\", realm=\"{}\", uri=\"{}\", algorithm={}, nonce=\"{}\"",
+            quote(&auth.username),
+            quote(&self.realm),
+            quote(uri),
+            algorithm.name(),
+            quote(&self.nonce),
+        );
+        if self.qop_auth {
+            let _ = write!(header, ", nc={NC}, cnonce=\"{cnonce}\", qop=auth");
+        }
+        let _ = write!(header, ", response=\"{response}\"");
+        if let Some(ref opaque) = self.opaque {
+            let _ = write!(header, ", opaque=\"{}\"", quote(opaque));
+        }
+        header
+    }
+}
+
+/// The supported `Digest` challenges in a `WWW-Authenticate` value, which
+/// may hold challenges of other schemes too.
+fn challenges(value: &str) -> Vec<Challenge> {
+    let mut challenges = Vec::new();
+    let mut rest = value.trim_start();
+    while !rest.is_empty() {
+        let scheme_len = rest
+            .find(|c: char| c.is_whitespace() || c == ',')
+            .unwrap_or(rest.len());
+        let scheme = &rest[..scheme_len];
+        let (params, after) = auth_params(&rest[scheme_len..]);
+        if scheme.eq_ignore_ascii_case("Digest") {
+            challenges.extend(Challenge::parse(&params));
+        }
+        rest = after.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    }
+    challenges
+}
+
+/// The `name=value` parameters at the start of `s`, up to the next
+/// challenge.
+fn auth_params(s: &str) -> (Vec<(String, String)>, &str) {
+    let mut params = Vec::new();
+    let mut rest = s;
+    loop {
+        let trimmed = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        let Some(eq) = trimmed.find('=') else {
+            return (params, trimmed);
+        };
+        let name = &trimmed[..eq];
+        // a token without `=` starts the next challenge
+        if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == ',') {
+            return (params, trimmed);
+        }
+        let value = trimmed[eq + 1..].trim_start();
+        let (value, after) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let mut unquoted = String::new();
+                let mut chars = quoted.char_indices();
+                let mut end = quoted.len();
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        '\\' => unquoted.extend(chars.next().map(|(_, c)| c)),
+                        '"' => {
+                            end = i + 1;
+                            break;
+                        }
+                        c => unquoted.push(c),
+                    }
+                }
+                (unquoted, &quoted[end.min(quoted.len())..])
+            }
+            None => {
+                let len = value.find(',').unwrap_or(value.len());
+                (value[..len].trim_end().to_owned(), &value[len..])
+            }
+        };
+        params.push((name.to_owned(), value));
+        rest = after;
+    }
+}
+
+fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The request target of `req`, as the `uri` of the challenge response.
+fn request_uri(req: &Request) -> String {
+    if let Some(path_and_query) = req.raw_path_and_query() {
+        return path_and_query.as_str().to_owned();
+    }
+    let url: &Url = req.url();
+    url[url::Position::BeforePath..url::Position::AfterQuery].to_owned()
+}
+
+fn cnonce() -> String {
+    format!(
+        "{:016x}{:016x}",
+        crate::util::fast_random(),
+        crate::util::fast_random()
+    )
+}
+
+/// Send `req`, and once more answering the `Digest` challenge of the
+/// server if it answers `401 Unauthorized`.
+pub(super) async fn send(
+    client: Client,
+    auth: DigestAuth,
+    req: Request,
+) -> crate::Result<Response> {
+    let retry = req.try_clone();
+    let res = client.execute_request(req).await?;
+    if res.status() != StatusCode::UNAUTHORIZED {
+        return Ok(res);
+    }
+
+    let Some(mut retry) = retry else {
+        debug!("digest challenge received, but body not reusable");
+        return Ok(res);
+    };
+    let Some(challenge) = Challenge::from_response(&res) else {
+        debug!("no supported digest challenge in 401 response");
+        return Ok(res);
+    };
+
+    let header = challenge.authorize(&auth, retry.method(), &request_uri(&retry), &cnonce());
+    let mut header = HeaderValue::try_from(header)
+        .map_err(|err| error::request(err).with_url(retry.url().clone()))?;
+    header.set_sensitive(true);
+    retry.headers_mut().insert(AUTHORIZATION, header);
+    client.execute_request(retry).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // https://datatracker.ietf.org/doc/html/rfc7616#section-3.9.1
+    const CHALLENGE: &str = r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm=ALGORITHM, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#;
+    const CNONCE: &str = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+
+    fn response(algorithm: &str) -> String {
+        let challenges = challenges(&CHALLENGE.replace("ALGORITHM", algorithm));
+        let auth = DigestAuth::new("Mufasa".to_owned(), "Circle of Life".to_owned());
+        challenges[0].authorize(&auth, &Method::GET, "/dir/index.html", CNONCE)
+    }
+
+    #[test]
+    fn answers_rfc_examples() {
+        assert!(response("MD5").contains("response=\"8ca523f5e9506fed4657c9700eebdbec\""));
+        assert!(response("SHA-256").contains(
+            "response=\"753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1\""
+        ));
+    }
+
+    #[test]
+    fn parses_challenges_among_others() {
+        let found = challenges(
+            r#"Basic realm="a", Digest realm="b", nonce="n\"1", Digest realm="c", nonce="2", qop="auth-int""#,
+        );
+        assert_eq!(
+            found,
+            vec![Challenge {
+                realm: "b".to_owned(),
+                nonce: "n\"1".to_owned(),
+                opaque: None,
+                algorithm: Algorithm::Md5,
+                qop_auth: false,
+            }]
+        );
+    }
+}
@@ -0,0 +1,454 @@
+//! Downloads resuming where they were cut off, optionally split in ranges
+//! fetched in parallel.
+use std::error::Error as StdError;
+use std::fmt;
+#[cfg(feature = "stream")]
+use std::path::Path;
+use std::pin::Pin;
+use std::time::Duration;
+
+use bytes::{Bytes, BytesMut};
+use futures_util::{Stream, StreamExt, TryStreamExt};
+use http::header::{
+    HeaderValue, ACCEPT_ENCODING, ACCEPT_RANGES, CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED,
+    RANGE,
+};
+use http::StatusCode;
+use log::debug;
+
+use super::http::Client;
+use super::request::Request;
+use super::response::Response;
+use crate::error;
+
+/// A download resuming with a `Range` request when its connection breaks.
+///
+/// The request after a failure asks for the bytes still missing, with an
+/// `If-Range` header holding the `ETag` or `Last-Modified` of the first
+/// response, so the download never mixes two versions of a resource.
+///
+/// To construct a `Download`, refer to [`RequestBuilder::download`].
+///
+/// [`RequestBuilder::download`]: super::RequestBuilder::download
+///
+/// # Example
+///
+/// ```
+/// # use futures_util::StreamExt;
+/// # async fn run() -> Result<(), rquest::Error> {
+/// let client = rquest::Client::new();
+/// let mut stream = client
+///     .get("https://example.com/large.iso")
+///     .download()
+///     .max_retries(5)
+///     .stream();
+///
+/// while let Some(chunk) = stream.next().await {
+///     let chunk = chunk?;
+///     println!("got {} bytes", chunk.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "Download does nothing until it is streamed"]
+pub struct Download {
+    client: Client,
+    request: crate::Result<Request>,
+    max_retries: usize,
+    retry_delay: Duration,
+    segments: usize,
+}
+
+/// The largest range a segmented download keeps in memory until the ranges
+/// before it are streamed.
+const MAX_SEGMENT_SIZE: u64 = 8 * 1024 * 1024;
+
+type BoxStream = Pin<Box<dyn Stream<Item = crate::Result<Bytes>> + Send>>;
+
+impl Download {
+    pub(super) fn new(client: Client, request: crate::Result<Request>) -> Download {
+        let request = request.and_then(|req| match req.body() {
+            Some(body) if body.as_bytes().is_none() => Err(error::builder(
+                "a download can not resume a request with a streaming body",
+            )),
+            _ => Ok(req),
+        });
+
+        Download {
+            client,
+            request,
+            max_retries: 3,
+            retry_delay: Duration::from_secs(1),
+            segments: 1,
+        }
+    }
+
+    /// Set how many times in a row the download resumes after a failure.
+    ///
+    /// Default is 3. The count starts again after each chunk received.
+    pub fn max_retries(mut self, max_retries: usize) -> Download {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set how long to wait before resuming after a failure.
+    ///
+    /// Default is 1 second.
+    pub fn retry_delay(mut self, delay: Duration) -> Download {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Split the download in ranges fetched by up to `segments` requests
+    /// in parallel.
+    ///
+    /// A first request for the first byte tells the size of the resource.
+    /// When the server answers it with the whole body instead, the download
+    /// falls back to this single stream. The ranges are streamed in order,
+    /// so the ones ahead of the stream wait in memory, up to 8 MiB each.
+    ///
+    /// Default is 1.
+    pub fn segments(mut self, segments: usize) -> Download {
+        self.segments = segments.max(1);
+        self
+    }
+
+    /// Stream the body, resuming it when it breaks.
+    ///
+    /// The stream yields the bytes of the resource once each and in order,
+    /// whatever the number of requests it took.
+    pub fn stream(self) -> impl Stream<Item = crate::Result<Bytes>> + Send {
+        let segments = self.segments;
+        let state = self.request.map(|request| State {
+            client: self.client,
+            request,
+            range: None,
+            response: None,
+            received: 0,
+            validator: None,
+            retries: 0,
+            max_retries: self.max_retries,
+            retry_delay: self.retry_delay,
+        });
+
+        futures_util::stream::once(async move {
+            match state {
+                Ok(state) if segments > 1 => state.split(segments).await,
+                Ok(state) => Ok(state.into_stream()),
+                Err(err) => Err(err),
+            }
+        })
+        .try_flatten()
+    }
+
+    /// Write the body to the file at `path`, resuming it when it breaks.
+    ///
+    /// As with [`Response::save_to`], the file only appears at `path` once
+    /// complete, replacing any file there. Returns the number of bytes
+    /// written.
+    #[cfg(feature = "stream")]
+    pub async fn to_file<P: AsRef<Path>>(self, path: P) -> crate::Result<u64> {
+        super::save::SaveOptions::new(path.as_ref().to_path_buf())
+            .save(self.stream())
+            .await
+    }
+}
+
+impl fmt::Debug for Download {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Download")
+            .field("request", &self.request)
+            .field("max_retries", &self.max_retries)
+            .field("retry_delay", &self.retry_delay)
+            .finish()
+    }
+}
+
+struct State {
+    client: Client,
+    request: Request,
+    /// The first and last bytes of the range to fetch.
+    range: Option<(u64, u64)>,
+    response: Option<Response>,
+    received: u64,
+    validator: Option<HeaderValue>,
+    retries: usize,
+    max_retries: usize,
+    retry_delay: Duration,
+}
+
+impl State {
+    fn into_stream(self) -> BoxStream {
+        Box::pin(futures_util::stream::try_unfold(
+            self,
+            |mut state| async move { Ok(state.next_chunk().await?.map(|chunk| (chunk, state))) },
+        ))
+    }
+
+    /// Stream the ranges of the resource fetched by `segments` requests,
+    /// or the whole body when the server does not take ranges.
+    async fn split(mut self, segments: usize) -> crate::Result<BoxStream> {
+        let mut req = self.request();
+        req.headers_mut()
+            .insert(RANGE, HeaderValue::from_static("bytes=0-0"));
+        let res = self.client.execute(req).await?.error_for_status()?;
+
+        let partial = res.status() == StatusCode::PARTIAL_CONTENT;
+        let total = match content_range_total(&res) {
+            Some(total) if partial && total > 1 => total,
+            // a range of a resource of unknown size, as in `bytes 0-0/*`,
+            // is not the whole of it
+            None if partial => {
+                debug!("server does not tell the size, downloading in a single stream");
+                return Ok(self.into_stream());
+            }
+            _ => {
+                debug!("server does not take ranges, downloading in a single stream");
+                self.validator = validator(&res);
+                self.response = Some(res);
+                return Ok(self.into_stream());
+            }
+        };
+        self.validator = validator(&res);
+        res.bytes().await?;
+
+        let size = total.div_ceil(segments as u64).clamp(1, MAX_SEGMENT_SIZE);
+        let ranges = (0..total.div_ceil(size)).map(move |i| {
+            let start = i * size;
+            (start, (start + size).min(total) - 1)
+        });
+        let pieces = ranges.map(move |range| self.for_range(range).collect());
+
+        Ok(Box::pin(
+            futures_util::stream::iter(pieces).buffered(segments),
+        ))
+    }
+
+    /// A download of the `range` of the same resource.
+    fn for_range(&self, range: (u64, u64)) -> State {
+        State {
+            client: self.client.clone(),
+            request: self.request(),
+            range: Some(range),
+            response: None,
+            received: 0,
+            validator: self.validator.clone(),
+            retries: 0,
+            max_retries: self.max_retries,
+            retry_delay: self.retry_delay,
+        }
+    }
+
+    async fn collect(mut self) -> crate::Result<Bytes> {
+        let mut buf = BytesMut::new();
+        while let Some(chunk) = self.next_chunk().await? {
+            buf.extend_from_slice(&chunk);
+        }
+        Ok(buf.freeze())
+    }
+
+    async fn next_chunk(&mut self) -> crate::Result<Option<Bytes>> {
+        loop {
+            let err = match self.response {
+                Some(ref mut res) => match res.chunk().await {
+                    Ok(Some(chunk)) => {
+                        self.received += chunk.len() as u64;
+                        self.retries = 0;
+                        return Ok(Some(chunk));
+                    }
+                    Ok(None) => return Ok(None),
+                    Err(err) => err,
+                },
+                None => match self.send().await {
+                    Ok(res) => {
+                        self.response = Some(res);
+                        continue;
+                    }
+                    Err(err) => err,
+                },
+            };
+
+            self.response = None;
+            if self.retries >= self.max_retries || !is_resumable(&err) {
+                return Err(err);
+            }
+            if self.range.is_none() && self.received > 0 && self.validator.is_none() {
+                debug!("download failed, but the server can't resume it: {err}");
+                return Err(err);
+            }
+
+            self.retries += 1;
+            debug!(
+                "resuming download at byte {} ({}/{}): {err}",
+                self.received, self.retries, self.max_retries
+            );
+            tokio::time::sleep(self.retry_delay).await;
+        }
+    }
+
+    /// A copy of the request, for the bytes of the resource as is.
+    fn request(&self) -> Request {
+        let mut req = self
+            .request
+            .try_clone()
+            .expect("download request body is reusable");
+
+        // Ranges count the bytes on the wire, so they must be the bytes of
+        // the resource
+        req.headers_mut()
+            .insert(ACCEPT_ENCODING, HeaderValue::from_static("identity"));
+        req
+    }
+
+    async fn send(&mut self) -> crate::Result<Response> {
+        let mut req = self.request();
+
+        let start = self.range.map_or(0, |(start, _)| start) + self.received;
+        let range = match self.range {
+            Some((_, end)) => Some(format!("bytes={start}-{end}")),
+            None if self.received > 0 => Some(format!("bytes={start}-")),
+            None => None,
+        };
+        if let Some(range) = range {
+            let headers = req.headers_mut();
+            headers.insert(
+                RANGE,
+                HeaderValue::from_str(&range).map_err(error::builder)?,
+            );
+            if let Some(ref validator) = self.validator {
+                headers.insert(IF_RANGE, validator.clone());
+            }
+        }
+
+        let res = self.client.execute(req).await?.error_for_status()?;
+
+        if self.range.is_none() && self.received == 0 {
+            self.validator = validator(&res);
+            return Ok(res);
+        }
+
+        if res.status() != StatusCode::PARTIAL_CONTENT || content_range_start(&res) != Some(start) {
+            return Err(error::body(NotResumed));
+        }
+        Ok(res)
+    }
+}
+
+/// The `If-Range` value for resuming after `res`, if the server takes
+/// ranges and names the version it sent.
+fn validator(res: &Response) -> Option<HeaderValue> {
+    let headers = res.headers();
+    if headers
+        .get(ACCEPT_RANGES)
+        .is_some_and(|ranges| ranges.as_bytes().eq_ignore_ascii_case(b"none"))
+    {
+        return None;
+    }
+
+    // Weak tags can not be compared for ranges
+    headers
+        .get(ETAG)
+        .filter(|etag| !etag.as_bytes().starts_with(b"W/"))
+        .or_else(|| headers.get(LAST_MODIFIED))
+        .cloned()
+}
+
+/// The first byte of the range in a `Content-Range: bytes 100-199/200`.
+fn content_range_start(res: &Response) -> Option<u64> {
+    res.headers()
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// The size of the resource in a `Content-Range: bytes 100-199/200`.
+fn content_range_total(res: &Response) -> Option<u64> {
+    res.headers()
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn is_resumable(err: &crate::Error) -> bool {
+    if err.is_builder() || err.is_status() || err.is_redirect() || err.is_body_too_large() {
+        return false;
+    }
+
+    let mut source = err.source();
+    while let Some(err) = source {
+        if err.is::<NotResumed>() {
+            return false;
+        }
+        source = err.source();
+    }
+    true
+}
+
+#[derive(Debug)]
+struct NotResumed;
+
+impl fmt::Display for NotResumed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("the server did not resume the download where it stopped")
+    }
+}
+
+impl StdError for NotResumed {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_content_range() {
+        let res = Response::from(
+            http::Response::builder()
+                .header(CONTENT_RANGE, "bytes 100-199/200")
+                .body("")
+                .unwrap(),
+        );
+        assert_eq!(content_range_start(&res), Some(100));
+        assert_eq!(content_range_total(&res), Some(200));
+
+        let res = Response::from(
+            http::Response::builder()
+                .header(CONTENT_RANGE, "bytes 0-0/*")
+                .body("")
+                .unwrap(),
+        );
+        assert_eq!(content_range_start(&res), Some(0));
+        assert_eq!(content_range_total(&res), None);
+    }
+
+    #[test]
+    fn weak_etags_are_not_validators() {
+        let res = Response::from(
+            http::Response::builder()
+                .header(ETAG, "W/\"abc\"")
+                .header(LAST_MODIFIED, "Wed, 21 Oct 2015 07:28:00 GMT")
+                .body("")
+                .unwrap(),
+        );
+        assert_eq!(validator(&res).unwrap(), "Wed, 21 Oct 2015 07:28:00 GMT");
+
+        let res = Response::from(
+            http::Response::builder()
+                .header(ETAG, "\"abc\"")
+                .header(ACCEPT_RANGES, "none")
+                .body("")
+                .unwrap(),
+        );
+        assert_eq!(validator(&res), None);
+    }
+}
@@ -0,0 +1,112 @@
+//! GraphQL over HTTP.
+use std::error::Error as StdError;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error;
+
+/// The `POST` body of a GraphQL request.
+#[derive(Serialize)]
+pub(super) struct Envelope<'a, V: ?Sized> {
+    pub(super) query: &'a str,
+    pub(super) variables: &'a V,
+}
+
+/// The media types a GraphQL server may answer with, preferred first.
+pub(super) const ACCEPT: &str = "application/graphql-response+json, application/json;q=0.9";
+
+#[derive(Deserialize)]
+struct Body {
+    #[serde(default)]
+    data: Option<Value>,
+    #[serde(default)]
+    errors: Vec<GraphQLError>,
+}
+
+/// An error reported in the `errors` of a GraphQL response.
+#[derive(Clone, Debug, Deserialize)]
+pub struct GraphQLError {
+    message: String,
+    #[serde(default)]
+    locations: Vec<GraphQLLocation>,
+    #[serde(default)]
+    path: Vec<Value>,
+    #[serde(default)]
+    extensions: Option<Value>,
+}
+
+/// A position in the query a [`GraphQLError`] is about.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub struct GraphQLLocation {
+    /// The line, starting at 1.
+    pub line: u32,
+    /// The column, starting at 1.
+    pub column: u32,
+}
+
+impl GraphQLError {
+    /// The description of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Where in the query the error is.
+    pub fn locations(&self) -> &[GraphQLLocation] {
+        &self.locations
+    }
+
+    /// The path of the field that failed, made of field names and list
+    /// indices.
+    pub fn path(&self) -> &[Value] {
+        &self.path
+    }
+
+    /// Any extra information the server added to the error.
+    pub fn extensions(&self) -> Option<&Value> {
+        self.extensions.as_ref()
+    }
+}
+
+impl fmt::Display for GraphQLError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(location) = self.locations.first() {
+            write!(f, " at {}:{}", location.line, location.column)?;
+        }
+        Ok(())
+    }
+}
+
+/// The errors of a GraphQL response, as the source of an `Error`.
+#[derive(Debug)]
+pub(crate) struct GraphQLErrors(pub(crate) Vec<GraphQLError>);
+
+impl fmt::Display for GraphQLErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "GraphQL response has errors: ")?;
+        for (i, err) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{err}")?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for GraphQLErrors {}
+
+/// Deserialize the `data` of a GraphQL response body, unless it has `errors`.
+pub(super) fn data<T: DeserializeOwned>(body: &[u8]) -> crate::Result<T> {
+    let body: Body = serde_json::from_slice(body).map_err(error::decode)?;
+    if !body.errors.is_empty() {
+        return Err(error::decode(GraphQLErrors(body.errors)));
+    }
+    match body.data {
+        Some(data) if !data.is_null() => serde_json::from_value(data).map_err(error::decode),
+        _ => Err(error::decode("GraphQL response has no data")),
+    }
+}
@@ -0,0 +1,504 @@
+//! Recording of traffic in the HTTP Archive (HAR) 1.2 format.
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use antidote::Mutex;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use bytes::Bytes;
+use http::header::{
+    HeaderMap, AUTHORIZATION, CONTENT_TYPE, COOKIE, LOCATION, PROXY_AUTHORIZATION, SET_COOKIE,
+};
+use http::{Method, Version};
+use serde::Serialize;
+
+use crate::Url;
+
+/// The default of [`HarRecorder::with_max_body_size`].
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// The value recorded for credentials, unless they are kept.
+const REDACTED: &str = "[redacted]";
+
+/// A recorder of the requests and responses of a client, written as a
+/// HAR 1.2 archive.
+///
+/// The archive can be opened in the network panel of browser devtools, or
+/// shared with the owners of a site. Each redirect is an entry of its own.
+/// The response of an entry is recorded once its body has been read, or
+/// dropped.
+///
+/// The values of the `Authorization`, `Proxy-Authorization`, `Cookie` and
+/// `Set-Cookie` headers, and of headers marked sensitive, are redacted,
+/// unless [`HarRecorder::redact_credentials`] is turned off.
+///
+/// The send time of each entry is not measured, and recorded as `0`; the
+/// wait time starts once the request is sent.
+///
+/// The recorder is cheap to clone, and its clones share their entries.
+///
+/// # Example
+///
+/// ```rust
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let har = rquest::HarRecorder::new();
+/// let client = rquest::Client::builder()
+///     .har_recorder(har.clone())
+///     .build()?;
+///
+/// client.get("https://example.com").send().await?.text().await?;
+/// har.write_to(std::fs::File::create("session.har")?)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Optional
+///
+/// This requires the optional `json` feature enabled.
+#[derive(Clone)]
+pub struct HarRecorder {
+    inner: Arc<Shared>,
+}
+
+struct Shared {
+    entries: Mutex<Vec<Entry>>,
+    max_body_size: usize,
+    redact: AtomicBool,
+}
+
+impl HarRecorder {
+    /// Create an empty recorder.
+    pub fn new() -> HarRecorder {
+        HarRecorder::with_max_body_size(MAX_BODY_SIZE)
+    }
+
+    /// Create an empty recorder, keeping at most `bytes` of each request and
+    /// response body.
+    ///
+    /// Longer bodies are cut, and their full size is still recorded. Use `0`
+    /// to only record the headers.
+    ///
+    /// Default is 1 MiB.
+    pub fn with_max_body_size(bytes: usize) -> HarRecorder {
+        HarRecorder {
+            inner: Arc::new(Shared {
+                entries: Mutex::new(Vec::new()),
+                max_body_size: bytes,
+                redact: AtomicBool::new(true),
+            }),
+        }
+    }
+
+    /// Whether to redact credentials in the headers of the next entries.
+    ///
+    /// Turn it off only for archives that stay private: they then hold the
+    /// tokens and cookies of the session.
+    ///
+    /// Default is `true`.
+    pub fn redact_credentials(self, redact: bool) -> HarRecorder {
+        self.inner.redact.store(redact, Ordering::Relaxed);
+        self
+    }
+
+    /// The number of entries recorded so far.
+    pub fn len(&self) -> usize {
+        self.inner.entries.lock().len()
+    }
+
+    /// Whether no entry was recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget the entries recorded so far.
+    pub fn clear(&self) {
+        self.inner.entries.lock().clear();
+    }
+
+    /// The archive of the entries recorded so far, as JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.archive()).expect("HAR archives are valid JSON")
+    }
+
+    /// Write the archive of the entries recorded so far to `writer`, as JSON.
+    pub fn write_to<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        serde_json::to_writer_pretty(writer, &self.archive()).map_err(io::Error::from)
+    }
+
+    fn archive(&self) -> Har {
+        Har {
+            log: Log {
+                version: "1.2",
+                creator: Creator {
+                    name: env!("CARGO_PKG_NAME"),
+                    version: env!("CARGO_PKG_VERSION"),
+                },
+                entries: self.inner.entries.lock().clone(),
+            },
+        }
+    }
+
+    /// Start recording an exchange once its response head is received.
+    pub(super) fn start<B>(&self, req: RequestHead<'_>, res: &http::Response<B>) -> Recording {
+        let max = self.inner.max_body_size;
+        let redact = self.inner.redact.load(Ordering::Relaxed);
+        let request = Request {
+            method: req.method.to_string(),
+            url: req.url.to_string(),
+            http_version: http_version(req.version.unwrap_or(res.version())),
+            cookies: Vec::new(),
+            headers: headers(req.headers, redact),
+            query_string: req
+                .url
+                .query_pairs()
+                .map(|(name, value)| Pair {
+                    name: name.into_owned(),
+                    value: value.into_owned(),
+                })
+                .collect(),
+            post_data: req.body.map(|body| PostData {
+                mime_type: mime_type(req.headers),
+                text: text(&body[..body.len().min(max)]),
+            }),
+            headers_size: -1,
+            body_size: req.body.map_or(0, |body| body.len() as i64),
+        };
+        let response = Response {
+            status: res.status().as_u16(),
+            status_text: res
+                .status()
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_owned(),
+            http_version: http_version(res.version()),
+            cookies: Vec::new(),
+            headers: headers(res.headers(), redact),
+            content: Content {
+                size: 0,
+                mime_type: mime_type(res.headers()),
+                text: None,
+            },
+            redirect_url: res
+                .headers()
+                .get(LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_owned(),
+            headers_size: -1,
+            body_size: -1,
+        };
+        Recording {
+            recorder: self.clone(),
+            started_at: req.sent.0,
+            started: req.sent.1,
+            received: Instant::now(),
+            exchange: Some((request, response)),
+            body: Vec::new(),
+            size: 0,
+        }
+    }
+}
+
+impl Default for HarRecorder {
+    fn default() -> HarRecorder {
+        HarRecorder::new()
+    }
+}
+
+impl fmt::Debug for HarRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HarRecorder")
+            .field("entries", &self.len())
+            .field("max_body_size", &self.inner.max_body_size)
+            .field("redact", &self.inner.redact.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// What was sent for an exchange being recorded.
+pub(super) struct RequestHead<'a> {
+    pub(super) sent: (SystemTime, Instant),
+    pub(super) method: &'a Method,
+    pub(super) url: &'a Url,
+    pub(super) version: Option<Version>,
+    pub(super) headers: &'a HeaderMap,
+    pub(super) body: Option<&'a Bytes>,
+}
+
+/// An exchange whose response body is being received.
+///
+/// The entry is added to the recorder when the recording is dropped.
+pub(crate) struct Recording {
+    recorder: HarRecorder,
+    started_at: SystemTime,
+    started: Instant,
+    received: Instant,
+    exchange: Option<(Request, Response)>,
+    body: Vec<u8>,
+    size: u64,
+}
+
+impl Recording {
+    /// Record a chunk of the decoded response body.
+    pub(crate) fn data(&mut self, chunk: &[u8]) {
+        self.size += chunk.len() as u64;
+        let room = self
+            .recorder
+            .inner
+            .max_body_size
+            .saturating_sub(self.body.len());
+        self.body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+    }
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        let Some((request, mut response)) = self.exchange.take() else {
+            return;
+        };
+        let done = Instant::now();
+        let wait = millis(self.received - self.started);
+        let receive = millis(done - self.received);
+
+        response.content.size = self.size;
+        if !self.body.is_empty() {
+            response.content.text = Some(text(&self.body));
+        }
+        self.recorder.inner.entries.lock().push(Entry {
+            started_date_time: rfc3339(self.started_at),
+            time: wait + receive,
+            request,
+            response,
+            cache: Cache {},
+            timings: Timings {
+                send: 0.0,
+                wait,
+                receive,
+            },
+        });
+    }
+}
+
+#[derive(Serialize)]
+struct Har {
+    log: Log,
+}
+
+#[derive(Serialize)]
+struct Log {
+    version: &'static str,
+    creator: Creator,
+    entries: Vec<Entry>,
+}
+
+#[derive(Serialize)]
+struct Creator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    started_date_time: String,
+    time: f64,
+    request: Request,
+    response: Response,
+    cache: Cache,
+    timings: Timings,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+    method: String,
+    url: String,
+    http_version: &'static str,
+    cookies: Vec<Pair>,
+    headers: Vec<Pair>,
+    query_string: Vec<Pair>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    post_data: Option<PostData>,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Response {
+    status: u16,
+    status_text: String,
+    http_version: &'static str,
+    cookies: Vec<Pair>,
+    headers: Vec<Pair>,
+    content: Content,
+    #[serde(rename = "redirectURL")]
+    redirect_url: String,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Clone, Serialize)]
+struct Pair {
+    name: String,
+    value: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PostData {
+    mime_type: String,
+    #[serde(flatten)]
+    text: Text,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Content {
+    size: u64,
+    mime_type: String,
+    #[serde(flatten)]
+    text: Option<Text>,
+}
+
+/// A body, as text if it is UTF-8, or else in base64.
+#[derive(Clone, Serialize)]
+struct Text {
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<&'static str>,
+}
+
+#[derive(Clone, Serialize)]
+struct Cache {}
+
+#[derive(Clone, Serialize)]
+struct Timings {
+    send: f64,
+    wait: f64,
+    receive: f64,
+}
+
+fn headers(headers: &HeaderMap, redact: bool) -> Vec<Pair> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let credentials = value.is_sensitive()
+                || [AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE, SET_COOKIE].contains(name);
+            let value = if redact && credentials {
+                REDACTED.to_owned()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            Pair {
+                name: name.to_string(),
+                value,
+            }
+        })
+        .collect()
+}
+
+fn mime_type(headers: &HeaderMap) -> String {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_owned()
+}
+
+fn text(body: &[u8]) -> Text {
+    let text = match std::str::from_utf8(body) {
+        Ok(text) => Some(text),
+        // a body cut in the middle of a character
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&body[..err.valid_up_to()]).ok()
+        }
+        Err(_) => None,
+    };
+    match text {
+        Some(text) => Text {
+            text: text.to_owned(),
+            encoding: None,
+        },
+        None => Text {
+            text: BASE64_STANDARD.encode(body),
+            encoding: Some("base64"),
+        },
+    }
+}
+
+fn http_version(version: Version) -> &'static str {
+    match version {
+        Version::HTTP_09 => "HTTP/0.9",
+        Version::HTTP_10 => "HTTP/1.0",
+        Version::HTTP_2 => "HTTP/2.0",
+        Version::HTTP_3 => "HTTP/3.0",
+        _ => "HTTP/1.1",
+    }
+}
+
+fn millis(duration: std::time::Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Format `time` as an RFC 3339 date in UTC, with milliseconds.
+fn rfc3339(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    let (year, month, day) = crate::util::civil_from_days(days as i64);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            rfc3339(UNIX_EPOCH + Duration::from_millis(1_709_251_199_123)),
+            "2024-02-29T23:59:59.123Z"
+        );
+    }
+
+    #[test]
+    fn redacts_credentials() {
+        let mut map = HeaderMap::new();
+        map.insert(AUTHORIZATION, "Bearer secret".parse().unwrap());
+        map.insert(COOKIE, "session=secret".parse().unwrap());
+        map.insert(CONTENT_TYPE, "text/plain".parse().unwrap());
+
+        let values = |redact| {
+            headers(&map, redact)
+                .into_iter()
+                .map(|pair| pair.value)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values(true), [REDACTED, REDACTED, "text/plain"]);
+        assert_eq!(
+            values(false),
+            ["Bearer secret", "session=secret", "text/plain"]
+        );
+    }
+
+    #[test]
+    fn binary_bodies_in_base64() {
+        assert_eq!(text(b"hi").text, "hi");
+        let binary = text(&[0xff, 0x00]);
+        assert_eq!(binary.text, "/wA=");
+        assert_eq!(binary.encoding, Some("base64"));
+    }
+}
@@ -0,0 +1,254 @@
+//! HTTP Strict Transport Security, upgrading requests to the hosts that asked
+//! to be reached over HTTPS only.
+//!
+//! See [RFC 6797](https://www.rfc-editor.org/rfc/rfc6797).
+use std::collections::HashSet;
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+use antidote::Mutex;
+use http::header::STRICT_TRANSPORT_SECURITY;
+use http::HeaderMap;
+use log::trace;
+use lru::LruCache;
+use url::{Host, Url};
+
+/// How many hosts a client remembers the policy of.
+const MAX_ENTRIES: usize = 1024;
+
+/// The hosts known to be reached over HTTPS only.
+pub(crate) struct Hsts {
+    /// Preloaded hosts, with their subdomains, never expiring.
+    preload: HashSet<String>,
+    /// Hosts learnt from `Strict-Transport-Security` headers.
+    entries: Mutex<LruCache<String, Policy>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Policy {
+    expires: Instant,
+    include_subdomains: bool,
+}
+
+impl Hsts {
+    pub(crate) fn new<I>(preload: I) -> Hsts
+    where
+        I: IntoIterator<Item = String>,
+    {
+        Hsts {
+            preload: preload
+                .into_iter()
+                .map(|host| host.trim_end_matches('.').to_ascii_lowercase())
+                .collect(),
+            entries: Mutex::new(LruCache::new(
+                NonZeroUsize::new(MAX_ENTRIES).expect("non-zero"),
+            )),
+        }
+    }
+
+    /// Switch `url` to `https` if its host is known to be reached over HTTPS
+    /// only. An explicit port is kept.
+    pub(crate) fn upgrade(&self, url: &mut Url) {
+        if url.scheme() != "http" {
+            return;
+        }
+        let known = match url.host() {
+            Some(Host::Domain(host)) => self.is_known(host),
+            _ => false,
+        };
+        if known {
+            trace!("upgrading {} to https", url);
+            let _ = url.set_scheme("https");
+        }
+    }
+
+    /// Remember the `Strict-Transport-Security` policy sent with a response
+    /// to `url`.
+    ///
+    /// Policies are only taken from secure responses to host names, never
+    /// from plain HTTP or IP addresses.
+    pub(crate) fn observe(&self, url: &Url, headers: &HeaderMap) {
+        if url.scheme() != "https" {
+            return;
+        }
+        let host = match url.host() {
+            Some(Host::Domain(host)) => host.trim_end_matches('.').to_ascii_lowercase(),
+            _ => return,
+        };
+        let Some((max_age, include_subdomains)) = headers
+            .get(STRICT_TRANSPORT_SECURITY)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse)
+        else {
+            return;
+        };
+
+        let mut entries = self.entries.lock();
+        if max_age.is_zero() {
+            entries.pop(&host);
+            return;
+        }
+        // cap the max-age, so its expiry is representable
+        let expires = Instant::now() + max_age.min(Duration::from_secs(u32::MAX as u64));
+        entries.put(
+            host,
+            Policy {
+                expires,
+                include_subdomains,
+            },
+        );
+    }
+
+    fn is_known(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        let now = Instant::now();
+        let mut entries = self.entries.lock();
+
+        let mut domain = host.as_str();
+        let mut exact = true;
+        loop {
+            if self.preload.contains(domain) {
+                return true;
+            }
+            match entries.get(domain).copied() {
+                Some(policy) if policy.expires <= now => {
+                    entries.pop(domain);
+                }
+                Some(policy) if exact || policy.include_subdomains => return true,
+                _ => {}
+            }
+            match domain.split_once('.') {
+                Some((_, parent)) => domain = parent,
+                None => return false,
+            }
+            exact = false;
+        }
+    }
+}
+
+/// Parse a `Strict-Transport-Security` value into its `max-age` and whether
+/// it includes subdomains.
+///
+/// A value without `max-age`, or repeating a directive, is invalid.
+fn parse(value: &str) -> Option<(Duration, bool)> {
+    let mut max_age = None;
+    let mut include_subdomains = false;
+
+    for directive in value.split(';') {
+        let directive = directive.trim();
+        if directive.is_empty() {
+            continue;
+        }
+        let (name, value) = match directive.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+            None => (directive, None),
+        };
+        if name.eq_ignore_ascii_case("max-age") {
+            let seconds = value?.parse::<u64>().ok()?;
+            if max_age.replace(Duration::from_secs(seconds)).is_some() {
+                return None;
+            }
+        } else if name.eq_ignore_ascii_case("includeSubDomains") {
+            if include_subdomains {
+                return None;
+            }
+            include_subdomains = true;
+        }
+    }
+
+    max_age.map(|max_age| (max_age, include_subdomains))
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn sts(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(STRICT_TRANSPORT_SECURITY, HeaderValue::from_static(value));
+        headers
+    }
+
+    fn upgraded(hsts: &Hsts, url: &str) -> String {
+        let mut url = Url::parse(url).unwrap();
+        hsts.upgrade(&mut url);
+        url.into()
+    }
+
+    #[test]
+    fn parse_directives() {
+        assert_eq!(
+            parse("max-age=31536000"),
+            Some((Duration::from_secs(31536000), false))
+        );
+        assert_eq!(
+            parse("Max-Age=\"60\"; includeSubDomains; preload"),
+            Some((Duration::from_secs(60), true))
+        );
+        assert_eq!(parse("includeSubDomains"), None);
+        assert_eq!(parse("max-age=60; max-age=120"), None);
+        assert_eq!(parse("max-age=soon"), None);
+    }
+
+    #[test]
+    fn learns_from_secure_responses() {
+        let hsts = Hsts::new(None);
+        let url = Url::parse("https://example.com/").unwrap();
+        hsts.observe(&url, &sts("max-age=60"));
+
+        assert_eq!(
+            upgraded(&hsts, "http://example.com/a"),
+            "https://example.com/a"
+        );
+        assert_eq!(
+            upgraded(&hsts, "http://example.com:8080/"),
+            "https://example.com:8080/"
+        );
+        assert_eq!(
+            upgraded(&hsts, "http://www.example.com/"),
+            "http://www.example.com/"
+        );
+
+        // plain HTTP responses and IP addresses are ignored
+        let url = Url::parse("http://other.com/").unwrap();
+        hsts.observe(&url, &sts("max-age=60"));
+        assert_eq!(upgraded(&hsts, "http://other.com/"), "http://other.com/");
+        let url = Url::parse("https://127.0.0.1/").unwrap();
+        hsts.observe(&url, &sts("max-age=60"));
+        assert_eq!(upgraded(&hsts, "http://127.0.0.1/"), "http://127.0.0.1/");
+
+        // max-age=0 forgets the host
+        let url = Url::parse("https://example.com/").unwrap();
+        hsts.observe(&url, &sts("max-age=0"));
+        assert_eq!(
+            upgraded(&hsts, "http://example.com/"),
+            "http://example.com/"
+        );
+    }
+
+    #[test]
+    fn include_subdomains_and_preload() {
+        let hsts = Hsts::new(vec!["Preloaded.org".to_owned()]);
+        let url = Url::parse("https://example.com/").unwrap();
+        hsts.observe(&url, &sts("max-age=60; includeSubDomains"));
+
+        assert_eq!(
+            upgraded(&hsts, "http://a.b.example.com/"),
+            "https://a.b.example.com/"
+        );
+        assert_eq!(
+            upgraded(&hsts, "http://notexample.com/"),
+            "http://notexample.com/"
+        );
+        assert_eq!(
+            upgraded(&hsts, "http://preloaded.org/"),
+            "https://preloaded.org/"
+        );
+        assert_eq!(
+            upgraded(&hsts, "http://www.preloaded.org/"),
+            "https://www.preloaded.org/"
+        );
+    }
+}
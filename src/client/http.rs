@@ -1,14 +1,16 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::future::Future;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::num::NonZeroUsize;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock, Weak};
 use std::task::{Context, Poll};
-use std::time::Duration;
-use std::{collections::HashMap, convert::TryInto, net::SocketAddr};
+use std::time::{Duration, Instant, SystemTime};
+use std::{convert::TryInto, net::SocketAddr};
 use std::{fmt, str};
 
+use crate::cache::HttpCache;
 use crate::connect::{
     sealed::{Conn, Unnameable},
     BoxedConnectorLayer, BoxedConnectorService, Connector, ConnectorBuilder,
@@ -16,42 +18,80 @@ use crate::connect::{
 #[cfg(feature = "cookies")]
 use crate::cookie;
 #[cfg(feature = "hickory-dns")]
-use crate::dns::hickory::{HickoryDnsResolver, LookupIpStrategy};
-use crate::dns::{gai::GaiResolver, DnsResolverWithOverrides, DynResolver, Resolve};
+use crate::dns::hickory::{HickoryDnsOptions, HickoryDnsResolver, LookupIpStrategy};
+use crate::dns::{
+    cache::{CachingResolver, DnsCache},
+    doh::DohResolver,
+    dot::DotResolver,
+    gai::GaiResolver,
+    svcb::HttpsRecords,
+    weighted::WeightedResolver,
+    DnsCacheConfig, DnsOverrides, DnsResolverWithOverrides, DotConfig, DynResolver, HttpsRecord,
+    OverrideTarget, Resolve,
+};
 use crate::error::{BoxError, Error};
-use crate::into_url::try_uri;
+use crate::into_url::{
+    into_url_with_scope_id, join_with_scope_id, try_uri, try_uri_with_path_and_query,
+};
+use crate::proxy::{CredentialsProvider, ProxyScheme};
 use crate::util::{
     self,
     client::{
-        connect::HttpConnector, Builder, Client as HyperClient, Http1Builder, Http2Builder,
-        InnerRequest, NetworkScheme, NetworkSchemeBuilder,
+        connect::{HttpConnector, ProxyProtocol, ProxyProtocolMatcher},
+        Alternative, Builder, Client as HyperClient, Dst, FreshConnection, Http1Builder,
+        Http2Builder, InnerRequest, NetworkScheme, NetworkSchemeBuilder, RequestTarget,
     },
     rt::{tokio::TokioTimer, TokioExecutor},
+    SeededRandom,
 };
 use crate::{error, impl_debug, Http1Config, Http2Config, TlsConfig};
 use crate::{
     redirect,
-    tls::{AlpnProtos, BoringTlsConnector, RootCertStoreProvider, TlsVersion},
+    tls::{AlpnProtos, BoringTlsConnector, RootCertStoreProvider, TlsPinning, TlsVersion},
+    transport,
 };
-use crate::{IntoUrl, Method, Proxy, StatusCode, Url};
-
+use crate::{IntoUrl, Method, Proxy, ProxyPool, StatusCode, Url};
+
+use super::alt_svc::AltSvcCache;
+use super::body::Replay;
+#[cfg(feature = "json")]
+use super::cassette::Cassette;
+use super::client_hints::{ClientHints, HintStore};
+use super::compression;
+use super::conditional::ValidatorCache;
 use super::decoder::Accepts;
+use super::fetch::FetchRequest;
+#[cfg(feature = "json")]
+use super::har::HarRecorder;
+use super::hsts::Hsts;
+use super::mock::MockTransport;
+use super::pacing::Pacer;
+use super::page::Page;
+use super::progress::{self, ProgressCallback};
 use super::request::{Request, RequestBuilder};
 use super::response::Response;
-use super::{Body, HttpContextProvider};
+use super::session::SessionState;
+use super::signer::{RequestSigner, SigningRequest};
+use super::stats::{self, Transfer, TransferStats};
+use super::throttle::{self, Throttle};
+use super::upgrade::Upgraded;
+use super::{
+    AuthProvider, Body, HttpContext, HttpContextProvider, MultipartStyle, Preset, QueryStyle,
+};
 
 use arc_swap::{ArcSwap, Guard};
-use bytes::Bytes;
 use http::{
     header::{
-        Entry, HeaderMap, HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH,
-        CONTENT_TYPE, LOCATION, PROXY_AUTHORIZATION, RANGE, REFERER, TRANSFER_ENCODING, USER_AGENT,
+        Entry, HeaderMap, HeaderValue, ACCEPT_ENCODING, AUTHORIZATION, CONTENT_ENCODING,
+        CONTENT_LENGTH, CONTENT_TYPE, COOKIE, LOCATION, PROXY_AUTHORIZATION, RANGE, REFERER,
+        TRANSFER_ENCODING, USER_AGENT,
     },
-    uri::Scheme,
+    uri::{PathAndQuery, Scheme},
     HeaderName, Uri, Version,
 };
 use log::{debug, trace};
 use pin_project_lite::pin_project;
+use serde::Serialize;
 
 use tokio::time::Sleep;
 use tower::util::BoxCloneSyncServiceLayer;
@@ -83,6 +123,15 @@ pub struct Client {
     inner: Arc<ArcSwap<ClientInner>>,
 }
 
+/// A `Client` that doesn't keep the client it refers to alive.
+pub(crate) struct WeakClient(Weak<ArcSwap<ClientInner>>);
+
+impl WeakClient {
+    pub(crate) fn upgrade(&self) -> Option<Client> {
+        self.0.upgrade().map(|inner| Client { inner })
+    }
+}
+
 /// A `ClientBuilder` can be used to create a `Client` with custom configuration.
 #[must_use]
 #[derive(Debug)]
@@ -94,36 +143,87 @@ struct Config {
     // NOTE: When adding a new field, update `fmt::Debug for ClientBuilder`
     headers: HeaderMap,
     headers_order: Option<Cow<'static, [HeaderName]>>,
+    multipart_style: MultipartStyle,
+    query_style: Option<QueryStyle>,
+    base_url: Option<(Url, Option<u32>)>,
+    default_query: Vec<(String, String)>,
+    auth: Option<AuthProvider>,
+    host_auth: Vec<(url::Origin, AuthProvider)>,
+    host_headers: Vec<(String, HeaderMap)>,
+    host_impersonations: Vec<(String, HttpContext)>,
+    #[cfg(feature = "json")]
+    har: Option<HarRecorder>,
+    #[cfg(feature = "json")]
+    cassette: Option<Cassette>,
+    http_cache: Option<HttpCache>,
+    mock: Option<MockTransport>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    session: Option<SessionState>,
+    deterministic: Option<u64>,
+    lenient_http1: bool,
     accepts: Accepts,
     connect_timeout: Option<Duration>,
+    tls_handshake_timeout: Option<Duration>,
     connection_verbose: bool,
+    debug_capture: bool,
     pool_idle_timeout: Option<Duration>,
     pool_max_idle_per_host: usize,
     pool_max_size: Option<NonZeroUsize>,
+    pool_max_connection_age: Option<Duration>,
+    pool_max_connection_requests: Option<usize>,
     tcp_keepalive: Option<Duration>,
+    tcp_keepalive_interval: Option<Duration>,
+    tcp_keepalive_retries: Option<u32>,
+    tcp_send_buffer_size: Option<usize>,
+    tcp_recv_buffer_size: Option<usize>,
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    tcp_user_timeout: Option<Duration>,
+    ip_ttl: Option<u32>,
+    #[cfg(unix)]
+    tcp_mss: Option<u32>,
     proxies: Vec<Proxy>,
     auto_sys_proxy: bool,
     redirect_policy: redirect::Policy,
     redirect_with_proxy_auth: bool,
+    redirect_preserve_location: bool,
     referer: bool,
     timeout: Option<Duration>,
     read_timeout: Option<Duration>,
+    download_throttle: Option<Throttle>,
+    upload_throttle: Option<Throttle>,
+    max_response_size: Option<u64>,
+    max_decompression_ratio: Option<u32>,
     network_scheme: NetworkSchemeBuilder,
     nodelay: bool,
     #[cfg(feature = "cookies")]
     cookie_store: CookieStoreOption,
     hickory_dns: bool,
     error: Option<Error>,
-    dns_overrides: HashMap<String, Vec<SocketAddr>>,
+    dns_overrides: DnsOverrides,
+    connect_to: HashMap<(String, u16), SocketAddr>,
     dns_resolver: Option<Arc<dyn Resolve>>,
+    doh: Option<Url>,
+    dot: Option<DotConfig>,
+    dns_cache: Option<DnsCacheConfig>,
+    https_rr: bool,
     #[cfg(feature = "hickory-dns")]
-    dns_strategy: Option<LookupIpStrategy>,
+    hickory_dns_options: HickoryDnsOptions,
     https_only: bool,
+    hsts: bool,
+    hsts_preload: Vec<String>,
+    alt_svc: bool,
+    client_hints: Option<ClientHints>,
+    rate_limit_pacing: bool,
     http2_max_retry_count: usize,
     tls_info: bool,
+    tls_pinning: Option<Arc<TlsPinning>>,
+    connector: Option<Arc<dyn transport::Connector>>,
+    proxy_protocol: ProxyProtocolMatcher,
     connector_layers: Option<Vec<BoxedConnectorLayer>>,
     builder: Builder,
     tls_config: TlsConfig,
+    proxy_tls_config: Option<TlsConfig>,
+    proxy_http2: bool,
 }
 
 impl_debug!(
@@ -131,29 +231,80 @@ impl_debug!(
     {
         headers,
         headers_order,
+        host_headers,
+        host_impersonations,
+        multipart_style,
+        query_style,
+        base_url,
+        default_query,
+        auth,
+        host_auth,
+        #[cfg(feature = "json")]
+        har,
+        #[cfg(feature = "json")]
+        cassette,
+        http_cache,
+        mock,
+        signer,
+        session,
+        deterministic,
+        lenient_http1,
         accepts,
         connect_timeout,
+        tls_handshake_timeout,
         connection_verbose,
+        debug_capture,
         pool_idle_timeout,
         pool_max_idle_per_host,
         pool_max_size,
+        pool_max_connection_age,
+        pool_max_connection_requests,
         tcp_keepalive,
+        tcp_keepalive_interval,
+        tcp_keepalive_retries,
+        tcp_send_buffer_size,
+        tcp_recv_buffer_size,
+        #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+        tcp_user_timeout,
+        ip_ttl,
+        #[cfg(unix)]
+        tcp_mss,
         proxies,
         auto_sys_proxy,
         redirect_policy,
         redirect_with_proxy_auth,
+        redirect_preserve_location,
         referer,
         timeout,
         read_timeout,
+        download_throttle,
+        upload_throttle,
+        max_response_size,
+        max_decompression_ratio,
         network_scheme,
         nodelay,
         hickory_dns,
         dns_overrides,
+        connect_to,
+        doh,
+        dot,
+        dns_cache,
+        https_rr,
         https_only,
+        hsts,
+        hsts_preload,
+        alt_svc,
+        client_hints,
+        rate_limit_pacing,
         http2_max_retry_count,
         tls_info,
+        tls_pinning,
+        connector,
+        proxy_protocol,
         builder,
-        tls_config
+        tls_config,
+        proxy_tls_config,
+        proxy_http2
     }
 );
 
@@ -173,37 +324,88 @@ impl ClientBuilder {
                 error: None,
                 headers: HeaderMap::new(),
                 headers_order: None,
+                multipart_style: MultipartStyle::Default,
+                query_style: None,
+                base_url: None,
+                default_query: Vec::new(),
+                auth: None,
+                host_auth: Vec::new(),
+                host_headers: Vec::new(),
+                host_impersonations: Vec::new(),
+                #[cfg(feature = "json")]
+                har: None,
+                #[cfg(feature = "json")]
+                cassette: None,
+                http_cache: None,
+                mock: None,
+                signer: None,
+                session: None,
+                deterministic: None,
+                lenient_http1: false,
                 accepts: Accepts::default(),
                 connect_timeout: None,
+                tls_handshake_timeout: None,
                 connection_verbose: false,
+                debug_capture: false,
                 pool_idle_timeout: Some(Duration::from_secs(90)),
                 pool_max_idle_per_host: usize::MAX,
                 pool_max_size: None,
+                pool_max_connection_age: None,
+                pool_max_connection_requests: None,
                 // TODO: Re-enable default duration once hyper's HttpConnector is fixed
                 // to no longer error when an option fails.
                 tcp_keepalive: None,
+                tcp_keepalive_interval: None,
+                tcp_keepalive_retries: None,
+                tcp_send_buffer_size: None,
+                tcp_recv_buffer_size: None,
+                #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+                tcp_user_timeout: None,
+                ip_ttl: None,
+                #[cfg(unix)]
+                tcp_mss: None,
                 proxies: Vec::new(),
                 auto_sys_proxy: true,
                 redirect_policy: redirect::Policy::none(),
                 redirect_with_proxy_auth: false,
+                redirect_preserve_location: false,
                 referer: true,
                 timeout: None,
                 read_timeout: None,
+                download_throttle: None,
+                upload_throttle: None,
+                max_response_size: None,
+                max_decompression_ratio: None,
                 network_scheme: NetworkScheme::builder(),
                 nodelay: true,
                 hickory_dns: cfg!(feature = "hickory-dns"),
                 #[cfg(feature = "hickory-dns")]
-                dns_strategy: None,
+                hickory_dns_options: HickoryDnsOptions::default(),
                 #[cfg(feature = "cookies")]
                 cookie_store: None,
-                dns_overrides: HashMap::new(),
+                dns_overrides: DnsOverrides::default(),
+                connect_to: HashMap::new(),
                 dns_resolver: None,
+                doh: None,
+                dot: None,
+                dns_cache: None,
+                https_rr: false,
                 builder: HyperClient::builder(TokioExecutor::new()),
                 https_only: false,
+                hsts: false,
+                hsts_preload: Vec::new(),
+                alt_svc: false,
+                client_hints: None,
+                rate_limit_pacing: false,
                 http2_max_retry_count: 2,
                 tls_info: false,
+                tls_pinning: None,
+                connector: None,
+                proxy_protocol: ProxyProtocolMatcher::default(),
                 connector_layers: None,
                 tls_config: TlsConfig::default(),
+                proxy_tls_config: None,
+                proxy_http2: false,
             },
         }
     }
@@ -226,7 +428,25 @@ impl ClientBuilder {
             proxies.push(Proxy::system());
         }
 
+        if let Some(seed) = config.deterministic {
+            config.tls_config.make_deterministic(seed);
+            if let Some(ref mut proxy_tls_config) = config.proxy_tls_config {
+                proxy_tls_config.make_deterministic(seed);
+            }
+        }
+
+        if config.lenient_http1 {
+            apply_lenient_http1(config.builder.http1());
+        }
+
         let http2_only = matches!(config.tls_config.alpn_protos, AlpnProtos::HTTP2);
+        // the profiles of hosts keep the certificate checks and session
+        // resumption of the client
+        let root_certs_store = config.tls_config.root_certs_store.clone();
+        let certs_verification = config.tls_config.certs_verification;
+        let verify_hostname = config.tls_config.verify_hostname;
+        let tls_sni = config.tls_config.tls_sni;
+        let pre_shared_key = config.tls_config.pre_shared_key;
 
         config
             .builder
@@ -235,15 +455,29 @@ impl ClientBuilder {
             .pool_timer(TokioTimer::new())
             .pool_idle_timeout(config.pool_idle_timeout)
             .pool_max_idle_per_host(config.pool_max_idle_per_host)
-            .pool_max_size(config.pool_max_size);
+            .pool_max_size(config.pool_max_size)
+            .pool_max_connection_age(config.pool_max_connection_age)
+            .pool_max_connection_requests(config.pool_max_connection_requests)
+            .debug_capture(config.debug_capture);
+
+        let doh = config.doh.is_some();
+        let doh_client = Arc::new(OnceLock::new());
+        let dns_cache = config
+            .dns_cache
+            .map(|config| Arc::new(DnsCache::new(config)));
+        let https_records = config.https_rr.then(|| Arc::new(HttpsRecords::new()));
 
         let connector = {
             let mut resolver: Arc<dyn Resolve> = if let Some(dns_resolver) = config.dns_resolver {
                 dns_resolver
+            } else if let Some(dot) = config.dot {
+                Arc::new(DotResolver::new(dot, https_records.clone())?)
             } else if config.hickory_dns {
                 #[cfg(feature = "hickory-dns")]
                 {
-                    Arc::new(HickoryDnsResolver::new(config.dns_strategy)?)
+                    Arc::new(HickoryDnsResolver::with_options(
+                        config.hickory_dns_options,
+                    )?)
                 }
                 #[cfg(not(feature = "hickory-dns"))]
                 {
@@ -252,6 +486,17 @@ impl ClientBuilder {
             } else {
                 Arc::new(GaiResolver::new())
             };
+            if let Some(endpoint) = config.doh {
+                resolver = Arc::new(DohResolver::new(
+                    endpoint,
+                    resolver,
+                    doh_client.clone(),
+                    https_records.clone(),
+                ));
+            }
+            if let Some(ref dns_cache) = dns_cache {
+                resolver = Arc::new(CachingResolver::new(resolver, dns_cache.clone()));
+            }
             if !config.dns_overrides.is_empty() {
                 resolver = Arc::new(DnsResolverWithOverrides::new(
                     resolver,
@@ -260,38 +505,200 @@ impl ClientBuilder {
             }
             let mut http = HttpConnector::new_with_resolver(DynResolver::new(resolver));
             http.set_connect_timeout(config.connect_timeout);
-
-            let tls = BoringTlsConnector::new(config.tls_config)?;
+            http.set_keepalive_interval(config.tcp_keepalive_interval);
+            http.set_keepalive_retries(config.tcp_keepalive_retries);
+            http.set_send_buffer_size(config.tcp_send_buffer_size);
+            http.set_recv_buffer_size(config.tcp_recv_buffer_size);
+            #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+            http.set_tcp_user_timeout(config.tcp_user_timeout);
+            http.set_ip_ttl(config.ip_ttl);
+            http.set_connect_to(config.connect_to);
+            #[cfg(unix)]
+            http.set_tcp_mss(config.tcp_mss);
+
+            let mut tls = BoringTlsConnector::new(config.tls_config)?;
+            tls.set_https_records(https_records.clone());
+            tls.set_handshake_timeout(config.tls_handshake_timeout);
+            let mut proxy_tls = config
+                .proxy_tls_config
+                .map(BoringTlsConnector::new)
+                .transpose()?;
+            if let Some(ref mut proxy_tls) = proxy_tls {
+                proxy_tls.set_handshake_timeout(config.tls_handshake_timeout);
+            }
             ConnectorBuilder::new(http, tls, config.nodelay, config.tls_info)
+                .proxy_tls(proxy_tls)
+                .proxy_http2(config.proxy_http2)
                 .timeout(config.connect_timeout)
                 .keepalive(config.tcp_keepalive)
                 .verbose(config.connection_verbose)
+                .debug_capture(config.debug_capture)
+                .tls_pinning(config.tls_pinning)
+                .transport(config.connector)
+                .proxy_protocol(Arc::new(config.proxy_protocol))
                 .build(config.connector_layers)
         };
 
-        Ok(Client {
-            inner: Arc::new(ArcSwap::from_pointee(ClientInner {
-                accepts: config.accepts,
-                #[cfg(feature = "cookies")]
-                cookie_store: config.cookie_store,
-                hyper: config.builder.build(connector),
-                headers: config.headers,
-                headers_order: config.headers_order,
-                redirect: config.redirect_policy,
-                redirect_with_proxy_auth: config.redirect_with_proxy_auth,
-                referer: config.referer,
-                request_timeout: config.timeout,
-                read_timeout: config.read_timeout,
-                https_only: config.https_only,
-                http2_max_retry_count: config.http2_max_retry_count,
-                proxies: Proxies::new(proxies),
-                network_scheme: config.network_scheme,
-            })),
-        })
+        if let Some(ref session) = config.session {
+            session.restore_tls_sessions(&connector);
+            #[cfg(feature = "cookies")]
+            if session.has_cookies() {
+                let cookie_store = config
+                    .cookie_store
+                    .get_or_insert_with(|| Arc::new(cookie::Jar::default()));
+                session.restore_cookies(&**cookie_store);
+            }
+        }
+
+        let mut inner = ClientInner {
+            accepts: config.accepts,
+            #[cfg(feature = "cookies")]
+            cookie_store: config.cookie_store,
+            hyper: config.builder.build(connector),
+            headers: config.headers,
+            headers_order: config.headers_order,
+            #[cfg(feature = "multipart")]
+            multipart_style: config.multipart_style,
+            query_style: config.query_style,
+            base_url: config.base_url,
+            default_query: config.default_query,
+            auth: config.auth.map(|provider| (provider, Arc::default())),
+            host_auth: config.host_auth,
+            host_headers: config.host_headers,
+            host_clients: Vec::new(),
+            #[cfg(feature = "json")]
+            har: config.har,
+            #[cfg(feature = "json")]
+            cassette: config.cassette,
+            http_cache: config.http_cache,
+            mock: config.mock,
+            signer: config.signer,
+            validators: ValidatorCache::new(),
+            deterministic: config
+                .deterministic
+                .map(|seed| Arc::new(SeededRandom::new(seed))),
+            lenient_http1: config.lenient_http1,
+            redirect: config.redirect_policy,
+            redirect_with_proxy_auth: config.redirect_with_proxy_auth,
+            redirect_preserve_location: config.redirect_preserve_location,
+            referer: config.referer,
+            request_timeout: config.timeout,
+            read_timeout: config.read_timeout,
+            download_throttle: config.download_throttle,
+            upload_throttle: config.upload_throttle,
+            max_response_size: config.max_response_size,
+            max_decompression_ratio: config.max_decompression_ratio,
+            https_only: config.https_only,
+            hsts: config
+                .hsts
+                .then(|| Arc::new(Hsts::new(config.hsts_preload))),
+            alt_svc: config.alt_svc.then(|| Arc::new(AltSvcCache::new())),
+            client_hints: config
+                .client_hints
+                .map(|hints| Arc::new(HintStore::new(hints))),
+            pacer: config.rate_limit_pacing.then(|| Arc::new(Pacer::new())),
+            stats: TransferStats::default(),
+            http2_max_retry_count: config.http2_max_retry_count,
+            proxies: Proxies::new(proxies),
+            network_scheme: config.network_scheme,
+            dns_cache,
+            https_records,
+            doh_client: None,
+        };
+
+        // the DoH queries are sent by a copy of the client without its
+        // proxies, which may need the resolver themselves, and without
+        // anything added to the requests it sends
+        if doh {
+            let bare = Client {
+                inner: Arc::new(ArcSwap::from_pointee(inner.bare())),
+            };
+            let _ = doh_client.set(WeakClient(Arc::downgrade(&bare.inner)));
+            inner.doh_client = Some(bare);
+        }
+
+        let base = inner.clone();
+        for (pattern, mut context) in config.host_impersonations {
+            context.tls_config.root_certs_store = root_certs_store.clone();
+            context.tls_config.certs_verification = certs_verification;
+            context.tls_config.verify_hostname = verify_hostname;
+            context.tls_config.tls_sni = tls_sni;
+            context.tls_config.pre_shared_key = pre_shared_key;
+
+            let mut host_inner = base.clone();
+            host_inner.hyper.fork_pool();
+            host_inner.impersonate(context)?;
+            let host_client = Client {
+                inner: Arc::new(ArcSwap::from_pointee(host_inner)),
+            };
+            inner.host_clients.push((pattern, host_client));
+        }
+
+        let client = Client {
+            inner: Arc::new(ArcSwap::from_pointee(inner)),
+        };
+
+        Ok(client)
     }
 
     // Higher-level options
 
+    /// Applies a bundle of settings tuned for a common workload.
+    ///
+    /// The preset sets the pool, timeout, redirect, retry and decompression
+    /// options described on [`Preset`], replacing earlier values. Call it
+    /// first and override individual settings afterwards.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use rquest::Preset;
+    ///
+    /// let client = rquest::Client::builder()
+    ///     .preset(Preset::Crawler)
+    ///     .timeout(Duration::from_secs(60))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn preset(mut self, preset: Preset) -> ClientBuilder {
+        match preset {
+            Preset::Crawler => {
+                self.config.pool_max_idle_per_host = 2;
+                self.config.pool_idle_timeout = Some(Duration::from_secs(30));
+                self.config.connect_timeout = Some(Duration::from_secs(10));
+                self.config.timeout = Some(Duration::from_secs(30));
+                self.config.read_timeout = None;
+                self.config.redirect_policy = redirect::Policy::limited(10);
+                self.config.referer = true;
+                self.config.http2_max_retry_count = 2;
+                self.config.accepts = Accepts::default();
+            }
+            Preset::ApiClient => {
+                self.config.pool_max_idle_per_host = 32;
+                self.config.pool_idle_timeout = Some(Duration::from_secs(90));
+                self.config.nodelay = true;
+                self.config.connect_timeout = Some(Duration::from_secs(5));
+                self.config.timeout = Some(Duration::from_secs(30));
+                self.config.read_timeout = None;
+                self.config.redirect_policy = redirect::Policy::limited(5);
+                self.config.http2_max_retry_count = 3;
+                self.config.accepts = Accepts::default();
+            }
+            Preset::Downloader => {
+                self.config.pool_max_idle_per_host = 4;
+                self.config.pool_idle_timeout = Some(Duration::from_secs(90));
+                self.config.connect_timeout = Some(Duration::from_secs(15));
+                self.config.timeout = None;
+                self.config.read_timeout = Some(Duration::from_secs(60));
+                self.config.redirect_policy = redirect::Policy::limited(10);
+                self.config.http2_max_retry_count = 1;
+                self.config.accepts = Accepts::none();
+            }
+        }
+        self
+    }
+
     /// Sets the `User-Agent` header to be used by this client.
     ///
     /// # Example
@@ -328,6 +735,181 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets the URL that relative request URLs are resolved against.
+    ///
+    /// A path is appended to the path of the base URL, while a path starting
+    /// with `/` replaces it, and an absolute URL is used as is.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # async fn doc() -> Result<(), rquest::Error> {
+    /// let client = rquest::Client::builder()
+    ///     .base_url("https://api.example.com/v1")
+    ///     .build()?;
+    ///
+    /// // GET https://api.example.com/v1/users
+    /// let res = client.get("users").send().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn base_url<U: IntoUrl>(mut self, url: U) -> ClientBuilder {
+        match into_base_url(url) {
+            Ok(base) => self.config.base_url = Some(base),
+            Err(err) => self.config.error = Some(err),
+        }
+        self
+    }
+
+    /// Sets query parameters to add to every request, such as an API key.
+    ///
+    /// They come before the parameters of the request itself. With a base
+    /// URL, they are only added to requests to its origin, so they do not
+    /// leak to other hosts.
+    ///
+    /// # Errors
+    ///
+    /// Building the client fails if the parameters can not be serialized
+    /// into a query string.
+    pub fn default_query<T: Serialize + ?Sized>(mut self, query: &T) -> ClientBuilder {
+        match serde_urlencoded::to_string(query) {
+            Ok(query) => self
+                .config
+                .default_query
+                .extend(url::form_urlencoded::parse(query.as_bytes()).into_owned()),
+            Err(err) => self.config.error = Some(crate::error::builder(err)),
+        }
+        self
+    }
+
+    /// Sets the provider of the `Authorization` header of every request.
+    ///
+    /// Requests already having an `Authorization` header are left as they
+    /// are. Only requests to the origin of the base URL are authorized, or
+    /// without one to the origin of the first request, so tokens do not leak
+    /// to other hosts.
+    ///
+    /// A request refused with `401 Unauthorized` is sent once more with a
+    /// new token, if its body can be sent again.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rquest::{AuthProvider, AuthToken};
+    /// # async fn fetch_token() -> rquest::Result<String> { Ok(String::new()) }
+    /// # fn doc() -> Result<(), rquest::Error> {
+    /// let client = rquest::Client::builder()
+    ///     .base_url("https://api.example.com")
+    ///     .default_auth(AuthProvider::new(|| async {
+    ///         AuthToken::bearer(&fetch_token().await?)
+    ///     }))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn default_auth(mut self, provider: AuthProvider) -> ClientBuilder {
+        self.config.auth = Some(provider);
+        self
+    }
+
+    /// Sets the provider of the `Authorization` header of the requests to
+    /// `origin`, such as `https://orders.example.com`, instead of the default
+    /// one.
+    ///
+    /// Only requests with the same scheme, host and port are authorized, so
+    /// a token for an `https` origin is never sent in cleartext. Requests
+    /// setting their own `Authorization` header are sent as is.
+    ///
+    /// # Errors
+    ///
+    /// Building the client fails if `origin` is not a valid URL.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "json")]
+    /// # fn doc() -> Result<(), rquest::Error> {
+    /// use rquest::OAuth2;
+    ///
+    /// let orders = OAuth2::client_credentials("https://auth.example.com/token", "id", "secret");
+    /// let client = rquest::Client::builder()
+    ///     .host_auth("https://orders.example.com", orders.into())
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn host_auth<U: IntoUrl>(mut self, origin: U, provider: AuthProvider) -> ClientBuilder {
+        match origin.into_url() {
+            Ok(url) => self.config.host_auth.push((url.origin(), provider)),
+            Err(err) => self.config.error = Some(err),
+        }
+        self
+    }
+
+    /// Records the requests and responses of the client in `recorder`, to
+    /// export them as a HAR archive.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `json` feature enabled.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn har_recorder(mut self, recorder: HarRecorder) -> ClientBuilder {
+        self.config.har = Some(recorder);
+        self
+    }
+
+    /// Replays the responses recorded in `cassette` instead of sending the
+    /// requests matching them, and records the others.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `json` feature enabled.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn cassette(mut self, cassette: Cassette) -> ClientBuilder {
+        self.config.cassette = Some(cassette);
+        self
+    }
+
+    /// Keeps the responses in `cache`, to answer the same requests with them
+    /// while they are fresh and revalidate them once stale, as of RFC 9111.
+    ///
+    /// See the [`cache`](crate::cache) module for more.
+    pub fn http_cache(mut self, cache: HttpCache) -> ClientBuilder {
+        self.config.http_cache = Some(cache);
+        self
+    }
+
+    /// Answers the requests with the canned responses of `mock`, instead of
+    /// sending them.
+    ///
+    /// This is meant for tests of code using a `Client`.
+    pub fn mock(mut self, mock: MockTransport) -> ClientBuilder {
+        self.config.mock = Some(mock);
+        self
+    }
+
+    /// Sign every request with `signer` just before it is sent, unless the
+    /// request sets its own.
+    ///
+    /// See [`RequestSigner`] for when it is called.
+    pub fn signer<S: RequestSigner>(mut self, signer: S) -> ClientBuilder {
+        self.config.signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Resume the state another client exported with
+    /// [`Client::export_session`].
+    ///
+    /// Its cookies are added to the cookie store, enabling the default one
+    /// if none is set, and its TLS sessions are resumed when connecting to
+    /// their hosts again, if the TLS configuration caches sessions.
+    pub fn import_session(mut self, state: SessionState) -> ClientBuilder {
+        self.config.session = Some(state);
+        self
+    }
+
     /// Sets the default headers for every request.
     ///
     /// # Example
@@ -377,6 +959,44 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets headers for the requests to the hosts matching `pattern`, on top
+    /// of the default headers.
+    ///
+    /// The headers replace the default headers of the same name, and are
+    /// themselves replaced by the headers of the request. When several
+    /// patterns match, the first one added wins for the headers they both set.
+    /// A redirect to another host swaps the headers of the previous host's
+    /// patterns for the ones of the new host.
+    ///
+    /// `pattern` is either a host name or IP address, matched exactly, a
+    /// wildcard such as `*.example.com` matching every subdomain, or `*`
+    /// matching every host.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rquest::header::{self, HeaderMap, HeaderValue};
+    ///
+    /// let mut github = HeaderMap::new();
+    /// github.insert(header::ACCEPT, HeaderValue::from_static("application/vnd.github+json"));
+    ///
+    /// let mut internal = HeaderMap::new();
+    /// internal.insert("x-api-key", HeaderValue::from_static("secret"));
+    ///
+    /// let client = rquest::Client::builder()
+    ///     .headers_for_host("api.github.com", github)
+    ///     .headers_for_host("*.internal", internal)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn headers_for_host<P>(mut self, pattern: P, headers: HeaderMap) -> ClientBuilder
+    where
+        P: Into<String>,
+    {
+        self.config.host_headers.push((pattern.into(), headers));
+        self
+    }
+
     /// Change the order in which headers will be sent
     ///
     /// Warning
@@ -388,6 +1008,34 @@ impl ClientBuilder {
         self
     }
 
+    /// Set how multipart forms are formatted, unless a form sets its own
+    /// style with [`Form::style`](crate::multipart::Form::style).
+    ///
+    /// Impersonation sets the style of the impersonated client.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `multipart` feature to be enabled.
+    #[cfg(feature = "multipart")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "multipart")))]
+    pub fn multipart_style(mut self, style: MultipartStyle) -> ClientBuilder {
+        self.config.multipart_style = style;
+        self
+    }
+
+    /// Serialize sequences and nested values in `style` in the query
+    /// strings and forms of requests, instead of rejecting them.
+    ///
+    /// By default, [`RequestBuilder::query`] and [`RequestBuilder::form`]
+    /// serialize with `serde_urlencoded`, which only accepts flat values.
+    ///
+    /// [`RequestBuilder::query`]: crate::RequestBuilder::query
+    /// [`RequestBuilder::form`]: crate::RequestBuilder::form
+    pub fn query_style(mut self, style: QueryStyle) -> ClientBuilder {
+        self.config.query_style = Some(style);
+        self
+    }
+
     /// Enable a persistent cookie store for the client.
     ///
     /// Cookies received in responses will be preserved and included in
@@ -637,13 +1285,31 @@ impl ClientBuilder {
         self
     }
 
+    /// Send the path and query of a redirect's `Location` header exactly as received.
+    ///
+    /// By default the `Location` is parsed into a `Url`, which normalizes its
+    /// path (decoding, dot segments, and so on). When enabled, absolute URLs
+    /// and absolute paths are requested verbatim instead; only the fragment is
+    /// dropped, since it is never sent. Relative references still need to be
+    /// resolved, and are normalized as before.
+    ///
+    /// The raw and parsed forms of each followed redirect are available from
+    /// [`Response::history`].
+    ///
+    /// Default is `false`.
+    pub fn redirect_preserve_location(mut self, enable: bool) -> ClientBuilder {
+        self.config.redirect_preserve_location = enable;
+        self
+    }
+
     // Proxy options
 
     /// Add a `Proxy` to the list of proxies the `Client` will use.
     ///
     /// # Note
     ///
-    /// Adding a proxy will disable the automatic usage of the "system" proxy.
+    /// Adding a proxy will disable the automatic usage of the "system" proxy,
+    /// unless [`ClientBuilder::use_system_proxy`] enables it again.
     pub fn proxy(mut self, proxy: Proxy) -> ClientBuilder {
         self.config.proxies.push(proxy);
         self.config.auto_sys_proxy = false;
@@ -663,6 +1329,57 @@ impl ClientBuilder {
         self
     }
 
+    /// Use the proxy settings of the system.
+    ///
+    /// These come from the `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and
+    /// `NO_PROXY` environment variables, or when none is set, from the
+    /// Internet Settings of the current user on Windows, and from the
+    /// network settings of SystemConfiguration on macOS, bypass lists
+    /// included.
+    ///
+    /// The system proxies are tried after the proxies added with
+    /// [`ClientBuilder::proxy`].
+    ///
+    /// Default is `true`, until a proxy is added or [`ClientBuilder::no_proxy`]
+    /// is called.
+    pub fn use_system_proxy(mut self, enable: bool) -> ClientBuilder {
+        self.config.auto_sys_proxy = enable;
+        self
+    }
+
+    /// Send a PROXY protocol header on new connections to matching hosts.
+    ///
+    /// The header announces the client's address to the load balancer in
+    /// front of the destination, and is sent right after the TCP connection is
+    /// established, before any TLS handshake.
+    ///
+    /// Connections through a proxy, or opened by a custom
+    /// [`Connector`](transport::Connector), carry the header at the start of
+    /// the tunnel to the destination instead. Since the addresses of the
+    /// tunnel's ends are not known, it then announces an unknown connection.
+    ///
+    /// `pattern` is either a host name or IP address, matched exactly, a
+    /// wildcard such as `*.internal` matching every subdomain, or `*` matching
+    /// every host. When several patterns match, the first one added wins.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rquest::ProxyProtocol;
+    ///
+    /// let client = rquest::Client::builder()
+    ///     .proxy_protocol("*.internal", ProxyProtocol::V2)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn proxy_protocol<P>(mut self, pattern: P, version: ProxyProtocol) -> ClientBuilder
+    where
+        P: Into<String>,
+    {
+        self.config.proxy_protocol.push(pattern.into(), version);
+        self
+    }
+
     // Timeout options
 
     /// Enables a request timeout.
@@ -678,12 +1395,70 @@ impl ClientBuilder {
 
     /// Set a timeout for only the read phase of a `Client`.
     ///
+    /// The timeout fires when no data arrives for this long: while waiting
+    /// for the response headers of each request, redirects included, and
+    /// between two chunks of the response body. Unlike
+    /// [`ClientBuilder::timeout`], it never cuts off a long download that
+    /// keeps making progress, so both can be combined to detect stalls
+    /// under a generous total deadline.
+    ///
     /// Default is `None`.
     pub fn read_timeout(mut self, timeout: Duration) -> ClientBuilder {
         self.config.read_timeout = Some(timeout);
         self
     }
 
+    /// Fail reading response bodies larger than `bytes`.
+    ///
+    /// The limit counts the bytes after decompression, so it also guards
+    /// against decompression bombs. A body announcing a larger
+    /// `Content-Length` fails before any of it is read. The error reports
+    /// `is_body_too_large()`.
+    ///
+    /// Default is `None`.
+    pub fn max_response_size(mut self, bytes: u64) -> ClientBuilder {
+        self.config.max_response_size = Some(bytes);
+        self
+    }
+
+    /// Fail reading compressed response bodies that expand more than
+    /// `ratio` times.
+    ///
+    /// The ratio is checked once the decompressed body passes 64 KiB, and
+    /// guards against small responses expanding into gigabytes. Most
+    /// legitimate bodies stay under a ratio of 100. The error reports
+    /// `is_decompression_limit()`.
+    ///
+    /// Default is `None`.
+    pub fn max_decompression_ratio(mut self, ratio: u32) -> ClientBuilder {
+        self.config.max_decompression_ratio = Some(ratio);
+        self
+    }
+
+    /// Cap the bandwidth response bodies are received at.
+    ///
+    /// One bucket is shared by all the requests of the `Client`, so the cap
+    /// holds for their combined bandwidth. A cap set with
+    /// `RequestBuilder::download_throttle()` applies on top of it.
+    ///
+    /// Default is `None`.
+    pub fn download_throttle(mut self, throttle: Throttle) -> ClientBuilder {
+        self.config.download_throttle = Some(throttle);
+        self
+    }
+
+    /// Cap the bandwidth request bodies are sent at.
+    ///
+    /// One bucket is shared by all the requests of the `Client`, so the cap
+    /// holds for their combined bandwidth. A cap set with
+    /// `RequestBuilder::upload_throttle()` applies on top of it.
+    ///
+    /// Default is `None`.
+    pub fn upload_throttle(mut self, throttle: Throttle) -> ClientBuilder {
+        self.config.upload_throttle = Some(throttle);
+        self
+    }
+
     /// Set a timeout for only the connect phase of a `Client`.
     ///
     /// Default is `None`.
@@ -697,6 +1472,24 @@ impl ClientBuilder {
         self
     }
 
+    /// Set a timeout for each TLS handshake, with the origin or with an
+    /// HTTPS proxy.
+    ///
+    /// Unlike `connect_timeout`, which covers the whole connect phase, this
+    /// fails fast on servers that accept the TCP connection but stall the
+    /// handshake. The resulting error reports `is_timeout()`.
+    ///
+    /// Default is `None`.
+    ///
+    /// # Note
+    ///
+    /// This **requires** the futures be executed in a tokio runtime with
+    /// a tokio timer enabled.
+    pub fn tls_handshake_timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.config.tls_handshake_timeout = Some(timeout);
+        self
+    }
+
     /// Set whether connections should emit verbose logs.
     ///
     /// Enabling this option will emit [log][] messages at the `TRACE` level
@@ -708,6 +1501,21 @@ impl ClientBuilder {
         self
     }
 
+    /// Capture what each request actually sends, to debug fingerprints.
+    ///
+    /// Responses then carry a [`DebugCapture`](crate::DebugCapture) with
+    /// the ClientHello of their connection, the request head as written on
+    /// HTTP/1, and the headers in the order they were written.
+    ///
+    /// Connections copy what they write while a request head is being
+    /// captured, so this is meant for debugging rather than production.
+    ///
+    /// Default is `false`.
+    pub fn debug_capture(mut self, enabled: bool) -> ClientBuilder {
+        self.config.debug_capture = enabled;
+        self
+    }
+
     // HTTP options
 
     /// Set an optional timeout for idle sockets being kept-alive.
@@ -730,6 +1538,10 @@ impl ClientBuilder {
     }
 
     /// Sets the maximum number of connections in the pool.
+    ///
+    /// The pool is split by host in shards, each given its share of the
+    /// limit. A shard holding its share evicts its least recently used host,
+    /// even if other shards have room left.
     pub fn pool_max_size<D>(mut self, max: D) -> ClientBuilder
     where
         D: Into<Option<NonZeroUsize>>,
@@ -738,10 +1550,40 @@ impl ClientBuilder {
         self
     }
 
+    /// Retire connections after they were used for `max_age`, give or take
+    /// a random tenth of it, so connections opened together don't retire
+    /// together.
+    ///
+    /// A retired connection finishes its requests, but takes no new one. This
+    /// keeps connections from piling up state on servers, or outliving the
+    /// idle limits of middleboxes.
+    ///
+    /// Default is `None`, for no limit.
+    pub fn pool_max_connection_age<D>(mut self, max_age: D) -> ClientBuilder
+    where
+        D: Into<Option<Duration>>,
+    {
+        self.config.pool_max_connection_age = max_age.into();
+        self
+    }
+
+    /// Retire connections after they were given `max` requests.
+    ///
+    /// Default is `None`, for no limit.
+    pub fn pool_max_connection_requests<D>(mut self, max: D) -> ClientBuilder
+    where
+        D: Into<Option<usize>>,
+    {
+        self.config.pool_max_connection_requests = max.into();
+        self
+    }
+
     /// Disable keep-alive for the client.
     pub fn no_keepalive(mut self) -> ClientBuilder {
         self.config.pool_max_idle_per_host = 0;
         self.config.tcp_keepalive = None;
+        self.config.tcp_keepalive_interval = None;
+        self.config.tcp_keepalive_retries = None;
         self
     }
 
@@ -793,7 +1635,25 @@ impl ClientBuilder {
         self
     }
 
-    /// Configures the HTTP/2 builder with the provided closure.
+    /// Tolerate the malformed HTTP/1 responses that browsers accept, instead
+    /// of failing them.
+    ///
+    /// This accepts headers folded over several lines (obs-fold), with the
+    /// folds replaced by spaces, and spaces between a header name and its
+    /// colon, and skips the header lines that cannot be parsed. Responses
+    /// without a reason phrase, or with bare LF line endings, are always
+    /// accepted.
+    ///
+    /// This overrides the matching settings of [`Http1Config`], including
+    /// those of an impersonation profile.
+    ///
+    /// Default is false.
+    pub fn lenient_http1(mut self, enabled: bool) -> ClientBuilder {
+        self.config.lenient_http1 = enabled;
+        self
+    }
+
+    /// Configures the HTTP/2 builder with the provided closure.
     ///
     /// This method allows you to customize the HTTP/2 builder by passing a closure
     /// that modifies the builder. The closure receives a mutable reference to the
@@ -908,6 +1768,93 @@ impl ClientBuilder {
         self
     }
 
+    /// Set the interval between TCP keepalive probes that go unacknowledged.
+    ///
+    /// If `None`, the system default is used.
+    pub fn tcp_keepalive_interval<D>(mut self, val: D) -> ClientBuilder
+    where
+        D: Into<Option<Duration>>,
+    {
+        self.config.tcp_keepalive_interval = val.into();
+        self
+    }
+
+    /// Set the number of unacknowledged TCP keepalive probes sent before the
+    /// connection is considered dead.
+    ///
+    /// If `None`, the system default is used.
+    pub fn tcp_keepalive_retries<C>(mut self, retries: C) -> ClientBuilder
+    where
+        C: Into<Option<u32>>,
+    {
+        self.config.tcp_keepalive_retries = retries.into();
+        self
+    }
+
+    /// Sets the size of the socket send buffer (`SO_SNDBUF`).
+    ///
+    /// If `None`, the option will not be set.
+    pub fn tcp_send_buffer_size<S>(mut self, size: S) -> ClientBuilder
+    where
+        S: Into<Option<usize>>,
+    {
+        self.config.tcp_send_buffer_size = size.into();
+        self
+    }
+
+    /// Sets the size of the socket receive buffer (`SO_RCVBUF`).
+    ///
+    /// The buffer is sized before connecting, so it also determines the
+    /// window and window scale factor advertised in the SYN.
+    ///
+    /// If `None`, the option will not be set.
+    pub fn tcp_recv_buffer_size<S>(mut self, size: S) -> ClientBuilder
+    where
+        S: Into<Option<usize>>,
+    {
+        self.config.tcp_recv_buffer_size = size.into();
+        self
+    }
+
+    /// Set the maximum time transmitted data may remain unacknowledged before
+    /// the connection is closed (`TCP_USER_TIMEOUT`).
+    ///
+    /// If `None`, the option will not be set.
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    pub fn tcp_user_timeout<D>(mut self, val: D) -> ClientBuilder
+    where
+        D: Into<Option<Duration>>,
+    {
+        self.config.tcp_user_timeout = val.into();
+        self
+    }
+
+    /// Sets the IPv4 time-to-live, or the IPv6 hop limit, of outgoing packets.
+    ///
+    /// Passive fingerprinting tools infer the operating system from the
+    /// initial TTL, so this can be aligned with the impersonated platform.
+    ///
+    /// If `None`, the system default is used.
+    pub fn ip_ttl<T>(mut self, ttl: T) -> ClientBuilder
+    where
+        T: Into<Option<u32>>,
+    {
+        self.config.ip_ttl = ttl.into();
+        self
+    }
+
+    /// Sets the TCP maximum segment size (`TCP_MAXSEG`) advertised in the SYN.
+    ///
+    /// If `None`, the system default is used.
+    #[cfg(unix)]
+    pub fn tcp_mss<T>(mut self, mss: T) -> ClientBuilder
+    where
+        T: Into<Option<u32>>,
+    {
+        self.config.tcp_mss = mss.into();
+        self
+    }
+
     // TLS/HTTP2 impersonate options
 
     /// Configures the client builder to impersonate the specified HTTP context.
@@ -948,6 +1895,10 @@ impl ClientBuilder {
             std::mem::swap(&mut self.config.headers_order, &mut Some(headers_order));
         }
 
+        if let Some(multipart_style) = http_context.multipart_style {
+            self.config.multipart_style = multipart_style;
+        }
+
         if let Some(http1_config) = http_context.http1_config.take() {
             let builder = self.config.builder.http1();
             apply_http1_config(builder, http1_config);
@@ -961,6 +1912,46 @@ impl ClientBuilder {
         self
     }
 
+    /// Impersonates another HTTP context toward the hosts matching `pattern`.
+    ///
+    /// The requests to those hosts get the headers, HTTP/1, HTTP/2 and TLS
+    /// settings of `provider` instead of the ones of the client, as an app
+    /// talking to its API with its own HTTP library while its web views
+    /// browse elsewhere. Their connections are made with that profile, and
+    /// pooled apart from the ones of the client.
+    ///
+    /// The other settings of the client, such as its proxies, cookie store,
+    /// root certificates, certificate verification and session resumption
+    /// with `pre_shared_key`, apply as they are when the client is built. A
+    /// request keeps the profile of its first host through its redirects.
+    ///
+    /// `pattern` is either a host name or IP address, matched exactly, a
+    /// wildcard such as `*.example.com` matching every subdomain, or `*`
+    /// matching every host. When several patterns match, the first one added
+    /// wins.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rquest::{Client, Impersonate};
+    ///
+    /// let client = Client::builder()
+    ///     .impersonate(Impersonate::Chrome131)
+    ///     .impersonate_for_host("api.app.com", Impersonate::OkHttp5)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn impersonate_for_host<H, P>(mut self, pattern: H, provider: P) -> ClientBuilder
+    where
+        H: Into<String>,
+        P: HttpContextProvider,
+    {
+        self.config
+            .host_impersonations
+            .push((pattern.into(), provider.context()));
+        self
+    }
+
     /// Enable Encrypted Client Hello (Secure SNI)
     pub fn enable_ech_grease(mut self, enabled: bool) -> ClientBuilder {
         self.config.tls_config.enable_ech_grease = enabled;
@@ -973,6 +1964,24 @@ impl ClientBuilder {
         self
     }
 
+    /// Removes the random elements of the fingerprint, so every run sends
+    /// the same bytes where it can.
+    ///
+    /// The GREASE values of the TLS ClientHello are left out, permuted TLS
+    /// extensions get an order drawn from `seed`, and multipart boundaries
+    /// are drawn from `seed` too. The HTTP/2 frames carry no random values
+    /// to begin with.
+    ///
+    /// The random, key shares and session ID of the ClientHello stay random,
+    /// as the handshake is not secure otherwise, and a resumed session adds
+    /// a pre-shared key. This is meant for golden-file tests of
+    /// impersonation profiles, not for real traffic: no browser sends a
+    /// ClientHello without GREASE.
+    pub fn deterministic(mut self, seed: u64) -> ClientBuilder {
+        self.config.deterministic = Some(seed);
+        self
+    }
+
     /// Enable TLS pre_shared_key
     pub fn pre_shared_key(mut self, enabled: bool) -> ClientBuilder {
         self.config.tls_config.pre_shared_key = enabled;
@@ -1069,6 +2078,55 @@ impl ClientBuilder {
         self
     }
 
+    /// Pin the TLS fingerprint of each origin, and detect when it changes.
+    ///
+    /// See [`TlsPinning`] for details. Passing the same `Arc` to several
+    /// clients shares the pinned fingerprints between them.
+    pub fn tls_pinning(mut self, pinning: Arc<TlsPinning>) -> ClientBuilder {
+        self.config.tls_pinning = Some(pinning);
+        self
+    }
+
+    /// Set the TLS settings of connections to `https://` proxies.
+    ///
+    /// By default, the client reaches HTTPS proxies with the same TLS
+    /// settings as servers, impersonated ClientHello included. Pass
+    /// `TlsConfig::default()` to use BoringSSL's own ClientHello instead, or
+    /// a config with its own root certificates for a proxy with a private
+    /// certificate authority.
+    ///
+    /// Only HTTP/1.1 is offered to the proxy, whatever the ALPN protocols of
+    /// the config, unless [`ClientBuilder::proxy_http2`] is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn run() -> Result<(), rquest::Error> {
+    /// let client = rquest::Client::builder()
+    ///     .proxy(rquest::Proxy::all("https://secure.proxy.example:443")?)
+    ///     .proxy_tls_config(rquest::TlsConfig::default())
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn proxy_tls_config(mut self, config: TlsConfig) -> ClientBuilder {
+        self.config.proxy_tls_config = Some(config);
+        self
+    }
+
+    /// Tunnel through `https://` proxies over HTTP/2 when they support it.
+    ///
+    /// When enabled, the client offers HTTP/2 to HTTPS proxies, and opens
+    /// each tunnel to an `https` destination as a `CONNECT` stream on one
+    /// shared connection per proxy. Proxies that only speak HTTP/1.1 get a
+    /// connection per tunnel, as usual.
+    ///
+    /// Default is `false`.
+    pub fn proxy_http2(mut self, enabled: bool) -> ClientBuilder {
+        self.config.proxy_http2 = enabled;
+        self
+    }
+
     /// Restrict the Client to be used with HTTPS only requests.
     ///
     /// Defaults to false.
@@ -1077,6 +2135,99 @@ impl ClientBuilder {
         self
     }
 
+    /// Enables HTTP Strict Transport Security, as browsers do.
+    ///
+    /// The client remembers the hosts sending a `Strict-Transport-Security`
+    /// header over HTTPS, for as long as its `max-age`, and with their
+    /// subdomains when it has `includeSubDomains`. Requests to those hosts
+    /// over plain HTTP are upgraded to HTTPS before being sent, and so are
+    /// redirects to them, so they are never downgraded. IP addresses are
+    /// never upgraded.
+    ///
+    /// Defaults to false.
+    pub fn hsts(mut self, enabled: bool) -> ClientBuilder {
+        self.config.hsts = enabled;
+        self
+    }
+
+    /// Enables HTTP Strict Transport Security, with `hosts` and their
+    /// subdomains known to be reached over HTTPS only from the start.
+    ///
+    /// This takes a preload list, such as the one browsers ship, so the
+    /// first request to those hosts is upgraded too. See
+    /// [`ClientBuilder::hsts`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let client = rquest::Client::builder()
+    ///     .hsts_preload(["example.com", "example.org"])
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn hsts_preload<I, S>(mut self, hosts: I) -> ClientBuilder
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.hsts = true;
+        self.config
+            .hsts_preload
+            .extend(hosts.into_iter().map(Into::into));
+        self
+    }
+
+    /// Enables alternative services, as browsers do.
+    ///
+    /// The client remembers the endpoints an origin advertises in `Alt-Svc`
+    /// headers over HTTPS, for as long as their `ma` parameter, and makes the
+    /// new connections to the origin to the first one it can speak, `h2` or
+    /// `http/1.1`. Alternatives over QUIC, such as `h3`, are remembered but
+    /// not used. TLS is still set up for the host of the origin, and a
+    /// connection to an alternative that fails falls back to the origin.
+    ///
+    /// Connections through a proxy always go to the origin.
+    ///
+    /// Defaults to false.
+    pub fn alt_svc(mut self, enabled: bool) -> ClientBuilder {
+        self.config.alt_svc = enabled;
+        self
+    }
+
+    /// Sets the high entropy client hints sent to the origins asking for
+    /// them.
+    ///
+    /// The client remembers the hints an origin asks for in the `Accept-CH`
+    /// header of a response over HTTPS, and sends the ones set in `hints`
+    /// with the requests to that origin from then on. The low entropy hints,
+    /// such as `Sec-CH-UA-Platform`, are in the default headers instead, so
+    /// `hints` should describe the same platform.
+    ///
+    /// Defaults to none.
+    pub fn client_hints(mut self, hints: ClientHints) -> ClientBuilder {
+        self.config.client_hints = Some(hints);
+        self
+    }
+
+    /// Enables pacing the requests to the origins asking for fewer.
+    ///
+    /// The client remembers the pause an origin asks for in the
+    /// `Retry-After` header of a `429 Too Many Requests` or `503 Service
+    /// Unavailable` response, or in rate limit headers saying its quota is
+    /// used up, such as `RateLimit: "default";r=0;t=30`. Until the pause is
+    /// over, new requests to the origin wait before being sent, in every
+    /// task using the client. A `429` without `Retry-After` pauses the
+    /// origin for a second, doubled by each one in a row, up to a minute.
+    ///
+    /// Pauses are capped at 10 minutes. The wait doesn't count towards the
+    /// timeout of the request, and redirects are followed without waiting.
+    ///
+    /// Defaults to false.
+    pub fn rate_limit_pacing(mut self, enabled: bool) -> ClientBuilder {
+        self.config.rate_limit_pacing = enabled;
+        self
+    }
+
     /// Set root certificate store.
     pub fn root_cert_store<S>(mut self, store: S) -> ClientBuilder
     where
@@ -1098,7 +2249,56 @@ impl ClientBuilder {
     #[cfg(feature = "hickory-dns")]
     #[cfg_attr(docsrs, doc(cfg(feature = "hickory-dns")))]
     pub fn hickory_dns_strategy(mut self, strategy: LookupIpStrategy) -> ClientBuilder {
-        self.config.dns_strategy = Some(strategy);
+        self.config.hickory_dns_options.strategy = Some(strategy);
+        self
+    }
+
+    /// Sets the nameservers the `hickory-dns` resolver queries, over UDP
+    /// with a TCP fallback, instead of those of the system configuration.
+    ///
+    /// # Optional
+    ///
+    /// Requires the `hickory-dns` feature to be enabled.
+    #[cfg(feature = "hickory-dns")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "hickory-dns")))]
+    pub fn hickory_dns_nameservers<I>(mut self, nameservers: I) -> ClientBuilder
+    where
+        I: IntoIterator<Item = SocketAddr>,
+    {
+        self.config.hickory_dns_options.nameservers = Some(nameservers.into_iter().collect());
+        self
+    }
+
+    /// Sets the number of dots a name needs before the `hickory-dns` resolver
+    /// tries it as an absolute name, before appending the search domains.
+    ///
+    /// # Optional
+    ///
+    /// Requires the `hickory-dns` feature to be enabled.
+    #[cfg(feature = "hickory-dns")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "hickory-dns")))]
+    pub fn hickory_dns_ndots(mut self, ndots: usize) -> ClientBuilder {
+        self.config.hickory_dns_options.ndots = Some(ndots);
+        self
+    }
+
+    /// Sets the domains the `hickory-dns` resolver appends to names with
+    /// fewer dots than `ndots`, instead of those of the system configuration.
+    ///
+    /// Invalid domain names make [`ClientBuilder::build`] fail.
+    ///
+    /// # Optional
+    ///
+    /// Requires the `hickory-dns` feature to be enabled.
+    #[cfg(feature = "hickory-dns")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "hickory-dns")))]
+    pub fn hickory_dns_search<I, S>(mut self, domains: I) -> ClientBuilder
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.hickory_dns_options.search =
+            Some(domains.into_iter().map(Into::into).collect());
         self
     }
 
@@ -1116,6 +2316,10 @@ impl ClientBuilder {
 
     /// Override DNS resolution for specific domains to a particular IP address.
     ///
+    /// `domain` may also be a wildcard like `*.example.com`, which overrides
+    /// every subdomain of `example.com`. Exact names take precedence over
+    /// wildcards, and longer wildcards over shorter ones.
+    ///
     /// Warning
     ///
     /// Since the DNS protocol has no notion of ports, if you wish to send
@@ -1128,6 +2332,11 @@ impl ClientBuilder {
 
     /// Override DNS resolution for specific domains to particular IP addresses.
     ///
+    /// `domain` may be a wildcard, as with [`ClientBuilder::resolve`]. The
+    /// addresses are tried in the given order; see
+    /// [`ClientBuilder::resolve_to_addrs_round_robin`] to spread connections
+    /// over them.
+    ///
     /// Warning
     ///
     /// Since the DNS protocol has no notion of ports, if you wish to send
@@ -1137,7 +2346,78 @@ impl ClientBuilder {
     pub fn resolve_to_addrs(mut self, domain: &str, addrs: &[SocketAddr]) -> ClientBuilder {
         self.config
             .dns_overrides
-            .insert(domain.to_string(), addrs.to_vec());
+            .insert(domain, OverrideTarget::Addrs(addrs.to_vec()));
+        self
+    }
+
+    /// Override DNS resolution for specific domains to particular IP
+    /// addresses, spreading connections over them.
+    ///
+    /// As [`ClientBuilder::resolve_to_addrs`], except that each resolution
+    /// rotates the addresses, so connections are spread over them
+    /// round-robin, with the others as fallbacks.
+    pub fn resolve_to_addrs_round_robin(
+        mut self,
+        domain: &str,
+        addrs: &[SocketAddr],
+    ) -> ClientBuilder {
+        self.config
+            .dns_overrides
+            .insert(domain, OverrideTarget::RoundRobin(addrs.to_vec()));
+        self
+    }
+
+    /// Override DNS resolution for specific domains to the host addresses of
+    /// a network, given in CIDR notation like `10.0.0.0/24` or `fd00::/64`.
+    ///
+    /// `domain` may be a wildcard, as with [`ClientBuilder::resolve`]. Each
+    /// resolution picks the next host address in the network, round-robin,
+    /// skipping its network and broadcast addresses.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let client = rquest::Client::builder()
+    ///     .resolve_to_range("*.staging.example.com", "10.20.0.0/28")
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// [`ClientBuilder::build`] fails if `cidr` is not a valid network.
+    pub fn resolve_to_range(mut self, domain: &str, cidr: &str) -> ClientBuilder {
+        match cidr.parse() {
+            Ok(range) => self
+                .config
+                .dns_overrides
+                .insert(domain, OverrideTarget::Range(range)),
+            Err(err) => self.config.error = Some(crate::error::builder(err)),
+        }
+        self
+    }
+
+    /// Connect to `addr` for requests to `host` on `port`, as with curl's
+    /// `--connect-to`.
+    ///
+    /// Unlike [`ClientBuilder::resolve`], only the given port is mapped,
+    /// and the connection is made to the port of `addr`. The request is
+    /// otherwise unchanged: its `Host` header, TLS server name and
+    /// certificate validation are for `host`. This sends requests for a
+    /// site to one of its origin servers, such as behind a CDN.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let client = rquest::Client::builder()
+    ///     .connect_to("example.com", 443, "203.0.113.7:8443".parse().unwrap())
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn connect_to(mut self, host: &str, port: u16, addr: SocketAddr) -> ClientBuilder {
+        self.config
+            .connect_to
+            .insert((host.to_ascii_lowercase(), port), addr);
         self
     }
 
@@ -1151,6 +2431,116 @@ impl ClientBuilder {
         self
     }
 
+    /// Override the DNS resolver implementation with one returning weighted
+    /// addresses, such as a service discovery client.
+    ///
+    /// Each resolution tries the addresses in a random order biased by their
+    /// weights. As with `dns_resolver`, the overrides passed to `resolve` and
+    /// `resolve_to_addrs` still apply.
+    pub fn weighted_dns_resolver<R: ResolveWeighted + 'static>(
+        mut self,
+        resolver: Arc<R>,
+    ) -> ClientBuilder {
+        self.config.dns_resolver = Some(Arc::new(WeightedResolver(resolver)));
+        self
+    }
+
+    /// Resolve names with DNS-over-HTTPS, querying the given endpoint.
+    ///
+    /// The queries use the same impersonated TLS stack and network settings
+    /// as any other request, but are sent directly, without the proxies,
+    /// cookies, default headers or authentication of this client. The
+    /// endpoint's own host name is resolved with the resolver that would be
+    /// used otherwise, including one set with `dns_resolver`.
+    ///
+    /// Overrides for specific names passed to `resolve` and `resolve_to_addrs`
+    /// still take precedence.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let client = rquest::Client::builder()
+    ///     .doh("https://cloudflare-dns.com/dns-query")
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn doh<U: IntoUrl>(mut self, endpoint: U) -> ClientBuilder {
+        match endpoint.into_url() {
+            Ok(endpoint) => self.config.doh = Some(endpoint),
+            Err(err) => self.config.error = Some(err),
+        }
+        self
+    }
+
+    /// Resolve names with DNS-over-TLS, querying the given upstream server.
+    ///
+    /// This replaces the system resolver, for example where DNS-over-HTTPS
+    /// to port 443 of a resolver is filtered. It has no effect if a custom
+    /// resolver is set with `dns_resolver`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rquest::dns::DotConfig;
+    ///
+    /// let client = rquest::Client::builder()
+    ///     .dot(DotConfig::quad9())
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn dot(mut self, upstream: DotConfig) -> ClientBuilder {
+        self.config.dot = Some(upstream);
+        self
+    }
+
+    /// Queries HTTPS (type 65) records alongside addresses, and uses them
+    /// when connecting.
+    ///
+    /// A host's HTTPS record can point to an alternative endpoint, whose
+    /// addresses are used instead, a port to connect to, and address hints
+    /// used when the name has no addresses of its own. When it carries an
+    /// Encrypted Client Hello configuration, the TLS handshake encrypts the
+    /// ClientHello with it. The record, including the ALPN protocols, such
+    /// as `h3`, that the server advertises, can be read back with
+    /// [`Client::https_record`].
+    ///
+    /// Only the DNS-over-HTTPS and DNS-over-TLS resolvers, enabled with
+    /// `doh` and `dot`, query HTTPS records.
+    ///
+    /// Default is `false`.
+    pub fn https_rr(mut self, enabled: bool) -> ClientBuilder {
+        self.config.https_rr = enabled;
+        self
+    }
+
+    /// Caches DNS resolutions inside the client.
+    ///
+    /// Resolutions are kept for their TTL, within the bounds set by `config`,
+    /// and failed ones for a short while, so hosts requested repeatedly are
+    /// only looked up once in a while. Use [`Client::flush_dns_cache`] and
+    /// [`Client::evict_dns_cache`] to forget them earlier.
+    ///
+    /// The cache sits in front of any resolver, including a custom one set
+    /// with `dns_resolver`, but behind the overrides set with `resolve`.
+    pub fn dns_cache(mut self, config: DnsCacheConfig) -> ClientBuilder {
+        self.config.dns_cache = Some(config);
+        self
+    }
+
+    /// Use a custom [`Connector`](transport::Connector) to open connections.
+    ///
+    /// The connector supplies the raw stream for each new connection. rquest
+    /// still performs the TLS handshake, with the configured impersonation
+    /// settings, and speaks HTTP over it. Proxies, DNS resolution and socket
+    /// options configured on this builder do not apply to such connections.
+    pub fn connector<C: transport::Connector + 'static>(
+        mut self,
+        connector: Arc<C>,
+    ) -> ClientBuilder {
+        self.config.connector = Some(connector as _);
+        self
+    }
+
     /// Adds a new Tower [`Layer`](https://docs.rs/tower/latest/tower/trait.Layer.html) to the
     /// base connector [`Service`](https://docs.rs/tower/latest/tower/trait.Service.html) which
     /// is responsible for connection establishment.a
@@ -1288,10 +2678,169 @@ impl Client {
     ///
     /// This method fails whenever the supplied `Url` cannot be parsed.
     pub fn request<U: IntoUrl>(&self, method: Method, url: U) -> RequestBuilder {
-        let req = url.into_url().map(move |url| Request::new(method, url));
+        let inner = self.inner.load();
+        let req = resolve_url(inner.base_url.as_ref(), url).map(move |(mut url, scope_id)| {
+            let same_origin = inner
+                .base_url
+                .as_ref()
+                .map_or(true, |(base, _)| base.origin() == url.origin());
+            if same_origin && !inner.default_query.is_empty() {
+                let defaults = url::form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(&inner.default_query)
+                    .finish();
+                let query = match url.query() {
+                    Some(query) if !query.is_empty() => format!("{defaults}&{query}"),
+                    _ => defaults,
+                };
+                url.set_query(Some(&query));
+            }
+
+            let mut req = Request::new(method, url);
+            req.network_scheme_mut().scope_id(scope_id);
+            req
+        });
         RequestBuilder::new(self.clone(), req)
     }
 
+    /// Convenience method to make a `GET` request to the URL expanded from
+    /// a URI template ([RFC 6570]) with `params`.
+    ///
+    /// `params` is a struct or map, whose fields are the variables of the
+    /// template: sequences are lists, nested structs and maps are
+    /// associative arrays, and other values are strings. Each expression
+    /// is encoded as its operator tells, so the values never need escaping.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn run() -> Result<(), rquest::Error> {
+    /// #[derive(serde::Serialize)]
+    /// struct Params<'a> {
+    ///     id: u64,
+    ///     page: u32,
+    ///     tags: &'a [&'a str],
+    /// }
+    ///
+    /// let req = rquest::Client::new()
+    ///     .get_templated(
+    ///         "https://api.example.com/users/{id}/posts{?page,tags*}",
+    ///         &Params { id: 42, page: 2, tags: &["a b", "c"] },
+    ///     )
+    ///     .build()?;
+    /// assert_eq!(
+    ///     req.url().as_str(),
+    ///     "https://api.example.com/users/42/posts?page=2&tags=a%20b&tags=c"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever the template is malformed, or its
+    /// expansion cannot be parsed as a `Url`.
+    ///
+    /// [RFC 6570]: https://datatracker.ietf.org/doc/html/rfc6570
+    pub fn get_templated<T: Serialize + ?Sized>(
+        &self,
+        template: &str,
+        params: &T,
+    ) -> RequestBuilder {
+        self.request_templated(Method::GET, template, params)
+    }
+
+    /// Start building a `Request` with a method given as a token, such as
+    /// an extension method like `PURGE` or `PROPFIND`.
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let res = rquest::Client::new()
+    ///     .request_raw("PURGE", "http://cache.example.com/assets/app.js")
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This method fails if `method` is not a valid method token, or
+    /// whenever the supplied `Url` cannot be parsed.
+    pub fn request_raw<U: IntoUrl>(&self, method: &str, url: U) -> RequestBuilder {
+        match Method::from_bytes(method.as_bytes()) {
+            Ok(method) => self.request(method, url),
+            Err(err) => RequestBuilder::new(self.clone(), Err(error::builder(err))),
+        }
+    }
+
+    /// Start building a `Request` with the `Method` and the URL expanded
+    /// from a URI template with `params`.
+    ///
+    /// See [`Client::get_templated`] for the expansion.
+    pub fn request_templated<T: Serialize + ?Sized>(
+        &self,
+        method: Method,
+        template: &str,
+        params: &T,
+    ) -> RequestBuilder {
+        match super::uri_template::expand(template, params) {
+            Ok(url) => self.request(method, url),
+            Err(err) => RequestBuilder::new(self.clone(), Err(error::builder(err))),
+        }
+    }
+
+    /// Open a raw tunnel to `host` on `port`, to speak another protocol over
+    /// it.
+    ///
+    /// The tunnel goes through the proxy the client would use for an `http`
+    /// URL to the same host: an HTTP proxy is sent a `CONNECT` request, and a
+    /// SOCKS proxy is asked to connect. Without a proxy, the tunnel is the
+    /// connection to the host itself. Nothing is sent over the tunnel, and
+    /// it is never pooled.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    ///
+    /// let client = rquest::Client::builder()
+    ///     .proxy(rquest::Proxy::all("http://proxy.example.com:8080")?)
+    ///     .build()?;
+    /// let mut tunnel = client.tunnel("smtp.example.com", 25).await?;
+    /// let mut greeting = [0; 512];
+    /// let n = tunnel.read(&mut greeting).await?;
+    /// tunnel.write_all(b"QUIT\r\n").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the host is not valid, or the tunnel cannot be
+    /// opened, such as when the proxy refuses to.
+    pub async fn tunnel(&self, host: &str, port: u16) -> crate::Result<Upgraded> {
+        let authority = if host.contains(':') && !host.starts_with('[') {
+            format!("[{host}]:{port}")
+        } else {
+            format!("{host}:{port}")
+        };
+        let url = Url::parse(&format!("http://{authority}")).map_err(error::builder)?;
+        let mut uri = try_uri(&url).ok_or_else(|| error::url_bad_uri(url.clone()))?;
+
+        let client = self.inner.load_full();
+        let network_scheme = client.network_scheme(&uri, NetworkScheme::default());
+        let mut dst = Dst::new(&mut uri, false, network_scheme, None)
+            .map_err(|err| error::request(err).with_url(url.clone()))?;
+        dst.set_tunnel();
+
+        let connector = (*client.hyper).clone();
+        let conn = tower::ServiceExt::oneshot(connector, dst)
+            .await
+            .map_err(|err| error::request(err).with_url(url))?;
+        Ok(Upgraded::from(conn))
+    }
+
     /// Executes a `Request`.
     ///
     /// A `Request` can be built manually with `Request::new()` or obtained
@@ -1308,26 +2857,273 @@ impl Client {
         self.execute_request(request)
     }
 
-    pub(super) fn execute_request(&self, req: Request) -> Pending {
+    /// Sends a request with the semantics of the `fetch()` of browsers.
+    ///
+    /// See [`FetchRequest`] for what each option does.
+    ///
+    /// # Errors
+    ///
+    /// Besides the errors of [`execute`](Client::execute), this method fails
+    /// on cross-origin requests its mode forbids, on redirects in
+    /// [`RequestRedirect::Error`] mode, and on cross-origin responses
+    /// failing the CORS check.
+    ///
+    /// [`RequestRedirect::Error`]: crate::RequestRedirect::Error
+    pub async fn fetch(&self, request: FetchRequest) -> Result<Response, Error> {
+        super::fetch::fetch(self, request).await
+    }
+
+    /// Opens a [`Page`], sending requests from the documents it loads like a
+    /// browser tab.
+    pub fn page(&self) -> Page {
+        Page::new(self.clone())
+    }
+
+    /// Returns the HTTPS record found for `host` while resolving it, if it
+    /// is still fresh.
+    ///
+    /// This is always `None` unless enabled with [`ClientBuilder::https_rr`].
+    pub fn https_record(&self, host: &str) -> Option<HttpsRecord> {
+        self.inner
+            .load()
+            .https_records
+            .as_ref()
+            .and_then(|records| records.get(host))
+            .map(|record| (*record).clone())
+    }
+
+    /// Forgets every resolution in the client's DNS cache.
+    ///
+    /// This does nothing unless the cache is enabled with
+    /// [`ClientBuilder::dns_cache`]. Pooled connections are kept.
+    pub fn flush_dns_cache(&self) {
+        if let Some(ref dns_cache) = self.inner.load().dns_cache {
+            dns_cache.flush();
+        }
+    }
+
+    /// Forgets the resolution of `host` in the client's DNS cache, so the
+    /// next connection to it looks it up again.
+    ///
+    /// This does nothing unless the cache is enabled with
+    /// [`ClientBuilder::dns_cache`].
+    pub fn evict_dns_cache(&self, host: &str) {
+        if let Some(ref dns_cache) = self.inner.load().dns_cache {
+            dns_cache.evict(host);
+        }
+    }
+
+    #[cfg(feature = "multipart")]
+    pub(super) fn multipart_style(&self) -> MultipartStyle {
+        self.inner.load().multipart_style
+    }
+
+    #[cfg(feature = "multipart")]
+    pub(super) fn deterministic(&self) -> Option<Arc<SeededRandom>> {
+        self.inner.load().deterministic.clone()
+    }
+
+    pub(super) fn validators(&self) -> ValidatorCache {
+        self.inner.load().validators.clone()
+    }
+
+    pub(super) fn query_style(&self) -> Option<QueryStyle> {
+        self.inner.load().query_style
+    }
+
+    /// Render `req` as a curl command, with the defaults of the client.
+    pub(super) fn curl(&self, req: &Request) -> String {
+        if let Some(client) = self.inner.load().host_client(req.url()) {
+            return client.curl(req);
+        }
+
+        let client = self.inner.load();
+
+        let mut headers = req.headers().clone();
+        client.insert_default_headers(&mut headers, req.url().host_str());
+
+        #[cfg(feature = "cookies")]
+        if let Some(cookie_store) = req.cookie_store().or(client.cookie_store.as_ref()) {
+            if headers.get(crate::header::COOKIE).is_none() {
+                add_cookie_header(&mut headers, &**cookie_store, req.url());
+            }
+        }
+
+        if let Some(accept_encoding) = client.accepts.as_str() {
+            if !headers.contains_key(ACCEPT_ENCODING) && !headers.contains_key(RANGE) {
+                headers.insert(ACCEPT_ENCODING, HeaderValue::from_static(accept_encoding));
+            }
+        }
+
+        let network_scheme = try_uri(req.url()).map(|uri| {
+            client.proxy_auth(&uri, &mut headers);
+            client.network_scheme(&uri, req.network_scheme().clone().build())
+        });
+
+        if let Some(ref order) = client.headers_order {
+            util::client::sort_headers(&mut headers, order);
+        }
+
+        super::curl::command(
+            req,
+            &headers,
+            network_scheme
+                .as_ref()
+                .and_then(NetworkScheme::proxy_scheme),
+        )
+    }
+
+    /// The provider to authorize `req` with, unless it is already authorized
+    /// or goes to another origin than the base URL, or than the first
+    /// request without one.
+    fn auth_for(&self, req: &Request) -> Option<AuthProvider> {
+        let inner = self.inner.load();
+        if req.headers().contains_key(AUTHORIZATION) {
+            return None;
+        }
+        let origin = req.url().origin();
+        if let Some((_, provider)) = inner.host_auth.iter().find(|(scope, _)| *scope == origin) {
+            return Some(provider.clone());
+        }
+        let (provider, first_origin) = inner.auth.as_ref()?;
+        let scope = match inner.base_url {
+            Some((ref base, _)) => base.origin(),
+            None => first_origin.get_or_init(|| origin.clone()).clone(),
+        };
+        (scope == origin).then(|| provider.clone())
+    }
+
+    pub(super) fn execute_request(&self, mut req: Request) -> Pending {
+        if let Some(client) = self.inner.load().host_client(req.url()) {
+            return client.execute_request(req);
+        }
+
+        if let Some(handle) = req.take_abort_handle() {
+            let url = req.url().clone();
+            return Pending::boxed(super::abort::send(handle, url, self.execute_request(req)));
+        }
+
+        #[cfg(feature = "ntlm")]
+        if let Some(ntlm) = req.take_ntlm_auth() {
+            return Pending::boxed(super::ntlm::send(self.clone(), ntlm, req));
+        }
+
+        #[cfg(feature = "negotiate")]
+        if req.take_negotiate_auth() {
+            return Pending::boxed(super::negotiate::send(self.clone(), req));
+        }
+
+        if let Some(digest) = req.take_digest_auth() {
+            return Pending::boxed(super::digest::send(self.clone(), digest, req));
+        }
+
+        if let Some(provider) = self.auth_for(&req) {
+            return Pending::boxed(super::auth::send(self.clone(), provider, req));
+        }
+
+        if let Some(cache) = self.inner.load().http_cache.clone() {
+            return Pending::boxed(cache.send(self.clone(), req));
+        }
+
+        self.send_or_replay(req)
+    }
+
+    /// Replay the response of `req` from the cassette, or send it.
+    pub(crate) fn send_or_replay(&self, req: Request) -> Pending {
+        #[cfg(feature = "json")]
+        if let Some(cassette) = self.inner.load().cassette.clone() {
+            return Pending::boxed(cassette.play(self.clone(), req));
+        }
+
+        self.send_request(req)
+    }
+
+    /// Send `req` on the network, past the hooks of `execute_request`.
+    pub(super) fn send_request(&self, mut req: Request) -> Pending {
+        let client = self.inner.load();
+
+        if let Some(ref mock) = client.mock {
+            return match mock.respond(&req) {
+                Ok(res) => Pending::boxed(std::future::ready(Ok(res))),
+                Err(err) => Pending::new_err(err),
+            };
+        }
+
+        if let Some(delay) = client
+            .pacer
+            .as_ref()
+            .and_then(|pacer| pacer.delay(req.url()))
+        {
+            return Pending::boxed(super::pacing::send(self.clone(), delay, req));
+        }
+
+        // Compress the body first, so it is final for the steps below
+        if let Some(encoding) = req.take_compress_body() {
+            let client = self.clone();
+            return Pending::boxed(async move {
+                if let Some(body) = req.body_mut().take() {
+                    *req.body_mut() = Some(compression::compress(body, encoding).await);
+                    req.headers_mut().remove(CONTENT_LENGTH);
+                    req.headers_mut().insert(
+                        CONTENT_ENCODING,
+                        HeaderValue::from_static(encoding.as_str()),
+                    );
+                }
+                client.send_request(req).await
+            });
+        }
+
+        // Wait for the PAC script off the runtime, so the proxy is then
+        // picked from its decision at once
+        #[cfg(feature = "pac")]
+        if req.url().has_host() {
+            if let Some((script, url)) = client
+                .proxies
+                .inner
+                .iter()
+                .find_map(|proxy| proxy.pending_pac(req.url()))
+            {
+                let script = script.clone();
+                let client = self.clone();
+                return Pending::boxed(async move {
+                    script.prefetch(&url).await;
+                    client.send_request(req).await
+                });
+            }
+        }
+
+        let signer = req.signer().cloned();
+        let fresh_connection = req.fresh_connection().then_some(FreshConnection);
+
         let (
             method,
-            url,
+            mut url,
             mut headers,
             body,
             timeout,
             read_timeout,
+            download_throttle,
+            upload_throttle,
+            download_progress,
+            upload_progress,
+            max_response_size,
+            decompress,
             version,
             redirect,
             _cookie_store,
             network_scheme,
             protocal,
+            raw_path_and_query,
+            request_target,
         ) = req.pieces();
 
         if url.scheme() != "http" && url.scheme() != "https" {
             return Pending::new_err(error::url_bad_scheme(url));
         }
 
-        let client = self.inner.load();
+        if let Some(ref hsts) = client.hsts {
+            hsts.upgrade(&mut url);
+        }
 
         // check if we're in https_only mode and check the scheme of the current URL
         if client.https_only && url.scheme() != "https" {
@@ -1336,10 +3132,10 @@ impl Client {
 
         // insert default headers in the request headers
         // without overwriting already appended headers.
-        for (key, value) in client.headers.iter() {
-            if let Entry::Vacant(entry) = headers.entry(key) {
-                entry.insert(value.clone());
-            }
+        client.insert_default_headers(&mut headers, url.host_str());
+
+        if let Some(ref hints) = client.client_hints {
+            hints.add(&url, &mut headers);
         }
 
         #[cfg(feature = "cookies")]
@@ -1363,11 +3159,25 @@ impl Client {
             }
         }
 
-        let uri = match try_uri(&url) {
+        let uri = match try_uri_with_path_and_query(&url, raw_path_and_query.as_ref()) {
             Some(uri) => uri,
             None => return Pending::new_err(error::url_bad_uri(url)),
         };
 
+        // Sign the request once its headers and body are final
+        let signer = signer.or_else(|| client.signer.clone());
+        let mut signature = Vec::new();
+        if let Some(ref signer) = signer {
+            let body = match body {
+                Some(ref body) => body.as_bytes(),
+                None => Some(&[][..]),
+            };
+            match sign(&**signer, &method, &url, &uri, &mut headers, body) {
+                Ok(names) => signature = names,
+                Err(err) => return Pending::new_err(err.with_url(url)),
+            }
+        }
+
         let (reusable, body) = match body {
             Some(body) => {
                 let (reusable, body) = body.try_reuse();
@@ -1376,11 +3186,78 @@ impl Client {
             None => (None, Body::empty()),
         };
 
+        // The caps of the client are shared by all its requests
+        let upload_throttles = client
+            .upload_throttle
+            .iter()
+            .chain(&upload_throttle)
+            .cloned()
+            .collect::<Vec<_>>();
+        let download_throttles = client
+            .download_throttle
+            .iter()
+            .chain(&download_throttle)
+            .cloned()
+            .collect::<Vec<_>>();
+        let body = progress::upload(
+            throttle::upload(body, &upload_throttles),
+            upload_progress.as_ref(),
+        );
+        let transfer = Transfer::new(&client.stats);
+
         client.proxy_auth(&uri, &mut headers);
 
+        // A proxy set on the request has its own credentials
+        let proxy_credentials = if network_scheme.proxy_scheme().is_none() {
+            client.proxy_credentials(&uri)
+        } else {
+            None
+        };
+
         let network_scheme = client.network_scheme(&uri, network_scheme);
+        let proxy_pool = client.proxy_pool(&network_scheme);
+
+        // Only an HTTP proxy reads its credentials, a SOCKS one would pass
+        // them on to the origin
+        let proxy_credentials = proxy_credentials.filter(|_| {
+            network_scheme
+                .proxy_scheme()
+                .is_some_and(ProxyScheme::is_http)
+        });
+
+        proxy_headers(&mut headers, &uri, &network_scheme);
+
+        // The credentials of a pool depend on the proxy it picked
+        if proxy_pool.is_some()
+            && uri.scheme() == Some(&Scheme::HTTP)
+            && !headers.contains_key(PROXY_AUTHORIZATION)
+        {
+            if let Some(auth) = network_scheme
+                .proxy_scheme()
+                .and_then(ProxyScheme::maybe_http_auth)
+            {
+                headers.insert(PROXY_AUTHORIZATION, auth.clone());
+            }
+        }
 
-        let in_flight = {
+        // Fetch the proxy credentials first, if there are none yet or they
+        // expired, unless the body could not be sent once they are here
+        let fetch_credentials = proxy_credentials
+            .as_ref()
+            .filter(|credentials| credentials.current().is_none())
+            .filter(|_| !matches!(reusable, Some(None)));
+
+        // Order the headers once, with the `Content-Length` the request
+        // would add, so the copy kept for redirects, retries and HAR is in
+        // order too, and the requests built from it need no sorting
+        if let Some(order) = client.headers_order.as_deref() {
+            util::client::add_content_length_header(&mut headers, &body);
+            util::client::sort_headers(&mut headers, order);
+        }
+
+        let in_flight = if let Some(credentials) = fetch_credentials {
+            ResponseFuture::ProxyCredentials(credentials.refresh(None))
+        } else {
             let res = InnerRequest::builder()
                 .uri(uri)
                 .method(method.clone())
@@ -1389,10 +3266,16 @@ impl Client {
                 .headers_order(client.headers_order.as_deref())
                 .network_scheme(network_scheme.clone())
                 .extension(protocal)
-                .body(body);
+                .extension(request_target)
+                .extension(fresh_connection)
+                .extension(client.alternative(&url))
+                .body(stats::upload(body, &transfer));
 
             match res {
-                Ok(req) => ResponseFuture::Default(client.hyper.request(req)),
+                Ok(req) => {
+                    transfer.sent(&req);
+                    ResponseFuture::Default(client.hyper.request(req))
+                }
                 Err(err) => return Pending::new_err(error::builder(err)),
             }
         };
@@ -1406,12 +3289,16 @@ impl Client {
 
         let read_timeout_fut = read_timeout.map(tokio::time::sleep).map(Box::pin);
 
+        let max_response_size = max_response_size.or(client.max_response_size);
+
         Pending {
             inner: PendingInner::Request(PendingRequest {
                 method,
                 url,
                 headers,
                 body: reusable,
+                signer,
+                signature,
                 version,
                 urls: Vec::new(),
                 retry_count: 0,
@@ -1419,11 +3306,26 @@ impl Client {
                 redirect,
                 cookie_store: _cookie_store,
                 network_scheme,
+                proxy_pool,
+                proxy_credentials,
+                proxy_auth_retried: false,
+                raw_path_and_query,
+                request_target,
+                fresh_connection,
+                history: Vec::new(),
+                sent: (SystemTime::now(), Instant::now()),
                 client,
                 in_flight,
                 total_timeout,
                 read_timeout_fut,
                 read_timeout,
+                upload_throttles,
+                download_throttles,
+                upload_progress,
+                download_progress,
+                max_response_size,
+                decompress,
+                transfer,
             }),
         }
     }
@@ -1478,6 +3380,56 @@ impl Client {
         }
     }
 
+    /// Export the state the client built up, to resume it in another client
+    /// with [`ClientBuilder::import_session`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "json")]
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = rquest::Client::builder().cookie_store(true).build()?;
+    /// client.get("https://example.com").send().await?;
+    /// std::fs::write("session.json", serde_json::to_vec(&client.export_session())?)?;
+    ///
+    /// // later, in another process
+    /// let state = serde_json::from_slice(&std::fs::read("session.json")?)?;
+    /// let client = rquest::Client::builder().import_session(state).build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn export_session(&self) -> SessionState {
+        let inner = self.inner.load();
+        let mut state = SessionState::default();
+        state.save_tls_sessions(inner.hyper.connector());
+        #[cfg(feature = "cookies")]
+        if let Some(ref cookie_store) = inner.cookie_store {
+            state.save_cookies(&**cookie_store);
+        }
+        state
+    }
+
+    /// Returns the bytes transferred by the requests of this client.
+    ///
+    /// The clients made from this one with [`Client::cloned`] or
+    /// [`Client::with`] share its counters.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let client = rquest::Client::new();
+    /// client.get("https://example.com").send().await?.bytes().await?;
+    ///
+    /// let stats = client.stats();
+    /// println!("{} requests, {} bytes received", stats.requests(), stats.received_bytes());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stats(&self) -> TransferStats {
+        self.inner.load().stats.clone()
+    }
+
     /// Clones the `Client` into a new instance.
     ///
     /// This method creates a new instance of the `Client` by cloning its internal state.
@@ -1489,13 +3441,43 @@ impl Client {
     ///
     /// ```rust
     /// let client = rquest::Client::new();
-    /// let cloned_client = client.cloned();
-    /// // Use the cloned client independently
+    /// let cloned_client = client.cloned();
+    /// // Use the cloned client independently
+    /// ```
+    pub fn cloned(&self) -> Self {
+        Self {
+            inner: Arc::new(ArcSwap::from_pointee((**self.inner.load()).clone())),
+        }
+    }
+
+    /// Creates a new `Client` with some settings changed by `f`.
+    ///
+    /// The new client shares the connection pool and TLS context of this
+    /// one, which is much cheaper than building another client for each
+    /// variation. Changing its settings later does not affect this client.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if a setting changed by `f` can not be applied.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// # fn doc() -> Result<(), rquest::Error> {
+    /// let client = rquest::Client::new();
+    /// let impatient = client.with(|c| c.timeout(Duration::from_secs(1)))?;
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn cloned(&self) -> Self {
-        Self {
-            inner: Arc::new(ArcSwap::from_pointee((**self.inner.load()).clone())),
-        }
+    pub fn with<F>(&self, f: F) -> Result<Client, Error>
+    where
+        F: for<'c> FnOnce(ClientMut<'c>) -> ClientMut<'c>,
+    {
+        let client = self.cloned();
+        f(client.as_mut()).apply()?;
+        Ok(client)
     }
 }
 
@@ -1549,6 +3531,32 @@ impl Proxies {
     }
 }
 
+/// Resolves `url` against `base`, if it is relative, keeping the IPv6 scope
+/// ID of the base while the host stays the same.
+fn resolve_url<U: IntoUrl>(
+    base: Option<&(Url, Option<u32>)>,
+    url: U,
+) -> crate::Result<(Url, Option<u32>)> {
+    if let Some((base, base_scope_id)) = base {
+        if let Err(url::ParseError::RelativeUrlWithoutBase) = Url::parse(url.as_str()) {
+            let url = base.join(url.as_str()).map_err(crate::error::builder)?;
+            let scope_id = (*base_scope_id).filter(|_| url.host() == base.host());
+            return crate::into_url::IntoUrlSealed::into_url(url).map(|url| (url, scope_id));
+        }
+    }
+    into_url_with_scope_id(url)
+}
+
+fn into_base_url<U: IntoUrl>(url: U) -> crate::Result<(Url, Option<u32>)> {
+    let (mut url, scope_id) = into_url_with_scope_id(url)?;
+    // without it, joining would replace the last segment
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+    Ok((url, scope_id))
+}
+
 #[derive(Clone)]
 struct ClientInner {
     accepts: Accepts,
@@ -1556,19 +3564,187 @@ struct ClientInner {
     cookie_store: Option<Arc<dyn cookie::CookieStore>>,
     headers: HeaderMap,
     headers_order: Option<Cow<'static, [HeaderName]>>,
+    #[cfg(feature = "multipart")]
+    multipart_style: MultipartStyle,
+    query_style: Option<QueryStyle>,
+    base_url: Option<(Url, Option<u32>)>,
+    default_query: Vec<(String, String)>,
+    /// The default provider, and the origin it is scoped to without a base
+    /// URL, that of the first request.
+    auth: Option<(AuthProvider, Arc<OnceLock<url::Origin>>)>,
+    host_auth: Vec<(url::Origin, AuthProvider)>,
+    host_headers: Vec<(String, HeaderMap)>,
+    host_clients: Vec<(String, Client)>,
+    #[cfg(feature = "json")]
+    har: Option<HarRecorder>,
+    #[cfg(feature = "json")]
+    cassette: Option<Cassette>,
+    http_cache: Option<HttpCache>,
+    mock: Option<MockTransport>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    validators: ValidatorCache,
+    deterministic: Option<Arc<SeededRandom>>,
+    lenient_http1: bool,
     hyper: HyperClient<Connector, super::Body>,
     redirect: redirect::Policy,
     redirect_with_proxy_auth: bool,
+    redirect_preserve_location: bool,
     referer: bool,
     request_timeout: Option<Duration>,
     read_timeout: Option<Duration>,
+    download_throttle: Option<Throttle>,
+    upload_throttle: Option<Throttle>,
+    max_response_size: Option<u64>,
+    max_decompression_ratio: Option<u32>,
     https_only: bool,
+    hsts: Option<Arc<Hsts>>,
+    alt_svc: Option<Arc<AltSvcCache>>,
+    client_hints: Option<Arc<HintStore>>,
+    pacer: Option<Arc<Pacer>>,
+    stats: TransferStats,
     http2_max_retry_count: usize,
     proxies: Proxies,
     network_scheme: NetworkSchemeBuilder,
+    dns_cache: Option<Arc<DnsCache>>,
+    https_records: Option<Arc<HttpsRecords>>,
+    /// The client sending DoH queries, kept alive by the clients resolving
+    /// names with it.
+    doh_client: Option<Client>,
 }
 
 impl ClientInner {
+    /// A copy with its own pool, and without proxies, cookies, default
+    /// headers, authentication or anything else added to requests.
+    fn bare(&self) -> ClientInner {
+        let mut hyper = self.hyper.clone();
+        hyper.fork_pool();
+        ClientInner {
+            #[cfg(feature = "cookies")]
+            cookie_store: None,
+            headers: HeaderMap::new(),
+            base_url: None,
+            default_query: Vec::new(),
+            auth: None,
+            host_auth: Vec::new(),
+            host_headers: Vec::new(),
+            host_clients: Vec::new(),
+            #[cfg(feature = "json")]
+            har: None,
+            #[cfg(feature = "json")]
+            cassette: None,
+            http_cache: None,
+            mock: None,
+            signer: None,
+            hyper,
+            download_throttle: None,
+            upload_throttle: None,
+            client_hints: None,
+            pacer: None,
+            proxies: Proxies::default(),
+            doh_client: None,
+            ..self.clone()
+        }
+    }
+
+    /// The headers of the patterns matching `host`, first added first.
+    fn host_headers<'a>(&'a self, host: Option<&'a str>) -> impl Iterator<Item = &'a HeaderMap> {
+        self.host_headers
+            .iter()
+            .filter(move |(pattern, _)| host.is_some_and(|host| util::host_matches(pattern, host)))
+            .map(|(_, headers)| headers)
+    }
+
+    /// Insert the headers of `host`, then the default headers, without
+    /// overwriting those already set.
+    fn insert_default_headers(&self, headers: &mut HeaderMap, host: Option<&str>) {
+        let defaults = self.host_headers(host).chain(Some(&self.headers));
+        for (key, value) in defaults.flat_map(HeaderMap::iter) {
+            if let Entry::Vacant(entry) = headers.entry(key) {
+                entry.insert(value.clone());
+            }
+        }
+    }
+
+    /// Swap the headers of the `previous` host for the ones of `next`, on a
+    /// redirect.
+    fn redirect_host_headers(
+        &self,
+        headers: &mut HeaderMap,
+        previous: Option<&str>,
+        next: Option<&str>,
+    ) {
+        if self.host_headers.is_empty() || previous == next {
+            return;
+        }
+        for name in self.host_headers(previous).flat_map(HeaderMap::keys) {
+            headers.remove(name);
+        }
+        let defaults = self.headers.iter().filter(|(name, _)| {
+            // credentials are not carried to another host
+            *name != AUTHORIZATION && *name != COOKIE && *name != PROXY_AUTHORIZATION
+        });
+        for (key, value) in self
+            .host_headers(next)
+            .flat_map(HeaderMap::iter)
+            .chain(defaults)
+        {
+            if let Entry::Vacant(entry) = headers.entry(key) {
+                entry.insert(value.clone());
+            }
+        }
+    }
+
+    /// The alternative service to connect to for `url`, if any.
+    fn alternative(&self, url: &Url) -> Option<Alternative> {
+        self.alt_svc.as_ref()?.endpoint(url).map(Alternative)
+    }
+
+    /// The client impersonating another context toward `url`, if any.
+    fn host_client(&self, url: &Url) -> Option<Client> {
+        let host = url.host_str()?;
+        self.host_clients
+            .iter()
+            .find(|(pattern, _)| util::host_matches(pattern, host))
+            .map(|(_, client)| client.clone())
+    }
+
+    /// Apply the headers, HTTP/1, HTTP/2 and TLS settings of `context`.
+    fn impersonate(&mut self, context: HttpContext) -> crate::Result<()> {
+        if let Some(mut headers) = context.default_headers {
+            std::mem::swap(&mut self.headers, &mut headers);
+        }
+
+        if let Some(headers_order) = context.headers_order {
+            std::mem::swap(&mut self.headers_order, &mut Some(headers_order));
+        }
+
+        #[cfg(feature = "multipart")]
+        if let Some(multipart_style) = context.multipart_style {
+            self.multipart_style = multipart_style;
+        }
+
+        if let Some(http1_config) = context.http1_config {
+            apply_http1_config(self.hyper.http1(), http1_config);
+            if self.lenient_http1 {
+                apply_lenient_http1(self.hyper.http1());
+            }
+        }
+
+        if let Some(http2_config) = context.http2_config {
+            apply_http2_config(self.hyper.http2(), http2_config);
+        }
+
+        let mut tls_config = context.tls_config;
+        if let Some(ref random) = self.deterministic {
+            tls_config.make_deterministic(random.seed());
+        }
+        let connector = BoringTlsConnector::new(tls_config).map_err(|err| {
+            error::builder(format!("Failed to create BoringTlsConnector: {}", err))
+        })?;
+        self.hyper.connector_mut().set_connector(connector);
+        Ok(())
+    }
+
     #[inline]
     fn proxy_auth(&self, dst: &Uri, headers: &mut HeaderMap) {
         if !self.proxies.maybe_http_auth {
@@ -1595,22 +3771,89 @@ impl ClientInner {
         }
     }
 
+    /// The credentials callback of the proxy `uri` goes through, if any.
     #[inline]
-    fn network_scheme(&self, uri: &Uri, default: NetworkScheme) -> NetworkScheme {
-        if matches!(default, NetworkScheme::Default) {
-            let mut builder = self.network_scheme.clone();
+    fn proxy_credentials(&self, uri: &Uri) -> Option<CredentialsProvider> {
+        let proxies = &self.proxies.inner;
+        if proxies.iter().all(|proxy| proxy.credentials().is_none()) {
+            return None;
+        }
+
+        proxies
+            .iter()
+            .find(|proxy| proxy.intercepts(uri))
+            .and_then(|proxy| proxy.credentials_for(uri))
+            .cloned()
+    }
+
+    /// The pool the proxy of `network_scheme` was picked from, if any.
+    #[inline]
+    fn proxy_pool(&self, network_scheme: &NetworkScheme) -> Option<ProxyPool> {
+        let proxy_scheme = network_scheme.proxy_scheme()?;
+        self.proxies
+            .inner
+            .iter()
+            .find_map(|proxy| proxy.pool_of(proxy_scheme))
+            .cloned()
+    }
+
+    /// Layers the request's network settings over the client's.
+    ///
+    /// Any setting the request leaves unset falls back to the client, so a
+    /// request that only picks a local address still goes through the
+    /// client's proxies and interface.
+    #[inline]
+    fn network_scheme(&self, uri: &Uri, mut request: NetworkScheme) -> NetworkScheme {
+        let mut builder = self.network_scheme.clone();
 
-            // iterate over the client's proxies and use the first valid one
-            for proxy in self.proxies.inner.iter() {
-                if let Some(proxy_scheme) = proxy.intercept(uri) {
+        match request.take_proxy_scheme() {
+            Some(proxy_scheme) => {
+                builder.proxy_scheme(proxy_scheme);
+            }
+            None => {
+                // the last matching proxy wins; searching from the end asks
+                // only the proxies up to it, so a pool picks once per request
+                if let Some(proxy_scheme) = self
+                    .proxies
+                    .inner
+                    .iter()
+                    .rev()
+                    .find_map(|proxy| proxy.intercept(uri))
+                {
                     builder.proxy_scheme(proxy_scheme);
                 }
             }
+        }
+
+        match request.take_addresses() {
+            (None, None) => (),
+            (ipv4, ipv6) => {
+                builder.addresses(ipv4, ipv6);
+            }
+        }
 
-            return builder.build();
+        #[cfg(any(
+            target_os = "android",
+            target_os = "fuchsia",
+            target_os = "linux",
+            all(
+                feature = "apple-bindable-device",
+                any(
+                    target_os = "ios",
+                    target_os = "visionos",
+                    target_os = "macos",
+                    target_os = "tvos",
+                    target_os = "watchos",
+                )
+            )
+        ))]
+        if let Some(interface) = request.take_interface() {
+            builder.interface(interface);
         }
 
-        default
+        builder.scope_id(request.take_scope_id());
+        builder.resolve_to(request.take_resolve_to());
+        builder.build()
     }
 }
 
@@ -1621,6 +3864,7 @@ impl_debug!(ClientInner,{
     hyper,
     redirect,
     redirect_with_proxy_auth,
+    redirect_preserve_location,
     referer,
     request_timeout,
     read_timeout,
@@ -1769,6 +4013,83 @@ impl<'c> ClientMut<'c> {
         self
     }
 
+    /// Sets the URL that relative request URLs are resolved against.
+    ///
+    /// See [`ClientBuilder::base_url`].
+    pub fn base_url<U: IntoUrl>(mut self, url: U) -> ClientMut<'c> {
+        match into_base_url(url) {
+            Ok(base) => self.inner_ref.base_url = Some(base),
+            Err(err) => self.error = Some(err),
+        }
+        self
+    }
+
+    /// Sets the provider of the `Authorization` header of every request.
+    ///
+    /// See [`ClientBuilder::default_auth`].
+    pub fn default_auth(mut self, provider: AuthProvider) -> ClientMut<'c> {
+        self.inner_ref.auth = Some((provider, Arc::default()));
+        self
+    }
+
+    /// Sets the timeout of every request, or removes it with `None`.
+    ///
+    /// See [`ClientBuilder::timeout`].
+    pub fn timeout<T>(mut self, timeout: T) -> ClientMut<'c>
+    where
+        T: Into<Option<Duration>>,
+    {
+        self.inner_ref.request_timeout = timeout.into();
+        self
+    }
+
+    /// Sets the read timeout of every request, or removes it with `None`.
+    ///
+    /// See [`ClientBuilder::read_timeout`].
+    pub fn read_timeout<T>(mut self, timeout: T) -> ClientMut<'c>
+    where
+        T: Into<Option<Duration>>,
+    {
+        self.inner_ref.read_timeout = timeout.into();
+        self
+    }
+
+    /// Sets the largest response body to read, or removes the limit with
+    /// `None`.
+    ///
+    /// See [`ClientBuilder::max_response_size`].
+    pub fn max_response_size<T>(mut self, bytes: T) -> ClientMut<'c>
+    where
+        T: Into<Option<u64>>,
+    {
+        self.inner_ref.max_response_size = bytes.into();
+        self
+    }
+
+    /// Sets the redirect policy.
+    ///
+    /// See [`ClientBuilder::redirect`].
+    pub fn redirect(mut self, policy: redirect::Policy) -> ClientMut<'c> {
+        self.inner_ref.redirect = policy;
+        self
+    }
+
+    /// Enables or disables automatic setting of the `Referer` header.
+    ///
+    /// See [`ClientBuilder::referer`].
+    pub fn referer(mut self, enable: bool) -> ClientMut<'c> {
+        self.inner_ref.referer = enable;
+        self
+    }
+
+    /// Restricts the client to HTTPS requests.
+    ///
+    /// See [`ClientBuilder::https_only`].
+    pub fn https_only(mut self, enabled: bool) -> ClientMut<'c> {
+        self.inner_ref.https_only = enabled;
+        self
+    }
+
     /// Set the cookie provider for this client.
     #[cfg(feature = "cookies")]
     pub fn cookie_provider<C>(mut self, cookie_store: Arc<C>) -> ClientMut<'c>
@@ -1892,39 +4213,9 @@ impl<'c> ClientMut<'c> {
     where
         P: HttpContextProvider,
     {
-        let context = provider.context();
-
-        if let Some(mut headers) = context.default_headers {
-            std::mem::swap(&mut self.inner_ref.headers, &mut headers);
-        }
-
-        if let Some(headers_order) = context.headers_order {
-            std::mem::swap(&mut self.inner_ref.headers_order, &mut Some(headers_order));
+        if let Err(err) = self.inner_ref.impersonate(provider.context()) {
+            self.error = Some(err);
         }
-
-        if let Some(http1_config) = context.http1_config {
-            apply_http1_config(self.inner_ref.hyper.http1(), http1_config);
-        }
-
-        if let Some(http2_config) = context.http2_config {
-            apply_http2_config(self.inner_ref.hyper.http2(), http2_config);
-        }
-
-        match BoringTlsConnector::new(context.tls_config) {
-            Ok(connector) => {
-                self.inner_ref
-                    .hyper
-                    .connector_mut()
-                    .set_connector(connector);
-            }
-            Err(err) => {
-                self.error = Some(error::builder(format!(
-                    "Failed to create BoringTlsConnector: {}",
-                    err
-                )))
-            }
-        }
-
         self
     }
 
@@ -1950,6 +4241,7 @@ pin_project! {
 
 enum PendingInner {
     Request(PendingRequest),
+    Boxed(Pin<Box<dyn Future<Output = Result<Response, Error>> + Send>>),
     Error(Option<Error>),
 }
 
@@ -1958,7 +4250,11 @@ pin_project! {
         method: Method,
         url: Url,
         headers: HeaderMap,
-        body: Option<Option<Bytes>>,
+        body: Option<Option<Replay>>,
+        signer: Option<Arc<dyn RequestSigner>>,
+        // The headers the signer set, replaced when the request is signed
+        // again
+        signature: Vec<HeaderName>,
         version: Option<Version>,
         urls: Vec<Url>,
         retry_count: usize,
@@ -1966,6 +4262,14 @@ pin_project! {
         redirect: Option<redirect::Policy>,
         cookie_store: CookieStoreOption,
         network_scheme: NetworkScheme,
+        proxy_pool: Option<ProxyPool>,
+        proxy_credentials: Option<CredentialsProvider>,
+        proxy_auth_retried: bool,
+        raw_path_and_query: Option<PathAndQuery>,
+        request_target: Option<RequestTarget>,
+        fresh_connection: Option<FreshConnection>,
+        history: Vec<redirect::History>,
+        sent: (SystemTime, Instant),
         client: Guard<Arc<ClientInner>>,
         #[pin]
         in_flight: ResponseFuture,
@@ -1974,11 +4278,19 @@ pin_project! {
         #[pin]
         read_timeout_fut: Option<Pin<Box<Sleep>>>,
         read_timeout: Option<Duration>,
+        upload_throttles: Vec<Throttle>,
+        download_throttles: Vec<Throttle>,
+        upload_progress: Option<ProgressCallback>,
+        download_progress: Option<ProgressCallback>,
+        max_response_size: Option<u64>,
+        decompress: bool,
+        transfer: Transfer,
     }
 }
 
 enum ResponseFuture {
     Default(HyperResponseFuture),
+    ProxyCredentials(Pin<Box<dyn Future<Output = Result<HeaderValue, Error>> + Send>>),
 }
 
 impl PendingRequest {
@@ -2010,7 +4322,7 @@ impl PendingRequest {
         trace!("can retry {:?}", err);
 
         let body = match self.body {
-            Some(Some(ref body)) => Body::reusable(body.clone()),
+            Some(Some(ref body)) => body.body(),
             Some(None) => {
                 debug!("error was retryable, but body not reusable");
                 return false;
@@ -2024,7 +4336,106 @@ impl PendingRequest {
         }
         self.retry_count += 1;
 
-        let uri = match try_uri(&self.url) {
+        self.resend(body)
+    }
+
+    /// Fetch new proxy credentials after the proxy refused the old ones,
+    /// once per request.
+    fn refresh_proxy_credentials(mut self: Pin<&mut Self>) -> bool {
+        if self.proxy_auth_retried {
+            return false;
+        }
+
+        if let Some(None) = self.body {
+            debug!("proxy credentials refused, but body not reusable");
+            return false;
+        }
+
+        let refused = self
+            .network_scheme
+            .proxy_scheme()
+            .and_then(ProxyScheme::maybe_http_auth)
+            .cloned();
+        let credentials = match self.proxy_credentials {
+            Some(ref credentials) => credentials.refresh(refused),
+            None => return false,
+        };
+
+        trace!("fetching new proxy credentials");
+        self.proxy_auth_retried = true;
+        *self.as_mut().in_flight().get_mut() = ResponseFuture::ProxyCredentials(credentials);
+        true
+    }
+
+    /// Send the request again with the new proxy credentials.
+    fn resend_with_proxy_auth(mut self: Pin<&mut Self>, header: HeaderValue) -> bool {
+        // Never hand them to a SOCKS proxy, which would forward them to the
+        // origin
+        match self.network_scheme.proxy_scheme_mut() {
+            Some(proxy_scheme) if proxy_scheme.is_http() => {
+                proxy_scheme.replace_http_auth(header.clone());
+            }
+            _ => return false,
+        }
+        // http destinations take them in the request, the others in the
+        // CONNECT request of their tunnel
+        if self.url.scheme() == "http" {
+            self.headers.insert(PROXY_AUTHORIZATION, header);
+        }
+
+        let body = match self.body {
+            Some(Some(ref body)) => body.body(),
+            Some(None) => return false,
+            None => Body::empty(),
+        };
+        self.resend(body)
+    }
+
+    /// Apply the bandwidth caps, progress reports and counters to a body sent
+    /// again.
+    fn upload_body(&self, body: Body) -> Body {
+        stats::upload(
+            progress::upload(
+                throttle::upload(body, &self.upload_throttles),
+                self.upload_progress.as_ref(),
+            ),
+            &self.transfer,
+        )
+    }
+
+    /// Give the response of a new request the whole read timeout.
+    fn reset_read_timeout(mut self: Pin<&mut Self>) {
+        let read_timeout = self.read_timeout;
+        self.as_mut()
+            .read_timeout()
+            .set(read_timeout.map(tokio::time::sleep).map(Box::pin));
+    }
+
+    /// Sign the request again, for its new URL, headers or body, replacing
+    /// the headers of the previous signature.
+    fn sign_again(
+        mut self: Pin<&mut Self>,
+        uri: &Uri,
+        headers: &mut HeaderMap,
+    ) -> Result<(), Error> {
+        let Some(ref signer) = self.signer else {
+            return Ok(());
+        };
+        for name in &self.signature {
+            headers.remove(name);
+        }
+        let body = match self.body {
+            Some(Some(ref body)) => body.as_bytes().map(|body| &body[..]),
+            Some(None) => None,
+            None => Some(&[][..]),
+        };
+        let signature = sign(&**signer, &self.method, &self.url, uri, headers, body)?;
+        self.signature = signature;
+        Ok(())
+    }
+
+    fn resend(mut self: Pin<&mut Self>, body: Body) -> bool {
+        let uri = match try_uri_with_path_and_query(&self.url, self.raw_path_and_query.as_ref()) {
             Some(uri) => uri,
             None => {
                 debug!("a parsed Url should always be a valid Uri: {}", self.url);
@@ -2032,6 +4443,14 @@ impl PendingRequest {
             }
         };
 
+        let mut headers = std::mem::take(self.as_mut().headers());
+        let signed = self.as_mut().sign_again(&uri, &mut headers);
+        *self.as_mut().headers() = headers;
+        if let Err(err) = signed {
+            debug!("signing the request again failed: {}", err);
+            return false;
+        }
+
         *self.as_mut().in_flight().get_mut() = {
             let res = InnerRequest::builder()
                 .uri(uri)
@@ -2040,20 +4459,74 @@ impl PendingRequest {
                 .headers(self.headers.clone())
                 .headers_order(self.client.headers_order.as_deref())
                 .network_scheme(self.network_scheme.clone())
-                .body(body);
+                .extension(self.request_target)
+                .extension(self.fresh_connection)
+                .extension(self.client.alternative(&self.url))
+                .body(self.upload_body(body));
 
             if let Ok(req) = res {
+                self.transfer.sent(&req);
                 ResponseFuture::Default(self.client.hyper.request(req))
             } else {
                 log::trace!("error request build");
                 return false;
             }
         };
+        self.sent = (SystemTime::now(), Instant::now());
+        self.reset_read_timeout();
 
         true
     }
 }
 
+/// Sign a request with `signer`, returning the names of the headers it set.
+fn sign(
+    signer: &dyn RequestSigner,
+    method: &Method,
+    url: &Url,
+    uri: &Uri,
+    headers: &mut HeaderMap,
+    body: Option<&[u8]>,
+) -> Result<Vec<HeaderName>, Error> {
+    let unsigned = headers.clone();
+    signer.sign(&mut SigningRequest {
+        method,
+        url,
+        uri,
+        headers,
+        body,
+    })?;
+    Ok(headers
+        .keys()
+        .filter(|name| !unsigned.get_all(*name).iter().eq(headers.get_all(*name)))
+        .cloned()
+        .collect())
+}
+
+/// Add the extra headers of the proxy to a request it forwards to `uri`, or
+/// remove them from one it tunnels, which the origin would see.
+fn proxy_headers(headers: &mut HeaderMap, uri: &Uri, network_scheme: &NetworkScheme) {
+    let Some(proxy_headers) = network_scheme
+        .proxy_scheme()
+        .and_then(ProxyScheme::connect_headers)
+    else {
+        return;
+    };
+    for (name, value) in proxy_headers {
+        match headers.entry(name) {
+            Entry::Vacant(entry) if uri.scheme() == Some(&Scheme::HTTP) => {
+                entry.insert(value.clone());
+            }
+            Entry::Occupied(entry)
+                if uri.scheme() != Some(&Scheme::HTTP) && entry.get() == value =>
+            {
+                entry.remove();
+            }
+            _ => {}
+        }
+    }
+}
+
 fn is_retryable_error(err: &(dyn std::error::Error + 'static)) -> bool {
     // pop the legacy::Error
     let err = if let Some(err) = err.source() {
@@ -2092,6 +4565,15 @@ impl Pending {
         }
     }
 
+    fn boxed<F>(fut: F) -> Pending
+    where
+        F: Future<Output = Result<Response, Error>> + Send + 'static,
+    {
+        Pending {
+            inner: PendingInner::Boxed(Box::pin(fut)),
+        }
+    }
+
     fn inner(self: Pin<&mut Self>) -> Pin<&mut PendingInner> {
         self.project().inner
     }
@@ -2104,6 +4586,7 @@ impl Future for Pending {
         let inner = self.inner();
         match inner.get_mut() {
             PendingInner::Request(ref mut req) => Pin::new(req).poll(cx),
+            PendingInner::Boxed(ref mut fut) => fut.as_mut().poll(cx),
             PendingInner::Error(ref mut err) => Poll::Ready(Err(err
                 .take()
                 .expect("Pending error polled more than once"))),
@@ -2138,13 +4621,70 @@ impl Future for PendingRequest {
                         if self.as_mut().retry_error(&e) {
                             continue;
                         }
+                        if error::is_proxy_auth_required(&e)
+                            && self.as_mut().refresh_proxy_credentials()
+                        {
+                            continue;
+                        }
+                        if let Some(ref pool) = self.proxy_pool {
+                            if let Some(proxy_scheme) = self.network_scheme.proxy_scheme() {
+                                pool.record_error(proxy_scheme);
+                            }
+                        }
                         return Poll::Ready(Err(error::request(e).with_url(self.url.clone())));
                     }
-                    Poll::Ready(Ok(res)) => res.map(super::body::boxed),
+                    Poll::Ready(Ok(res)) => {
+                        self.transfer.received(&res);
+                        if res.status() == StatusCode::PROXY_AUTHENTICATION_REQUIRED
+                            && self.as_mut().refresh_proxy_credentials()
+                        {
+                            continue;
+                        }
+                        if let Some(ref pool) = self.proxy_pool {
+                            if let Some(proxy_scheme) = self.network_scheme.proxy_scheme() {
+                                pool.record_response(proxy_scheme, res.status());
+                            }
+                        }
+                        res.map(super::body::boxed)
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+                ResponseFuture::ProxyCredentials(f) => match f.as_mut().poll(cx) {
+                    Poll::Ready(Ok(header)) => {
+                        if self.as_mut().resend_with_proxy_auth(header) {
+                            continue;
+                        }
+                        return Poll::Ready(Err(
+                            error::request("request not resendable").with_url(self.url.clone())
+                        ));
+                    }
+                    Poll::Ready(Err(e)) => {
+                        return Poll::Ready(Err(e.with_url(self.url.clone())));
+                    }
                     Poll::Pending => return Poll::Pending,
                 },
             };
 
+            // dropped on redirects, which records them as they are
+            #[cfg(feature = "json")]
+            let recording = self.client.har.as_ref().map(|har| {
+                har.start(
+                    super::har::RequestHead {
+                        sent: self.sent,
+                        method: &self.method,
+                        url: &self.url,
+                        version: self.version,
+                        headers: &self.headers,
+                        body: self
+                            .body
+                            .as_ref()
+                            .and_then(Option::as_ref)
+                            .and_then(Replay::as_bytes),
+                    },
+                    &res,
+                )
+            });
+
             #[cfg(feature = "cookies")]
             let cookie_store = self
                 .cookie_store
@@ -2162,10 +4702,34 @@ impl Future for PendingRequest {
                 }
             }
 
+            if let Some(ref hsts) = self.client.hsts {
+                hsts.observe(&self.url, res.headers());
+            }
+
+            if let Some(ref alt_svc) = self.client.alt_svc {
+                alt_svc.observe(&self.url, res.headers());
+            }
+
+            if let Some(ref hints) = self.client.client_hints {
+                hints.observe(&self.url, res.headers());
+            }
+
+            if let Some(ref pacer) = self.client.pacer {
+                pacer.observe(&self.url, res.status(), res.headers());
+            }
+
             let previous_method = self.method.clone();
 
+            let rewrites_to_get = self
+                .redirect
+                .as_ref()
+                .unwrap_or(&self.client.redirect)
+                .rewrites_to_get(res.status(), &self.method);
+
             let should_redirect = match res.status() {
-                StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND | StatusCode::SEE_OTHER => {
+                StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND | StatusCode::SEE_OTHER
+                    if rewrites_to_get =>
+                {
                     self.body = None;
                     for header in &[
                         TRANSFER_ENCODING,
@@ -2184,22 +4748,29 @@ impl Future for PendingRequest {
                     }
                     true
                 }
-                StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT => {
-                    match self.body {
-                        Some(Some(_)) | None => true,
-                        Some(None) => false,
-                    }
-                }
+                StatusCode::MOVED_PERMANENTLY
+                | StatusCode::FOUND
+                | StatusCode::SEE_OTHER
+                | StatusCode::TEMPORARY_REDIRECT
+                | StatusCode::PERMANENT_REDIRECT => match self.body {
+                    Some(Some(_)) | None => true,
+                    Some(None) => false,
+                },
                 _ => false,
             };
 
             if should_redirect {
-                let loc = res.headers().get(LOCATION).and_then(|val| {
+                let location = res.headers().get(LOCATION).cloned();
+                let mut loc_scope_id = None;
+                let loc = location.as_ref().and_then(|val| {
                     let loc = (|| -> Option<Url> {
                         // Some sites may send a utf-8 Location header,
                         // even though we're supposed to treat those bytes
-                        // as opaque, we'll check specifically for utf8.
-                        self.url.join(str::from_utf8(val.as_bytes()).ok()?).ok()
+                        // as opaque; others send latin-1, which is
+                        // percent-encoded.
+                        let (url, scope_id) = join_with_scope_id(&self.url, &location_str(val))?;
+                        loc_scope_id = scope_id;
+                        Some(url)
                     })();
 
                     // Check that the `url` is also a valid `http::Uri`.
@@ -2218,7 +4789,10 @@ impl Future for PendingRequest {
                     }
                     loc
                 });
-                if let Some(loc) = loc {
+                if let Some(mut loc) = loc {
+                    if let Some(ref hsts) = self.client.hsts {
+                        hsts.upgrade(&mut loc);
+                    }
                     if self.client.referer {
                         if let Some(referer) = make_referer(&loc, &self.url) {
                             self.headers.insert(REFERER, referer);
@@ -2254,7 +4828,26 @@ impl Future for PendingRequest {
                                 )));
                             }
 
-                            self.url = loc;
+                            let location = location.expect("Location header was parsed");
+                            self.raw_path_and_query = if self.client.redirect_preserve_location {
+                                raw_location_path_and_query(&location)
+                            } else {
+                                None
+                            };
+                            let previous = std::mem::replace(&mut self.url, loc);
+
+                            // The zone of the location, or else the one of the
+                            // request while on its host
+                            if loc_scope_id.is_some() || previous.host() != self.url.host() {
+                                self.network_scheme.set_scope_id(loc_scope_id);
+                            }
+
+                            self.history.push(redirect::History::new(
+                                res.status(),
+                                previous,
+                                location,
+                                self.url.clone(),
+                            ));
                             let mut headers =
                                 std::mem::replace(self.as_mut().headers(), HeaderMap::new());
 
@@ -2264,8 +4857,16 @@ impl Future for PendingRequest {
                                 &self.urls,
                                 self.client.redirect_with_proxy_auth,
                             );
+                            self.client.redirect_host_headers(
+                                &mut headers,
+                                self.urls.last().and_then(Url::host_str),
+                                self.url.host_str(),
+                            );
 
-                            let uri = match try_uri(&self.url) {
+                            let uri = match try_uri_with_path_and_query(
+                                &self.url,
+                                self.raw_path_and_query.as_ref(),
+                            ) {
                                 Some(uri) => uri,
                                 None => {
                                     return Poll::Ready(Err(error::url_bad_uri(self.url.clone())));
@@ -2273,7 +4874,7 @@ impl Future for PendingRequest {
                             };
 
                             let body = match self.body {
-                                Some(Some(ref body)) => Body::reusable(body.clone()),
+                                Some(Some(ref body)) => body.body(),
                                 _ => Body::empty(),
                             };
 
@@ -2291,6 +4892,25 @@ impl Future for PendingRequest {
                                 }
                             }
 
+                            // The signature is for the previous URL: sign the
+                            // request again, unless it leaves for another host,
+                            // which gets none, as with credentials
+                            let previous = self.urls.last();
+                            let cross_host = previous.is_some_and(|previous| {
+                                self.url.host_str() != previous.host_str()
+                                    || self.url.port_or_known_default()
+                                        != previous.port_or_known_default()
+                            });
+                            if cross_host {
+                                for name in std::mem::take(&mut self.signature) {
+                                    headers.remove(name);
+                                }
+                                self.signer = None;
+                            } else if let Err(err) = self.as_mut().sign_again(&uri, &mut headers) {
+                                return Poll::Ready(Err(err.with_url(self.url.clone())));
+                            }
+                            proxy_headers(&mut headers, &uri, &self.network_scheme);
+
                             *self.as_mut().in_flight().get_mut() = {
                                 let req = InnerRequest::builder()
                                     .uri(uri)
@@ -2299,11 +4919,17 @@ impl Future for PendingRequest {
                                     .headers(headers.clone())
                                     .headers_order(self.client.headers_order.as_deref())
                                     .network_scheme(self.network_scheme.clone())
-                                    .body(body)?;
+                                    .extension(self.request_target)
+                                    .extension(self.fresh_connection)
+                                    .extension(self.client.alternative(&self.url))
+                                    .body(self.upload_body(body))?;
 
                                 std::mem::swap(self.as_mut().headers(), &mut headers);
+                                self.transfer.sent(&req);
                                 ResponseFuture::Default(self.client.hyper.request(req))
                             };
+                            self.sent = (SystemTime::now(), Instant::now());
+                            self.as_mut().reset_read_timeout();
 
                             continue;
                         }
@@ -2317,13 +4943,37 @@ impl Future for PendingRequest {
                 }
             }
 
+            let mut res = res.map(|body| {
+                stats::download(
+                    progress::download(
+                        throttle::download(body, &self.download_throttles),
+                        self.download_progress.as_ref(),
+                    ),
+                    &self.transfer,
+                )
+            });
+            if !self.history.is_empty() {
+                let history = std::mem::take(&mut self.history);
+                res.extensions_mut()
+                    .insert(redirect::RedirectHistory(history));
+            }
+
             let res = Response::new(
                 res,
                 self.url.clone(),
-                self.client.accepts,
+                if self.decompress {
+                    self.client.accepts
+                } else {
+                    Accepts::none()
+                },
                 self.total_timeout.take(),
                 self.read_timeout,
-            );
+                self.max_response_size,
+                self.client.max_decompression_ratio,
+            )
+            .counted(self.transfer.clone());
+            #[cfg(feature = "json")]
+            let res = res.recorded(recording);
             return Poll::Ready(Ok(res));
         }
     }
@@ -2337,11 +4987,59 @@ impl fmt::Debug for Pending {
                 .field("method", &req.method)
                 .field("url", &req.url)
                 .finish(),
+            PendingInner::Boxed(_) => f.debug_struct("Pending").finish_non_exhaustive(),
             PendingInner::Error(ref err) => f.debug_struct("Pending").field("error", err).finish(),
         }
     }
 }
 
+/// The text of a `Location` header, with its bytes that are not UTF-8
+/// percent-encoded, as browsers do.
+fn location_str(location: &HeaderValue) -> Cow<'_, str> {
+    match str::from_utf8(location.as_bytes()) {
+        Ok(location) => Cow::Borrowed(location),
+        Err(_) => Cow::Owned(
+            percent_encoding::percent_encode(location.as_bytes(), percent_encoding::CONTROLS)
+                .to_string(),
+        ),
+    }
+}
+
+/// Extracts the path and query of a `Location` header exactly as sent.
+///
+/// Only absolute URLs and absolute paths can be sent verbatim; other relative
+/// references have to be resolved against the current URL first.
+fn raw_location_path_and_query(location: &HeaderValue) -> Option<PathAndQuery> {
+    let location = location_str(location);
+    let location = location.as_ref();
+
+    // The fragment is never sent.
+    let location = location.split('#').next()?;
+
+    let authority_and_path = if let Some(rest) = location.strip_prefix("//") {
+        rest
+    } else if location.starts_with('/') {
+        return PathAndQuery::try_from(location).ok();
+    } else {
+        let (scheme, rest) = location.split_once("://")?;
+        if scheme.is_empty()
+            || !scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        {
+            return None;
+        }
+        rest
+    };
+
+    // Without a path, there is nothing to preserve.
+    let path_and_query = &authority_and_path[authority_and_path.find(['/', '?', '#'])?..];
+    if !path_and_query.starts_with('/') {
+        return None;
+    }
+    PathAndQuery::try_from(path_and_query).ok()
+}
+
 fn make_referer(next: &Url, previous: &Url) -> Option<HeaderValue> {
     if next.scheme() == "http" && previous.scheme() == "https" {
         return None;
@@ -2382,6 +5080,14 @@ fn apply_http1_config(mut builder: Http1Builder<'_>, http1: Http1Config) {
     }
 }
 
+/// Accept the malformed HTTP/1 responses browsers accept.
+fn apply_lenient_http1(mut builder: Http1Builder<'_>) {
+    builder
+        .allow_obsolete_multiline_headers_in_responses(true)
+        .allow_spaces_after_header_name_in_responses(true)
+        .ignore_invalid_headers_in_responses(true);
+}
+
 fn apply_http2_config(mut builder: Http2Builder<'_>, http2: Http2Config) {
     builder
         .initial_stream_id(http2.initial_stream_id)
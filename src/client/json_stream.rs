@@ -0,0 +1,358 @@
+//! Incremental deserialization of streamed JSON bodies.
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Buf, Bytes, BytesMut};
+use futures_util::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+
+use super::response::Response;
+use crate::error;
+
+type BoxStream<T> = Pin<Box<dyn Stream<Item = crate::Result<T>> + Send>>;
+
+fn chunks(res: Response) -> BoxStream<Bytes> {
+    Box::pin(futures_util::stream::unfold(res, |mut res| async move {
+        res.chunk().await.transpose().map(|chunk| (chunk, res))
+    }))
+}
+
+/// The values of a newline-delimited JSON body, as in NDJSON or JSON Lines.
+///
+/// Each line is deserialized as soon as it is received, so only one line
+/// is held in memory at a time. Blank lines are skipped.
+///
+/// To construct a `JsonLines`, refer to [`Response::json_lines`].
+#[must_use = "streams do nothing unless polled"]
+pub struct JsonLines<T> {
+    body: BoxStream<Bytes>,
+    buf: BytesMut,
+    /// How much of `buf` is known to hold no newline.
+    scanned: usize,
+    done: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> JsonLines<T> {
+    pub(super) fn new(res: Response) -> JsonLines<T> {
+        JsonLines {
+            body: chunks(res),
+            buf: BytesMut::new(),
+            scanned: 0,
+            done: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Take the next complete line out of the buffer.
+    fn next_line(&mut self) -> Option<Bytes> {
+        let pos = self.buf[self.scanned..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map(|pos| self.scanned + pos);
+        match pos {
+            Some(pos) => {
+                let line = self.buf.split_to(pos).freeze();
+                self.buf.advance(1);
+                self.scanned = 0;
+                Some(line)
+            }
+            None if self.done && !self.buf.is_empty() => {
+                self.scanned = 0;
+                Some(self.buf.split().freeze())
+            }
+            None => {
+                self.scanned = self.buf.len();
+                None
+            }
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Stream for JsonLines<T> {
+    type Item = crate::Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            while let Some(line) = self.next_line() {
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                return Poll::Ready(Some(serde_json::from_slice(&line).map_err(error::decode)));
+            }
+            if self.done {
+                return Poll::Ready(None);
+            }
+            match futures_util::ready!(self.body.poll_next_unpin(cx)) {
+                Some(Ok(chunk)) => self.buf.extend_from_slice(&chunk),
+                Some(Err(err)) => {
+                    self.done = true;
+                    self.buf.clear();
+                    return Poll::Ready(Some(Err(err)));
+                }
+                None => self.done = true,
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for JsonLines<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("JsonLines")
+            .field("buffered", &self.buf.len())
+            .finish()
+    }
+}
+
+/// The elements of a body holding a JSON array, such as a large export.
+///
+/// Each element is deserialized as soon as it is received, so only one
+/// element is held in memory at a time, however long the array.
+///
+/// An element that can not be deserialized to `T` yields an error, and the
+/// stream goes on with the next element. A body that is not a well-formed
+/// array yields an error, and ends the stream.
+///
+/// To construct a `JsonArray`, refer to [`Response::json_array_stream`].
+#[must_use = "streams do nothing unless polled"]
+pub struct JsonArray<T> {
+    body: BoxStream<Bytes>,
+    buf: BytesMut,
+    state: State,
+    scanner: Scanner,
+    eof: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    /// Before the opening bracket.
+    Open,
+    /// Before an element, or the closing bracket right after the opening one.
+    Element { first: bool },
+    /// After an element, before a comma or the closing bracket.
+    Separator,
+    /// After the closing bracket.
+    Closed,
+    /// After an error.
+    Failed,
+}
+
+/// Where the end of the element being received was looked for so far.
+#[derive(Default)]
+struct Scanner {
+    scanned: usize,
+    depth: usize,
+    in_string: bool,
+    escape: bool,
+}
+
+impl<T> JsonArray<T> {
+    pub(super) fn new(res: Response) -> JsonArray<T> {
+        JsonArray {
+            body: chunks(res),
+            buf: BytesMut::new(),
+            state: State::Open,
+            scanner: Scanner::default(),
+            eof: false,
+            _marker: PhantomData,
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let len = self
+            .buf
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(self.buf.len());
+        self.buf.advance(len);
+        !self.buf.is_empty()
+    }
+
+    /// Take the next complete element out of the buffer.
+    fn next_element(&mut self) -> Option<crate::Result<Bytes>> {
+        loop {
+            match self.state {
+                State::Open => {
+                    if !self.skip_whitespace() {
+                        return None;
+                    }
+                    if self.buf[0] != b'[' {
+                        return Some(Err(error::decode("expected a JSON array")));
+                    }
+                    self.buf.advance(1);
+                    self.state = State::Element { first: true };
+                }
+                State::Element { first } => {
+                    if !self.skip_whitespace() {
+                        return None;
+                    }
+                    if first && self.buf[0] == b']' {
+                        self.buf.advance(1);
+                        self.state = State::Closed;
+                        continue;
+                    }
+                    let end = self.scanner.find_end(&self.buf)?;
+                    if end == 0 {
+                        return Some(Err(error::decode("expected a JSON array element")));
+                    }
+                    self.scanner = Scanner::default();
+                    self.state = State::Separator;
+                    return Some(Ok(self.buf.split_to(end).freeze()));
+                }
+                State::Separator => {
+                    if !self.skip_whitespace() {
+                        return None;
+                    }
+                    self.state = match self.buf[0] {
+                        b',' => State::Element { first: false },
+                        b']' => State::Closed,
+                        _ => return Some(Err(error::decode("expected `,` or `]`"))),
+                    };
+                    self.buf.advance(1);
+                }
+                State::Closed => {
+                    if self.skip_whitespace() {
+                        return Some(Err(error::decode("trailing characters after JSON array")));
+                    }
+                    return None;
+                }
+                State::Failed => return None,
+            }
+        }
+    }
+}
+
+impl Scanner {
+    /// Find the comma or bracket ending the element at the start of `buf`,
+    /// outside of any nested value or string.
+    fn find_end(&mut self, buf: &[u8]) -> Option<usize> {
+        for (i, &b) in buf.iter().enumerate().skip(self.scanned) {
+            if self.in_string {
+                match b {
+                    _ if self.escape => self.escape = false,
+                    b'\\' => self.escape = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match b {
+                b'"' => self.in_string = true,
+                b'[' | b'{' => self.depth += 1,
+                b']' | b'}' if self.depth > 0 => self.depth -= 1,
+                b',' | b']' | b'}' => return Some(i),
+                _ => {}
+            }
+        }
+        self.scanned = buf.len();
+        None
+    }
+}
+
+impl<T: DeserializeOwned> Stream for JsonArray<T> {
+    type Item = crate::Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match self.next_element() {
+                Some(Ok(element)) => {
+                    return Poll::Ready(Some(
+                        serde_json::from_slice(&element).map_err(error::decode),
+                    ));
+                }
+                Some(Err(err)) => {
+                    self.state = State::Failed;
+                    self.buf.clear();
+                    return Poll::Ready(Some(Err(err)));
+                }
+                None => {}
+            }
+            if self.eof {
+                return match self.state {
+                    State::Closed | State::Failed => Poll::Ready(None),
+                    _ => {
+                        self.state = State::Failed;
+                        Poll::Ready(Some(Err(error::decode("unexpected end of JSON array"))))
+                    }
+                };
+            }
+            match futures_util::ready!(self.body.poll_next_unpin(cx)) {
+                Some(Ok(chunk)) => self.buf.extend_from_slice(&chunk),
+                Some(Err(err)) => {
+                    self.state = State::Failed;
+                    self.eof = true;
+                    self.buf.clear();
+                    return Poll::Ready(Some(Err(err)));
+                }
+                None => self.eof = true,
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for JsonArray<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("JsonArray")
+            .field("buffered", &self.buf.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elements(chunks: &[&str]) -> Vec<Result<String, String>> {
+        let mut array = JsonArray::<()> {
+            body: Box::pin(futures_util::stream::empty()),
+            buf: BytesMut::new(),
+            state: State::Open,
+            scanner: Scanner::default(),
+            eof: false,
+            _marker: PhantomData,
+        };
+        let mut out = Vec::new();
+        for chunk in chunks {
+            array.buf.extend_from_slice(chunk.as_bytes());
+            while array.state != State::Failed {
+                match array.next_element() {
+                    Some(Ok(element)) => out.push(Ok(String::from_utf8(element.to_vec()).unwrap())),
+                    Some(Err(err)) => {
+                        array.state = State::Failed;
+                        out.push(Err(err.to_string()));
+                    }
+                    None => break,
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn splits_elements_across_chunks() {
+        let out = elements(&[" [ {\"a\": [1, ", "2]}, \"x,]\\\"", "\" , 3 ", "]\n"]);
+        assert_eq!(
+            out,
+            vec![
+                Ok("{\"a\": [1, 2]}".to_owned()),
+                Ok("\"x,]\\\"\" ".to_owned()),
+                Ok("3 ".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_array() {
+        assert!(elements(&["[ ]"]).is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_arrays() {
+        assert!(elements(&["{\"a\": 1}"])[0].is_err());
+        assert!(elements(&["[1,,2]"])[1].is_err());
+        assert!(elements(&["[1] 2"])[1].is_err());
+    }
+}
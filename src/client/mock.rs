@@ -0,0 +1,172 @@
+//! Canned responses for requests, to test code using a `Client` without a
+//! server.
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use antidote::Mutex;
+use http::Method;
+use log::trace;
+
+use super::body::Body;
+use super::request::Request;
+use super::response::Response;
+use crate::error;
+use crate::response::ResponseUrl;
+
+type Handler = Arc<dyn Fn(&Request) -> http::Response<Body> + Send + Sync>;
+
+/// A transport answering requests with canned responses, without opening
+/// any connection.
+///
+/// Each route matches a method and a URL pattern. A pattern starting with
+/// `/` matches the path of the URL, and otherwise the URL up to its path.
+/// Either way, a pattern with a `?` also matches the query exactly. The
+/// first matching route answers; a request matching none fails.
+///
+/// The responses are returned as is: redirects are not followed, and
+/// cookies are not stored.
+///
+/// # Example
+///
+/// ```rust
+/// # async fn run() -> Result<(), rquest::Error> {
+/// use rquest::MockTransport;
+///
+/// let mock = MockTransport::new()
+///     .route(http::Method::GET, "/users/1", |_req| {
+///         http::Response::new(r#"{"name":"ferris"}"#)
+///     })
+///     .route(http::Method::POST, "https://api.example.com/users", |_req| {
+///         http::Response::builder().status(201).body("").unwrap()
+///     });
+///
+/// let client = rquest::Client::builder().mock(mock.clone()).build()?;
+/// let res = client.get("https://api.example.com/users/1").send().await?;
+/// assert_eq!(res.text().await?, r#"{"name":"ferris"}"#);
+/// assert_eq!(mock.calls(), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct MockTransport {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    routes: Mutex<Vec<Route>>,
+    calls: AtomicUsize,
+}
+
+struct Route {
+    method: Method,
+    pattern: String,
+    handler: Handler,
+}
+
+impl MockTransport {
+    /// A transport without routes.
+    pub fn new() -> MockTransport {
+        MockTransport::default()
+    }
+
+    /// Answer the requests with `method` matching `pattern` with the
+    /// response `handler` returns.
+    pub fn route<F, B>(self, method: Method, pattern: &str, handler: F) -> MockTransport
+    where
+        F: Fn(&Request) -> http::Response<B> + Send + Sync + 'static,
+        B: Into<Body>,
+    {
+        self.inner.routes.lock().push(Route {
+            method,
+            pattern: pattern.to_owned(),
+            handler: Arc::new(move |req| handler(req).map(Into::into)),
+        });
+        self
+    }
+
+    /// The number of requests answered so far.
+    pub fn calls(&self) -> usize {
+        self.inner.calls.load(Ordering::Relaxed)
+    }
+
+    /// Answer `req` with the first matching route.
+    pub(super) fn respond(&self, req: &Request) -> crate::Result<Response> {
+        let handler = self
+            .inner
+            .routes
+            .lock()
+            .iter()
+            .find(|route| route.matches(req))
+            .map(|route| route.handler.clone());
+        let handler = handler.ok_or_else(|| {
+            error::request(format!("no mock route for {} {}", req.method(), req.url()))
+                .with_url(req.url().clone())
+        })?;
+
+        trace!("mock response for {} {}", req.method(), req.url());
+        self.inner.calls.fetch_add(1, Ordering::Relaxed);
+        let mut res = handler(req);
+        res.extensions_mut().insert(ResponseUrl(req.url().clone()));
+        Ok(Response::from(res))
+    }
+}
+
+impl fmt::Debug for MockTransport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MockTransport")
+            .field("routes", &self.inner.routes.lock())
+            .field("calls", &self.calls())
+            .finish()
+    }
+}
+
+impl Route {
+    fn matches(&self, req: &Request) -> bool {
+        if self.method != *req.method() {
+            return false;
+        }
+        let url = req.url();
+        let (pattern, query) = match self.pattern.split_once('?') {
+            Some((pattern, query)) => (pattern, Some(query)),
+            None => (self.pattern.as_str(), None),
+        };
+        let target = if pattern.starts_with('/') {
+            url.path()
+        } else {
+            &url[..url::Position::AfterPath]
+        };
+        target == pattern && query.map_or(true, |query| url.query() == Some(query))
+    }
+}
+
+impl fmt::Debug for Route {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(method: Method, pattern: &str) -> Route {
+        Route {
+            method,
+            pattern: pattern.to_owned(),
+            handler: Arc::new(|_| http::Response::new(Body::default())),
+        }
+    }
+
+    #[test]
+    fn matches_paths_and_urls() {
+        let req = Request::new(Method::GET, "https://a.test/x?q=1".parse().unwrap());
+        assert!(route(Method::GET, "/x").matches(&req));
+        assert!(route(Method::GET, "/x?q=1").matches(&req));
+        assert!(route(Method::GET, "https://a.test/x").matches(&req));
+        assert!(!route(Method::GET, "/x?q=2").matches(&req));
+        assert!(!route(Method::GET, "https://b.test/x").matches(&req));
+        assert!(!route(Method::POST, "/x").matches(&req));
+    }
+}
@@ -1,20 +1,99 @@
+pub use self::abort::AbortHandle;
+pub use self::auth::{AuthProvider, AuthToken};
 pub use self::body::Body;
+#[cfg(feature = "stream")]
+pub use self::body::BodySender;
+#[cfg(feature = "json")]
+pub use self::cassette::{Cassette, CassetteMode};
+#[cfg(feature = "charset")]
+pub use self::charset::TextStream;
+pub use self::client_hints::ClientHints;
+pub use self::compression::Encoding;
+pub use self::conditional::{ConditionalRequest, Revalidation};
 pub use self::conf::{Http1Config, Http2Config};
-pub use self::context::{HttpContext, HttpContextProvider};
+pub use self::context::{HttpContext, HttpContextProvider, MultipartStyle};
+pub use self::download::Download;
+pub use self::fetch::{FetchRequest, RequestCredentials, RequestMode, RequestRedirect};
+#[cfg(feature = "json")]
+pub use self::graphql::{GraphQLError, GraphQLLocation};
+#[cfg(feature = "json")]
+pub use self::har::HarRecorder;
 pub use self::http::{Client, ClientBuilder, ClientMut, ClientRef};
+#[cfg(feature = "json")]
+pub use self::json_stream::{JsonArray, JsonLines};
+pub use self::mock::MockTransport;
+#[cfg(feature = "json")]
+pub use self::oauth2::OAuth2;
+pub use self::page::Page;
+pub use self::preset::Preset;
+pub use self::query::{ArrayStyle, NestedStyle, QueryStyle};
 pub use self::request::{Request, RequestBuilder};
 pub use self::response::Response;
+#[cfg(feature = "stream")]
+pub use self::save::SaveTo;
+pub use self::session::SessionState;
+pub use self::signer::{RequestSigner, SigningRequest};
+#[cfg(feature = "aws-sigv4")]
+pub use self::sigv4::AwsCredentials;
+pub use self::sse::{Event, EventSource, EventStream};
+pub use self::stats::TransferStats;
+pub use self::template::RequestTemplate;
+pub use self::throttle::Throttle;
 pub use self::upgrade::Upgraded;
 
+mod abort;
+mod alt_svc;
+mod auth;
 pub mod body;
+#[cfg(feature = "json")]
+mod cassette;
+#[cfg(feature = "charset")]
+mod charset;
+mod client_hints;
+mod compression;
+mod conditional;
 mod conf;
 pub mod context;
+mod curl;
 pub mod decoder;
+mod digest;
+mod download;
+mod fetch;
+#[cfg(feature = "json")]
+pub(crate) mod graphql;
+#[cfg(feature = "json")]
+mod har;
+mod hsts;
 pub mod http;
+#[cfg(feature = "json")]
+mod json_stream;
+mod mock;
 #[cfg(feature = "multipart")]
 pub mod multipart;
+#[cfg(feature = "negotiate")]
+mod negotiate;
+#[cfg(feature = "ntlm")]
+mod ntlm;
+#[cfg(feature = "json")]
+mod oauth2;
+mod pacing;
+mod page;
+mod preset;
+mod progress;
+mod query;
 pub(crate) mod request;
 mod response;
+#[cfg(feature = "stream")]
+mod save;
+mod session;
+mod signer;
+#[cfg(feature = "aws-sigv4")]
+mod sigv4;
+mod sse;
+mod stats;
+mod template;
+mod throttle;
 mod upgrade;
+mod uri_template;
 #[cfg(feature = "websocket")]
 pub mod websocket;
@@ -13,10 +13,14 @@ use mime_guess::Mime;
 use percent_encoding::{self, AsciiSet, NON_ALPHANUMERIC};
 #[cfg(feature = "stream")]
 use tokio::fs::File;
+#[cfg(feature = "stream")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+#[cfg(feature = "stream")]
+use tokio_util::io::ReaderStream;
 
 use futures_util::{future, stream, Stream, StreamExt};
 
-use super::Body;
+use super::{Body, MultipartStyle};
 use crate::header::HeaderMap;
 
 /// An async multipart/form-data request.
@@ -36,6 +40,7 @@ pub(crate) struct FormParts<P> {
     pub(crate) computed_headers: Vec<Vec<u8>>,
     pub(crate) fields: Vec<(Cow<'static, str>, P)>,
     pub(crate) percent_encoding: PercentEncoding,
+    pub(crate) style: Option<MultipartStyle>,
 }
 
 pub(crate) struct PartMetadata {
@@ -138,6 +143,44 @@ impl Form {
         self.with_inner(|inner| inner.percent_encode_noop())
     }
 
+    /// Format this `Form` like a browser or HTTP library does, with a new
+    /// boundary of its kind.
+    ///
+    /// Browser styles put field names and filenames in quotes as they are,
+    /// percent-encoding only quotes and line breaks, as the HTML standard
+    /// specifies.
+    ///
+    /// Forms without a style of their own take the style of the client
+    /// sending them, see [`ClientBuilder::multipart_style`].
+    ///
+    /// [`ClientBuilder::multipart_style`]: crate::ClientBuilder::multipart_style
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rquest::MultipartStyle;
+    ///
+    /// let form = rquest::multipart::Form::new()
+    ///     .style(MultipartStyle::WebKit)
+    ///     .text("q", "rust");
+    /// assert!(form.boundary().starts_with("----WebKitFormBoundary"));
+    /// ```
+    pub fn style(self, style: MultipartStyle) -> Form {
+        self.with_inner(|inner| inner.style(style))
+    }
+
+    pub(crate) fn default_style(self, style: MultipartStyle) -> Form {
+        self.with_inner(|inner| inner.default_style(style))
+    }
+
+    /// Replace the boundary with one drawn from `random`.
+    pub(crate) fn reseed_boundary(self, random: impl FnMut() -> u64) -> Form {
+        self.with_inner(|mut inner| {
+            inner.boundary = gen_boundary_with(inner.style.unwrap_or_default(), random);
+            inner
+        })
+    }
+
     /// Consume this instance and transform into an instance of Body for use in a request.
     pub(crate) fn stream(self) -> Body {
         if self.inner.fields.is_empty() {
@@ -263,6 +306,54 @@ impl Part {
         Part::new(value.into(), Some(length))
     }
 
+    /// Makes a new parameter streamed from an `AsyncRead`.
+    ///
+    /// Without a known length, the form is sent with chunked encoding.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `stream` feature to be enabled.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+    pub fn reader<R>(reader: R) -> Part
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        Part::stream(Body::wrap_stream(ReaderStream::new(reader)))
+    }
+
+    /// Makes a new parameter streamed from an `AsyncRead` holding `length`
+    /// bytes, so the form has a known length.
+    ///
+    /// At most `length` bytes are read, and a reader ending early fails the
+    /// request.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> std::io::Result<()> {
+    /// let file = tokio::fs::File::open("backup.tar").await?;
+    /// let len = file.metadata().await?.len();
+    /// let part = rquest::multipart::Part::reader_with_length(file, len)
+    ///     .file_name("backup.tar");
+    /// let form = rquest::multipart::Form::new().part("backup", part);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `stream` feature to be enabled.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+    pub fn reader_with_length<R>(reader: R, length: u64) -> Part
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        let stream = ReaderStream::new(reader.take(length));
+        Part::stream_with_length(Body::wrap_stream(stream), length)
+    }
+
     /// Makes a file parameter.
     ///
     /// # Errors
@@ -362,13 +453,39 @@ impl PartProps for Part {
 impl<P: PartProps> FormParts<P> {
     pub(crate) fn new() -> Self {
         FormParts {
-            boundary: gen_boundary(),
+            boundary: gen_boundary(MultipartStyle::Default),
             computed_headers: Vec::new(),
             fields: Vec::new(),
             percent_encoding: PercentEncoding::PathSegment,
+            style: None,
         }
     }
 
+    /// Format the form like `style`, with a new boundary.
+    pub(crate) fn style(mut self, style: MultipartStyle) -> Self {
+        self.boundary = gen_boundary(style);
+        self.percent_encoding = match style {
+            MultipartStyle::Default => PercentEncoding::PathSegment,
+            _ => PercentEncoding::Quoted,
+        };
+        self.style = Some(style);
+        self
+    }
+
+    /// Format the form like `style`, unless it has a style, or a
+    /// percent-encoding of its own.
+    pub(crate) fn default_style(self, style: MultipartStyle) -> Self {
+        if self.style.is_some() || style == MultipartStyle::Default {
+            return self;
+        }
+        let percent_encoding = self.percent_encoding;
+        let mut parts = self.style(style);
+        if percent_encoding != PercentEncoding::PathSegment {
+            parts.percent_encoding = percent_encoding;
+        }
+        parts
+    }
+
     pub(crate) fn boundary(&self) -> &str {
         &self.boundary
     }
@@ -523,10 +640,13 @@ const ATTR_CHAR_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
     .remove(b'|')
     .remove(b'~');
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub(crate) enum PercentEncoding {
     PathSegment,
     AttrChar,
     NoOp,
+    /// Quoted as is, but for quotes and line breaks, like browsers do.
+    Quoted,
 }
 
 impl PercentEncoding {
@@ -534,6 +654,19 @@ impl PercentEncoding {
         let mut buf = Vec::new();
         buf.extend_from_slice(b"Content-Disposition: form-data; ");
 
+        if *self == Self::Quoted {
+            buf.extend_from_slice(b"name=\"");
+            buf.extend_from_slice(escape_quoted(name).as_bytes());
+            buf.extend_from_slice(b"\"");
+            if let Some(filename) = &field.file_name {
+                buf.extend_from_slice(b"; filename=\"");
+                buf.extend_from_slice(escape_quoted(filename).as_bytes());
+                buf.extend_from_slice(b"\"");
+            }
+            Self::encode_part_headers(&mut buf, field);
+            return buf;
+        }
+
         match self.percent_encode(name) {
             Cow::Borrowed(value) => {
                 // nothing has been percent encoded
@@ -561,6 +694,11 @@ impl PercentEncoding {
             buf.extend_from_slice(b"\"");
         }
 
+        Self::encode_part_headers(&mut buf, field);
+        buf
+    }
+
+    fn encode_part_headers(buf: &mut Vec<u8>, field: &PartMetadata) {
         if let Some(mime) = &field.mime {
             buf.extend_from_slice(b"\r\nContent-Type: ");
             buf.extend_from_slice(mime.as_ref().as_bytes());
@@ -572,7 +710,6 @@ impl PercentEncoding {
             buf.extend_from_slice(b": ");
             buf.extend_from_slice(v.as_bytes());
         }
-        buf
     }
 
     fn percent_encode<'a>(&self, value: &'a str) -> Cow<'a, str> {
@@ -581,20 +718,68 @@ impl PercentEncoding {
         match self {
             Self::PathSegment => percent_encode(value, PATH_SEGMENT_ENCODE_SET).into(),
             Self::AttrChar => percent_encode(value, ATTR_CHAR_ENCODE_SET).into(),
-            Self::NoOp => value.into(),
+            Self::NoOp | Self::Quoted => value.into(),
         }
     }
 }
 
-fn gen_boundary() -> String {
-    use crate::util::fast_random as random;
+/// Percent-encode the quotes and line breaks of a quoted header parameter.
+fn escape_quoted(value: &str) -> Cow<'_, str> {
+    if !value.contains(['"', '\r', '\n']) {
+        return value.into();
+    }
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+        .into()
+}
+
+fn gen_boundary(style: MultipartStyle) -> String {
+    gen_boundary_with(style, crate::util::fast_random)
+}
 
-    let a = random();
-    let b = random();
-    let c = random();
-    let d = random();
+fn gen_boundary_with(style: MultipartStyle, mut random: impl FnMut() -> u64) -> String {
+    match style {
+        MultipartStyle::WebKit => {
+            // WebKit draws from 64 characters, `A` and `B` appearing twice.
+            const CHARS: &[u8; 64] =
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789AB";
+
+            let mut boundary = String::from("----WebKitFormBoundary");
+            for mut bits in [random(), random()] {
+                for _ in 0..8 {
+                    boundary.push(CHARS[(bits & 63) as usize] as char);
+                    bits >>= 6;
+                }
+            }
+            boundary
+        }
+        MultipartStyle::Gecko => {
+            format!("----geckoformboundary{:016x}{:016x}", random(), random())
+        }
+        MultipartStyle::OkHttp => {
+            // a random (version 4) UUID
+            let a = (random() & !0xf000) | 0x4000;
+            let b = (random() & !(0xc000 << 48)) | (0x8000 << 48);
+            format!(
+                "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+                a >> 32,
+                (a >> 16) & 0xffff,
+                a & 0xffff,
+                b >> 48,
+                b & 0xffff_ffff_ffff
+            )
+        }
+        MultipartStyle::Default => {
+            let a = random();
+            let b = random();
+            let c = random();
+            let d = random();
 
-    format!("{a:016x}-{b:016x}-{c:016x}-{d:016x}")
+            format!("{a:016x}-{b:016x}-{c:016x}-{d:016x}")
+        }
+    }
 }
 
 #[cfg(test)]
@@ -744,5 +929,45 @@ mod tests {
             PercentEncoding::AttrChar.encode_headers(name, &field.meta),
             &b"Content-Disposition: form-data; name*=utf-8''start%25%27%22%0D%0A%C3%9Fend"[..]
         );
+
+        let field = Part::text("").file_name("a\"b.txt");
+        assert_eq!(
+            PercentEncoding::Quoted.encode_headers(name, &field.meta),
+            "Content-Disposition: form-data; name=\"start%'%22%0D%0Aßend\"; filename=\"a%22b.txt\""
+                .as_bytes()
+        );
+    }
+
+    #[test]
+    fn boundary_styles() {
+        let webkit = gen_boundary(MultipartStyle::WebKit);
+        let suffix = webkit.strip_prefix("----WebKitFormBoundary").unwrap();
+        assert_eq!(suffix.len(), 16);
+        assert!(suffix.bytes().all(|b| b.is_ascii_alphanumeric()));
+
+        let gecko = gen_boundary(MultipartStyle::Gecko);
+        let suffix = gecko.strip_prefix("----geckoformboundary").unwrap();
+        assert_eq!(suffix.len(), 32);
+        assert!(suffix.bytes().all(|b| b.is_ascii_hexdigit()));
+
+        let uuid = gen_boundary(MultipartStyle::OkHttp);
+        let groups: Vec<_> = uuid.split('-').map(str::len).collect();
+        assert_eq!(groups, [8, 4, 4, 4, 12]);
+        assert_eq!(&uuid[14..15], "4");
+        assert!(matches!(&uuid[19..20], "8" | "9" | "a" | "b"));
+    }
+
+    #[test]
+    fn default_style_keeps_explicit_choices() {
+        let form = Form::new()
+            .style(MultipartStyle::Gecko)
+            .default_style(MultipartStyle::WebKit);
+        assert!(form.boundary().starts_with("----geckoformboundary"));
+
+        let form = Form::new()
+            .percent_encode_noop()
+            .default_style(MultipartStyle::WebKit);
+        assert!(form.boundary().starts_with("----WebKitFormBoundary"));
+        assert!(form.inner.percent_encoding == PercentEncoding::NoOp);
     }
 }
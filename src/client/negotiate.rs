@@ -0,0 +1,146 @@
+//! `Negotiate` authentication ([RFC 4559]) with Kerberos, through GSSAPI
+//! on Unix and SSPI on Windows.
+//!
+//! [RFC 4559]: https://datatracker.ietf.org/doc/html/rfc4559
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use cross_krb5::{ClientCtx, InitiateFlags, PendingClientCtx, Step};
+use http::header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use log::debug;
+
+use super::http::Client;
+use super::request::Request;
+use super::response::Response;
+use crate::{error, StatusCode};
+
+/// The most tokens sent for a request, for servers that never finish the
+/// handshake.
+const MAX_ROUNDS: usize = 4;
+
+/// The token of a `Negotiate` challenge in the `WWW-Authenticate` headers
+/// of `res`, empty for the first challenge, or `None` without one.
+fn challenge(res: &Response) -> Option<Vec<u8>> {
+    res.headers()
+        .get_all(WWW_AUTHENTICATE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|challenge| {
+            let challenge = challenge.trim();
+            let (scheme, token) = challenge.split_once(' ').unwrap_or((challenge, ""));
+            if !scheme.eq_ignore_ascii_case("Negotiate") {
+                return None;
+            }
+            BASE64_STANDARD.decode(token.trim()).ok()
+        })
+}
+
+/// The state of the security context between two requests.
+enum Context {
+    None,
+    Pending(PendingClientCtx),
+    Done,
+}
+
+impl Context {
+    /// The token answering the challenge `token` of the server, starting a
+    /// security context for the `HTTP` service of `host` on the first one.
+    fn step(self, host: &str, token: &[u8]) -> Result<(Context, Option<Vec<u8>>), String> {
+        match self {
+            Context::None => {
+                let target = format!("HTTP/{host}");
+                let (pending, token) = ClientCtx::new(InitiateFlags::empty(), None, &target, None)
+                    .map_err(|err| {
+                        format!("failed to get a Kerberos ticket for {target}: {err}")
+                    })?;
+                Ok((Context::Pending(pending), Some(token.to_vec())))
+            }
+            Context::Pending(pending) => match pending.step(token) {
+                Ok(Step::Continue((pending, token))) => {
+                    Ok((Context::Pending(pending), Some(token.to_vec())))
+                }
+                Ok(Step::Finished((_, token))) => {
+                    Ok((Context::Done, token.map(|token| token.to_vec())))
+                }
+                Err(err) => Err(format!("negotiate handshake failed: {err}")),
+            },
+            Context::Done => Ok((Context::Done, None)),
+        }
+    }
+}
+
+/// Send `req`, and answer the `Negotiate` challenges of the server with the
+/// credentials of the current user, as in a Windows domain or after
+/// `kinit`.
+///
+/// No ticket is sent unless the server asks for one with a `401`, so hosts
+/// outside the realm never see them. Each token of the server continuing
+/// the handshake is answered, up to a few rounds.
+///
+/// Getting a ticket may contact the KDC, so it is done on the blocking
+/// threads of the runtime.
+pub(super) async fn send(client: Client, req: Request) -> crate::Result<Response> {
+    let url = req.url().clone();
+    let (Some(host), Some(retry)) = (url.host_str().map(ToOwned::to_owned), req.try_clone()) else {
+        debug!("negotiate authentication needs a body that can be sent again");
+        return client.execute_request(req).await;
+    };
+
+    let mut res = client.execute_request(req).await?;
+    let mut context = Context::None;
+    for _ in 0..MAX_ROUNDS {
+        if res.status() != StatusCode::UNAUTHORIZED {
+            break;
+        }
+        let Some(token) = challenge(&res) else {
+            debug!("no negotiate challenge in 401 response");
+            break;
+        };
+        let Some(mut next) = retry.try_clone() else {
+            break;
+        };
+
+        let host = host.clone();
+        let (stepped, token) = tokio::task::spawn_blocking(move || context.step(&host, &token))
+            .await
+            .map_err(|err| error::request(err).with_url(url.clone()))?
+            .map_err(|err| error::request(err).with_url(url.clone()))?;
+        context = stepped;
+        let Some(token) = token else {
+            break;
+        };
+
+        debug!("sending negotiate token to {}", url);
+        let mut header =
+            HeaderValue::try_from(format!("Negotiate {}", BASE64_STANDARD.encode(token)))
+                .map_err(|err| error::request(err).with_url(url.clone()))?;
+        header.set_sensitive(true);
+        next.headers_mut().insert(AUTHORIZATION, header);
+        res = client.execute_request(next).await?;
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(challenges: &[&'static str]) -> Response {
+        let mut builder = http::Response::builder().status(401);
+        for challenge in challenges {
+            builder = builder.header(WWW_AUTHENTICATE, *challenge);
+        }
+        Response::from(builder.body("").unwrap())
+    }
+
+    #[test]
+    fn parses_challenges() {
+        assert_eq!(challenge(&response(&["Negotiate"])), Some(Vec::new()));
+        assert_eq!(
+            challenge(&response(&["Basic realm=\"x\"", "Negotiate YWJj"])),
+            Some(b"abc".to_vec())
+        );
+        assert_eq!(challenge(&response(&["Basic realm=\"x\""])), None);
+        assert_eq!(challenge(&response(&[])), None);
+    }
+}
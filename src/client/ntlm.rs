@@ -0,0 +1,461 @@
+//! NTLM authentication ([MS-NLMP]), with NTLMv2 responses.
+//!
+//! NTLM authenticates a connection rather than a request: the challenge
+//! answered must come from the connection the answer is sent on, which the
+//! pool reuses as long as the response of the challenge is read whole.
+//!
+//! [MS-NLMP]: https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-nlmp/
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use boring2::hash::{hash, MessageDigest};
+use http::header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use http::Version;
+use log::debug;
+
+use super::http::Client;
+use super::request::Request;
+use super::response::Response;
+use crate::StatusCode;
+
+const SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+
+const NEGOTIATE_UNICODE: u32 = 0x0000_0001;
+const NEGOTIATE_OEM: u32 = 0x0000_0002;
+const REQUEST_TARGET: u32 = 0x0000_0004;
+const NEGOTIATE_NTLM: u32 = 0x0000_0200;
+const NEGOTIATE_ALWAYS_SIGN: u32 = 0x0000_8000;
+const NEGOTIATE_EXTENDED_SESSIONSECURITY: u32 = 0x0008_0000;
+
+const NEGOTIATE_FLAGS: u32 = NEGOTIATE_UNICODE
+    | NEGOTIATE_OEM
+    | REQUEST_TARGET
+    | NEGOTIATE_NTLM
+    | NEGOTIATE_ALWAYS_SIGN
+    | NEGOTIATE_EXTENDED_SESSIONSECURITY;
+
+/// The `MsvAvTimestamp` pair of the target info of a challenge.
+const AV_TIMESTAMP: u16 = 7;
+
+/// Seconds between 1601, the epoch of Windows file times, and 1970.
+const FILETIME_UNIX_OFFSET: u64 = 11_644_473_600;
+
+/// The credentials of a request authenticating with NTLM.
+#[derive(Clone)]
+pub(crate) struct NtlmAuth {
+    domain: String,
+    username: String,
+    password: String,
+}
+
+impl NtlmAuth {
+    /// Credentials of a `DOMAIN\user` or a `user@domain` name.
+    pub(crate) fn new(username: String, password: String) -> NtlmAuth {
+        let (domain, username) = match username.split_once('\\') {
+            Some((domain, username)) => (domain.to_owned(), username.to_owned()),
+            None => match username.rsplit_once('@') {
+                Some((username, domain)) => (domain.to_owned(), username.to_owned()),
+                None => (String::new(), username),
+            },
+        };
+        NtlmAuth {
+            domain,
+            username,
+            password,
+        }
+    }
+}
+
+impl fmt::Debug for NtlmAuth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NtlmAuth")
+            .field("domain", &self.domain)
+            .field("username", &self.username)
+            .finish()
+    }
+}
+
+/// The `CHALLENGE_MESSAGE` of a server.
+#[derive(Debug, PartialEq, Eq)]
+struct Challenge {
+    flags: u32,
+    server_challenge: [u8; 8],
+    target_info: Vec<u8>,
+}
+
+impl Challenge {
+    fn parse(message: &[u8]) -> Option<Challenge> {
+        if message.get(..8)? != SIGNATURE || u32_at(message, 8)? != 2 {
+            return None;
+        }
+        let flags = u32_at(message, 20)?;
+        let server_challenge = message.get(24..32)?.try_into().ok()?;
+        let target_info = match security_buffer(message, 40) {
+            Some(range) => message.get(range)?.to_vec(),
+            None => Vec::new(),
+        };
+        Some(Challenge {
+            flags,
+            server_challenge,
+            target_info,
+        })
+    }
+
+    fn from_response(res: &Response) -> Option<Challenge> {
+        res.headers()
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(|value| {
+                let (scheme, token) = value.trim().split_once(' ')?;
+                if !scheme.eq_ignore_ascii_case("NTLM") {
+                    return None;
+                }
+                Challenge::parse(&BASE64_STANDARD.decode(token.trim()).ok()?)
+            })
+    }
+
+    /// The timestamp of the server, as a Windows file time.
+    fn timestamp(&self) -> Option<u64> {
+        let mut pairs = &self.target_info[..];
+        while pairs.len() >= 4 {
+            let id = u16::from_le_bytes([pairs[0], pairs[1]]);
+            let len = usize::from(u16::from_le_bytes([pairs[2], pairs[3]]));
+            let value = pairs.get(4..4 + len)?;
+            match id {
+                0 => return None,
+                AV_TIMESTAMP => return Some(u64::from_le_bytes(value.try_into().ok()?)),
+                _ => pairs = &pairs[4 + len..],
+            }
+        }
+        None
+    }
+
+    /// The `AUTHENTICATE_MESSAGE` answering the challenge.
+    fn authenticate(&self, auth: &NtlmAuth, client_challenge: [u8; 8], time: u64) -> Vec<u8> {
+        let nt_hash = md4(&utf16le(&auth.password));
+        let identity = utf16le(&(auth.username.to_uppercase() + &auth.domain));
+        let ntowf = hmac_md5(&nt_hash, &identity);
+
+        let server_timestamp = self.timestamp();
+        let mut blob = vec![1, 1, 0, 0, 0, 0, 0, 0];
+        blob.extend_from_slice(&server_timestamp.unwrap_or(time).to_le_bytes());
+        blob.extend_from_slice(&client_challenge);
+        blob.extend_from_slice(&[0; 4]);
+        blob.extend_from_slice(&self.target_info);
+        blob.extend_from_slice(&[0; 4]);
+
+        let mut nt_response =
+            hmac_md5(&ntowf, &[&self.server_challenge[..], &blob].concat()).to_vec();
+        nt_response.extend_from_slice(&blob);
+
+        // with a server timestamp, the LMv2 response is replaced with zeros
+        let lm_response = match server_timestamp {
+            Some(_) => vec![0; 24],
+            None => {
+                let challenges = [self.server_challenge, client_challenge].concat();
+                [&hmac_md5(&ntowf, &challenges)[..], &client_challenge].concat()
+            }
+        };
+
+        let flags = (self.flags & NEGOTIATE_FLAGS & !NEGOTIATE_OEM) | NEGOTIATE_UNICODE;
+        let fields = [
+            lm_response,
+            nt_response,
+            utf16le(&auth.domain),
+            utf16le(&auth.username),
+            // the workstation
+            Vec::new(),
+            // the encrypted random session key
+            Vec::new(),
+        ];
+
+        const HEADER_LEN: usize = 64;
+        let mut message =
+            Vec::with_capacity(HEADER_LEN + fields.iter().map(Vec::len).sum::<usize>());
+        message.extend_from_slice(SIGNATURE);
+        message.extend_from_slice(&3u32.to_le_bytes());
+        let mut offset = HEADER_LEN;
+        for field in &fields {
+            message.extend_from_slice(&(field.len() as u16).to_le_bytes());
+            message.extend_from_slice(&(field.len() as u16).to_le_bytes());
+            message.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += field.len();
+        }
+        message.extend_from_slice(&flags.to_le_bytes());
+        for field in &fields {
+            message.extend_from_slice(field);
+        }
+        message
+    }
+}
+
+/// The `NEGOTIATE_MESSAGE` starting the handshake.
+fn negotiate() -> Vec<u8> {
+    let mut message = Vec::with_capacity(32);
+    message.extend_from_slice(SIGNATURE);
+    message.extend_from_slice(&1u32.to_le_bytes());
+    message.extend_from_slice(&NEGOTIATE_FLAGS.to_le_bytes());
+    // the empty domain and workstation, at the end of the message
+    for _ in 0..2 {
+        message.extend_from_slice(&[0; 4]);
+        message.extend_from_slice(&32u32.to_le_bytes());
+    }
+    message
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// The range of the payload a security buffer at `at` points to.
+fn security_buffer(bytes: &[u8], at: usize) -> Option<std::ops::Range<usize>> {
+    let len = usize::from(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?));
+    let offset = u32_at(bytes, at + 4)? as usize;
+    Some(offset..offset + len)
+}
+
+fn utf16le(s: &str) -> Vec<u8> {
+    s.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+fn hmac_md5(key: &[u8], data: &[u8]) -> [u8; 16] {
+    const BLOCK_LEN: usize = 64;
+    let md5 = |data: &[u8]| -> [u8; 16] {
+        let digest = hash(MessageDigest::md5(), data).expect("hashing in memory does not fail");
+        digest[..].try_into().expect("MD5 digests are 16 bytes")
+    };
+
+    let mut block = [0u8; BLOCK_LEN];
+    if key.len() > BLOCK_LEN {
+        block[..16].copy_from_slice(&md5(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let inner = [&block.map(|b| b ^ 0x36)[..], data].concat();
+    let outer = [&block.map(|b| b ^ 0x5c)[..], &md5(&inner)].concat();
+    md5(&outer)
+}
+
+/// MD4 ([RFC 1320]), which the NT hash of a password is made with, and
+/// BoringSSL leaves out.
+///
+/// [RFC 1320]: https://datatracker.ietf.org/doc/html/rfc1320
+fn md4(data: &[u8]) -> [u8; 16] {
+    let mut state: [u32; 4] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476];
+
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    for block in padded.chunks_exact(64) {
+        let x: Vec<u32> = block
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = state;
+
+        let f = |x: u32, y: u32, z: u32| (x & y) | (!x & z);
+        let g = |x: u32, y: u32, z: u32| (x & y) | (x & z) | (y & z);
+        let h = |x: u32, y: u32, z: u32| x ^ y ^ z;
+
+        for &i in &[0, 4, 8, 12] {
+            a = a.wrapping_add(f(b, c, d)).wrapping_add(x[i]).rotate_left(3);
+            d = d
+                .wrapping_add(f(a, b, c))
+                .wrapping_add(x[i + 1])
+                .rotate_left(7);
+            c = c
+                .wrapping_add(f(d, a, b))
+                .wrapping_add(x[i + 2])
+                .rotate_left(11);
+            b = b
+                .wrapping_add(f(c, d, a))
+                .wrapping_add(x[i + 3])
+                .rotate_left(19);
+        }
+        for &i in &[0, 1, 2, 3] {
+            let k = 0x5a82_7999;
+            a = a
+                .wrapping_add(g(b, c, d))
+                .wrapping_add(x[i])
+                .wrapping_add(k)
+                .rotate_left(3);
+            d = d
+                .wrapping_add(g(a, b, c))
+                .wrapping_add(x[i + 4])
+                .wrapping_add(k)
+                .rotate_left(5);
+            c = c
+                .wrapping_add(g(d, a, b))
+                .wrapping_add(x[i + 8])
+                .wrapping_add(k)
+                .rotate_left(9);
+            b = b
+                .wrapping_add(g(c, d, a))
+                .wrapping_add(x[i + 12])
+                .wrapping_add(k)
+                .rotate_left(13);
+        }
+        for &i in &[0, 2, 1, 3] {
+            let k = 0x6ed9_eba1;
+            a = a
+                .wrapping_add(h(b, c, d))
+                .wrapping_add(x[i])
+                .wrapping_add(k)
+                .rotate_left(3);
+            d = d
+                .wrapping_add(h(a, b, c))
+                .wrapping_add(x[i + 8])
+                .wrapping_add(k)
+                .rotate_left(9);
+            c = c
+                .wrapping_add(h(d, a, b))
+                .wrapping_add(x[i + 4])
+                .wrapping_add(k)
+                .rotate_left(11);
+            b = b
+                .wrapping_add(h(c, d, a))
+                .wrapping_add(x[i + 12])
+                .wrapping_add(k)
+                .rotate_left(15);
+        }
+
+        state[0] = state[0].wrapping_add(a);
+        state[1] = state[1].wrapping_add(b);
+        state[2] = state[2].wrapping_add(c);
+        state[3] = state[3].wrapping_add(d);
+    }
+
+    let mut digest = [0; 16];
+    for (chunk, word) in digest.chunks_exact_mut(4).zip(state) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+fn client_challenge() -> [u8; 8] {
+    crate::util::fast_random().to_le_bytes()
+}
+
+/// The current time, as a Windows file time.
+fn now() -> u64 {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    (since_epoch.as_secs() + FILETIME_UNIX_OFFSET) * 10_000_000
+        + u64::from(since_epoch.subsec_nanos() / 100)
+}
+
+fn header(message: &[u8]) -> HeaderValue {
+    let mut value = HeaderValue::try_from(format!("NTLM {}", BASE64_STANDARD.encode(message)))
+        .expect("base64 is always a valid header value");
+    value.set_sensitive(true);
+    value
+}
+
+/// Send `req` starting an NTLM handshake, and once more answering the
+/// challenge of the server if it answers `401 Unauthorized`.
+///
+/// The requests are sent with HTTP/1.1, which authenticated connections
+/// need.
+pub(super) async fn send(
+    client: Client,
+    auth: NtlmAuth,
+    mut req: Request,
+) -> crate::Result<Response> {
+    *req.version_mut() = Some(Version::HTTP_11);
+    let Some(mut retry) = req.try_clone() else {
+        debug!("ntlm authentication needs a body that can be sent again");
+        return client.execute_request(req).await;
+    };
+
+    req.headers_mut()
+        .insert(AUTHORIZATION, header(&negotiate()));
+    let res = client.execute_request(req).await?;
+    if res.status() != StatusCode::UNAUTHORIZED {
+        return Ok(res);
+    }
+    let Some(challenge) = Challenge::from_response(&res) else {
+        debug!("no ntlm challenge in 401 response");
+        return Ok(res);
+    };
+    // read the response whole, for its connection to answer on
+    res.bytes().await?;
+
+    let message = challenge.authenticate(&auth, client_challenge(), now());
+    retry.headers_mut().insert(AUTHORIZATION, header(&message));
+    client.execute_request(retry).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn md4_rfc_examples() {
+        assert_eq!(hex(&md4(b"")), "31d6cfe0d16ae931b73c59d7e0c089c0");
+        assert_eq!(hex(&md4(b"abc")), "a448017aaf21d8525fc10ae87aa6729d");
+        assert_eq!(
+            hex(&md4(
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+            )),
+            "e33b4ddc9c38f2199c3e7b164fcc0536"
+        );
+    }
+
+    #[test]
+    fn splits_domain_from_username() {
+        let split = |name: &str| {
+            let auth = NtlmAuth::new(name.to_owned(), String::new());
+            (auth.domain, auth.username)
+        };
+        assert_eq!(split("CORP\\alice"), ("CORP".into(), "alice".into()));
+        assert_eq!(
+            split("alice@corp.example"),
+            ("corp.example".into(), "alice".into())
+        );
+        assert_eq!(split("alice"), (String::new(), "alice".into()));
+    }
+
+    // https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-nlmp/125f7a94-933e-4023-a146-a449e49bf774
+    #[test]
+    fn answers_spec_example() {
+        let auth = NtlmAuth::new("Domain\\User".to_owned(), "Password".to_owned());
+        let mut target_info = Vec::new();
+        for (id, value) in [(2u16, "Domain"), (1, "Server")] {
+            let value = utf16le(value);
+            target_info.extend_from_slice(&id.to_le_bytes());
+            target_info.extend_from_slice(&(value.len() as u16).to_le_bytes());
+            target_info.extend_from_slice(&value);
+        }
+        target_info.extend_from_slice(&[0; 4]);
+        let challenge = Challenge {
+            flags: NEGOTIATE_FLAGS,
+            server_challenge: [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef],
+            target_info,
+        };
+
+        assert_eq!(
+            hex(&md4(&utf16le("Password"))),
+            "a4f49c406510bdcab6824ee7c30fd852"
+        );
+        let message = challenge.authenticate(&auth, [0xaa; 8], 0);
+        let lm = security_buffer(&message, 12).unwrap();
+        let nt = security_buffer(&message, 20).unwrap();
+        assert_eq!(
+            hex(&message[lm]),
+            "86c35097ac9cec102554764a57cccc19aaaaaaaaaaaaaaaa"
+        );
+        assert_eq!(hex(&message[nt][..16]), "68cd0ab851e51c96aabc927bebef6a1c");
+    }
+}
@@ -0,0 +1,188 @@
+//! OAuth 2.0 access tokens, for the `Authorization` header of a client.
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use antidote::Mutex;
+use http::header::ACCEPT;
+use log::trace;
+use serde::Deserialize;
+
+use super::auth::{AuthProvider, AuthToken};
+use super::http::Client;
+
+/// How long before it expires a token is replaced, so that it does not
+/// expire on the way to the server.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// The fetching of OAuth 2.0 access tokens from a token endpoint, with the
+/// client credentials or a refresh token grant.
+///
+/// Turned into an [`AuthProvider`], the last token is added to requests
+/// until it expires or the server refuses it, then a new one is fetched. A
+/// refresh token the endpoint rotates is kept for the next fetch.
+///
+/// # Example
+///
+/// ```rust
+/// use rquest::OAuth2;
+/// # fn doc() -> Result<(), rquest::Error> {
+/// let oauth = OAuth2::client_credentials("https://auth.example.com/token", "id", "secret")
+///     .scope("orders:read");
+/// let client = rquest::Client::builder()
+///     .base_url("https://api.example.com")
+///     .default_auth(oauth.into())
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Optional
+///
+/// This requires the optional `json` feature enabled.
+pub struct OAuth2 {
+    token_url: String,
+    client_id: String,
+    client_secret: Option<String>,
+    refresh_token: Option<String>,
+    scopes: Vec<String>,
+    client: OnceLock<Client>,
+}
+
+impl OAuth2 {
+    /// Fetch tokens with the client credentials grant, authenticating with
+    /// HTTP Basic.
+    pub fn client_credentials<U, I, S>(token_url: U, client_id: I, client_secret: S) -> OAuth2
+    where
+        U: Into<String>,
+        I: Into<String>,
+        S: Into<String>,
+    {
+        OAuth2 {
+            token_url: token_url.into(),
+            client_id: client_id.into(),
+            client_secret: Some(client_secret.into()),
+            refresh_token: None,
+            scopes: Vec::new(),
+            client: OnceLock::new(),
+        }
+    }
+
+    /// Fetch tokens with the refresh token grant, for a public client
+    /// unless [`client_secret`](OAuth2::client_secret) is set.
+    pub fn refresh_token<U, I, R>(token_url: U, client_id: I, refresh_token: R) -> OAuth2
+    where
+        U: Into<String>,
+        I: Into<String>,
+        R: Into<String>,
+    {
+        OAuth2 {
+            token_url: token_url.into(),
+            client_id: client_id.into(),
+            client_secret: None,
+            refresh_token: Some(refresh_token.into()),
+            scopes: Vec::new(),
+            client: OnceLock::new(),
+        }
+    }
+
+    /// Set the secret of a confidential client, sent with HTTP Basic.
+    pub fn client_secret<S: Into<String>>(mut self, client_secret: S) -> OAuth2 {
+        self.client_secret = Some(client_secret.into());
+        self
+    }
+
+    /// Request the scope `scope`, in addition to the others.
+    pub fn scope<S: Into<String>>(mut self, scope: S) -> OAuth2 {
+        self.scopes.push(scope.into());
+        self
+    }
+
+    /// Fetch the tokens with `client`, instead of a default one.
+    ///
+    /// It must not use the provider made of this itself.
+    pub fn client(mut self, client: Client) -> OAuth2 {
+        self.client = OnceLock::from(client);
+        self
+    }
+}
+
+impl fmt::Debug for OAuth2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth2")
+            .field("token_url", &self.token_url)
+            .field("client_id", &self.client_id)
+            .field("scopes", &self.scopes)
+            .finish()
+    }
+}
+
+impl From<OAuth2> for AuthProvider {
+    fn from(oauth: OAuth2) -> AuthProvider {
+        let oauth = Arc::new(Fetcher {
+            refresh_token: Mutex::new(oauth.refresh_token.clone()),
+            oauth,
+        });
+        AuthProvider::new(move || oauth.clone().fetch())
+    }
+}
+
+struct Fetcher {
+    oauth: OAuth2,
+    refresh_token: Mutex<Option<String>>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+}
+
+impl Fetcher {
+    async fn fetch(self: Arc<Self>) -> crate::Result<AuthToken> {
+        let oauth = &self.oauth;
+        let refresh_token = self.refresh_token.lock().clone();
+
+        let mut form = Vec::new();
+        match refresh_token {
+            Some(ref token) => {
+                form.push(("grant_type", "refresh_token"));
+                form.push(("refresh_token", token.as_str()));
+            }
+            None => form.push(("grant_type", "client_credentials")),
+        }
+        let scope = oauth.scopes.join(" ");
+        if !scope.is_empty() {
+            form.push(("scope", scope.as_str()));
+        }
+        if oauth.client_secret.is_none() {
+            form.push(("client_id", oauth.client_id.as_str()));
+        }
+
+        trace!("fetching OAuth 2.0 token from {}", oauth.token_url);
+        let client = oauth.client.get_or_init(Client::new);
+        let mut req = client
+            .post(oauth.token_url.as_str())
+            .header(ACCEPT, "application/json")
+            .form(&form);
+        if let Some(ref secret) = oauth.client_secret {
+            req = req.basic_auth(&oauth.client_id, Some(secret));
+        }
+        let token = req
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<TokenResponse>()
+            .await?;
+
+        if let Some(rotated) = token.refresh_token {
+            *self.refresh_token.lock() = Some(rotated);
+        }
+        let mut auth = AuthToken::bearer(&token.access_token)?;
+        if let Some(expires_in) = token.expires_in {
+            auth = auth.expires_in(Duration::from_secs(expires_in).saturating_sub(EXPIRY_MARGIN));
+        }
+        Ok(auth)
+    }
+}
@@ -0,0 +1,257 @@
+//! Pacing the requests to the origins asking for fewer, with `Retry-After`
+//! and rate limit headers.
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use antidote::Mutex;
+use http::header::{HeaderMap, HeaderName, RETRY_AFTER};
+use http::StatusCode;
+use log::debug;
+use lru::LruCache;
+use url::Url;
+
+use super::http::Client;
+use super::request::Request;
+use super::response::Response;
+use crate::util::parse_http_date;
+
+/// How many origins a client remembers the pace of.
+const MAX_ENTRIES: usize = 1024;
+
+/// The longest a request waits, whatever the origin asks for.
+const MAX_DELAY: Duration = Duration::from_secs(10 * 60);
+
+/// The first wait after a `429` without `Retry-After`, doubled by each of
+/// the following ones.
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The longest wait after `429`s without `Retry-After`.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+const RATELIMIT: HeaderName = HeaderName::from_static("ratelimit");
+const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+const X_RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+const X_RATELIMIT_RESET: HeaderName = HeaderName::from_static("x-ratelimit-reset");
+
+/// When the origins asking for a pause can be sent requests again, by
+/// origin.
+pub(crate) struct Pacer {
+    entries: Mutex<LruCache<String, Pause>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Pause {
+    until: Instant,
+    /// The `429`s in a row without `Retry-After`.
+    backoffs: u32,
+}
+
+impl Pacer {
+    pub(crate) fn new() -> Pacer {
+        Pacer {
+            entries: Mutex::new(LruCache::new(
+                NonZeroUsize::new(MAX_ENTRIES).expect("non-zero"),
+            )),
+        }
+    }
+
+    /// How long to wait before sending a request to `url`, if its origin
+    /// asked for a pause.
+    pub(crate) fn delay(&self, url: &Url) -> Option<Duration> {
+        let origin = url.origin().ascii_serialization();
+        let pause = *self.entries.lock().get(&origin)?;
+        pause.until.checked_duration_since(Instant::now())
+    }
+
+    /// Remember the pause a response from `url` asks for, if any.
+    pub(crate) fn observe(&self, url: &Url, status: StatusCode, headers: &HeaderMap) {
+        let origin = url.origin().ascii_serialization();
+        let mut entries = self.entries.lock();
+        let previous = entries.get(&origin).copied();
+
+        let mut backoffs = 0;
+        let mut delay = rate_limit_reset(headers, SystemTime::now());
+        if matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+        ) {
+            let retry_after = headers
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| retry_after(value, SystemTime::now()));
+            delay = match (retry_after, status) {
+                (Some(retry_after), _) => delay.max(Some(retry_after)),
+                (None, StatusCode::TOO_MANY_REQUESTS) => {
+                    backoffs = previous.map_or(0, |pause| pause.backoffs) + 1;
+                    let backoff = BASE_BACKOFF.saturating_mul(1u32 << (backoffs - 1).min(16));
+                    delay.max(Some(backoff.min(MAX_BACKOFF)))
+                }
+                (None, _) => delay,
+            };
+        }
+
+        let now = Instant::now();
+        match delay {
+            Some(delay) => {
+                let until = now + delay.min(MAX_DELAY);
+                debug!("pausing requests to {} for {:?}", origin, delay);
+                let until = previous.map_or(until, |pause| pause.until.max(until));
+                entries.put(origin, Pause { until, backoffs });
+            }
+            // the origin is over its pause, and its backoff
+            None if previous.is_some_and(|pause| pause.until <= now) => {
+                entries.pop(&origin);
+            }
+            None => {}
+        }
+    }
+}
+
+/// Parse a `Retry-After` value, in seconds or as a date, into the delay it
+/// asks for from `now`.
+fn retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = parse_http_date(value)?;
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+/// The delay until the quota of the client is reset, if the rate limit
+/// headers say it is used up.
+///
+/// Both the `RateLimit` header of the IETF drafts, with `r` and `t`
+/// parameters or `remaining` and `reset` ones, and the separate
+/// `RateLimit-Remaining` and `RateLimit-Reset` headers are understood, with
+/// or without an `X-` prefix. A reset too large to be a delay is taken as a
+/// Unix timestamp, as some servers send.
+fn rate_limit_reset(headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+
+    let (remaining, reset) = match headers.get(RATELIMIT).and_then(|v| v.to_str().ok()) {
+        Some(value) => {
+            let mut remaining = None;
+            let mut reset = None;
+            for param in value.split([',', ';']) {
+                let Some((name, value)) = param.split_once('=') else {
+                    continue;
+                };
+                let value = value.trim().trim_matches('"').parse::<u64>().ok();
+                match name.trim() {
+                    "r" | "remaining" => remaining = remaining.or(value),
+                    "t" | "reset" => reset = reset.or(value),
+                    _ => {}
+                }
+            }
+            (remaining, reset)
+        }
+        None => (
+            header(RATELIMIT_REMAINING).or_else(|| header(X_RATELIMIT_REMAINING)),
+            header(RATELIMIT_RESET).or_else(|| header(X_RATELIMIT_RESET)),
+        ),
+    };
+
+    if remaining? > 0 {
+        return None;
+    }
+    let reset = Duration::from_secs(reset?);
+    let since_epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+    if reset > since_epoch / 2 {
+        Some(reset.saturating_sub(since_epoch))
+    } else {
+        Some(reset)
+    }
+}
+
+/// Send `req` once its origin is over its pause.
+pub(super) async fn send(client: Client, delay: Duration, req: Request) -> crate::Result<Response> {
+    tokio::time::sleep(delay).await;
+    client.send_request(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn parse_retry_after() {
+        let now = UNIX_EPOCH + Duration::from_secs(784111777);
+        assert_eq!(retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(
+            retry_after("Sun, 06 Nov 1994 08:49:47 GMT", now),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            retry_after("Sun, 06 Nov 1994 08:49:27 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn parse_rate_limits() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let reset = |pairs| rate_limit_reset(&headers(pairs), now);
+
+        assert_eq!(
+            reset(&[("ratelimit", "\"default\";r=0;t=30")]),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            reset(&[("ratelimit", "limit=100, remaining=0, reset=5")]),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(reset(&[("ratelimit", "\"default\";r=10;t=30")]), None);
+        assert_eq!(
+            reset(&[("ratelimit-remaining", "0"), ("ratelimit-reset", "7")]),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(
+            reset(&[
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", "1700000042")
+            ]),
+            Some(Duration::from_secs(42))
+        );
+        assert_eq!(reset(&[("x-ratelimit-remaining", "0")]), None);
+    }
+
+    #[test]
+    fn backs_off_without_retry_after() {
+        let pacer = Pacer::new();
+        let url = Url::parse("https://example.com/a").unwrap();
+        let other = Url::parse("https://example.org/").unwrap();
+
+        pacer.observe(&url, StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new());
+        let first = pacer.delay(&url).unwrap();
+        assert!(first <= BASE_BACKOFF);
+        assert_eq!(pacer.delay(&other), None);
+
+        pacer.observe(&url, StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new());
+        assert!(pacer.delay(&url).unwrap() > BASE_BACKOFF);
+
+        pacer.observe(
+            &url,
+            StatusCode::SERVICE_UNAVAILABLE,
+            &headers(&[("retry-after", "3600")]),
+        );
+        assert!(pacer.delay(&url).unwrap() > MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,204 @@
+//! A browser tab, sending the requests of the documents it loads with the
+//! headers a browser would.
+use std::collections::HashMap;
+
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use url::Url;
+
+use super::client_hints::{parse_hints, ACCEPT_CH, CRITICAL_CH};
+use super::fetch::{self, FetchRequest, RequestCredentials, RequestMode};
+use super::http::Client;
+use super::response::Response;
+use crate::into_url::IntoUrlSealed;
+use crate::{IntoUrl, Method};
+
+const SEC_FETCH_USER: HeaderName = HeaderName::from_static("sec-fetch-user");
+
+/// A browser tab, bound to a client.
+///
+/// A `Page` remembers the document it is on, so the requests it sends carry
+/// the `Referer`, `Origin` and `Sec-Fetch-*` headers of requests made from
+/// that document. It also remembers the client hints each origin asked for
+/// with `Accept-CH`, and sends them to that origin from then on.
+///
+/// To construct a `Page`, refer to [`Client::page`].
+///
+/// # Example
+///
+/// ```
+/// # use rquest::{FetchRequest, Method, Url};
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = rquest::Client::new();
+/// let mut page = client.page().client_hint(
+///     rquest::header::HeaderName::from_static("sec-ch-ua-platform-version"),
+///     rquest::header::HeaderValue::from_static("\"15.0.0\""),
+/// );
+///
+/// // a document, then the XHR it makes
+/// page.navigate("https://www.example.com/").await?;
+/// let res = page
+///     .fetch(FetchRequest::new(
+///         Method::GET,
+///         Url::parse("https://www.example.com/api/items")?,
+///     ))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Page {
+    client: Client,
+    history: Vec<Url>,
+    /// The values of the client hints origins may ask for.
+    hints: HeaderMap,
+    /// The client hints each origin asked for, by origin.
+    accepted: HashMap<String, Vec<HeaderName>>,
+}
+
+impl Page {
+    pub(super) fn new(client: Client) -> Page {
+        Page {
+            client,
+            history: Vec::new(),
+            hints: HeaderMap::new(),
+            accepted: HashMap::new(),
+        }
+    }
+
+    /// Set the value of a client hint, sent to the origins that ask for it
+    /// with `Accept-CH`.
+    ///
+    /// The low entropy hints a browser always sends, such as `Sec-CH-UA`,
+    /// belong in the headers of the client instead, and the hints of every
+    /// page of a client in [`ClientBuilder::client_hints`].
+    ///
+    /// [`ClientBuilder::client_hints`]: crate::ClientBuilder::client_hints
+    pub fn client_hint(mut self, name: HeaderName, value: HeaderValue) -> Page {
+        self.hints.insert(name, value);
+        self
+    }
+
+    /// The URL of the document the page is on, after redirects.
+    pub fn url(&self) -> Option<&Url> {
+        self.history.last()
+    }
+
+    /// The URLs of the documents the page was on, the current one last.
+    pub fn history(&self) -> &[Url] {
+        &self.history
+    }
+
+    /// The client that sends the requests of the page.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Load a document, as if the user followed a link to it from the
+    /// current document, or typed its URL if there's none.
+    ///
+    /// The page moves to the URL of the response, even if its status is an
+    /// error, as a browser displays error pages. Before that, a response
+    /// with a `Critical-CH` header naming hints that weren't sent is retried
+    /// once with them.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the URL can't be parsed, or on the errors of
+    /// [`Client::fetch`].
+    pub async fn navigate<U: IntoUrl>(&mut self, url: U) -> crate::Result<Response> {
+        let url = url.into_url()?;
+
+        let mut res = self.send_navigation(url.clone()).await?;
+        let origin = res.url().origin();
+        // the hints went with the first request, whatever its redirects
+        let sent = match self.accepted.get(&origin.ascii_serialization()) {
+            Some(sent) if origin == url.origin() => sent.clone(),
+            _ => Vec::new(),
+        };
+        if self.accept_hints(&res) && self.missing_critical_hints(&res, &sent) {
+            let url = res.url().clone();
+            res = self.send_navigation(url).await?;
+            self.accept_hints(&res);
+        }
+
+        self.history.push(res.url().clone());
+        Ok(res)
+    }
+
+    /// Send a request from the current document, such as an XHR.
+    ///
+    /// A request without a [`referrer`](FetchRequest::referrer) gets the URL
+    /// of the current document. The request carries the client hints its
+    /// origin asked for.
+    ///
+    /// # Errors
+    ///
+    /// This method fails on the errors of [`Client::fetch`].
+    pub async fn fetch(&self, mut request: FetchRequest) -> crate::Result<Response> {
+        if request.referrer.is_none() {
+            request.referrer = self.url().cloned();
+        }
+        self.add_hints(&request.url, &mut request.headers);
+        self.client.fetch(request).await
+    }
+
+    async fn send_navigation(&self, url: Url) -> crate::Result<Response> {
+        let mut request = FetchRequest {
+            mode: RequestMode::Navigate,
+            credentials: RequestCredentials::Include,
+            referrer: self.url().cloned(),
+            ..FetchRequest::new(Method::GET, url)
+        };
+        if fetch::is_trustworthy(&request.url) {
+            request
+                .headers
+                .insert(SEC_FETCH_USER, HeaderValue::from_static("?1"));
+        }
+        self.add_hints(&request.url, &mut request.headers);
+        self.client.fetch(request).await
+    }
+
+    /// Add the client hints the origin of `url` asked for to `headers`.
+    fn add_hints(&self, url: &Url, headers: &mut HeaderMap) {
+        let Some(accepted) = self.accepted.get(&url.origin().ascii_serialization()) else {
+            return;
+        };
+        for name in accepted {
+            if let Some(value) = self.hints.get(name) {
+                headers.entry(name).or_insert_with(|| value.clone());
+            }
+        }
+    }
+
+    /// Remember the client hints a document response asked for, returning
+    /// whether it had an `Accept-CH` header.
+    ///
+    /// Like browsers, only trustworthy origins are listened to, and a new
+    /// `Accept-CH` replaces the hints an origin asked for before.
+    fn accept_hints(&mut self, res: &Response) -> bool {
+        if !fetch::is_trustworthy(res.url()) || !res.headers().contains_key(ACCEPT_CH) {
+            return false;
+        }
+
+        let hints = parse_hints(res.headers(), ACCEPT_CH);
+        let origin = res.url().origin().ascii_serialization();
+        if hints.is_empty() {
+            self.accepted.remove(&origin);
+        } else {
+            self.accepted.insert(origin, hints);
+        }
+        true
+    }
+
+    /// Whether a response has a `Critical-CH` header naming hints the page
+    /// has a value for, but that weren't among the `sent` ones.
+    fn missing_critical_hints(&self, res: &Response, sent: &[HeaderName]) -> bool {
+        let origin = res.url().origin().ascii_serialization();
+        let accepted = self.accepted.get(&origin);
+        parse_hints(res.headers(), CRITICAL_CH).iter().any(|name| {
+            !sent.contains(name)
+                && self.hints.contains_key(name)
+                && accepted.is_some_and(|accepted| accepted.contains(name))
+        })
+    }
+}
@@ -0,0 +1,36 @@
+/// A bundle of client settings tuned for a common workload.
+///
+/// Apply one with [`ClientBuilder::preset`], then adjust individual settings
+/// as usual. Later builder calls override the preset.
+///
+/// [`ClientBuilder::preset`]: crate::ClientBuilder::preset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Preset {
+    /// Many requests spread over many hosts.
+    ///
+    /// - keeps at most 2 idle connections per host, for 30 seconds
+    /// - connect timeout of 10 seconds, total timeout of 30 seconds
+    /// - follows up to 10 redirects, sending `Referer`
+    /// - retries HTTP/2 requests up to 2 times
+    /// - decompresses every supported encoding
+    Crawler,
+    /// Many requests to a handful of hosts.
+    ///
+    /// - keeps up to 32 idle connections per host, for 90 seconds
+    /// - `TCP_NODELAY`, connect timeout of 5 seconds, total timeout of 30 seconds
+    /// - follows up to 5 redirects
+    /// - retries HTTP/2 requests up to 3 times
+    /// - decompresses every supported encoding
+    ApiClient,
+    /// Few, large responses.
+    ///
+    /// - keeps up to 4 idle connections per host, for 90 seconds
+    /// - connect timeout of 15 seconds, no total timeout, and a read timeout of
+    ///   60 seconds to detect stalled transfers
+    /// - follows up to 10 redirects
+    /// - retries HTTP/2 requests once
+    /// - no decompression, so bodies are received as sent, with their
+    ///   `Content-Length`
+    Downloader,
+}
@@ -0,0 +1,93 @@
+//! Progress reports for request and response bodies.
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use http_body::{Body as HttpBody, Frame};
+use pin_project_lite::pin_project;
+
+use super::body::{self, Body, ResponseBody};
+
+/// A callback receiving the bytes transferred so far, and the total when
+/// it is known.
+pub(crate) type ProgressCallback = Arc<dyn Fn(u64, Option<u64>) + Send + Sync>;
+
+pin_project! {
+    /// A body reporting each chunk going through.
+    pub(crate) struct ProgressBody<B> {
+        #[pin]
+        inner: B,
+        callback: ProgressCallback,
+        transferred: u64,
+        total: Option<u64>,
+    }
+}
+
+impl<B> HttpBody for ProgressBody<B>
+where
+    B: HttpBody<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = futures_util::ready!(this.inner.poll_frame(cx));
+
+        if let Some(data) = frame
+            .as_ref()
+            .and_then(|frame| frame.as_ref().ok())
+            .and_then(Frame::data_ref)
+        {
+            if !data.is_empty() {
+                *this.transferred += data.len() as u64;
+                (this.callback)(*this.transferred, *this.total);
+            }
+        }
+
+        Poll::Ready(frame)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+}
+
+fn with_progress<B>(inner: B, callback: ProgressCallback) -> ProgressBody<B>
+where
+    B: HttpBody,
+{
+    let total = inner.size_hint().exact();
+    ProgressBody {
+        inner,
+        callback,
+        transferred: 0,
+        total,
+    }
+}
+
+/// Report the progress of a request body being sent.
+pub(crate) fn upload(body: Body, callback: Option<&ProgressCallback>) -> Body {
+    match callback {
+        Some(callback) => Body::wrap(with_progress(body, callback.clone())),
+        None => body,
+    }
+}
+
+/// Report the progress of a response body being received.
+pub(crate) fn download(body: ResponseBody, callback: Option<&ProgressCallback>) -> ResponseBody {
+    match callback {
+        Some(callback) => body::boxed(with_progress(body, callback.clone())),
+        None => body,
+    }
+}
@@ -0,0 +1,618 @@
+//! Query strings and forms with sequences and nested values.
+use std::fmt;
+
+use serde::ser::{self, Serialize};
+
+/// How sequences are serialized in a query string or form.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ArrayStyle {
+    /// The key repeated for each element: `a=1&a=2`.
+    #[default]
+    Repeat,
+    /// The key suffixed with brackets: `a[]=1&a[]=2`.
+    Brackets,
+    /// The key suffixed with the index of each element: `a[0]=1&a[1]=2`.
+    Indices,
+    /// The elements joined with commas: `a=1,2`.
+    Comma,
+}
+
+/// How the fields of nested structs and maps are named in a query string
+/// or form.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NestedStyle {
+    /// The field in brackets, as PHP and Rails read them: `a[b]=1`.
+    #[default]
+    Brackets,
+    /// The field after a dot: `a.b=1`.
+    Dots,
+}
+
+/// How a query string or form serializes sequences and nested values,
+/// which `serde_urlencoded` rejects.
+///
+/// Set it for a client with [`ClientBuilder::query_style`], or for one
+/// value with [`RequestBuilder::query_with`] and
+/// [`RequestBuilder::form_with`].
+///
+/// [`ClientBuilder::query_style`]: crate::ClientBuilder::query_style
+/// [`RequestBuilder::query_with`]: crate::RequestBuilder::query_with
+/// [`RequestBuilder::form_with`]: crate::RequestBuilder::form_with
+///
+/// # Example
+///
+/// ```
+/// use rquest::{ArrayStyle, QueryStyle};
+///
+/// # fn run() -> Result<(), rquest::Error> {
+/// #[derive(serde::Serialize)]
+/// struct Filter<'a> {
+///     tags: &'a [&'a str],
+/// }
+///
+/// let req = rquest::Client::new()
+///     .get("https://example.com/search")
+///     .query_with(
+///         &Filter { tags: &["a", "b"] },
+///         QueryStyle::new().arrays(ArrayStyle::Brackets),
+///     )
+///     .build()?;
+/// assert_eq!(req.url().query(), Some("tags%5B%5D=a&tags%5B%5D=b"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueryStyle {
+    arrays: ArrayStyle,
+    nested: NestedStyle,
+}
+
+impl QueryStyle {
+    /// Repeated keys for sequences, and brackets for nested fields.
+    pub fn new() -> QueryStyle {
+        QueryStyle::default()
+    }
+
+    /// Set how sequences are serialized.
+    pub fn arrays(mut self, style: ArrayStyle) -> QueryStyle {
+        self.arrays = style;
+        self
+    }
+
+    /// Set how the fields of nested values are named.
+    pub fn nested(mut self, style: NestedStyle) -> QueryStyle {
+        self.nested = style;
+        self
+    }
+}
+
+/// Flatten `value` into the key-value pairs of a query string or form.
+pub(crate) fn to_pairs<T: Serialize + ?Sized>(
+    value: &T,
+    style: QueryStyle,
+) -> Result<Vec<(String, String)>, Error> {
+    let mut pairs = Vec::new();
+    value.serialize(Serializer {
+        key: Key::Top,
+        style,
+        pairs: &mut pairs,
+    })?;
+    Ok(pairs)
+}
+
+/// An error serializing a query string or form.
+#[derive(Debug)]
+pub(crate) struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error(msg.to_string())
+    }
+}
+
+/// Where the value being serialized sits.
+#[derive(Clone)]
+enum Key {
+    /// The whole value: a struct, a map, or a sequence of pairs.
+    Top,
+    /// An element of a top-level sequence, which must be a key-value pair.
+    Pair,
+    /// A value, under this key.
+    Named(String),
+}
+
+struct Serializer<'a> {
+    key: Key,
+    style: QueryStyle,
+    pairs: &'a mut Vec<(String, String)>,
+}
+
+impl<'a> Serializer<'a> {
+    fn push(self, value: String) -> Result<(), Error> {
+        match self.key {
+            Key::Named(key) => {
+                self.pairs.push((key, value));
+                Ok(())
+            }
+            _ => Err(Error(
+                "top-level value must be a struct, a map or a sequence of pairs".to_owned(),
+            )),
+        }
+    }
+
+    /// The key of the field `name` of this value.
+    fn field(&self, name: &str) -> Result<Key, Error> {
+        match self.key {
+            Key::Top => Ok(Key::Named(name.to_owned())),
+            Key::Pair => Err(Error(
+                "elements of a top-level sequence must be pairs".to_owned(),
+            )),
+            Key::Named(ref key) => Ok(Key::Named(match self.style.nested {
+                NestedStyle::Brackets => format!("{key}[{name}]"),
+                NestedStyle::Dots => format!("{key}.{name}"),
+            })),
+        }
+    }
+
+    fn nested(&mut self, key: Key) -> Serializer<'_> {
+        Serializer {
+            key,
+            style: self.style,
+            pairs: &mut *self.pairs,
+        }
+    }
+
+    fn seq(mut self, variant: Option<&str>) -> Result<SeqSerializer<'a>, Error> {
+        if let Some(variant) = variant {
+            self.key = self.field(variant)?;
+        }
+        Ok(SeqSerializer {
+            key: self.key,
+            style: self.style,
+            pairs: self.pairs,
+            index: 0,
+            pair_key: None,
+            joined: Vec::new(),
+        })
+    }
+
+    fn map(mut self, variant: Option<&str>) -> Result<MapSerializer<'a>, Error> {
+        if let Some(variant) = variant {
+            self.key = self.field(variant)?;
+        }
+        Ok(MapSerializer {
+            parent: self,
+            next_key: None,
+        })
+    }
+}
+
+/// Serialize a map key, or an element joined with commas, to a string.
+fn to_scalar<T: Serialize + ?Sized>(value: &T, style: QueryStyle) -> Result<String, Error> {
+    let mut pairs = Vec::new();
+    value.serialize(Serializer {
+        key: Key::Named(String::new()),
+        style,
+        pairs: &mut pairs,
+    })?;
+    match (pairs.pop(), pairs.is_empty()) {
+        (Some((_, value)), true) => Ok(value),
+        _ => Err(Error(
+            "map keys and joined elements must be single values".to_owned(),
+        )),
+    }
+}
+
+macro_rules! serialize_display {
+    ($($method:ident: $ty:ty),*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<(), Error> {
+                self.push(v.to_string())
+            }
+        )*
+    };
+}
+
+impl<'a> ser::Serializer for Serializer<'a> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeTuple = SeqSerializer<'a>;
+    type SerializeTupleStruct = SeqSerializer<'a>;
+    type SerializeTupleVariant = SeqSerializer<'a>;
+    type SerializeMap = MapSerializer<'a>;
+    type SerializeStruct = MapSerializer<'a>;
+    type SerializeStructVariant = MapSerializer<'a>;
+
+    serialize_display! {
+        serialize_bool: bool,
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_i128: i128,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64,
+        serialize_u128: u128,
+        serialize_f32: f32,
+        serialize_f64: f64,
+        serialize_char: char,
+        serialize_str: &str
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<(), Error> {
+        Err(Error("bytes can not be serialized in a query".to_owned()))
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.push(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        mut self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        let key = self.field(variant)?;
+        value.serialize(self.nested(key))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SeqSerializer<'a>, Error> {
+        self.seq(None)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<SeqSerializer<'a>, Error> {
+        self.seq(None)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<SeqSerializer<'a>, Error> {
+        self.seq(None)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SeqSerializer<'a>, Error> {
+        self.seq(Some(variant))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer<'a>, Error> {
+        self.map(None)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<MapSerializer<'a>, Error> {
+        self.map(None)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<MapSerializer<'a>, Error> {
+        self.map(Some(variant))
+    }
+}
+
+struct SeqSerializer<'a> {
+    key: Key,
+    style: QueryStyle,
+    pairs: &'a mut Vec<(String, String)>,
+    index: usize,
+    /// The key of a pair, once its first element is serialized.
+    pair_key: Option<String>,
+    /// The elements serialized so far, in the comma style.
+    joined: Vec<String>,
+}
+
+impl SeqSerializer<'_> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let index = self.index;
+        self.index += 1;
+
+        let key = match self.key {
+            Key::Top => Key::Pair,
+            Key::Pair => {
+                return match (index, self.pair_key.take()) {
+                    (0, _) => {
+                        self.pair_key = Some(to_scalar(value, self.style)?);
+                        Ok(())
+                    }
+                    (1, Some(key)) => value.serialize(Serializer {
+                        key: Key::Named(key),
+                        style: self.style,
+                        pairs: &mut *self.pairs,
+                    }),
+                    _ => Err(Error(
+                        "elements of a top-level sequence must be pairs".to_owned(),
+                    )),
+                };
+            }
+            Key::Named(ref key) => match self.style.arrays {
+                ArrayStyle::Repeat => Key::Named(key.clone()),
+                ArrayStyle::Brackets => Key::Named(format!("{key}[]")),
+                ArrayStyle::Indices => Key::Named(format!("{key}[{index}]")),
+                ArrayStyle::Comma => {
+                    self.joined.push(to_scalar(value, self.style)?);
+                    return Ok(());
+                }
+            },
+        };
+
+        value.serialize(Serializer {
+            key,
+            style: self.style,
+            pairs: &mut *self.pairs,
+        })
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        match self.key {
+            Key::Pair if self.index != 2 => Err(Error(
+                "elements of a top-level sequence must be pairs".to_owned(),
+            )),
+            Key::Named(key) if self.style.arrays == ArrayStyle::Comma && self.index > 0 => {
+                self.pairs.push((key, self.joined.join(",")));
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+struct MapSerializer<'a> {
+    parent: Serializer<'a>,
+    next_key: Option<Key>,
+}
+
+impl MapSerializer<'_> {
+    fn field<T: Serialize + ?Sized>(&mut self, name: &str, value: &T) -> Result<(), Error> {
+        let key = self.parent.field(name)?;
+        value.serialize(self.parent.nested(key))
+    }
+}
+
+impl ser::SerializeMap for MapSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        let key = to_scalar(key, self.parent.style)?;
+        self.next_key = Some(self.parent.field(&key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| Error("map value without a key".to_owned()))?;
+        value.serialize(self.parent.nested(key))
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(name, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(name, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Filter {
+        q: &'static str,
+        tags: Vec<u32>,
+        page: Page,
+        empty: Option<u8>,
+    }
+
+    #[derive(Serialize)]
+    struct Page {
+        size: u8,
+        sort: Vec<&'static str>,
+    }
+
+    fn encode(style: QueryStyle) -> String {
+        let filter = Filter {
+            q: "rust",
+            tags: vec![1, 2],
+            page: Page {
+                size: 10,
+                sort: vec!["name", "date"],
+            },
+            empty: None,
+        };
+        to_pairs(&filter, style)
+            .unwrap()
+            .into_iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    #[test]
+    fn array_styles() {
+        assert_eq!(
+            encode(QueryStyle::new()),
+            "q=rust&tags=1&tags=2&page[size]=10&page[sort]=name&page[sort]=date"
+        );
+        assert_eq!(
+            encode(QueryStyle::new().arrays(ArrayStyle::Brackets)),
+            "q=rust&tags[]=1&tags[]=2&page[size]=10&page[sort][]=name&page[sort][]=date"
+        );
+        assert_eq!(
+            encode(QueryStyle::new().arrays(ArrayStyle::Indices)),
+            "q=rust&tags[0]=1&tags[1]=2&page[size]=10&page[sort][0]=name&page[sort][1]=date"
+        );
+        assert_eq!(
+            encode(
+                QueryStyle::new()
+                    .arrays(ArrayStyle::Comma)
+                    .nested(NestedStyle::Dots)
+            ),
+            "q=rust&tags=1,2&page.size=10&page.sort=name,date"
+        );
+    }
+
+    #[test]
+    fn pairs_and_maps() {
+        let pairs = to_pairs(&[("a", 1), ("b", 2)], QueryStyle::new()).unwrap();
+        assert_eq!(
+            pairs,
+            [
+                ("a".to_owned(), "1".to_owned()),
+                ("b".to_owned(), "2".to_owned())
+            ]
+        );
+
+        let map = std::collections::BTreeMap::from([(1, vec!["x"])]);
+        let pairs = to_pairs(&map, QueryStyle::new()).unwrap();
+        assert_eq!(pairs, [("1".to_owned(), "x".to_owned())]);
+
+        assert!(to_pairs(&"scalar", QueryStyle::new()).is_err());
+        assert!(to_pairs(&[("a", 1, 2)], QueryStyle::new()).is_err());
+    }
+}
@@ -4,20 +4,37 @@ use std::future::Future;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::time::Duration;
 
-use http::{request::Parts, Request as HttpRequest, Version};
+use http::{request::Parts, uri::PathAndQuery, Request as HttpRequest, Version};
 use serde::Serialize;
 
+use super::abort::AbortHandle;
 use super::body::Body;
+use super::compression::Encoding;
+use super::conditional::ConditionalRequest;
+use super::curl;
+use super::digest::DigestAuth;
+use super::download::Download;
 use super::http::{Client, Pending};
 #[cfg(feature = "multipart")]
 use super::multipart;
+#[cfg(feature = "ntlm")]
+use super::ntlm::NtlmAuth;
+use super::progress::ProgressCallback;
+use super::query::QueryStyle;
 use super::response::Response;
+use super::signer::RequestSigner;
+#[cfg(feature = "aws-sigv4")]
+use super::sigv4::{AwsCredentials, AwsSigner};
+use super::sse::EventSource;
+use super::template::RequestTemplate;
+use super::throttle::Throttle;
 #[cfg(feature = "cookies")]
 use crate::cookie;
-use crate::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
-use crate::util::client::{NetworkScheme, NetworkSchemeBuilder};
+use crate::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT, CONNECTION, CONTENT_TYPE, UPGRADE,
+};
+use crate::util::client::{NetworkScheme, NetworkSchemeBuilder, RequestTarget};
 use crate::{redirect, IntoUrl, Method, Proxy, Url};
-#[cfg(feature = "cookies")]
 use std::sync::Arc;
 
 #[cfg(not(feature = "cookies"))]
@@ -28,11 +45,19 @@ type PiecesWithCookieStore = (
     Option<Body>,
     Option<Duration>,
     Option<Duration>,
+    Option<Throttle>,
+    Option<Throttle>,
+    Option<ProgressCallback>,
+    Option<ProgressCallback>,
+    Option<u64>,
+    bool,
     Option<Version>,
     Option<redirect::Policy>,
     (),
     NetworkScheme,
     Option<hyper2::ext::Protocol>,
+    Option<PathAndQuery>,
+    Option<RequestTarget>,
 );
 
 #[cfg(feature = "cookies")]
@@ -43,11 +68,19 @@ type PiecesWithCookieStore = (
     Option<Body>,
     Option<Duration>,
     Option<Duration>,
+    Option<Throttle>,
+    Option<Throttle>,
+    Option<ProgressCallback>,
+    Option<ProgressCallback>,
+    Option<u64>,
+    bool,
     Option<Version>,
     Option<redirect::Policy>,
     Option<Arc<dyn cookie::CookieStore>>,
     NetworkScheme,
     Option<hyper2::ext::Protocol>,
+    Option<PathAndQuery>,
+    Option<RequestTarget>,
 );
 
 /// A request which can be executed with `Client::execute()`.
@@ -58,12 +91,29 @@ pub struct Request {
     body: Option<Body>,
     timeout: Option<Duration>,
     read_timeout: Option<Duration>,
+    download_throttle: Option<Throttle>,
+    upload_throttle: Option<Throttle>,
+    download_progress: Option<ProgressCallback>,
+    upload_progress: Option<ProgressCallback>,
+    max_response_size: Option<u64>,
+    compress_body: Option<Encoding>,
+    decompress: bool,
     version: Option<Version>,
     redirect: Option<redirect::Policy>,
     #[cfg(feature = "cookies")]
     cookie_store: Option<Arc<dyn cookie::CookieStore>>,
     network_scheme: NetworkSchemeBuilder,
     protocol: Option<hyper2::ext::Protocol>,
+    raw_path_and_query: Option<PathAndQuery>,
+    request_target: Option<RequestTarget>,
+    digest_auth: Option<DigestAuth>,
+    #[cfg(feature = "ntlm")]
+    ntlm_auth: Option<NtlmAuth>,
+    #[cfg(feature = "negotiate")]
+    negotiate_auth: bool,
+    signer: Option<Arc<dyn RequestSigner>>,
+    abort: Option<AbortHandle>,
+    fresh_connection: bool,
 }
 
 /// A builder to construct the properties of a `Request`.
@@ -86,12 +136,29 @@ impl Request {
             body: None,
             timeout: None,
             read_timeout: None,
+            download_throttle: None,
+            upload_throttle: None,
+            download_progress: None,
+            upload_progress: None,
+            max_response_size: None,
+            compress_body: None,
+            decompress: true,
             version: None,
             redirect: None,
             #[cfg(feature = "cookies")]
             cookie_store: None,
             network_scheme: NetworkScheme::builder(),
             protocol: None,
+            raw_path_and_query: None,
+            request_target: None,
+            digest_auth: None,
+            #[cfg(feature = "ntlm")]
+            ntlm_auth: None,
+            #[cfg(feature = "negotiate")]
+            negotiate_auth: false,
+            signer: None,
+            abort: None,
+            fresh_connection: false,
         }
     }
 
@@ -131,6 +198,18 @@ impl Request {
         &mut self.headers
     }
 
+    /// Get the raw path and query, if one was set.
+    #[inline]
+    pub fn raw_path_and_query(&self) -> Option<&PathAndQuery> {
+        self.raw_path_and_query.as_ref()
+    }
+
+    /// Get a mutable reference to the raw path and query.
+    #[inline]
+    pub fn raw_path_and_query_mut(&mut self) -> &mut Option<PathAndQuery> {
+        &mut self.raw_path_and_query
+    }
+
     /// Get a mutable reference to the redirect policy.
     #[inline]
     pub fn redirect_mut(&mut self) -> &mut Option<redirect::Policy> {
@@ -198,12 +277,45 @@ impl Request {
         &mut self.version
     }
 
+    /// Get the form of the HTTP/1 request target.
+    #[inline]
+    pub fn request_target(&self) -> Option<RequestTarget> {
+        self.request_target
+    }
+
+    /// Get a mutable reference to the form of the HTTP/1 request target.
+    #[inline]
+    pub fn request_target_mut(&mut self) -> &mut Option<RequestTarget> {
+        &mut self.request_target
+    }
+
     /// Set the mutable reference to the protocol.
     #[inline]
     pub fn protocol_mut(&mut self) -> &mut Option<hyper2::ext::Protocol> {
         &mut self.protocol
     }
 
+    /// Render the request as a curl command, for debugging and bug reports.
+    ///
+    /// Only the settings of the request itself are rendered, not the default
+    /// headers or proxies of a client; see [`RequestBuilder::to_curl`] for
+    /// those. A streaming body is read from the standard input.
+    ///
+    /// The command includes the credentials of the request, if any.
+    pub fn as_curl(&self) -> String {
+        let network_scheme = self.network_scheme.clone().build();
+        curl::command(self, &self.headers, network_scheme.proxy_scheme())
+    }
+
+    pub(super) fn network_scheme(&self) -> &NetworkSchemeBuilder {
+        &self.network_scheme
+    }
+
+    #[cfg(feature = "cookies")]
+    pub(super) fn cookie_store(&self) -> Option<&Arc<dyn cookie::CookieStore>> {
+        self.cookie_store.as_ref()
+    }
+
     /// Attempt to clone the request.
     ///
     /// `None` is returned if the request can not be cloned, i.e. if the body is a stream.
@@ -215,18 +327,77 @@ impl Request {
         let mut req = Request::new(self.method().clone(), self.url().clone());
         *req.timeout_mut() = self.timeout().copied();
         *req.read_timeout_mut() = self.read_timeout().copied();
+        req.download_throttle = self.download_throttle.clone();
+        req.upload_throttle = self.upload_throttle.clone();
+        req.download_progress = self.download_progress.clone();
+        req.upload_progress = self.upload_progress.clone();
+        req.max_response_size = self.max_response_size;
+        req.compress_body = self.compress_body;
+        req.decompress = self.decompress;
         *req.headers_mut() = self.headers().clone();
+        *req.raw_path_and_query_mut() = self.raw_path_and_query.clone();
         *req.version_mut() = self.version();
+        *req.request_target_mut() = self.request_target();
         *req.redirect_mut() = self.redirect.clone();
         *req.network_scheme_mut() = self.network_scheme.clone();
         #[cfg(feature = "cookies")]
         {
             *req.cookie_store_mut() = self.cookie_store.clone();
         }
+        req.digest_auth = self.digest_auth.clone();
+        #[cfg(feature = "ntlm")]
+        {
+            req.ntlm_auth = self.ntlm_auth.clone();
+        }
+        #[cfg(feature = "negotiate")]
+        {
+            req.negotiate_auth = self.negotiate_auth;
+        }
+        req.signer = self.signer.clone();
+        req.abort = self.abort.clone();
+        req.fresh_connection = self.fresh_connection;
         req.body = body;
         Some(req)
     }
 
+    /// Whether the request is sent on a new connection of its own.
+    pub(super) fn fresh_connection(&self) -> bool {
+        self.fresh_connection
+    }
+
+    /// Take the abort handle of the request, to race it against the response.
+    pub(super) fn take_abort_handle(&mut self) -> Option<AbortHandle> {
+        self.abort.take()
+    }
+
+    /// Take the Digest credentials of the request, to answer a challenge.
+    pub(super) fn take_digest_auth(&mut self) -> Option<DigestAuth> {
+        self.digest_auth.take()
+    }
+
+    /// Take the NTLM credentials of the request, to start the handshake.
+    #[cfg(feature = "ntlm")]
+    pub(super) fn take_ntlm_auth(&mut self) -> Option<NtlmAuth> {
+        self.ntlm_auth.take()
+    }
+
+    /// Take whether the request authenticates with `Negotiate`.
+    #[cfg(feature = "negotiate")]
+    pub(super) fn take_negotiate_auth(&mut self) -> bool {
+        std::mem::take(&mut self.negotiate_auth)
+    }
+
+    /// Take the coding to compress the body with, to compress it before
+    /// anything else reads it.
+    pub(super) fn take_compress_body(&mut self) -> Option<Encoding> {
+        self.compress_body.take()
+    }
+
+    /// The signer of the request, to sign it once it is final.
+    pub(super) fn signer(&self) -> Option<&Arc<dyn RequestSigner>> {
+        self.signer.as_ref()
+    }
+
     pub(super) fn pieces(self) -> PiecesWithCookieStore {
         (
             self.method,
@@ -235,6 +406,12 @@ impl Request {
             self.body,
             self.timeout,
             self.read_timeout,
+            self.download_throttle,
+            self.upload_throttle,
+            self.download_progress,
+            self.upload_progress,
+            self.max_response_size,
+            self.decompress,
             self.version,
             self.redirect,
             #[cfg(feature = "cookies")]
@@ -243,6 +420,8 @@ impl Request {
             (),
             self.network_scheme.build(),
             self.protocol,
+            self.raw_path_and_query,
+            self.request_target,
         )
     }
 }
@@ -408,6 +587,163 @@ impl RequestBuilder {
         )
     }
 
+    /// Enable HTTP Digest authentication.
+    ///
+    /// The request is sent without credentials first. If the server answers
+    /// `401 Unauthorized` with a `Digest` challenge, the request is sent
+    /// again with an `Authorization` header answering it, if its body can
+    /// be sent again. The `MD5` and `SHA-256` algorithms, and their `-sess`
+    /// variants, are supported with `qop=auth`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let res = rquest::Client::new()
+    ///     .get("http://192.168.1.1/status.xml")
+    ///     .digest_auth("admin", "secret")
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn digest_auth<U, P>(mut self, username: U, password: P) -> RequestBuilder
+    where
+        U: fmt::Display,
+        P: fmt::Display,
+    {
+        if let Ok(ref mut req) = self.request {
+            req.digest_auth = Some(DigestAuth::new(username.to_string(), password.to_string()));
+        }
+        self
+    }
+
+    /// Enable NTLM authentication, for Windows servers.
+    ///
+    /// The `username` may be given as `DOMAIN\user` or `user@domain`. The
+    /// request is sent starting the handshake, then again answering the
+    /// challenge of the server if it answers `401 Unauthorized`, with
+    /// NTLMv2. NTLM authenticates connections, so both are sent with
+    /// HTTP/1.1, and the body must be one that can be sent again.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `ntlm` feature enabled.
+    #[cfg(feature = "ntlm")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ntlm")))]
+    pub fn ntlm_auth<U, P>(mut self, username: U, password: P) -> RequestBuilder
+    where
+        U: fmt::Display,
+        P: fmt::Display,
+    {
+        if let Ok(ref mut req) = self.request {
+            req.ntlm_auth = Some(NtlmAuth::new(username.to_string(), password.to_string()));
+        }
+        self
+    }
+
+    /// Enable `Negotiate` authentication with the Kerberos credentials of
+    /// the current user, for servers in a Windows domain or a Kerberos realm.
+    ///
+    /// The ticket is for the `HTTP/<host>` service of the host of the URL.
+    /// On Unix, GSSAPI gets it from the credentials cache, such as after
+    /// `kinit`; on Windows, SSPI gets it from the logon session.
+    ///
+    /// The request is first sent without a ticket, which is only sent if the
+    /// server answers `401 Unauthorized` with a `Negotiate` challenge, so
+    /// the body must be one that can be sent again.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `negotiate` feature enabled.
+    #[cfg(feature = "negotiate")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "negotiate")))]
+    pub fn negotiate_auth(mut self) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            req.negotiate_auth = true;
+        }
+        self
+    }
+
+    /// Sign the request with AWS Signature Version 4, for AWS or an
+    /// S3-compatible API.
+    ///
+    /// The request is signed just before it is sent, once its default
+    /// headers, cookies and body are final, adding the `x-amz-date` header,
+    /// `x-amz-security-token` for temporary credentials, `x-amz-content-sha256`
+    /// for the `s3` service, and the `Authorization` header after the other
+    /// headers. The `Host`, `Content-Type`, `Content-MD5` and `x-amz-*`
+    /// headers are signed. A streaming body is sent as `UNSIGNED-PAYLOAD`.
+    ///
+    /// Redirected requests are not signed again.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// use rquest::AwsCredentials;
+    ///
+    /// let credentials = AwsCredentials::new("AKIDEXAMPLE", "secret");
+    /// let res = rquest::Client::new()
+    ///     .put("https://bucket.s3.us-east-1.amazonaws.com/hello.txt")
+    ///     .body("hello")
+    ///     .sign_aws_v4(credentials, "us-east-1", "s3")
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `aws-sigv4` feature enabled.
+    #[cfg(feature = "aws-sigv4")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "aws-sigv4")))]
+    pub fn sign_aws_v4<R, S>(
+        self,
+        credentials: AwsCredentials,
+        region: R,
+        service: S,
+    ) -> RequestBuilder
+    where
+        R: Into<String>,
+        S: Into<String>,
+    {
+        self.signer(AwsSigner::new(credentials, region.into(), service.into()))
+    }
+
+    /// Sign the request with `signer` just before it is sent, instead of
+    /// the signer of the client.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// use rquest::header::HeaderValue;
+    /// use rquest::SigningRequest;
+    /// # fn hmac_hex(data: &[u8]) -> String { String::new() }
+    ///
+    /// let res = rquest::Client::new()
+    ///     .post("https://api.example.com/orders")
+    ///     .body("{}")
+    ///     .signer(|req: &mut SigningRequest<'_>| {
+    ///         let payload = [req.uri().path().as_bytes(), req.body().unwrap_or_default()].concat();
+    ///         let signature = HeaderValue::from_str(&hmac_hex(&payload)).expect("hex is valid");
+    ///         req.headers_mut().insert("x-signature", signature);
+    ///         Ok(())
+    ///     })
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn signer<S: RequestSigner>(mut self, signer: S) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            req.signer = Some(Arc::new(signer));
+        }
+        self
+    }
+
     /// Set the request body.
     pub fn body<T: Into<Body>>(mut self, body: T) -> RequestBuilder {
         if let Ok(ref mut req) = self.request {
@@ -440,8 +776,144 @@ impl RequestBuilder {
         self
     }
 
+    /// Cancels the request once `handle` is aborted.
+    ///
+    /// Aborting fails the request if it is still waiting for its response,
+    /// and the body of the response otherwise, with an error for which
+    /// `Error::is_canceled()` is true. See [`AbortHandle`].
+    pub fn abort_handle(mut self, handle: &AbortHandle) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            req.abort = Some(handle.clone());
+        }
+        self
+    }
+
+    /// Fails reading a response body larger than `bytes`.
+    ///
+    /// It overrides the limit configured using
+    /// `ClientBuilder::max_response_size()`.
+    pub fn max_response_size(mut self, bytes: u64) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            req.max_response_size = Some(bytes);
+        }
+        self
+    }
+
+    /// Caps the bandwidth the response body is received at.
+    ///
+    /// It applies on top of the cap configured using
+    /// `ClientBuilder::download_throttle()`, so the lowest of both wins.
+    pub fn download_throttle(mut self, throttle: Throttle) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            req.download_throttle = Some(throttle);
+        }
+        self
+    }
+
+    /// Caps the bandwidth the request body is sent at.
+    ///
+    /// It applies on top of the cap configured using
+    /// `ClientBuilder::upload_throttle()`, so the lowest of both wins.
+    pub fn upload_throttle(mut self, throttle: Throttle) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            req.upload_throttle = Some(throttle);
+        }
+        self
+    }
+
+    /// Calls `callback` each time a chunk of the request body is sent.
+    ///
+    /// The callback receives the bytes sent so far, and the size of the body
+    /// when it is known upfront. A request sent again, after a redirect or a
+    /// retry, reports its body from the start.
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let client = rquest::Client::new();
+    /// client
+    ///     .post("http://httpbin.org/post")
+    ///     .body(vec![0; 1024 * 1024])
+    ///     .on_upload_progress(|sent, total| {
+    ///         println!("sent {sent} of {total:?} bytes");
+    ///     })
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_upload_progress<F>(mut self, callback: F) -> RequestBuilder
+    where
+        F: Fn(u64, Option<u64>) + Send + Sync + 'static,
+    {
+        if let Ok(ref mut req) = self.request {
+            req.upload_progress = Some(Arc::new(callback));
+        }
+        self
+    }
+
+    /// Calls `callback` each time a chunk of the response body is received.
+    ///
+    /// The callback receives the bytes received so far, and the
+    /// `Content-Length` of the response when it has one. Both count the bytes
+    /// on the wire, before any decompression.
+    pub fn on_download_progress<F>(mut self, callback: F) -> RequestBuilder
+    where
+        F: Fn(u64, Option<u64>) + Send + Sync + 'static,
+    {
+        if let Ok(ref mut req) = self.request {
+            req.download_progress = Some(Arc::new(callback));
+        }
+        self
+    }
+
+    /// Compresses the request body with `encoding`.
+    ///
+    /// The request gets the matching `Content-Encoding`. A body in memory is
+    /// compressed once before sending, on the blocking threads of the
+    /// runtime, so it keeps an exact `Content-Length` and can be sent again
+    /// on redirects and retries; a streaming body is compressed as it
+    /// streams.
+    ///
+    /// ```
+    /// # #[cfg(feature = "gzip")]
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let client = rquest::Client::new();
+    /// let res = client
+    ///     .post("http://httpbin.org/post")
+    ///     .body("a large, compressible payload")
+    ///     .compress_body(rquest::Encoding::Gzip)
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compress_body(mut self, encoding: Encoding) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            req.compress_body = Some(encoding);
+        }
+        self
+    }
+
+    /// Sets whether the response body is decompressed automatically.
+    ///
+    /// With `false`, the response keeps the body as received, along with
+    /// its original `Content-Encoding` and `Content-Length` headers. The
+    /// request still advertises the encodings the client accepts, unless an
+    /// `Accept-Encoding` header is set on it.
+    ///
+    /// Default is the configuration of the client.
+    pub fn decompress(mut self, enable: bool) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            req.decompress = enable;
+        }
+        self
+    }
+
     /// Sends a multipart/form-data body.
     ///
+    /// Unless the form has a style of its own, it is formatted in the
+    /// multipart style of the client, with a new boundary.
+    ///
     /// ```
     /// # use rquest::Error;
     ///
@@ -461,7 +933,11 @@ impl RequestBuilder {
     /// ```
     #[cfg(feature = "multipart")]
     #[cfg_attr(docsrs, doc(cfg(feature = "multipart")))]
-    pub fn multipart(self, mut multipart: multipart::Form) -> RequestBuilder {
+    pub fn multipart(self, multipart: multipart::Form) -> RequestBuilder {
+        let mut multipart = multipart.default_style(self.client.multipart_style());
+        if let Some(random) = self.client.deterministic() {
+            multipart = multipart.reseed_boundary(|| random.next());
+        }
         let mut builder = self.header_operation(
             CONTENT_TYPE,
             format!("multipart/form-data; boundary={}", multipart.boundary()),
@@ -500,6 +976,10 @@ impl RequestBuilder {
     /// This method will fail if the object you provide cannot be serialized
     /// into a query string.
     pub fn query<T: Serialize + ?Sized>(mut self, query: &T) -> RequestBuilder {
+        if let Some(style) = self.client.query_style() {
+            return self.query_with(query, style);
+        }
+
         let mut error = None;
         if let Ok(ref mut req) = self.request {
             let url = req.url_mut();
@@ -521,6 +1001,32 @@ impl RequestBuilder {
         self
     }
 
+    /// Modify the query string of the URL, serializing sequences and nested
+    /// values in `style`.
+    ///
+    /// Like [`query`](RequestBuilder::query), this appends to the query
+    /// string.
+    ///
+    /// # Errors
+    /// This method will fail if the object you provide cannot be serialized
+    /// into a query string.
+    pub fn query_with<T: Serialize + ?Sized>(
+        mut self,
+        query: &T,
+        style: QueryStyle,
+    ) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            match super::query::to_pairs(query, style) {
+                Ok(pairs) if !pairs.is_empty() => {
+                    req.url_mut().query_pairs_mut().extend_pairs(pairs);
+                }
+                Ok(_) => {}
+                Err(err) => self.request = Err(crate::error::builder(err)),
+            }
+        }
+        self
+    }
+
     /// Set HTTP version
     pub fn version(mut self, version: Version) -> RequestBuilder {
         if let Ok(ref mut req) = self.request {
@@ -529,6 +1035,123 @@ impl RequestBuilder {
         self
     }
 
+    /// Ask the server to switch the connection to `protocol`, with the
+    /// `Upgrade` and `Connection` headers of an HTTP/1.1 request.
+    ///
+    /// The connection is then taken from the `101 Switching Protocols`
+    /// response with [`Response::upgrade`], to speak the protocol over it.
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use tokio::io::AsyncWriteExt;
+    ///
+    /// let res = rquest::Client::new()
+    ///     .get("http://example.com/tunnel")
+    ///     .upgrade("custom-proto/1")
+    ///     .send()
+    ///     .await?;
+    /// let mut stream = res.upgrade().await?;
+    /// stream.write_all(b"hello").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn upgrade(mut self, protocol: &str) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            match HeaderValue::from_str(protocol) {
+                Ok(protocol) => {
+                    req.headers.insert(UPGRADE, protocol);
+                    req.headers
+                        .insert(CONNECTION, HeaderValue::from_static("upgrade"));
+                    req.version = Some(Version::HTTP_11);
+                }
+                Err(err) => self.request = Err(crate::error::builder(err)),
+            }
+        }
+        self
+    }
+
+    /// Send the given path and query exactly as written.
+    ///
+    /// The request target normally comes from the parsed `Url`, which
+    /// normalizes the path. This bypasses that, preserving encodings such as
+    /// `%2F`, repeated slashes and dot segments. The scheme and authority
+    /// still come from the `Url`, and redirects use the normalized form.
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let client = rquest::Client::new();
+    /// let res = client.get("http://httpbin.org")
+    ///     .raw_path_and_query("/anything/a%2Fb//../c?x=%41")
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn raw_path_and_query(mut self, path_and_query: &str) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            if !path_and_query.starts_with('/') {
+                self.request = Err(crate::error::builder(
+                    "raw path and query must start with '/'",
+                ));
+                return self;
+            }
+
+            match PathAndQuery::try_from(path_and_query) {
+                Ok(path_and_query) => req.raw_path_and_query = Some(path_and_query),
+                Err(err) => self.request = Err(crate::error::builder(err)),
+            }
+        }
+        self
+    }
+
+    /// Set the form of the request target sent on HTTP/1.
+    ///
+    /// With [`RequestTarget::Absolute`], the whole URL is sent in the
+    /// request line, as to a proxy, even when the connection is direct.
+    /// This is kept across redirects.
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// use rquest::RequestTarget;
+    ///
+    /// let res = rquest::Client::new()
+    ///     .get("http://httpbin.org/get")
+    ///     .request_target(RequestTarget::Absolute)
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn request_target(mut self, target: RequestTarget) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            req.request_target = Some(target);
+        }
+        self
+    }
+
+    /// Send the request, and its redirects, on a new connection of its own.
+    ///
+    /// The connection is neither taken from the pool nor returned to it, so
+    /// it is closed once the response is read. This suits endpoints tying
+    /// state to connections, and new TLS or HTTP/2 fingerprints per request.
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let res = rquest::Client::new()
+    ///     .get("https://httpbin.org/get")
+    ///     .fresh_connection(true)
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fresh_connection(mut self, enabled: bool) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            req.fresh_connection = enabled;
+        }
+        self
+    }
+
     /// Set the redirect policy for this request.
     pub fn redirect(mut self, policy: redirect::Policy) -> RequestBuilder {
         if let Ok(ref mut req) = self.request {
@@ -557,6 +1180,24 @@ impl RequestBuilder {
     }
 
     /// Set the local address for this request.
+    ///
+    /// This overrides the client's local address for this request only, so a
+    /// single `Client` can spread requests across several source addresses.
+    /// Connections are pooled per local address, and any network setting the
+    /// request leaves unset, such as proxies, still comes from the client.
+    ///
+    /// ```rust
+    /// # use std::net::IpAddr;
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let client = rquest::Client::new();
+    /// let local_addr = IpAddr::from([10, 0, 0, 2]);
+    /// let res = client.get("http://httpbin.org/ip")
+    ///     .local_address(local_addr)
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
     pub fn local_address<V>(mut self, local_address: V) -> RequestBuilder
     where
         V: Into<Option<IpAddr>>,
@@ -567,6 +1208,40 @@ impl RequestBuilder {
         self
     }
 
+    /// Connect to `addr` for the host of this request, instead of resolving
+    /// it.
+    ///
+    /// Unlike [`ClientBuilder::resolve`], this overrides the resolution for
+    /// this request only, and not for the hosts it may be redirected to. The
+    /// port is still the one of the URL. Connections are only shared with
+    /// requests resolved to the same address, and the override has no
+    /// effect through a proxy resolving the host itself.
+    ///
+    /// ```rust
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// use std::net::{IpAddr, Ipv4Addr};
+    ///
+    /// let edge = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+    /// let res = rquest::Client::new()
+    ///     .get("https://example.com/")
+    ///     .resolve_to(edge)
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ClientBuilder::resolve`]: crate::ClientBuilder::resolve
+    pub fn resolve_to(mut self, addr: IpAddr) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            if let Some(host) = req.url.host_str() {
+                let host = host.trim_start_matches('[').trim_end_matches(']');
+                req.network_scheme.resolve_to((host.to_owned(), addr));
+            }
+        }
+        self
+    }
+
     /// Set the local addresses for this request.
     pub fn local_addresses<V4, V6>(mut self, ipv4: V4, ipv6: V6) -> RequestBuilder
     where
@@ -580,6 +1255,9 @@ impl RequestBuilder {
     }
 
     /// Set the interface for this request.
+    ///
+    /// Like [`RequestBuilder::local_address`], this overrides the client's
+    /// interface for this request only.
     #[cfg(any(
         target_os = "android",
         target_os = "fuchsia",
@@ -643,6 +1321,10 @@ impl RequestBuilder {
     /// This method fails if the passed value cannot be serialized into
     /// url encoded format
     pub fn form<T: Serialize + ?Sized>(mut self, form: &T) -> RequestBuilder {
+        if let Some(style) = self.client.query_style() {
+            return self.form_with(form, style);
+        }
+
         let mut error = None;
         if let Ok(ref mut req) = self.request {
             match serde_urlencoded::to_string(form) {
@@ -663,6 +1345,37 @@ impl RequestBuilder {
         self
     }
 
+    /// Send a form body, serializing sequences and nested values in
+    /// `style`.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the passed value cannot be serialized into
+    /// url encoded format
+    pub fn form_with<T: Serialize + ?Sized>(
+        mut self,
+        form: &T,
+        style: QueryStyle,
+    ) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            match super::query::to_pairs(form, style) {
+                Ok(pairs) => {
+                    let body = url::form_urlencoded::Serializer::new(String::new())
+                        .extend_pairs(pairs)
+                        .finish();
+                    req.headers_mut()
+                        .entry(CONTENT_TYPE)
+                        .or_insert(HeaderValue::from_static(
+                            "application/x-www-form-urlencoded",
+                        ));
+                    *req.body_mut() = Some(body.into());
+                }
+                Err(err) => self.request = Err(crate::error::builder(err)),
+            }
+        }
+        self
+    }
+
     /// Send a JSON body.
     ///
     /// # Optional
@@ -694,6 +1407,173 @@ impl RequestBuilder {
         self
     }
 
+    /// Send a GraphQL query, as the JSON body of the request.
+    ///
+    /// The query and its variables are wrapped in the standard
+    /// `{"query", "variables"}` envelope, and the request accepts GraphQL
+    /// responses. Use it with a `POST` request, and read the response with
+    /// [`Response::graphql_data`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let viewer: serde_json::Value = rquest::Client::new()
+    ///     .post("https://api.github.com/graphql")
+    ///     .graphql(
+    ///         "query($n: Int!) { viewer { repositories(first: $n) { totalCount } } }",
+    ///         &serde_json::json!({ "n": 1 }),
+    ///     )
+    ///     .send()
+    ///     .await?
+    ///     .graphql_data()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `json` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// Serialization can fail if `V`'s implementation of `Serialize` decides to
+    /// fail, or if `V` contains a map with non-string keys.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn graphql<V: Serialize + ?Sized>(mut self, query: &str, variables: &V) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            req.headers_mut()
+                .entry(ACCEPT)
+                .or_insert(HeaderValue::from_static(super::graphql::ACCEPT));
+        }
+        self.json(&super::graphql::Envelope { query, variables })
+    }
+
+    /// Send a MessagePack body.
+    ///
+    /// Structs are serialized as maps, keyed by field name.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `msgpack` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// Serialization can fail if `T`'s implementation of `Serialize` decides to
+    /// fail.
+    #[cfg(feature = "msgpack")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "msgpack")))]
+    pub fn msgpack<T: Serialize + ?Sized>(self, msgpack: &T) -> RequestBuilder {
+        let body = rmp_serde::to_vec_named(msgpack).map_err(crate::error::builder);
+        self.serialized_body(body, "application/msgpack")
+    }
+
+    /// Send a CBOR body.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `cbor` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// Serialization can fail if `T`'s implementation of `Serialize` decides to
+    /// fail.
+    #[cfg(feature = "cbor")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+    pub fn cbor<T: Serialize + ?Sized>(self, cbor: &T) -> RequestBuilder {
+        let mut body = Vec::new();
+        let body = ciborium::into_writer(cbor, &mut body)
+            .map(|()| body)
+            .map_err(crate::error::builder);
+        self.serialized_body(body, "application/cbor")
+    }
+
+    /// Send an XML body.
+    ///
+    /// The root element is named after the type of `xml`.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `xml` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// Serialization can fail if `T`'s implementation of `Serialize` decides to
+    /// fail, or if `T` can not be represented as an XML document.
+    #[cfg(feature = "xml")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "xml")))]
+    pub fn xml<T: Serialize + ?Sized>(self, xml: &T) -> RequestBuilder {
+        let body = quick_xml::se::to_string(xml)
+            .map(String::into_bytes)
+            .map_err(crate::error::builder);
+        self.serialized_body(body, "application/xml")
+    }
+
+    /// Send a Protocol Buffers body.
+    ///
+    /// To send it compressed, combine it with
+    /// [`compress_body`](RequestBuilder::compress_body).
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `protobuf` feature enabled.
+    #[cfg(feature = "protobuf")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+    pub fn protobuf<M: prost::Message>(self, message: &M) -> RequestBuilder {
+        self.serialized_body(Ok(message.encode_to_vec()), "application/x-protobuf")
+    }
+
+    /// Set a serialized body, with its content type unless one is set.
+    #[cfg(any(
+        feature = "msgpack",
+        feature = "cbor",
+        feature = "xml",
+        feature = "protobuf"
+    ))]
+    fn serialized_body(
+        mut self,
+        body: crate::Result<Vec<u8>>,
+        content_type: &'static str,
+    ) -> RequestBuilder {
+        if let Ok(ref mut req) = self.request {
+            match body {
+                Ok(body) => {
+                    req.headers_mut()
+                        .entry(CONTENT_TYPE)
+                        .or_insert(HeaderValue::from_static(content_type));
+                    *req.body_mut() = Some(body.into());
+                }
+                Err(err) => self.request = Err(err),
+            }
+        }
+        self
+    }
+
+    /// Render the request as the client would send it, as a curl command.
+    ///
+    /// The default headers, cookies and proxy of the client are included,
+    /// with the headers in the order the client sends them. A streaming body
+    /// is read from the standard input.
+    ///
+    /// `None` is returned if there was an error while building the request.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let client = rquest::Client::new();
+    /// let curl = client
+    ///     .post("https://httpbin.org/post")
+    ///     .body("hello")
+    ///     .to_curl()
+    ///     .unwrap();
+    /// println!("{curl}");
+    /// ```
+    pub fn to_curl(&self) -> Option<String> {
+        self.request.as_ref().ok().map(|req| self.client.curl(req))
+    }
+
     /// Build a `Request`, which can be inspected, modified and executed with
     /// `Client::execute()`.
     pub fn build(self) -> crate::Result<Request> {
@@ -737,6 +1617,67 @@ impl RequestBuilder {
         }
     }
 
+    /// Turns the request into a [`Download`] resuming where it stopped
+    /// when its connection breaks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let client = rquest::Client::new();
+    /// let stream = client
+    ///     .get("https://example.com/large.iso")
+    ///     .download()
+    ///     .stream();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn download(self) -> Download {
+        Download::new(self.client, self.request)
+    }
+
+    /// Turns the request into a [`ConditionalRequest`], revalidating the
+    /// resource with the `ETag` and `Last-Modified` of the last response to
+    /// its URL.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let client = rquest::Client::new();
+    /// let revalidation = client
+    ///     .get("https://example.com/feed")
+    ///     .conditional()
+    ///     .send()
+    ///     .await?;
+    /// if !revalidation.is_modified() {
+    ///     println!("still the same");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn conditional(self) -> ConditionalRequest {
+        ConditionalRequest::new(self.client, self.request)
+    }
+
+    /// Turns the request into an [`EventSource`], a stream of Server-Sent
+    /// Events reconnecting when its connection closes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let client = rquest::Client::new();
+    /// let events = client
+    ///     .get("https://example.com/events")
+    ///     .event_source();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn event_source(self) -> EventSource {
+        EventSource::new(self.client, self.request)
+    }
+
     /// Attempt to clone the RequestBuilder.
     ///
     /// `None` is returned if the RequestBuilder can not be cloned,
@@ -766,6 +1707,19 @@ impl RequestBuilder {
                 request: Ok(req),
             })
     }
+
+    /// Prepare the request to send many times, with different URL
+    /// parameters.
+    ///
+    /// See [`RequestTemplate`] for the `{name}` placeholders of the URL.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if there was an error while building the request,
+    /// or if the body is a stream, which can only be sent once.
+    pub fn template(self) -> crate::Result<RequestTemplate> {
+        RequestTemplate::new(self.client, self.request?)
+    }
 }
 
 impl fmt::Debug for Request {
@@ -835,22 +1789,13 @@ where
             headers,
             ..
         } = parts;
-        let url = crate::into_url::IntoUrlSealed::into_url(uri.to_string())?;
-        Ok(Request {
-            method,
-            url,
-            headers,
-            body: Some(body.into()),
-            timeout: None,
-            read_timeout: None,
-            // TODO: Add version
-            version: None,
-            redirect: None,
-            #[cfg(feature = "cookies")]
-            cookie_store: None,
-            network_scheme: NetworkScheme::builder(),
-            protocol: None,
-        })
+        let (url, scope_id) = crate::into_url::into_url_with_scope_id(uri.to_string())?;
+        let mut req = Request::new(method, url);
+        *req.headers_mut() = headers;
+        *req.body_mut() = Some(body.into());
+        // TODO: Add version
+        req.network_scheme_mut().scope_id(scope_id);
+        Ok(req)
     }
 }
 
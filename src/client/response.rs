@@ -1,3 +1,5 @@
+#[cfg(feature = "charset")]
+use std::borrow::Cow;
 use std::fmt;
 use std::net::SocketAddr;
 use std::pin::Pin;
@@ -6,24 +8,33 @@ use std::time::Duration;
 use bytes::Bytes;
 use http_body_util::BodyExt;
 use hyper2::{HeaderMap, StatusCode, Version};
-#[cfg(feature = "json")]
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "cbor",
+    feature = "xml"
+))]
 use serde::de::DeserializeOwned;
 use tokio::time::Sleep;
 use url::Url;
 use util::client::connect::HttpInfo;
+use util::client::DebugCapture;
 
 use super::body::Body;
 use super::body::ResponseBody;
 use super::decoder::{Accepts, Decoder};
+use super::stats::{Transfer, TransferStats};
 
 #[cfg(feature = "cookies")]
 use crate::cookie;
-use crate::util;
+use crate::{redirect, util};
 
 #[cfg(feature = "charset")]
 use encoding_rs::{Encoding, UTF_8};
-#[cfg(feature = "charset")]
-use mime::Mime;
+
+/// The body size from which `Response::json` parses with `simd-json`.
+#[cfg(feature = "simd-json")]
+const SIMD_JSON_THRESHOLD: usize = 64 * 1024;
 
 /// A Response to a submitted `Request`.
 pub struct Response {
@@ -40,13 +51,17 @@ impl Response {
         accepts: Accepts,
         total_timeout: Option<Pin<Box<Sleep>>>,
         read_timeout: Option<Duration>,
+        max_size: Option<u64>,
+        max_ratio: Option<u32>,
     ) -> Response {
         let (mut parts, body) = res.into_parts();
         let decoder = Decoder::detect(
             &mut parts.headers,
             super::body::response(body, total_timeout, read_timeout),
             accepts,
-        );
+        )
+        .max_size(max_size)
+        .max_ratio(max_ratio);
         let res = hyper2::Response::from_parts(parts, decoder);
 
         Response {
@@ -55,6 +70,48 @@ impl Response {
         }
     }
 
+    /// Record the body in a HAR entry as it is read.
+    #[cfg(feature = "json")]
+    pub(super) fn recorded(mut self, recording: Option<super::har::Recording>) -> Response {
+        if let Some(recording) = recording {
+            self.res.body_mut().record(recording);
+        }
+        self
+    }
+
+    /// Fail reading the body once `handle` is aborted.
+    pub(super) fn abort_on(mut self, handle: &super::abort::AbortHandle) -> Response {
+        self.res.body_mut().abort_on(handle);
+        self
+    }
+
+    /// Count the bytes of the body as it is read.
+    pub(super) fn counted(mut self, transfer: Transfer) -> Response {
+        self.res.body_mut().count(transfer);
+        self
+    }
+
+    /// Get the bytes transferred by the request of this `Response`, with its
+    /// redirects.
+    ///
+    /// The counters go on as the body is read, so the handle can be taken
+    /// before consuming the body.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let res = rquest::get("https://example.com").await?;
+    /// let stats = res.stats();
+    /// let body = res.bytes().await?;
+    /// assert_eq!(stats.response_decoded_bytes(), body.len() as u64);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stats(&self) -> TransferStats {
+        self.res.body().stats().unwrap_or_default()
+    }
+
     /// Get the `StatusCode` of this `Response`.
     #[inline]
     pub fn status(&self) -> StatusCode {
@@ -111,6 +168,15 @@ impl Response {
         &self.url
     }
 
+    /// Get the redirects that were followed to get this `Response`, oldest first.
+    pub fn history(&self) -> &[redirect::History] {
+        self.res
+            .extensions()
+            .get::<redirect::RedirectHistory>()
+            .map(|history| history.0.as_slice())
+            .unwrap_or_default()
+    }
+
     /// Get the remote address used to get this `Response`.
     pub fn remote_addr(&self) -> Option<SocketAddr> {
         self.res
@@ -119,6 +185,18 @@ impl Response {
             .map(|info| info.remote_addr())
     }
 
+    /// How the [`HttpCache`](crate::cache::HttpCache) of the client
+    /// answered, or `None` without one.
+    pub fn cache_status(&self) -> Option<crate::cache::CacheStatus> {
+        self.res.extensions().get().copied()
+    }
+
+    /// What was sent for this `Response`, if the client has
+    /// [`debug_capture`](crate::ClientBuilder::debug_capture) enabled.
+    pub fn debug_capture(&self) -> Option<&DebugCapture> {
+        self.res.extensions().get()
+    }
+
     /// Returns a reference to the associated extensions.
     pub fn extensions(&self) -> &http::Extensions {
         self.res.extensions()
@@ -136,7 +214,8 @@ impl Response {
     /// This method decodes the response body with BOM sniffing
     /// and with malformed sequences replaced with the REPLACEMENT CHARACTER.
     /// Encoding is determined from the `charset` parameter of `Content-Type` header,
-    /// and defaults to `utf-8` if not presented.
+    /// or for HTML without one, from a `<meta charset>` in the first 1024 bytes of
+    /// the document like browsers do, and defaults to `utf-8` if not presented.
     ///
     /// Note that the BOM is stripped from the returned String.
     ///
@@ -167,8 +246,7 @@ impl Response {
         #[cfg(not(feature = "charset"))]
         {
             let full = self.bytes().await?;
-            let text = String::from_utf8_lossy(&full);
-            Ok(text.into_owned())
+            Ok(into_string(full, 0))
         }
     }
 
@@ -177,7 +255,8 @@ impl Response {
     /// This method decodes the response body with BOM sniffing
     /// and with malformed sequences replaced with the REPLACEMENT CHARACTER.
     /// You can provide a default encoding for decoding the raw message, while the
-    /// `charset` parameter of `Content-Type` header is still prioritized. For more information
+    /// `charset` parameter of `Content-Type` header, and then the `<meta charset>`
+    /// of an HTML document, are still prioritized. For more information
     /// about the possible encoding name, please go to [`encoding_rs`] docs.
     ///
     /// Note that the BOM is stripped from the returned String.
@@ -204,21 +283,56 @@ impl Response {
     #[cfg(feature = "charset")]
     #[cfg_attr(docsrs, doc(cfg(feature = "charset")))]
     pub async fn text_with_charset(self, default_encoding: &str) -> crate::Result<String> {
-        let content_type = self
-            .headers()
-            .get(crate::header::CONTENT_TYPE)
-            .and_then(|value| value.to_str().ok())
-            .and_then(|value| value.parse::<Mime>().ok());
-        let encoding_name = content_type
-            .as_ref()
-            .and_then(|mime| mime.get_param("charset").map(|charset| charset.as_str()))
-            .unwrap_or(default_encoding);
-        let encoding = Encoding::for_label(encoding_name.as_bytes()).unwrap_or(UTF_8);
+        let (declared, html) = super::charset::declared(self.headers());
+        let default_encoding = Encoding::for_label(default_encoding.as_bytes()).unwrap_or(UTF_8);
 
         let full = self.bytes().await?;
 
+        let encoding = declared
+            .or_else(|| html.then(|| super::charset::prescan(&full)).flatten())
+            .unwrap_or(default_encoding);
         let (text, _, _) = encoding.decode(&full);
-        Ok(text.into_owned())
+        match text {
+            Cow::Owned(text) => Ok(text),
+            // valid as it is, past its BOM
+            Cow::Borrowed(text) => {
+                let bom = full.len() - text.len();
+                Ok(into_string(full, bom))
+            }
+        }
+    }
+
+    /// Convert the response into a `Stream` of its text, decoded as it
+    /// arrives.
+    ///
+    /// The encoding is found like [`Response::text`] does, and malformed
+    /// sequences are replaced with the REPLACEMENT CHARACTER. A character
+    /// split across chunks is returned whole with the next chunk.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `charset` feature enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    ///
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut text = rquest::get("http://httpbin.org/html")
+    ///     .await?
+    ///     .text_lossy_stream();
+    ///
+    /// while let Some(chunk) = text.next().await {
+    ///     print!("{}", chunk?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "charset")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "charset")))]
+    pub fn text_lossy_stream(self) -> super::charset::TextStream {
+        super::charset::TextStream::new(self, UTF_8)
     }
 
     /// Try to deserialize the response body as JSON.
@@ -262,14 +376,180 @@ impl Response {
     /// details please see [`serde_json::from_reader`].
     ///
     /// [`serde_json::from_reader`]: https://docs.serde.rs/serde_json/fn.from_reader.html
+    ///
+    /// With the optional `simd-json` feature enabled, bodies of 64 KiB and
+    /// more are deserialized with `simd-json` instead.
     #[cfg(feature = "json")]
     #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
     pub async fn json<T: DeserializeOwned>(self) -> crate::Result<T> {
         let full = self.bytes().await?;
 
+        // below this, setting up the SIMD parser costs more than it saves
+        #[cfg(feature = "simd-json")]
+        if full.len() >= SIMD_JSON_THRESHOLD {
+            let mut full = Vec::from(full);
+            return simd_json::serde::from_slice(&mut full).map_err(crate::error::decode);
+        }
+
         serde_json::from_slice(&full).map_err(crate::error::decode)
     }
 
+    /// Try to deserialize the response body as MessagePack.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `msgpack` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever the response body is not in MessagePack
+    /// format or it cannot be properly deserialized to target type `T`.
+    #[cfg(feature = "msgpack")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "msgpack")))]
+    pub async fn msgpack<T: DeserializeOwned>(self) -> crate::Result<T> {
+        let full = self.bytes().await?;
+
+        rmp_serde::from_slice(&full).map_err(crate::error::decode)
+    }
+
+    /// Try to deserialize the response body as CBOR.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `cbor` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever the response body is not in CBOR format
+    /// or it cannot be properly deserialized to target type `T`.
+    #[cfg(feature = "cbor")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+    pub async fn cbor<T: DeserializeOwned>(self) -> crate::Result<T> {
+        let full = self.bytes().await?;
+
+        ciborium::from_reader(&full[..]).map_err(crate::error::decode)
+    }
+
+    /// Try to deserialize the response body as XML.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `xml` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever the response body is not well-formed XML
+    /// or it cannot be properly deserialized to target type `T`.
+    #[cfg(feature = "xml")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "xml")))]
+    pub async fn xml<T: DeserializeOwned>(self) -> crate::Result<T> {
+        let full = self.bytes().await?;
+
+        quick_xml::de::from_reader(&full[..]).map_err(crate::error::decode)
+    }
+
+    /// Try to decode the response body as a Protocol Buffers message.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `protobuf` feature enabled.
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever the response body is not a valid encoding
+    /// of `M`.
+    #[cfg(feature = "protobuf")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+    pub async fn protobuf<M: prost::Message + Default>(self) -> crate::Result<M> {
+        let full = self.bytes().await?;
+
+        M::decode(full).map_err(crate::error::decode)
+    }
+
+    /// Try to deserialize the `data` of a GraphQL response body.
+    ///
+    /// # Errors
+    ///
+    /// Besides failing like [`Response::json`], this method fails when the
+    /// response reports `errors`, even along partial `data`, or has no
+    /// `data`. The reported errors are available with
+    /// [`Error::graphql_errors`](crate::Error::graphql_errors).
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `json` feature enabled.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn graphql_data<T: DeserializeOwned>(self) -> crate::Result<T> {
+        let full = self.bytes().await?;
+
+        super::graphql::data(&full)
+    }
+
+    /// Deserialize the response body as newline-delimited JSON, one value
+    /// per line, as the lines arrive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use futures_util::StreamExt;
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let mut logs = rquest::get("https://example.com/logs.ndjson")
+    ///     .await?
+    ///     .json_lines::<serde_json::Value>();
+    ///
+    /// while let Some(entry) = logs.next().await {
+    ///     println!("{}", entry?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// A line that is not valid JSON, or can not be deserialized to `T`,
+    /// yields an error, and the stream goes on with the next line.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `json` feature enabled.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn json_lines<T: DeserializeOwned>(self) -> super::json_stream::JsonLines<T> {
+        super::json_stream::JsonLines::new(self)
+    }
+
+    /// Deserialize the elements of a response body holding a JSON array,
+    /// as they arrive.
+    ///
+    /// Unlike [`Response::json`], this never holds the whole body in
+    /// memory, so arbitrarily large arrays can be processed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use futures_util::StreamExt;
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let mut users = rquest::get("https://example.com/export/users.json")
+    ///     .await?
+    ///     .json_array_stream::<serde_json::Value>();
+    ///
+    /// while let Some(user) = users.next().await {
+    ///     println!("{}", user?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `json` feature enabled.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn json_array_stream<T: DeserializeOwned>(self) -> super::json_stream::JsonArray<T> {
+        super::json_stream::JsonArray::new(self)
+    }
+
     /// Get the full response body as `Bytes`.
     ///
     /// # Example
@@ -354,6 +634,90 @@ impl Response {
         super::body::DataStream(self.res.into_body())
     }
 
+    /// Convert the response into an `AsyncRead` of the body, such as for
+    /// `tokio::io::copy` or a decoder reading from it.
+    ///
+    /// Errors reading the body are returned as `io::Error`s, wrapping the
+    /// [`crate::Error`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut reader = rquest::get("https://example.com/archive.tar.gz")
+    ///     .await?
+    ///     .into_async_read();
+    /// let mut file = tokio::fs::File::create("archive.tar.gz").await?;
+    /// tokio::io::copy(&mut reader, &mut file).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `stream` feature to be enabled.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+    pub fn into_async_read(self) -> impl tokio::io::AsyncRead + Send + Unpin {
+        use futures_util::TryStreamExt;
+
+        tokio_util::io::StreamReader::new(self.bytes_stream().map_err(crate::Error::into_io))
+    }
+
+    /// Save the response body to the file at `path`.
+    ///
+    /// The body goes to a temporary file next to `path`, which is synced
+    /// and renamed to `path` only once the whole body is written, so an
+    /// interrupted download never leaves a truncated file at `path`. The
+    /// length, and optionally the digest, of the body are verified first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let len = rquest::get("https://example.com/archive.tar.gz")
+    ///     .await?
+    ///     .save_to("archive.tar.gz")
+    ///     .sha256("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08")
+    ///     .await?;
+    /// println!("saved {len} bytes");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `stream` feature to be enabled.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+    pub fn save_to<P: AsRef<std::path::Path>>(self, path: P) -> super::save::SaveTo {
+        super::save::SaveTo::new(self, path.as_ref().to_path_buf())
+    }
+
+    /// Parse the response body as a stream of Server-Sent Events.
+    ///
+    /// The stream ends with the body; to reconnect when the connection
+    /// closes, refer to [`RequestBuilder::event_source`].
+    ///
+    /// [`RequestBuilder::event_source`]: super::RequestBuilder::event_source
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use futures_util::StreamExt;
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let mut events = rquest::get("https://example.com/events").await?.sse();
+    ///
+    /// while let Some(event) = events.next().await {
+    ///     println!("{}", event?.data());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sse(self) -> super::sse::EventStream {
+        super::sse::EventStream::new(self)
+    }
+
     // util methods
 
     /// Turn a response into an error if the server returned an error.
@@ -470,9 +834,19 @@ impl From<Response> for Body {
     }
 }
 
+/// The text of `bytes` past `start`, reusing their buffer when it is valid
+/// UTF-8 and they hold the only reference to it, instead of copying it.
+fn into_string(bytes: Bytes, start: usize) -> String {
+    let bytes = Vec::from(bytes.slice(start..));
+    match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Response;
+    use super::{into_string, Response};
     use crate::ResponseBuilderExt;
     use http::response::Builder;
     use url::Url;
@@ -490,4 +864,13 @@ mod tests {
         assert_eq!(response.status(), 200);
         assert_eq!(*response.url(), url);
     }
+
+    #[test]
+    fn into_string_skips_bom_and_replaces_invalid_utf8() {
+        assert_eq!(into_string(bytes::Bytes::from("\u{feff}héllo"), 3), "héllo");
+        assert_eq!(
+            into_string(bytes::Bytes::from_static(b"a\xffb"), 0),
+            "a\u{fffd}b"
+        );
+    }
 }
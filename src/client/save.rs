@@ -0,0 +1,184 @@
+//! Saving bodies to files that only appear once complete.
+use std::fmt;
+use std::future::{Future, IntoFuture};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt};
+use tokio::io::AsyncWriteExt;
+
+use super::response::Response;
+use crate::error;
+
+/// A response body being saved to a file.
+///
+/// The body is written to a temporary file next to the destination, which
+/// is synced to disk and renamed over the destination once the whole body
+/// was written and verified. A download failing halfway leaves no file
+/// behind, so a file at the destination is always complete.
+///
+/// To construct a `SaveTo`, refer to [`Response::save_to`]. Await it to
+/// save the body, and get the number of bytes written.
+#[must_use = "SaveTo does nothing until it is awaited"]
+pub struct SaveTo {
+    response: Response,
+    options: SaveOptions,
+}
+
+#[derive(Debug)]
+pub(crate) struct SaveOptions {
+    path: PathBuf,
+    expected_len: Option<u64>,
+    sha256: Option<String>,
+}
+
+impl SaveTo {
+    pub(super) fn new(response: Response, path: PathBuf) -> SaveTo {
+        SaveTo {
+            response,
+            options: SaveOptions::new(path),
+        }
+    }
+
+    /// Fail unless the body is `len` bytes long.
+    ///
+    /// The `Content-Length` of the response is checked in any case.
+    pub fn expected_len(mut self, len: u64) -> SaveTo {
+        self.options.expected_len = Some(len);
+        self
+    }
+
+    /// Fail unless the SHA-256 digest of the body is `hex`.
+    pub fn sha256(mut self, hex: &str) -> SaveTo {
+        self.options.sha256 = Some(hex.to_ascii_lowercase());
+        self
+    }
+}
+
+impl IntoFuture for SaveTo {
+    type Output = crate::Result<u64>;
+    type IntoFuture = Pin<Box<dyn Future<Output = crate::Result<u64>> + Send>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let mut options = self.options;
+        if options.expected_len.is_none() {
+            options.expected_len = self.response.content_length();
+        }
+        Box::pin(options.save(self.response.bytes_stream()))
+    }
+}
+
+impl fmt::Debug for SaveTo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SaveTo")
+            .field("url", &self.response.url().as_str())
+            .field("path", &self.options.path)
+            .field("expected_len", &self.options.expected_len)
+            .field("sha256", &self.options.sha256)
+            .finish()
+    }
+}
+
+impl SaveOptions {
+    pub(crate) fn new(path: PathBuf) -> SaveOptions {
+        SaveOptions {
+            path,
+            expected_len: None,
+            sha256: None,
+        }
+    }
+
+    /// Write `stream` to a temporary file, then move it to the path.
+    pub(crate) async fn save<S>(self, stream: S) -> crate::Result<u64>
+    where
+        S: Stream<Item = crate::Result<Bytes>> + Send,
+    {
+        let tmp = temp_path(&self.path);
+        let res = self.write(&tmp, stream).await;
+        if res.is_err() {
+            let _ = tokio::fs::remove_file(&tmp).await;
+        }
+        res
+    }
+
+    async fn write<S>(&self, tmp: &Path, stream: S) -> crate::Result<u64>
+    where
+        S: Stream<Item = crate::Result<Bytes>> + Send,
+    {
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(tmp)
+            .await
+            .map_err(error::builder)?;
+
+        let mut hasher = self.sha256.as_ref().map(|_| boring2::sha::Sha256::new());
+        let mut written = 0;
+
+        futures_util::pin_mut!(stream);
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            if let Some(ref mut hasher) = hasher {
+                hasher.update(&chunk);
+            }
+            file.write_all(&chunk).await.map_err(error::body)?;
+            written += chunk.len() as u64;
+        }
+
+        if let Some(expected) = self.expected_len {
+            if written != expected {
+                return Err(error::body(format!(
+                    "body is {written} bytes long, expected {expected}"
+                )));
+            }
+        }
+        if let (Some(hasher), Some(expected)) = (hasher, self.sha256.as_ref()) {
+            let digest = hasher
+                .finish()
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>();
+            if digest != *expected {
+                return Err(error::body(format!(
+                    "body has SHA-256 digest {digest}, expected {expected}"
+                )));
+            }
+        }
+
+        file.sync_all().await.map_err(error::body)?;
+        drop(file);
+        tokio::fs::rename(tmp, &self.path)
+            .await
+            .map_err(error::body)?;
+
+        // the rename itself only lasts once the directory is synced
+        #[cfg(unix)]
+        {
+            let dir = match self.path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+            let dir = tokio::fs::File::open(dir).await.map_err(error::body)?;
+            dir.sync_all().await.map_err(error::body)?;
+        }
+
+        Ok(written)
+    }
+}
+
+/// A file name next to `path`, unique to this process and call.
+fn temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    path.with_file_name(format!(
+        ".{name}.{}.{}.part",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
@@ -0,0 +1,118 @@
+//! The state a client builds up while browsing, to resume it in another.
+use std::fmt;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use boring2::ssl::SslSession;
+use http::uri::Authority;
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::connect::Connector;
+
+/// The state of a client, to make another client carry on as the same
+/// visitor, such as in a later run of the program.
+///
+/// It holds the cookies of the cookie store, and the TLS sessions to resume
+/// the connections to the hosts visited. It can be saved with `serde`, in
+/// any format it supports.
+///
+/// To get one, refer to [`Client::export_session`], and to resume it to
+/// [`ClientBuilder::import_session`].
+///
+/// [`Client::export_session`]: crate::Client::export_session
+/// [`ClientBuilder::import_session`]: crate::ClientBuilder::import_session
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SessionState {
+    #[serde(default)]
+    cookies: Vec<SavedCookie>,
+    #[serde(default)]
+    tls_sessions: Vec<SavedTlsSession>,
+}
+
+// the cookies and sessions are credentials, kept out of logs
+impl fmt::Debug for SessionState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SessionState")
+            .field("cookies", &self.cookies.len())
+            .field("tls_sessions", &self.tls_sessions.len())
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SavedCookie {
+    url: String,
+    set_cookie: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SavedTlsSession {
+    authority: String,
+    /// The session, DER-encoded in base64.
+    session: String,
+}
+
+impl SessionState {
+    /// Whether the state holds nothing to resume.
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty() && self.tls_sessions.is_empty()
+    }
+
+    #[cfg(feature = "cookies")]
+    pub(super) fn save_cookies(&mut self, store: &dyn crate::cookie::CookieStore) {
+        self.cookies = store
+            .export_cookies()
+            .into_iter()
+            .map(|(url, set_cookie)| SavedCookie {
+                url: url.into(),
+                set_cookie,
+            })
+            .collect();
+    }
+
+    #[cfg(feature = "cookies")]
+    pub(super) fn restore_cookies(&self, store: &dyn crate::cookie::CookieStore) {
+        for cookie in &self.cookies {
+            let Ok(url) = cookie.url.parse::<crate::Url>() else {
+                continue;
+            };
+            let Ok(value) = http::HeaderValue::from_str(&cookie.set_cookie) else {
+                continue;
+            };
+            store.set_cookies(&mut std::iter::once(&value), &url);
+        }
+    }
+
+    #[cfg(feature = "cookies")]
+    pub(super) fn has_cookies(&self) -> bool {
+        !self.cookies.is_empty()
+    }
+
+    pub(super) fn save_tls_sessions(&mut self, connector: &Connector) {
+        self.tls_sessions = connector
+            .tls()
+            .sessions()
+            .into_iter()
+            .filter_map(|(authority, session)| {
+                Some(SavedTlsSession {
+                    authority: authority.to_string(),
+                    session: BASE64_STANDARD.encode(session.to_der().ok()?),
+                })
+            })
+            .collect();
+    }
+
+    pub(super) fn restore_tls_sessions(&self, connector: &Connector) {
+        for saved in &self.tls_sessions {
+            let session = BASE64_STANDARD
+                .decode(&saved.session)
+                .ok()
+                .and_then(|der| SslSession::from_der(&der).ok());
+            match (saved.authority.parse::<Authority>(), session) {
+                (Ok(authority), Some(session)) => connector.tls().add_session(authority, session),
+                _ => debug!("skipping unreadable TLS session of {}", saved.authority),
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Signing of requests once they are final.
+use std::fmt;
+
+use boring2::hash::{hash, MessageDigest};
+use http::{HeaderMap, Method, Uri};
+
+use crate::Url;
+
+/// A signer of requests, such as for HMAC or OAuth 1.0 signatures.
+///
+/// It is called once a request is final, just before it is sent: after the
+/// default headers, cookies and `Accept-Encoding` of the client are added,
+/// and the body is compressed. Headers it adds come after the others.
+///
+/// It is called again, with the headers it set removed, each time the
+/// request is sent anew: on retries, with new proxy credentials, and on
+/// redirects to the same host. A redirect to another host is sent without
+/// the headers it set, like the `Authorization` of the request.
+///
+/// Any `Fn(&mut SigningRequest<'_>) -> rquest::Result<()>` is a signer.
+///
+/// To use one, refer to [`RequestBuilder::signer`] and
+/// [`ClientBuilder::signer`].
+///
+/// [`RequestBuilder::signer`]: crate::RequestBuilder::signer
+/// [`ClientBuilder::signer`]: crate::ClientBuilder::signer
+pub trait RequestSigner: Send + Sync + 'static {
+    /// Sign `req`, adding its signature to the headers.
+    ///
+    /// An error fails the request without sending it.
+    fn sign(&self, req: &mut SigningRequest<'_>) -> crate::Result<()>;
+}
+
+impl fmt::Debug for dyn RequestSigner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RequestSigner").finish_non_exhaustive()
+    }
+}
+
+impl<F> RequestSigner for F
+where
+    F: Fn(&mut SigningRequest<'_>) -> crate::Result<()> + Send + Sync + 'static,
+{
+    fn sign(&self, req: &mut SigningRequest<'_>) -> crate::Result<()> {
+        self(req)
+    }
+}
+
+/// A request about to be sent, as a [`RequestSigner`] sees it.
+pub struct SigningRequest<'a> {
+    pub(super) method: &'a Method,
+    pub(super) url: &'a Url,
+    pub(super) uri: &'a Uri,
+    pub(super) headers: &'a mut HeaderMap,
+    pub(super) body: Option<&'a [u8]>,
+}
+
+impl SigningRequest<'_> {
+    /// The method of the request.
+    pub fn method(&self) -> &Method {
+        self.method
+    }
+
+    /// The URL of the request.
+    pub fn url(&self) -> &Url {
+        self.url
+    }
+
+    /// The URI the request is sent to, with the path and query exactly as
+    /// they are sent.
+    pub fn uri(&self) -> &Uri {
+        self.uri
+    }
+
+    /// The headers of the request.
+    pub fn headers(&self) -> &HeaderMap {
+        self.headers
+    }
+
+    /// The headers of the request, to add the signature to.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        self.headers
+    }
+
+    /// The body of the request, as sent, or `None` if it is a stream.
+    ///
+    /// A request without a body has an empty one.
+    pub fn body(&self) -> Option<&[u8]> {
+        self.body
+    }
+
+    /// The SHA-256 hash of the body, or `None` if it is a stream.
+    pub fn body_sha256(&self) -> Option<[u8; 32]> {
+        let digest = hash(MessageDigest::sha256(), self.body?).ok()?;
+        digest.as_ref().try_into().ok()
+    }
+}
+
+impl fmt::Debug for SigningRequest<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SigningRequest")
+            .field("method", self.method)
+            .field("uri", self.uri)
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
@@ -0,0 +1,512 @@
+//! Server-Sent Events.
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt};
+use http::header::{HeaderValue, ACCEPT, CACHE_CONTROL, CONTENT_TYPE};
+use http::StatusCode;
+use log::debug;
+#[cfg(feature = "json")]
+use serde::de::DeserializeOwned;
+
+use super::http::Client;
+use super::request::Request;
+use super::response::Response;
+use crate::error;
+
+type BoxStream<T> = Pin<Box<dyn Stream<Item = crate::Result<T>> + Send>>;
+
+/// An event received from a `text/event-stream` response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    id: Option<String>,
+    event: String,
+    data: String,
+    retry: Option<Duration>,
+}
+
+impl Event {
+    /// The last event ID set by the stream, by this event or one before it.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// The type of the event, `message` unless the server named it.
+    pub fn event(&self) -> &str {
+        &self.event
+    }
+
+    /// The data of the event, its lines joined with `\n`.
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    /// The reconnection delay the server asked for with this event.
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    /// Deserialize the data of the event as JSON.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `json` feature enabled.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn json<T: DeserializeOwned>(&self) -> crate::Result<T> {
+        serde_json::from_str(&self.data).map_err(error::decode)
+    }
+}
+
+/// The events of a `text/event-stream` response.
+///
+/// To construct an `EventStream`, refer to [`Response::sse`]. It ends with
+/// the response; [`EventSource`] reconnects instead.
+pub struct EventStream {
+    body: BoxStream<Bytes>,
+    parser: Parser,
+    done: bool,
+}
+
+impl EventStream {
+    pub(super) fn new(res: Response) -> EventStream {
+        EventStream::with_last_event_id(res, None)
+    }
+
+    fn with_last_event_id(res: Response, last_event_id: Option<String>) -> EventStream {
+        let body = futures_util::stream::unfold(res, |mut res| async move {
+            res.chunk().await.transpose().map(|chunk| (chunk, res))
+        });
+        EventStream {
+            body: Box::pin(body),
+            parser: Parser {
+                id: last_event_id,
+                ..Parser::default()
+            },
+            done: false,
+        }
+    }
+
+    /// The last event ID set by the stream so far.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.parser.id.as_deref()
+    }
+}
+
+impl Stream for EventStream {
+    type Item = crate::Result<Event>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(event) = self.parser.events.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            if self.done {
+                return Poll::Ready(None);
+            }
+            match futures_util::ready!(self.body.poll_next_unpin(cx)) {
+                Some(Ok(chunk)) => self.parser.feed(&chunk),
+                Some(Err(err)) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(err)));
+                }
+                // an event without its blank line is incomplete, and dropped
+                None => self.done = true,
+            }
+        }
+    }
+}
+
+impl fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventStream")
+            .field("last_event_id", &self.parser.id)
+            .finish()
+    }
+}
+
+/// The events of a `text/event-stream` resource, reconnecting when the
+/// connection closes.
+///
+/// Each reconnection sends the ID of the last event received in a
+/// `Last-Event-ID` header, after the delay of the last `retry` field, so
+/// the server can resume the stream where it stopped. The stream ends when
+/// the server answers `204 No Content`, and fails on other non-successful
+/// responses, or after too many failed reconnections in a row.
+///
+/// To construct an `EventSource`, refer to [`RequestBuilder::event_source`].
+///
+/// [`RequestBuilder::event_source`]: super::RequestBuilder::event_source
+///
+/// # Example
+///
+/// ```
+/// # use futures_util::StreamExt;
+/// # async fn run() -> Result<(), rquest::Error> {
+/// let client = rquest::Client::new();
+/// let mut events = client.get("https://example.com/events").event_source();
+///
+/// while let Some(event) = events.next().await {
+///     let event = event?;
+///     println!("{}: {}", event.event(), event.data());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "streams do nothing unless polled"]
+pub struct EventSource {
+    state: Option<crate::Result<Reconnect>>,
+    inner: Option<BoxStream<Event>>,
+}
+
+impl EventSource {
+    pub(super) fn new(client: Client, request: crate::Result<Request>) -> EventSource {
+        let request = request.and_then(|req| match req.body() {
+            Some(body) if body.as_bytes().is_none() => Err(error::builder(
+                "an event source can not resend a request with a streaming body",
+            )),
+            _ => Ok(req),
+        });
+
+        let state = request.map(|request| Reconnect {
+            client,
+            request,
+            stream: None,
+            last_event_id: None,
+            delay: Duration::from_secs(3),
+            failures: 0,
+            max_failures: 3,
+            connected: false,
+            done: false,
+        });
+
+        EventSource {
+            state: Some(state),
+            inner: None,
+        }
+    }
+
+    /// Set the delay before reconnecting, until the server sets one with a
+    /// `retry` field.
+    ///
+    /// Default is 3 seconds.
+    pub fn retry_delay(mut self, delay: Duration) -> EventSource {
+        if let Some(Ok(ref mut state)) = self.state {
+            state.delay = delay;
+        }
+        self
+    }
+
+    /// Set how many reconnections in a row may fail before the stream
+    /// gives up. The count restarts with each event received.
+    ///
+    /// Default is 3.
+    pub fn max_retries(mut self, retries: usize) -> EventSource {
+        if let Some(Ok(ref mut state)) = self.state {
+            state.max_failures = retries;
+        }
+        self
+    }
+}
+
+impl Stream for EventSource {
+    type Item = crate::Result<Event>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.inner.is_none() {
+            let state = self.state.take();
+            let inner = futures_util::stream::unfold(state, |state| async move {
+                let mut state = match state? {
+                    Ok(state) => state,
+                    Err(err) => return Some((Err(err), None)),
+                };
+                let event = state.next().await?;
+                Some((event, Some(Ok(state))))
+            });
+            self.inner = Some(Box::pin(inner));
+        }
+        self.inner
+            .as_mut()
+            .expect("inner stream is set")
+            .poll_next_unpin(cx)
+    }
+}
+
+impl fmt::Debug for EventSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventSource").finish()
+    }
+}
+
+struct Reconnect {
+    client: Client,
+    request: Request,
+    stream: Option<EventStream>,
+    last_event_id: Option<String>,
+    delay: Duration,
+    failures: usize,
+    max_failures: usize,
+    connected: bool,
+    done: bool,
+}
+
+impl Reconnect {
+    async fn next(&mut self) -> Option<crate::Result<Event>> {
+        loop {
+            if let Some(ref mut stream) = self.stream {
+                match stream.next().await {
+                    Some(Ok(event)) => {
+                        self.failures = 0;
+                        self.last_event_id = event.id.clone();
+                        if let Some(retry) = event.retry {
+                            self.delay = retry;
+                        }
+                        return Some(Ok(event));
+                    }
+                    Some(Err(err)) => debug!("event stream broke: {err}"),
+                    None => debug!("event stream closed"),
+                }
+                // `id` and `retry` fields apply even without data
+                self.last_event_id = stream.last_event_id().map(ToOwned::to_owned);
+                if let Some(retry) = stream.parser.reconnection_time {
+                    self.delay = retry;
+                }
+                self.stream = None;
+            }
+
+            if self.done {
+                return None;
+            }
+            if self.connected {
+                tokio::time::sleep(self.delay).await;
+            }
+
+            match self.connect().await {
+                Ok(Some(stream)) => self.stream = Some(stream),
+                Ok(None) => return None,
+                Err(err) => {
+                    self.failures += 1;
+                    if self.failures > self.max_failures || !(err.is_connect() || err.is_timeout())
+                    {
+                        self.done = true;
+                        return Some(Err(err));
+                    }
+                    debug!("reconnecting event stream: {err}");
+                }
+            }
+        }
+    }
+
+    async fn connect(&mut self) -> crate::Result<Option<EventStream>> {
+        let mut req = self
+            .request
+            .try_clone()
+            .expect("event source request body is reusable");
+
+        let headers = req.headers_mut();
+        headers.insert(ACCEPT, HeaderValue::from_static("text/event-stream"));
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        if let Some(ref id) = self.last_event_id {
+            if let Ok(id) = HeaderValue::from_str(id) {
+                headers.insert("last-event-id", id);
+            }
+        }
+
+        self.connected = true;
+        let res = self.client.execute(req).await?;
+
+        if res.status() == StatusCode::NO_CONTENT {
+            self.done = true;
+            return Ok(None);
+        }
+        let res = res.error_for_status().inspect_err(|_| self.done = true)?;
+
+        let is_event_stream = res
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<mime::Mime>().ok())
+            .is_some_and(|mime| mime.essence_str() == "text/event-stream");
+        if !is_event_stream {
+            self.done = true;
+            return Err(error::decode("response is not a text/event-stream"));
+        }
+
+        Ok(Some(EventStream::with_last_event_id(
+            res,
+            self.last_event_id.clone(),
+        )))
+    }
+}
+
+/// An incremental parser of the `text/event-stream` format.
+#[derive(Default)]
+struct Parser {
+    line: Vec<u8>,
+    /// The last line ended with `\r`, and a `\n` right after belongs to it.
+    after_cr: bool,
+    started: bool,
+    event: String,
+    data: String,
+    has_data: bool,
+    id: Option<String>,
+    retry: Option<Duration>,
+    /// The last `retry` field of the stream, with or without data.
+    reconnection_time: Option<Duration>,
+    events: VecDeque<Event>,
+}
+
+impl Parser {
+    fn feed(&mut self, chunk: &[u8]) {
+        let mut rest = chunk;
+        while let Some(pos) = rest.iter().position(|&b| b == b'\n' || b == b'\r') {
+            let (line, tail) = rest.split_at(pos);
+            let skip = self.after_cr && pos == 0 && tail[0] == b'\n';
+            self.after_cr = tail[0] == b'\r';
+            rest = &tail[1..];
+            if skip {
+                continue;
+            }
+            self.line.extend_from_slice(line);
+            self.process_line();
+        }
+        if !rest.is_empty() {
+            self.after_cr = false;
+            self.line.extend_from_slice(rest);
+        }
+    }
+
+    fn process_line(&mut self) {
+        let raw = std::mem::take(&mut self.line);
+        let mut line = String::from_utf8_lossy(&raw).into_owned();
+        if !self.started {
+            self.started = true;
+            if let Some(stripped) = line.strip_prefix('\u{feff}') {
+                line = stripped.to_owned();
+            }
+        }
+
+        if line.is_empty() {
+            self.dispatch();
+            return;
+        }
+        if line.starts_with(':') {
+            return;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_str(), ""),
+        };
+        match field {
+            "event" => self.event = value.to_owned(),
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "id" if !value.contains('\0') => self.id = Some(value.to_owned()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(ms) = value.parse() {
+                    self.retry = Some(Duration::from_millis(ms));
+                    self.reconnection_time = self.retry;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn dispatch(&mut self) {
+        let event = std::mem::take(&mut self.event);
+        let retry = self.retry.take();
+        if !std::mem::take(&mut self.has_data) {
+            return;
+        }
+
+        self.events.push_back(Event {
+            id: self.id.clone(),
+            event: if event.is_empty() {
+                "message".to_owned()
+            } else {
+                event
+            },
+            data: std::mem::take(&mut self.data),
+            retry,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(chunks: &[&[u8]]) -> Vec<Event> {
+        let mut parser = Parser::default();
+        for chunk in chunks {
+            parser.feed(chunk);
+        }
+        parser.events.into_iter().collect()
+    }
+
+    #[test]
+    fn parses_fields() {
+        let events =
+            parse(&[b"\xEF\xBB\xBFid: 1\nevent: update\ndata: a\ndata:b\nretry: 1500\n\n"]);
+        assert_eq!(
+            events,
+            vec![Event {
+                id: Some("1".to_owned()),
+                event: "update".to_owned(),
+                data: "a\nb".to_owned(),
+                retry: Some(Duration::from_millis(1500)),
+            }]
+        );
+    }
+
+    #[test]
+    fn handles_line_endings_across_chunks() {
+        let events = parse(&[
+            b"data: one\r",
+            b"\n\r",
+            b"\ndata: tw",
+            b"o\r\r",
+            b": comment\n",
+        ]);
+        let data: Vec<_> = events.iter().map(Event::data).collect();
+        assert_eq!(data, ["one", "two"]);
+        assert!(events.iter().all(|event| event.event() == "message"));
+    }
+
+    #[test]
+    fn keeps_last_event_id_and_skips_empty_events() {
+        let events = parse(&[b"id: 7\n\nevent: ping\n\ndata: x\n\n"]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id(), Some("7"));
+        assert_eq!(events[0].event(), "message");
+    }
+
+    #[test]
+    fn applies_retry_without_data() {
+        let mut parser = Parser::default();
+        parser.feed(b"retry: 2500\n\n");
+        assert!(parser.events.is_empty());
+        assert_eq!(parser.reconnection_time, Some(Duration::from_millis(2500)));
+
+        parser.feed(b"data: x\n\n");
+        assert_eq!(parser.events[0].retry(), None);
+        assert_eq!(parser.reconnection_time, Some(Duration::from_millis(2500)));
+    }
+
+    #[test]
+    fn splits_utf8_across_chunks() {
+        let text = "data: héllo\n\n".as_bytes();
+        let events = parse(&[&text[..8], &text[8..]]);
+        assert_eq!(events[0].data(), "héllo");
+    }
+}
@@ -0,0 +1,253 @@
+//! Counting the bytes requests and responses transfer.
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use http::{HeaderMap, Request, Response};
+use http_body::{Body as HttpBody, Frame};
+use pin_project_lite::pin_project;
+
+use super::body::{self, Body, ResponseBody};
+
+/// The bytes transferred by a request, or by all the requests of a client.
+///
+/// The counters are live: a handle taken from a [`Response`] before reading
+/// its body sees the body bytes as they are read. The stats of a request
+/// include its redirects and retries.
+///
+/// Heads are counted as HTTP/1.1 writes them, before HTTP/2 compresses them,
+/// and bodies without their framing, so the counters are a close estimate of
+/// the bytes on the wire rather than an exact count.
+///
+/// [`Response`]: crate::Response
+#[derive(Clone, Debug, Default)]
+pub struct TransferStats {
+    inner: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    requests: AtomicU64,
+    request_header_bytes: AtomicU64,
+    request_body_bytes: AtomicU64,
+    response_header_bytes: AtomicU64,
+    response_body_bytes: AtomicU64,
+    response_decoded_bytes: AtomicU64,
+}
+
+impl TransferStats {
+    /// The requests sent.
+    pub fn requests(&self) -> u64 {
+        self.inner.requests.load(Ordering::Relaxed)
+    }
+
+    /// The bytes of the request lines and headers sent.
+    pub fn request_header_bytes(&self) -> u64 {
+        self.inner.request_header_bytes.load(Ordering::Relaxed)
+    }
+
+    /// The bytes of the request bodies sent.
+    pub fn request_body_bytes(&self) -> u64 {
+        self.inner.request_body_bytes.load(Ordering::Relaxed)
+    }
+
+    /// The bytes of the status lines and headers received.
+    pub fn response_header_bytes(&self) -> u64 {
+        self.inner.response_header_bytes.load(Ordering::Relaxed)
+    }
+
+    /// The bytes of the response bodies received, before decompression.
+    pub fn response_body_bytes(&self) -> u64 {
+        self.inner.response_body_bytes.load(Ordering::Relaxed)
+    }
+
+    /// The bytes of the response bodies read, after decompression.
+    pub fn response_decoded_bytes(&self) -> u64 {
+        self.inner.response_decoded_bytes.load(Ordering::Relaxed)
+    }
+
+    /// The bytes sent, heads and bodies.
+    pub fn sent_bytes(&self) -> u64 {
+        self.request_header_bytes() + self.request_body_bytes()
+    }
+
+    /// The bytes received, heads and bodies before decompression.
+    pub fn received_bytes(&self) -> u64 {
+        self.response_header_bytes() + self.response_body_bytes()
+    }
+}
+
+/// The counters of a request, and of the client sending it.
+#[derive(Clone, Debug)]
+pub(crate) struct Transfer {
+    request: TransferStats,
+    client: TransferStats,
+}
+
+impl Transfer {
+    pub(crate) fn new(client: &TransferStats) -> Transfer {
+        Transfer {
+            request: TransferStats::default(),
+            client: client.clone(),
+        }
+    }
+
+    /// The counters of the request.
+    pub(crate) fn stats(&self) -> TransferStats {
+        self.request.clone()
+    }
+
+    fn add(&self, counter: fn(&Counters) -> &AtomicU64, bytes: u64) {
+        counter(&self.request.inner).fetch_add(bytes, Ordering::Relaxed);
+        counter(&self.client.inner).fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Count a request about to be sent, without its body.
+    pub(crate) fn sent<B>(&self, req: &Request<B>) {
+        let target = req
+            .uri()
+            .path_and_query()
+            .map_or(1, |path_and_query| path_and_query.as_str().len());
+        // "GET / HTTP/1.1\r\n"
+        let line = req.method().as_str().len() + 1 + target + 1 + 8 + 2;
+
+        self.add(|counters| &counters.requests, 1);
+        self.add(
+            |counters| &counters.request_header_bytes,
+            (line + headers_len(req.headers())) as u64,
+        );
+    }
+
+    /// Count the head of a response received.
+    pub(crate) fn received<B>(&self, res: &Response<B>) {
+        let reason = res.status().canonical_reason().map_or(0, str::len);
+        // "HTTP/1.1 200 OK\r\n"
+        let line = 8 + 1 + 3 + 1 + reason + 2;
+
+        self.add(
+            |counters| &counters.response_header_bytes,
+            (line + headers_len(res.headers())) as u64,
+        );
+    }
+
+    /// Count the bytes of a response body, after decompression.
+    pub(crate) fn decoded(&self, bytes: usize) {
+        self.add(|counters| &counters.response_decoded_bytes, bytes as u64);
+    }
+}
+
+/// The length of `headers` written as HTTP/1.1 lines, with the empty line
+/// ending the head.
+fn headers_len(headers: &HeaderMap) -> usize {
+    headers
+        .iter()
+        .map(|(name, value)| name.as_str().len() + 2 + value.len() + 2)
+        .sum::<usize>()
+        + 2
+}
+
+pin_project! {
+    /// A body counting the bytes of its chunks.
+    struct CountedBody<B> {
+        #[pin]
+        inner: B,
+        transfer: Transfer,
+        counter: fn(&Counters) -> &AtomicU64,
+    }
+}
+
+impl<B> HttpBody for CountedBody<B>
+where
+    B: HttpBody<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = futures_util::ready!(this.inner.poll_frame(cx));
+
+        if let Some(data) = frame
+            .as_ref()
+            .and_then(|frame| frame.as_ref().ok())
+            .and_then(Frame::data_ref)
+        {
+            this.transfer.add(*this.counter, data.len() as u64);
+        }
+
+        Poll::Ready(frame)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+}
+
+/// Count the bytes of a request body being sent.
+pub(crate) fn upload(body: Body, transfer: &Transfer) -> Body {
+    // a body in memory is sent whole, with no need to wrap it
+    if let Some(bytes) = body.as_bytes() {
+        transfer.add(|counters| &counters.request_body_bytes, bytes.len() as u64);
+        return body;
+    }
+
+    Body::wrap(CountedBody {
+        inner: body,
+        transfer: transfer.clone(),
+        counter: |counters| &counters.request_body_bytes,
+    })
+}
+
+/// Count the bytes of a response body being received, before decompression.
+pub(crate) fn download(body: ResponseBody, transfer: &Transfer) -> ResponseBody {
+    body::boxed(CountedBody {
+        inner: body,
+        transfer: transfer.clone(),
+        counter: |counters| &counters.response_body_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_heads_in_request_and_client() {
+        let client = TransferStats::default();
+        let transfer = Transfer::new(&client);
+
+        let req = Request::get("http://example.com/a?b")
+            .header("accept", "*/*")
+            .body(())
+            .unwrap();
+        transfer.sent(&req);
+        // "GET /a?b HTTP/1.1\r\n" "accept: */*\r\n" "\r\n"
+        assert_eq!(transfer.stats().request_header_bytes(), 19 + 13 + 2);
+
+        let res = Response::builder()
+            .status(404)
+            .header("content-length", "0")
+            .body(())
+            .unwrap();
+        transfer.received(&res);
+        // "HTTP/1.1 404 Not Found\r\n" "content-length: 0\r\n" "\r\n"
+        assert_eq!(transfer.stats().response_header_bytes(), 24 + 19 + 2);
+
+        let other = Transfer::new(&client);
+        other.sent(&req);
+        assert_eq!(transfer.stats().requests(), 1);
+        assert_eq!(client.requests(), 2);
+        assert_eq!(client.sent_bytes(), 2 * 34);
+    }
+}
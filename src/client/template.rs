@@ -0,0 +1,216 @@
+//! Requests prepared once and sent many times.
+use std::fmt;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use super::http::Client;
+use super::request::{Request, RequestBuilder};
+use crate::{error, Url};
+
+/// The characters of a parameter value to percent-encode: all but the
+/// unreserved ones, so a value never adds a path segment or query parameter.
+const VALUE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// A request prepared once, to send many times with different URL
+/// parameters.
+///
+/// The method, headers, timeouts, body and every other setting of the
+/// request are captured when the template is made, so sending it again only
+/// fills in the parameters of the URL and copies the request.
+///
+/// The URL may hold `{name}` placeholders, filled in with the percent-encoded
+/// value of the parameter of that name.
+///
+/// To construct a `RequestTemplate`, refer to [`RequestBuilder::template`].
+///
+/// # Example
+///
+/// ```rust
+/// # async fn run() -> Result<(), rquest::Error> {
+/// let client = rquest::Client::new();
+/// let template = client
+///     .get("https://api.example.com/items/{id}")
+///     .header("accept", "application/json")
+///     .template()?;
+///
+/// for id in ["1", "2", "3"] {
+///     let res = template.request(&[("id", id)]).send().await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct RequestTemplate {
+    client: Client,
+    request: Request,
+    url: Vec<Segment>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+}
+
+impl RequestTemplate {
+    pub(super) fn new(client: Client, request: Request) -> crate::Result<RequestTemplate> {
+        if request.try_clone().is_none() {
+            return Err(error::builder("a request template needs a reusable body"));
+        }
+        let url = parse(request.url().as_str());
+        Ok(RequestTemplate {
+            client,
+            request,
+            url,
+        })
+    }
+
+    /// The names of the parameters of the URL, in order.
+    pub fn params(&self) -> impl Iterator<Item = &str> {
+        self.url.iter().filter_map(|segment| match segment {
+            Segment::Param(name) => Some(name.as_str()),
+            Segment::Literal(_) => None,
+        })
+    }
+
+    /// Start a request from the template, with the URL parameters `params`.
+    ///
+    /// The returned builder may change the request further before sending.
+    ///
+    /// # Errors
+    ///
+    /// Sending the request fails if a parameter of the URL is missing from
+    /// `params`.
+    pub fn request<K, V>(&self, params: &[(K, V)]) -> RequestBuilder
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let request = self.url(params).and_then(|url| {
+            let mut request = self
+                .request
+                .try_clone()
+                .ok_or_else(|| error::builder("request body is not reusable"))?;
+            *request.url_mut() = url;
+            Ok(request)
+        });
+        RequestBuilder::new(self.client.clone(), request)
+    }
+
+    fn url<K, V>(&self, params: &[(K, V)]) -> crate::Result<Url>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        if !self.url.iter().any(|s| matches!(s, Segment::Param(_))) {
+            return Ok(self.request.url().clone());
+        }
+
+        let mut url = String::with_capacity(self.request.url().as_str().len());
+        for segment in &self.url {
+            match segment {
+                Segment::Literal(literal) => url.push_str(literal),
+                Segment::Param(name) => {
+                    let value = params
+                        .iter()
+                        .find(|(key, _)| key.as_ref() == name)
+                        .map(|(_, value)| value.as_ref())
+                        .ok_or_else(|| error::builder(format!("missing URL parameter `{name}`")))?;
+                    url.extend(utf8_percent_encode(value, VALUE));
+                }
+            }
+        }
+        Url::parse(&url).map_err(error::builder)
+    }
+}
+
+impl fmt::Debug for RequestTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RequestTemplate")
+            .field("method", self.request.method())
+            .field("url", &self.request.url().as_str())
+            .field("headers", self.request.headers())
+            .finish()
+    }
+}
+
+/// Split a URL at its `{name}` placeholders, which parsing may have
+/// percent-encoded.
+fn parse(url: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut rest = url;
+    while let Some(ch) = rest.chars().next() {
+        match placeholder(rest) {
+            Some((name, after)) => {
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(Segment::Param(name.to_owned()));
+                rest = after;
+            }
+            None => {
+                literal.push(ch);
+                rest = &rest[ch.len_utf8()..];
+            }
+        }
+    }
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    segments
+}
+
+/// The name of the placeholder at the start of `s`, and what follows it.
+fn placeholder(s: &str) -> Option<(&str, &str)> {
+    let (open, close) = if s.starts_with('{') {
+        (1, "}")
+    } else if s.get(..3).is_some_and(|s| s.eq_ignore_ascii_case("%7B")) {
+        (3, "%7D")
+    } else {
+        return None;
+    };
+    let s = &s[open..];
+    let len = s
+        .bytes()
+        .take_while(|b| b.is_ascii_alphanumeric() || *b == b'_')
+        .count();
+    let (name, after) = s.split_at(len);
+    let closed = after
+        .get(..close.len())
+        .is_some_and(|s| s.eq_ignore_ascii_case(close));
+    if name.is_empty() || !closed {
+        return None;
+    }
+    Some((name, &after[close.len()..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_placeholders() {
+        assert_eq!(
+            parse("https://a.test/items/%7Bid%7D/x?q={query}&{n"),
+            vec![
+                Segment::Literal("https://a.test/items/".to_owned()),
+                Segment::Param("id".to_owned()),
+                Segment::Literal("/x?q=".to_owned()),
+                Segment::Param("query".to_owned()),
+                Segment::Literal("&{n".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_other_braces() {
+        assert_eq!(
+            parse("https://a.test/%7B%7D/{a-b}"),
+            vec![Segment::Literal("https://a.test/%7B%7D/{a-b}".to_owned())]
+        );
+    }
+}
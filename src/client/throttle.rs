@@ -0,0 +1,205 @@
+//! Bandwidth caps for request and response bodies.
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use antidote::Mutex;
+use bytes::Bytes;
+use http_body::{Body as HttpBody, Frame};
+use pin_project_lite::pin_project;
+use tokio::time::Sleep;
+
+use super::body::{self, Body, ResponseBody};
+
+/// A cap on the bytes per second a body is sent or received at.
+///
+/// The cap is a token bucket: after an idle period, up to `burst` bytes go
+/// through at once, then the rate settles at `bytes_per_sec`.
+///
+/// Clones share their bucket, so one `Throttle` given to several clients or
+/// requests caps their combined bandwidth.
+///
+/// # Example
+///
+/// ```
+/// use rquest::Throttle;
+///
+/// # fn run() -> Result<(), rquest::Error> {
+/// let client = rquest::Client::builder()
+///     .download_throttle(Throttle::new(512 * 1024).burst(64 * 1024))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Throttle {
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+struct Bucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Throttle {
+    /// Cap the bandwidth at `bytes_per_sec`, with bursts of up to one
+    /// second's worth of bytes.
+    pub fn new(bytes_per_sec: u64) -> Throttle {
+        let rate = bytes_per_sec.max(1) as f64;
+        Throttle {
+            bucket: Arc::new(Mutex::new(Bucket {
+                rate,
+                burst: rate,
+                tokens: rate,
+                updated: Instant::now(),
+            })),
+        }
+    }
+
+    /// Let up to `bytes` through at once after an idle period.
+    pub fn burst(self, bytes: u64) -> Throttle {
+        {
+            let mut bucket = self.bucket.lock();
+            bucket.burst = bytes as f64;
+            bucket.tokens = bucket.tokens.min(bucket.burst);
+        }
+        self
+    }
+
+    /// Take `len` bytes from the bucket, returning how long to wait before
+    /// they may go through.
+    fn reserve(&self, len: usize) -> Duration {
+        let now = Instant::now();
+        let mut bucket = self.bucket.lock();
+
+        let elapsed = now.saturating_duration_since(bucket.updated);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * bucket.rate).min(bucket.burst);
+        bucket.updated = now;
+
+        // Chunks larger than the bucket go through too, once the debt is
+        // paid off.
+        bucket.tokens -= len as f64;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / bucket.rate)
+        }
+    }
+}
+
+impl fmt::Debug for Throttle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bucket = self.bucket.lock();
+        f.debug_struct("Throttle")
+            .field("bytes_per_sec", &bucket.rate)
+            .field("burst", &bucket.burst)
+            .finish()
+    }
+}
+
+pin_project! {
+    /// A body holding back each chunk until its throttles let it through.
+    pub(crate) struct ThrottledBody<B> {
+        #[pin]
+        inner: B,
+        throttles: Vec<Throttle>,
+        #[pin]
+        sleep: Option<Sleep>,
+        pending: Option<Frame<Bytes>>,
+    }
+}
+
+impl<B> HttpBody for ThrottledBody<B>
+where
+    B: HttpBody<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(sleep) = this.sleep.as_mut().as_pin_mut() {
+                futures_util::ready!(sleep.poll(cx));
+                this.sleep.set(None);
+                return Poll::Ready(this.pending.take().map(Ok));
+            }
+
+            let frame = match futures_util::ready!(this.inner.as_mut().poll_frame(cx)) {
+                Some(Ok(frame)) => frame,
+                other => return Poll::Ready(other),
+            };
+
+            let len = frame.data_ref().map_or(0, Bytes::len);
+            let delay = this
+                .throttles
+                .iter()
+                .map(|throttle| throttle.reserve(len))
+                .max()
+                .unwrap_or_default();
+
+            if delay.is_zero() {
+                return Poll::Ready(Some(Ok(frame)));
+            }
+
+            *this.pending = Some(frame);
+            this.sleep.set(Some(tokio::time::sleep(delay)));
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        let mut hint = self.inner.size_hint();
+        if let Some(len) = self
+            .pending
+            .as_ref()
+            .and_then(Frame::data_ref)
+            .map(|data| data.len() as u64)
+        {
+            if let Some(upper) = hint.upper() {
+                hint.set_upper(upper + len);
+            }
+            hint.set_lower(hint.lower() + len);
+        }
+        hint
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        self.pending.is_none() && self.inner.is_end_stream()
+    }
+}
+
+fn throttled<B>(inner: B, throttles: Vec<Throttle>) -> ThrottledBody<B> {
+    ThrottledBody {
+        inner,
+        throttles,
+        sleep: None,
+        pending: None,
+    }
+}
+
+/// Cap the bandwidth a request body is sent at.
+pub(crate) fn upload(body: Body, throttles: &[Throttle]) -> Body {
+    if throttles.is_empty() {
+        return body;
+    }
+    Body::wrap(throttled(body, throttles.to_vec()))
+}
+
+/// Cap the bandwidth a response body is received at.
+pub(crate) fn download(body: ResponseBody, throttles: &[Throttle]) -> ResponseBody {
+    if throttles.is_empty() {
+        return body;
+    }
+    body::boxed(throttled(body, throttles.to_vec()))
+}
@@ -2,13 +2,32 @@ use std::pin::Pin;
 use std::task::{self, Poll};
 use std::{fmt, io};
 
+use crate::connect::sealed::Conn;
 use crate::util::rt::TokioIo;
+use crate::StatusCode;
 use futures_util::TryFutureExt;
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
-/// An upgraded HTTP connection.
+/// An upgraded HTTP connection, or a tunnel from [`Client::tunnel`].
+///
+/// [`Client::tunnel`]: crate::Client::tunnel
 pub struct Upgraded {
-    inner: TokioIo<hyper2::upgrade::Upgraded>,
+    inner: Inner,
+}
+
+enum Inner {
+    Upgraded(TokioIo<hyper2::upgrade::Upgraded>),
+    Tunnel(TokioIo<Conn>),
+}
+
+/// Calls the same method on the stream of either kind.
+macro_rules! delegate {
+    ($self:ident.$method:ident($($arg:expr),*)) => {
+        match $self.inner {
+            Inner::Upgraded(ref mut io) => Pin::new(io).$method($($arg),*),
+            Inner::Tunnel(ref mut io) => Pin::new(io).$method($($arg),*),
+        }
+    };
 }
 
 impl AsyncRead for Upgraded {
@@ -17,7 +36,7 @@ impl AsyncRead for Upgraded {
         cx: &mut task::Context<'_>,
         buf: &mut ReadBuf<'_>,
     ) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.inner).poll_read(cx, buf)
+        delegate!(self.poll_read(cx, buf))
     }
 }
 
@@ -27,7 +46,7 @@ impl AsyncWrite for Upgraded {
         cx: &mut task::Context<'_>,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.inner).poll_write(cx, buf)
+        delegate!(self.poll_write(cx, buf))
     }
 
     fn poll_write_vectored(
@@ -35,19 +54,22 @@ impl AsyncWrite for Upgraded {
         cx: &mut task::Context<'_>,
         bufs: &[io::IoSlice<'_>],
     ) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+        delegate!(self.poll_write_vectored(cx, bufs))
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.inner).poll_flush(cx)
+        delegate!(self.poll_flush(cx))
     }
 
     fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.inner).poll_shutdown(cx)
+        delegate!(self.poll_shutdown(cx))
     }
 
     fn is_write_vectored(&self) -> bool {
-        self.inner.is_write_vectored()
+        match self.inner {
+            Inner::Upgraded(ref io) => io.is_write_vectored(),
+            Inner::Tunnel(ref io) => io.is_write_vectored(),
+        }
     }
 }
 
@@ -60,14 +82,37 @@ impl fmt::Debug for Upgraded {
 impl From<hyper2::upgrade::Upgraded> for Upgraded {
     fn from(inner: hyper2::upgrade::Upgraded) -> Self {
         Upgraded {
-            inner: TokioIo::new(inner),
+            inner: Inner::Upgraded(TokioIo::new(inner)),
+        }
+    }
+}
+
+impl From<Conn> for Upgraded {
+    fn from(conn: Conn) -> Self {
+        Upgraded {
+            inner: Inner::Tunnel(TokioIo::new(conn)),
         }
     }
 }
 
 impl super::response::Response {
     /// Consumes the response and returns a future for a possible HTTP upgrade.
+    ///
+    /// The connection is upgraded by a `101 Switching Protocols` response,
+    /// or by a successful response to a `CONNECT` request.
+    ///
+    /// # Errors
+    ///
+    /// This fails if the server did not switch protocols, such as with a
+    /// `4xx` response refusing to.
     pub async fn upgrade(self) -> crate::Result<Upgraded> {
+        let status = self.status();
+        if status != StatusCode::SWITCHING_PROTOCOLS && !status.is_success() {
+            return Err(crate::error::upgrade(format!(
+                "server did not switch protocols: {status}"
+            ))
+            .with_url(self.url().clone()));
+        }
         hyper2::upgrade::on(self.res)
             .map_ok(Upgraded::from)
             .map_err(crate::error::upgrade)
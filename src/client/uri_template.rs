@@ -0,0 +1,349 @@
+//! URI Templates ([RFC 6570]), up to level 4.
+//!
+//! [RFC 6570]: https://datatracker.ietf.org/doc/html/rfc6570
+use std::fmt::Write as _;
+
+use serde::Serialize;
+
+use super::query::{self, QueryStyle};
+
+/// The value of a template variable.
+#[derive(Debug, PartialEq)]
+enum Value {
+    String(String),
+    List(Vec<String>),
+    Map(Vec<(String, String)>),
+}
+
+/// The variables of a template, from a serializable value.
+///
+/// A field holding a sequence is a list, a nested struct or map is an
+/// associative array, and any other field is a string.
+struct Vars(Vec<(String, Value)>);
+
+impl Vars {
+    fn new<T: Serialize + ?Sized>(params: &T) -> Result<Vars, query::Error> {
+        let mut vars: Vec<(String, Value)> = Vec::new();
+        for (key, value) in query::to_pairs(params, QueryStyle::new())? {
+            // nested fields are named `name[field]`
+            let (name, field) = match key.split_once('[') {
+                Some((name, field)) if field.ends_with(']') => {
+                    (name.to_owned(), Some(field[..field.len() - 1].to_owned()))
+                }
+                _ => (key, None),
+            };
+            let var = match vars.iter_mut().find(|(key, _)| *key == name) {
+                Some((_, var)) => var,
+                None => {
+                    let var = match field {
+                        Some(field) => Value::Map(vec![(field, value)]),
+                        None => Value::String(value),
+                    };
+                    vars.push((name, var));
+                    continue;
+                }
+            };
+            match (&mut *var, field) {
+                (Value::Map(map), Some(field)) => map.push((field, value)),
+                (Value::List(list), None) => list.push(value),
+                // a repeated key is a sequence
+                (Value::String(first), None) => {
+                    let first = std::mem::take(first);
+                    *var = Value::List(vec![first, value]);
+                }
+                _ => {
+                    return Err(<query::Error as serde::ser::Error>::custom(format!(
+                        "template variable `{name}` mixes fields and values"
+                    )))
+                }
+            }
+        }
+        Ok(Vars(vars))
+    }
+
+    fn get(&self, name: &str) -> Option<&Value> {
+        self.0
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// How an operator expands its variables.
+struct Operator {
+    first: &'static str,
+    sep: &'static str,
+    named: bool,
+    if_empty: &'static str,
+    allow_reserved: bool,
+}
+
+impl Operator {
+    fn new(op: Option<char>) -> Option<Operator> {
+        let (first, sep, named, if_empty, allow_reserved) = match op {
+            None => ("", ",", false, "", false),
+            Some('+') => ("", ",", false, "", true),
+            Some('#') => ("#", ",", false, "", true),
+            Some('.') => (".", ".", false, "", false),
+            Some('/') => ("/", "/", false, "", false),
+            Some(';') => (";", ";", true, "", false),
+            Some('?') => ("?", "&", true, "=", false),
+            Some('&') => ("&", "&", true, "=", false),
+            Some(_) => return None,
+        };
+        Some(Operator {
+            first,
+            sep,
+            named,
+            if_empty,
+            allow_reserved,
+        })
+    }
+
+    /// Percent-encode `value`, but for the unreserved characters, and the
+    /// reserved ones and percent-encoded triplets if the operator allows
+    /// them.
+    fn encode(&self, value: &str, out: &mut String) {
+        let bytes = value.as_bytes();
+        for (i, &b) in bytes.iter().enumerate() {
+            let unreserved = b.is_ascii_alphanumeric() || b"-._~".contains(&b);
+            let reserved = b":/?#[]@!$&'()*+,;=".contains(&b);
+            let triplet = b == b'%'
+                && bytes.get(i + 1).is_some_and(u8::is_ascii_hexdigit)
+                && bytes.get(i + 2).is_some_and(u8::is_ascii_hexdigit);
+            if unreserved || (self.allow_reserved && (reserved || triplet)) {
+                out.push(b as char);
+            } else {
+                let _ = write!(out, "%{b:02X}");
+            }
+        }
+    }
+}
+
+/// Expand `template` with the variables of `params`.
+pub(crate) fn expand<T: Serialize + ?Sized>(template: &str, params: &T) -> Result<String, String> {
+    let vars = Vars::new(params).map_err(|err| err.to_string())?;
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let close = rest[open..]
+            .find('}')
+            .ok_or_else(|| format!("unclosed expression in URI template `{template}`"))?;
+        expand_expression(&rest[open + 1..open + close], &vars, &mut out)?;
+        rest = &rest[open + close + 1..];
+    }
+    if rest.contains('}') {
+        return Err(format!("unopened expression in URI template `{template}`"));
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn expand_expression(expression: &str, vars: &Vars, out: &mut String) -> Result<(), String> {
+    let invalid = || format!("invalid URI template expression `{{{expression}}}`");
+    let (op, list) = match expression.chars().next() {
+        Some(c) if !c.is_ascii_alphanumeric() && c != '_' && c != '%' => {
+            (Some(c), &expression[c.len_utf8()..])
+        }
+        _ => (None, expression),
+    };
+    let op = Operator::new(op).ok_or_else(invalid)?;
+
+    let mut first = true;
+    for varspec in list.split(',') {
+        let (name, explode, prefix) = if let Some(name) = varspec.strip_suffix('*') {
+            (name, true, None)
+        } else if let Some((name, len)) = varspec.split_once(':') {
+            let len = len
+                .parse::<usize>()
+                .ok()
+                .filter(|len| (1..10_000).contains(len))
+                .ok_or_else(invalid)?;
+            (name, false, Some(len))
+        } else {
+            (varspec, false, None)
+        };
+        if name.is_empty()
+            || !name
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'.' || b == b'%')
+        {
+            return Err(invalid());
+        }
+
+        let value = match vars.get(name) {
+            Some(Value::List(list)) if list.is_empty() => continue,
+            Some(Value::Map(map)) if map.is_empty() => continue,
+            Some(value) => value,
+            None => continue,
+        };
+        out.push_str(if first { op.first } else { op.sep });
+        first = false;
+
+        match value {
+            Value::String(value) => {
+                if op.named {
+                    out.push_str(name);
+                    if value.is_empty() {
+                        out.push_str(op.if_empty);
+                        continue;
+                    }
+                    out.push('=');
+                }
+                let value = match prefix {
+                    Some(len) => value
+                        .char_indices()
+                        .nth(len)
+                        .map_or(value.as_str(), |(end, _)| &value[..end]),
+                    None => value,
+                };
+                op.encode(value, out);
+            }
+            Value::List(list) if !explode => {
+                if op.named {
+                    out.push_str(name);
+                    out.push('=');
+                }
+                for (i, item) in list.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    op.encode(item, out);
+                }
+            }
+            Value::List(list) => {
+                for (i, item) in list.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(op.sep);
+                    }
+                    if op.named {
+                        out.push_str(name);
+                        if item.is_empty() {
+                            out.push_str(op.if_empty);
+                            continue;
+                        }
+                        out.push('=');
+                    }
+                    op.encode(item, out);
+                }
+            }
+            Value::Map(map) if !explode => {
+                if op.named {
+                    out.push_str(name);
+                    out.push('=');
+                }
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    op.encode(key, out);
+                    out.push(',');
+                    op.encode(value, out);
+                }
+            }
+            Value::Map(map) => {
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(op.sep);
+                    }
+                    op.encode(key, out);
+                    if op.named && value.is_empty() {
+                        out.push_str(op.if_empty);
+                        continue;
+                    }
+                    out.push('=');
+                    op.encode(value, out);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Params {
+        var: &'static str,
+        hello: &'static str,
+        half: &'static str,
+        path: &'static str,
+        empty: &'static str,
+        x: &'static str,
+        y: &'static str,
+        list: Vec<&'static str>,
+        keys: BTreeMap<&'static str, &'static str>,
+        undef: Option<&'static str>,
+    }
+
+    /// The variables of the examples of RFC 6570, section 3.2.
+    fn params() -> Params {
+        Params {
+            var: "value",
+            hello: "Hello World!",
+            half: "50%",
+            path: "/foo/bar",
+            empty: "",
+            x: "1024",
+            y: "768",
+            list: vec!["red", "green", "blue"],
+            keys: [("comma", ","), ("dot", "."), ("semi", ";")]
+                .into_iter()
+                .collect(),
+            undef: None,
+        }
+    }
+
+    #[track_caller]
+    fn check(template: &str, expected: &str) {
+        assert_eq!(expand(template, &params()).unwrap(), expected, "{template}");
+    }
+
+    #[test]
+    fn expands_rfc_examples() {
+        check("{var}", "value");
+        check("{hello}", "Hello%20World%21");
+        check("{half}", "50%25");
+        check("O{empty}X", "OX");
+        check("O{undef}X", "OX");
+        check("{x,y}", "1024,768");
+        check("{var:3}", "val");
+        check("{list}", "red,green,blue");
+        check("{list*}", "red,green,blue");
+        check("{keys}", "comma,%2C,dot,.,semi,%3B");
+        check("{keys*}", "comma=%2C,dot=.,semi=%3B");
+
+        check("{+path}/here", "/foo/bar/here");
+        check("{+hello}", "Hello%20World!");
+        check("{+half}", "50%25");
+        check("{#var}", "#value");
+        check("{#path:6}/here", "#/foo/b/here");
+
+        check("X{.var}", "X.value");
+        check("X{.list*}", "X.red.green.blue");
+        check("{/var,x}/here", "/value/1024/here");
+        check("{/list*,path:4}", "/red/green/blue/%2Ffoo");
+        check("{/keys*}", "/comma=%2C/dot=./semi=%3B");
+
+        check("{;x,y,empty}", ";x=1024;y=768;empty");
+        check("{;list*}", ";list=red;list=green;list=blue");
+        check("{?x,y,empty}", "?x=1024&y=768&empty=");
+        check("{?undef}", "");
+        check("{?list}", "?list=red,green,blue");
+        check("{?keys*}", "?comma=%2C&dot=.&semi=%3B");
+        check("?fixed=yes{&x}", "?fixed=yes&x=1024");
+        check("{&list*}", "&list=red&list=green&list=blue");
+    }
+
+    #[test]
+    fn rejects_malformed_templates() {
+        assert!(expand("{var", &params()).is_err());
+        assert!(expand("var}", &params()).is_err());
+        assert!(expand("{!var}", &params()).is_err());
+        assert!(expand("{var:0}", &params()).is_err());
+    }
+}
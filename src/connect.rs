@@ -1,13 +1,19 @@
+use self::h2_tunnel::ProxyConnection;
 use self::tls_conn::BoringTlsConn;
-use crate::tls::{BoringTlsConnector, HttpsConnector, MaybeHttpsStream};
-use crate::util::client::connect::{Connected, Connection};
+use crate::tls::{AlpnProtos, BoringTlsConnector, HttpsConnector, MaybeHttpsStream, TlsPinning};
+use crate::transport::{self, Transport};
+use crate::util::client::connect::{Connected, Connection, ProxyProtocolMatcher};
 use crate::util::client::Dst;
 use crate::util::rt::TokioIo;
 use crate::util::{self, into_uri};
+use boring2::ssl::SslRef;
+use bytes::Bytes;
 use http::uri::Scheme;
+use http::Uri;
 use hyper2::rt::{Read, ReadBufCursor, Write};
 use pin_project_lite::pin_project;
 use sealed::{Conn, Unnameable};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tokio_boring2::SslStream;
 use tower::util::{BoxCloneSyncServiceLayer, MapRequestLayer};
 use tower::{timeout::TimeoutLayer, util::BoxCloneSyncService, ServiceBuilder};
@@ -16,6 +22,7 @@ use tower_service::Service;
 use std::future::Future;
 use std::io::{self, IoSlice};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
 
@@ -33,23 +40,39 @@ pub(crate) type BoxedConnectorLayer =
 pub(crate) struct ConnectorBuilder {
     http: HttpConnector,
     tls: BoringTlsConnector,
+    proxy_tls: Option<BoringTlsConnector>,
+    h2_tunnels: Option<h2_tunnel::H2Tunnels>,
     verbose: verbose::Wrapper,
     timeout: Option<Duration>,
     nodelay: bool,
     tls_info: bool,
+    debug_capture: bool,
+    tls_pinning: Option<Arc<TlsPinning>>,
+    transport: Option<Arc<dyn transport::Connector>>,
+    proxy_protocol: Arc<ProxyProtocolMatcher>,
 }
 
 impl ConnectorBuilder {
-    pub(crate) fn build<L>(self, layers: L) -> Connector
+    pub(crate) fn build<L>(mut self, layers: L) -> Connector
     where
         L: Into<Option<Vec<BoxedConnectorLayer>>>,
     {
+        if self.debug_capture {
+            self.tls.set_capture_client_hello(true);
+        }
+
         let base_service = ConnectorService {
             http: self.http,
             tls: self.tls,
+            proxy_tls: self.proxy_tls,
+            h2_tunnels: self.h2_tunnels,
             verbose: self.verbose,
             nodelay: self.nodelay,
             tls_info: self.tls_info,
+            debug_capture: self.debug_capture,
+            tls_pinning: self.tls_pinning,
+            transport: self.transport,
+            proxy_protocol: self.proxy_protocol,
             timeout: self.timeout,
         };
 
@@ -119,13 +142,60 @@ impl ConnectorBuilder {
         ConnectorBuilder {
             http,
             tls,
+            proxy_tls: None,
+            h2_tunnels: None,
             verbose: verbose::OFF,
             timeout: None,
             nodelay,
             tls_info,
+            debug_capture: false,
+            tls_pinning: None,
+            transport: None,
+            proxy_protocol: Arc::default(),
         }
     }
 
+    #[inline]
+    pub(crate) fn proxy_tls(mut self, proxy_tls: Option<BoringTlsConnector>) -> ConnectorBuilder {
+        self.proxy_tls = proxy_tls;
+        self
+    }
+
+    #[inline]
+    pub(crate) fn proxy_http2(self, enabled: bool) -> ConnectorBuilder {
+        self.h2_tunnels(enabled.then(h2_tunnel::H2Tunnels::default))
+    }
+
+    #[inline]
+    fn h2_tunnels(mut self, h2_tunnels: Option<h2_tunnel::H2Tunnels>) -> ConnectorBuilder {
+        self.h2_tunnels = h2_tunnels;
+        self
+    }
+
+    #[inline]
+    pub(crate) fn tls_pinning(mut self, pinning: Option<Arc<TlsPinning>>) -> ConnectorBuilder {
+        self.tls_pinning = pinning;
+        self
+    }
+
+    #[inline]
+    pub(crate) fn transport(
+        mut self,
+        transport: Option<Arc<dyn transport::Connector>>,
+    ) -> ConnectorBuilder {
+        self.transport = transport;
+        self
+    }
+
+    #[inline]
+    pub(crate) fn proxy_protocol(
+        mut self,
+        proxy_protocol: Arc<ProxyProtocolMatcher>,
+    ) -> ConnectorBuilder {
+        self.proxy_protocol = proxy_protocol;
+        self
+    }
+
     #[inline]
     pub(crate) fn keepalive(mut self, dur: Option<Duration>) -> ConnectorBuilder {
         self.http.set_keepalive(dur);
@@ -143,6 +213,12 @@ impl ConnectorBuilder {
         self.verbose.0 = enabled;
         self
     }
+
+    #[inline]
+    pub(crate) fn debug_capture(mut self, enabled: bool) -> ConnectorBuilder {
+        self.debug_capture = enabled;
+        self
+    }
 }
 
 #[derive(Clone)]
@@ -159,9 +235,20 @@ pub(crate) enum Connector {
 }
 
 impl Connector {
+    /// The TLS connector of the base service.
+    pub(crate) fn tls(&self) -> &BoringTlsConnector {
+        match self {
+            Connector::Simple(service) => &service.tls,
+            Connector::WithLayers { base_service, .. } => &base_service.tls,
+        }
+    }
+
     pub(crate) fn set_connector(&mut self, mut connector: BoringTlsConnector) {
         match self {
             Connector::Simple(service) => {
+                connector.set_https_records(service.tls.https_records());
+                connector.set_handshake_timeout(service.tls.handshake_timeout());
+                connector.set_capture_client_hello(service.debug_capture);
                 std::mem::swap(&mut service.tls, &mut connector);
             }
             Connector::WithLayers {
@@ -169,6 +256,8 @@ impl Connector {
                 base_service,
                 ..
             } => {
+                connector.set_https_records(base_service.tls.https_records());
+                connector.set_handshake_timeout(base_service.tls.handshake_timeout());
                 let mut connector = ConnectorBuilder::new(
                     base_service.http.clone(),
                     connector,
@@ -177,6 +266,12 @@ impl Connector {
                 )
                 .timeout(base_service.timeout)
                 .verbose(base_service.verbose.0)
+                .debug_capture(base_service.debug_capture)
+                .proxy_tls(base_service.proxy_tls.clone())
+                .h2_tunnels(base_service.h2_tunnels.clone())
+                .tls_pinning(base_service.tls_pinning.clone())
+                .transport(base_service.transport.clone())
+                .proxy_protocol(base_service.proxy_protocol.clone())
                 .build(std::mem::take(layers));
 
                 std::mem::swap(self, &mut connector);
@@ -209,6 +304,10 @@ impl Service<Dst> for Connector {
 pub(crate) struct ConnectorService {
     http: HttpConnector,
     tls: BoringTlsConnector,
+    /// The TLS connector for HTTPS proxies, if not `tls`.
+    proxy_tls: Option<BoringTlsConnector>,
+    /// The HTTP/2 connections to proxies, if tunnels may share them.
+    h2_tunnels: Option<h2_tunnel::H2Tunnels>,
     verbose: verbose::Wrapper,
     /// When there is a single timeout layer and no other layers,
     /// we embed it directly inside our base Service::call().
@@ -217,9 +316,49 @@ pub(crate) struct ConnectorService {
     timeout: Option<Duration>,
     nodelay: bool,
     tls_info: bool,
+    /// Whether connections are recorded for debug captures.
+    debug_capture: bool,
+    tls_pinning: Option<Arc<TlsPinning>>,
+    transport: Option<Arc<dyn transport::Connector>>,
+    proxy_protocol: Arc<ProxyProtocolMatcher>,
 }
 
 impl ConnectorService {
+    /// The TLS connector to reach HTTPS proxies with.
+    fn proxy_tls(&self) -> BoringTlsConnector {
+        self.proxy_tls.clone().unwrap_or_else(|| self.tls.clone())
+    }
+
+    /// Checks the server's TLS fingerprint against the one pinned for its origin.
+    fn check_tls_pinning(&self, uri: &Uri, ssl: &SslRef) -> Result<(), BoxError> {
+        if let Some(ref pinning) = self.tls_pinning {
+            let host = uri.host().ok_or(crate::error::uri_bad_host())?;
+            let origin = format!("{}:{}", host, uri.port_u16().unwrap_or(443));
+            pinning.check(&origin, ssl)?;
+        }
+        Ok(())
+    }
+
+    /// Sends a PROXY protocol header over a tunneled connection to a matching
+    /// destination, before any TLS handshake.
+    ///
+    /// The addresses of the tunnel's ends are not known to us, so the header
+    /// announces an unknown connection.
+    async fn tunnel_proxy_protocol<T>(&self, dst: &Dst, conn: &mut T) -> Result<(), BoxError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        if let Some(version) = dst.host().and_then(|host| self.proxy_protocol.find(host)) {
+            log::trace!(
+                "sending {:?} PROXY protocol header to {:?}",
+                version,
+                dst.uri()
+            );
+            conn.write_all(&version.encode_unknown()).await?;
+        }
+        Ok(())
+    }
+
     #[cfg(feature = "socks")]
     async fn connect_socks(&self, mut dst: Dst, proxy: ProxyScheme) -> Result<Conn, BoxError> {
         let dns = match proxy {
@@ -245,9 +384,11 @@ impl ConnectorService {
 
             log::trace!("socks HTTPS over proxy");
             let host = dst.host().ok_or(crate::error::uri_bad_host())?;
-            let conn = socks::connect(proxy, &dst, dns).await?;
+            let mut conn = socks::connect(proxy, &dst, dns).await?;
+            self.tunnel_proxy_protocol(&dst, &mut conn).await?;
 
             let io = http.connect(&dst, host, TokioIo::new(conn)).await?;
+            self.check_tls_pinning(dst.uri(), io.ssl())?;
 
             return Ok(Conn {
                 inner: self.verbose.wrap(BoringTlsConn {
@@ -258,8 +399,43 @@ impl ConnectorService {
             });
         }
 
-        socks::connect(proxy, &dst, dns).await.map(|tcp| Conn {
-            inner: self.verbose.wrap(TokioIo::new(tcp)),
+        let mut conn = socks::connect(proxy, &dst, dns).await?;
+        self.tunnel_proxy_protocol(&dst, &mut conn).await?;
+
+        Ok(Conn {
+            inner: self.verbose.wrap(TokioIo::new(conn)),
+            is_proxy: false,
+            tls_info: false,
+        })
+    }
+
+    async fn connect_with_transport(
+        self,
+        mut dst: Dst,
+        transport: Arc<dyn transport::Connector>,
+    ) -> Result<Conn, BoxError> {
+        log::trace!("connect with custom transport");
+        let mut stream = Transport(transport.connect(dst.uri()).await?);
+        self.tunnel_proxy_protocol(&dst, &mut stream).await?;
+
+        if dst.scheme() == Some(&Scheme::HTTPS) {
+            let http = HttpsConnector::new(self.http.clone(), self.tls.clone(), &mut dst);
+            let host = dst.host().ok_or(crate::error::uri_bad_host())?;
+
+            let io = http.connect(&dst, host, TokioIo::new(stream)).await?;
+            self.check_tls_pinning(dst.uri(), io.ssl())?;
+
+            return Ok(Conn {
+                inner: self.verbose.wrap(BoringTlsConn {
+                    inner: TokioIo::new(io),
+                }),
+                is_proxy: false,
+                tls_info: self.tls_info,
+            });
+        }
+
+        Ok(Conn {
+            inner: self.verbose.wrap(TokioIo::new(stream)),
             is_proxy: false,
             tls_info: false,
         })
@@ -279,11 +455,35 @@ impl ConnectorService {
             http.set_nodelay(true);
         }
 
+        // Announce the client to load balancers in front of the destination
+        if !is_proxy {
+            if let Some(version) = dst.host().and_then(|host| self.proxy_protocol.find(host)) {
+                http.set_proxy_protocol(Some(version));
+            }
+        }
+
         log::trace!("connect with maybe proxy");
-        let mut http = HttpsConnector::new(http, self.tls, &mut dst);
-        let io = http.call(dst.into()).await?;
+        let alternative = dst.alternative().filter(|_| !is_proxy).cloned();
+        let mut http = HttpsConnector::new(http, self.tls.clone(), &mut dst);
+        if is_proxy {
+            http = http.proxy_connector(self.proxy_tls(), AlpnProtos::HTTP1);
+        }
+        let uri = dst.uri().clone();
+        let io = match alternative {
+            Some(authority) => match http.call_via(uri.clone(), authority).await {
+                Ok(io) => io,
+                Err(err) => {
+                    // the origin itself is always an option
+                    log::debug!("alternative service of {:?} failed: {}", uri, err);
+                    http.call(dst.into()).await?
+                }
+            },
+            None => http.call(dst.into()).await?,
+        };
 
         if let MaybeHttpsStream::Https(stream) = io {
+            self.check_tls_pinning(&uri, stream.inner().ssl())?;
+
             if !self.nodelay {
                 stream
                     .inner()
@@ -306,6 +506,33 @@ impl ConnectorService {
         }
     }
 
+    /// Sets up TLS to `dst` over a tunnel through a proxy.
+    async fn connect_tunneled<T>(
+        &self,
+        dst: &Dst,
+        http: &HttpsConnector<HttpConnector>,
+        host: &str,
+        mut tunneled: T,
+    ) -> Result<Conn, BoxError>
+    where
+        T: Read + Write + Unpin + Send + Sync + std::fmt::Debug + 'static,
+        BoringTlsConn<TokioIo<T>>: AsyncConnWithInfo,
+    {
+        self.tunnel_proxy_protocol(dst, &mut TokioIo::new(&mut tunneled))
+            .await?;
+
+        let io = http.connect(dst, host, tunneled).await?;
+        self.check_tls_pinning(dst.uri(), io.ssl())?;
+
+        Ok(Conn {
+            inner: self.verbose.wrap(BoringTlsConn {
+                inner: TokioIo::new(io),
+            }),
+            is_proxy: false,
+            tls_info: self.tls_info,
+        })
+    }
+
     async fn connect_via_proxy(
         self,
         mut dst: Dst,
@@ -313,9 +540,10 @@ impl ConnectorService {
     ) -> Result<Conn, BoxError> {
         log::debug!("proxy({:?}) intercepts '{:?}'", proxy_scheme, dst.uri());
 
+        let headers = proxy_scheme.connect_headers().cloned();
         let (proxy_dst, auth) = match proxy_scheme {
-            ProxyScheme::Http { host, auth } => (into_uri(Scheme::HTTP, host)?, auth),
-            ProxyScheme::Https { host, auth } => (into_uri(Scheme::HTTPS, host)?, auth),
+            ProxyScheme::Http { host, auth, .. } => (into_uri(Scheme::HTTP, host)?, auth),
+            ProxyScheme::Https { host, auth, .. } => (into_uri(Scheme::HTTPS, host)?, auth),
             #[cfg(feature = "socks")]
             ProxyScheme::Socks4 { .. } | ProxyScheme::Socks5 { .. } => {
                 return self.connect_socks(dst, proxy_scheme).await;
@@ -323,23 +551,61 @@ impl ConnectorService {
         };
 
         if dst.scheme() == Some(&Scheme::HTTPS) {
-            let mut http = HttpsConnector::new(self.http.clone(), self.tls, &mut dst);
+            let http = HttpsConnector::new(self.http.clone(), self.tls.clone(), &mut dst);
 
             let host = dst.host().ok_or(crate::error::uri_bad_host())?;
             let port = dst.port_u16().unwrap_or(443);
 
+            if let Some(ref h2_tunnels) = self.h2_tunnels {
+                if proxy_dst.scheme() == Some(&Scheme::HTTPS) {
+                    let connect = async {
+                        let mut proxy_http =
+                            http.proxy_connector(self.proxy_tls(), AlpnProtos::ALL);
+                        let conn = proxy_http.call(proxy_dst.clone()).await?;
+                        let h2 = h2_tunnel::negotiated_h2(&conn);
+                        Ok::<_, BoxError>((conn, h2))
+                    };
+                    let mut sender = match h2_tunnels.get_or_connect(&proxy_dst, connect).await? {
+                        ProxyConnection::Http2(sender) => sender,
+                        ProxyConnection::Http1(conn) => {
+                            log::trace!("tunneling HTTPS over HTTP/1.1 proxy");
+                            let tunneled =
+                                tunnel::connect(conn, host, port, auth, headers.as_ref()).await?;
+                            return self.connect_tunneled(&dst, &http, host, tunneled).await;
+                        }
+                    };
+
+                    log::trace!("tunneling HTTPS over HTTP/2 proxy");
+                    let tunneled =
+                        h2_tunnel::connect(&mut sender, host, port, auth, headers.as_ref()).await?;
+                    return self.connect_tunneled(&dst, &http, host, tunneled).await;
+                }
+            }
+
             log::trace!("tunneling HTTPS over proxy");
-            let conn = http.call(proxy_dst).await?;
-            let tunneled = tunnel::connect(conn, host, port, auth).await?;
+            let mut proxy_http = http.proxy_connector(self.proxy_tls(), AlpnProtos::HTTP1);
+            let conn = proxy_http.call(proxy_dst).await?;
+            let tunneled = tunnel::connect(conn, host, port, auth, headers.as_ref()).await?;
+            return self.connect_tunneled(&dst, &http, host, tunneled).await;
+        }
 
-            let io = http.connect(&dst, host, tunneled).await?;
+        if dst.is_tunnel() {
+            let http = HttpsConnector::new(self.http.clone(), self.tls.clone(), &mut dst);
+
+            let host = dst.host().ok_or(crate::error::uri_bad_host())?;
+            let port = dst.port_u16().unwrap_or(80);
+
+            log::trace!("tunneling over proxy");
+            let mut proxy_http = http.proxy_connector(self.proxy_tls(), AlpnProtos::HTTP1);
+            let conn = proxy_http.call(proxy_dst).await?;
+            let mut tunneled = tunnel::connect(conn, host, port, auth, headers.as_ref()).await?;
+            self.tunnel_proxy_protocol(&dst, &mut TokioIo::new(&mut tunneled))
+                .await?;
 
             return Ok(Conn {
-                inner: self.verbose.wrap(BoringTlsConn {
-                    inner: TokioIo::new(io),
-                }),
+                inner: self.verbose.wrap(tunneled),
                 is_proxy: false,
-                tls_info: self.tls_info,
+                tls_info: false,
             });
         }
 
@@ -376,22 +642,42 @@ impl Service<Dst> for ConnectorService {
     fn call(&mut self, mut dst: Dst) -> Self::Future {
         log::debug!("starting new connection: {:?}", dst.uri());
 
+        if let Some(transport) = self.transport.clone() {
+            return self.connecting(self.clone().connect_with_transport(dst, transport));
+        }
+
         if let Some(proxy_scheme) = dst.take_proxy_scheme() {
-            return Box::pin(with_timeout(
-                self.clone().connect_via_proxy(dst, proxy_scheme),
-                self.timeout,
-            ));
+            return self.connecting(self.clone().connect_via_proxy(dst, proxy_scheme));
+        }
+
+        self.connecting(self.clone().connect_with_maybe_proxy(dst, false))
+    }
+}
+
+impl ConnectorService {
+    fn connecting<F>(&self, connect: F) -> Connecting
+    where
+        F: Future<Output = Result<Conn, BoxError>> + Send + 'static,
+    {
+        if !self.debug_capture {
+            return Box::pin(with_timeout(connect, self.timeout));
         }
 
-        Box::pin(with_timeout(
-            self.clone().connect_with_maybe_proxy(dst, false),
-            self.timeout,
-        ))
+        let timeout = self.timeout;
+        Box::pin(async move {
+            let conn = with_timeout(connect, timeout).await?;
+            Ok(record::wrap(conn))
+        })
     }
 }
 
 trait TlsInfoFactory {
     fn tls_info(&self) -> Option<crate::tls::TlsInfo>;
+
+    /// The ClientHello that opened the connection, if it was captured.
+    fn client_hello(&self) -> Option<Bytes> {
+        None
+    }
 }
 
 impl TlsInfoFactory for tokio::net::TcpStream {
@@ -400,10 +686,20 @@ impl TlsInfoFactory for tokio::net::TcpStream {
     }
 }
 
+impl TlsInfoFactory for Transport {
+    fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
+        None
+    }
+}
+
 impl<T: TlsInfoFactory> TlsInfoFactory for TokioIo<T> {
     fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
         self.inner().tls_info()
     }
+
+    fn client_hello(&self) -> Option<Bytes> {
+        self.inner().client_hello()
+    }
 }
 
 impl TlsInfoFactory for SslStream<TokioIo<TokioIo<tokio::net::TcpStream>>> {
@@ -415,12 +711,50 @@ impl TlsInfoFactory for SslStream<TokioIo<TokioIo<tokio::net::TcpStream>>> {
                 peer_certificate: Some(c),
             })
     }
+
+    fn client_hello(&self) -> Option<Bytes> {
+        crate::tls::client_hello(self.ssl())
+    }
+}
+
+impl TlsInfoFactory for SslStream<TokioIo<h2_tunnel::H2Stream>> {
+    fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
+        self.ssl()
+            .peer_certificate()
+            .and_then(|c| c.to_der().ok())
+            .map(|c| crate::tls::TlsInfo {
+                peer_certificate: Some(c),
+            })
+    }
+
+    fn client_hello(&self) -> Option<Bytes> {
+        crate::tls::client_hello(self.ssl())
+    }
+}
+
+impl TlsInfoFactory for SslStream<TokioIo<TokioIo<Transport>>> {
+    fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
+        self.ssl()
+            .peer_certificate()
+            .and_then(|c| c.to_der().ok())
+            .map(|c| crate::tls::TlsInfo {
+                peer_certificate: Some(c),
+            })
+    }
+
+    fn client_hello(&self) -> Option<Bytes> {
+        crate::tls::client_hello(self.ssl())
+    }
 }
 
 impl TlsInfoFactory for SslStream<TokioIo<MaybeHttpsStream<TokioIo<tokio::net::TcpStream>>>> {
     fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
         self.get_ref().inner().tls_info()
     }
+
+    fn client_hello(&self) -> Option<Bytes> {
+        crate::tls::client_hello(self.ssl())
+    }
 }
 
 impl TlsInfoFactory for MaybeHttpsStream<TokioIo<tokio::net::TcpStream>> {
@@ -531,9 +865,11 @@ pub(crate) mod sealed {
 pub(crate) type Connecting = Pin<Box<dyn Future<Output = Result<Conn, BoxError>> + Send>>;
 
 mod tls_conn {
+    use super::h2_tunnel::H2Stream;
     use super::TlsInfoFactory;
     use crate::{
         tls::MaybeHttpsStream,
+        transport::Transport,
         util::{
             client::connect::{Connected, Connection},
             rt::TokioIo,
@@ -580,6 +916,28 @@ mod tls_conn {
         }
     }
 
+    impl Connection for BoringTlsConn<TokioIo<H2Stream>> {
+        fn connected(&self) -> Connected {
+            let connected = self.inner.inner().get_ref().connected();
+            if self.inner.inner().ssl().selected_alpn_protocol() == Some(b"h2") {
+                connected.negotiated_h2()
+            } else {
+                connected
+            }
+        }
+    }
+
+    impl Connection for BoringTlsConn<TokioIo<TokioIo<Transport>>> {
+        fn connected(&self) -> Connected {
+            let connected = self.inner.inner().get_ref().connected();
+            if self.inner.inner().ssl().selected_alpn_protocol() == Some(b"h2") {
+                connected.negotiated_h2()
+            } else {
+                connected
+            }
+        }
+    }
+
     impl<T: AsyncRead + AsyncWrite + Unpin> Read for BoringTlsConn<T> {
         fn poll_read(
             self: Pin<&mut Self>,
@@ -638,13 +996,18 @@ mod tls_conn {
         fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
             self.inner.tls_info()
         }
+
+        fn client_hello(&self) -> Option<bytes::Bytes> {
+            self.inner.client_hello()
+        }
     }
 }
 
 mod tunnel {
     use super::BoxError;
     use crate::util::rt::TokioIo;
-    use http::HeaderValue;
+    use http::header::{HOST, PROXY_AUTHORIZATION, USER_AGENT};
+    use http::{HeaderMap, HeaderValue};
     use hyper2::rt::{Read, Write};
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
@@ -653,6 +1016,7 @@ mod tunnel {
         host: &str,
         port: u16,
         auth: Option<HeaderValue>,
+        headers: Option<&HeaderMap>,
     ) -> Result<T, BoxError>
     where
         T: Read + Write + Unpin,
@@ -667,11 +1031,24 @@ mod tunnel {
         .into_bytes();
 
         // user-agent
-        buf.extend_from_slice(b"User-Agent: ");
-        buf.extend_from_slice(env!("CARGO_PKG_NAME").as_bytes());
-        buf.extend_from_slice(b"/");
-        buf.extend_from_slice(env!("CARGO_PKG_VERSION").as_bytes());
-        buf.extend_from_slice(b"\r\n");
+        if !headers.is_some_and(|headers| headers.contains_key(USER_AGENT)) {
+            buf.extend_from_slice(b"User-Agent: ");
+            buf.extend_from_slice(env!("CARGO_PKG_NAME").as_bytes());
+            buf.extend_from_slice(b"/");
+            buf.extend_from_slice(env!("CARGO_PKG_VERSION").as_bytes());
+            buf.extend_from_slice(b"\r\n");
+        }
+
+        // custom headers
+        for (name, value) in headers.into_iter().flatten() {
+            if name == HOST || name == PROXY_AUTHORIZATION {
+                continue;
+            }
+            buf.extend_from_slice(name.as_str().as_bytes());
+            buf.extend_from_slice(b": ");
+            buf.extend_from_slice(value.as_bytes());
+            buf.extend_from_slice(b"\r\n");
+        }
 
         // proxy-authorization
         if let Some(value) = auth {
@@ -709,7 +1086,7 @@ mod tunnel {
                 }
             // else read more
             } else if recvd.starts_with(b"HTTP/1.1 407") {
-                return Err("proxy authentication required".into());
+                return Err(crate::error::ProxyAuthRequired.into());
             } else {
                 return Err("unsuccessful tunnel".into());
             }
@@ -717,6 +1094,342 @@ mod tunnel {
     }
 }
 
+mod h2_tunnel {
+    use std::collections::{HashMap, HashSet};
+    use std::fmt;
+    use std::future::Future;
+    use std::io::{self, IoSlice};
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+
+    use antidote::Mutex;
+    use bytes::Bytes;
+    use http::header::{HOST, PROXY_AUTHORIZATION, USER_AGENT};
+    use http::{HeaderMap, HeaderValue, Method, Request, StatusCode, Uri};
+    use http_body_util::Empty;
+    use hyper2::client::conn::http2::{Builder, SendRequest};
+    use hyper2::rt::{Read, ReadBufCursor, Write};
+    use sync_wrapper::SyncWrapper;
+    use tokio::net::TcpStream;
+
+    use super::BoxError;
+    use crate::tls::MaybeHttpsStream;
+    use crate::util::client::connect::{Connected, Connection};
+    use crate::util::rt::{TokioExecutor, TokioIo};
+
+    type ProxyConn = MaybeHttpsStream<TokioIo<TcpStream>>;
+
+    /// The HTTP/2 connections to proxies, each shared by all the tunnels
+    /// through its proxy.
+    #[derive(Clone, Default)]
+    pub(crate) struct H2Tunnels {
+        senders: Arc<Mutex<HashMap<Uri, SendRequest<Empty<Bytes>>>>>,
+        // A lock per proxy held while connecting to it, so the tunnels
+        // opened meanwhile wait to share the connection rather than open
+        // their own, as the pool does for HTTP/2 connections.
+        connecting: Arc<Mutex<HashMap<Uri, Arc<tokio::sync::Mutex<()>>>>>,
+        // The proxies which answered without HTTP/2, connected to without
+        // waiting on each other.
+        http1: Arc<Mutex<HashSet<Uri>>>,
+    }
+
+    /// A connection to a proxy, to open tunnels on.
+    pub(crate) enum ProxyConnection<T> {
+        /// A connection shared by the tunnels through the proxy.
+        Http2(SendRequest<Empty<Bytes>>),
+        /// A connection of its own for a single tunnel.
+        Http1(T),
+    }
+
+    impl H2Tunnels {
+        /// Get the open HTTP/2 connection to `proxy`, or open one with
+        /// `connect`, which also tells whether the proxy negotiated HTTP/2.
+        ///
+        /// A single connection to each proxy is opened at a time.
+        pub(super) async fn get_or_connect<T, F>(
+            &self,
+            proxy: &Uri,
+            connect: F,
+        ) -> Result<ProxyConnection<T>, BoxError>
+        where
+            T: Read + Write + Unpin + Send + 'static,
+            F: Future<Output = Result<(T, bool), BoxError>>,
+        {
+            if let Some(sender) = self.get(proxy) {
+                return Ok(ProxyConnection::Http2(sender));
+            }
+
+            let http1 = self.http1.lock().contains(proxy);
+            let lock = (!http1).then(|| {
+                self.connecting
+                    .lock()
+                    .entry(proxy.clone())
+                    .or_default()
+                    .clone()
+            });
+            let _connecting = match lock {
+                Some(ref lock) => Some(lock.lock().await),
+                None => None,
+            };
+            // connected while waiting
+            if let Some(sender) = self.get(proxy) {
+                return Ok(ProxyConnection::Http2(sender));
+            }
+
+            let (conn, h2) = connect.await?;
+            if !h2 {
+                self.http1.lock().insert(proxy.clone());
+                return Ok(ProxyConnection::Http1(conn));
+            }
+            self.http1.lock().remove(proxy);
+            self.handshake(proxy, conn)
+                .await
+                .map(ProxyConnection::Http2)
+        }
+
+        /// Get the open connection to `proxy`, if any.
+        fn get(&self, proxy: &Uri) -> Option<SendRequest<Empty<Bytes>>> {
+            let mut senders = self.senders.lock();
+            match senders.get(proxy) {
+                Some(sender) if !sender.is_closed() => Some(sender.clone()),
+                Some(_) => {
+                    senders.remove(proxy);
+                    None
+                }
+                None => None,
+            }
+        }
+
+        /// Starts HTTP/2 over a new connection to `proxy`, and keeps it for
+        /// the next tunnels.
+        async fn handshake<T>(
+            &self,
+            proxy: &Uri,
+            conn: T,
+        ) -> Result<SendRequest<Empty<Bytes>>, BoxError>
+        where
+            T: Read + Write + Unpin + Send + 'static,
+        {
+            let (sender, connection) = Builder::new(TokioExecutor::new()).handshake(conn).await?;
+            tokio::spawn(async move {
+                if let Err(err) = connection.await {
+                    log::debug!("proxy connection error: {}", err);
+                }
+            });
+
+            self.senders.lock().insert(proxy.clone(), sender.clone());
+            Ok(sender)
+        }
+    }
+
+    pub(super) fn negotiated_h2(conn: &ProxyConn) -> bool {
+        match conn {
+            MaybeHttpsStream::Https(tls) => {
+                tls.inner().ssl().selected_alpn_protocol() == Some(b"h2")
+            }
+            MaybeHttpsStream::Http(_) => false,
+        }
+    }
+
+    /// Opens a tunnel to `host:port` as a `CONNECT` stream.
+    pub(super) async fn connect(
+        sender: &mut SendRequest<Empty<Bytes>>,
+        host: &str,
+        port: u16,
+        auth: Option<HeaderValue>,
+        headers: Option<&HeaderMap>,
+    ) -> Result<H2Stream, BoxError> {
+        let mut req = Request::builder()
+            .method(Method::CONNECT)
+            .uri(format!("{}:{}", host, port))
+            .header(
+                USER_AGENT,
+                concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")),
+            );
+
+        // custom headers
+        if let Some(req_headers) = req.headers_mut() {
+            for (name, value) in headers.into_iter().flatten() {
+                if name != HOST && name != PROXY_AUTHORIZATION {
+                    req_headers.append(name, value.clone());
+                }
+            }
+        }
+
+        // proxy-authorization
+        if let Some(value) = auth {
+            log::debug!("tunnel to {}:{} using basic auth", host, port);
+            req = req.header(PROXY_AUTHORIZATION, value);
+        }
+
+        sender.ready().await?;
+        let res = sender.send_request(req.body(Empty::new())?).await?;
+
+        match res.status() {
+            StatusCode::OK => Ok(H2Stream {
+                inner: SyncWrapper::new(hyper2::upgrade::on(res).await?),
+            }),
+            StatusCode::PROXY_AUTHENTICATION_REQUIRED => {
+                Err(crate::error::ProxyAuthRequired.into())
+            }
+            _ => Err("unsuccessful tunnel".into()),
+        }
+    }
+
+    /// A tunnel through an HTTP/2 connection to a proxy.
+    pub(crate) struct H2Stream {
+        inner: SyncWrapper<hyper2::upgrade::Upgraded>,
+    }
+
+    impl Connection for H2Stream {
+        fn connected(&self) -> Connected {
+            Connected::new()
+        }
+    }
+
+    impl Read for H2Stream {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: ReadBufCursor<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(self.get_mut().inner.get_mut()).poll_read(cx, buf)
+        }
+    }
+
+    impl Write for H2Stream {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(self.get_mut().inner.get_mut()).poll_write(cx, buf)
+        }
+
+        fn poll_write_vectored(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(self.get_mut().inner.get_mut()).poll_write_vectored(cx, bufs)
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(self.get_mut().inner.get_mut()).poll_flush(cx)
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(self.get_mut().inner.get_mut()).poll_shutdown(cx)
+        }
+    }
+
+    impl fmt::Debug for H2Stream {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("H2Stream").finish()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::convert::Infallible;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use http_body_util::Empty;
+        use hyper::service::service_fn;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+        use super::*;
+
+        /// An HTTP/2 proxy on one end of a pipe, answering `CONNECT` with
+        /// `status` and echoing the tunnels it accepts.
+        fn proxy(status: StatusCode) -> TokioIo<DuplexStream> {
+            let (client, server) = tokio::io::duplex(64 * 1024);
+            let service = service_fn(
+                move |req: hyper::Request<hyper::body::Incoming>| async move {
+                    assert_eq!(req.method(), Method::CONNECT);
+                    if status == StatusCode::OK {
+                        tokio::spawn(async move {
+                            let upgraded = hyper::upgrade::on(req).await.unwrap();
+                            let mut upgraded = hyper_util::rt::TokioIo::new(upgraded);
+                            let mut buf = [0; 4];
+                            upgraded.read_exact(&mut buf).await.unwrap();
+                            upgraded.write_all(&buf).await.unwrap();
+                        });
+                    }
+                    let mut res = hyper::Response::new(Empty::<Bytes>::new());
+                    *res.status_mut() = status;
+                    Ok::<_, Infallible>(res)
+                },
+            );
+            tokio::spawn(
+                hyper::server::conn::http2::Builder::new(hyper_util::rt::TokioExecutor::new())
+                    .serve_connection(hyper_util::rt::TokioIo::new(server), service),
+            );
+            TokioIo::new(client)
+        }
+
+        #[tokio::test]
+        async fn tunnels_share_one_connection() {
+            let tunnels = H2Tunnels::default();
+            let uri = Uri::from_static("https://proxy.test:443");
+            let connects = AtomicUsize::new(0);
+
+            let (tunnels, uri, connects) = (&tunnels, &uri, &connects);
+            let open = move || async move {
+                let dial = async {
+                    connects.fetch_add(1, Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                    Ok((proxy(StatusCode::OK), true))
+                };
+                let mut sender = match tunnels.get_or_connect(uri, dial).await.unwrap() {
+                    ProxyConnection::Http2(sender) => sender,
+                    ProxyConnection::Http1(_) => panic!("proxy speaks HTTP/2"),
+                };
+                let tunnel = connect(&mut sender, "example.com", 443, None, None)
+                    .await
+                    .unwrap();
+                let mut tunnel = TokioIo::new(tunnel);
+                tunnel.write_all(b"ping").await.unwrap();
+                let mut buf = [0; 4];
+                tunnel.read_exact(&mut buf).await.unwrap();
+                assert_eq!(&buf, b"ping");
+            };
+
+            futures_util::future::join3(open(), open(), open()).await;
+            assert_eq!(connects.load(Ordering::SeqCst), 1);
+        }
+
+        #[tokio::test]
+        async fn proxy_without_http2() {
+            let tunnels = H2Tunnels::default();
+            let uri = Uri::from_static("https://proxy.test:443");
+            for _ in 0..2 {
+                let dial = async { Ok((proxy(StatusCode::OK), false)) };
+                let conn = tunnels.get_or_connect(&uri, dial).await.unwrap();
+                assert!(matches!(conn, ProxyConnection::Http1(_)));
+            }
+            assert!(tunnels.http1.lock().contains(&uri));
+        }
+
+        #[tokio::test]
+        async fn proxy_auth_required() {
+            let tunnels = H2Tunnels::default();
+            let uri = Uri::from_static("https://proxy.test:443");
+            let dial = async { Ok((proxy(StatusCode::PROXY_AUTHENTICATION_REQUIRED), true)) };
+            let ProxyConnection::Http2(mut sender) =
+                tunnels.get_or_connect(&uri, dial).await.unwrap()
+            else {
+                panic!("proxy speaks HTTP/2");
+            };
+            let err = connect(&mut sender, "example.com", 443, None, None)
+                .await
+                .unwrap_err();
+            assert!(err.is::<crate::error::ProxyAuthRequired>());
+        }
+    }
+}
+
 #[cfg(feature = "socks")]
 mod socks {
     use std::io;
@@ -757,10 +1470,18 @@ mod socks {
         }
 
         match proxy {
-            ProxyScheme::Socks4 { addr, .. } => {
-                let stream = Socks4Stream::connect(addr, (host.as_str(), port))
-                    .await
-                    .map_err(|e| format!("socks connect error: {e}"))?;
+            ProxyScheme::Socks4 {
+                addr, ref user_id, ..
+            } => {
+                let stream = if let Some(user_id) = user_id {
+                    Socks4Stream::connect_with_userid(addr, (host.as_str(), port), user_id)
+                        .await
+                        .map_err(|e| format!("socks connect error: {e}"))?
+                } else {
+                    Socks4Stream::connect(addr, (host.as_str(), port))
+                        .await
+                        .map_err(|e| format!("socks connect error: {e}"))?
+                };
                 Ok(stream.into_inner())
             }
             ProxyScheme::Socks5 { addr, ref auth, .. } => {
@@ -786,6 +1507,103 @@ mod socks {
     }
 }
 
+mod record {
+    use super::{BoxConn, Conn, TlsInfoFactory};
+    use crate::util::client::connect::{Connected, Connection};
+    use crate::util::client::Recording;
+    use bytes::Bytes;
+    use hyper2::rt::{Read, ReadBufCursor, Write};
+    use std::io::{self, IoSlice};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    /// Record what `conn` writes, for debug captures.
+    pub(super) fn wrap(conn: Conn) -> Conn {
+        let recording = Recording::new(conn.inner.client_hello());
+        Conn {
+            inner: Box::new(Recorder {
+                inner: conn.inner,
+                recording,
+            }),
+            ..conn
+        }
+    }
+
+    struct Recorder {
+        inner: BoxConn,
+        recording: Recording,
+    }
+
+    impl Connection for Recorder {
+        fn connected(&self) -> Connected {
+            self.inner.connected().extra(self.recording.clone())
+        }
+    }
+
+    impl Read for Recorder {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: ReadBufCursor<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl Write for Recorder {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let n = futures_util::ready!(Pin::new(&mut self.inner).poll_write(cx, buf))?;
+            self.recording.write(&buf[..n]);
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<io::Result<usize>> {
+            let mut left =
+                futures_util::ready!(Pin::new(&mut self.inner).poll_write_vectored(cx, bufs))?;
+            let n = left;
+            for buf in bufs {
+                if left == 0 {
+                    break;
+                }
+                let len = left.min(buf.len());
+                self.recording.write(&buf[..len]);
+                left -= len;
+            }
+            Poll::Ready(Ok(n))
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.inner.is_write_vectored()
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+    }
+
+    impl TlsInfoFactory for Recorder {
+        fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
+            self.inner.tls_info()
+        }
+
+        fn client_hello(&self) -> Option<Bytes> {
+            self.inner.client_hello()
+        }
+    }
+}
+
 mod verbose {
     use crate::util::client::connect::{Connected, Connection};
     use hyper2::rt::{Read, ReadBufCursor, Write};
@@ -910,6 +1728,10 @@ mod verbose {
         fn tls_info(&self) -> Option<crate::tls::TlsInfo> {
             self.inner.tls_info()
         }
+
+        fn client_hello(&self) -> Option<bytes::Bytes> {
+            self.inner.client_hello()
+        }
     }
 
     struct Escape<'a>(&'a [u8]);
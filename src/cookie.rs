@@ -7,6 +7,7 @@ use std::time::SystemTime;
 
 use crate::header::{HeaderValue, SET_COOKIE};
 use bytes::Bytes;
+use cookie_store::{CookieDomain, CookieExpiration};
 
 /// Actions for a persistent cookie store providing session support.
 pub trait CookieStore: Send + Sync {
@@ -16,6 +17,14 @@ pub trait CookieStore: Send + Sync {
     fn cookies(&self, url: &url::Url) -> Option<HeaderValue>;
     /// Remove all cookies from the store.
     fn clear(&self) {}
+    /// The cookies of the store, as the URLs that set them and `Set-Cookie`
+    /// values setting them again, to save them with
+    /// [`Client::export_session`](crate::Client::export_session).
+    ///
+    /// The default saves none.
+    fn export_cookies(&self) -> Vec<(url::Url, String)> {
+        Vec::new()
+    }
 }
 
 /// A single HTTP cookie.
@@ -190,6 +199,35 @@ impl CookieStore for Jar {
     fn clear(&self) {
         self.0.write().clear();
     }
+
+    fn export_cookies(&self) -> Vec<(url::Url, String)> {
+        self.0
+            .read()
+            .iter_unexpired()
+            .filter_map(|cookie| {
+                let host = match cookie.domain {
+                    CookieDomain::HostOnly(ref host) | CookieDomain::Suffix(ref host) => host,
+                    CookieDomain::NotPresent | CookieDomain::Empty => return None,
+                };
+                let scheme = if cookie.secure() == Some(true) {
+                    "https"
+                } else {
+                    "http"
+                };
+                let url = format!("{scheme}://{host}{}", cookie.path.as_ref());
+                let url = url::Url::parse(&url).ok()?;
+
+                // a relative `Max-Age` would start over once set again
+                let mut raw = cookie_crate::Cookie::clone(cookie);
+                raw.set_max_age(None);
+                match cookie.expires {
+                    CookieExpiration::AtUtc(at) => raw.set_expires(at),
+                    CookieExpiration::SessionEnd => raw.unset_expires(),
+                }
+                Some((url, raw.to_string()))
+            })
+            .collect()
+    }
 }
 
 impl Default for Jar {
@@ -0,0 +1,304 @@
+//! Caching of DNS resolutions.
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use antidote::Mutex;
+use lru::LruCache;
+
+use super::{Addrs, Name, Resolve, Resolving, ResolvingWithTtl};
+use crate::error::BoxError;
+
+/// Settings of the DNS cache of a `Client`.
+///
+/// Resolutions are kept for the TTL reported by the resolver, clamped to
+/// `min_ttl..=max_ttl`, or for `default_ttl` when the resolver reports none,
+/// as with the system's `getaddrinfo`. Failed resolutions are kept for
+/// `negative_ttl`.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use rquest::dns::DnsCacheConfig;
+///
+/// let client = rquest::Client::builder()
+///     .dns_cache(DnsCacheConfig::new().max_ttl(Duration::from_secs(60)))
+///     .build()
+///     .unwrap();
+///
+/// // Forget every cached resolution, e.g. after a network change
+/// client.flush_dns_cache();
+/// ```
+#[derive(Debug, Clone)]
+pub struct DnsCacheConfig {
+    min_ttl: Duration,
+    max_ttl: Duration,
+    default_ttl: Duration,
+    negative_ttl: Duration,
+    max_entries: NonZeroUsize,
+}
+
+impl Default for DnsCacheConfig {
+    fn default() -> DnsCacheConfig {
+        DnsCacheConfig {
+            min_ttl: Duration::from_secs(1),
+            max_ttl: Duration::from_secs(300),
+            default_ttl: Duration::from_secs(60),
+            negative_ttl: Duration::from_secs(5),
+            max_entries: NonZeroUsize::new(1024).expect("non-zero"),
+        }
+    }
+}
+
+impl DnsCacheConfig {
+    /// Creates the default settings: TTLs clamped to 1 second..5 minutes,
+    /// 60 seconds when the resolver reports none, failures kept for 5
+    /// seconds, and at most 1024 hosts.
+    pub fn new() -> DnsCacheConfig {
+        DnsCacheConfig::default()
+    }
+
+    /// Sets the shortest time a resolution is kept, even if its TTL is lower.
+    pub fn min_ttl(mut self, ttl: Duration) -> DnsCacheConfig {
+        self.min_ttl = ttl;
+        self
+    }
+
+    /// Sets the longest time a resolution is kept, even if its TTL is higher.
+    pub fn max_ttl(mut self, ttl: Duration) -> DnsCacheConfig {
+        self.max_ttl = ttl;
+        self
+    }
+
+    /// Sets how long to keep resolutions whose TTL is unknown.
+    pub fn default_ttl(mut self, ttl: Duration) -> DnsCacheConfig {
+        self.default_ttl = ttl;
+        self
+    }
+
+    /// Sets how long to keep failed resolutions. `Duration::ZERO` disables
+    /// negative caching.
+    pub fn negative_ttl(mut self, ttl: Duration) -> DnsCacheConfig {
+        self.negative_ttl = ttl;
+        self
+    }
+
+    /// Sets the number of hosts to keep; the least recently used one is
+    /// evicted to make room for another.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_entries` is zero.
+    pub fn max_entries(mut self, max_entries: usize) -> DnsCacheConfig {
+        self.max_entries = NonZeroUsize::new(max_entries).expect("max_entries must be non-zero");
+        self
+    }
+
+    fn clamp(&self, ttl: Option<Duration>) -> Duration {
+        ttl.unwrap_or(self.default_ttl)
+            .max(self.min_ttl)
+            .min(self.max_ttl)
+    }
+}
+
+enum Entry {
+    Resolved(Vec<SocketAddr>),
+    Failed(Arc<str>),
+}
+
+/// The resolutions cached by a client, shared with its [`CachingResolver`].
+pub(crate) struct DnsCache {
+    config: DnsCacheConfig,
+    entries: Mutex<LruCache<String, (Entry, Instant)>>,
+}
+
+impl DnsCache {
+    pub(crate) fn new(config: DnsCacheConfig) -> DnsCache {
+        DnsCache {
+            entries: Mutex::new(LruCache::new(config.max_entries)),
+            config,
+        }
+    }
+
+    /// Forgets every cached resolution.
+    pub(crate) fn flush(&self) {
+        self.entries.lock().clear();
+    }
+
+    /// Forgets the cached resolution of `host`.
+    pub(crate) fn evict(&self, host: &str) {
+        self.entries.lock().pop(&host.to_ascii_lowercase());
+    }
+
+    fn get(&self, host: &str) -> Option<Result<(Vec<SocketAddr>, Duration), Arc<str>>> {
+        let mut entries = self.entries.lock();
+        let now = Instant::now();
+        match entries.get(host) {
+            Some((Entry::Resolved(addrs), expires)) if *expires > now => {
+                Some(Ok((addrs.clone(), *expires - now)))
+            }
+            Some((Entry::Failed(err), expires)) if *expires > now => Some(Err(err.clone())),
+            Some(_) => {
+                entries.pop(host);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&self, host: String, entry: Entry, ttl: Duration) {
+        if ttl.is_zero() {
+            return;
+        }
+        self.entries.lock().put(host, (entry, Instant::now() + ttl));
+    }
+}
+
+/// A resolver that answers from a [`DnsCache`] before asking another.
+pub(crate) struct CachingResolver {
+    resolver: Arc<dyn Resolve>,
+    cache: Arc<DnsCache>,
+}
+
+impl CachingResolver {
+    pub(crate) fn new(resolver: Arc<dyn Resolve>, cache: Arc<DnsCache>) -> CachingResolver {
+        CachingResolver { resolver, cache }
+    }
+}
+
+impl Resolve for CachingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolving = self.resolve_with_ttl(name);
+        Box::pin(async move { Ok(resolving.await?.0) })
+    }
+
+    fn resolve_with_ttl(&self, name: Name) -> ResolvingWithTtl {
+        let host = name.as_str().to_ascii_lowercase();
+        match self.cache.get(&host) {
+            Some(Ok((addrs, ttl))) => {
+                let addrs: Addrs = Box::new(addrs.into_iter());
+                return Box::pin(futures_util::future::ok::<_, BoxError>((addrs, Some(ttl))));
+            }
+            Some(Err(err)) => {
+                let err: BoxError = err.as_ref().into();
+                return Box::pin(futures_util::future::err(err));
+            }
+            None => (),
+        }
+
+        let resolving = self.resolver.resolve_with_ttl(name);
+        let cache = self.cache.clone();
+        Box::pin(async move {
+            match resolving.await {
+                Ok((addrs, ttl)) => {
+                    let addrs: Vec<SocketAddr> = addrs.collect();
+                    let ttl = cache.config.clamp(ttl);
+                    cache.insert(host, Entry::Resolved(addrs.clone()), ttl);
+                    let addrs: Addrs = Box::new(addrs.into_iter());
+                    Ok((addrs, Some(ttl)))
+                }
+                Err(err) => {
+                    let ttl = cache.config.negative_ttl;
+                    cache.insert(host, Entry::Failed(err.to_string().into()), ttl);
+                    Err(err)
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    struct Counting(AtomicUsize, bool);
+
+    impl Resolve for Counting {
+        fn resolve(&self, _: Name) -> Resolving {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            let res: Result<Addrs, BoxError> = if self.1 {
+                let addrs: Addrs = Box::new(std::iter::once(SocketAddr::from(([127, 0, 0, 1], 0))));
+                Ok(addrs)
+            } else {
+                Err("no such host".into())
+            };
+            Box::pin(futures_util::future::ready(res))
+        }
+    }
+
+    fn resolve(resolver: &CachingResolver, host: &str) -> Result<Vec<SocketAddr>, String> {
+        let name = Name::from_str(host).unwrap();
+        futures_util::FutureExt::now_or_never(resolver.resolve(name))
+            .unwrap()
+            .map(Iterator::collect)
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn caches_resolutions() {
+        let inner = Arc::new(Counting(AtomicUsize::new(0), true));
+        let cache = Arc::new(DnsCache::new(DnsCacheConfig::new()));
+        let resolver = CachingResolver::new(inner.clone(), cache.clone());
+
+        let addrs = resolve(&resolver, "example.com").unwrap();
+        assert_eq!(addrs, [SocketAddr::from(([127, 0, 0, 1], 0))]);
+        assert_eq!(resolve(&resolver, "EXAMPLE.com").unwrap(), addrs);
+        assert_eq!(inner.0.load(Ordering::SeqCst), 1);
+
+        cache.evict("example.com");
+        resolve(&resolver, "example.com").unwrap();
+        assert_eq!(inner.0.load(Ordering::SeqCst), 2);
+
+        cache.flush();
+        resolve(&resolver, "example.com").unwrap();
+        assert_eq!(inner.0.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn caches_failures() {
+        let inner = Arc::new(Counting(AtomicUsize::new(0), false));
+        let cache = Arc::new(DnsCache::new(DnsCacheConfig::new()));
+        let resolver = CachingResolver::new(inner.clone(), cache);
+
+        assert_eq!(
+            resolve(&resolver, "example.com").unwrap_err(),
+            "no such host"
+        );
+        assert_eq!(
+            resolve(&resolver, "example.com").unwrap_err(),
+            "no such host"
+        );
+        assert_eq!(inner.0.load(Ordering::SeqCst), 1);
+
+        let cache = Arc::new(DnsCache::new(
+            DnsCacheConfig::new().negative_ttl(Duration::ZERO),
+        ));
+        let resolver = CachingResolver::new(inner.clone(), cache);
+        resolve(&resolver, "example.com").unwrap_err();
+        resolve(&resolver, "example.com").unwrap_err();
+        assert_eq!(inner.0.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn clamps_ttl() {
+        let config = DnsCacheConfig::new()
+            .min_ttl(Duration::from_secs(10))
+            .max_ttl(Duration::from_secs(100))
+            .default_ttl(Duration::from_secs(50));
+
+        assert_eq!(config.clamp(None), Duration::from_secs(50));
+        assert_eq!(
+            config.clamp(Some(Duration::from_secs(1))),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            config.clamp(Some(Duration::from_secs(1000))),
+            Duration::from_secs(100)
+        );
+    }
+}
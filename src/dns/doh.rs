@@ -0,0 +1,136 @@
+//! DNS-over-HTTPS resolver, as specified by [RFC 8484].
+//!
+//! [RFC 8484]: https://www.rfc-editor.org/rfc/rfc8484
+use std::sync::{Arc, OnceLock};
+
+use http::header::{ACCEPT, CONTENT_TYPE};
+
+use super::svcb::{into_addrs, HttpsRecord, HttpsRecords};
+use super::wire::{decode_answers, encode_query, Answers, TYPE_A, TYPE_AAAA, TYPE_HTTPS};
+use super::{Addrs, Name, Resolve, Resolving, ResolvingWithTtl};
+use crate::client::http::WeakClient;
+use crate::error::BoxError;
+use crate::Url;
+
+const DNS_MESSAGE: &str = "application/dns-message";
+
+/// Resolves names by querying a DoH endpoint with a bare copy of the client
+/// it serves.
+///
+/// Queries use the same impersonated TLS stack and network settings as the
+/// requests they resolve names for, but none of their proxies, cookies or
+/// default headers. The endpoint's own host name is resolved with the
+/// `bootstrap` resolver.
+pub(crate) struct DohResolver {
+    endpoint: Url,
+    bootstrap: Arc<dyn Resolve>,
+    client: Arc<OnceLock<WeakClient>>,
+    https_records: Option<Arc<HttpsRecords>>,
+}
+
+impl DohResolver {
+    pub(crate) fn new(
+        endpoint: Url,
+        bootstrap: Arc<dyn Resolve>,
+        client: Arc<OnceLock<WeakClient>>,
+        https_records: Option<Arc<HttpsRecords>>,
+    ) -> DohResolver {
+        DohResolver {
+            endpoint,
+            bootstrap,
+            client,
+            https_records,
+        }
+    }
+}
+
+impl Resolve for DohResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolving = self.resolve_with_ttl(name);
+        Box::pin(async move { Ok(resolving.await?.0) })
+    }
+
+    fn resolve_with_ttl(&self, name: Name) -> ResolvingWithTtl {
+        if self.endpoint.host_str() == Some(name.as_str()) {
+            return self.bootstrap.resolve_with_ttl(name);
+        }
+
+        let client = match self.client.get().and_then(WeakClient::upgrade) {
+            Some(client) => client,
+            None => {
+                let err: BoxError = "DoH client was dropped".into();
+                return Box::pin(futures_util::future::err(err));
+            }
+        };
+        let endpoint = self.endpoint.clone();
+        let https_records = self.https_records.clone();
+
+        Box::pin(async move {
+            let name = name.as_str();
+            let (answers, record) = match https_records {
+                Some(_) => {
+                    let (answers, https) = futures_util::future::join(
+                        lookup(&client, &endpoint, name),
+                        query(&client, &endpoint, name, TYPE_HTTPS),
+                    )
+                    .await;
+                    let record = https.ok().and_then(HttpsRecord::select);
+
+                    // connect to the alternative endpoint, if any
+                    let target = record
+                        .as_ref()
+                        .and_then(|(record, _)| record.target())
+                        .filter(|target| !target.eq_ignore_ascii_case(name))
+                        .map(str::to_owned);
+                    match target {
+                        Some(target) => (lookup(&client, &endpoint, &target).await, record),
+                        None => (answers, record),
+                    }
+                }
+                None => (lookup(&client, &endpoint, name).await, None),
+            };
+
+            let (addrs, ttl) = into_addrs("DoH", name, answers, record, https_records.as_deref())?;
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok((addrs, ttl))
+        })
+    }
+}
+
+/// Looks up the A and AAAA records of `name`.
+async fn lookup(client: &crate::Client, endpoint: &Url, name: &str) -> Result<Answers, BoxError> {
+    let (v4, v6) = futures_util::future::join(
+        query(client, endpoint, name, TYPE_A),
+        query(client, endpoint, name, TYPE_AAAA),
+    )
+    .await;
+
+    match (v4, v6) {
+        (Err(err), Err(_)) => Err(err),
+        (v4, v6) => {
+            let mut answers = v4.unwrap_or_default();
+            answers.extend(v6.unwrap_or_default());
+            Ok(answers)
+        }
+    }
+}
+
+async fn query(
+    client: &crate::Client,
+    endpoint: &Url,
+    name: &str,
+    qtype: u16,
+) -> Result<Answers, BoxError> {
+    let body = client
+        .post(endpoint.clone())
+        .header(CONTENT_TYPE, DNS_MESSAGE)
+        .header(ACCEPT, DNS_MESSAGE)
+        .body(encode_query(0, name, qtype)?)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+
+    decode_answers(&body)
+}
@@ -0,0 +1,309 @@
+//! DNS-over-TLS resolver, as specified by [RFC 7858].
+//!
+//! [RFC 7858]: https://www.rfc-editor.org/rfc/rfc7858
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use boring2::hash::{hash, MessageDigest};
+use boring2::ssl::{SslConnector, SslMethod, SslRef};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use super::svcb::{into_addrs, HttpsRecord, HttpsRecords};
+use super::wire::{
+    decode_answers, encode_query, message_id, Answers, TYPE_A, TYPE_AAAA, TYPE_HTTPS,
+};
+use super::{Addrs, Name, Resolve, Resolving, ResolvingWithTtl};
+use crate::error::BoxError;
+use crate::tls::RootCertStoreProvider;
+
+/// The port DNS-over-TLS servers listen on.
+const DOT_PORT: u16 = 853;
+
+/// The time a server has to answer, by default.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An upstream DNS-over-TLS server.
+///
+/// # Example
+///
+/// ```
+/// use rquest::dns::DotConfig;
+///
+/// let upstream = DotConfig::new("dns.example.net", ["192.0.2.53".parse().unwrap()])
+///     .pin_spki_sha256([0u8; 32]);
+///
+/// let client = rquest::Client::builder()
+///     .dot(upstream)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct DotConfig {
+    server_name: String,
+    addrs: Vec<SocketAddr>,
+    spki_pins: Vec<[u8; 32]>,
+    root_certs_store: RootCertStoreProvider,
+    timeout: Duration,
+}
+
+impl DotConfig {
+    /// Creates a new `DotConfig` for the server named `server_name`,
+    /// reachable on port 853 of the given addresses.
+    ///
+    /// The server's certificate is verified against `server_name`.
+    pub fn new<S, I>(server_name: S, addrs: I) -> DotConfig
+    where
+        S: Into<String>,
+        I: IntoIterator<Item = IpAddr>,
+    {
+        DotConfig {
+            server_name: server_name.into(),
+            addrs: addrs
+                .into_iter()
+                .map(|ip| SocketAddr::new(ip, DOT_PORT))
+                .collect(),
+            spki_pins: Vec::new(),
+            root_certs_store: RootCertStoreProvider::Default,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Cloudflare's resolver, `one.one.one.one`.
+    pub fn cloudflare() -> DotConfig {
+        DotConfig::new(
+            "one.one.one.one",
+            [IpAddr::from([1, 1, 1, 1]), IpAddr::from([1, 0, 0, 1])],
+        )
+    }
+
+    /// Google's resolver, `dns.google`.
+    pub fn google() -> DotConfig {
+        DotConfig::new(
+            "dns.google",
+            [IpAddr::from([8, 8, 8, 8]), IpAddr::from([8, 8, 4, 4])],
+        )
+    }
+
+    /// Quad9's resolver, `dns.quad9.net`.
+    pub fn quad9() -> DotConfig {
+        DotConfig::new(
+            "dns.quad9.net",
+            [
+                IpAddr::from([9, 9, 9, 9]),
+                IpAddr::from([149, 112, 112, 112]),
+            ],
+        )
+    }
+
+    /// Sets the port to connect to, instead of 853.
+    pub fn port(mut self, port: u16) -> DotConfig {
+        for addr in &mut self.addrs {
+            addr.set_port(port);
+        }
+        self
+    }
+
+    /// Pins the SHA-256 digest of the server's SubjectPublicKeyInfo.
+    ///
+    /// Once at least one pin is set, connections to servers whose public key
+    /// matches none of them are rejected, as described in [RFC 7858 section
+    /// 4.2](https://www.rfc-editor.org/rfc/rfc7858#section-4.2).
+    pub fn pin_spki_sha256(mut self, digest: [u8; 32]) -> DotConfig {
+        self.spki_pins.push(digest);
+        self
+    }
+
+    /// Sets the root certificates the server's certificate is verified
+    /// against, instead of the default ones.
+    pub fn root_cert_store<S>(mut self, store: S) -> DotConfig
+    where
+        S: Into<RootCertStoreProvider>,
+    {
+        self.root_certs_store = store.into();
+        self
+    }
+
+    /// Sets the time each server has to connect and answer a lookup, before
+    /// the next one is tried.
+    ///
+    /// Default is 5 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> DotConfig {
+        self.timeout = timeout;
+        self
+    }
+}
+
+pub(crate) struct DotResolver {
+    config: Arc<DotConfig>,
+    connector: SslConnector,
+    https_records: Option<Arc<HttpsRecords>>,
+}
+
+impl DotResolver {
+    pub(crate) fn new(
+        config: DotConfig,
+        https_records: Option<Arc<HttpsRecords>>,
+    ) -> crate::Result<DotResolver> {
+        let mut builder = SslConnector::builder(SslMethod::tls()).map_err(crate::error::builder)?;
+        config
+            .root_certs_store
+            .clone()
+            .apply_to_builder(&mut builder)
+            .map_err(crate::error::builder)?;
+
+        Ok(DotResolver {
+            config: Arc::new(config),
+            connector: builder.build(),
+            https_records,
+        })
+    }
+}
+
+impl Resolve for DotResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolving = self.resolve_with_ttl(name);
+        Box::pin(async move { Ok(resolving.await?.0) })
+    }
+
+    fn resolve_with_ttl(&self, name: Name) -> ResolvingWithTtl {
+        let config = self.config.clone();
+        let connector = self.connector.clone();
+        let https_records = self.https_records.clone();
+
+        Box::pin(async move {
+            let name = name.as_str();
+            let https = https_records.is_some();
+            let mut last_err: BoxError = "no DoT server configured".into();
+            for addr in &config.addrs {
+                let (answers, record) =
+                    match exchange(&config, &connector, *addr, name, https).await {
+                        Ok((answers, https)) => (answers, https.and_then(HttpsRecord::select)),
+                        Err(err) => {
+                            log::debug!("DoT server {} failed: {}", addr, err);
+                            last_err = err;
+                            continue;
+                        }
+                    };
+
+                // connect to the alternative endpoint, if any
+                let target = record
+                    .as_ref()
+                    .and_then(|(record, _)| record.target())
+                    .filter(|target| !target.eq_ignore_ascii_case(name))
+                    .map(str::to_owned);
+                let answers = match target {
+                    Some(target) => exchange(&config, &connector, *addr, &target, false)
+                        .await
+                        .map(|(answers, _)| answers),
+                    None => Ok(answers),
+                };
+
+                let (addrs, ttl) =
+                    into_addrs("DoT", name, answers, record, https_records.as_deref())?;
+                let addrs: Addrs = Box::new(addrs.into_iter());
+                return Ok((addrs, ttl));
+            }
+            Err(last_err)
+        })
+    }
+}
+
+/// Looks up the A and AAAA records of `name` over a single connection, and
+/// its HTTPS records if `https` is set, within the timeout of the server.
+async fn exchange(
+    config: &DotConfig,
+    connector: &SslConnector,
+    addr: SocketAddr,
+    name: &str,
+    https: bool,
+) -> Result<(Answers, Option<Answers>), BoxError> {
+    tokio::time::timeout(
+        config.timeout,
+        exchange_queries(config, connector, addr, name, https),
+    )
+    .await
+    .map_err(|_| "DoT server timed out")?
+}
+
+async fn exchange_queries(
+    config: &DotConfig,
+    connector: &SslConnector,
+    addr: SocketAddr,
+    name: &str,
+    https: bool,
+) -> Result<(Answers, Option<Answers>), BoxError> {
+    let tcp = TcpStream::connect(addr).await?;
+    let ssl = connector.configure()?.into_ssl(&config.server_name)?;
+    let mut tls = tokio_boring2::SslStreamBuilder::new(ssl, tcp)
+        .connect()
+        .await?;
+    check_spki_pins(config, tls.ssl())?;
+
+    // Pipeline the queries; each message is prefixed with its length, and
+    // the IDs start at a random one, as they would over UDP
+    let qtypes: &[u16] = if https {
+        &[TYPE_A, TYPE_AAAA, TYPE_HTTPS]
+    } else {
+        &[TYPE_A, TYPE_AAAA]
+    };
+    let first_id = crate::util::fast_random() as u16;
+    let mut queries = Vec::new();
+    for (i, qtype) in (0..).zip(qtypes) {
+        let query = encode_query(first_id.wrapping_add(i), name, *qtype)?;
+        queries.extend_from_slice(&(query.len() as u16).to_be_bytes());
+        queries.extend_from_slice(&query);
+    }
+    tls.write_all(&queries).await?;
+    tls.flush().await?;
+
+    let mut answers = Answers::default();
+    let mut https_answers = None;
+    let mut answered = vec![false; qtypes.len()];
+    while answered.contains(&false) {
+        let len = tls.read_u16().await? as usize;
+        let mut msg = vec![0; len];
+        tls.read_exact(&mut msg).await?;
+
+        let idx = match message_id(&msg) {
+            Some(id) if (id.wrapping_sub(first_id) as usize) < qtypes.len() => {
+                id.wrapping_sub(first_id) as usize
+            }
+            _ => return Err("unexpected DNS response ID".into()),
+        };
+        if std::mem::replace(&mut answered[idx], true) {
+            return Err("unexpected DNS response ID".into());
+        }
+
+        match qtypes[idx] {
+            // a missing HTTPS record is no reason to fail
+            TYPE_HTTPS => https_answers = decode_answers(&msg).ok(),
+            _ => answers.extend(decode_answers(&msg)?),
+        }
+    }
+
+    Ok((answers, https_answers))
+}
+
+fn check_spki_pins(config: &DotConfig, ssl: &SslRef) -> Result<(), BoxError> {
+    if config.spki_pins.is_empty() {
+        return Ok(());
+    }
+
+    let cert = ssl
+        .peer_certificate()
+        .ok_or("DoT server sent no certificate")?;
+    let spki = cert.public_key()?.public_key_to_der()?;
+    let digest = hash(MessageDigest::sha256(), &spki)?;
+
+    if config.spki_pins.iter().any(|pin| pin[..] == digest[..]) {
+        Ok(())
+    } else {
+        Err(format!(
+            "DoT server {} doesn't match any SPKI pin",
+            config.server_name
+        )
+        .into())
+    }
+}
@@ -1,11 +1,26 @@
 //! DNS resolution via the [hickory-resolver](https://github.com/hickory-dns/hickory-dns) crate
 
-use super::{Addrs, Name, Resolve, Resolving};
+use super::{Addrs, Name, Resolve, Resolving, ResolvingWithTtl};
 pub use hickory_resolver::config::LookupIpStrategy;
-use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+use hickory_resolver::config::{
+    NameServerConfig, NameServerConfigGroup, Protocol, ResolverConfig, ResolverOpts,
+};
+use hickory_resolver::proto::rr::Name as DomainName;
 use hickory_resolver::{lookup_ip::LookupIpIntoIter, system_conf, TokioAsyncResolver};
 use std::net::SocketAddr;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Instant;
+
+/// Settings layered over the system configuration, set through
+/// `ClientBuilder::hickory_dns_*`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HickoryDnsOptions {
+    pub(crate) strategy: Option<LookupIpStrategy>,
+    pub(crate) nameservers: Option<Vec<SocketAddr>>,
+    pub(crate) ndots: Option<usize>,
+    pub(crate) search: Option<Vec<String>>,
+}
 
 /// Wrapper around an `AsyncResolver`, which implements the `Resolve` trait.
 #[derive(Debug, Clone)]
@@ -25,14 +40,50 @@ impl HickoryDnsResolver {
     where
         S: Into<Option<LookupIpStrategy>>,
     {
-        let (config, mut opts) = match system_conf::read_system_conf() {
+        Self::with_options(HickoryDnsOptions {
+            strategy: strategy.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Create a new resolver from the system configuration, replacing
+    /// the nameservers, `ndots` and search domains that were set.
+    pub(crate) fn with_options(options: HickoryDnsOptions) -> crate::Result<Self> {
+        let (mut config, mut opts) = match system_conf::read_system_conf() {
             Ok((config, opts)) => (config, opts),
             Err(err) => {
                 log::debug!("error reading DNS system conf: {}", err);
                 (ResolverConfig::default(), ResolverOpts::default())
             }
         };
-        opts.ip_strategy = strategy.into().unwrap_or(LookupIpStrategy::Ipv4AndIpv6);
+        opts.ip_strategy = options.strategy.unwrap_or(LookupIpStrategy::Ipv4AndIpv6);
+        if let Some(ndots) = options.ndots {
+            opts.ndots = ndots;
+        }
+
+        if options.nameservers.is_some() || options.search.is_some() {
+            let name_servers = match options.nameservers {
+                Some(addrs) => {
+                    let mut group = NameServerConfigGroup::with_capacity(addrs.len() * 2);
+                    for addr in addrs {
+                        group.push(NameServerConfig::new(addr, Protocol::Udp));
+                        group.push(NameServerConfig::new(addr, Protocol::Tcp));
+                    }
+                    group
+                }
+                None => NameServerConfigGroup::from(config.name_servers().to_vec()),
+            };
+            let search = match options.search {
+                Some(domains) => domains
+                    .iter()
+                    .map(|domain| DomainName::from_str(domain))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(crate::error::builder)?,
+                None => config.search().to_vec(),
+            };
+            config = ResolverConfig::from_parts(config.domain().cloned(), search, name_servers);
+        }
+
         Ok(Self {
             state: Arc::new(TokioAsyncResolver::tokio(config, opts)),
         })
@@ -54,6 +105,20 @@ impl Resolve for HickoryDnsResolver {
             Ok(addrs)
         })
     }
+
+    fn resolve_with_ttl(&self, name: Name) -> ResolvingWithTtl {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let lookup = resolver.state.lookup_ip(name.as_str()).await?;
+            let ttl = lookup
+                .valid_until()
+                .saturating_duration_since(Instant::now());
+            let addrs: Addrs = Box::new(SocketAddrs {
+                iter: lookup.into_iter(),
+            });
+            Ok((addrs, Some(ttl)))
+        })
+    }
 }
 
 impl Iterator for SocketAddrs {
@@ -1,11 +1,21 @@
 //! DNS resolution
 
+pub use cache::DnsCacheConfig;
+pub use dot::DotConfig;
 #[cfg(feature = "hickory-dns")]
 pub use hickory::{HickoryDnsResolver, LookupIpStrategy};
-pub use resolve::{Addrs, Name, Resolve, Resolving};
-pub(crate) use resolve::{DnsResolverWithOverrides, DynResolver};
+pub use resolve::{Addrs, Name, Resolve, Resolving, ResolvingWithTtl};
+pub(crate) use resolve::{DnsOverrides, DnsResolverWithOverrides, DynResolver, OverrideTarget};
+pub use svcb::HttpsRecord;
+pub use weighted::{ResolveWeighted, ResolvingWeighted, WeightedAddr};
 
+pub(crate) mod cache;
+pub(crate) mod doh;
+pub(crate) mod dot;
 pub(crate) mod gai;
 #[cfg(feature = "hickory-dns")]
 pub(crate) mod hickory;
 pub(crate) mod resolve;
+pub(crate) mod svcb;
+pub(crate) mod weighted;
+mod wire;
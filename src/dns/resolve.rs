@@ -1,13 +1,16 @@
 use crate::util::client::connect::dns::Name as HyperName;
+use ipnet::IpNet;
 use tower_service::Service;
 
 use std::collections::HashMap;
 use std::future::Future;
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use crate::error::BoxError;
 
@@ -17,6 +20,12 @@ pub type Addrs = Box<dyn Iterator<Item = SocketAddr> + Send>;
 /// Alias for the `Future` type returned by a DNS resolver.
 pub type Resolving = Pin<Box<dyn Future<Output = Result<Addrs, BoxError>> + Send>>;
 
+/// Alias for the `Future` type returned by [`Resolve::resolve_with_ttl`].
+///
+/// It resolves to the addresses and, if known, how long they may be cached.
+pub type ResolvingWithTtl =
+    Pin<Box<dyn Future<Output = Result<(Addrs, Option<Duration>), BoxError>> + Send>>;
+
 /// Trait for customizing DNS resolution in rquest.
 pub trait Resolve: Send + Sync {
     /// Performs DNS resolution on a `Name`.
@@ -31,6 +40,16 @@ pub trait Resolve: Send + Sync {
     /// Explicitly specified port in the URL will override any port in the resolved `SocketAddr`s.
     /// Otherwise, port `0` will be replaced by the conventional port for the given scheme (e.g. 80 for http).
     fn resolve(&self, name: Name) -> Resolving;
+
+    /// Performs DNS resolution on a `Name`, also returning how long the
+    /// addresses may be cached, usually the lowest TTL of the records.
+    ///
+    /// The client's DNS cache uses this. The default implementation calls
+    /// `resolve` and reports no TTL, leaving the cache to its default.
+    fn resolve_with_ttl(&self, name: Name) -> ResolvingWithTtl {
+        let resolving = self.resolve(name);
+        Box::pin(async move { Ok((resolving.await?, None)) })
+    }
 }
 
 /// A name that must be resolved to addresses.
@@ -42,6 +61,29 @@ impl Name {
     pub fn as_str(&self) -> &str {
         self.0.as_str()
     }
+
+    /// Get the port the connection is for, or `0` if unknown.
+    ///
+    /// Addresses with port `0` are given this port; a resolver may return
+    /// other ports, as service discovery does, which are used unless the URL
+    /// has an explicit port.
+    pub fn port(&self) -> u16 {
+        self.0.port()
+    }
+
+    /// Whether IPv4 addresses can be connected to.
+    ///
+    /// This is `false` when the client is bound to an IPv6 local address only.
+    pub fn wants_ipv4(&self) -> bool {
+        self.0.families().0
+    }
+
+    /// Whether IPv6 addresses can be connected to.
+    ///
+    /// This is `false` when the client is bound to an IPv4 local address only.
+    pub fn wants_ipv6(&self) -> bool {
+        self.0.families().1
+    }
 }
 
 impl FromStr for Name {
@@ -79,16 +121,124 @@ impl Service<HyperName> for DynResolver {
     }
 }
 
+/// Addresses to resolve overridden names to.
+#[derive(Debug)]
+pub(crate) enum OverrideTarget {
+    /// The given addresses, in order.
+    Addrs(Vec<SocketAddr>),
+    /// The given addresses, the first of which is rotated on each resolution.
+    RoundRobin(Vec<SocketAddr>),
+    /// One host address of the network per resolution, in turn.
+    Range(IpNet),
+}
+
+#[derive(Debug)]
+struct Override {
+    target: OverrideTarget,
+    next: AtomicUsize,
+}
+
+impl Override {
+    fn addrs(&self) -> Vec<SocketAddr> {
+        match self.target {
+            OverrideTarget::Addrs(ref addrs) => addrs.clone(),
+            OverrideTarget::RoundRobin(ref addrs) if addrs.is_empty() => Vec::new(),
+            OverrideTarget::RoundRobin(ref addrs) => {
+                let mut addrs = addrs.clone();
+                let len = addrs.len();
+                addrs.rotate_left(self.next() % len);
+                addrs
+            }
+            OverrideTarget::Range(ref range) => {
+                vec![SocketAddr::new(nth_host(range, self.next()), 0)]
+            }
+        }
+    }
+
+    fn next(&self) -> usize {
+        self.next.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+/// Returns the `n`th usable host address of `range`, wrapping around.
+fn nth_host(range: &IpNet, n: usize) -> IpAddr {
+    // network and broadcast addresses are skipped, except in /31 and /32
+    // (and their IPv6 counterparts) where every address is a host
+    let host_bits = range.max_prefix_len() - range.prefix_len();
+    let (skip, hosts) = match host_bits {
+        0 => (0, 1),
+        1 => (0, 2),
+        bits if bits >= 64 => (1, u64::MAX as u128),
+        bits => (1, (1u128 << bits) - 2),
+    };
+    let offset = skip + (n as u128 % hosts);
+
+    match range.network() {
+        IpAddr::V4(network) => IpAddr::V4(Ipv4Addr::from(u32::from(network) + offset as u32)),
+        IpAddr::V6(network) => IpAddr::V6(Ipv6Addr::from(u128::from(network) + offset)),
+    }
+}
+
+/// Names resolved without asking the DNS resolver.
+///
+/// Patterns are either exact names or wildcards like `*.example.com`, which
+/// match every subdomain of `example.com` but not `example.com` itself. An
+/// exact match wins over wildcards, and longer wildcards over shorter ones.
+#[derive(Debug, Default)]
+pub(crate) struct DnsOverrides {
+    exact: HashMap<String, Override>,
+    wildcards: Vec<(String, Override)>,
+}
+
+impl DnsOverrides {
+    pub(crate) fn insert(&mut self, pattern: &str, target: OverrideTarget) {
+        let pattern = pattern.to_ascii_lowercase();
+        let entry = Override {
+            target,
+            next: AtomicUsize::new(0),
+        };
+
+        match pattern.strip_prefix("*.") {
+            Some(suffix) => {
+                let suffix = format!(".{}", suffix);
+                self.wildcards.retain(|(other, _)| *other != suffix);
+                self.wildcards.push((suffix, entry));
+                self.wildcards
+                    .sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
+            }
+            None => {
+                self.exact.insert(pattern, entry);
+            }
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.exact.is_empty() && self.wildcards.is_empty()
+    }
+
+    fn get(&self, name: &str) -> Option<Vec<SocketAddr>> {
+        let name = name.to_ascii_lowercase();
+        self.exact
+            .get(&name)
+            .or_else(|| {
+                self.wildcards
+                    .iter()
+                    .find(|(suffix, _)| {
+                        name.len() > suffix.len() && name.ends_with(suffix.as_str())
+                    })
+                    .map(|(_, entry)| entry)
+            })
+            .map(Override::addrs)
+    }
+}
+
 pub(crate) struct DnsResolverWithOverrides {
     dns_resolver: Arc<dyn Resolve>,
-    overrides: Arc<HashMap<String, Vec<SocketAddr>>>,
+    overrides: Arc<DnsOverrides>,
 }
 
 impl DnsResolverWithOverrides {
-    pub(crate) fn new(
-        dns_resolver: Arc<dyn Resolve>,
-        overrides: HashMap<String, Vec<SocketAddr>>,
-    ) -> Self {
+    pub(crate) fn new(dns_resolver: Arc<dyn Resolve>, overrides: DnsOverrides) -> Self {
         DnsResolverWithOverrides {
             dns_resolver,
             overrides: Arc::new(overrides),
@@ -100,12 +250,22 @@ impl Resolve for DnsResolverWithOverrides {
     fn resolve(&self, name: Name) -> Resolving {
         match self.overrides.get(name.as_str()) {
             Some(dest) => {
-                let addrs: Addrs = Box::new(dest.clone().into_iter());
+                let addrs: Addrs = Box::new(dest.into_iter());
                 Box::pin(futures_util::future::ready(Ok(addrs)))
             }
             None => self.dns_resolver.resolve(name),
         }
     }
+
+    fn resolve_with_ttl(&self, name: Name) -> ResolvingWithTtl {
+        match self.overrides.get(name.as_str()) {
+            Some(dest) => {
+                let addrs: Addrs = Box::new(dest.into_iter());
+                Box::pin(futures_util::future::ok::<_, BoxError>((addrs, None)))
+            }
+            None => self.dns_resolver.resolve_with_ttl(name),
+        }
+    }
 }
 
 mod sealed {
@@ -124,3 +284,101 @@ mod sealed {
 
     impl std::error::Error for InvalidNameError {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(s: &str) -> SocketAddr {
+        SocketAddr::new(s.parse().unwrap(), 0)
+    }
+
+    #[test]
+    fn overrides_match_wildcards() {
+        let mut overrides = DnsOverrides::default();
+        overrides.insert(
+            "*.example.com",
+            OverrideTarget::Addrs(vec![addr("10.0.0.1")]),
+        );
+        overrides.insert(
+            "*.api.example.com",
+            OverrideTarget::Addrs(vec![addr("10.0.0.2")]),
+        );
+        overrides.insert(
+            "www.example.com",
+            OverrideTarget::Addrs(vec![addr("10.0.0.3")]),
+        );
+
+        assert_eq!(overrides.get("a.Example.com"), Some(vec![addr("10.0.0.1")]));
+        assert_eq!(
+            overrides.get("a.b.example.com"),
+            Some(vec![addr("10.0.0.1")])
+        );
+        assert_eq!(
+            overrides.get("v1.api.example.com"),
+            Some(vec![addr("10.0.0.2")])
+        );
+        assert_eq!(
+            overrides.get("www.example.com"),
+            Some(vec![addr("10.0.0.3")])
+        );
+        assert_eq!(overrides.get("example.com"), None);
+        assert_eq!(overrides.get("badexample.com"), None);
+    }
+
+    #[test]
+    fn overrides_round_robin() {
+        let mut overrides = DnsOverrides::default();
+        overrides.insert(
+            "a.test",
+            OverrideTarget::RoundRobin(vec![addr("10.0.0.1"), addr("10.0.0.2")]),
+        );
+        overrides.insert(
+            "e.test",
+            OverrideTarget::Addrs(vec![addr("10.3.0.1"), addr("10.3.0.2")]),
+        );
+        overrides.insert(
+            "b.test",
+            OverrideTarget::Range("10.1.0.0/30".parse().unwrap()),
+        );
+        overrides.insert(
+            "c.test",
+            OverrideTarget::Range("10.2.0.7/32".parse().unwrap()),
+        );
+        overrides.insert(
+            "d.test",
+            OverrideTarget::Range("fd00::/64".parse().unwrap()),
+        );
+
+        assert_eq!(
+            overrides.get("a.test"),
+            Some(vec![addr("10.0.0.1"), addr("10.0.0.2")])
+        );
+        assert_eq!(
+            overrides.get("a.test"),
+            Some(vec![addr("10.0.0.2"), addr("10.0.0.1")])
+        );
+        assert_eq!(
+            overrides.get("a.test"),
+            Some(vec![addr("10.0.0.1"), addr("10.0.0.2")])
+        );
+
+        // plain addresses keep their order
+        for _ in 0..2 {
+            assert_eq!(
+                overrides.get("e.test"),
+                Some(vec![addr("10.3.0.1"), addr("10.3.0.2")])
+            );
+        }
+
+        assert_eq!(overrides.get("b.test"), Some(vec![addr("10.1.0.1")]));
+        assert_eq!(overrides.get("b.test"), Some(vec![addr("10.1.0.2")]));
+        assert_eq!(overrides.get("b.test"), Some(vec![addr("10.1.0.1")]));
+
+        assert_eq!(overrides.get("c.test"), Some(vec![addr("10.2.0.7")]));
+        assert_eq!(overrides.get("c.test"), Some(vec![addr("10.2.0.7")]));
+
+        assert_eq!(overrides.get("d.test"), Some(vec![addr("fd00::1")]));
+        assert_eq!(overrides.get("d.test"), Some(vec![addr("fd00::2")]));
+    }
+}
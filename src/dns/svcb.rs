@@ -0,0 +1,305 @@
+//! HTTPS resource records, as specified by [RFC 9460].
+//!
+//! [RFC 9460]: https://www.rfc-editor.org/rfc/rfc9460
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use antidote::Mutex;
+use lru::LruCache;
+
+use super::wire::Answers;
+use crate::error::BoxError;
+
+const KEY_ALPN: u16 = 1;
+const KEY_NO_DEFAULT_ALPN: u16 = 2;
+const KEY_PORT: u16 = 3;
+const KEY_IPV4HINT: u16 = 4;
+const KEY_ECH: u16 = 5;
+const KEY_IPV6HINT: u16 = 6;
+
+/// An HTTPS (type 65) DNS record, describing how to connect to an origin.
+///
+/// Servers publish these to advertise HTTP/3 support, alternative
+/// endpoints and Encrypted Client Hello configurations. See
+/// [`ClientBuilder::https_rr`] and [`Client::https_record`].
+///
+/// [`ClientBuilder::https_rr`]: crate::ClientBuilder::https_rr
+/// [`Client::https_record`]: crate::Client::https_record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpsRecord {
+    priority: u16,
+    target: String,
+    alpn: Vec<String>,
+    no_default_alpn: bool,
+    port: Option<u16>,
+    ipv4_hints: Vec<Ipv4Addr>,
+    ipv6_hints: Vec<Ipv6Addr>,
+    ech_config: Option<Vec<u8>>,
+}
+
+impl HttpsRecord {
+    /// Get the priority; `0` marks an alias to [`target`](Self::target).
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+
+    /// Get the name of the alternative endpoint, if it differs from the
+    /// origin's.
+    pub fn target(&self) -> Option<&str> {
+        if self.target.is_empty() {
+            None
+        } else {
+            Some(&self.target)
+        }
+    }
+
+    /// Get the ALPN protocols the endpoint supports, such as `h3` or `h2`.
+    ///
+    /// Unless [`no_default_alpn`](Self::no_default_alpn) is set, `http/1.1`
+    /// is supported as well.
+    pub fn alpn(&self) -> &[String] {
+        &self.alpn
+    }
+
+    /// Whether the endpoint only supports the protocols listed in `alpn`.
+    pub fn no_default_alpn(&self) -> bool {
+        self.no_default_alpn
+    }
+
+    /// Whether the endpoint advertises HTTP/3.
+    pub fn supports_h3(&self) -> bool {
+        self.alpn.iter().any(|alpn| alpn == "h3")
+    }
+
+    /// Get the port of the endpoint, if not the default one.
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// Get the IPv4 addresses hinted for the endpoint.
+    pub fn ipv4_hints(&self) -> &[Ipv4Addr] {
+        &self.ipv4_hints
+    }
+
+    /// Get the IPv6 addresses hinted for the endpoint.
+    pub fn ipv6_hints(&self) -> &[Ipv6Addr] {
+        &self.ipv6_hints
+    }
+
+    /// Get the encoded `ECHConfigList` for Encrypted Client Hello.
+    pub fn ech_config(&self) -> Option<&[u8]> {
+        self.ech_config.as_deref()
+    }
+
+    /// The hinted addresses, for when the endpoint's name has none.
+    pub(crate) fn hints(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+        let v4 = self.ipv4_hints.iter().map(|ip| SocketAddr::from((*ip, 0)));
+        let v6 = self.ipv6_hints.iter().map(|ip| SocketAddr::from((*ip, 0)));
+        v4.chain(v6)
+    }
+
+    /// Decodes the RDATA of an HTTPS record.
+    pub(crate) fn decode(rdata: &[u8]) -> Result<HttpsRecord, BoxError> {
+        const TRUNCATED: &str = "truncated HTTPS record";
+
+        let priority = u16::from_be_bytes([
+            *rdata.first().ok_or(TRUNCATED)?,
+            *rdata.get(1).ok_or(TRUNCATED)?,
+        ]);
+
+        // the target name is never compressed
+        let mut pos = 2;
+        let mut labels = Vec::new();
+        loop {
+            let len = *rdata.get(pos).ok_or(TRUNCATED)? as usize;
+            pos += 1;
+            if len == 0 {
+                break;
+            }
+            let label = rdata.get(pos..pos + len).ok_or(TRUNCATED)?;
+            labels.push(String::from_utf8_lossy(label).into_owned());
+            pos += len;
+        }
+
+        let mut record = HttpsRecord {
+            priority,
+            target: labels.join("."),
+            alpn: Vec::new(),
+            no_default_alpn: false,
+            port: None,
+            ipv4_hints: Vec::new(),
+            ipv6_hints: Vec::new(),
+            ech_config: None,
+        };
+
+        while pos < rdata.len() {
+            let header = rdata.get(pos..pos + 4).ok_or(TRUNCATED)?;
+            let key = u16::from_be_bytes([header[0], header[1]]);
+            let len = u16::from_be_bytes([header[2], header[3]]) as usize;
+            let value = rdata.get(pos + 4..pos + 4 + len).ok_or(TRUNCATED)?;
+            pos += 4 + len;
+
+            match key {
+                KEY_ALPN => {
+                    let mut i = 0;
+                    while i < value.len() {
+                        let len = value[i] as usize;
+                        let id = value.get(i + 1..i + 1 + len).ok_or(TRUNCATED)?;
+                        record.alpn.push(String::from_utf8_lossy(id).into_owned());
+                        i += 1 + len;
+                    }
+                }
+                KEY_NO_DEFAULT_ALPN => record.no_default_alpn = true,
+                KEY_PORT if len == 2 => {
+                    record.port = Some(u16::from_be_bytes([value[0], value[1]]))
+                }
+                KEY_IPV4HINT => {
+                    for ip in value.chunks_exact(4) {
+                        record
+                            .ipv4_hints
+                            .push(Ipv4Addr::new(ip[0], ip[1], ip[2], ip[3]));
+                    }
+                }
+                KEY_ECH => record.ech_config = Some(value.to_vec()),
+                KEY_IPV6HINT => {
+                    for ip in value.chunks_exact(16) {
+                        let mut octets = [0; 16];
+                        octets.copy_from_slice(ip);
+                        record.ipv6_hints.push(Ipv6Addr::from(octets));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Ok(record)
+    }
+
+    /// Picks the record to use among the answers to an HTTPS query: the
+    /// alias if there is one, otherwise the service with the lowest priority.
+    pub(crate) fn select(answers: Answers) -> Option<(HttpsRecord, Duration)> {
+        let ttl = Duration::from_secs(answers.ttl.unwrap_or(0).into());
+        answers
+            .https
+            .into_iter()
+            .min_by_key(|record| record.priority)
+            .map(|record| (record, ttl))
+    }
+}
+
+/// Turns the answers for a name, and its HTTPS record if any, into the
+/// addresses to connect to and their TTL.
+///
+/// The record's hints stand in for missing addresses, and its port replaces
+/// the default one. The record is kept in `records` for the TLS handshake.
+pub(crate) fn into_addrs(
+    protocol: &str,
+    name: &str,
+    answers: Result<Answers, BoxError>,
+    record: Option<(HttpsRecord, Duration)>,
+    records: Option<&HttpsRecords>,
+) -> Result<(Vec<SocketAddr>, Option<Duration>), BoxError> {
+    let (mut addrs, ttl) = match (answers, &record) {
+        (Ok(answers), _) if !answers.addrs.is_empty() => {
+            let addrs = answers
+                .addrs
+                .into_iter()
+                .map(|ip| SocketAddr::new(ip, 0))
+                .collect::<Vec<_>>();
+            let ttl = answers.ttl.map(|secs| Duration::from_secs(secs.into()));
+            (addrs, ttl)
+        }
+        (_, Some((record, ttl))) if record.hints().next().is_some() => {
+            (record.hints().collect(), Some(*ttl))
+        }
+        (Err(err), _) => return Err(err),
+        (Ok(_), _) => return Err(format!("{} found no addresses for {}", protocol, name).into()),
+    };
+
+    if let Some((record, record_ttl)) = record {
+        if let Some(port) = record.port() {
+            for addr in &mut addrs {
+                addr.set_port(port);
+            }
+        }
+        if let Some(records) = records {
+            records.insert(name, record, record_ttl);
+        }
+    }
+
+    Ok((addrs, ttl))
+}
+
+/// The HTTPS records found while resolving, by host name.
+///
+/// Filled by the resolver, and read when connecting to use the Encrypted
+/// Client Hello configuration of a host.
+pub(crate) struct HttpsRecords {
+    entries: Mutex<LruCache<String, (Arc<HttpsRecord>, Instant)>>,
+}
+
+impl HttpsRecords {
+    pub(crate) fn new() -> HttpsRecords {
+        HttpsRecords {
+            entries: Mutex::new(LruCache::new(NonZeroUsize::new(1024).expect("non-zero"))),
+        }
+    }
+
+    pub(crate) fn insert(&self, host: &str, record: HttpsRecord, ttl: Duration) {
+        let expires = Instant::now() + ttl;
+        self.entries
+            .lock()
+            .put(host.to_ascii_lowercase(), (Arc::new(record), expires));
+    }
+
+    pub(crate) fn get(&self, host: &str) -> Option<Arc<HttpsRecord>> {
+        let host = host.to_ascii_lowercase();
+        let mut entries = self.entries.lock();
+        match entries.get(&host) {
+            Some((record, expires)) if *expires > Instant::now() => Some(record.clone()),
+            Some(_) => {
+                entries.pop(&host);
+                None
+            }
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_record() {
+        let mut rdata = vec![0, 1];
+        // target "svc.example"
+        rdata.extend_from_slice(b"\x03svc\x07example\x00");
+        // alpn h3, h2
+        rdata.extend_from_slice(&[0, 1, 0, 6, 2, b'h', b'3', 2, b'h', b'2']);
+        // port 8443
+        rdata.extend_from_slice(&[0, 3, 0, 2, 0x20, 0xfb]);
+        // ipv4hint 192.0.2.1
+        rdata.extend_from_slice(&[0, 4, 0, 4, 192, 0, 2, 1]);
+        // ech
+        rdata.extend_from_slice(&[0, 5, 0, 3, 1, 2, 3]);
+
+        let record = HttpsRecord::decode(&rdata).unwrap();
+        assert_eq!(record.priority(), 1);
+        assert_eq!(record.target(), Some("svc.example"));
+        assert_eq!(record.alpn(), ["h3", "h2"]);
+        assert!(record.supports_h3());
+        assert_eq!(record.port(), Some(8443));
+        assert_eq!(record.ipv4_hints(), [Ipv4Addr::new(192, 0, 2, 1)]);
+        assert_eq!(record.ech_config(), Some(&[1, 2, 3][..]));
+
+        // same name as the owner, no parameters
+        let record = HttpsRecord::decode(&[0, 1, 0]).unwrap();
+        assert_eq!(record.target(), None);
+        assert!(!record.supports_h3());
+
+        assert!(HttpsRecord::decode(&[0, 1, 3, b'a']).is_err());
+    }
+}
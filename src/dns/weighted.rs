@@ -0,0 +1,119 @@
+//! Resolvers returning weighted addresses, such as service discovery.
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use super::{Addrs, Name, Resolve, Resolving};
+use crate::error::BoxError;
+
+/// An address and its relative weight.
+///
+/// Among the addresses of a resolution, one with weight `2` is tried first
+/// twice as often as one with weight `1`. Addresses with weight `0` are only
+/// tried after all others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WeightedAddr {
+    addr: SocketAddr,
+    weight: u32,
+}
+
+impl WeightedAddr {
+    /// Creates a new `WeightedAddr`.
+    pub fn new(addr: SocketAddr, weight: u32) -> WeightedAddr {
+        WeightedAddr { addr, weight }
+    }
+
+    /// Get the address.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Get the weight.
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+}
+
+/// Alias for the `Future` type returned by a weighted resolver.
+pub type ResolvingWeighted =
+    Pin<Box<dyn Future<Output = Result<Vec<WeightedAddr>, BoxError>> + Send>>;
+
+/// Trait for resolvers that weigh the addresses they return, such as those
+/// backed by Consul or Kubernetes service discovery.
+///
+/// The [`Name`] carries the port and address families of the connection.
+/// Returned addresses keep their port, unless it is `0` or the URL has an
+/// explicit port.
+///
+/// Set one with [`ClientBuilder::weighted_dns_resolver`].
+///
+/// [`ClientBuilder::weighted_dns_resolver`]: crate::ClientBuilder::weighted_dns_resolver
+pub trait ResolveWeighted: Send + Sync {
+    /// Resolves a `Name` into weighted addresses.
+    fn resolve_weighted(&self, name: Name) -> ResolvingWeighted;
+}
+
+/// Adapts a [`ResolveWeighted`] into a [`Resolve`], ordering the addresses
+/// by a weighted shuffle.
+pub(crate) struct WeightedResolver(pub(crate) Arc<dyn ResolveWeighted>);
+
+impl Resolve for WeightedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolving = self.0.resolve_weighted(name);
+        Box::pin(async move {
+            let addrs: Addrs = Box::new(shuffle(resolving.await?).into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Orders addresses by weighted random sampling without replacement,
+/// following Efraimidis and Spirakis.
+fn shuffle(addrs: Vec<WeightedAddr>) -> Vec<SocketAddr> {
+    let mut keyed: Vec<(f64, SocketAddr)> = addrs
+        .into_iter()
+        .map(|weighted| {
+            let key = match weighted.weight {
+                0 => 0.0,
+                weight => {
+                    // uniform in (0, 1]
+                    let u = ((crate::util::fast_random() >> 11) + 1) as f64 / (1u64 << 53) as f64;
+                    u.powf(1.0 / weight as f64)
+                }
+            };
+            (key, weighted.addr)
+        })
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    keyed.into_iter().map(|(_, addr)| addr).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shuffle_by_weight() {
+        let heavy = SocketAddr::from(([10, 0, 0, 1], 80));
+        let light = SocketAddr::from(([10, 0, 0, 2], 80));
+        let never = SocketAddr::from(([10, 0, 0, 3], 80));
+        let addrs = vec![
+            WeightedAddr::new(never, 0),
+            WeightedAddr::new(light, 1),
+            WeightedAddr::new(heavy, 9),
+        ];
+
+        let mut heavy_first = 0;
+        for _ in 0..1000 {
+            let shuffled = shuffle(addrs.clone());
+            assert_eq!(shuffled.len(), 3);
+            assert_eq!(shuffled[2], never);
+            if shuffled[0] == heavy {
+                heavy_first += 1;
+            }
+        }
+        // expected 900
+        assert!((800..980).contains(&heavy_first), "{}", heavy_first);
+    }
+}
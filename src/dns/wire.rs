@@ -0,0 +1,166 @@
+//! Encoding of DNS queries and decoding of their answers, in the wire
+//! format shared by DNS-over-HTTPS and DNS-over-TLS.
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::svcb::HttpsRecord;
+use crate::error::BoxError;
+
+pub(crate) const TYPE_A: u16 = 1;
+pub(crate) const TYPE_AAAA: u16 = 28;
+pub(crate) const TYPE_HTTPS: u16 = 65;
+const CLASS_IN: u16 = 1;
+
+/// Encodes a recursive query for `name`.
+pub(crate) fn encode_query(id: u16, name: &str, qtype: u16) -> Result<Vec<u8>, BoxError> {
+    let mut buf = Vec::with_capacity(18 + name.len());
+    buf.extend_from_slice(&id.to_be_bytes());
+    // flags (RD), QDCOUNT, ANCOUNT, NSCOUNT, ARCOUNT
+    buf.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(format!("invalid DNS name: {}", name).into());
+        }
+        buf.push(label.len() as u8);
+        buf.extend_from_slice(label.as_bytes());
+    }
+    buf.push(0);
+
+    buf.extend_from_slice(&qtype.to_be_bytes());
+    buf.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(buf)
+}
+
+/// Get the ID of a DNS message.
+pub(crate) fn message_id(msg: &[u8]) -> Option<u16> {
+    msg.get(..2).map(|id| u16::from_be_bytes([id[0], id[1]]))
+}
+
+/// The addresses and HTTPS records in a DNS response.
+#[derive(Debug, Default)]
+pub(crate) struct Answers {
+    pub(crate) addrs: Vec<IpAddr>,
+    pub(crate) https: Vec<HttpsRecord>,
+    /// The lowest TTL of the answer records, in seconds.
+    pub(crate) ttl: Option<u32>,
+}
+
+impl Answers {
+    /// Adds the answers of another response, such as the AAAA answers
+    /// to the same name.
+    pub(crate) fn extend(&mut self, other: Answers) {
+        self.addrs.extend(other.addrs);
+        self.https.extend(other.https);
+        self.ttl = match (self.ttl, other.ttl) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+}
+
+/// Decodes the A, AAAA and HTTPS records in the answer section of a DNS
+/// response.
+pub(crate) fn decode_answers(msg: &[u8]) -> Result<Answers, BoxError> {
+    let header = msg.get(..12).ok_or("truncated DNS response")?;
+    let rcode = header[3] & 0x0f;
+    if rcode != 0 {
+        return Err(format!("DNS response code {}", rcode).into());
+    }
+
+    let qdcount = u16::from_be_bytes([header[4], header[5]]);
+    let ancount = u16::from_be_bytes([header[6], header[7]]);
+
+    let mut pos = 12;
+    for _ in 0..qdcount {
+        pos = skip_name(msg, pos)? + 4;
+    }
+
+    let mut answers = Answers::default();
+    for _ in 0..ancount {
+        pos = skip_name(msg, pos)?;
+        let fixed = msg.get(pos..pos + 10).ok_or("truncated DNS response")?;
+        let rtype = u16::from_be_bytes([fixed[0], fixed[1]]);
+        let class = u16::from_be_bytes([fixed[2], fixed[3]]);
+        let ttl = u32::from_be_bytes([fixed[4], fixed[5], fixed[6], fixed[7]]);
+        let rdlength = u16::from_be_bytes([fixed[8], fixed[9]]) as usize;
+        pos += 10;
+
+        let rdata = msg
+            .get(pos..pos + rdlength)
+            .ok_or("truncated DNS response")?;
+        pos += rdlength;
+
+        if class != CLASS_IN {
+            continue;
+        }
+        // the chain is only as fresh as its shortest-lived link
+        answers.ttl = Some(answers.ttl.map_or(ttl, |min| min.min(ttl)));
+        match (rtype, rdata.len()) {
+            (TYPE_A, 4) => answers.addrs.push(IpAddr::V4(Ipv4Addr::new(
+                rdata[0], rdata[1], rdata[2], rdata[3],
+            ))),
+            (TYPE_AAAA, 16) => {
+                let mut octets = [0; 16];
+                octets.copy_from_slice(rdata);
+                answers.addrs.push(IpAddr::V6(Ipv6Addr::from(octets)));
+            }
+            (TYPE_HTTPS, _) => answers.https.push(HttpsRecord::decode(rdata)?),
+            _ => (),
+        }
+    }
+
+    Ok(answers)
+}
+
+/// Returns the position right after the (possibly compressed) name at `pos`.
+fn skip_name(msg: &[u8], mut pos: usize) -> Result<usize, BoxError> {
+    loop {
+        let len = *msg.get(pos).ok_or("truncated DNS response")?;
+        match len {
+            0 => return Ok(pos + 1),
+            // a pointer ends the name
+            len if len & 0xc0 == 0xc0 => return Ok(pos + 2),
+            len => pos += 1 + len as usize,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode() {
+        let query = encode_query(0, "example.com.", TYPE_AAAA).unwrap();
+        assert_eq!(
+            &query[..],
+            b"\0\0\x01\0\0\x01\0\0\0\0\0\0\x07example\x03com\0\0\x1c\0\x01"
+        );
+        assert!(encode_query(0, "example..com", TYPE_A).is_err());
+
+        let query = encode_query(0x1234, "example.com", TYPE_A).unwrap();
+        assert_eq!(message_id(&query), Some(0x1234));
+    }
+
+    #[test]
+    fn decode() {
+        let mut msg = encode_query(0, "example.com", TYPE_A).unwrap();
+        // QR, RA, ANCOUNT = 2
+        msg[2] = 0x81;
+        msg[3] = 0x80;
+        msg[7] = 2;
+        // CNAME example.com -> www.example.com, pointing into the question
+        msg.extend_from_slice(&[0xc0, 12, 0, 5, 0, 1, 0, 0, 0, 60, 0, 6]);
+        msg.extend_from_slice(b"\x03www\xc0\x0c");
+        // A www.example.com -> 93.184.215.14
+        msg.extend_from_slice(&[0xc0, 41, 0, 1, 0, 1, 0, 0, 0, 30, 0, 4, 93, 184, 215, 14]);
+
+        let answers = decode_answers(&msg).unwrap();
+        assert_eq!(answers.addrs, [IpAddr::V4(Ipv4Addr::new(93, 184, 215, 14))]);
+        assert_eq!(answers.ttl, Some(30));
+
+        // NXDOMAIN
+        msg[3] = 0x83;
+        assert!(decode_answers(&msg).is_err());
+    }
+}
@@ -1,7 +1,9 @@
 use std::error::Error as StdError;
 use std::fmt;
 use std::io;
+use std::net::SocketAddr;
 
+use crate::util::client::connect::ConnectError;
 use crate::{StatusCode, Url};
 
 /// A `Result` alias where the `Err` case is `rquest::Error`.
@@ -18,6 +20,49 @@ pub struct Error {
 
 pub(crate) type BoxError = Box<dyn StdError + Send + Sync>;
 
+/// What an [`Error`] was caused by, as returned by [`Error::kind`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Building the client or the request failed.
+    Builder,
+    /// The host of the URL could not be resolved.
+    Dns,
+    /// The TCP connection could not be opened.
+    Connect,
+    /// The TLS handshake failed.
+    Tls {
+        /// The TLS alert the server sent, such as `40` for
+        /// `handshake_failure`.
+        alert: Option<u8>,
+        /// The BoringSSL `X509_V_ERR_*` code the certificate verification
+        /// failed with, such as `10` for `X509_V_ERR_CERT_HAS_EXPIRED`.
+        verify_result: Option<i32>,
+    },
+    /// A timeout elapsed.
+    Timeout,
+    /// The request was canceled with an [`AbortHandle`](crate::AbortHandle).
+    Canceled,
+    /// The server reset the HTTP/2 stream, or closed the connection with a
+    /// `GOAWAY` frame, with the given error code.
+    H2Reset {
+        /// The HTTP/2 error code, such as `0x7` for `REFUSED_STREAM`.
+        reason: u32,
+    },
+    /// Following a redirect failed.
+    Redirect,
+    /// The response had an error status, from `Response::error_for_status`.
+    Status(StatusCode),
+    /// Sending the request body or reading the response body failed.
+    Body,
+    /// Decoding the response body failed.
+    Decode,
+    /// Upgrading the connection failed.
+    Upgrade,
+    /// Sending the request failed for another reason.
+    Request,
+}
+
 struct Inner {
     kind: Kind,
     source: Option<BoxError>,
@@ -115,6 +160,21 @@ impl Error {
         false
     }
 
+    /// Returns true if the error is from a request canceled with an
+    /// [`AbortHandle`](crate::AbortHandle).
+    pub fn is_canceled(&self) -> bool {
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if err.is::<Canceled>() {
+                return true;
+            }
+            source = err.source();
+        }
+
+        false
+    }
+
     /// Returns true if the error is related to the request
     pub fn is_request(&self) -> bool {
         matches!(self.inner.kind, Kind::Request)
@@ -142,11 +202,189 @@ impl Error {
         matches!(self.inner.kind, Kind::Body)
     }
 
+    /// Returns true if the response body was larger than the limit set with
+    /// `max_response_size`.
+    pub fn is_body_too_large(&self) -> bool {
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if err.is::<BodyTooLarge>() {
+                return true;
+            }
+            source = err.source();
+        }
+
+        false
+    }
+
+    /// Returns true if a compressed response body expanded more than the
+    /// ratio set with `max_decompression_ratio`.
+    pub fn is_decompression_limit(&self) -> bool {
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if err.is::<DecompressionLimit>() {
+                return true;
+            }
+            source = err.source();
+        }
+
+        false
+    }
+
+    /// Returns the errors a GraphQL response reported, if the error comes
+    /// from `Response::graphql_data`.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn graphql_errors(&self) -> Option<&[crate::GraphQLError]> {
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if let Some(errors) = err.downcast_ref::<crate::client::graphql::GraphQLErrors>() {
+                return Some(&errors.0);
+            }
+            source = err.source();
+        }
+
+        None
+    }
+
     /// Returns true if the error is related to decoding the response's body
     pub fn is_decode(&self) -> bool {
         matches!(self.inner.kind, Kind::Decode)
     }
 
+    /// Returns true if the error is related to upgrading the connection
+    pub fn is_upgrade(&self) -> bool {
+        matches!(self.inner.kind, Kind::Upgrade)
+    }
+
+    /// Returns what the error was caused by.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// use rquest::ErrorKind;
+    ///
+    /// if let Err(e) = rquest::get("https://expired.badssl.com").await {
+    ///     if let ErrorKind::Tls {
+    ///         verify_result: Some(code),
+    ///         ..
+    ///     } = e.kind()
+    ///     {
+    ///         println!("certificate verification failed: {}", code);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self.inner.kind {
+            Kind::Builder => return ErrorKind::Builder,
+            Kind::Redirect => return ErrorKind::Redirect,
+            Kind::Status(code) => return ErrorKind::Status(code),
+            Kind::Decode => return ErrorKind::Decode,
+            Kind::Upgrade => return ErrorKind::Upgrade,
+            Kind::Request | Kind::Body => {}
+        }
+
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if err.is::<Canceled>() {
+                return ErrorKind::Canceled;
+            }
+            if err.is::<TimedOut>() {
+                return ErrorKind::Timeout;
+            }
+            if let Some(connect) = err.downcast_ref::<ConnectError>() {
+                return if connect.is_dns() {
+                    ErrorKind::Dns
+                } else {
+                    ErrorKind::Connect
+                };
+            }
+            if let Some(tls) = err.downcast_ref::<TlsHandshake>() {
+                return ErrorKind::Tls {
+                    alert: tls_alert(&*tls.source),
+                    verify_result: tls.verify_result,
+                };
+            }
+            if let Some(h2) = err.downcast_ref::<h2::Error>() {
+                if h2.is_reset() || h2.is_go_away() {
+                    if let Some(reason) = h2.reason() {
+                        return ErrorKind::H2Reset {
+                            reason: reason.into(),
+                        };
+                    }
+                }
+            }
+            source = err.source();
+        }
+
+        match self.inner.kind {
+            Kind::Body => ErrorKind::Body,
+            _ => ErrorKind::Request,
+        }
+    }
+
+    /// Returns the remote address a connection was attempted to, if the
+    /// error happened while connecting.
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        let mut source = self.source();
+
+        while let Some(err) = source {
+            if let Some(connect) = err.downcast_ref::<ConnectError>() {
+                return connect.remote_addr();
+            }
+            if let Some(tls) = err.downcast_ref::<TlsHandshake>() {
+                return tls.remote_addr;
+            }
+            source = err.source();
+        }
+
+        None
+    }
+
+    /// Returns true if sending the request again may succeed.
+    ///
+    /// This is the case for failures to connect or timeouts, for TLS
+    /// handshakes interrupted by an I/O error,
+    /// for HTTP/2 streams refused or closed gracefully by the server, and
+    /// for the `408`, `429`, `502`, `503` and `504` statuses.
+    ///
+    /// Whether the request is idempotent is not considered, it's up to the
+    /// caller to only retry the requests that are safe to send twice.
+    pub fn is_retryable(&self) -> bool {
+        match self.kind() {
+            ErrorKind::Connect | ErrorKind::Timeout => true,
+            ErrorKind::Dns => self.is_timeout(),
+            ErrorKind::Tls { .. } => {
+                let mut source = self.source();
+                while let Some(err) = source {
+                    if err.is::<io::Error>() {
+                        return true;
+                    }
+                    source = err.source();
+                }
+                false
+            }
+            ErrorKind::H2Reset { reason } => {
+                reason == u32::from(h2::Reason::NO_ERROR)
+                    || reason == u32::from(h2::Reason::REFUSED_STREAM)
+            }
+            ErrorKind::Status(code) => matches!(
+                code,
+                StatusCode::REQUEST_TIMEOUT
+                    | StatusCode::TOO_MANY_REQUESTS
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+            _ => false,
+        }
+    }
+
     /// Returns the status code, if the error was generated from a response.
     pub fn status(&self) -> Option<StatusCode> {
         match self.inner.kind {
@@ -359,6 +597,114 @@ impl fmt::Display for TimedOut {
 
 impl StdError for TimedOut {}
 
+#[derive(Debug)]
+pub(crate) struct Canceled;
+
+impl fmt::Display for Canceled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("request canceled")
+    }
+}
+
+impl StdError for Canceled {}
+
+/// A failed TLS handshake, with what BoringSSL reported about it.
+#[derive(Debug)]
+pub(crate) struct TlsHandshake {
+    pub(crate) remote_addr: Option<SocketAddr>,
+    pub(crate) verify_result: Option<i32>,
+    pub(crate) source: BoxError,
+}
+
+impl fmt::Display for TlsHandshake {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.remote_addr {
+            Some(addr) => write!(f, "tls handshake with {} failed", addr),
+            None => f.write_str("tls handshake failed"),
+        }
+    }
+}
+
+impl StdError for TlsHandshake {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+/// The `ERR_LIB_SSL` library of BoringSSL errors.
+const ERR_LIB_SSL: i32 = 16;
+
+/// BoringSSL reports the alerts received as `ERR_LIB_SSL` reasons offset by
+/// `SSL_AD_REASON_OFFSET`.
+const SSL_AD_REASON_OFFSET: i32 = 1000;
+
+/// The TLS alert a handshake failed with, found in the BoringSSL errors
+/// caused by `err`.
+fn tls_alert(err: &(dyn StdError + 'static)) -> Option<u8> {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if let Some(stack) = err.downcast_ref::<boring2::error::ErrorStack>() {
+            return stack
+                .errors()
+                .iter()
+                .filter(|err| err.library_code() == ERR_LIB_SSL)
+                .find_map(|err| u8::try_from(err.reason_code() - SSL_AD_REASON_OFFSET).ok());
+        }
+        source = err.source();
+    }
+    None
+}
+
+#[derive(Debug)]
+pub(crate) struct ProxyAuthRequired;
+
+impl fmt::Display for ProxyAuthRequired {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("proxy authentication required")
+    }
+}
+
+impl StdError for ProxyAuthRequired {}
+
+/// Whether `err` was caused by a proxy refusing to open a tunnel without
+/// credentials.
+pub(crate) fn is_proxy_auth_required(err: &(dyn StdError + 'static)) -> bool {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if err.is::<ProxyAuthRequired>() {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+#[derive(Debug)]
+pub(crate) struct BodyTooLarge(pub(crate) u64);
+
+impl fmt::Display for BodyTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "response body is larger than {} bytes", self.0)
+    }
+}
+
+impl StdError for BodyTooLarge {}
+
+#[derive(Debug)]
+pub(crate) struct DecompressionLimit(pub(crate) u32);
+
+impl fmt::Display for DecompressionLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "response body expanded more than {} times while decompressing",
+            self.0
+        )
+    }
+}
+
+impl StdError for DecompressionLimit {}
+
 #[derive(Debug)]
 pub(crate) struct BadScheme;
 
@@ -418,6 +764,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn kind_and_retryable() {
+        let err = super::request(super::TimedOut);
+        assert_eq!(err.kind(), ErrorKind::Timeout);
+        assert!(err.is_retryable());
+
+        let err = super::body(super::Canceled);
+        assert_eq!(err.kind(), ErrorKind::Canceled);
+        assert!(!err.is_retryable());
+
+        let err = super::body("eof");
+        assert_eq!(err.kind(), ErrorKind::Body);
+        assert!(!err.is_retryable());
+
+        let url = Url::parse("http://example.com").unwrap();
+        let err = super::status_code(url.clone(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            err.kind(),
+            ErrorKind::Status(StatusCode::SERVICE_UNAVAILABLE)
+        );
+        assert!(err.is_retryable());
+        assert!(!super::status_code(url, StatusCode::NOT_FOUND).is_retryable());
+    }
+
+    #[test]
+    fn tls_handshake_kind() {
+        let addr = SocketAddr::from(([127, 0, 0, 1], 443));
+        let err = super::request(TlsHandshake {
+            remote_addr: Some(addr),
+            verify_result: Some(10),
+            source: "certificate has expired".into(),
+        });
+        assert_eq!(
+            err.kind(),
+            ErrorKind::Tls {
+                alert: None,
+                verify_result: Some(10),
+            }
+        );
+        assert_eq!(err.remote_addr(), Some(addr));
+        assert!(!err.is_retryable());
+
+        let reset = io::Error::from(io::ErrorKind::ConnectionReset);
+        let err = super::request(TlsHandshake {
+            remote_addr: None,
+            verify_result: None,
+            source: reset.into(),
+        });
+        assert!(err.is_retryable());
+    }
+
     #[test]
     fn is_timeout() {
         let err = super::request(super::TimedOut);
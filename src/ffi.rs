@@ -0,0 +1,351 @@
+//! A C API, for bindings in other languages.
+//!
+//! The functions block the calling thread until they are done, running the
+//! requests on a runtime shared by all the clients. To get a shared library,
+//! build the crate with the `ffi` feature as a `cdylib`:
+//!
+//! ```text
+//! cargo rustc --release --features ffi --crate-type cdylib
+//! ```
+//!
+//! The declarations for C are in `include/rquest.h`.
+//!
+//! Strings passed in are NUL-terminated UTF-8. A function failing returns
+//! `NULL`, and [`rquest_last_error`] then describes the error; a panic is
+//! caught and reported the same way, rather than unwinding into the caller.
+//! The functions must not be called from a thread running a Tokio runtime.
+//! Everything
+//! returned must be released with the matching `_free` function; the
+//! pointers into a response stay valid until the response is freed.
+//!
+//! # Optional
+//!
+//! This requires the optional `ffi` feature enabled.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::OnceLock;
+
+use bytes::Bytes;
+use http::header::{HeaderName, HeaderValue};
+use serde::de::value::{Error as DeError, StrDeserializer};
+use serde::Deserialize;
+use tokio::runtime::Runtime;
+
+use crate::{Client, Impersonate, Method};
+
+/// A client, made with [`rquest_client_new`].
+pub struct RquestClient {
+    client: Client,
+}
+
+/// A response read whole, returned by [`rquest_request`].
+pub struct RquestResponse {
+    status: u16,
+    url: CString,
+    headers: CString,
+    body: Bytes,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .thread_name("rquest-ffi")
+            .build()
+            .expect("failed to start the rquest runtime")
+    })
+}
+
+fn set_last_error(err: impl std::fmt::Display) {
+    let message = err.to_string().replace('\0', " ");
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(message).ok());
+}
+
+/// Run the body of an entry point, recording its error or panic as the last
+/// error of the thread, or clearing it on success.
+fn guard<T>(f: impl FnOnce() -> Result<T, String>) -> Option<T> {
+    let err = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = None);
+            return Some(value);
+        }
+        Ok(Err(err)) => err,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown panic");
+            format!("panicked: {message}")
+        }
+    };
+    set_last_error(err);
+    None
+}
+
+/// The string at `ptr`, or `None` if it is `NULL`.
+///
+/// # Safety
+///
+/// `ptr` must be `NULL` or point to a NUL-terminated string.
+unsafe fn string<'a>(ptr: *const c_char, what: &str) -> Result<Option<&'a str>, String> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map(Some)
+        .map_err(|_| format!("{what} is not valid UTF-8"))
+}
+
+/// The message of the error of the last function called on this thread, or
+/// `NULL` if it succeeded.
+///
+/// The string is valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn rquest_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Create a client impersonating the profile named `impersonate`, such as
+/// `"chrome_131"`, or a client without impersonation if it is `NULL`.
+///
+/// # Safety
+///
+/// `impersonate` must be `NULL` or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rquest_client_new(impersonate: *const c_char) -> *mut RquestClient {
+    let client = guard(|| {
+        let name = string(impersonate, "profile name")?;
+        let mut builder = Client::builder();
+        if let Some(name) = name {
+            let profile = Impersonate::deserialize(StrDeserializer::<DeError>::new(name))
+                .map_err(|_| format!("unknown profile `{name}`"))?;
+            builder = builder.impersonate(profile);
+        }
+        let _runtime = runtime().enter();
+        builder.build().map_err(|err| err.to_string())
+    });
+
+    match client {
+        Some(client) => Box::into_raw(Box::new(RquestClient { client })),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Free a client.
+///
+/// # Safety
+///
+/// `client` must be `NULL` or returned by [`rquest_client_new`], and not
+/// freed already.
+#[no_mangle]
+pub unsafe extern "C" fn rquest_client_free(client: *mut RquestClient) {
+    if !client.is_null() {
+        guard(|| {
+            drop(Box::from_raw(client));
+            Ok(())
+        });
+    }
+}
+
+/// Send a request, and read its response whole.
+///
+/// `headers` holds one `Name: value` header per line, or is `NULL`. `body`
+/// points to `body_len` bytes, or is `NULL` for a request without a body.
+///
+/// # Safety
+///
+/// `client` must be a live client. `method` and `url` must be NUL-terminated
+/// strings, `headers` `NULL` or one, and `body` `NULL` or valid for reads of
+/// `body_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rquest_request(
+    client: *const RquestClient,
+    method: *const c_char,
+    url: *const c_char,
+    headers: *const c_char,
+    body: *const u8,
+    body_len: usize,
+) -> *mut RquestResponse {
+    let res = guard(|| request(client, method, url, headers, body, body_len));
+    match res {
+        Some(res) => Box::into_raw(Box::new(res)),
+        None => ptr::null_mut(),
+    }
+}
+
+unsafe fn request(
+    client: *const RquestClient,
+    method: *const c_char,
+    url: *const c_char,
+    headers: *const c_char,
+    body: *const u8,
+    body_len: usize,
+) -> Result<RquestResponse, String> {
+    let client = client.as_ref().ok_or("client is NULL")?;
+    let method = string(method, "method")?.ok_or("method is NULL")?;
+    let method = Method::from_bytes(method.as_bytes()).map_err(|err| err.to_string())?;
+    let url = string(url, "url")?.ok_or("url is NULL")?;
+
+    let mut req = client.client.request(method, url);
+    for line in string(headers, "headers")?.unwrap_or_default().lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| format!("header line without `:`: {line}"))?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|e| e.to_string())?;
+        let value = HeaderValue::from_str(value.trim()).map_err(|e| e.to_string())?;
+        req = req.header(name, value);
+    }
+    if !body.is_null() {
+        req = req.body(std::slice::from_raw_parts(body, body_len).to_vec());
+    }
+
+    // blocking on the runtime would panic there
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return Err("called from within a Tokio runtime".to_owned());
+    }
+    runtime().block_on(async move {
+        let res = req.send().await.map_err(|err| err.to_string())?;
+        let status = res.status().as_u16();
+        let url = CString::new(res.url().as_str()).map_err(|err| err.to_string())?;
+        let mut lines = String::new();
+        for (name, value) in res.headers() {
+            lines.push_str(name.as_str());
+            lines.push_str(": ");
+            lines.push_str(&String::from_utf8_lossy(value.as_bytes()));
+            lines.push('\n');
+        }
+        let headers = CString::new(lines).map_err(|err| err.to_string())?;
+        let body = res.bytes().await.map_err(|err| err.to_string())?;
+        Ok(RquestResponse {
+            status,
+            url,
+            headers,
+            body,
+        })
+    })
+}
+
+/// The status code of a response.
+///
+/// # Safety
+///
+/// `res` must be a live response.
+#[no_mangle]
+pub unsafe extern "C" fn rquest_response_status(res: *const RquestResponse) -> u16 {
+    (*res).status
+}
+
+/// The final URL of a response, after redirects.
+///
+/// # Safety
+///
+/// `res` must be a live response.
+#[no_mangle]
+pub unsafe extern "C" fn rquest_response_url(res: *const RquestResponse) -> *const c_char {
+    (*res).url.as_ptr()
+}
+
+/// The headers of a response, one `Name: value` header per line.
+///
+/// # Safety
+///
+/// `res` must be a live response.
+#[no_mangle]
+pub unsafe extern "C" fn rquest_response_headers(res: *const RquestResponse) -> *const c_char {
+    (*res).headers.as_ptr()
+}
+
+/// The body of a response, decompressed, of `*len` bytes.
+///
+/// # Safety
+///
+/// `res` must be a live response, and `len` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rquest_response_body(
+    res: *const RquestResponse,
+    len: *mut usize,
+) -> *const u8 {
+    let body = &(*res).body;
+    *len = body.len();
+    body.as_ptr()
+}
+
+/// Free a response, and the strings and body it returned.
+///
+/// # Safety
+///
+/// `res` must be `NULL` or returned by [`rquest_request`], and not freed
+/// already.
+#[no_mangle]
+pub unsafe extern "C" fn rquest_response_free(res: *mut RquestResponse) {
+    if !res.is_null() {
+        guard(|| {
+            drop(Box::from_raw(res));
+            Ok(())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_profile() {
+        let name = CString::new("netscape_4").unwrap();
+        let client = unsafe { rquest_client_new(name.as_ptr()) };
+        assert!(client.is_null());
+        let err = unsafe { CStr::from_ptr(rquest_last_error()) };
+        assert_eq!(err.to_str().unwrap(), "unknown profile `netscape_4`");
+
+        let client = unsafe { rquest_client_new(ptr::null()) };
+        assert!(!client.is_null());
+        assert!(rquest_last_error().is_null());
+        unsafe { rquest_client_free(client) };
+    }
+
+    #[test]
+    fn panics_are_errors() {
+        assert_eq!(guard::<()>(|| panic!("boom")), None);
+        let err = unsafe { CStr::from_ptr(rquest_last_error()) };
+        assert_eq!(err.to_str().unwrap(), "panicked: boom");
+    }
+
+    #[tokio::test]
+    async fn request_within_runtime() {
+        let client = unsafe { rquest_client_new(ptr::null()) };
+        let method = CString::new("GET").unwrap();
+        let url = CString::new("http://127.0.0.1/").unwrap();
+        let res = unsafe {
+            rquest_request(
+                client,
+                method.as_ptr(),
+                url.as_ptr(),
+                ptr::null(),
+                ptr::null(),
+                0,
+            )
+        };
+        assert!(res.is_null());
+        let err = unsafe { CStr::from_ptr(rquest_last_error()) };
+        assert_eq!(err.to_str().unwrap(), "called from within a Tokio runtime");
+        unsafe { rquest_client_free(client) };
+    }
+}
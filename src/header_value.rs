@@ -0,0 +1,94 @@
+use http::HeaderValue;
+
+/// Extension trait for header values that are not UTF-8 text.
+///
+/// HTTP header values are bytes: servers may send ISO-8859-1 (latin-1)
+/// text or opaque tokens that [`HeaderValue::to_str`] rejects. A value
+/// received this way is kept as is, so cloning it into a request echoes
+/// it back byte for byte; this trait reads and builds such values as text.
+///
+/// This trait is "sealed", such that only types within rquest can
+/// implement it.
+///
+/// # Example
+///
+/// ```
+/// use rquest::header::HeaderValue;
+/// use rquest::HeaderValueExt;
+///
+/// # fn run() -> Result<(), rquest::Error> {
+/// let value = HeaderValue::from_latin1("attachment; filename=\"café.txt\"")?;
+/// assert_eq!(value.as_bytes().len(), 31);
+/// assert!(value.to_str().is_err());
+/// assert_eq!(value.to_latin1(), "attachment; filename=\"café.txt\"");
+/// # Ok(())
+/// # }
+/// ```
+pub trait HeaderValueExt: sealed::Sealed {
+    /// Encode `text` in ISO-8859-1, one byte per character.
+    ///
+    /// # Errors
+    ///
+    /// This fails if `text` has a character above U+00FF, or a control
+    /// character other than a tab.
+    fn from_latin1(text: &str) -> crate::Result<HeaderValue>;
+
+    /// Decode the value as ISO-8859-1, where each byte is a character, so
+    /// it never fails.
+    fn to_latin1(&self) -> String;
+
+    /// Decode the value as UTF-8 if it is valid, or else as ISO-8859-1.
+    fn to_text(&self) -> String;
+}
+
+impl HeaderValueExt for HeaderValue {
+    fn from_latin1(text: &str) -> crate::Result<HeaderValue> {
+        let bytes = text
+            .chars()
+            .map(|c| u8::try_from(c).ok())
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| crate::error::builder("header value is not ISO-8859-1 text"))?;
+        HeaderValue::from_bytes(&bytes).map_err(crate::error::builder)
+    }
+
+    fn to_latin1(&self) -> String {
+        self.as_bytes().iter().map(|&b| char::from(b)).collect()
+    }
+
+    fn to_text(&self) -> String {
+        match std::str::from_utf8(self.as_bytes()) {
+            Ok(text) => text.to_owned(),
+            Err(_) => self.to_latin1(),
+        }
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for http::HeaderValue {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latin1_round_trips_every_byte() {
+        let bytes: Vec<u8> = (0x20..=0xff).filter(|&b| b != 0x7f).collect();
+        let value = HeaderValue::from_bytes(&bytes).unwrap();
+        let text = value.to_latin1();
+        assert_eq!(HeaderValue::from_latin1(&text).unwrap(), value);
+    }
+
+    #[test]
+    fn text_prefers_utf8() {
+        let utf8 = HeaderValue::from_bytes("café".as_bytes()).unwrap();
+        assert_eq!(utf8.to_text(), "café");
+        let latin1 = HeaderValue::from_bytes(b"caf\xe9").unwrap();
+        assert_eq!(latin1.to_text(), "café");
+
+        assert!(HeaderValue::from_latin1("€").is_err());
+        assert!(HeaderValue::from_latin1("a\nb").is_err());
+    }
+}
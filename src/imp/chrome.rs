@@ -1,3 +1,10 @@
+// Edge profiles share the settings of Chrome, so one family may leave some
+// of them unused.
+#![cfg_attr(
+    not(all(feature = "impersonate-chrome", feature = "impersonate-edge")),
+    allow(dead_code, unused_macros)
+)]
+
 use crate::imp::impersonate_imports::*;
 use http2::*;
 use tls::*;
@@ -28,6 +35,7 @@ macro_rules! mod_generator {
                                     ImpersonateOS::$other_os,
                                 )
                             }))
+                            .multipart_style(MultipartStyle::WebKit)
                             .build(),
                     )*
                     _ => HttpContext::builder()
@@ -40,6 +48,7 @@ macro_rules! mod_generator {
                                 ImpersonateOS::$default_os,
                             )
                         }))
+                        .multipart_style(MultipartStyle::WebKit)
                         .build(),
                 }
             }
@@ -295,6 +304,7 @@ mod http2 {
     ];
 }
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v100,
     tls_config!(1),
@@ -324,6 +334,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v101,
     tls_config!(1),
@@ -353,6 +364,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v104,
     tls_config!(1),
@@ -382,6 +394,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v105,
     tls_config!(2),
@@ -411,6 +424,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v106,
     tls_config!(3),
@@ -440,6 +454,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v107,
     tls_config!(3),
@@ -469,6 +484,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v108,
     tls_config!(3),
@@ -498,6 +514,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v109,
     tls_config!(3),
@@ -527,6 +544,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v114,
     tls_config!(3),
@@ -552,6 +570,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v116,
     tls_config!(4),
@@ -581,6 +600,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v117,
     tls_config!(5),
@@ -610,6 +630,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v118,
     tls_config!(4),
@@ -639,6 +660,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v119,
     tls_config!(4),
@@ -668,6 +690,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v120,
     tls_config!(5),
@@ -697,6 +720,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v123,
     tls_config!(5),
@@ -722,6 +746,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v124,
     tls_config!(6, CURVES_2),
@@ -751,6 +776,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v126,
     tls_config!(6, CURVES_2),
@@ -780,6 +806,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v127,
     tls_config!(6, CURVES_2),
@@ -809,6 +836,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v128,
     tls_config!(6, CURVES_2),
@@ -837,6 +865,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v129,
     tls_config!(6, CURVES_2),
@@ -866,6 +895,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v130,
     tls_config!(6, CURVES_2),
@@ -895,6 +925,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-chrome")]
 mod_generator!(
     v131,
     tls_config!(6, CURVES_3),
@@ -924,6 +955,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-edge")]
 mod_generator!(
     edge101,
     tls_config!(1),
@@ -954,6 +986,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-edge")]
 mod_generator!(
     edge122,
     tls_config!(5),
@@ -983,6 +1016,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-edge")]
 mod_generator!(
     edge127,
     tls_config!(6, CURVES_2),
@@ -1013,6 +1047,7 @@ mod_generator!(
     ]
 );
 
+#[cfg(feature = "impersonate-edge")]
 mod_generator!(
     edge131,
     tls_config!(6, CURVES_3),
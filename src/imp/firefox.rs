@@ -23,6 +23,7 @@ macro_rules! mod_generator {
                                 .tls_config($tls_config)
                                 .http2_config(conditional_http2!(option.skip_http2, $http2_config))
                                 .default_headers(conditional_headers!(option.skip_headers, $header_initializer, $other_ua))
+                                .multipart_style(MultipartStyle::Gecko)
                                 .build()
                         }
                     ),*
@@ -31,6 +32,7 @@ macro_rules! mod_generator {
                             .tls_config($tls_config)
                             .http2_config(conditional_http2!(option.skip_http2, $http2_config))
                             .default_headers(conditional_headers!(option.skip_headers, $header_initializer, $default_ua))
+                            .multipart_style(MultipartStyle::Gecko)
                             .build()
                     }
                 }
@@ -147,11 +147,12 @@ macro_rules! join {
 }
 
 macro_rules! impersonate_match {
-    ($ver:expr, $opt:expr, $($variant:pat => $path:expr),+) => {
+    ($ver:expr, $opt:expr, $($(#[$meta:meta])* $variant:pat => $path:expr),* $(,)?) => {
         match $ver {
             $(
+                $(#[$meta])*
                 $variant => $path($opt),
-            )+
+            )*
         }
     }
 }
@@ -4,22 +4,30 @@
 
 #[macro_use]
 mod macros;
+#[cfg(any(feature = "impersonate-chrome", feature = "impersonate-edge"))]
 mod chrome;
+#[cfg(feature = "impersonate-firefox")]
 mod firefox;
+#[cfg(feature = "impersonate-okhttp")]
 mod okhttp;
+#[cfg(feature = "impersonate-safari")]
 mod safari;
 
 use crate::{HttpContext, HttpContextProvider};
 use serde::{Deserialize, Serialize};
 use typed_builder::TypedBuilder;
 
+#[cfg(any(feature = "impersonate-chrome", feature = "impersonate-edge"))]
 use chrome::*;
+#[cfg(feature = "impersonate-firefox")]
 use firefox::*;
+#[cfg(feature = "impersonate-okhttp")]
 use okhttp::*;
+#[cfg(feature = "impersonate-safari")]
 use safari::*;
 
 mod impersonate_imports {
-    pub use crate::{Http2Config, HttpContext, ImpersonateOS, ImpersonateOption};
+    pub use crate::{Http2Config, HttpContext, ImpersonateOS, ImpersonateOption, MultipartStyle};
     pub use http::{
         header::{ACCEPT, ACCEPT_LANGUAGE, UPGRADE_INSECURE_REQUESTS, USER_AGENT},
         HeaderMap, HeaderName, HeaderValue,
@@ -57,6 +65,13 @@ mod http2_imports {
 /// version number, following the pattern `browser_version`. For example, `Chrome100` is
 /// serialized as `"chrome_100"`.
 ///
+/// # Optional
+///
+/// The variants of each family are only there with its feature enabled:
+/// `impersonate-chrome`, `impersonate-edge`, `impersonate-firefox`,
+/// `impersonate-okhttp` or `impersonate-safari`. The default `impersonate`
+/// feature enables them all.
+///
 /// # Examples
 ///
 /// ```rust
@@ -69,122 +84,198 @@ mod http2_imports {
 /// let deserialized: Impersonate = serde_json::from_str(&serialized).unwrap();
 /// assert_eq!(deserialized, Impersonate::Chrome100);
 /// ```
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Impersonate {
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_100")]
     Chrome100,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_101")]
     Chrome101,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_104")]
     Chrome104,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_105")]
     Chrome105,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_106")]
     Chrome106,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_107")]
     Chrome107,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_108")]
     Chrome108,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_109")]
     Chrome109,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_114")]
     Chrome114,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_116")]
     Chrome116,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_117")]
     Chrome117,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_118")]
     Chrome118,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_119")]
     Chrome119,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_120")]
     Chrome120,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_123")]
     Chrome123,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_124")]
     Chrome124,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_126")]
     Chrome126,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_127")]
     Chrome127,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_128")]
     Chrome128,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_129")]
     Chrome129,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_130")]
     Chrome130,
+    #[cfg(feature = "impersonate-chrome")]
     #[serde(rename = "chrome_131")]
-    #[default]
     Chrome131,
 
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_ios_17.2")]
     SafariIos17_2,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_ios_17.4.1")]
     SafariIos17_4_1,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_ios_16.5")]
     SafariIos16_5,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_15.3")]
     Safari15_3,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_15.5")]
     Safari15_5,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_15.6.1")]
     Safari15_6_1,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_16")]
     Safari16,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_16.5")]
     Safari16_5,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_17.0")]
     Safari17_0,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_17.2.1")]
     Safari17_2_1,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_17.4.1")]
     Safari17_4_1,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_17.5")]
     Safari17_5,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_18")]
     Safari18,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_ipad_18")]
     SafariIPad18,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_18.2")]
     Safari18_2,
+    #[cfg(feature = "impersonate-safari")]
     #[serde(rename = "safari_ios_18.1.1")]
     SafariIos18_1_1,
 
+    #[cfg(feature = "impersonate-okhttp")]
     #[serde(rename = "okhttp_3.9")]
     OkHttp3_9,
+    #[cfg(feature = "impersonate-okhttp")]
     #[serde(rename = "okhttp_3.11")]
     OkHttp3_11,
+    #[cfg(feature = "impersonate-okhttp")]
     #[serde(rename = "okhttp_3.13")]
     OkHttp3_13,
+    #[cfg(feature = "impersonate-okhttp")]
     #[serde(rename = "okhttp_3.14")]
     OkHttp3_14,
+    #[cfg(feature = "impersonate-okhttp")]
     #[serde(rename = "okhttp_4.9")]
     OkHttp4_9,
+    #[cfg(feature = "impersonate-okhttp")]
     #[serde(rename = "okhttp_4.10")]
     OkHttp4_10,
+    #[cfg(feature = "impersonate-okhttp")]
     #[serde(rename = "okhttp_5")]
     OkHttp5,
 
+    #[cfg(feature = "impersonate-edge")]
     #[serde(rename = "edge_101")]
     Edge101,
+    #[cfg(feature = "impersonate-edge")]
     #[serde(rename = "edge_122")]
     Edge122,
+    #[cfg(feature = "impersonate-edge")]
     #[serde(rename = "edge_127")]
     Edge127,
+    #[cfg(feature = "impersonate-edge")]
     #[serde(rename = "edge_131")]
     Edge131,
 
+    #[cfg(feature = "impersonate-firefox")]
     #[serde(rename = "firefox_109")]
     Firefox109,
+    #[cfg(feature = "impersonate-firefox")]
     #[serde(rename = "firefox_117")]
     Firefox117,
+    #[cfg(feature = "impersonate-firefox")]
     #[serde(rename = "firefox_128")]
     Firefox128,
+    #[cfg(feature = "impersonate-firefox")]
     #[serde(rename = "firefox_133")]
     Firefox133,
 }
 
 /// ======== Impersonate impls ========
+#[cfg(any(
+    feature = "impersonate-chrome",
+    feature = "impersonate-edge",
+    feature = "impersonate-firefox",
+    feature = "impersonate-okhttp",
+    feature = "impersonate-safari"
+))]
+impl Default for Impersonate {
+    /// The latest Chrome, or the latest profile of the first family enabled.
+    #[allow(unreachable_code)]
+    fn default() -> Self {
+        #[cfg(feature = "impersonate-chrome")]
+        return Impersonate::Chrome131;
+        #[cfg(feature = "impersonate-edge")]
+        return Impersonate::Edge131;
+        #[cfg(feature = "impersonate-firefox")]
+        return Impersonate::Firefox133;
+        #[cfg(feature = "impersonate-safari")]
+        return Impersonate::Safari18_2;
+        #[cfg(feature = "impersonate-okhttp")]
+        return Impersonate::OkHttp5;
+    }
+}
+
 impl HttpContextProvider for Impersonate {
     fn context(self) -> HttpContext {
         ImpersonateOption::builder()
@@ -235,6 +326,7 @@ pub enum ImpersonateOS {
 }
 
 /// ======== ImpersonateOS impls ========
+#[cfg(any(feature = "impersonate-chrome", feature = "impersonate-edge"))]
 impl ImpersonateOS {
     #[inline]
     fn platform(&self) -> &'static str {
@@ -253,10 +345,39 @@ impl ImpersonateOS {
     }
 }
 
-#[derive(Default, TypedBuilder)]
+#[cfg_attr(
+    any(
+        feature = "impersonate-chrome",
+        feature = "impersonate-edge",
+        feature = "impersonate-firefox",
+        feature = "impersonate-okhttp",
+        feature = "impersonate-safari"
+    ),
+    derive(Default)
+)]
+#[derive(TypedBuilder)]
+#[cfg_attr(
+    not(any(
+        feature = "impersonate-chrome",
+        feature = "impersonate-edge",
+        feature = "impersonate-firefox",
+        feature = "impersonate-okhttp",
+        feature = "impersonate-safari"
+    )),
+    allow(dead_code)
+)]
 pub struct ImpersonateOption {
     /// The browser version to impersonate.
-    #[builder(default)]
+    #[cfg_attr(
+        any(
+            feature = "impersonate-chrome",
+            feature = "impersonate-edge",
+            feature = "impersonate-firefox",
+            feature = "impersonate-okhttp",
+            feature = "impersonate-safari"
+        ),
+        builder(default)
+    )]
     impersonate: Impersonate,
 
     /// The operating system.
@@ -279,68 +400,121 @@ impl HttpContextProvider for ImpersonateOption {
             self.impersonate,
             self,
 
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome100 => v100::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome101 => v101::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome104 => v104::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome105 => v105::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome106 => v106::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome107 => v107::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome108 => v108::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome109 => v109::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome114 => v114::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome116 => v116::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome117 => v117::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome118 => v118::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome119 => v119::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome120 => v120::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome123 => v123::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome124 => v124::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome126 => v126::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome127 => v127::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome128 => v128::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome129 => v129::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome130 => v130::http_context,
+            #[cfg(feature = "impersonate-chrome")]
             Impersonate::Chrome131 => v131::http_context,
 
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::SafariIos17_2 => safari_ios_17_2::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::SafariIos17_4_1 => safari_ios_17_4_1::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::SafariIos16_5 => safari_ios_16_5::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::Safari15_3 => safari15_3::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::Safari15_5 => safari15_5::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::Safari15_6_1 => safari15_6_1::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::Safari16 => safari16::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::Safari16_5 => safari16_5::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::Safari17_0 => safari17_0::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::Safari17_2_1 => safari17_2_1::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::Safari17_4_1 => safari17_4_1::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::Safari17_5 => safari17_5::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::Safari18 => safari18::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::SafariIPad18 => safari_ipad_18::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::Safari18_2 => safari18_2::http_context,
+            #[cfg(feature = "impersonate-safari")]
             Impersonate::SafariIos18_1_1 => safari_ios_18_1_1::http_context,
 
+            #[cfg(feature = "impersonate-okhttp")]
             Impersonate::OkHttp3_9 => okhttp3_9::http_context,
+            #[cfg(feature = "impersonate-okhttp")]
             Impersonate::OkHttp3_11 => okhttp3_11::http_context,
+            #[cfg(feature = "impersonate-okhttp")]
             Impersonate::OkHttp3_13 => okhttp3_13::http_context,
+            #[cfg(feature = "impersonate-okhttp")]
             Impersonate::OkHttp3_14 => okhttp3_14::http_context,
+            #[cfg(feature = "impersonate-okhttp")]
             Impersonate::OkHttp4_9 => okhttp4_9::http_context,
+            #[cfg(feature = "impersonate-okhttp")]
             Impersonate::OkHttp4_10 => okhttp4_10::http_context,
+            #[cfg(feature = "impersonate-okhttp")]
             Impersonate::OkHttp5 => okhttp5::http_context,
 
+            #[cfg(feature = "impersonate-edge")]
             Impersonate::Edge101 => edge101::http_context,
+            #[cfg(feature = "impersonate-edge")]
             Impersonate::Edge122 => edge122::http_context,
+            #[cfg(feature = "impersonate-edge")]
             Impersonate::Edge127 => edge127::http_context,
+            #[cfg(feature = "impersonate-edge")]
             Impersonate::Edge131 => edge131::http_context,
 
+            #[cfg(feature = "impersonate-firefox")]
             Impersonate::Firefox109 => ff109::http_context,
+            #[cfg(feature = "impersonate-firefox")]
             Impersonate::Firefox117 => ff117::http_context,
+            #[cfg(feature = "impersonate-firefox")]
             Impersonate::Firefox128 => ff128::http_context,
-            Impersonate::Firefox133 => ff133::http_context
+            #[cfg(feature = "impersonate-firefox")]
+            Impersonate::Firefox133 => ff133::http_context,
         )
     }
 }
 
-#[cfg(feature = "json")]
+#[cfg(all(feature = "json", feature = "impersonate-chrome"))]
 mod tests {
     #[test]
     fn test_impersonate_serde() {
@@ -17,6 +17,7 @@ macro_rules! mod_generator {
                         super::header_initializer,
                         $ua
                     ))
+                    .multipart_style(MultipartStyle::OkHttp)
                     .build()
             }
         }
@@ -17,6 +17,7 @@ macro_rules! mod_generator {
                         $header_initializer,
                         $ua
                     ))
+                    .multipart_style(MultipartStyle::WebKit)
                     .build()
             }
         }
@@ -93,6 +93,139 @@ pub(crate) fn try_uri(url: &Url) -> Option<http::Uri> {
     url.as_str().parse().ok()
 }
 
+/// Like [`try_uri`], but sends `path_and_query` verbatim in place of the
+/// normalized path and query of `url`.
+pub(crate) fn try_uri_with_path_and_query(
+    url: &Url,
+    path_and_query: Option<&http::uri::PathAndQuery>,
+) -> Option<http::Uri> {
+    let uri = try_uri(url)?;
+    match path_and_query {
+        Some(path_and_query) => {
+            let mut parts = uri.into_parts();
+            parts.path_and_query = Some(path_and_query.clone());
+            http::Uri::from_parts(parts).ok()
+        }
+        None => Some(uri),
+    }
+}
+
+/// Converts `url` into a `Url`, splitting off an IPv6 zone identifier.
+///
+/// `Url` cannot represent zone identifiers (RFC 6874), so the zone is removed
+/// from the host before parsing and returned as the scope id of the network
+/// interface it names.
+pub(crate) fn into_url_with_scope_id<U: IntoUrl>(url: U) -> crate::Result<(Url, Option<u32>)> {
+    let Some((stripped, zone)) = split_ipv6_zone(url.as_str()) else {
+        return url.into_url().map(|url| (url, None));
+    };
+
+    let scope_id = zone_to_scope_id(zone)
+        .ok_or_else(|| crate::error::builder(format!("invalid IPv6 zone identifier: {}", zone)))?;
+
+    stripped.into_url().map(|url| (url, Some(scope_id)))
+}
+
+/// Resolves `location` against `base`, splitting off an IPv6 zone
+/// identifier as [`into_url_with_scope_id`] does, or `None` if it is not a
+/// valid URL.
+pub(crate) fn join_with_scope_id(base: &Url, location: &str) -> Option<(Url, Option<u32>)> {
+    match split_ipv6_zone(location) {
+        Some((stripped, zone)) => Some((base.join(&stripped).ok()?, Some(zone_to_scope_id(zone)?))),
+        None => Some((base.join(location).ok()?, None)),
+    }
+}
+
+/// Removes the zone identifier from a bracketed IPv6 host, such as
+/// `http://[fe80::1%25eth0]:8080/`, returning the remaining URL and the zone.
+///
+/// Both the RFC 6874 `%25` delimiter and a bare `%` are accepted.
+fn split_ipv6_zone(url: &str) -> Option<(String, &str)> {
+    let authority_start = url.find("://")? + 3;
+    let authority_end = url[authority_start..]
+        .find(['/', '?', '#'])
+        .map_or(url.len(), |i| authority_start + i);
+    let host_start = url[authority_start..authority_end]
+        .rfind('@')
+        .map_or(authority_start, |i| authority_start + i + 1);
+
+    if !url[host_start..].starts_with('[') {
+        return None;
+    }
+
+    let host_end = host_start + url[host_start..authority_end].find(']')?;
+    let delimiter = host_start + url[host_start..host_end].find('%')?;
+    let zone = &url[delimiter + 1..host_end];
+    let zone = match zone.strip_prefix("25") {
+        Some(rest) if !rest.is_empty() => rest,
+        _ => zone,
+    };
+
+    if zone.is_empty() {
+        return None;
+    }
+
+    let mut stripped = String::with_capacity(url.len());
+    stripped.push_str(&url[..delimiter]);
+    stripped.push_str(&url[host_end..]);
+    Some((stripped, zone))
+}
+
+/// Resolves a zone identifier, either a numeric index or an interface name.
+///
+/// Interface names are looked up on Linux and Android, and on Apple
+/// platforms with the `apple-bindable-device` feature; elsewhere only
+/// numeric indexes are accepted.
+fn zone_to_scope_id(zone: &str) -> Option<u32> {
+    if let Ok(scope_id) = zone.parse::<u32>() {
+        return Some(scope_id);
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    {
+        if zone.contains('/') || zone.starts_with('.') {
+            return None;
+        }
+        std::fs::read_to_string(format!("/sys/class/net/{zone}/ifindex"))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    #[cfg(all(
+        feature = "apple-bindable-device",
+        any(
+            target_os = "ios",
+            target_os = "visionos",
+            target_os = "macos",
+            target_os = "tvos",
+            target_os = "watchos",
+        )
+    ))]
+    {
+        let name = std::ffi::CString::new(zone).ok()?;
+        std::num::NonZeroU32::new(unsafe { libc::if_nametoindex(name.as_ptr()) })
+            .map(std::num::NonZeroU32::get)
+    }
+
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "linux",
+        all(
+            feature = "apple-bindable-device",
+            any(
+                target_os = "ios",
+                target_os = "visionos",
+                target_os = "macos",
+                target_os = "tvos",
+                target_os = "watchos",
+            )
+        )
+    )))]
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,6 +248,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn into_url_ipv6_zone() {
+        let (url, scope_id) = into_url_with_scope_id("http://[fe80::1%253]:8080/path").unwrap();
+        assert_eq!(url.as_str(), "http://[fe80::1]:8080/path");
+        assert_eq!(scope_id, Some(3));
+
+        let (url, scope_id) = into_url_with_scope_id("http://user@[fe80::1%7]/").unwrap();
+        assert_eq!(url.as_str(), "http://user@[fe80::1]/");
+        assert_eq!(scope_id, Some(7));
+
+        let (url, scope_id) = into_url_with_scope_id("http://[::1]/").unwrap();
+        assert_eq!(url.as_str(), "http://[::1]/");
+        assert_eq!(scope_id, None);
+
+        let err = into_url_with_scope_id("http://[fe80::1%25no-such-iface0]/").unwrap_err();
+        assert!(err.is_builder());
+    }
+
+    #[test]
+    fn join_ipv6_zone() {
+        let base = Url::parse("http://[fe80::1]/a/b").unwrap();
+
+        let (url, scope_id) = join_with_scope_id(&base, "http://[fe80::2%254]/c").unwrap();
+        assert_eq!(url.as_str(), "http://[fe80::2]/c");
+        assert_eq!(scope_id, Some(4));
+
+        let (url, scope_id) = join_with_scope_id(&base, "c").unwrap();
+        assert_eq!(url.as_str(), "http://[fe80::1]/a/c");
+        assert_eq!(scope_id, None);
+    }
+
     #[tokio::test]
     async fn execute_request_rejects_invalid_hostname() {
         let url_str = "https://{{hostname}}/";
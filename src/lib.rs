@@ -221,6 +221,10 @@
 //! The following are a list of [Cargo features][cargo-features] that can be
 //! enabled or disabled:
 //!
+//! - **impersonate** *(enabled by default)*: Provides all the [`Impersonate`]
+//!   profiles. A build needing one family can enable it alone instead, with
+//!   **impersonate-chrome**, **impersonate-edge**, **impersonate-firefox**,
+//!   **impersonate-okhttp** or **impersonate-safari**.
 //! - **websocket**: Provides websocket support.
 //! - **cookies**: Provides cookie session support.
 //! - **gzip**: Provides response body gzip decompression.
@@ -228,11 +232,29 @@
 //! - **zstd**: Provides response body zstd decompression.
 //! - **deflate**: Provides response body deflate decompression.
 //! - **json**: Provides serialization and deserialization for JSON bodies.
+//! - **simd-json**: Deserializes large JSON bodies with `simd-json`, for
+//!   `Response::json`.
+//! - **msgpack**: Provides serialization and deserialization for MessagePack
+//!   bodies.
+//! - **cbor**: Provides serialization and deserialization for CBOR bodies.
+//! - **xml**: Provides serialization and deserialization for XML bodies.
+//! - **protobuf**: Provides encoding and decoding for Protocol Buffers bodies.
 //! - **multipart**: Provides functionality for multipart forms.
 //! - **stream**: Adds support for `futures::Stream`.
-//! - **socks**: Provides SOCKS5 proxy support.
+//! - **socks**: Provides SOCKS4, SOCKS4A and SOCKS5 proxy support.
+//! - **pac**: Evaluates PAC (proxy auto-config) scripts with an embedded
+//!   JavaScript engine.
 //! - **hickory-dns**: Enables a hickory-dns async resolver instead of default
 //!   threadpool using `getaddrinfo`.
+//! - **aws-sigv4**: Signs requests with AWS Signature Version 4, for AWS and
+//!   S3-compatible APIs.
+//! - **ntlm**: Provides NTLM authentication, for Windows servers.
+//! - **negotiate**: Provides `Negotiate` (Kerberos) authentication, through
+//!   GSSAPI on Unix and SSPI on Windows.
+//! - **cli**: Builds the `rquest-cli` binary, a command line client for
+//!   trying impersonation profiles and checking fingerprints.
+//! - **ffi**: Provides a C API in the [`ffi`] module, for bindings in other
+//!   languages.
 //!
 //! [hyper]: http://hyper.rs
 //! [client]: ./struct.Client.html
@@ -255,21 +277,24 @@ pub use url::Url;
 // universal mods
 #[macro_use]
 mod error;
+mod header_value;
 mod into_url;
 mod response;
 
-pub use self::error::{Error, Result};
+pub use self::error::{Error, ErrorKind, Result};
+pub use self::header_value::HeaderValueExt;
 pub use self::into_url::IntoUrl;
 pub use self::response::ResponseBuilderExt;
 
 /// Macro to implement Debug for a type, skipping certain fields.
 #[macro_export]
 macro_rules! impl_debug {
-    ($type:ty, { $($field_name:ident),* }) => {
+    ($type:ty, { $($(#[$meta:meta])* $field_name:ident),* }) => {
         impl std::fmt::Debug for $type {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 let mut debug_struct = f.debug_struct(stringify!($type));
                 $(
+                    $(#[$meta])*
                     debug_struct.field(stringify!($field_name), &self.$field_name);
                 )*
                 debug_struct.finish()
@@ -352,26 +377,54 @@ pub use self::client::websocket::{
     CloseCode, CloseFrame, Message, Utf8Bytes, WebSocket, WebSocketRequestBuilder,
     WebSocketResponse,
 };
+#[cfg(feature = "aws-sigv4")]
+pub use self::client::AwsCredentials;
+#[cfg(feature = "charset")]
+pub use self::client::TextStream;
 pub use self::client::{
-    Body, Client, ClientBuilder, ClientMut, ClientRef, Http1Config, Http2Config, HttpContext,
-    HttpContextProvider, Request, RequestBuilder, Response, Upgraded,
+    AbortHandle, ArrayStyle, AuthProvider, AuthToken, Body, Client, ClientBuilder, ClientHints,
+    ClientMut, ClientRef, ConditionalRequest, Download, Encoding, Event, EventSource, EventStream,
+    FetchRequest, Http1Config, Http2Config, HttpContext, HttpContextProvider, MockTransport,
+    MultipartStyle, NestedStyle, Page, Preset, QueryStyle, Request, RequestBuilder,
+    RequestCredentials, RequestMode, RequestRedirect, RequestSigner, RequestTemplate, Response,
+    Revalidation, SessionState, SigningRequest, Throttle, TransferStats, Upgraded,
+};
+#[cfg(feature = "stream")]
+pub use self::client::{BodySender, SaveTo};
+#[cfg(feature = "json")]
+pub use self::client::{
+    Cassette, CassetteMode, GraphQLError, GraphQLLocation, HarRecorder, JsonArray, JsonLines,
+    OAuth2,
 };
 pub use self::imp::{Impersonate, ImpersonateOS, ImpersonateOption};
-pub use self::proxy::{NoProxy, Proxy};
+#[cfg(feature = "pac")]
+pub use self::proxy::PacScript;
+pub use self::proxy::{
+    NoProxy, Proxy, ProxyCredentials, ProxyDecision, ProxyPool, ProxyPoolBuilder, ProxyRotation,
+    ProxyStats,
+};
 pub use self::tls::{
-    AlpnProtos, AlpsProtos, RootCertStore, RootCertStoreProvider, TlsConfig, TlsInfo, TlsVersion,
+    AlpnProtos, AlpsProtos, FingerprintMismatch, RootCertStore, RootCertStoreProvider,
+    ServerFingerprint, TlsConfig, TlsInfo, TlsPinning, TlsVersion,
+};
+pub use self::util::client::{
+    connect::ProxyProtocol, DebugCapture, Dst, Http1Builder, Http2Builder, RequestTarget,
 };
-pub use self::util::client::{Dst, Http1Builder, Http2Builder};
 pub use boring2::ssl::{CertCompressionAlgorithm, ExtensionType, SslCurve};
 pub use hyper2::{Priority, PseudoOrder, SettingsOrder, StreamDependency, StreamId};
 
+pub mod cache;
 mod client;
 mod connect;
 #[cfg(feature = "cookies")]
 pub mod cookie;
 pub mod dns;
+#[cfg(feature = "ffi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ffi")))]
+pub mod ffi;
 mod proxy;
 pub mod redirect;
+pub mod transport;
 
 mod imp;
 mod tls;
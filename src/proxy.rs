@@ -1,12 +1,26 @@
 use std::fmt;
-#[cfg(feature = "socks")]
+use std::future::Future;
 use std::net::SocketAddr;
 use std::sync::{Arc, LazyLock};
 
 use crate::into_url::{IntoUrl, IntoUrlSealed};
 use crate::Url;
 
-use http::{header::HeaderValue, Uri};
+pub(crate) use self::credentials::CredentialsProvider;
+pub use self::credentials::ProxyCredentials;
+#[cfg(feature = "pac")]
+pub use self::pac::PacScript;
+pub use self::pac::ProxyDecision;
+pub use self::pool::{ProxyPool, ProxyPoolBuilder, ProxyRotation, ProxyStats};
+
+mod credentials;
+mod pac;
+mod pool;
+
+use http::{
+    header::{HeaderMap, HeaderValue},
+    Uri,
+};
 use ipnet::IpNet;
 use percent_encoding::percent_decode;
 use std::collections::HashMap;
@@ -16,18 +30,24 @@ use std::net::IpAddr;
 #[cfg(all(target_os = "macos", feature = "macos-system-configuration"))]
 use system_configuration::{
     core_foundation::{
-        base::CFType,
+        array::CFArray,
+        base::{CFType, CFTypeRef, TCFType},
         dictionary::CFDictionary,
         number::CFNumber,
         string::{CFString, CFStringRef},
     },
     dynamic_store::SCDynamicStoreBuilder,
+    sys::schema_definitions::kSCPropNetProxiesExceptionsList,
+    sys::schema_definitions::kSCPropNetProxiesExcludeSimpleHostnames,
     sys::schema_definitions::kSCPropNetProxiesHTTPEnable,
     sys::schema_definitions::kSCPropNetProxiesHTTPPort,
     sys::schema_definitions::kSCPropNetProxiesHTTPProxy,
     sys::schema_definitions::kSCPropNetProxiesHTTPSEnable,
     sys::schema_definitions::kSCPropNetProxiesHTTPSPort,
     sys::schema_definitions::kSCPropNetProxiesHTTPSProxy,
+    sys::schema_definitions::kSCPropNetProxiesSOCKSEnable,
+    sys::schema_definitions::kSCPropNetProxiesSOCKSPort,
+    sys::schema_definitions::kSCPropNetProxiesSOCKSProxy,
 };
 
 /// Configuration of a proxy that a `Client` should pass requests to.
@@ -66,6 +86,10 @@ use system_configuration::{
 pub struct Proxy {
     intercept: Intercept,
     no_proxy: Option<NoProxy>,
+    credentials: Option<CredentialsProvider>,
+    connect_headers: Option<ConnectHeaders>,
+    #[cfg(feature = "pac")]
+    pac: Option<PacScript>,
 }
 
 /// Represents a possible matching entry for an IP address
@@ -77,19 +101,26 @@ enum Ip {
 
 /// A wrapper around a list of IP cidr blocks or addresses with a [IpMatcher::contains] method for
 /// checking if an IP address is contained within the matcher
+///
+/// An entry with a port only matches that port.
 #[derive(Clone, Debug, Default)]
-struct IpMatcher(Vec<Ip>);
+struct IpMatcher(Vec<(Ip, Option<u16>)>);
 
 /// A wrapper around a list of domains with a [DomainMatcher::contains] method for checking if a
 /// domain is contained within the matcher
+///
+/// An entry with a port only matches that port.
 #[derive(Clone, Debug, Default)]
-struct DomainMatcher(Vec<String>);
+struct DomainMatcher(Vec<(String, Option<u16>)>);
+
+type NoProxyFunc = Arc<dyn Fn(&Url) -> bool + Send + Sync + 'static>;
 
 /// A configuration for filtering out requests that shouldn't be proxied
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct NoProxy {
     ips: IpMatcher,
     domains: DomainMatcher,
+    predicates: Vec<NoProxyFunc>,
 }
 
 /// A particular scheme used for proxying requests.
@@ -99,14 +130,22 @@ pub struct NoProxy {
 pub enum ProxyScheme {
     Http {
         auth: Option<HeaderValue>,
+        headers: Option<ConnectHeaders>,
         host: http::uri::Authority,
     },
     Https {
         auth: Option<HeaderValue>,
+        headers: Option<ConnectHeaders>,
         host: http::uri::Authority,
     },
+    /// Fields may be added to this variant, so match it with `..`.
     #[cfg(feature = "socks")]
-    Socks4 { addr: SocketAddr, remote_dns: bool },
+    #[non_exhaustive]
+    Socks4 {
+        addr: SocketAddr,
+        remote_dns: bool,
+        user_id: Option<String>,
+    },
     #[cfg(feature = "socks")]
     Socks5 {
         addr: SocketAddr,
@@ -116,13 +155,71 @@ pub enum ProxyScheme {
 }
 
 impl ProxyScheme {
-    fn maybe_http_auth(&self) -> Option<&HeaderValue> {
+    pub(crate) fn maybe_http_auth(&self) -> Option<&HeaderValue> {
         match self {
             ProxyScheme::Http { auth, .. } | ProxyScheme::Https { auth, .. } => auth.as_ref(),
             #[cfg(feature = "socks")]
             _ => None,
         }
     }
+
+    /// Whether the proxy speaks HTTP, and so reads `Proxy-Authorization`
+    /// itself. A SOCKS proxy would pass the header on to the origin.
+    pub(crate) fn is_http(&self) -> bool {
+        matches!(self, ProxyScheme::Http { .. } | ProxyScheme::Https { .. })
+    }
+
+    /// Whether both schemes reach the same proxy, whatever their credentials.
+    fn same_proxy(&self, other: &ProxyScheme) -> bool {
+        match (self, other) {
+            (ProxyScheme::Http { host: a, .. }, ProxyScheme::Http { host: b, .. })
+            | (ProxyScheme::Https { host: a, .. }, ProxyScheme::Https { host: b, .. }) => a == b,
+            #[cfg(feature = "socks")]
+            (
+                ProxyScheme::Socks4 {
+                    addr: a,
+                    remote_dns: a_dns,
+                    ..
+                },
+                ProxyScheme::Socks4 {
+                    addr: b,
+                    remote_dns: b_dns,
+                    ..
+                },
+            )
+            | (
+                ProxyScheme::Socks5 {
+                    addr: a,
+                    remote_dns: a_dns,
+                    ..
+                },
+                ProxyScheme::Socks5 {
+                    addr: b,
+                    remote_dns: b_dns,
+                    ..
+                },
+            ) => a == b && a_dns == b_dns,
+            _ => false,
+        }
+    }
+}
+
+/// Extra headers sent to a proxy, see [`Proxy::custom_connect_headers`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct ConnectHeaders(Arc<HeaderMap>);
+
+// Equal maps may list their headers in different orders, so hash them
+// sorted.
+impl std::hash::Hash for ConnectHeaders {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let mut headers = self
+            .0
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_bytes()))
+            .collect::<Vec<_>>();
+        headers.sort_unstable();
+        headers.hash(state);
+    }
 }
 
 /// Trait used for converting into a proxy scheme. This trait supports
@@ -276,18 +373,113 @@ impl Proxy {
         }))
     }
 
+    /// Provide a function returning a [`ProxyDecision`] for each URL, like
+    /// the `FindProxyForURL` function of a PAC script.
+    ///
+    /// As with PAC scripts in browsers, the function only sees the scheme,
+    /// host and port of the URL.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate rquest;
+    /// # fn run() -> Result<(), Box<std::error::Error>> {
+    /// use rquest::ProxyDecision;
+    ///
+    /// let proxy = ProxyDecision::proxy("http://proxy.corp.example:3128")?;
+    /// let client = rquest::Client::builder()
+    ///     .proxy(rquest::Proxy::pac_fn(move |url| {
+    ///         match url.host_str() {
+    ///             Some(host) if host.ends_with(".corp.example") => ProxyDecision::Direct,
+    ///             _ => proxy.clone(),
+    ///         }
+    ///     }))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() {}
+    /// ```
+    pub fn pac_fn<F>(fun: F) -> Proxy
+    where
+        F: Fn(&Url) -> ProxyDecision + Send + Sync + 'static,
+    {
+        Proxy::custom(move |url| match fun(url) {
+            ProxyDecision::Direct => None,
+            ProxyDecision::Proxy(proxy) => Some(proxy),
+        })
+    }
+
+    /// Route traffic as a PAC (proxy auto-config) script decides.
+    ///
+    /// When the script fails for a URL, the request goes direct.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate rquest;
+    /// # async fn run() -> Result<(), rquest::Error> {
+    /// let script = rquest::PacScript::fetch("http://wpad.corp.example/wpad.dat").await?;
+    /// let client = rquest::Client::builder()
+    ///     .proxy(rquest::Proxy::pac(script))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() {}
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `pac` feature to be enabled.
+    #[cfg(feature = "pac")]
+    pub fn pac(script: PacScript) -> Proxy {
+        let mut proxy = Proxy::pac_fn({
+            let script = script.clone();
+            move |url| {
+                script.find_proxy(url).unwrap_or_else(|err| {
+                    log::warn!("PAC script failed for {}: {}", url, err);
+                    ProxyDecision::Direct
+                })
+            }
+        });
+        proxy.pac = Some(script);
+        proxy
+    }
+
+    /// Send all traffic through a [`ProxyPool`], which picks a proxy for
+    /// each request and leaves out the failing ones.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate rquest;
+    /// # fn run() -> Result<(), Box<std::error::Error>> {
+    /// let pool = rquest::ProxyPool::builder()
+    ///     .proxy("http://proxy1.example:8080")
+    ///     .proxy("socks5://proxy2.example:1080")
+    ///     .build()?;
+    /// let client = rquest::Client::builder()
+    ///     .proxy(rquest::Proxy::pool(pool))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() {}
+    /// ```
+    pub fn pool(pool: ProxyPool) -> Proxy {
+        Proxy::new(Intercept::Pool(Pool { auth: None, pool }))
+    }
+
     pub(crate) fn system() -> Proxy {
-        static SYS_PROXIES: LazyLock<Arc<SystemProxyMap>> =
-            LazyLock::new(|| Arc::new(get_sys_proxies(get_from_platform())));
+        static SYS_PROXIES: LazyLock<(Arc<SystemProxyMap>, Option<NoProxy>)> =
+            LazyLock::new(|| get_sys_proxies(get_from_platform()));
 
-        let mut proxy = if cfg!(feature = "internal_proxy_sys_no_cache") {
-            Proxy::new(Intercept::System(Arc::new(get_sys_proxies(
-                get_from_platform(),
-            ))))
+        let (map, no_proxy) = if cfg!(feature = "internal_proxy_sys_no_cache") {
+            get_sys_proxies(get_from_platform())
         } else {
-            Proxy::new(Intercept::System(SYS_PROXIES.clone()))
+            SYS_PROXIES.clone()
         };
-        proxy.no_proxy = NoProxy::from_env();
+
+        let mut proxy = Proxy::new(Intercept::System(map));
+        proxy.no_proxy = no_proxy;
         proxy
     }
 
@@ -295,6 +487,10 @@ impl Proxy {
         Proxy {
             intercept,
             no_proxy: None,
+            credentials: None,
+            connect_headers: None,
+            #[cfg(feature = "pac")]
+            pac: None,
         }
     }
 
@@ -335,6 +531,72 @@ impl Proxy {
         self
     }
 
+    /// Send extra headers to the proxy, such as the session or the exit
+    /// country of a proxy provider.
+    ///
+    /// The headers go in the `CONNECT` requests of the tunnels to `https`
+    /// destinations, and in the requests to `http` destinations, which the
+    /// proxy forwards. They have no effect on SOCKS proxies.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate rquest;
+    /// # fn run() -> Result<(), Box<std::error::Error>> {
+    /// use rquest::header::{HeaderMap, HeaderValue};
+    ///
+    /// let mut headers = HeaderMap::new();
+    /// headers.insert("x-proxy-session", HeaderValue::from_static("a1b2c3"));
+    /// headers.insert("x-proxy-country", HeaderValue::from_static("de"));
+    ///
+    /// let proxy = rquest::Proxy::all("http://proxy.example:8080")?
+    ///     .custom_connect_headers(headers);
+    /// # Ok(())
+    /// # }
+    /// # fn main() {}
+    /// ```
+    pub fn custom_connect_headers(mut self, headers: HeaderMap) -> Proxy {
+        self.connect_headers = Some(ConnectHeaders(Arc::new(headers)));
+        self
+    }
+
+    /// Fetch the proxy credentials with an async callback.
+    ///
+    /// The callback is called before the first request, when the proxy
+    /// answers `407 Proxy Authentication Required`, and when the last
+    /// credentials have [expired](ProxyCredentials::expires_in). Its
+    /// credentials take precedence over those of the proxy URL.
+    ///
+    /// A request is sent again once with the new credentials, unless its
+    /// body is a stream.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate rquest;
+    /// # use std::time::Duration;
+    /// # async fn fetch_token() -> Result<String, rquest::Error> { Ok(String::new()) }
+    /// # fn run() -> Result<(), Box<std::error::Error>> {
+    /// use rquest::ProxyCredentials;
+    ///
+    /// let proxy = rquest::Proxy::all("http://proxy.example:8080")?
+    ///     .credentials_provider(|| async {
+    ///         let token = fetch_token().await?;
+    ///         Ok(ProxyCredentials::basic("session", &token).expires_in(Duration::from_secs(300)))
+    ///     });
+    /// # Ok(())
+    /// # }
+    /// # fn main() {}
+    /// ```
+    pub fn credentials_provider<F, Fut>(mut self, provider: F) -> Proxy
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = crate::Result<ProxyCredentials>> + Send + 'static,
+    {
+        self.credentials = Some(CredentialsProvider::new(provider));
+        self
+    }
+
     /// Adds a `No Proxy` exclusion list to this Proxy
     ///
     /// # Example
@@ -354,6 +616,13 @@ impl Proxy {
     }
 
     pub(crate) fn maybe_has_http_auth(&self) -> bool {
+        if self.credentials.is_some() && !matches!(self.intercept, Intercept::Https(_)) {
+            match &self.intercept {
+                Intercept::All(p) | Intercept::Http(p) if !p.is_http() => {}
+                _ => return true,
+            }
+        }
+
         match &self.intercept {
             Intercept::All(p) | Intercept::Http(p) => p.maybe_http_auth().is_some(),
             // Custom *may* match 'http', so assume so.
@@ -362,11 +631,21 @@ impl Proxy {
                 .get("http")
                 .and_then(|s| s.maybe_http_auth())
                 .is_some(),
-            Intercept::Https(_) => false,
+            // The credentials of a pool depend on the proxy it picks, so the
+            // client adds them once the request's proxy is known.
+            Intercept::Https(_) | Intercept::Pool(_) => false,
         }
     }
 
     pub(crate) fn http_basic_auth<D: Dst>(&self, uri: &D) -> Option<HeaderValue> {
+        if let Some(credentials) = self.credentials_for(uri) {
+            if !matches!(self.intercept, Intercept::Https(_)) {
+                if let Some(header) = credentials.current() {
+                    return Some(header);
+                }
+            }
+        }
+
         match &self.intercept {
             Intercept::All(p) | Intercept::Http(p) => p.maybe_http_auth().cloned(),
             Intercept::System(system) => system
@@ -375,15 +654,23 @@ impl Proxy {
             Intercept::Custom(custom) => {
                 custom.call(uri).and_then(|s| s.maybe_http_auth().cloned())
             }
-            Intercept::Https(_) => None,
+            Intercept::Https(_) | Intercept::Pool(_) => None,
         }
     }
 
     pub(crate) fn intercept<D: Dst>(&self, uri: &D) -> Option<ProxyScheme> {
-        let in_no_proxy = self
-            .no_proxy
-            .as_ref()
-            .is_some_and(|np| np.contains(uri.host()));
+        let mut scheme = self.intercept_scheme(uri)?;
+        if let Some(header) = self.credentials.as_ref().and_then(|c| c.current()) {
+            scheme.replace_http_auth(header);
+        }
+        if let Some(ref headers) = self.connect_headers {
+            scheme.set_connect_headers(headers);
+        }
+        Some(scheme)
+    }
+
+    fn intercept_scheme<D: Dst>(&self, uri: &D) -> Option<ProxyScheme> {
+        let in_no_proxy = self.in_no_proxy(uri);
         match self.intercept {
             Intercept::All(ref u) => {
                 if !in_no_proxy {
@@ -420,7 +707,52 @@ impl Proxy {
                     None
                 }
             }
+            Intercept::Pool(ref pool) => {
+                if !in_no_proxy {
+                    Some(pool.pool.select(uri.host()).if_no_auth(&pool.auth))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn in_no_proxy<D: Dst>(&self, uri: &D) -> bool {
+        self.no_proxy.as_ref().is_some_and(|np| np.contains(uri))
+    }
+
+    /// Whether `uri` goes through this proxy, without picking a proxy.
+    pub(crate) fn intercepts<D: Dst>(&self, uri: &D) -> bool {
+        !self.in_no_proxy(uri) && self.is_match(uri)
+    }
+
+    /// The PAC script of this proxy and the URL it decides on, if its
+    /// decision for `uri` isn't known yet.
+    #[cfg(feature = "pac")]
+    pub(crate) fn pending_pac<D: Dst>(&self, uri: &D) -> Option<(&PacScript, Url)> {
+        let script = self.pac.as_ref()?;
+        if self.in_no_proxy(uri) {
+            return None;
         }
+        let url = dst_url(uri);
+        script.cached(&url).is_none().then_some((script, url))
+    }
+
+    pub(crate) fn credentials(&self) -> Option<&CredentialsProvider> {
+        self.credentials.as_ref()
+    }
+
+    /// The credentials callback, if `uri` goes through an HTTP proxy: the
+    /// credentials are only for the proxy, which a SOCKS proxy can't read.
+    pub(crate) fn credentials_for<D: Dst>(&self, uri: &D) -> Option<&CredentialsProvider> {
+        let credentials = self.credentials.as_ref()?;
+        let is_http = match &self.intercept {
+            Intercept::All(p) | Intercept::Http(p) | Intercept::Https(p) => p.is_http(),
+            Intercept::System(system) => system.get(uri.scheme()).is_some_and(ProxyScheme::is_http),
+            Intercept::Custom(custom) => custom.call(uri).is_some_and(|p| p.is_http()),
+            Intercept::Pool(pool) => pool.pool.is_http(),
+        };
+        is_http.then_some(credentials)
     }
 
     pub(crate) fn is_match<D: Dst>(&self, uri: &D) -> bool {
@@ -430,6 +762,15 @@ impl Proxy {
             Intercept::Https(_) => uri.scheme() == "https",
             Intercept::System(ref map) => map.contains_key(uri.scheme()),
             Intercept::Custom(ref custom) => custom.call(uri).is_some(),
+            Intercept::Pool(_) => true,
+        }
+    }
+
+    /// The pool `scheme` was picked from, if this is a `Proxy::pool`.
+    pub(crate) fn pool_of(&self, scheme: &ProxyScheme) -> Option<&ProxyPool> {
+        match self.intercept {
+            Intercept::Pool(ref pool) if pool.pool.contains(scheme) => Some(&pool.pool),
+            _ => None,
         }
     }
 }
@@ -439,6 +780,7 @@ impl fmt::Debug for Proxy {
         f.debug_tuple("Proxy")
             .field(&self.intercept)
             .field(&self.no_proxy)
+            .field(&self.credentials)
             .finish()
     }
 }
@@ -463,6 +805,9 @@ impl NoProxy {
     /// * IP addresses (both IPv4 and IPv6) are allowed, as are optional subnet masks (by adding /size,
     ///   for example "`192.168.1.0/24`").
     /// * An entry "`*`" matches all hostnames (this is the only wildcard allowed)
+    /// * An entry "`<local>`" matches the hostnames without a dot, like `localhost` or `intranet`
+    /// * A domain name or IP address may be followed by a port, like `internal.example:8080`,
+    ///   `10.1.2.3:443` or `[::1]:8080`, to only match that port.
     /// * Any other entry is considered a domain name (and may contain a leading dot, for example `google.com`
     ///   and `.google.com` are equivalent) and would match both that domain AND all subdomains.
     ///
@@ -483,20 +828,70 @@ impl NoProxy {
         for part in parts {
             match part.parse::<IpNet>() {
                 // If we can parse an IP net or address, then use it, otherwise, assume it is a domain
-                Ok(ip) => ips.push(Ip::Network(ip)),
+                Ok(ip) => ips.push((Ip::Network(ip), None)),
                 Err(_) => match part.parse::<IpAddr>() {
-                    Ok(addr) => ips.push(Ip::Address(addr)),
-                    Err(_) => domains.push(part.to_owned()),
+                    Ok(addr) => ips.push((Ip::Address(addr), None)),
+                    Err(_) => match part.parse::<SocketAddr>() {
+                        Ok(addr) => ips.push((Ip::Address(addr.ip()), Some(addr.port()))),
+                        Err(_) => match part.rsplit_once(':') {
+                            Some((domain, port)) if port.parse::<u16>().is_ok() => {
+                                domains.push((domain.to_owned(), port.parse().ok()))
+                            }
+                            _ => domains.push((part.to_owned(), None)),
+                        },
+                    },
                 },
             }
         }
         Some(NoProxy {
             ips: IpMatcher(ips),
             domains: DomainMatcher(domains),
+            predicates: Vec::new(),
         })
     }
 
-    fn contains(&self, host: &str) -> bool {
+    /// Also bypass the proxy for the URLs `predicate` returns `true` for.
+    ///
+    /// As with [`Proxy::custom`], the predicate only sees the scheme, host
+    /// and port of the URL.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate rquest;
+    /// # fn run() -> Result<(), Box<std::error::Error>> {
+    /// let no_proxy = rquest::NoProxy::from_string("10.0.0.0/8, .corp.example")
+    ///     .unwrap_or_default()
+    ///     .predicate(|url| url.port() == Some(8443));
+    /// let proxy = rquest::Proxy::all("http://proxy.example:8080")?.no_proxy(Some(no_proxy));
+    /// # Ok(())
+    /// # }
+    /// # fn main() {}
+    /// ```
+    pub fn predicate<F>(mut self, predicate: F) -> NoProxy
+    where
+        F: Fn(&Url) -> bool + Send + Sync + 'static,
+    {
+        self.predicates.push(Arc::new(predicate));
+        self
+    }
+
+    fn contains<D: Dst>(&self, dst: &D) -> bool {
+        let host = dst.host();
+        let port = dst.port().or_else(|| match dst.scheme() {
+            "http" => Some(80),
+            "https" => Some(443),
+            _ => None,
+        });
+
+        self.contains_host(host, port)
+            || (!self.predicates.is_empty() && {
+                let url = dst_url(dst);
+                self.predicates.iter().any(|predicate| predicate(&url))
+            })
+    }
+
+    fn contains_host(&self, host: &str, port: Option<u16>) -> bool {
         // According to RFC3986, raw IPv6 hosts will be wrapped in []. So we need to strip those off
         // the end in order to parse correctly
         let host = if host.starts_with('[') {
@@ -507,15 +902,28 @@ impl NoProxy {
         };
         match host.parse::<IpAddr>() {
             // If we can parse an IP addr, then use it, otherwise, assume it is a domain
-            Ok(ip) => self.ips.contains(ip),
-            Err(_) => self.domains.contains(host),
+            Ok(ip) => self.ips.contains(ip, port),
+            Err(_) => self.domains.contains(host, port),
         }
     }
 }
 
+impl fmt::Debug for NoProxy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NoProxy")
+            .field("ips", &self.ips)
+            .field("domains", &self.domains)
+            .field("predicates", &self.predicates.len())
+            .finish()
+    }
+}
+
 impl IpMatcher {
-    fn contains(&self, addr: IpAddr) -> bool {
-        for ip in &self.0 {
+    fn contains(&self, addr: IpAddr, port: Option<u16>) -> bool {
+        for (ip, only_port) in &self.0 {
+            if only_port.is_some() && *only_port != port {
+                continue;
+            }
             match ip {
                 Ip::Address(address) => {
                     if &addr == address {
@@ -537,9 +945,12 @@ impl DomainMatcher {
     // The following links may be useful to understand the origin of these rules:
     // * https://curl.se/libcurl/c/CURLOPT_NOPROXY.html
     // * https://github.com/curl/curl/issues/1208
-    fn contains(&self, domain: &str) -> bool {
+    fn contains(&self, domain: &str, port: Option<u16>) -> bool {
         let domain_len = domain.len();
-        for d in &self.0 {
+        for (d, only_port) in &self.0 {
+            if only_port.is_some() && *only_port != port {
+                continue;
+            }
             if d == domain || d.strip_prefix('.') == Some(domain) {
                 return true;
             } else if domain.ends_with(d) {
@@ -552,7 +963,7 @@ impl DomainMatcher {
                     // then that means we must be matching a subdomain of d, and that matches
                     return true;
                 }
-            } else if d == "*" {
+            } else if d == "*" || (d == "<local>" && !domain.contains('.')) {
                 return true;
             }
         }
@@ -567,6 +978,7 @@ impl ProxyScheme {
     fn http(host: &str) -> crate::Result<Self> {
         Ok(ProxyScheme::Http {
             auth: None,
+            headers: None,
             host: host.parse().map_err(crate::error::builder)?,
         })
     }
@@ -575,6 +987,7 @@ impl ProxyScheme {
     fn https(host: &str) -> crate::Result<Self> {
         Ok(ProxyScheme::Https {
             auth: None,
+            headers: None,
             host: host.parse().map_err(crate::error::builder)?,
         })
     }
@@ -583,12 +996,14 @@ impl ProxyScheme {
     ///
     /// # Note
     ///
-    /// Current SOCKS4 support is provided via blocking IO.
+    /// SOCKS4 only supports IPv4 destinations, and has no passwords; the
+    /// username of the proxy URL is sent as the user ID.
     #[cfg(feature = "socks")]
     fn socks4(addr: SocketAddr) -> crate::Result<Self> {
         Ok(ProxyScheme::Socks4 {
             addr,
             remote_dns: false,
+            user_id: None,
         })
     }
 
@@ -598,12 +1013,14 @@ impl ProxyScheme {
     ///
     /// # Note
     ///
-    /// Current SOCKS4 support is provided via blocking IO.
+    /// SOCKS4A has no passwords; the username of the proxy URL is sent as
+    /// the user ID.
     #[cfg(feature = "socks")]
     fn socks4a(addr: SocketAddr) -> crate::Result<Self> {
         Ok(ProxyScheme::Socks4 {
             addr,
             remote_dns: true,
+            user_id: None,
         })
     }
 
@@ -658,8 +1075,11 @@ impl ProxyScheme {
                 *auth = Some(header);
             }
             #[cfg(feature = "socks")]
-            ProxyScheme::Socks4 { .. } => {
-                panic!("Socks4 is not supported for this method")
+            ProxyScheme::Socks4 {
+                ref mut user_id, ..
+            } => {
+                // SOCKS4 has no passwords, only a user ID
+                *user_id = Some(username.into());
             }
             #[cfg(feature = "socks")]
             ProxyScheme::Socks5 { ref mut auth, .. } => {
@@ -687,6 +1107,38 @@ impl ProxyScheme {
         }
     }
 
+    /// The extra headers of the requests to an HTTP or HTTPS proxy.
+    pub(crate) fn connect_headers(&self) -> Option<&HeaderMap> {
+        match self {
+            ProxyScheme::Http { headers, .. } | ProxyScheme::Https { headers, .. } => {
+                headers.as_ref().map(|headers| &*headers.0)
+            }
+            #[cfg(feature = "socks")]
+            _ => None,
+        }
+    }
+
+    fn set_connect_headers(&mut self, update: &ConnectHeaders) {
+        match self {
+            ProxyScheme::Http { headers, .. } | ProxyScheme::Https { headers, .. } => {
+                *headers = Some(update.clone());
+            }
+            #[cfg(feature = "socks")]
+            ProxyScheme::Socks4 { .. } | ProxyScheme::Socks5 { .. } => {}
+        }
+    }
+
+    /// Use `header` as the `Proxy-Authorization` of an HTTP or HTTPS proxy.
+    pub(crate) fn replace_http_auth(&mut self, header: HeaderValue) {
+        match self {
+            ProxyScheme::Http { auth, .. } | ProxyScheme::Https { auth, .. } => {
+                *auth = Some(header);
+            }
+            #[cfg(feature = "socks")]
+            ProxyScheme::Socks4 { .. } | ProxyScheme::Socks5 { .. } => {}
+        }
+    }
+
     fn if_no_auth(mut self, update: &Option<HeaderValue>) -> Self {
         match self {
             ProxyScheme::Http { ref mut auth, .. } => {
@@ -750,6 +1202,18 @@ impl ProxyScheme {
             scheme = scheme.with_basic_auth(decoded_username, decoded_password);
         }
 
+        #[cfg(feature = "socks")]
+        if let ProxyScheme::Socks4 {
+            ref mut user_id, ..
+        } = scheme
+        {
+            if !url.username().is_empty() {
+                let decoded_username =
+                    percent_decode(url.username().as_bytes()).decode_utf8_lossy();
+                *user_id = Some(decoded_username.into_owned());
+            }
+        }
+
         Ok(scheme)
     }
 }
@@ -757,10 +1221,12 @@ impl ProxyScheme {
 impl fmt::Debug for ProxyScheme {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ProxyScheme::Http { auth: _auth, host } => write!(f, "http://{}", host),
-            ProxyScheme::Https { auth: _auth, host } => write!(f, "https://{}", host),
+            ProxyScheme::Http { host, .. } => write!(f, "http://{}", host),
+            ProxyScheme::Https { host, .. } => write!(f, "https://{}", host),
             #[cfg(feature = "socks")]
-            ProxyScheme::Socks4 { addr, remote_dns } => {
+            ProxyScheme::Socks4 {
+                addr, remote_dns, ..
+            } => {
                 let h = if *remote_dns { "a" } else { "" };
                 write!(f, "socks4{}://{}", h, addr)
             }
@@ -779,6 +1245,15 @@ impl fmt::Debug for ProxyScheme {
 
 type SystemProxyMap = HashMap<String, ProxyScheme>;
 
+/// The proxy settings of the operating system.
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+struct PlatformProxies {
+    /// The proxies, like `http=127.0.0.1:8080;https=127.0.0.1:8080`.
+    servers: String,
+    /// The hosts to reach directly, in the `NO_PROXY` format.
+    bypass: Option<String>,
+}
+
 #[derive(Clone, Debug)]
 enum Intercept {
     All(ProxyScheme),
@@ -786,6 +1261,7 @@ enum Intercept {
     Https(ProxyScheme),
     System(Arc<SystemProxyMap>),
     Custom(Custom),
+    Pool(Pool),
 }
 
 impl Intercept {
@@ -799,6 +1275,10 @@ impl Intercept {
                 let header = encode_basic_auth(username, password);
                 custom.auth = Some(header);
             }
+            Intercept::Pool(ref mut pool) => {
+                let header = encode_basic_auth(username, password);
+                pool.auth = Some(header);
+            }
         }
     }
 
@@ -811,6 +1291,9 @@ impl Intercept {
             Intercept::Custom(ref mut custom) => {
                 custom.auth = Some(header_value);
             }
+            Intercept::Pool(ref mut pool) => {
+                pool.auth = Some(header_value);
+            }
         }
     }
 }
@@ -826,15 +1309,7 @@ struct Custom {
 
 impl Custom {
     fn call<D: Dst>(&self, uri: &D) -> Option<ProxyScheme> {
-        let url = format!(
-            "{}://{}{}{}",
-            uri.scheme(),
-            uri.host(),
-            uri.port().map_or("", |_| ":"),
-            uri.port().map_or(String::new(), |p| p.to_string())
-        )
-        .parse()
-        .expect("should be valid Url");
+        let url = dst_url(uri);
 
         (self.func)(&url)
             .and_then(|result| result.ok())
@@ -848,10 +1323,30 @@ impl fmt::Debug for Custom {
     }
 }
 
+#[derive(Clone, Debug)]
+struct Pool {
+    // This auth only applies if the picked ProxyScheme doesn't have an auth...
+    auth: Option<HeaderValue>,
+    pool: ProxyPool,
+}
+
 pub(crate) fn encode_basic_auth(username: &str, password: &str) -> HeaderValue {
     crate::util::basic_auth(username, Some(password))
 }
 
+/// The URL of the origin of `dst`, for custom functions.
+fn dst_url<D: Dst>(dst: &D) -> Url {
+    format!(
+        "{}://{}{}{}",
+        dst.scheme(),
+        dst.host(),
+        dst.port().map_or("", |_| ":"),
+        dst.port().map_or(String::new(), |p| p.to_string())
+    )
+    .parse()
+    .expect("should be valid Url")
+}
+
 /// A helper trait to allow testing `Proxy::intercept` without having to
 /// construct `hyper2::client::connect::Destination`s.
 pub(crate) trait Dst {
@@ -896,16 +1391,20 @@ impl Dst for Url {
 /// Windows and MacOS platforms instead. Errors encountered while discovering
 /// these settings are ignored.
 ///
+/// The hosts to reach directly come from `NO_PROXY`, or else from the
+/// platform's bypass list when the platform proxies are used.
+///
 /// Returns:
 ///     System proxies information as a hashmap like
 ///     {"http": Url::parse("http://127.0.0.1:80"), "https": Url::parse("https://127.0.0.1:80")}
+///     and the hosts to reach directly
 fn get_sys_proxies(
     #[cfg_attr(
         not(any(target_os = "windows", target_os = "macos")),
         allow(unused_variables)
     )]
-    platform_proxies: Option<String>,
-) -> SystemProxyMap {
+    platform_proxies: Option<PlatformProxies>,
+) -> (Arc<SystemProxyMap>, Option<NoProxy>) {
     let proxies = get_from_environment();
 
     #[cfg(any(target_os = "windows", target_os = "macos"))]
@@ -913,11 +1412,20 @@ fn get_sys_proxies(
         // if there are errors in acquiring the platform proxies,
         // we'll just return an empty HashMap
         if let Some(platform_proxies) = platform_proxies {
-            return parse_platform_values(platform_proxies);
+            let no_proxy = NoProxy::from_env().or_else(|| {
+                platform_proxies
+                    .bypass
+                    .as_deref()
+                    .and_then(NoProxy::from_string)
+            });
+            return (
+                Arc::new(parse_platform_values(platform_proxies.servers)),
+                no_proxy,
+            );
         }
     }
 
-    proxies
+    (Arc::new(proxies), NoProxy::from_env())
 }
 
 fn insert_proxy(proxies: &mut SystemProxyMap, scheme: impl Into<String>, addr: String) -> bool {
@@ -974,14 +1482,19 @@ fn is_cgi() -> bool {
 }
 
 #[cfg(target_os = "windows")]
-fn get_from_platform_impl() -> Result<Option<String>, Box<dyn Error>> {
+fn get_from_platform_impl() -> Result<Option<PlatformProxies>, Box<dyn Error>> {
     let internet_setting = windows_registry::CURRENT_USER
         .open("Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings")?;
     // ensure the proxy is enable, if the value doesn't exist, an error will returned.
     let proxy_enable = internet_setting.get_u32("ProxyEnable")?;
     let proxy_server = internet_setting.get_string("ProxyServer")?;
+    // the bypass list is optional, like `*.example.com;10.*;<local>`.
+    let proxy_override = internet_setting.get_string("ProxyOverride").ok();
 
-    Ok((proxy_enable == 1).then_some(proxy_server))
+    Ok((proxy_enable == 1).then(|| PlatformProxies {
+        servers: proxy_server,
+        bypass: proxy_override.map(|list| parse_platform_bypass(list.split(';'))),
+    }))
 }
 
 #[cfg(all(target_os = "macos", feature = "macos-system-configuration"))]
@@ -1023,7 +1536,7 @@ fn parse_setting_from_dynamic_store(
 }
 
 #[cfg(all(target_os = "macos", feature = "macos-system-configuration"))]
-fn get_from_platform_impl() -> Result<Option<String>, Box<dyn Error>> {
+fn get_from_platform_impl() -> Result<Option<PlatformProxies>, Box<dyn Error>> {
     let store = SCDynamicStoreBuilder::new("rquest").build();
 
     let proxies_map = if let Some(proxies_map) = store.get_proxies() {
@@ -1047,17 +1560,61 @@ fn get_from_platform_impl() -> Result<Option<String>, Box<dyn Error>> {
         "https",
     );
 
-    match http_proxy_config.as_ref().zip(https_proxy_config.as_ref()) {
-        Some((http_config, https_config)) => Ok(Some(format!("{http_config};{https_config}"))),
-        None => Ok(http_proxy_config.or(https_proxy_config)),
+    // macOS speaks SOCKS5 to SOCKS proxies.
+    let socks_proxy_config = parse_setting_from_dynamic_store(
+        &proxies_map,
+        unsafe { kSCPropNetProxiesSOCKSEnable },
+        unsafe { kSCPropNetProxiesSOCKSProxy },
+        unsafe { kSCPropNetProxiesSOCKSPort },
+        "socks",
+    )
+    .map(|config| config.replacen('=', "=socks5://", 1));
+
+    let servers = [http_proxy_config, https_proxy_config, socks_proxy_config]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(";");
+    if servers.is_empty() {
+        return Ok(None);
+    }
+
+    let mut bypass = proxies_map
+        .find(unsafe { kSCPropNetProxiesExceptionsList })
+        .and_then(|list| list.downcast::<CFArray>())
+        .map(|list| {
+            list.get_all_values()
+                .into_iter()
+                .filter_map(|value| {
+                    unsafe { CFType::wrap_under_get_rule(value as CFTypeRef) }
+                        .downcast::<CFString>()
+                        .map(|host| host.to_string())
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let exclude_simple_hostnames = proxies_map
+        .find(unsafe { kSCPropNetProxiesExcludeSimpleHostnames })
+        .and_then(|flag| flag.downcast::<CFNumber>())
+        .and_then(|flag| flag.to_i32())
+        .unwrap_or(0)
+        == 1;
+    if exclude_simple_hostnames {
+        bypass.push("<local>".to_owned());
     }
+
+    Ok(Some(PlatformProxies {
+        servers,
+        bypass: (!bypass.is_empty()).then(|| parse_platform_bypass(bypass.iter())),
+    }))
 }
 
 #[cfg(any(
     target_os = "windows",
     all(target_os = "macos", feature = "macos-system-configuration")
 ))]
-fn get_from_platform() -> Option<String> {
+fn get_from_platform() -> Option<PlatformProxies> {
     get_from_platform_impl().ok().flatten()
 }
 
@@ -1065,10 +1622,53 @@ fn get_from_platform() -> Option<String> {
     target_os = "windows",
     all(target_os = "macos", feature = "macos-system-configuration")
 )))]
-fn get_from_platform() -> Option<String> {
+fn get_from_platform() -> Option<PlatformProxies> {
     None
 }
 
+/// Convert a platform bypass list, like `*.example.com`, `10.*`,
+/// `169.254/16` and `<local>`, to the `NO_PROXY` format.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn parse_platform_bypass<I, S>(entries: I) -> String
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            let entry = entry.as_ref().trim();
+            if entry.is_empty() {
+                return None;
+            }
+
+            // `*.example.com` is `.example.com` to NoProxy.
+            if let Some(domain) = entry.strip_prefix("*.") {
+                return Some(format!(".{}", domain));
+            }
+
+            // `10.*` and `169.254/16` are partial IPv4 networks.
+            let (prefix, len) = match entry.split_once('/') {
+                Some((prefix, len)) => (prefix, len.parse::<u8>().ok()),
+                None => (entry.trim_end_matches(".*"), None),
+            };
+            let octets = prefix.split('.').collect::<Vec<_>>();
+            let is_partial_ipv4 = (entry.ends_with(".*") || len.is_some())
+                && octets.len() <= 4
+                && octets.iter().all(|octet| octet.parse::<u8>().is_ok());
+            if is_partial_ipv4 {
+                let len = len.unwrap_or(octets.len() as u8 * 8);
+                let mut network = octets;
+                network.resize(4, "0");
+                return Some(format!("{}/{}", network.join("."), len));
+            }
+
+            Some(entry.to_owned())
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 #[cfg(any(target_os = "windows", target_os = "macos"))]
 fn parse_platform_values_impl(platform_values: String) -> SystemProxyMap {
     let mut proxies = HashMap::new();
@@ -1079,9 +1679,11 @@ fn parse_platform_values_impl(platform_values: String) -> SystemProxyMap {
             match protocol_parts.as_slice() {
                 [protocol, address] => {
                     // If address doesn't specify an explicit protocol as protocol://address
-                    // then default to HTTP
+                    // then default to HTTP, or SOCKS4 for WinINET's `socks=` setting
                     let address = if extract_type_prefix(address).is_some() {
                         String::from(*address)
+                    } else if *protocol == "socks" {
+                        format!("socks4://{}", address)
                     } else {
                         format!("http://{}", address)
                     };
@@ -1096,6 +1698,14 @@ fn parse_platform_values_impl(platform_values: String) -> SystemProxyMap {
                 }
             }
         }
+
+        // A SOCKS proxy carries whatever has no proxy of its own.
+        if let Some(socks) = proxies.remove("socks") {
+            proxies
+                .entry("http".into())
+                .or_insert_with(|| socks.clone());
+            proxies.entry("https".into()).or_insert(socks);
+        }
     } else if let Some(scheme) = extract_type_prefix(&platform_values) {
         // Explicit protocol has been specified
         insert_proxy(&mut proxies, scheme, platform_values.to_owned());
@@ -0,0 +1,160 @@
+//! Proxy credentials fetched on demand, for providers with short-lived tokens.
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use antidote::Mutex;
+use http::header::HeaderValue;
+
+type CredentialsFuture = Pin<Box<dyn Future<Output = crate::Result<ProxyCredentials>> + Send>>;
+
+/// The credentials a [`Proxy::credentials_provider`](super::Proxy::credentials_provider)
+/// callback returns.
+#[derive(Clone)]
+pub struct ProxyCredentials {
+    header: HeaderValue,
+    expires_at: Option<Instant>,
+}
+
+impl ProxyCredentials {
+    /// Credentials for `Basic` authentication.
+    pub fn basic(username: &str, password: &str) -> ProxyCredentials {
+        ProxyCredentials::header(super::encode_basic_auth(username, password))
+    }
+
+    /// Credentials sent as is in the `Proxy-Authorization` header, such as
+    /// a `Bearer` token.
+    pub fn header(mut value: HeaderValue) -> ProxyCredentials {
+        value.set_sensitive(true);
+        ProxyCredentials {
+            header: value,
+            expires_at: None,
+        }
+    }
+
+    /// Fetch new credentials before the next request once `ttl` has passed.
+    ///
+    /// Credentials without expiry are only fetched again when the proxy
+    /// answers `407 Proxy Authentication Required`.
+    pub fn expires_in(mut self, ttl: Duration) -> ProxyCredentials {
+        self.expires_at = Instant::now().checked_add(ttl);
+        self
+    }
+
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Instant::now())
+    }
+}
+
+impl fmt::Debug for ProxyCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyCredentials")
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+/// A callback returning proxy credentials, and the last credentials it
+/// returned.
+#[derive(Clone)]
+pub(crate) struct CredentialsProvider {
+    callback: Arc<dyn Fn() -> CredentialsFuture + Send + Sync>,
+    current: Arc<Mutex<Option<ProxyCredentials>>>,
+    /// Held while the callback runs, so the requests refused at the same
+    /// time wait for a single call.
+    refreshing: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl CredentialsProvider {
+    pub(crate) fn new<F, Fut>(callback: F) -> CredentialsProvider
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = crate::Result<ProxyCredentials>> + Send + 'static,
+    {
+        CredentialsProvider {
+            callback: Arc::new(move || Box::pin(callback()) as CredentialsFuture),
+            current: Arc::new(Mutex::new(None)),
+            refreshing: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    /// The header of the last credentials, unless they expired.
+    pub(crate) fn current(&self) -> Option<HeaderValue> {
+        self.current
+            .lock()
+            .as_ref()
+            .filter(|credentials| !credentials.is_expired())
+            .map(|credentials| credentials.header.clone())
+    }
+
+    /// Call the callback for new credentials, and keep them for the next
+    /// requests.
+    ///
+    /// `refused` are the credentials the proxy refused, if any. The callback
+    /// isn't called if another request replaced them in the meantime.
+    pub(crate) fn refresh(
+        &self,
+        refused: Option<HeaderValue>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<HeaderValue>> + Send>> {
+        let provider = self.clone();
+        Box::pin(async move {
+            let _refreshing = provider.refreshing.lock().await;
+            if let Some(header) = provider.current() {
+                if refused.as_ref() != Some(&header) {
+                    return Ok(header);
+                }
+            }
+
+            let credentials = (provider.callback)().await?;
+            let header = credentials.header.clone();
+            *provider.current.lock() = Some(credentials);
+            Ok(header)
+        })
+    }
+}
+
+impl fmt::Debug for CredentialsProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CredentialsProvider")
+            .field("current", &*self.current.lock())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn concurrent_refreshes_call_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = CredentialsProvider::new({
+            let calls = calls.clone();
+            move || {
+                let call = calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    Ok(ProxyCredentials::basic("user", &call.to_string()))
+                }
+            }
+        });
+
+        let refused = ProxyCredentials::basic("user", "old").header;
+        let (a, b) = tokio::join!(
+            provider.refresh(Some(refused.clone())),
+            provider.refresh(Some(refused))
+        );
+        assert_eq!(a.unwrap(), b.unwrap());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // the new credentials refused too
+        let refused = provider.current();
+        provider.refresh(refused).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,640 @@
+//! Proxy auto-config (PAC) decisions, and evaluation of PAC scripts.
+use std::fmt;
+use std::str::FromStr;
+
+use super::IntoProxyScheme;
+use crate::{IntoUrl, Url};
+
+/// Where a request should go, as decided by a PAC script or a
+/// [`Proxy::pac_fn`](super::Proxy::pac_fn) function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyDecision {
+    /// Connect to the server directly.
+    Direct,
+    /// Go through the proxy at this URL.
+    Proxy(Url),
+}
+
+impl ProxyDecision {
+    /// Go through the proxy at `url`, e.g. `http://proxy.example:8080` or
+    /// `socks5h://proxy.example:1080`.
+    pub fn proxy<U: IntoUrl>(url: U) -> crate::Result<ProxyDecision> {
+        let url = url.into_url()?;
+        url.clone().into_proxy_scheme()?;
+        Ok(ProxyDecision::Proxy(url))
+    }
+}
+
+/// Parses the return value of `FindProxyForURL`, such as
+/// `"PROXY proxy.example:8080; DIRECT"`.
+///
+/// The first entry the client supports is used: `DIRECT`, `PROXY`, `HTTP`,
+/// `HTTPS`, and with the `socks` feature `SOCKS`, `SOCKS4` and `SOCKS5`.
+/// The other entries are not tried when that proxy fails.
+impl FromStr for ProxyDecision {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<ProxyDecision> {
+        s.split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .find_map(parse_entry)
+            .ok_or_else(|| crate::error::builder(format!("no usable PAC entry in {:?}", s)))
+    }
+}
+
+fn parse_entry(entry: &str) -> Option<ProxyDecision> {
+    let mut parts = entry.split_whitespace();
+    let kind = parts.next()?.to_ascii_uppercase();
+    if kind == "DIRECT" {
+        return Some(ProxyDecision::Direct);
+    }
+
+    let scheme = match kind.as_str() {
+        "PROXY" | "HTTP" => "http",
+        "HTTPS" => "https",
+        "SOCKS" | "SOCKS4" => "socks4",
+        // Like browsers, let SOCKS5 proxies resolve the hosts.
+        "SOCKS5" => "socks5h",
+        _ => return None,
+    };
+    let addr = parts.next()?;
+
+    ProxyDecision::proxy(format!("{}://{}", scheme, addr).as_str()).ok()
+}
+
+impl fmt::Display for ProxyDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProxyDecision::Direct => f.write_str("DIRECT"),
+            ProxyDecision::Proxy(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+#[cfg(feature = "pac")]
+pub use self::script::PacScript;
+
+#[cfg(feature = "pac")]
+mod script {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::net::{IpAddr, ToSocketAddrs, UdpSocket};
+    use std::num::NonZeroUsize;
+    use std::sync::mpsc;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use antidote::Mutex;
+    use boa_engine::{js_string, Context, JsResult, JsString, JsValue, NativeFunction, Source};
+    use lru::LruCache;
+    use tokio::sync::oneshot;
+
+    use super::ProxyDecision;
+    use crate::{IntoUrl, Url};
+
+    /// How many decisions of a script are remembered.
+    const MAX_DECISIONS: usize = 1024;
+
+    /// How long a decision of the script, or a `dnsResolve` answer, is
+    /// reused. Scripts may depend on the time of day.
+    const TTL: Duration = Duration::from_secs(60);
+
+    type Query = (Url, Reply);
+
+    /// Where the script thread sends the return value of `FindProxyForURL`.
+    enum Reply {
+        Blocking(mpsc::SyncSender<Result<String, String>>),
+        Async(oneshot::Sender<Result<String, String>>),
+    }
+
+    /// A compiled PAC script.
+    ///
+    /// The script runs on a thread of its own, so the requests waiting for
+    /// it don't block the runtime. `dnsResolve`, `isResolvable` and
+    /// `isInNet` on host names block that thread on DNS, and their answers
+    /// are reused for a minute.
+    ///
+    /// The decision of the script is reused for a minute too, for all the
+    /// requests to the same scheme, host and port, as `FindProxyForURL`
+    /// sees nothing else of them.
+    ///
+    /// Cloning a `PacScript` is cheap, and clones share the thread and the
+    /// decisions.
+    ///
+    /// # Optional
+    ///
+    /// This requires the optional `pac` feature to be enabled.
+    #[derive(Clone)]
+    pub struct PacScript {
+        queries: Arc<mpsc::Sender<Query>>,
+        decisions: Arc<Mutex<LruCache<String, Decision>>>,
+    }
+
+    #[derive(Clone)]
+    struct Decision {
+        at: Instant,
+        result: Result<ProxyDecision, String>,
+    }
+
+    impl PacScript {
+        /// Compile a PAC script from its source.
+        ///
+        /// Fails if the script does not run, or does not define a
+        /// `FindProxyForURL` function.
+        pub fn new(source: impl Into<String>) -> crate::Result<PacScript> {
+            let source = source.into();
+            let (queries, rx) = mpsc::channel::<Query>();
+            let (ready_tx, ready_rx) = mpsc::sync_channel(1);
+
+            thread::Builder::new()
+                .name("rquest-pac".into())
+                .spawn(move || {
+                    let mut context = match init(&source) {
+                        Ok(context) => {
+                            let _ = ready_tx.send(Ok(()));
+                            context
+                        }
+                        Err(err) => {
+                            let _ = ready_tx.send(Err(err));
+                            return;
+                        }
+                    };
+
+                    // Runs until every `PacScript` clone is dropped.
+                    for (url, reply) in rx {
+                        let result = find_proxy(&mut context, &url);
+                        match reply {
+                            Reply::Blocking(tx) => {
+                                let _ = tx.send(result);
+                            }
+                            Reply::Async(tx) => {
+                                let _ = tx.send(result);
+                            }
+                        }
+                    }
+                })
+                .map_err(crate::error::builder)?;
+
+            ready_rx
+                .recv()
+                .map_err(crate::error::builder)?
+                .map_err(|err| crate::error::builder(format!("invalid PAC script: {}", err)))?;
+
+            Ok(PacScript {
+                queries: Arc::new(queries),
+                decisions: Arc::new(Mutex::new(LruCache::new(
+                    NonZeroUsize::new(MAX_DECISIONS).expect("non-zero"),
+                ))),
+            })
+        }
+
+        /// Download a PAC script, such as `http://wpad.example/wpad.dat`,
+        /// and compile it.
+        ///
+        /// The script is downloaded without any proxy.
+        pub async fn fetch<U: IntoUrl>(url: U) -> crate::Result<PacScript> {
+            let client = crate::Client::builder().no_proxy().build()?;
+            let source = client
+                .get(url)
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            PacScript::new(source)
+        }
+
+        /// Call the `FindProxyForURL` function of the script for `url`.
+        ///
+        /// This blocks until the script returns, unless its decision for the
+        /// scheme, host and port of `url` is known. The client calls the
+        /// script without blocking before sending a request.
+        pub fn find_proxy(&self, url: &Url) -> crate::Result<ProxyDecision> {
+            if let Some(result) = self.cached(url) {
+                return result;
+            }
+
+            let (reply, result) = mpsc::sync_channel(1);
+            self.queries
+                .send((url.clone(), Reply::Blocking(reply)))
+                .map_err(crate::error::builder)?;
+            let result = result.recv().map_err(crate::error::builder)?;
+            self.remember(url, result)
+        }
+
+        /// Call the `FindProxyForURL` function of the script for `url`,
+        /// waiting for it without blocking, so the next
+        /// [`find_proxy`](PacScript::find_proxy) returns at once.
+        pub(crate) async fn prefetch(&self, url: &Url) {
+            let (reply, result) = oneshot::channel();
+            if self
+                .queries
+                .send((url.clone(), Reply::Async(reply)))
+                .is_err()
+            {
+                return;
+            }
+            if let Ok(result) = result.await {
+                let _ = self.remember(url, result);
+            }
+        }
+
+        /// The decision of the script for `url`, if it is known and fresh.
+        pub(crate) fn cached(&self, url: &Url) -> Option<crate::Result<ProxyDecision>> {
+            let mut decisions = self.decisions.lock();
+            let decision = decisions.get(url.as_str())?;
+            if decision.at.elapsed() > TTL {
+                decisions.pop(url.as_str());
+                return None;
+            }
+            Some(
+                decision.result.clone().map_err(|err| {
+                    crate::error::builder(format!("FindProxyForURL failed: {}", err))
+                }),
+            )
+        }
+
+        fn remember(
+            &self,
+            url: &Url,
+            result: Result<String, String>,
+        ) -> crate::Result<ProxyDecision> {
+            let result = result.and_then(|ret| ret.parse().map_err(|err| format!("{}", err)));
+            self.decisions.lock().put(
+                url.as_str().to_owned(),
+                Decision {
+                    at: Instant::now(),
+                    result: result.clone(),
+                },
+            );
+            result.map_err(|err| crate::error::builder(format!("FindProxyForURL failed: {}", err)))
+        }
+    }
+
+    impl fmt::Debug for PacScript {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("PacScript").finish()
+        }
+    }
+
+    fn init(source: &str) -> Result<Context, String> {
+        let mut context = Context::default();
+
+        context
+            .register_global_builtin_callable(
+                js_string!("dnsResolve"),
+                1,
+                NativeFunction::from_fn_ptr(dns_resolve),
+            )
+            .map_err(|err| err.to_string())?;
+        context
+            .register_global_builtin_callable(
+                js_string!("myIpAddress"),
+                0,
+                NativeFunction::from_fn_ptr(my_ip_address),
+            )
+            .map_err(|err| err.to_string())?;
+        context
+            .register_global_builtin_callable(
+                js_string!("alert"),
+                1,
+                NativeFunction::from_fn_ptr(alert),
+            )
+            .map_err(|err| err.to_string())?;
+
+        context
+            .eval(Source::from_bytes(PAC_UTILS))
+            .map_err(|err| err.to_string())?;
+        context
+            .eval(Source::from_bytes(source.as_bytes()))
+            .map_err(|err| err.to_string())?;
+
+        let global = context.global_object();
+        let func = global
+            .get(js_string!("FindProxyForURL"), &mut context)
+            .map_err(|err| err.to_string())?;
+        if !func.is_callable() {
+            return Err("FindProxyForURL is not defined".into());
+        }
+
+        Ok(context)
+    }
+
+    fn find_proxy(context: &mut Context, url: &Url) -> Result<String, String> {
+        let host = url.host_str().unwrap_or_default();
+
+        let call = |context: &mut Context| -> JsResult<String> {
+            let global = context.global_object();
+            let func = global.get(js_string!("FindProxyForURL"), context)?;
+            let func = func.as_callable().ok_or_else(|| {
+                boa_engine::JsNativeError::typ().with_message("FindProxyForURL is not a function")
+            })?;
+            let ret = func.call(
+                &JsValue::undefined(),
+                &[
+                    JsValue::from(JsString::from(url.as_str())),
+                    JsValue::from(JsString::from(host)),
+                ],
+                context,
+            )?;
+            Ok(ret.to_string(context)?.to_std_string_escaped())
+        };
+
+        call(context).map_err(|err| err.to_string())
+    }
+
+    fn arg(args: &[JsValue], context: &mut Context) -> JsResult<String> {
+        let value = args.first().cloned().unwrap_or_default();
+        Ok(value.to_string(context)?.to_std_string_escaped())
+    }
+
+    thread_local! {
+        /// The answers of `dnsResolve` on the script thread, and when they
+        /// were looked up.
+        static RESOLVED: RefCell<HashMap<String, (Instant, Option<IpAddr>)>> =
+            RefCell::new(HashMap::new());
+    }
+
+    fn dns_resolve(_: &JsValue, args: &[JsValue], context: &mut Context) -> JsResult<JsValue> {
+        let host = arg(args, context)?;
+        let cached = RESOLVED.with_borrow(|resolved| {
+            resolved
+                .get(&host)
+                .filter(|(at, _)| at.elapsed() <= TTL)
+                .map(|&(_, ip)| ip)
+        });
+        let ip = cached.unwrap_or_else(|| {
+            let ip = (host.as_str(), 0)
+                .to_socket_addrs()
+                .ok()
+                .and_then(|mut addrs| addrs.find(|addr| addr.is_ipv4()))
+                .map(|addr| addr.ip());
+            RESOLVED.with_borrow_mut(|resolved| {
+                resolved.retain(|_, (at, _)| at.elapsed() <= TTL);
+                resolved.insert(host, (Instant::now(), ip));
+            });
+            ip
+        });
+
+        Ok(match ip {
+            Some(ip) => JsValue::from(JsString::from(ip.to_string().as_str())),
+            None => JsValue::null(),
+        })
+    }
+
+    fn my_ip_address(_: &JsValue, _: &[JsValue], _: &mut Context) -> JsResult<JsValue> {
+        // Connecting a UDP socket sends nothing, but picks the address of the
+        // interface with the default route.
+        let ip = UdpSocket::bind("0.0.0.0:0")
+            .and_then(|socket| {
+                socket.connect("192.0.2.1:80")?;
+                socket.local_addr()
+            })
+            .map(|addr| addr.ip())
+            .unwrap_or(IpAddr::from([127, 0, 0, 1]));
+
+        Ok(JsValue::from(JsString::from(ip.to_string().as_str())))
+    }
+
+    fn alert(_: &JsValue, args: &[JsValue], context: &mut Context) -> JsResult<JsValue> {
+        log::debug!("PAC alert: {}", arg(args, context)?);
+        Ok(JsValue::undefined())
+    }
+
+    /// The helper functions PAC scripts may call, besides `dnsResolve`,
+    /// `myIpAddress` and `alert`.
+    const PAC_UTILS: &str = r#"
+function isPlainHostName(host) {
+    return host.indexOf('.') < 0 && host.indexOf(':') < 0;
+}
+
+function dnsDomainIs(host, domain) {
+    return host.length >= domain.length &&
+        host.substring(host.length - domain.length) == domain;
+}
+
+function localHostOrDomainIs(host, hostdom) {
+    return host == hostdom || hostdom.lastIndexOf(host + '.', 0) == 0;
+}
+
+function isResolvable(host) {
+    return dnsResolve(host) != null;
+}
+
+function dnsDomainLevels(host) {
+    return host.split('.').length - 1;
+}
+
+function __ipv4(addr) {
+    var m = /^(\d{1,3})\.(\d{1,3})\.(\d{1,3})\.(\d{1,3})$/.exec(addr);
+    if (m == null || m[1] > 255 || m[2] > 255 || m[3] > 255 || m[4] > 255) {
+        return null;
+    }
+    return ((m[1] << 24) | (m[2] << 16) | (m[3] << 8) | m[4]) >>> 0;
+}
+
+function isInNet(host, pattern, mask) {
+    var ip = __ipv4(host);
+    if (ip == null) {
+        var resolved = dnsResolve(host);
+        ip = resolved == null ? null : __ipv4(resolved);
+    }
+    var pat = __ipv4(pattern);
+    var m = __ipv4(mask);
+    if (ip == null || pat == null || m == null) {
+        return false;
+    }
+    return ((ip & m) >>> 0) == ((pat & m) >>> 0);
+}
+
+function shExpMatch(str, pattern) {
+    var re = pattern
+        .replace(/[.+^${}()|[\]\\]/g, '\\$&')
+        .replace(/\*/g, '.*')
+        .replace(/\?/g, '.');
+    return new RegExp('^' + re + '$').test(str);
+}
+
+function __args(args) {
+    var list = Array.prototype.slice.call(args);
+    var gmt = list.length > 0 && list[list.length - 1] == 'GMT';
+    if (gmt) {
+        list.pop();
+    }
+    return { list: list, gmt: gmt, now: new Date() };
+}
+
+function __inRange(start, now, end) {
+    return start <= end ? start <= now && now <= end : now >= start || now <= end;
+}
+
+var __days = ['SUN', 'MON', 'TUE', 'WED', 'THU', 'FRI', 'SAT'];
+var __months = ['JAN', 'FEB', 'MAR', 'APR', 'MAY', 'JUN',
+    'JUL', 'AUG', 'SEP', 'OCT', 'NOV', 'DEC'];
+
+function weekdayRange() {
+    var a = __args(arguments);
+    var today = a.gmt ? a.now.getUTCDay() : a.now.getDay();
+    var start = __days.indexOf(String(a.list[0]).toUpperCase());
+    var end = a.list.length > 1 ? __days.indexOf(String(a.list[1]).toUpperCase()) : start;
+    if (start < 0 || end < 0) {
+        return false;
+    }
+    return __inRange(start, today, end);
+}
+
+function dateRange() {
+    var a = __args(arguments);
+    var now = {
+        d: a.gmt ? a.now.getUTCDate() : a.now.getDate(),
+        m: a.gmt ? a.now.getUTCMonth() : a.now.getMonth(),
+        y: a.gmt ? a.now.getUTCFullYear() : a.now.getFullYear()
+    };
+    function parse(list) {
+        var date = {};
+        for (var i = 0; i < list.length; i++) {
+            if (typeof list[i] == 'string') {
+                date.m = __months.indexOf(list[i].toUpperCase());
+            } else if (list[i] > 31) {
+                date.y = list[i];
+            } else {
+                date.d = list[i];
+            }
+        }
+        return date;
+    }
+    function value(date, fields) {
+        return (fields.y !== undefined ? date.y * 10000 : 0) +
+            (fields.m !== undefined ? date.m * 100 : 0) +
+            (fields.d !== undefined ? date.d : 0);
+    }
+    if (a.list.length == 1) {
+        var date = parse(a.list);
+        return value(date, date) == value(now, date);
+    }
+    var half = a.list.length / 2;
+    var start = parse(a.list.slice(0, half));
+    var end = parse(a.list.slice(half));
+    return __inRange(value(start, start), value(now, start), value(end, start));
+}
+
+function timeRange() {
+    var a = __args(arguments);
+    var l = a.list;
+    var now = a.gmt
+        ? a.now.getUTCHours() * 3600 + a.now.getUTCMinutes() * 60 + a.now.getUTCSeconds()
+        : a.now.getHours() * 3600 + a.now.getMinutes() * 60 + a.now.getSeconds();
+    var start, end;
+    switch (l.length) {
+        case 1: start = l[0] * 3600; end = start + 3599; break;
+        case 2: start = l[0] * 3600; end = l[1] * 3600 - 1; break;
+        case 4: start = l[0] * 3600 + l[1] * 60; end = l[2] * 3600 + l[3] * 60 - 1; break;
+        case 6: start = l[0] * 3600 + l[1] * 60 + l[2]; end = l[3] * 3600 + l[4] * 60 + l[5]; break;
+        default: return false;
+    }
+    return __inRange(start, now, end);
+}
+"#;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_pac_result() {
+        assert_eq!(
+            "PROXY proxy.example:8080; DIRECT"
+                .parse::<ProxyDecision>()
+                .unwrap(),
+            ProxyDecision::Proxy("http://proxy.example:8080".parse().unwrap())
+        );
+        assert_eq!(
+            " https secure.example:443 "
+                .parse::<ProxyDecision>()
+                .unwrap(),
+            ProxyDecision::Proxy("https://secure.example:443".parse().unwrap())
+        );
+        assert_eq!(
+            "DIRECT".parse::<ProxyDecision>().unwrap(),
+            ProxyDecision::Direct
+        );
+        assert!("".parse::<ProxyDecision>().is_err());
+        assert!("QUIC example:443".parse::<ProxyDecision>().is_err());
+    }
+
+    #[test]
+    fn skip_unsupported_pac_entries() {
+        let decision = "QUIC example:443; PROXY fallback.example:3128"
+            .parse::<ProxyDecision>()
+            .unwrap();
+        assert_eq!(
+            decision,
+            ProxyDecision::Proxy("http://fallback.example:3128".parse().unwrap())
+        );
+    }
+
+    #[cfg(feature = "pac")]
+    #[test]
+    fn evaluate_pac_script() {
+        let script = PacScript::new(
+            r#"
+            function FindProxyForURL(url, host) {
+                if (isPlainHostName(host) || dnsDomainIs(host, ".internal")) {
+                    return "DIRECT";
+                }
+                if (shExpMatch(url, "https://*")) {
+                    return "PROXY secure.example:3128";
+                }
+                return "PROXY proxy.example:8080; DIRECT";
+            }
+            "#,
+        )
+        .unwrap();
+
+        let find = |url: &str| script.find_proxy(&url.parse().unwrap()).unwrap();
+        assert_eq!(find("http://intranet/"), ProxyDecision::Direct);
+        assert_eq!(find("http://wiki.internal/"), ProxyDecision::Direct);
+        assert_eq!(
+            find("https://hyper.rs/"),
+            ProxyDecision::Proxy("http://secure.example:3128".parse().unwrap())
+        );
+        assert_eq!(
+            find("http://hyper.rs/"),
+            ProxyDecision::Proxy("http://proxy.example:8080".parse().unwrap())
+        );
+
+        assert!(PacScript::new("function nope() {}").is_err());
+    }
+
+    #[cfg(feature = "pac")]
+    #[tokio::test]
+    async fn pac_decisions_are_reused_per_origin() {
+        let script = PacScript::new(
+            r#"
+            var calls = 0;
+            function FindProxyForURL(url, host) {
+                calls++;
+                return "PROXY proxy" + calls + ".example:8080";
+            }
+            "#,
+        )
+        .unwrap();
+
+        let url: Url = "http://hyper.rs/".parse().unwrap();
+        assert!(script.cached(&url).is_none());
+        script.prefetch(&url).await;
+        let first = ProxyDecision::Proxy("http://proxy1.example:8080".parse().unwrap());
+        assert_eq!(script.cached(&url).unwrap().unwrap(), first);
+        assert_eq!(script.find_proxy(&url).unwrap(), first);
+
+        let other: Url = "https://hyper.rs/".parse().unwrap();
+        assert_eq!(
+            script.find_proxy(&other).unwrap(),
+            ProxyDecision::Proxy("http://proxy2.example:8080".parse().unwrap())
+        );
+    }
+}
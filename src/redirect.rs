@@ -7,7 +7,9 @@
 use std::fmt;
 use std::{error::Error as StdError, sync::Arc};
 
-use crate::header::{HeaderMap, AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION, WWW_AUTHENTICATE};
+use crate::header::{
+    HeaderMap, HeaderValue, AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION, WWW_AUTHENTICATE,
+};
 use http::Method;
 use hyper2::StatusCode;
 
@@ -22,9 +24,34 @@ use crate::Url;
 ///   the allowed maximum redirect hops in a chain.
 /// - `none` can be used to disable all redirect behavior.
 /// - `custom` can be used to create a customized policy.
+///
+/// How the request method changes when following a `301`, `302` or `303`
+/// redirect is controlled separately, with [`Policy::method_rewrite`].
 #[derive(Clone)]
 pub struct Policy {
     inner: PolicyKind,
+    method_rewrite: MethodRewrite,
+}
+
+/// How the request method is rewritten when following a `301 Moved
+/// Permanently`, `302 Found` or `303 See Other` redirect.
+///
+/// Whenever the method is rewritten to `GET`, the request body and its
+/// `Content-*` and `Transfer-Encoding` headers are dropped. A `HEAD` request
+/// always stays a `HEAD` request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MethodRewrite {
+    /// Rewrite every method to `GET`, for all three status codes.
+    ///
+    /// This matches HTTP clients such as OkHttp, and is the default.
+    #[default]
+    AlwaysGet,
+    /// Rewrite `POST` to `GET` on `301` and `302`, and every method to `GET`
+    /// on `303`, as the Fetch standard requires of browsers.
+    Browser,
+    /// Keep the method and body on `301` and `302`, and rewrite to `GET` only
+    /// on `303`, as RFC 9110 specifies.
+    Strict,
 }
 
 /// A type that holds information on the next request and previous requests
@@ -38,6 +65,18 @@ pub struct Attempt<'a> {
     previous: &'a [Url],
 }
 
+/// A redirect that was followed on the way to a response.
+///
+/// Keeps the `Location` header exactly as the server sent it, next to the
+/// `Url` it was resolved to.
+#[derive(Debug, Clone)]
+pub struct History {
+    status: StatusCode,
+    previous: Url,
+    location: HeaderValue,
+    next: Url,
+}
+
 /// An action to perform when a redirect status code is found.
 #[derive(Debug)]
 pub struct Action {
@@ -51,6 +90,7 @@ impl Policy {
     pub fn limited(max: usize) -> Self {
         Self {
             inner: PolicyKind::Limit(max),
+            method_rewrite: MethodRewrite::default(),
         }
     }
 
@@ -58,6 +98,7 @@ impl Policy {
     pub fn none() -> Self {
         Self {
             inner: PolicyKind::None,
+            method_rewrite: MethodRewrite::default(),
         }
     }
 
@@ -105,9 +146,28 @@ impl Policy {
     {
         Self {
             inner: PolicyKind::Custom(Arc::new(policy)),
+            method_rewrite: MethodRewrite::default(),
         }
     }
 
+    /// Set how the request method is rewritten on `301`, `302` and `303`
+    /// redirects.
+    ///
+    /// Browsers and app HTTP stacks differ here, so an impersonated client
+    /// should match the behavior of its target.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rquest::redirect::{MethodRewrite, Policy};
+    ///
+    /// let policy = Policy::default().method_rewrite(MethodRewrite::Browser);
+    /// ```
+    pub fn method_rewrite(mut self, rewrite: MethodRewrite) -> Self {
+        self.method_rewrite = rewrite;
+        self
+    }
+
     /// Apply this policy to a given [`Attempt`] to produce a [`Action`].
     ///
     /// # Note
@@ -160,6 +220,22 @@ impl Policy {
         .inner
     }
 
+    /// Returns true if following a redirect with `status` turns a `method`
+    /// request into a `GET` request without a body.
+    pub(crate) fn rewrites_to_get(&self, status: StatusCode, method: &Method) -> bool {
+        match status {
+            StatusCode::SEE_OTHER => true,
+            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => match self.method_rewrite {
+                MethodRewrite::AlwaysGet => true,
+                MethodRewrite::Browser => {
+                    matches!(*method, Method::GET | Method::HEAD | Method::POST)
+                }
+                MethodRewrite::Strict => false,
+            },
+            _ => false,
+        }
+    }
+
     pub(crate) fn remove_sensitive_headers(
         headers: &mut HeaderMap,
         next: &Url,
@@ -240,6 +316,41 @@ impl Attempt<'_> {
     }
 }
 
+impl History {
+    pub(crate) fn new(status: StatusCode, previous: Url, location: HeaderValue, next: Url) -> Self {
+        History {
+            status,
+            previous,
+            location,
+            next,
+        }
+    }
+
+    /// Get the status code of the redirect response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Get the URL that responded with the redirect.
+    pub fn previous(&self) -> &Url {
+        &self.previous
+    }
+
+    /// Get the raw `Location` header value, as sent by the server.
+    pub fn location(&self) -> &HeaderValue {
+        &self.location
+    }
+
+    /// Get the URL the `Location` header was resolved to.
+    pub fn url(&self) -> &Url {
+        &self.next
+    }
+}
+
+/// The redirects followed for a response, stored in its extensions.
+#[derive(Clone, Debug)]
+pub(crate) struct RedirectHistory(pub(crate) Vec<History>);
+
 #[derive(Clone)]
 enum PolicyKind {
     Custom(Arc<dyn Fn(Attempt) -> Action + Send + Sync + 'static>),
@@ -249,7 +360,10 @@ enum PolicyKind {
 
 impl fmt::Debug for Policy {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_tuple("Policy").field(&self.inner).finish()
+        f.debug_tuple("Policy")
+            .field(&self.inner)
+            .field(&self.method_rewrite)
+            .finish()
     }
 }
 
@@ -427,3 +541,37 @@ fn test_proxy_auth_redirect_headers() {
     Policy::remove_sensitive_headers(&mut headers, &next, &prev, true);
     assert_eq!(headers, filtered_headers);
 }
+
+#[test]
+fn test_redirect_method_rewrite() {
+    let statuses = [
+        StatusCode::MOVED_PERMANENTLY,
+        StatusCode::FOUND,
+        StatusCode::SEE_OTHER,
+    ];
+
+    let policy = Policy::default();
+    for status in statuses {
+        assert!(policy.rewrites_to_get(status, &Method::POST));
+        assert!(policy.rewrites_to_get(status, &Method::PUT));
+    }
+
+    let policy = Policy::default().method_rewrite(MethodRewrite::Browser);
+    for status in statuses {
+        assert!(policy.rewrites_to_get(status, &Method::POST));
+        assert_eq!(
+            policy.rewrites_to_get(status, &Method::PUT),
+            status == StatusCode::SEE_OTHER
+        );
+    }
+
+    let policy = Policy::default().method_rewrite(MethodRewrite::Strict);
+    for status in statuses {
+        assert_eq!(
+            policy.rewrites_to_get(status, &Method::POST),
+            status == StatusCode::SEE_OTHER
+        );
+    }
+
+    assert!(!policy.rewrites_to_get(StatusCode::TEMPORARY_REDIRECT, &Method::POST));
+}
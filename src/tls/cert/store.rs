@@ -11,6 +11,7 @@ use boring2::{
 use std::path::Path;
 
 /// A collection of certificates Store.
+#[derive(Clone)]
 pub struct RootCertStore(X509Store);
 
 /// ====== impl RootCertStore ======
@@ -208,7 +209,7 @@ impl RootCertStore {
 }
 
 /// The root certificate store.
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub enum RootCertStoreProvider {
     /// An owned `X509Store`.
     Owned(RootCertStore),
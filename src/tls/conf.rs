@@ -1,5 +1,6 @@
 use super::{AlpnProtos, AlpsProtos, RootCertStoreProvider, TlsVersion};
-use boring2::ssl::{CertCompressionAlgorithm, SslCurve};
+use crate::util::SeededRandom;
+use boring2::ssl::{CertCompressionAlgorithm, ExtensionType, SslCurve};
 use std::borrow::Cow;
 use typed_builder::TypedBuilder;
 
@@ -193,6 +194,29 @@ impl Default for TlsConfig {
     }
 }
 
+impl TlsConfig {
+    /// Removes the random parts of the ClientHello that can be chosen: the
+    /// GREASE values are left out, and permuted extensions get an order
+    /// drawn from `seed` instead of a new one per connection.
+    pub(crate) fn make_deterministic(&mut self, seed: u64) {
+        self.grease_enabled = Some(false);
+        self.enable_ech_grease = false;
+        if self.permute_extensions == Some(true) {
+            self.permute_extensions = Some(false);
+            if self.extension_permutation_indices.is_none() {
+                let random = SeededRandom::new(seed);
+                let len = ExtensionType::BORING_SSLEXTENSION_PERMUTATION.len();
+                let mut indices: Vec<u8> = (0..len as u8).collect();
+                for i in (1..len).rev() {
+                    let j = (random.next() % (i as u64 + 1)) as usize;
+                    indices.swap(i, j);
+                }
+                self.extension_permutation_indices = Some(indices.into());
+            }
+        }
+    }
+}
+
 /// A trait for converting various types into an optional `Cow` containing a slice of `CertCompressionAlgorithm`.
 ///
 /// This trait is used to provide a unified way to convert different types
@@ -1,34 +1,39 @@
 /// referrer: https://github.com/cloudflare/boring/blob/master/hyper-boring/src/lib.rs
 use super::cache::{SessionCache, SessionKey};
-use super::{key_index, HandshakeSettings, MaybeHttpsStream};
+use super::{client_hello, key_index, HandshakeSettings, MaybeHttpsStream};
 
 use crate::connect::HttpConnector;
+use crate::dns::svcb::HttpsRecords;
 use crate::error::BoxError;
 use crate::tls::ext::SslRefExt;
-use crate::tls::{ConnectConfigurationExt, SslConnectorBuilderExt, TlsConfig, TlsResult};
-use crate::util::client::connect::Connection;
+use crate::tls::{
+    AlpnProtos, ConnectConfigurationExt, SslConnectorBuilderExt, TlsConfig, TlsResult,
+};
+use crate::util::client::connect::{Connection, HttpInfo};
 use crate::util::rt::TokioIo;
 
 use antidote::Mutex;
 use boring2::error::ErrorStack;
 use boring2::ssl::{
     ConnectConfiguration, Ssl, SslConnector, SslConnectorBuilder, SslMethod, SslOptions, SslRef,
-    SslSessionCacheMode,
+    SslSession, SslSessionCacheMode,
 };
-use http::uri::Scheme;
+use http::uri::{Authority, Scheme};
 use http::Uri;
 use hyper2::rt::{Read, Write};
 
+use tokio::net::TcpStream;
 use tokio_boring2::SslStream;
 use tower_service::Service;
 
 use std::error::Error;
 use std::fmt::Debug;
 use std::future::Future;
-use std::net::{IpAddr, Ipv6Addr};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 /// A Connector using BoringSSL to support `http` and `https` schemes.
 #[derive(Clone)]
@@ -55,6 +60,12 @@ impl HttpsConnector<HttpConnector> {
             _ => (),
         }
 
+        // Set the scope id of an IPv6 link-local destination
+        http.set_scope_id(dst.take_scope_id());
+
+        // Set the address of a host, for this request only
+        http.set_resolve_to(dst.take_resolve_to());
+
         // Set the interface
         #[cfg(any(
             target_os = "android",
@@ -77,6 +88,41 @@ impl HttpsConnector<HttpConnector> {
         connector.set_ssl_callback(move |ssl, _| ssl.alpn_protos(alpn_protos));
         connector
     }
+
+    /// Creates a connector to reach an HTTPS proxy with the same network
+    /// settings, using the proxy's TLS connector and offering `alpn_protos`.
+    pub fn proxy_connector(
+        &self,
+        connector: BoringTlsConnector,
+        alpn_protos: AlpnProtos,
+    ) -> HttpsConnector<HttpConnector> {
+        let mut proxy = HttpsConnector::with_connector(self.http.clone(), connector);
+        proxy.set_ssl_callback(move |ssl, _| ssl.alpn_protos(Some(alpn_protos)));
+        proxy
+    }
+
+    /// Connects to `uri` at `authority`, an alternative service of its
+    /// origin. TLS is still set up for the host of `uri`.
+    pub(crate) async fn call_via(
+        &mut self,
+        uri: Uri,
+        authority: Authority,
+    ) -> Result<MaybeHttpsStream<TokioIo<TcpStream>>, BoxError> {
+        let mut parts = uri.clone().into_parts();
+        parts.authority = Some(authority);
+        let conn = self.http.call(Uri::from_parts(parts)?).await?;
+
+        if uri.scheme() != Some(&Scheme::HTTPS) {
+            return Ok(MaybeHttpsStream::Http(conn));
+        }
+
+        let remote_addr = remote_addr(&conn);
+        self.inner
+            .connect(&uri, tls_host(&uri)?, conn, remote_addr)
+            .await
+            .map(TokioIo::new)
+            .map(MaybeHttpsStream::Https)
+    }
 }
 
 impl<S, T> HttpsConnector<S>
@@ -118,7 +164,7 @@ where
     where
         A: Read + Write + Unpin + Send + Sync + Debug + 'static,
     {
-        self.inner.connect(uri, host, conn).await
+        self.inner.connect(uri, host, conn, None).await
     }
 }
 
@@ -135,6 +181,9 @@ struct Inner {
     callback: Option<Callback>,
     ssl_callback: Option<SslCallback>,
     skip_session_ticket: bool,
+    https_records: Option<Arc<HttpsRecords>>,
+    handshake_timeout: Option<Duration>,
+    capture_client_hello: bool,
 }
 
 type Callback =
@@ -279,29 +328,88 @@ impl BoringTlsConnector {
                 callback: Some(callback),
                 ssl_callback: None,
                 skip_session_ticket: settings.skip_session_ticket,
+                https_records: None,
+                handshake_timeout: None,
+                capture_client_hello: false,
             },
         }
     }
+
+    /// Offers Encrypted Client Hello to hosts whose HTTPS record carries an
+    /// ECH configuration.
+    pub(crate) fn set_https_records(&mut self, https_records: Option<Arc<HttpsRecords>>) {
+        self.inner.https_records = https_records;
+    }
+
+    pub(crate) fn https_records(&self) -> Option<Arc<HttpsRecords>> {
+        self.inner.https_records.clone()
+    }
+
+    /// The cached sessions of every host, to resume them in another client.
+    pub(crate) fn sessions(&self) -> Vec<(Authority, SslSession)> {
+        self.inner.cache.as_ref().map_or_else(Vec::new, |cache| {
+            cache
+                .lock()
+                .iter()
+                .map(|(key, session)| (key.0.clone(), session.clone()))
+                .collect()
+        })
+    }
+
+    /// Cache `session` to resume with `authority`, if sessions are cached.
+    pub(crate) fn add_session(&self, authority: Authority, session: SslSession) {
+        if let Some(ref cache) = self.inner.cache {
+            cache.lock().insert(SessionKey(authority), session);
+        }
+    }
+
+    /// Fails the TLS handshakes that take longer than `timeout`.
+    pub(crate) fn set_handshake_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.handshake_timeout = timeout;
+    }
+
+    pub(crate) fn handshake_timeout(&self) -> Option<Duration> {
+        self.inner.handshake_timeout
+    }
+
+    /// Records the ClientHello of the handshakes, to be read back with
+    /// [`client_hello`](crate::tls::client_hello).
+    pub(crate) fn set_capture_client_hello(&mut self, enabled: bool) {
+        self.inner.capture_client_hello = enabled;
+    }
+
+    pub(crate) fn capture_client_hello(&self) -> bool {
+        self.inner.capture_client_hello
+    }
 }
 
 impl Inner {
     /// Connects to the given URI using the given connection.
     ///
-    /// This function is used to connect to the given URI using the given connection.
+    /// This function is used to connect to the given URI using the given connection,
+    /// made to `remote_addr`.
     pub async fn connect<A>(
         &self,
         uri: &Uri,
         host: &str,
         conn: A,
+        remote_addr: Option<SocketAddr>,
     ) -> Result<SslStream<TokioIo<A>>, BoxError>
     where
         A: Read + Write + Unpin + Send + Sync + Debug + 'static,
     {
         let ssl = self.setup_ssl(uri, host)?;
-        tokio_boring2::SslStreamBuilder::new(ssl, TokioIo::new(conn))
-            .connect()
-            .await
-            .map_err(Into::into)
+        let handshake = tokio_boring2::SslStreamBuilder::new(ssl, TokioIo::new(conn)).connect();
+
+        match self.handshake_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, handshake).await {
+                Ok(stream) => stream.map_err(|err| handshake_error(err, remote_addr)),
+                Err(_elapsed) => Err(Box::new(crate::error::TimedOut) as BoxError),
+            },
+            None => handshake
+                .await
+                .map_err(|err| handshake_error(err, remote_addr)),
+        }
     }
 
     fn setup_ssl(&self, uri: &Uri, host: &str) -> Result<Ssl, ErrorStack> {
@@ -332,10 +440,24 @@ impl Inner {
 
         let mut ssl = conf.into_ssl(host)?;
 
+        if let Some(record) = self
+            .https_records
+            .as_ref()
+            .and_then(|records| records.get(host))
+        {
+            if let Some(ech_config) = record.ech_config() {
+                ssl.set_ech_config_list(ech_config)?;
+            }
+        }
+
         if let Some(ref ssl_callback) = self.ssl_callback {
             ssl_callback(&mut ssl, uri)?;
         }
 
+        if self.capture_client_hello {
+            client_hello::capture(&mut ssl)?;
+        }
+
         Ok(ssl)
     }
 }
@@ -373,25 +495,10 @@ where
 
         let f = async move {
             let conn = connect.await.map_err(Into::into)?;
-
-            let mut host = uri.host().ok_or("URI missing host")?;
-
-            // If `host` is an IPv6 address, we must strip away the square brackets that surround
-            // it (otherwise, boring will fail to parse the host as an IP address, eventually
-            // causing the handshake to fail due a hostname verification error).
-            if !host.is_empty() {
-                let last = host.len() - 1;
-                let mut chars = host.chars();
-
-                if let (Some('['), Some(']')) = (chars.next(), chars.last()) {
-                    if host[1..last].parse::<Ipv6Addr>().is_ok() {
-                        host = &host[1..last];
-                    }
-                }
-            }
+            let remote_addr = remote_addr(&conn);
 
             inner
-                .connect(&uri, host, conn)
+                .connect(&uri, tls_host(&uri)?, conn, remote_addr)
                 .await
                 .map(TokioIo::new)
                 .map(MaybeHttpsStream::Https)
@@ -400,3 +507,51 @@ where
         Box::pin(f)
     }
 }
+
+/// The remote address `conn` is connected to, if it's known.
+fn remote_addr<T: Connection>(conn: &T) -> Option<SocketAddr> {
+    let mut extensions = http::Extensions::new();
+    conn.connected().get_extras(&mut extensions);
+    extensions.get::<HttpInfo>().map(HttpInfo::remote_addr)
+}
+
+/// Keep what BoringSSL reported about a failed handshake with `remote_addr`.
+fn handshake_error<S>(
+    err: tokio_boring2::HandshakeError<S>,
+    remote_addr: Option<SocketAddr>,
+) -> BoxError
+where
+    S: Debug + Send + Sync + 'static,
+{
+    let verify_result = err
+        .ssl()
+        .and_then(|ssl| ssl.verify_result().err())
+        .map(|err| err.as_raw());
+
+    Box::new(crate::error::TlsHandshake {
+        remote_addr,
+        verify_result,
+        source: err.into(),
+    })
+}
+
+/// The host of `uri` to set up TLS for.
+fn tls_host(uri: &Uri) -> Result<&str, BoxError> {
+    let mut host = uri.host().ok_or("URI missing host")?;
+
+    // If `host` is an IPv6 address, we must strip away the square brackets that surround
+    // it (otherwise, boring will fail to parse the host as an IP address, eventually
+    // causing the handshake to fail due a hostname verification error).
+    if !host.is_empty() {
+        let last = host.len() - 1;
+        let mut chars = host.chars();
+
+        if let (Some('['), Some(']')) = (chars.next(), chars.last()) {
+            if host[1..last].parse::<Ipv6Addr>().is_ok() {
+                host = &host[1..last];
+            }
+        }
+    }
+
+    Ok(host)
+}
@@ -84,6 +84,13 @@ impl SessionCache {
         Some(session)
     }
 
+    /// The sessions of every key, the most recent last.
+    pub fn iter(&self) -> impl Iterator<Item = (&SessionKey, &SslSession)> {
+        self.sessions
+            .iter()
+            .flat_map(|(key, sessions)| sessions.iter().map(move |session| (key, &session.0)))
+    }
+
     pub fn remove(&mut self, session: &SslSessionRef) {
         let key = match self.reverse.remove(session.id()) {
             Some(key) => key,
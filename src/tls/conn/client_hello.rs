@@ -0,0 +1,66 @@
+//! Capturing the ClientHello of handshakes, to debug fingerprints.
+use std::ffi::{c_int, c_void};
+use std::sync::{LazyLock, OnceLock};
+
+use boring2::ex_data::Index;
+use boring2::ssl::{Ssl, SslRef};
+use boring_sys as ffi;
+use bytes::Bytes;
+use foreign_types::ForeignTypeRef;
+
+use crate::tls::TlsResult;
+
+/// The content type of handshake records.
+const SSL3_RT_HANDSHAKE: c_int = 22;
+
+/// The type of ClientHello handshake messages.
+const SSL3_MT_CLIENT_HELLO: u8 = 1;
+
+/// The ClientHello of a connection, once written.
+type Slot = OnceLock<Bytes>;
+
+fn slot_index() -> TlsResult<Index<Ssl, Slot>> {
+    static IDX: LazyLock<TlsResult<Index<Ssl, Slot>>> = LazyLock::new(Ssl::new_ex_index);
+    IDX.clone()
+}
+
+/// Record the ClientHello `ssl` writes.
+pub(super) fn capture(ssl: &mut SslRef) -> TlsResult<()> {
+    ssl.set_ex_data(slot_index()?, Slot::new());
+    unsafe { ffi::SSL_set_msg_callback(ssl.as_ptr(), Some(on_message)) };
+    Ok(())
+}
+
+/// The ClientHello `ssl` wrote, with its handshake header, if it was
+/// captured.
+///
+/// After a HelloRetryRequest, this is the first ClientHello.
+pub(crate) fn client_hello(ssl: &SslRef) -> Option<Bytes> {
+    ssl.ex_data(slot_index().ok()?)?.get().cloned()
+}
+
+unsafe extern "C" fn on_message(
+    is_write: c_int,
+    _version: c_int,
+    content_type: c_int,
+    buf: *const c_void,
+    len: usize,
+    ssl: *mut ffi::SSL,
+    _arg: *mut c_void,
+) {
+    if is_write == 0 || content_type != SSL3_RT_HANDSHAKE || buf.is_null() || len == 0 {
+        return;
+    }
+
+    // SAFETY: BoringSSL hands over `len` readable bytes, for the duration
+    // of the call.
+    let message = std::slice::from_raw_parts(buf.cast::<u8>(), len);
+    if message[0] != SSL3_MT_CLIENT_HELLO {
+        return;
+    }
+
+    let ssl = SslRef::from_ptr(ssl);
+    if let Some(slot) = slot_index().ok().and_then(|idx| ssl.ex_data(idx)) {
+        let _ = slot.set(Bytes::copy_from_slice(message));
+    }
+}
@@ -1,6 +1,7 @@
 //! Hyper SSL support via BoringSSL.
 mod boring;
 mod cache;
+mod client_hello;
 
 use crate::tls::{AlpsProtos, TlsResult};
 use crate::util::client::connect::{Connected, Connection};
@@ -20,6 +21,7 @@ use tokio_boring2::SslStream;
 use typed_builder::TypedBuilder;
 
 pub use self::boring::{BoringTlsConnector, HttpsConnector};
+pub(crate) use self::client_hello::client_hello;
 
 fn key_index() -> TlsResult<Index<Ssl, SessionKey>> {
     static IDX: LazyLock<TlsResult<Index<Ssl, SessionKey>>> = LazyLock::new(Ssl::new_ex_index);
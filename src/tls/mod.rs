@@ -9,11 +9,14 @@ mod cert;
 mod conf;
 mod conn;
 mod ext;
+mod pin;
 
 use boring2::{error::ErrorStack, ssl::SslVersion};
 
+pub(crate) use self::conn::client_hello;
 pub use self::conn::{BoringTlsConnector, HttpsConnector, MaybeHttpsStream};
 pub use self::ext::{ConnectConfigurationExt, SslConnectorBuilderExt};
+pub use self::pin::{FingerprintMismatch, ServerFingerprint, TlsPinning};
 pub use self::{
     cert::{RootCertStore, RootCertStoreProvider},
     conf::TlsConfig,
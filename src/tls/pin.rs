@@ -0,0 +1,378 @@
+//! Per-origin pinning of server TLS characteristics.
+use antidote::Mutex;
+use boring2::hash::MessageDigest;
+use boring2::ssl::SslRef;
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+type ChangeCallback = Arc<dyn Fn(&FingerprintMismatch) + Send + Sync>;
+
+/// The characteristics of a server's TLS handshake.
+///
+/// Combines the SHA-256 digest of the leaf certificate with the negotiated
+/// protocol version, cipher suite and ALPN protocol, in the spirit of JA3S.
+///
+/// A fingerprint made from a certificate digest alone, with
+/// [`from_sha256`](ServerFingerprint::from_sha256) or by parsing it, pins the
+/// certificate only, whatever the rest of the handshake.
+///
+/// # Example
+///
+/// ```
+/// use rquest::{ServerFingerprint, TlsPinning};
+///
+/// let fingerprint: ServerFingerprint =
+///     "9F:86:D0:81:88:4C:7D:65:9A:2F:EA:A0:C5:5A:D0:15:A3:BF:4F:1B:2B:0B:82:2C:D1:5D:6C:15:B0:F0:0A:08"
+///         .parse()
+///         .unwrap();
+///
+/// let pinning = TlsPinning::new();
+/// pinning.pin("example.com:443", fingerprint);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServerFingerprint {
+    certificate_sha256: Option<Vec<u8>>,
+    version: Option<&'static str>,
+    cipher: Option<&'static str>,
+    alpn: Option<Vec<u8>>,
+    certificate_only: bool,
+}
+
+impl ServerFingerprint {
+    /// Creates a fingerprint of the leaf certificate with the SHA-256 digest
+    /// `digest` of its DER encoding.
+    pub fn from_sha256(digest: [u8; 32]) -> ServerFingerprint {
+        ServerFingerprint {
+            certificate_sha256: Some(digest.to_vec()),
+            version: None,
+            cipher: None,
+            alpn: None,
+            certificate_only: true,
+        }
+    }
+
+    pub(crate) fn from_ssl(ssl: &SslRef) -> ServerFingerprint {
+        ServerFingerprint {
+            certificate_sha256: ssl
+                .peer_certificate()
+                .and_then(|cert| cert.digest(MessageDigest::sha256()).ok())
+                .map(|digest| digest.to_vec()),
+            version: Some(ssl.version_str()),
+            cipher: ssl.current_cipher().map(|cipher| cipher.name()),
+            alpn: ssl.selected_alpn_protocol().map(<[u8]>::to_vec),
+            certificate_only: false,
+        }
+    }
+
+    /// Whether a handshake with the fingerprint `actual` matches this pin.
+    fn matches(&self, actual: &ServerFingerprint) -> bool {
+        if self.certificate_only {
+            self.certificate_sha256 == actual.certificate_sha256
+        } else {
+            self == actual
+        }
+    }
+
+    /// Get the SHA-256 digest of the DER encoded leaf certificate.
+    pub fn certificate_sha256(&self) -> Option<&[u8]> {
+        self.certificate_sha256.as_deref()
+    }
+
+    /// Get the negotiated protocol version, e.g. `TLSv1.3`, or `None` for a
+    /// fingerprint of the certificate only.
+    pub fn version(&self) -> Option<&str> {
+        self.version
+    }
+
+    /// Get the name of the negotiated cipher suite.
+    pub fn cipher(&self) -> Option<&str> {
+        self.cipher
+    }
+
+    /// Get the negotiated ALPN protocol.
+    pub fn alpn(&self) -> Option<&[u8]> {
+        self.alpn.as_deref()
+    }
+}
+
+/// Parses the hex encoded SHA-256 digest of a certificate, with or without
+/// colons between the bytes, as printed by
+/// `openssl x509 -noout -fingerprint -sha256`.
+impl FromStr for ServerFingerprint {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<ServerFingerprint> {
+        let invalid = || crate::error::builder(format!("invalid SHA-256 fingerprint {:?}", s));
+        let hex = s.trim().replace(':', "");
+        if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let mut digest = [0; 32];
+        for (byte, pair) in digest.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+        }
+        Ok(ServerFingerprint::from_sha256(digest))
+    }
+}
+
+/// Records the [`ServerFingerprint`] of each origin, and reacts when it changes.
+///
+/// The first TLS handshake with an origin pins its fingerprint. A later
+/// handshake presenting a different fingerprint, as a transparent
+/// intercepting proxy would, fails the connection with a
+/// [`FingerprintMismatch`] error, unless the pinning is [`alert_only`].
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use rquest::TlsPinning;
+///
+/// let pinning = TlsPinning::new().on_change(|mismatch| {
+///     eprintln!("TLS fingerprint changed: {}", mismatch);
+/// });
+///
+/// let client = rquest::Client::builder()
+///     .tls_pinning(Arc::new(pinning))
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// [`alert_only`]: TlsPinning::alert_only
+pub struct TlsPinning {
+    pins: Mutex<HashMap<String, ServerFingerprint>>,
+    enforce: bool,
+    on_change: Option<ChangeCallback>,
+}
+
+impl TlsPinning {
+    /// Creates a new `TlsPinning` that rejects changed fingerprints.
+    pub fn new() -> TlsPinning {
+        TlsPinning {
+            pins: Mutex::new(HashMap::new()),
+            enforce: true,
+            on_change: None,
+        }
+    }
+
+    /// Only report changed fingerprints, without failing the connection.
+    ///
+    /// The originally pinned fingerprint is kept.
+    pub fn alert_only(mut self) -> TlsPinning {
+        self.enforce = false;
+        self
+    }
+
+    /// Sets a callback invoked whenever an origin's fingerprint changes.
+    pub fn on_change<F>(mut self, callback: F) -> TlsPinning
+    where
+        F: Fn(&FingerprintMismatch) + Send + Sync + 'static,
+    {
+        self.on_change = Some(Arc::new(callback));
+        self
+    }
+
+    /// Pins a known fingerprint for an origin, given as `host:port`.
+    pub fn pin<O: Into<String>>(&self, origin: O, fingerprint: ServerFingerprint) {
+        self.pins.lock().insert(origin.into(), fingerprint);
+    }
+
+    /// Get the fingerprint pinned for an origin, given as `host:port`.
+    pub fn get(&self, origin: &str) -> Option<ServerFingerprint> {
+        self.pins.lock().get(origin).cloned()
+    }
+
+    /// Forgets the fingerprint pinned for an origin, given as `host:port`.
+    pub fn remove(&self, origin: &str) -> Option<ServerFingerprint> {
+        self.pins.lock().remove(origin)
+    }
+
+    /// Forgets all pinned fingerprints.
+    pub fn clear(&self) {
+        self.pins.lock().clear();
+    }
+
+    pub(crate) fn check(&self, origin: &str, ssl: &SslRef) -> Result<(), FingerprintMismatch> {
+        self.check_fingerprint(origin, ServerFingerprint::from_ssl(ssl))
+    }
+
+    fn check_fingerprint(
+        &self,
+        origin: &str,
+        actual: ServerFingerprint,
+    ) -> Result<(), FingerprintMismatch> {
+        let pinned = {
+            let mut pins = self.pins.lock();
+            match pins.get(origin) {
+                Some(pinned) if pinned.matches(&actual) => return Ok(()),
+                Some(pinned) => pinned.clone(),
+                None => {
+                    pins.insert(origin.to_owned(), actual);
+                    return Ok(());
+                }
+            }
+        };
+
+        let mismatch = FingerprintMismatch {
+            origin: origin.to_owned(),
+            pinned,
+            actual,
+        };
+
+        log::warn!("{}", mismatch);
+
+        if let Some(ref on_change) = self.on_change {
+            on_change(&mismatch);
+        }
+
+        if self.enforce {
+            Err(mismatch)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for TlsPinning {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for TlsPinning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsPinning")
+            .field("pins", &self.pins.lock().len())
+            .field("enforce", &self.enforce)
+            .finish()
+    }
+}
+
+/// The fingerprint presented by an origin differs from the pinned one.
+///
+/// Returned as the source of a connect error when [`TlsPinning`] is enforced.
+#[derive(Debug, Clone)]
+pub struct FingerprintMismatch {
+    origin: String,
+    pinned: ServerFingerprint,
+    actual: ServerFingerprint,
+}
+
+impl FingerprintMismatch {
+    /// Get the origin, as `host:port`.
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    /// Get the fingerprint that was pinned for the origin.
+    pub fn pinned(&self) -> &ServerFingerprint {
+        &self.pinned
+    }
+
+    /// Get the fingerprint the origin presented.
+    pub fn actual(&self) -> &ServerFingerprint {
+        &self.actual
+    }
+
+    /// Returns true if the leaf certificate changed.
+    pub fn is_certificate_changed(&self) -> bool {
+        self.pinned.certificate_sha256 != self.actual.certificate_sha256
+    }
+}
+
+impl fmt::Display for FingerprintMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TLS fingerprint of {} changed", self.origin)?;
+        if self.is_certificate_changed() {
+            f.write_str(" (certificate)")?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for FingerprintMismatch {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    fn fingerprint(digest: u8, alpn: &[u8]) -> ServerFingerprint {
+        ServerFingerprint {
+            certificate_sha256: Some(vec![digest; 32]),
+            version: Some("TLSv1.3"),
+            cipher: Some("TLS_AES_128_GCM_SHA256"),
+            alpn: Some(alpn.to_vec()),
+            certificate_only: false,
+        }
+    }
+
+    #[test]
+    fn parses_sha256() {
+        let plain: ServerFingerprint = DIGEST.parse().unwrap();
+        let colons: ServerFingerprint = DIGEST
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| std::str::from_utf8(pair).unwrap().to_ascii_uppercase())
+            .collect::<Vec<_>>()
+            .join(":")
+            .parse()
+            .unwrap();
+        assert_eq!(plain, colons);
+        assert_eq!(plain.certificate_sha256().unwrap()[..2], [0x9f, 0x86]);
+        assert_eq!(plain.version(), None);
+
+        assert!("9f86".parse::<ServerFingerprint>().is_err());
+        assert!(DIGEST
+            .replace('9', "g")
+            .parse::<ServerFingerprint>()
+            .is_err());
+    }
+
+    #[test]
+    fn pinned_certificate() {
+        let pinning = TlsPinning::new();
+        pinning.pin("a.test:443", ServerFingerprint::from_sha256([1; 32]));
+
+        assert!(pinning
+            .check_fingerprint("a.test:443", fingerprint(1, b"h2"))
+            .is_ok());
+        assert!(pinning
+            .check_fingerprint("a.test:443", fingerprint(1, b"http/1.1"))
+            .is_ok());
+
+        let mismatch = pinning
+            .check_fingerprint("a.test:443", fingerprint(2, b"h2"))
+            .unwrap_err();
+        assert!(mismatch.is_certificate_changed());
+        assert_eq!(mismatch.origin(), "a.test:443");
+    }
+
+    #[test]
+    fn pinned_handshake() {
+        let pinning = TlsPinning::new();
+        assert!(pinning
+            .check_fingerprint("a.test:443", fingerprint(1, b"h2"))
+            .is_ok());
+        assert!(pinning
+            .check_fingerprint("a.test:443", fingerprint(1, b"h2"))
+            .is_ok());
+
+        let mismatch = pinning
+            .check_fingerprint("a.test:443", fingerprint(1, b"http/1.1"))
+            .unwrap_err();
+        assert!(!mismatch.is_certificate_changed());
+
+        let pinning = TlsPinning::new().alert_only();
+        pinning.pin("a.test:443", fingerprint(1, b"h2"));
+        assert!(pinning
+            .check_fingerprint("a.test:443", fingerprint(2, b"h2"))
+            .is_ok());
+        assert_eq!(pinning.get("a.test:443"), Some(fingerprint(1, b"h2")));
+    }
+}
@@ -0,0 +1,131 @@
+//! Custom transports
+//!
+//! By default rquest opens TCP connections itself, optionally through a
+//! configured proxy. A [`Connector`] replaces that step: it hands rquest an
+//! already connected byte stream, such as a custom tunnel or an in-memory
+//! pipe, and rquest layers the impersonated TLS handshake and HTTP on top.
+
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use http::Uri;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::error::BoxError;
+use crate::util::client::connect::{Connected, Connection};
+
+/// A bidirectional byte stream returned by a [`Connector`].
+///
+/// This is implemented for every type that is `AsyncRead + AsyncWrite`,
+/// `Send`, `Sync` and `Unpin`.
+pub trait Io: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static> Io for T {}
+
+/// Alias for a boxed [`Io`] trait object.
+pub type BoxIo = Box<dyn Io>;
+
+/// Alias for the `Future` type returned by a connector.
+pub type Connecting = Pin<Box<dyn Future<Output = Result<BoxIo, BoxError>> + Send>>;
+
+/// Trait for supplying the connections used by rquest.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use rquest::transport::{Connecting, Connector};
+///
+/// struct Tcp;
+///
+/// impl Connector for Tcp {
+///     fn connect(&self, uri: &http::Uri) -> Connecting {
+///         let host = uri.host().unwrap_or_default().to_owned();
+///         let port = uri.port_u16().unwrap_or(80);
+///         Box::pin(async move {
+///             let stream = tokio::net::TcpStream::connect((host, port)).await?;
+///             Ok(Box::new(stream) as _)
+///         })
+///     }
+/// }
+///
+/// let client = rquest::Client::builder()
+///     .connector(Arc::new(Tcp))
+///     .build()
+///     .unwrap();
+/// ```
+pub trait Connector: Send + Sync {
+    /// Opens a connection to the destination `uri`.
+    ///
+    /// Only the scheme and authority of `uri` are meaningful. For `https`
+    /// destinations, rquest performs the TLS handshake over the returned
+    /// stream, so the connector must not do so itself.
+    ///
+    /// The connector is responsible for reaching the destination, so any
+    /// proxies configured on the client are not used.
+    fn connect(&self, uri: &Uri) -> Connecting;
+}
+
+impl fmt::Debug for dyn Connector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Connector").finish_non_exhaustive()
+    }
+}
+
+/// A stream obtained from a user [`Connector`].
+pub(crate) struct Transport(pub(crate) BoxIo);
+
+impl fmt::Debug for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transport").finish()
+    }
+}
+
+impl Connection for Transport {
+    fn connected(&self) -> Connected {
+        Connected::new()
+    }
+}
+
+impl AsyncRead for Transport {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Transport {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.0.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
@@ -37,6 +37,9 @@ pub(super) use self::sealed::Resolve;
 #[derive(Clone, Hash, Eq, PartialEq)]
 pub struct Name {
     host: Box<str>,
+    port: u16,
+    ipv4: bool,
+    ipv6: bool,
 }
 
 /// A resolver using blocking `getaddrinfo` calls in a threadpool.
@@ -57,13 +60,37 @@ pub struct GaiFuture {
 
 impl Name {
     pub(super) fn new(host: Box<str>) -> Name {
-        Name { host }
+        Name {
+            host,
+            port: 0,
+            ipv4: true,
+            ipv6: true,
+        }
+    }
+
+    /// Sets the port the addresses are for, and the address families that
+    /// can be connected to.
+    pub(super) fn with_hints(mut self, port: u16, ipv4: bool, ipv6: bool) -> Name {
+        self.port = port;
+        self.ipv4 = ipv4;
+        self.ipv6 = ipv6;
+        self
     }
 
     /// View the hostname as a string slice.
     pub fn as_str(&self) -> &str {
         &self.host
     }
+
+    /// Get the port the addresses are for, or `0` if unknown.
+    pub(crate) fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Whether IPv4 and IPv6 addresses, respectively, can be connected to.
+    pub(crate) fn families(&self) -> (bool, bool) {
+        (self.ipv4, self.ipv6)
+    }
 }
 
 impl fmt::Debug for Name {
@@ -194,7 +221,7 @@ impl SocketAddrs {
         }
     }
 
-    pub(super) fn try_parse(host: &str, port: u16) -> Option<SocketAddrs> {
+    pub(super) fn try_parse(host: &str, port: u16, scope_id: Option<u32>) -> Option<SocketAddrs> {
         if let Ok(addr) = host.parse::<Ipv4Addr>() {
             let addr = SocketAddrV4::new(addr, port);
             return Some(SocketAddrs {
@@ -202,7 +229,11 @@ impl SocketAddrs {
             });
         }
         if let Ok(addr) = host.parse::<Ipv6Addr>() {
-            let addr = SocketAddrV6::new(addr, port, 0, 0);
+            // Zone identifiers are only meaningful for link-local addresses
+            let scope_id = scope_id
+                .filter(|_| addr.segments()[0] & 0xffc0 == 0xfe80)
+                .unwrap_or(0);
+            let addr = SocketAddrV6::new(addr, port, 0, scope_id);
             return Some(SocketAddrs {
                 iter: vec![SocketAddr::V6(addr)].into_iter(),
             });
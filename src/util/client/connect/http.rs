@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::error::Error as StdError;
 use std::fmt;
 use std::future::Future;
@@ -14,11 +15,12 @@ use http::uri::{Scheme, Uri};
 use log::{debug, trace, warn};
 use pin_project_lite::pin_project;
 use socket2::TcpKeepalive;
+use tokio::io::AsyncWriteExt;
 use tokio::net::{TcpSocket, TcpStream};
 use tokio::time::Sleep;
 
 use super::dns::{self, resolve, GaiResolver, Resolve};
-use super::{Connected, Connection};
+use super::{Connected, Connection, ProxyProtocol};
 use crate::util::rt::TokioIo;
 
 /// A connector for the `http` scheme.
@@ -94,6 +96,13 @@ struct Config {
     interface: Option<std::borrow::Cow<'static, str>>,
     #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
     tcp_user_timeout: Option<Duration>,
+    ip_ttl: Option<u32>,
+    #[cfg(unix)]
+    tcp_mss: Option<u32>,
+    scope_id: Option<u32>,
+    proxy_protocol: Option<ProxyProtocol>,
+    connect_to: HashMap<(String, u16), SocketAddr>,
+    resolve_to: Option<(String, IpAddr)>,
 }
 
 #[derive(Default, Debug, Clone, Copy)]
@@ -264,6 +273,13 @@ impl<R> HttpConnector<R> {
                 interface: None,
                 #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
                 tcp_user_timeout: None,
+                ip_ttl: None,
+                #[cfg(unix)]
+                tcp_mss: None,
+                scope_id: None,
+                proxy_protocol: None,
+                connect_to: HashMap::new(),
+                resolve_to: None,
             }),
             resolver,
         }
@@ -423,6 +439,63 @@ impl<R> HttpConnector<R> {
         self
     }
 
+    /// Sets the IPv4 time-to-live, or the IPv6 hop limit, of outgoing packets.
+    ///
+    /// Default is `None`, which keeps the system default.
+    #[inline]
+    pub fn set_ip_ttl(&mut self, ttl: Option<u32>) -> &mut Self {
+        self.config_mut().ip_ttl = ttl;
+        self
+    }
+
+    /// Sets the TCP maximum segment size (`TCP_MAXSEG`) advertised in the SYN.
+    ///
+    /// Default is `None`, which keeps the system default.
+    #[cfg(unix)]
+    #[inline]
+    pub fn set_tcp_mss(&mut self, mss: Option<u32>) -> &mut Self {
+        self.config_mut().tcp_mss = mss;
+        self
+    }
+
+    /// Sets the PROXY protocol header sent right after connecting.
+    ///
+    /// Default is `None`.
+    #[inline]
+    pub fn set_proxy_protocol(&mut self, version: Option<ProxyProtocol>) -> &mut Self {
+        self.config_mut().proxy_protocol = version;
+        self
+    }
+
+    /// Sets the scope id used when connecting to an IPv6 link-local address.
+    ///
+    /// This is the interface index that a zone identifier, such as the `eth0`
+    /// in `fe80::1%eth0`, refers to. It is ignored for any other address.
+    #[inline]
+    pub fn set_scope_id(&mut self, scope_id: Option<u32>) -> &mut Self {
+        self.config_mut().scope_id = scope_id;
+        self
+    }
+
+    /// Sets the addresses to connect to instead of some host and port pairs.
+    ///
+    /// The host is matched in lowercase. No name is resolved for a pair
+    /// found here, and the port of the address is used as is.
+    #[inline]
+    pub fn set_connect_to(&mut self, connect_to: HashMap<(String, u16), SocketAddr>) -> &mut Self {
+        self.config_mut().connect_to = connect_to;
+        self
+    }
+
+    /// Sets the address to reach a host at, instead of resolving it.
+    ///
+    /// The host is matched in lowercase, and the port is the one of the URI.
+    #[inline]
+    pub fn set_resolve_to(&mut self, resolve_to: Option<(String, IpAddr)>) -> &mut Self {
+        self.config_mut().resolve_to = resolve_to;
+        self
+    }
+
     /// Sets the value of the TCP_USER_TIMEOUT option on the socket.
     #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
     #[inline]
@@ -487,12 +560,16 @@ fn get_host_port<'u>(config: &Config, dst: &'u Uri) -> Result<(&'u str, u16), Co
             return Err(ConnectError {
                 msg: INVALID_NOT_HTTP.into(),
                 cause: None,
+                dns: false,
+                addr: None,
             });
         }
     } else if dst.scheme().is_none() {
         return Err(ConnectError {
             msg: INVALID_MISSING_SCHEME.into(),
             cause: None,
+            dns: false,
+            addr: None,
         });
     }
 
@@ -502,6 +579,8 @@ fn get_host_port<'u>(config: &Config, dst: &'u Uri) -> Result<(&'u str, u16), Co
             return Err(ConnectError {
                 msg: INVALID_MISSING_HOST.into(),
                 cause: None,
+                dns: false,
+                addr: None,
             })
         }
     };
@@ -529,12 +608,29 @@ where
         let (host, port) = get_host_port(config, &dst)?;
         let host = host.trim_start_matches('[').trim_end_matches(']');
 
-        // If the host is already an IP addr (v4 or v6),
-        // skip resolving the dns and start connecting right away.
-        let addrs = if let Some(addrs) = dns::SocketAddrs::try_parse(host, port) {
+        // A host given an address for the request, or a pair mapped with
+        // `connect_to`, goes to its address, and if the host is already an
+        // IP addr (v4 or v6), skip resolving the dns and start connecting
+        // right away.
+        let addrs = if let Some((_, ip)) = config
+            .resolve_to
+            .as_ref()
+            .filter(|(name, _)| name.eq_ignore_ascii_case(host))
+        {
+            dns::SocketAddrs::new(vec![SocketAddr::new(*ip, port)])
+        } else if let Some(addr) = config.connect_to.get(&(host.to_ascii_lowercase(), port)) {
+            dns::SocketAddrs::new(vec![*addr])
+        } else if let Some(addrs) = dns::SocketAddrs::try_parse(host, port, config.scope_id) {
             addrs
         } else {
-            let addrs = resolve(&mut self.resolver, dns::Name::new(host.into()))
+            // a local address of only one family restricts the remote one
+            let (ipv4, ipv6) = match (config.local_address_ipv4, config.local_address_ipv6) {
+                (Some(_), None) => (true, false),
+                (None, Some(_)) => (false, true),
+                _ => (true, true),
+            };
+            let name = dns::Name::new(host.into()).with_hints(port, ipv4, ipv6);
+            let addrs = resolve(&mut self.resolver, name)
                 .await
                 .map_err(ConnectError::dns)?;
             let addrs = addrs
@@ -549,12 +645,22 @@ where
 
         let c = ConnectingTcp::new(addrs, config);
 
-        let sock = c.connect().await?;
+        let mut sock = c.connect().await?;
 
         if let Err(e) = sock.set_nodelay(config.nodelay) {
             warn!("tcp set_nodelay error: {}", e);
         }
 
+        if let Some(version) = config.proxy_protocol {
+            let (src, dst) = sock
+                .local_addr()
+                .and_then(|src| sock.peer_addr().map(|dst| (src, dst)))
+                .map_err(ConnectError::m("tcp get address error"))?;
+            sock.write_all(&version.encode(src, dst))
+                .await
+                .map_err(ConnectError::m("tcp write proxy protocol header error"))?;
+        }
+
         Ok(TokioIo::new(sock))
     }
 }
@@ -626,6 +732,8 @@ impl<R: Resolve> Future for HttpConnecting<R> {
 pub struct ConnectError {
     msg: Box<str>,
     cause: Option<Box<dyn StdError + Send + Sync>>,
+    dns: bool,
+    addr: Option<SocketAddr>,
 }
 
 impl ConnectError {
@@ -637,6 +745,8 @@ impl ConnectError {
         ConnectError {
             msg: msg.into(),
             cause: Some(cause.into()),
+            dns: false,
+            addr: None,
         }
     }
 
@@ -644,7 +754,25 @@ impl ConnectError {
     where
         E: Into<Box<dyn StdError + Send + Sync>>,
     {
-        ConnectError::new("dns error", cause)
+        ConnectError {
+            dns: true,
+            ..ConnectError::new("dns error", cause)
+        }
+    }
+
+    fn with_addr(mut self, addr: SocketAddr) -> ConnectError {
+        self.addr = Some(addr);
+        self
+    }
+
+    /// Whether resolving the host failed.
+    pub(crate) fn is_dns(&self) -> bool {
+        self.dns
+    }
+
+    /// The remote address the connection was attempted to.
+    pub(crate) fn remote_addr(&self) -> Option<SocketAddr> {
+        self.addr
     }
 
     fn m<S, E>(msg: S) -> impl FnOnce(E) -> ConnectError
@@ -757,7 +885,7 @@ impl ConnectingTcpRemote {
                 }
                 Err(e) => {
                     trace!("connect error for {}: {:?}", addr, e);
-                    err = Some(e);
+                    err = Some(e.with_addr(addr));
                 }
             }
         }
@@ -826,6 +954,24 @@ fn connect(
         }
     }
 
+    if let Some(ttl) = config.ip_ttl {
+        let res = if addr.is_ipv4() {
+            socket.set_ttl(ttl)
+        } else {
+            socket.set_unicast_hops_v6(ttl)
+        };
+        if let Err(e) = res {
+            warn!("tcp set_ttl error: {}", e);
+        }
+    }
+
+    #[cfg(unix)]
+    if let Some(mss) = config.tcp_mss {
+        if let Err(e) = socket.set_mss(mss) {
+            warn!("tcp set_mss error: {}", e);
+        }
+    }
+
     #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
     // That this only works for some socket types, particularly AF_INET sockets.
     if let Some(interface) = &config.interface {
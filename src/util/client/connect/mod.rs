@@ -73,10 +73,13 @@ use std::{
 
 use ::http::Extensions;
 
-pub use self::http::{HttpConnector, HttpInfo};
+pub use self::http::{ConnectError, HttpConnector, HttpInfo};
+pub use self::proxy_protocol::ProxyProtocol;
+pub(crate) use self::proxy_protocol::ProxyProtocolMatcher;
 
 pub mod dns;
 mod http;
+mod proxy_protocol;
 
 pub(crate) mod capture;
 #[allow(unused)]
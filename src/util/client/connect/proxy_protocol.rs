@@ -0,0 +1,172 @@
+//! HAProxy PROXY protocol headers.
+//!
+//! See <https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt>.
+use std::net::SocketAddr;
+
+use crate::util::host_matches;
+
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// The version of the PROXY protocol header sent on new connections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProxyProtocol {
+    /// The human-readable header, e.g. `PROXY TCP4 10.0.0.1 10.0.0.2 51234 443\r\n`.
+    V1,
+    /// The binary header.
+    V2,
+}
+
+impl ProxyProtocol {
+    /// Encodes the header announcing a connection from `src` to `dst`.
+    ///
+    /// Addresses of different families are announced as an unknown
+    /// (v1) or local (v2) connection.
+    pub(crate) fn encode(self, src: SocketAddr, dst: SocketAddr) -> Vec<u8> {
+        match self {
+            ProxyProtocol::V1 => encode_v1(src, dst),
+            ProxyProtocol::V2 => encode_v2(src, dst),
+        }
+    }
+
+    /// Encodes the header announcing a connection whose addresses are not
+    /// known, such as one tunneled through a proxy.
+    ///
+    /// The receiver then falls back to the addresses of the connection it
+    /// accepted.
+    pub(crate) fn encode_unknown(self) -> Vec<u8> {
+        match self {
+            ProxyProtocol::V1 => b"PROXY UNKNOWN\r\n".to_vec(),
+            ProxyProtocol::V2 => {
+                let mut buf = V2_SIGNATURE.to_vec();
+                // version 2, LOCAL command; unspecified family
+                buf.extend_from_slice(&[0x20, 0x00]);
+                buf.extend_from_slice(&0u16.to_be_bytes());
+                buf
+            }
+        }
+    }
+}
+
+fn encode_v1(src: SocketAddr, dst: SocketAddr) -> Vec<u8> {
+    let family = match (src, dst) {
+        (SocketAddr::V4(_), SocketAddr::V4(_)) => "TCP4",
+        (SocketAddr::V6(_), SocketAddr::V6(_)) => "TCP6",
+        _ => return ProxyProtocol::V1.encode_unknown(),
+    };
+
+    format!(
+        "PROXY {} {} {} {} {}\r\n",
+        family,
+        src.ip(),
+        dst.ip(),
+        src.port(),
+        dst.port()
+    )
+    .into_bytes()
+}
+
+fn encode_v2(src: SocketAddr, dst: SocketAddr) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(16 + 36);
+    buf.extend_from_slice(&V2_SIGNATURE);
+
+    match (src, dst) {
+        (SocketAddr::V4(src), SocketAddr::V4(dst)) => {
+            // version 2, PROXY command; TCP over IPv4
+            buf.extend_from_slice(&[0x21, 0x11]);
+            buf.extend_from_slice(&12u16.to_be_bytes());
+            buf.extend_from_slice(&src.ip().octets());
+            buf.extend_from_slice(&dst.ip().octets());
+        }
+        (SocketAddr::V6(src), SocketAddr::V6(dst)) => {
+            // version 2, PROXY command; TCP over IPv6
+            buf.extend_from_slice(&[0x21, 0x21]);
+            buf.extend_from_slice(&36u16.to_be_bytes());
+            buf.extend_from_slice(&src.ip().octets());
+            buf.extend_from_slice(&dst.ip().octets());
+        }
+        _ => return ProxyProtocol::V2.encode_unknown(),
+    }
+
+    buf.extend_from_slice(&src.port().to_be_bytes());
+    buf.extend_from_slice(&dst.port().to_be_bytes());
+    buf
+}
+
+/// Selects the PROXY protocol version to use for a host.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ProxyProtocolMatcher {
+    rules: Vec<(String, ProxyProtocol)>,
+}
+
+impl ProxyProtocolMatcher {
+    /// Adds a rule; earlier rules take precedence.
+    pub(crate) fn push(&mut self, pattern: String, version: ProxyProtocol) {
+        self.rules.push((pattern.to_ascii_lowercase(), version));
+    }
+
+    pub(crate) fn find(&self, host: &str) -> Option<ProxyProtocol> {
+        self.rules
+            .iter()
+            .find(|(pattern, _)| host_matches(pattern, host))
+            .map(|(_, version)| *version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v1_header() {
+        let src = "192.168.0.1:56324".parse().unwrap();
+        let dst = "10.0.0.1:443".parse().unwrap();
+        assert_eq!(
+            ProxyProtocol::V1.encode(src, dst),
+            b"PROXY TCP4 192.168.0.1 10.0.0.1 56324 443\r\n"
+        );
+
+        let src = "[::1]:56324".parse().unwrap();
+        let dst = "[::2]:443".parse().unwrap();
+        assert_eq!(
+            ProxyProtocol::V1.encode(src, dst),
+            b"PROXY TCP6 ::1 ::2 56324 443\r\n"
+        );
+
+        let dst = "10.0.0.1:443".parse().unwrap();
+        assert_eq!(ProxyProtocol::V1.encode(src, dst), b"PROXY UNKNOWN\r\n");
+    }
+
+    #[test]
+    fn v2_header() {
+        let src = "192.168.0.1:56324".parse().unwrap();
+        let dst = "10.0.0.1:443".parse().unwrap();
+        let header = ProxyProtocol::V2.encode(src, dst);
+        assert_eq!(&header[..12], &V2_SIGNATURE);
+        assert_eq!(
+            &header[12..],
+            &[0x21, 0x11, 0, 12, 192, 168, 0, 1, 10, 0, 0, 1, 0xdc, 0x04, 0x01, 0xbb]
+        );
+
+        let src = "192.168.0.1:56324".parse().unwrap();
+        let dst = "[::1]:443".parse().unwrap();
+        let header = ProxyProtocol::V2.encode(src, dst);
+        assert_eq!(&header[12..], &[0x20, 0x00, 0, 0]);
+        assert_eq!(header, ProxyProtocol::V2.encode_unknown());
+    }
+
+    #[test]
+    fn matcher() {
+        let mut matcher = ProxyProtocolMatcher::default();
+        matcher.push("*.internal".into(), ProxyProtocol::V2);
+        matcher.push("LB.example.com".into(), ProxyProtocol::V1);
+
+        assert_eq!(matcher.find("api.internal"), Some(ProxyProtocol::V2));
+        assert_eq!(matcher.find("a.b.Internal"), Some(ProxyProtocol::V2));
+        assert_eq!(matcher.find("internal"), None);
+        assert_eq!(matcher.find("lb.example.com"), Some(ProxyProtocol::V1));
+        assert_eq!(matcher.find("example.com"), None);
+
+        matcher.push("*".into(), ProxyProtocol::V1);
+        assert_eq!(matcher.find("example.com"), Some(ProxyProtocol::V1));
+    }
+}
@@ -0,0 +1,142 @@
+//! Capturing what requests actually put on the wire, to debug fingerprints.
+use std::sync::Arc;
+
+use antidote::Mutex;
+use bytes::{Bytes, BytesMut};
+use http::{HeaderMap, Version};
+
+/// How many bytes of a request are kept to find the end of its head.
+const MAX_HEAD: usize = 64 * 1024;
+
+/// What was sent for a request, captured when the client has
+/// [`debug_capture`] enabled.
+///
+/// Read it from the extensions of the response, or with
+/// [`Response::debug_capture`].
+///
+/// [`debug_capture`]: crate::ClientBuilder::debug_capture
+/// [`Response::debug_capture`]: crate::Response::debug_capture
+#[derive(Clone, Debug)]
+pub struct DebugCapture {
+    version: Version,
+    client_hello: Option<Bytes>,
+    request_head: Option<Bytes>,
+    headers: HeaderMap,
+}
+
+impl DebugCapture {
+    /// The version of the connection the request was sent on.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// The ClientHello handshake message that opened the connection, with
+    /// its 4 bytes header, or `None` for plain text connections.
+    ///
+    /// Connections are reused, so the ClientHello may predate the request.
+    pub fn client_hello(&self) -> Option<&Bytes> {
+        self.client_hello.as_ref()
+    }
+
+    /// The request line and headers, exactly as written on an HTTP/1
+    /// connection, or `None` for HTTP/2, where they are HPACK-compressed
+    /// into a HEADERS frame instead.
+    pub fn request_head(&self) -> Option<&Bytes> {
+        self.request_head.as_ref()
+    }
+
+    /// The headers handed to the connection, in the order they were
+    /// written.
+    ///
+    /// For HTTP/2, the pseudo-headers come first, in the order of the
+    /// client's [`Http2Config`](crate::Http2Config).
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+}
+
+/// What a connection writes, recorded a request at a time.
+#[derive(Clone)]
+pub(crate) struct Recording {
+    inner: Arc<Shared>,
+}
+
+struct Shared {
+    client_hello: Option<Bytes>,
+    /// The bytes written since `start`, until `finish`.
+    written: Mutex<Option<BytesMut>>,
+}
+
+impl Recording {
+    pub(crate) fn new(client_hello: Option<Bytes>) -> Recording {
+        Recording {
+            inner: Arc::new(Shared {
+                client_hello,
+                written: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Record `buf`, if a request is being recorded.
+    pub(crate) fn write(&self, buf: &[u8]) {
+        if let Some(ref mut written) = *self.inner.written.lock() {
+            let len = buf.len().min(MAX_HEAD - written.len());
+            written.extend_from_slice(&buf[..len]);
+        }
+    }
+
+    /// Start recording the request about to be written.
+    pub(crate) fn start(&self) {
+        *self.inner.written.lock() = Some(BytesMut::new());
+    }
+
+    /// Stop recording, and capture the request sent with `headers`.
+    pub(crate) fn finish(&self, version: Version, headers: HeaderMap) -> DebugCapture {
+        let request_head = self.inner.written.lock().take().and_then(|mut written| {
+            let end = written.windows(4).position(|w| w == b"\r\n\r\n")?;
+            Some(written.split_to(end + 4).freeze())
+        });
+
+        DebugCapture {
+            version,
+            client_hello: self.inner.client_hello.clone(),
+            request_head,
+            headers,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_the_head_of_a_started_request() {
+        let recording = Recording::new(Some(Bytes::from_static(b"\x01\x00\x00\x00")));
+
+        // nothing is kept until a request starts
+        recording.write(b"PRI * HTTP/2.0\r\n\r\n");
+        let capture = recording.finish(Version::HTTP_2, HeaderMap::new());
+        assert_eq!(capture.request_head(), None);
+        assert_eq!(
+            capture.client_hello().map(|b| &b[..]),
+            Some(&b"\x01\x00\x00\x00"[..])
+        );
+
+        recording.start();
+        recording.write(b"GET / HTTP/1.1\r\nhost: a\r\n");
+        recording.write(b"\r\nbody");
+        let capture = recording.finish(Version::HTTP_11, HeaderMap::new());
+        assert_eq!(
+            capture.request_head().map(|b| &b[..]),
+            Some(&b"GET / HTTP/1.1\r\nhost: a\r\n\r\n"[..])
+        );
+
+        // a head too large to be kept whole isn't captured
+        recording.start();
+        recording.write(&vec![b'a'; MAX_HEAD + 1]);
+        recording.write(b"\r\n\r\n");
+        let capture = recording.finish(Version::HTTP_11, HeaderMap::new());
+        assert_eq!(capture.request_head(), None);
+    }
+}
@@ -5,6 +5,7 @@
 //! in much the same way it did in hyper 0.14.
 
 pub mod connect;
+mod debug;
 mod network;
 #[doc(hidden)]
 // Publicly available, but just for legacy purposes. A better pool will be
@@ -15,16 +16,17 @@ mod request;
 use std::error::Error as StdError;
 use std::fmt;
 use std::future::Future;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::num::NonZeroUsize;
 use std::ops::{Deref, DerefMut};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{self, Poll};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use futures_util::future::{self, Either, FutureExt, TryFutureExt};
-use http::uri::Scheme;
+use http::uri::{Authority, Scheme};
 use hyper2::client::conn::TrySendError as ConnTrySendError;
 use hyper2::header::{HeaderValue, HOST};
 use hyper2::rt::Timer;
@@ -42,8 +44,11 @@ use pool::Ver;
 use common::{lazy as hyper_lazy, timer, Exec, Lazy};
 
 use super::into_uri;
+pub use debug::DebugCapture;
+pub(crate) use debug::Recording;
 pub use network::{NetworkScheme, NetworkSchemeBuilder};
-pub use request::InnerRequest;
+pub(crate) use request::{add_content_length_header, sort_headers, FreshConnection};
+pub use request::{InnerRequest, RequestTarget};
 
 type BoxSendFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
 
@@ -78,6 +83,9 @@ impl<C, B> std::ops::DerefMut for Client<C, B> {
 struct Config {
     retry_canceled_requests: bool,
     set_host: bool,
+    debug_capture: bool,
+    max_connection_age: Option<Duration>,
+    max_connection_requests: Option<usize>,
     ver: Ver,
 }
 
@@ -136,9 +144,16 @@ impl PoolKey {
 #[derive(Clone)]
 pub struct Dst {
     alpn_protos: Option<AlpnProtos>,
+    tunnel: bool,
+    alternative: Option<Authority>,
     inner: Arc<PoolKey>,
 }
 
+/// A request extension connecting to another endpoint than the one of the
+/// URI, an alternative service of its origin.
+#[derive(Clone, Debug)]
+pub(crate) struct Alternative(pub(crate) Authority);
+
 impl Dst {
     /// Create a new `Dst` from a request
     pub fn new(
@@ -172,6 +187,8 @@ impl Dst {
         into_uri(scheme, auth)
             .map(|uri| Dst {
                 alpn_protos,
+                tunnel: false,
+                alternative: None,
                 inner: Arc::new(PoolKey::new(uri, network)),
             })
             .map_err(|_| e!(UserAbsoluteUriRequired))
@@ -193,6 +210,30 @@ impl Dst {
         self.alpn_protos
     }
 
+    /// Make the connection a raw tunnel to the destination, with no HTTP
+    /// spoken over it, so an HTTP proxy is asked to `CONNECT` to it.
+    #[inline(always)]
+    pub(crate) fn set_tunnel(&mut self) {
+        self.tunnel = true;
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_tunnel(&self) -> bool {
+        self.tunnel
+    }
+
+    /// Connect to `authority` instead of the authority of the URI, which TLS
+    /// is still set up for.
+    #[inline(always)]
+    pub(crate) fn set_alternative(&mut self, authority: Authority) {
+        self.alternative = Some(authority);
+    }
+
+    #[inline(always)]
+    pub(crate) fn alternative(&self) -> Option<&Authority> {
+        self.alternative.as_ref()
+    }
+
     #[inline(always)]
     pub(crate) fn take_addresses(&mut self) -> (Option<Ipv4Addr>, Option<Ipv6Addr>) {
         Arc::make_mut(&mut self.inner).network.take_addresses()
@@ -218,6 +259,16 @@ impl Dst {
         Arc::make_mut(&mut self.inner).network.take_interface()
     }
 
+    #[inline(always)]
+    pub(crate) fn take_scope_id(&mut self) -> Option<u32> {
+        Arc::make_mut(&mut self.inner).network.take_scope_id()
+    }
+
+    #[inline(always)]
+    pub(crate) fn take_resolve_to(&mut self) -> Option<(String, IpAddr)> {
+        Arc::make_mut(&mut self.inner).network.take_resolve_to()
+    }
+
     #[inline(always)]
     pub(crate) fn take_proxy_scheme(&mut self) -> Option<ProxyScheme> {
         Arc::make_mut(&mut self.inner).network.take_proxy_scheme()
@@ -229,7 +280,7 @@ impl Dst {
     }
 }
 
-impl_debug!(Dst, { alpn_protos, inner });
+impl_debug!(Dst, { alpn_protos, alternative, inner });
 
 impl std::ops::Deref for Dst {
     type Target = Uri;
@@ -344,12 +395,16 @@ where
             other => return ResponseFuture::error_version(other),
         };
 
-        let ctx = match Dst::new(req.uri_mut(), is_http_connect, network_scheme, alpn_protos) {
+        let alternative = req.extensions_mut().remove::<Alternative>();
+        let mut ctx = match Dst::new(req.uri_mut(), is_http_connect, network_scheme, alpn_protos) {
             Ok(s) => s,
             Err(err) => {
                 return ResponseFuture::new(future::err(err));
             }
         };
+        if let Some(Alternative(authority)) = alternative {
+            ctx.set_alternative(authority);
+        }
 
         ResponseFuture::new(self.clone().send_request(req, ctx))
     }
@@ -392,8 +447,9 @@ where
         mut req: Request<B>,
         dst: Dst,
     ) -> Result<Response<hyper2::body::Incoming>, TrySendError<B>> {
+        let fresh = req.extensions().get::<FreshConnection>().is_some();
         let mut pooled = self
-            .connection_for(dst)
+            .connection_for(dst, fresh)
             .await
             // `connection_for` already retries checkout errors, so if
             // it returns an error, there's not much else to retry
@@ -428,6 +484,12 @@ where
             // CONNECT always sends authority-form, so check it first...
             if req.method() == Method::CONNECT {
                 authority_form(req.uri_mut());
+            } else if let Some(target) = req.extensions().get::<RequestTarget>().copied() {
+                match target {
+                    RequestTarget::Origin => origin_form(req.uri_mut()),
+                    // kept as is, even for HTTPS
+                    RequestTarget::Absolute => {}
+                }
             } else if pooled.conn_info.is_proxied {
                 absolute_form(req.uri_mut());
             } else {
@@ -437,6 +499,26 @@ where
             authority_form(req.uri_mut());
         }
 
+        let recording = if self.config.debug_capture {
+            recording(&pooled.conn_info).map(|recording| {
+                // an HTTP/2 connection interleaves requests, only HTTP/1
+                // writes a request at a time
+                if pooled.is_http1() {
+                    recording.start();
+                }
+                let version = if pooled.is_http2() {
+                    Version::HTTP_2
+                } else {
+                    req.version()
+                };
+                (recording, version, req.headers().clone())
+            })
+        } else {
+            None
+        };
+
+        pooled.retirement.count();
+
         let mut res = match pooled.try_send_request(req).await {
             Ok(res) => res,
             Err(mut err) => {
@@ -461,6 +543,11 @@ where
             extra.set(res.extensions_mut());
         }
 
+        if let Some((recording, version, headers)) = recording {
+            let capture = recording.finish(version, headers);
+            res.extensions_mut().insert(capture);
+        }
+
         // If pooled is HTTP/2, we can toss this reference immediately.
         //
         // when pooled is dropped, it will try to insert back into the
@@ -497,7 +584,14 @@ where
     async fn connection_for(
         &self,
         dst: Dst,
+        fresh: bool,
     ) -> Result<pool::Pooled<PoolClient<B>, PoolKey>, Error> {
+        if fresh {
+            // a connection of its own, never reused, and closed once the
+            // response is done with
+            return self.connect_to(dst, &pool::Pool::disabled()).await;
+        }
+
         loop {
             match self.one_connection_for(dst.clone()).await {
                 Ok(pooled) => return Ok(pooled),
@@ -524,7 +618,7 @@ where
         // Return a single connection if pooling is not enabled
         if !self.pool.is_enabled() {
             return self
-                .connect_to(dst)
+                .connect_to(dst, &self.pool)
                 .await
                 .map_err(ClientConnectError::Normal);
         }
@@ -543,7 +637,7 @@ where
         //   connection future is spawned into the runtime to complete,
         //   and then be inserted into the pool as an idle connection.
         let checkout = self.pool.checkout(dst.pool_key().clone());
-        let connect = self.connect_to(dst);
+        let connect = self.connect_to(dst, &self.pool);
         let is_ver_h2 = self.config.ver == Ver::Http2;
 
         // The order of the `select` is depended on below...
@@ -612,10 +706,11 @@ where
     fn connect_to(
         &self,
         dst: Dst,
+        pool: &pool::Pool<PoolClient<B>, PoolKey>,
     ) -> impl Lazy<Output = Result<pool::Pooled<PoolClient<B>, PoolKey>, Error>> + Send + Unpin
     {
         let executor = self.exec.clone();
-        let pool = self.pool.clone();
+        let pool = pool.clone();
 
         let h1_builder = self.h1_builder.clone();
         let h2_builder = self.h2_builder.clone();
@@ -626,6 +721,7 @@ where
         };
         let is_ver_h2 = ver == Ver::Http2;
         let connector = self.connector.clone();
+        let config = self.config;
         hyper_lazy(move || {
             // Try to take a "connecting lock".
             //
@@ -714,6 +810,7 @@ where
                                 PoolClient {
                                     conn_info: connected,
                                     tx,
+                                    retirement: Retirement::new(&config),
                                 },
                             ))
                         }))
@@ -724,10 +821,21 @@ where
 
     /// Get a reference to the inner connector.
     #[inline]
+    pub(crate) fn connector(&self) -> &C {
+        &self.connector
+    }
+
+    /// Get a mutable reference to the inner connector.
+    #[inline]
     pub(crate) fn connector_mut(&mut self) -> &mut C {
         &mut self.connector
     }
 
+    /// Stop sharing the connection pool with the clients this one was cloned from.
+    pub(crate) fn fork_pool(&mut self) {
+        self.pool = self.pool.fork();
+    }
+
     /// Http1 configuration.
     pub(crate) fn http1(&mut self) -> Http1Builder<'_> {
         Http1Builder {
@@ -842,6 +950,45 @@ impl Future for ResponseFuture {
 struct PoolClient<B> {
     conn_info: Connected,
     tx: PoolTx<B>,
+    retirement: Retirement,
+}
+
+/// When a connection is retired, to take no new request.
+#[derive(Clone)]
+struct Retirement {
+    deadline: Option<Instant>,
+    /// The requests given to the connection, shared by the handles of an
+    /// HTTP/2 connection.
+    requests: Arc<AtomicUsize>,
+    max_requests: Option<usize>,
+}
+
+impl Retirement {
+    fn new(config: &Config) -> Retirement {
+        let deadline = config.max_connection_age.map(|max_age| {
+            let jitter = (max_age / 10).mul_f64(super::fast_random() as f64 / u64::MAX as f64);
+            Instant::now() + (max_age - jitter)
+        });
+
+        Retirement {
+            deadline,
+            requests: Arc::new(AtomicUsize::new(0)),
+            max_requests: config.max_connection_requests,
+        }
+    }
+
+    /// Count a request given to the connection.
+    fn count(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn is_due(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+            || self
+                .max_requests
+                .is_some_and(|max| self.requests.load(Ordering::Relaxed) >= max)
+    }
 }
 
 enum PoolTx<B> {
@@ -907,7 +1054,7 @@ where
     B: Send + 'static,
 {
     fn is_open(&self) -> bool {
-        !self.is_poisoned() && self.is_ready()
+        !self.is_poisoned() && self.is_ready() && !self.retirement.is_due()
     }
 
     fn reserve(self) -> pool::Reservation<Self> {
@@ -915,16 +1062,19 @@ where
             PoolTx::Http1(tx) => pool::Reservation::Unique(PoolClient {
                 conn_info: self.conn_info,
                 tx: PoolTx::Http1(tx),
+                retirement: self.retirement,
             }),
 
             PoolTx::Http2(tx) => {
                 let b = PoolClient {
                     conn_info: self.conn_info.clone(),
                     tx: PoolTx::Http2(tx.clone()),
+                    retirement: self.retirement.clone(),
                 };
                 let a = PoolClient {
                     conn_info: self.conn_info,
                     tx: PoolTx::Http2(tx),
+                    retirement: self.retirement,
                 };
                 pool::Reservation::Shared(a, b)
             }
@@ -1016,6 +1166,13 @@ fn is_schema_secure(uri: &Uri) -> bool {
         .unwrap_or_default()
 }
 
+/// The recording of a connection, from the extras of its connector.
+fn recording(connected: &Connected) -> Option<Recording> {
+    let mut extensions = http::Extensions::new();
+    connected.get_extras(&mut extensions);
+    extensions.remove::<Recording>()
+}
+
 /// Http1 part of builder.
 #[derive(Debug)]
 pub struct Http1Builder<'a> {
@@ -1099,6 +1256,9 @@ impl Builder {
             client_config: Config {
                 retry_canceled_requests: true,
                 set_host: true,
+                debug_capture: false,
+                max_connection_age: None,
+                max_connection_requests: None,
                 ver: Ver::Auto,
             },
             exec: exec.clone(),
@@ -1156,6 +1316,9 @@ impl Builder {
 
     /// Sets the maximum number of connections in the pool.
     ///
+    /// The pool is split by host in shards, one per CPU, each keeping an
+    /// equal share of the connections.
+    ///
     /// Default is `None` (no limit).
     pub fn pool_max_size(&mut self, max_size: impl Into<Option<NonZeroUsize>>) -> &mut Self {
         self.pool_config.max_pool_size = max_size.into();
@@ -1229,6 +1392,41 @@ impl Builder {
         self
     }
 
+    /// Set whether to capture what requests put on the wire into a
+    /// [`DebugCapture`] extension of their responses.
+    ///
+    /// The connector must add a [`Recording`] of each connection to its
+    /// [`Connected`] extras.
+    ///
+    /// Default is `false`.
+    #[inline]
+    pub fn debug_capture(&mut self, val: bool) -> &mut Self {
+        self.client_config.debug_capture = val;
+        self
+    }
+
+    /// Set how long connections are used before being retired, with a random
+    /// jitter of up to a tenth of it, so connections opened together don't
+    /// retire together.
+    ///
+    /// A retired connection finishes its requests, but takes no new one.
+    ///
+    /// Default is `None`, for no limit.
+    #[inline]
+    pub fn pool_max_connection_age(&mut self, val: Option<Duration>) -> &mut Self {
+        self.client_config.max_connection_age = val;
+        self
+    }
+
+    /// Set how many requests a connection is given before being retired.
+    ///
+    /// Default is `None`, for no limit.
+    #[inline]
+    pub fn pool_max_connection_requests(&mut self, val: Option<usize>) -> &mut Self {
+        self.client_config.max_connection_requests = val;
+        self
+    }
+
     /// Combine the configuration of this builder with a connector to create a `Client`.
     pub fn build<C, B>(&self, connector: C) -> Client<C, B>
     where
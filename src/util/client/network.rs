@@ -46,6 +46,19 @@ pub enum NetworkScheme {
         /// - **Examples:** HTTP, HTTPS, SOCKS5, SOCKS5h.
         /// - **Purpose:** Routes network traffic through a specified proxy.
         proxy_scheme: Option<ProxyScheme>,
+
+        /// Specifies the scope id of an IPv6 link-local destination.
+        ///
+        /// - **Source:** The zone identifier of the request URL, e.g. `[fe80::1%25eth0]`.
+        /// - **Purpose:** Selects the interface used to reach a link-local address.
+        scope_id: Option<u32>,
+
+        /// Specifies a host and the IP address to reach it at.
+        ///
+        /// - **Source:** [`RequestBuilder::resolve_to`](crate::RequestBuilder::resolve_to).
+        /// - **Purpose:** Overrides the resolution of the host for a single request,
+        ///   and not of the hosts it may be redirected to.
+        resolve_to: Option<(String, IpAddr)>,
     },
 
     /// The default network scheme.
@@ -72,6 +85,22 @@ impl NetworkScheme {
         }
     }
 
+    #[inline(always)]
+    pub(crate) fn proxy_scheme(&self) -> Option<&ProxyScheme> {
+        match self {
+            NetworkScheme::Scheme { proxy_scheme, .. } => proxy_scheme.as_ref(),
+            _ => None,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn proxy_scheme_mut(&mut self) -> Option<&mut ProxyScheme> {
+        match self {
+            NetworkScheme::Scheme { proxy_scheme, .. } => proxy_scheme.as_mut(),
+            _ => None,
+        }
+    }
+
     #[inline(always)]
     pub fn take_addresses(&mut self) -> (Option<Ipv4Addr>, Option<Ipv6Addr>) {
         match self {
@@ -80,6 +109,38 @@ impl NetworkScheme {
         }
     }
 
+    #[inline(always)]
+    pub fn take_scope_id(&mut self) -> Option<u32> {
+        match self {
+            NetworkScheme::Scheme { scope_id, .. } => scope_id.take(),
+            _ => None,
+        }
+    }
+
+    /// Sets the scope id of an IPv6 link-local destination, as for a
+    /// redirect to another host.
+    pub(crate) fn set_scope_id(&mut self, scope_id: Option<u32>) {
+        match self {
+            NetworkScheme::Scheme {
+                scope_id: current, ..
+            } => *current = scope_id,
+            NetworkScheme::Default if scope_id.is_some() => {
+                let mut builder = NetworkScheme::builder();
+                builder.scope_id(scope_id);
+                *self = builder.build();
+            }
+            NetworkScheme::Default => {}
+        }
+    }
+
+    #[inline(always)]
+    pub fn take_resolve_to(&mut self) -> Option<(String, IpAddr)> {
+        match self {
+            NetworkScheme::Scheme { resolve_to, .. } => resolve_to.take(),
+            _ => None,
+        }
+    }
+
     #[cfg(any(
         target_os = "android",
         target_os = "fuchsia",
@@ -126,6 +187,8 @@ impl fmt::Debug for NetworkScheme {
                 interface,
                 addresses,
                 proxy_scheme,
+                scope_id,
+                resolve_to,
             } => {
                 write!(f, "{{")?;
 
@@ -149,6 +212,16 @@ impl fmt::Debug for NetworkScheme {
                     write!(f, " proxy={:?},", proxy)?;
                 }
 
+                // Only print the scope_id value if it is Some and not None
+                if let Some(scope_id) = scope_id {
+                    write!(f, " scope_id={},", scope_id)?;
+                }
+
+                // Only print the resolve_to value if it is Some and not None
+                if let Some((host, ip)) = resolve_to {
+                    write!(f, " resolve_to={}={},", host, ip)?;
+                }
+
                 write!(f, "}}")
             }
             #[cfg(not(any(
@@ -169,6 +242,8 @@ impl fmt::Debug for NetworkScheme {
             NetworkScheme::Scheme {
                 addresses,
                 proxy_scheme,
+                scope_id,
+                resolve_to,
             } => {
                 write!(f, "{{ ")?;
 
@@ -187,6 +262,16 @@ impl fmt::Debug for NetworkScheme {
                     write!(f, " proxy={:?},", proxy)?;
                 }
 
+                // Only print the scope_id value if it is Some and not None
+                if let Some(scope_id) = scope_id {
+                    write!(f, " scope_id={},", scope_id)?;
+                }
+
+                // Only print the resolve_to value if it is Some and not None
+                if let Some((host, ip)) = resolve_to {
+                    write!(f, " resolve_to={}={},", host, ip)?;
+                }
+
                 write!(f, "}}")
             }
             NetworkScheme::Default => {
@@ -217,6 +302,8 @@ pub struct NetworkSchemeBuilder {
     interface: Option<std::borrow::Cow<'static, str>>,
     addresses: (Option<Ipv4Addr>, Option<Ipv6Addr>),
     proxy_scheme: Option<ProxyScheme>,
+    scope_id: Option<u32>,
+    resolve_to: Option<(String, IpAddr)>,
 }
 
 /// ==== impl NetworkSchemeBuilder ====
@@ -271,6 +358,18 @@ impl NetworkSchemeBuilder {
         self
     }
 
+    #[inline]
+    pub fn scope_id(&mut self, scope_id: impl Into<Option<u32>>) -> &mut Self {
+        self.scope_id = scope_id.into();
+        self
+    }
+
+    #[inline]
+    pub fn resolve_to(&mut self, resolve_to: impl Into<Option<(String, IpAddr)>>) -> &mut Self {
+        self.resolve_to = resolve_to.into();
+        self
+    }
+
     #[inline]
     pub fn build(self) -> NetworkScheme {
         #[cfg(any(
@@ -290,8 +389,14 @@ impl NetworkSchemeBuilder {
         ))]
         {
             if matches!(
-                (&self.proxy_scheme, &self.addresses, &self.interface),
-                (None, (None, None), None)
+                (
+                    &self.proxy_scheme,
+                    &self.addresses,
+                    &self.interface,
+                    &self.scope_id,
+                    &self.resolve_to
+                ),
+                (None, (None, None), None, None, None)
             ) {
                 return NetworkScheme::Default;
             }
@@ -300,6 +405,8 @@ impl NetworkSchemeBuilder {
                 interface: self.interface,
                 addresses: self.addresses,
                 proxy_scheme: self.proxy_scheme,
+                scope_id: self.scope_id,
+                resolve_to: self.resolve_to,
             }
         }
 
@@ -319,13 +426,23 @@ impl NetworkSchemeBuilder {
             )
         )))]
         {
-            if matches!((&self.proxy_scheme, &self.addresses), (None, (None, None))) {
+            if matches!(
+                (
+                    &self.proxy_scheme,
+                    &self.addresses,
+                    &self.scope_id,
+                    &self.resolve_to
+                ),
+                (None, (None, None), None, None)
+            ) {
                 return NetworkScheme::Default;
             }
 
             NetworkScheme::Scheme {
                 addresses: self.addresses,
                 proxy_scheme: self.proxy_scheme,
+                scope_id: self.scope_id,
+                resolve_to: self.resolve_to,
             }
         }
     }
@@ -1,9 +1,10 @@
+use std::collections::hash_map::RandomState;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::convert::Infallible;
 use std::error::Error as StdError;
 use std::fmt::{self, Debug};
 use std::future::Future;
-use std::hash::Hash;
+use std::hash::{BuildHasher, Hash};
 use std::num::NonZero;
 use std::ops::{Deref, DerefMut};
 use std::pin::Pin;
@@ -26,7 +27,29 @@ use crate::util::common::{exec, exec::Exec, timer::Timer};
 #[allow(missing_debug_implementations)]
 pub struct Pool<T, K: Key> {
     // If the pool is disabled, this is None.
-    inner: Option<Arc<Mutex<PoolInner<T, K>>>>,
+    inner: Option<Arc<Shards<T, K>>>,
+}
+
+/// The most shards a pool is split in.
+const MAX_SHARDS: usize = 64;
+
+/// The pool, split by key in shards locked apart, so tasks checking out
+/// connections to different hosts don't wait on each other.
+struct Shards<T, K: Eq + Hash> {
+    shards: Box<[Arc<Mutex<PoolInner<T, K>>>]>,
+    hasher: RandomState,
+}
+
+impl<T, K: Eq + Hash> Shards<T, K> {
+    /// The shard pooling the connections for `key`.
+    fn get(&self, key: &K) -> &Arc<Mutex<PoolInner<T, K>>> {
+        if self.shards.len() == 1 {
+            return &self.shards[0];
+        }
+        // the number of shards is a power of two
+        let hash = self.hasher.hash_one(key) as usize;
+        &self.shards[hash & (self.shards.len() - 1)]
+    }
 }
 
 // Before using a pooled connection, make sure the sender is not dead.
@@ -123,33 +146,105 @@ impl<T, K: Key> Pool<T, K> {
         E: hyper2::rt::Executor<exec::BoxSendFuture> + Send + Sync + Clone + 'static,
         M: hyper2::rt::Timer + Send + Sync + Clone + 'static,
     {
+        if !config.is_enabled() {
+            return Pool { inner: None };
+        }
+
         let exec = Exec::new(executor);
         let timer = timer.map(Timer::new);
-        let idle = match config.max_pool_size {
-            Some(max_size) => LruCache::new(max_size),
-            None => LruCache::unbounded(),
-        };
-        let inner = if config.is_enabled() {
-            Some(Arc::new(Mutex::new(PoolInner {
-                connecting: HashSet::new(),
-                idle,
-                idle_interval_ref: None,
-                max_idle_per_host: config.max_idle_per_host,
-                waiters: HashMap::new(),
-                exec,
-                timer,
-                timeout: config.idle_timeout,
-            })))
-        } else {
-            None
-        };
+        // a shard per CPU, but no more than connections the pool may keep,
+        // so each shard keeps its share of them
+        let mut count = std::thread::available_parallelism()
+            .map_or(1, NonZero::get)
+            .next_power_of_two()
+            .min(MAX_SHARDS);
+        if let Some(max_size) = config.max_pool_size {
+            while count > max_size.get() {
+                count /= 2;
+            }
+        }
 
-        Pool { inner }
+        // the shares of the shards add up to the limit, and each shard
+        // evicts its own least recently used host once it holds its share
+        let shards = (0..count)
+            .map(|i| {
+                let idle = match config.max_pool_size {
+                    Some(max_size) => {
+                        let share =
+                            max_size.get() / count + usize::from(i < max_size.get() % count);
+                        LruCache::new(NonZero::new(share).expect("non-zero"))
+                    }
+                    None => LruCache::unbounded(),
+                };
+                Arc::new(Mutex::new(PoolInner {
+                    connecting: HashSet::new(),
+                    idle,
+                    idle_interval_ref: None,
+                    max_idle_per_host: config.max_idle_per_host,
+                    waiters: HashMap::new(),
+                    exec: exec.clone(),
+                    timer: timer.clone(),
+                    timeout: config.idle_timeout,
+                }))
+            })
+            .collect();
+
+        Pool {
+            inner: Some(Arc::new(Shards {
+                shards,
+                hasher: RandomState::new(),
+            })),
+        }
+    }
+
+    /// A pool keeping no connection, for connections used once.
+    pub(crate) fn disabled() -> Pool<T, K> {
+        Pool { inner: None }
     }
 
     pub(crate) fn is_enabled(&self) -> bool {
         self.inner.is_some()
     }
+
+    /// An empty pool with the same settings, sharing no connection with this one.
+    pub(crate) fn fork(&self) -> Pool<T, K> {
+        let inner = self.inner.as_ref().map(|inner| {
+            let shards = inner
+                .shards
+                .iter()
+                .map(|shard| {
+                    let shard = shard.lock();
+                    // an unbounded cache has a capacity of `usize::MAX`,
+                    // which `LruCache::new` would try to allocate
+                    let idle = match shard.idle.cap() {
+                        cap if cap.get() == usize::MAX => LruCache::unbounded(),
+                        cap => LruCache::new(cap),
+                    };
+                    Arc::new(Mutex::new(PoolInner {
+                        connecting: HashSet::new(),
+                        idle,
+                        idle_interval_ref: None,
+                        max_idle_per_host: shard.max_idle_per_host,
+                        waiters: HashMap::new(),
+                        exec: shard.exec.clone(),
+                        timer: shard.timer.clone(),
+                        timeout: shard.timeout,
+                    }))
+                })
+                .collect();
+            Arc::new(Shards {
+                shards,
+                hasher: inner.hasher.clone(),
+            })
+        });
+
+        Pool { inner }
+    }
+
+    /// The shard of the pool for `key`, if the pool is enabled.
+    fn shard(&self, key: &K) -> Option<&Arc<Mutex<PoolInner<T, K>>>> {
+        self.inner.as_ref().map(|inner| inner.get(key))
+    }
 }
 
 impl<T: Poolable, K: Key> Pool<T, K> {
@@ -167,7 +262,7 @@ impl<T: Poolable, K: Key> Pool<T, K> {
     /// connections. This does nothing for HTTP/1.
     pub fn connecting(&self, key: &K, ver: Ver) -> Option<Connecting<T, K>> {
         if ver == Ver::Http2 {
-            if let Some(ref enabled) = self.inner {
+            if let Some(enabled) = self.shard(key) {
                 let mut inner = enabled.lock();
                 return if inner.connecting.insert(key.clone()) {
                     let connecting = Connecting {
@@ -192,7 +287,7 @@ impl<T: Poolable, K: Key> Pool<T, K> {
     }
 
     pub fn pooled(&self, mut connecting: Connecting<T, K>, value: T) -> Pooled<T, K> {
-        let (value, pool_ref) = if let Some(ref enabled) = self.inner {
+        let (value, pool_ref) = if let Some(enabled) = self.shard(&connecting.key) {
             match value.reserve() {
                 Reservation::Shared(to_insert, to_return) => {
                     let mut inner = enabled.lock();
@@ -243,7 +338,7 @@ impl<T: Poolable, K: Key> Pool<T, K> {
         // shared... :(
         let mut pool_ref = WeakOpt::none();
         if !value.can_share() {
-            if let Some(ref enabled) = self.inner {
+            if let Some(enabled) = self.shard(key) {
                 pool_ref = WeakOpt::downgrade(enabled);
             }
         }
@@ -609,7 +704,7 @@ impl<T: Poolable, K: Key> Checkout<T, K> {
 
     fn checkout(&mut self, cx: &mut task::Context<'_>) -> Option<Pooled<T, K>> {
         let entry = {
-            let mut inner = self.pool.inner.as_ref()?.lock();
+            let mut inner = self.pool.shard(&self.key)?.lock();
             let expiration = Expiration::new(inner.timeout);
             let maybe_entry = inner.idle.get_mut(&self.key).and_then(|list| {
                 trace!("take? {:?}: expiration = {:?}", self.key, expiration.0);
@@ -684,7 +779,7 @@ impl<T, K: Key> Drop for Checkout<T, K> {
     fn drop(&mut self) {
         if self.waiter.take().is_some() {
             trace!("checkout dropped for {:?}", self.key);
-            if let Some(mut inner) = self.pool.inner.as_ref().map(|i| i.lock()) {
+            if let Some(mut inner) = self.pool.shard(&self.key).map(|i| i.lock()) {
                 inner.clean_waiters(&self.key);
             }
         }
@@ -800,3 +895,115 @@ impl<T> WeakOpt<T> {
         self.0.as_ref().and_then(Weak::upgrade)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use futures_util::FutureExt;
+
+    use super::{Config, Pool, Poolable, Reservation};
+    use crate::util::rt::{TokioExecutor, TokioTimer};
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Uniq(i32);
+
+    impl Poolable for Uniq {
+        fn is_open(&self) -> bool {
+            true
+        }
+
+        fn reserve(self) -> Reservation<Self> {
+            Reservation::Unique(self)
+        }
+
+        fn can_share(&self) -> bool {
+            false
+        }
+    }
+
+    fn pool(max_pool_size: usize) -> Pool<Uniq, String> {
+        let config = Config {
+            idle_timeout: None,
+            max_idle_per_host: usize::MAX,
+            max_pool_size: NonZero::new(max_pool_size),
+        };
+        Pool::new(config, TokioExecutor::new(), None::<TokioTimer>)
+    }
+
+    /// Pool an idle connection for `key`.
+    fn put(pool: &Pool<Uniq, String>, key: &str, value: i32) {
+        let connecting = pool.connecting(&key.to_owned(), super::Ver::Auto).unwrap();
+        drop(pool.pooled(connecting, Uniq(value)));
+    }
+
+    fn idle_hosts(pool: &Pool<Uniq, String>) -> Vec<usize> {
+        let shards = &pool.inner.as_ref().unwrap().shards;
+        shards.iter().map(|shard| shard.lock().idle.len()).collect()
+    }
+
+    #[test]
+    fn checkout_across_shards() {
+        let pool = pool(1000);
+        for i in 0..100 {
+            put(&pool, &format!("host{i}"), i);
+        }
+        for i in 0..100 {
+            let pooled = pool
+                .checkout(format!("host{i}"))
+                .now_or_never()
+                .expect("idle connection")
+                .unwrap();
+            assert_eq!(*pooled, Uniq(i));
+        }
+    }
+
+    #[test]
+    fn shards_share_the_max_size() {
+        let pool = pool(10);
+        let shards = &pool.inner.as_ref().unwrap().shards;
+        let caps: usize = shards
+            .iter()
+            .map(|shard| shard.lock().idle.cap().get())
+            .sum();
+        assert_eq!(caps, 10);
+
+        for i in 0..1000 {
+            put(&pool, &format!("host{i}"), i);
+        }
+        // each shard evicts its least recently used hosts once full
+        assert_eq!(idle_hosts(&pool).iter().sum::<usize>(), 10);
+        assert!(pool.checkout("host999".to_owned()).now_or_never().is_some());
+        assert!(pool.checkout("host0".to_owned()).now_or_never().is_none());
+    }
+
+    #[test]
+    fn fork_shares_no_connection() {
+        let pool = pool(10);
+        put(&pool, "host", 1);
+
+        let fork = pool.fork();
+        assert_eq!(idle_hosts(&fork).iter().sum::<usize>(), 0);
+        assert!(fork.checkout("host".to_owned()).now_or_never().is_none());
+
+        put(&fork, "host", 2);
+        let pooled = pool.checkout("host".to_owned()).now_or_never().unwrap();
+        assert_eq!(*pooled.unwrap(), Uniq(1));
+        let pooled = fork.checkout("host".to_owned()).now_or_never().unwrap();
+        assert_eq!(*pooled.unwrap(), Uniq(2));
+    }
+
+    #[test]
+    fn fork_unbounded_pool() {
+        // without a max size, as by default
+        let pool = pool(0);
+        put(&pool, "host", 1);
+
+        let fork = pool.fork();
+        for i in 0..100 {
+            put(&fork, &format!("host{i}"), i);
+        }
+        assert_eq!(idle_hosts(&fork).iter().sum::<usize>(), 100);
+        assert_eq!(idle_hosts(&pool).iter().sum::<usize>(), 1);
+    }
+}
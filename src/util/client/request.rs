@@ -9,6 +9,27 @@ use http::{
 use http_body::Body;
 use std::{any::Any, marker::PhantomData};
 
+/// The form of the request target of an HTTP/1 request line.
+///
+/// By default the target is in origin-form (`GET /path HTTP/1.1`), or in
+/// absolute-form (`GET http://host/path HTTP/1.1`) when sent to an HTTP
+/// proxy, and `CONNECT` requests are in authority-form. Forcing a form is
+/// useful to test proxies, or servers that accept both. HTTP/2 requests
+/// have no request line, and ignore it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RequestTarget {
+    /// The path and query, such as `/where?q=now`.
+    Origin,
+    /// The whole URI, such as `http://www.example.org/where?q=now`.
+    Absolute,
+}
+
+/// Marks a request to be sent on a new connection of its own, rather than
+/// one from the pool.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FreshConnection;
+
 pub struct InnerRequest<B>
 where
     B: Body + Send + Unpin + 'static,
@@ -146,7 +167,7 @@ fn map_alpn_protos(version: Version) -> Option<AlpnProtos> {
 
 /// Add the `Content-Length` header to the request.
 #[inline]
-fn add_content_length_header<B>(headers: &mut HeaderMap, body: &B)
+pub(crate) fn add_content_length_header<B>(headers: &mut HeaderMap, body: &B)
 where
     B: Body,
 {
@@ -160,27 +181,82 @@ where
 /// Sort the headers in the specified order.
 ///
 /// Headers in `headers_order` are sorted to the front, preserving their order.
-/// Remaining headers are appended in their original order.
+/// Remaining headers are appended in their original order. Every value of a
+/// repeated header is kept. Headers already in order are left as they are,
+/// without building a new map.
 #[inline]
-fn sort_headers(headers: &mut HeaderMap, headers_order: &[HeaderName]) {
-    if headers.len() <= 1 {
+pub(crate) fn sort_headers(headers: &mut HeaderMap, headers_order: &[HeaderName]) {
+    if headers.keys_len() <= 1 || is_sorted(headers, headers_order) {
         return;
     }
 
-    let mut sorted_headers = HeaderMap::with_capacity(headers.keys_len());
+    let mut sorted_headers = HeaderMap::with_capacity(headers.len());
 
-    // First insert headers in the specified order
-    for (key, value) in headers_order
-        .iter()
-        .filter_map(|key| headers.remove(key).map(|value| (key, value)))
-    {
-        sorted_headers.insert(key, value);
+    // First insert headers in the specified order, with all their values
+    for key in headers_order {
+        for value in headers.get_all(key) {
+            sorted_headers.append(key, value.clone());
+        }
     }
 
     // Then insert any remaining headers that were not ordered
-    for (key, value) in headers.drain().filter_map(|(k, v)| k.map(|k| (k, v))) {
-        sorted_headers.insert(key, value);
+    for (key, value) in headers.iter() {
+        if !headers_order.contains(key) {
+            sorted_headers.append(key, value.clone());
+        }
     }
 
     std::mem::swap(headers, &mut sorted_headers);
 }
+
+/// Whether the headers in `headers_order` come first in `headers`, in that
+/// order.
+fn is_sorted(headers: &HeaderMap, headers_order: &[HeaderName]) -> bool {
+    let mut next = 0;
+    let mut unordered = false;
+    for key in headers.keys() {
+        match headers_order.iter().position(|name| name == key) {
+            Some(i) if !unordered && i >= next => next = i + 1,
+            Some(_) => return false,
+            None => unordered = true,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use http::header::{ACCEPT, COOKIE, HOST, USER_AGENT};
+
+    use super::*;
+
+    #[test]
+    fn sort_headers_keeps_every_value() {
+        let order = [HOST, USER_AGENT, ACCEPT];
+
+        let mut headers = HeaderMap::new();
+        headers.append(COOKIE, HeaderValue::from_static("a=1"));
+        headers.append(ACCEPT, HeaderValue::from_static("text/html"));
+        headers.append(COOKIE, HeaderValue::from_static("b=2"));
+        headers.append(ACCEPT, HeaderValue::from_static("*/*"));
+        headers.append(HOST, HeaderValue::from_static("example.com"));
+        assert!(!is_sorted(&headers, &order));
+
+        sort_headers(&mut headers, &order);
+        let sorted = headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.to_str().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sorted,
+            [
+                ("host", "example.com"),
+                ("accept", "text/html"),
+                ("accept", "*/*"),
+                ("cookie", "a=1"),
+                ("cookie", "b=2"),
+            ]
+        );
+        assert!(is_sorted(&headers, &order));
+    }
+}
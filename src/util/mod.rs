@@ -38,6 +38,108 @@ where
     header
 }
 
+/// A generator drawing the same values for the same seed, for output that
+/// must be reproducible.
+#[derive(Debug)]
+pub(crate) struct SeededRandom {
+    seed: u64,
+    state: std::sync::atomic::AtomicU64,
+}
+
+impl SeededRandom {
+    pub(crate) fn new(seed: u64) -> SeededRandom {
+        SeededRandom {
+            seed,
+            state: std::sync::atomic::AtomicU64::new(seed),
+        }
+    }
+
+    pub(crate) fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // splitmix64
+    pub(crate) fn next(&self) -> u64 {
+        const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut z = self
+            .state
+            .fetch_add(GAMMA, std::sync::atomic::Ordering::Relaxed)
+            .wrapping_add(GAMMA);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// The civil date `(year, month, day)` of a count of days since the Unix
+/// epoch, from Howard Hinnant's `civil_from_days`.
+#[cfg_attr(not(any(feature = "json", feature = "aws-sigv4")), allow(dead_code))]
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// The count of days since the Unix epoch of the civil date `(year, month,
+/// day)`, the inverse of [`civil_from_days`].
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Parse an HTTP-date ([RFC 9110, section 5.6.7]), in the IMF-fixdate
+/// format or in the obsolete RFC 850 and asctime ones.
+///
+/// [RFC 9110, section 5.6.7]: https://datatracker.ietf.org/doc/html/rfc9110#section-5.6.7
+pub(crate) fn parse_http_date(value: &str) -> Option<std::time::SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let fields: Vec<&str> = value
+        .split(|c: char| c == ' ' || c == '-' || c == ',')
+        .filter(|field| !field.is_empty())
+        .collect();
+    // IMF-fixdate and RFC 850: weekday day month year time GMT;
+    // asctime: weekday month day time year
+    let (day, month, year, time) = match fields[..] {
+        [_, day, month, year, time, "GMT"] => (day, month, year, time),
+        [_, month, day, time, year] => (day, month, year, time),
+        _ => return None,
+    };
+
+    let day: i64 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|name| *name == month)? as i64 + 1;
+    let mut year: i64 = year.parse().ok()?;
+    if year < 100 {
+        // RFC 850 years are interpreted as the nearest, in the past
+        year += if year < 70 { 2000 } else { 1900 };
+    }
+    let mut time = time.split(':').map(|field| field.parse::<i64>().ok());
+    let (hour, minute, second) = match (time.next(), time.next(), time.next(), time.next()) {
+        (Some(Some(h)), Some(Some(m)), Some(Some(s)), None) => (h, m, s),
+        _ => return None,
+    };
+    if !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let secs = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second;
+    let secs = u64::try_from(secs).ok()?;
+    Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+}
+
 // xor-shift
 pub(crate) fn fast_random() -> u64 {
     use std::cell::Cell;
@@ -103,6 +205,23 @@ pub(crate) fn replace_headers(dst: &mut HeaderMap, src: HeaderMap) {
     }
 }
 
+/// Whether `host` matches `pattern`: a host name or IP address, matched
+/// exactly, a wildcard such as `*.internal` matching every subdomain, or `*`
+/// matching every host.
+pub(crate) fn host_matches(pattern: &str, host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if pattern == "*" {
+        return true;
+    }
+
+    match pattern.strip_prefix("*.") {
+        Some(suffix) => host.len().checked_sub(suffix.len() + 1).is_some_and(|dot| {
+            host.as_bytes()[dot] == b'.' && host[dot + 1..].eq_ignore_ascii_case(suffix)
+        }),
+        None => host.eq_ignore_ascii_case(pattern),
+    }
+}
+
 /// Convert a scheme and host to a URI
 #[inline]
 pub(crate) fn into_uri(scheme: Scheme, host: Authority) -> Result<Uri, http::Error> {
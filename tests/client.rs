@@ -161,6 +161,234 @@ async fn response_json() {
     assert_eq!("Hello", text);
 }
 
+#[tokio::test]
+#[cfg(feature = "simd-json")]
+async fn response_json_large() {
+    let server = server::http(move |_req| async {
+        let items = vec!["item"; 20_000];
+        http::Response::new(serde_json::to_vec(&items).unwrap().into())
+    });
+
+    let items = Client::new()
+        .get(format!("http://{}/json", server.addr()))
+        .send()
+        .await
+        .expect("Failed to get")
+        .json::<Vec<String>>()
+        .await
+        .expect("Failed to get json");
+    assert_eq!(items.len(), 20_000);
+    assert!(items.iter().all(|item| item == "item"));
+}
+
+#[tokio::test]
+#[cfg(feature = "json")]
+async fn response_json_lines() {
+    use futures_util::StreamExt;
+
+    let server = server::http(move |_req| async {
+        http::Response::new("{\"n\":1}\r\n\n{\"n\":2}\nnot json\n{\"n\":3}".into())
+    });
+
+    let res = Client::new()
+        .get(format!("http://{}/ndjson", server.addr()))
+        .send()
+        .await
+        .expect("Failed to get");
+    let values: Vec<_> = res
+        .json_lines::<HashMap<String, u32>>()
+        .map(|value| value.map(|value| value["n"]))
+        .collect()
+        .await;
+
+    assert_eq!(values.len(), 4);
+    assert_eq!(values[0].as_ref().unwrap(), &1);
+    assert_eq!(values[1].as_ref().unwrap(), &2);
+    assert!(values[2].as_ref().unwrap_err().is_decode());
+    assert_eq!(values[3].as_ref().unwrap(), &3);
+}
+
+#[tokio::test]
+#[cfg(feature = "json")]
+async fn response_json_array_stream() {
+    use futures_util::TryStreamExt;
+
+    let server = server::http(move |_req| async {
+        http::Response::new("[{\"n\": [1]}, {\"n\": [2, 3]}]".into())
+    });
+
+    let res = Client::new()
+        .get(format!("http://{}/array", server.addr()))
+        .send()
+        .await
+        .expect("Failed to get");
+    let values: Vec<HashMap<String, Vec<u32>>> = res
+        .json_array_stream()
+        .try_collect()
+        .await
+        .expect("Failed to stream json");
+
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[0]["n"], [1]);
+    assert_eq!(values[1]["n"], [2, 3]);
+}
+
+#[tokio::test]
+#[cfg(all(feature = "msgpack", feature = "cbor"))]
+async fn msgpack_and_cbor_round_trip() {
+    use http_body_util::BodyExt;
+
+    let server = server::http(move |req| async move {
+        let content_type = req.headers()[CONTENT_TYPE].clone();
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        http::Response::builder()
+            .header(CONTENT_TYPE, content_type)
+            .body(body.into())
+            .unwrap()
+    });
+
+    let client = Client::new();
+    let url = format!("http://{}/echo", server.addr());
+    let value = std::collections::BTreeMap::from([("name", "rquest"), ("lang", "rust")]);
+
+    let res = client.post(&url).msgpack(&value).send().await.unwrap();
+    assert_eq!(res.headers()[CONTENT_TYPE], "application/msgpack");
+    let echoed: std::collections::BTreeMap<String, String> = res.msgpack().await.unwrap();
+    assert_eq!(echoed["name"], "rquest");
+
+    let res = client.post(&url).cbor(&value).send().await.unwrap();
+    assert_eq!(res.headers()[CONTENT_TYPE], "application/cbor");
+    let echoed: std::collections::BTreeMap<String, String> = res.cbor().await.unwrap();
+    assert_eq!(echoed["lang"], "rust");
+}
+
+#[tokio::test]
+#[cfg(feature = "xml")]
+async fn xml_round_trip() {
+    use http_body_util::BodyExt;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Url {
+        loc: String,
+        priority: f32,
+    }
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()[CONTENT_TYPE], "application/xml");
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(
+            body,
+            "<Url><loc>https://example.com/</loc><priority>0.5</priority></Url>"
+        );
+        http::Response::new(body.into())
+    });
+
+    let url = Url {
+        loc: "https://example.com/".to_owned(),
+        priority: 0.5,
+    };
+    let echoed: Url = Client::new()
+        .post(format!("http://{}/sitemap", server.addr()))
+        .xml(&url)
+        .send()
+        .await
+        .unwrap()
+        .xml()
+        .await
+        .unwrap();
+    assert_eq!(echoed, url);
+}
+
+#[tokio::test]
+#[cfg(feature = "json")]
+async fn graphql_data_and_errors() {
+    use http_body_util::BodyExt;
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.method(), "POST");
+        assert_eq!(req.headers()[CONTENT_TYPE], "application/json");
+        assert!(req.headers()["accept"]
+            .to_str()
+            .unwrap()
+            .starts_with("application/graphql-response+json"));
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let response = if body["variables"]["id"] == 1 {
+            r#"{"data": {"user": {"name": "ann"}}}"#
+        } else {
+            r#"{"data": null, "errors": [{"message": "no such user",
+                "locations": [{"line": 1, "column": 9}], "path": ["user"]}]}"#
+        };
+        http::Response::new(response.into())
+    });
+
+    let client = Client::new();
+    let url = format!("http://{}/graphql", server.addr());
+    let query = "query($id: Int!) { user(id: $id) { name } }";
+
+    let data: serde_json::Value = client
+        .post(&url)
+        .graphql(query, &serde_json::json!({ "id": 1 }))
+        .send()
+        .await
+        .unwrap()
+        .graphql_data()
+        .await
+        .unwrap();
+    assert_eq!(data["user"]["name"], "ann");
+
+    let err = client
+        .post(&url)
+        .graphql(query, &serde_json::json!({ "id": 2 }))
+        .send()
+        .await
+        .unwrap()
+        .graphql_data::<serde_json::Value>()
+        .await
+        .unwrap_err();
+    assert!(err.is_decode());
+    let errors = err.graphql_errors().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message(), "no such user");
+    assert_eq!(errors[0].locations()[0].column, 9);
+    assert_eq!(errors[0].path(), ["user"]);
+}
+
+#[tokio::test]
+#[cfg(feature = "protobuf")]
+async fn protobuf_round_trip() {
+    use http_body_util::BodyExt;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Point {
+        #[prost(int32, tag = "1")]
+        x: i32,
+        #[prost(string, tag = "2")]
+        label: String,
+    }
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()[CONTENT_TYPE], "application/x-protobuf");
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        http::Response::new(body.into())
+    });
+
+    let point = Point {
+        x: 42,
+        label: "answer".to_owned(),
+    };
+    let echoed: Point = Client::new()
+        .post(format!("http://{}/point", server.addr()))
+        .protobuf(&point)
+        .send()
+        .await
+        .unwrap()
+        .protobuf()
+        .await
+        .unwrap();
+    assert_eq!(echoed, point);
+}
+
 #[tokio::test]
 async fn body_pipe_response() {
     use http_body_util::BodyExt;
@@ -266,6 +494,64 @@ async fn overridden_dns_resolution_with_gai_multiple() {
     assert_eq!("Hello", text);
 }
 
+#[tokio::test]
+async fn overridden_dns_resolution_with_wildcard_and_range() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let server = server::http(move |_req| async { http::Response::new("Hello".into()) });
+
+    let client = rquest::Client::builder()
+        .no_proxy()
+        .resolve_to_range("*.staging.test", "127.0.0.1/32")
+        .resolve("*.example.test", server.addr())
+        .build()
+        .expect("client builder");
+
+    for host in ["a.staging.test", "a.b.staging.test", "www.example.test"] {
+        let url = format!("http://{host}:{}/domain_override", server.addr().port());
+        let res = client.get(&url).send().await.expect("request");
+        assert_eq!(res.status(), rquest::StatusCode::OK);
+    }
+
+    let err = rquest::Client::builder()
+        .resolve_to_range("*.staging.test", "127.0.0.1/33")
+        .build()
+        .unwrap_err();
+    assert!(err.is_builder());
+}
+
+#[tokio::test]
+async fn weighted_dns_resolver_keeps_discovered_ports() {
+    use rquest::dns::{Name, ResolveWeighted, ResolvingWeighted, WeightedAddr};
+    use std::net::SocketAddr;
+
+    struct Discovery(SocketAddr);
+
+    impl ResolveWeighted for Discovery {
+        fn resolve_weighted(&self, name: Name) -> ResolvingWeighted {
+            assert_eq!(name.as_str(), "svc.test");
+            assert_eq!(name.port(), 80);
+            assert!(name.wants_ipv4() && name.wants_ipv6());
+            let addrs = vec![WeightedAddr::new(self.0, 1)];
+            Box::pin(async move { Ok(addrs) })
+        }
+    }
+
+    let server = server::http(move |_req| async { http::Response::new("Hello".into()) });
+
+    let client = rquest::Client::builder()
+        .no_proxy()
+        .weighted_dns_resolver(std::sync::Arc::new(Discovery(server.addr())))
+        .build()
+        .expect("client builder");
+
+    let res = client
+        .get("http://svc.test/")
+        .send()
+        .await
+        .expect("request");
+    assert_eq!(res.text().await.expect("text"), "Hello");
+}
+
 #[cfg(feature = "hickory-dns")]
 #[tokio::test]
 async fn overridden_dns_resolution_with_hickory_dns() {
@@ -355,6 +641,189 @@ fn update_json_content_type_if_set_manually() {
     assert_eq!("application/json", req.headers().get(CONTENT_TYPE).unwrap());
 }
 
+#[tokio::test]
+async fn per_request_local_address() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // answers each connection with the address it came from
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/local", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, peer) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut buf = [0; 1024];
+                let _ = socket.read(&mut buf).await;
+                let body = peer.ip().to_string();
+                let res = format!(
+                    "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(res.as_bytes()).await;
+            });
+        }
+    });
+
+    let client = Client::builder()
+        .no_proxy()
+        .local_address(std::net::IpAddr::from([0, 0, 0, 0]))
+        .build()
+        .unwrap();
+
+    let mut local_addresses = vec![[127, 0, 0, 1]];
+    // the whole 127.0.0.0/8 network is local on Linux
+    if cfg!(target_os = "linux") {
+        local_addresses.push([127, 0, 0, 2]);
+    }
+    for local_address in local_addresses {
+        let local_address = std::net::IpAddr::from(local_address);
+        let res = client
+            .get(&url)
+            .local_address(local_address)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), rquest::StatusCode::OK);
+        assert_eq!(res.text().await.unwrap(), local_address.to_string());
+    }
+}
+
+#[tokio::test]
+async fn tcp_socket_options() {
+    let server = server::http(move |_req| async { http::Response::default() });
+
+    let builder = Client::builder()
+        .no_proxy()
+        .tcp_nodelay(true)
+        .tcp_keepalive(std::time::Duration::from_secs(30))
+        .tcp_keepalive_interval(std::time::Duration::from_secs(5))
+        .tcp_keepalive_retries(3)
+        .tcp_send_buffer_size(64 * 1024)
+        .tcp_recv_buffer_size(64 * 1024);
+
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    let builder = builder.tcp_user_timeout(std::time::Duration::from_secs(10));
+
+    let builder = builder.ip_ttl(64);
+
+    #[cfg(unix)]
+    let builder = builder.tcp_mss(1200);
+
+    let client = builder.build().unwrap();
+    let url = format!("http://{}/tcp", server.addr());
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+
+    // the pooled connection is a socket of this process
+    #[cfg(target_os = "linux")]
+    {
+        let user_timeouts = std::fs::read_dir("/proc/self/fd")
+            .unwrap()
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let target = std::fs::read_link(entry.path()).ok()?;
+                if !target.to_string_lossy().starts_with("socket:") {
+                    return None;
+                }
+                let fd = entry.file_name().to_str()?.parse::<i32>().ok()?;
+                let mut value: libc::c_uint = 0;
+                let mut len = std::mem::size_of_val(&value) as libc::socklen_t;
+                let ret = unsafe {
+                    libc::getsockopt(
+                        fd,
+                        libc::IPPROTO_TCP,
+                        libc::TCP_USER_TIMEOUT,
+                        &mut value as *mut _ as *mut libc::c_void,
+                        &mut len,
+                    )
+                };
+                (ret == 0).then_some(value)
+            })
+            .collect::<Vec<_>>();
+        assert!(user_timeouts.contains(&10_000), "{user_timeouts:?}");
+    }
+}
+
+#[tokio::test]
+async fn raw_path_and_query() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.uri(), "/a%2Fb//../c?x=%41");
+        http::Response::default()
+    });
+
+    let url = format!("http://{}/ignored", server.addr());
+    let res = Client::builder()
+        .no_proxy()
+        .build()
+        .unwrap()
+        .get(&url)
+        .raw_path_and_query("/a%2Fb//../c?x=%41")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+
+    let err = Client::new()
+        .get(&url)
+        .raw_path_and_query("no-leading-slash")
+        .build()
+        .unwrap_err();
+    assert!(err.is_builder());
+}
+
+#[tokio::test]
+async fn ipv6_zone_sets_scope_id() {
+    use std::net::SocketAddr;
+
+    let scope_id = |err: rquest::Error| match err.remote_addr() {
+        Some(SocketAddr::V6(addr)) => addr.scope_id(),
+        other => panic!("unexpected remote address {other:?}"),
+    };
+
+    let client = Client::builder()
+        .no_proxy()
+        .connect_timeout(std::time::Duration::from_secs(1))
+        .build()
+        .unwrap();
+
+    // nothing listens there, the address tried is what matters
+    let err = client
+        .get("http://[fe80::1%251]:9/")
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_connect());
+    assert_eq!(scope_id(err), 1);
+
+    // and a redirect takes the zone of its location
+    let server = server::http(move |_req| async {
+        http::Response::builder()
+            .status(302)
+            .header("location", "http://[fe80::1%252]:9/")
+            .body(Default::default())
+            .unwrap()
+    });
+    let err = client
+        .get(format!("http://{}/", server.addr()))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_connect());
+    assert_eq!(scope_id(err), 2);
+
+    // relative URLs keep the zone of the base URL
+    let client = Client::builder()
+        .no_proxy()
+        .connect_timeout(std::time::Duration::from_secs(1))
+        .base_url("http://[fe80::1%253]:9/api")
+        .build()
+        .unwrap();
+    let err = client.get("users").send().await.unwrap_err();
+    assert!(err.is_connect());
+    assert_eq!(scope_id(err), 3);
+}
+
 #[tokio::test]
 async fn test_tls_info() {
     let resp = rquest::Client::builder()
@@ -557,3 +1026,2473 @@ async fn http2_version() {
 
     assert_eq!(resp.version(), rquest::Version::HTTP_2);
 }
+
+#[tokio::test]
+async fn custom_connector() {
+    use rquest::transport::{Connecting, Connector};
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    struct Fixed(SocketAddr);
+
+    impl Connector for Fixed {
+        fn connect(&self, uri: &http::Uri) -> Connecting {
+            assert_eq!(uri.host(), Some("custom.transport"));
+            let addr = self.0;
+            Box::pin(async move {
+                let stream = tokio::net::TcpStream::connect(addr).await?;
+                Ok(Box::new(stream) as _)
+            })
+        }
+    }
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["host"], "custom.transport");
+        http::Response::default()
+    });
+
+    let client = Client::builder()
+        .connector(Arc::new(Fixed(server.addr())))
+        .build()
+        .unwrap();
+
+    let res = client.get("http://custom.transport/").send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn proxy_protocol_header() {
+    use tokio::io::AsyncWriteExt;
+
+    let server = server::low_level_with_response(|raw_request, client_socket| {
+        Box::new(async move {
+            let request = String::from_utf8_lossy(raw_request);
+            let status = if request.starts_with("PROXY TCP4 127.0.0.1 127.0.0.1 ") {
+                "200 OK"
+            } else {
+                "400 Bad Request"
+            };
+            let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status);
+            client_socket
+                .write_all(response.as_bytes())
+                .await
+                .expect("response write_all failed");
+        })
+    });
+
+    let client = Client::builder()
+        .no_proxy()
+        .proxy_protocol("127.0.0.1", rquest::ProxyProtocol::V1)
+        .build()
+        .unwrap();
+
+    let res = client
+        .get(format!("http://{}/", server.addr()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn proxy_protocol_header_over_custom_connector() {
+    use rquest::transport::{Connecting, Connector};
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use tokio::io::AsyncWriteExt;
+
+    struct Fixed(SocketAddr);
+
+    impl Connector for Fixed {
+        fn connect(&self, _uri: &http::Uri) -> Connecting {
+            let addr = self.0;
+            Box::pin(async move {
+                let stream = tokio::net::TcpStream::connect(addr).await?;
+                Ok(Box::new(stream) as _)
+            })
+        }
+    }
+
+    let server = server::low_level_with_response(|raw_request, client_socket| {
+        Box::new(async move {
+            let status = if raw_request.starts_with(b"PROXY UNKNOWN\r\nGET / HTTP/1.1\r\n") {
+                "200 OK"
+            } else {
+                "400 Bad Request"
+            };
+            let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status);
+            client_socket
+                .write_all(response.as_bytes())
+                .await
+                .expect("response write_all failed");
+        })
+    });
+
+    let client = Client::builder()
+        .connector(Arc::new(Fixed(server.addr())))
+        .proxy_protocol("*.internal", rquest::ProxyProtocol::V1)
+        .build()
+        .unwrap();
+
+    let res = client.get("http://lb.internal/").send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn preset_redirects_and_decompression() {
+    // `/n` redirects n times, then tells whether encodings were accepted
+    let server = server::http(move |req| async move {
+        let left = req.uri().path()[1..].parse::<u32>().unwrap();
+        if left > 0 {
+            return http::Response::builder()
+                .status(302)
+                .header("location", format!("/{}", left - 1))
+                .body(Default::default())
+                .unwrap();
+        }
+        let accepted = req.headers().contains_key("accept-encoding");
+        http::Response::new(accepted.to_string().into())
+    });
+    let decompresses = cfg!(any(
+        feature = "gzip",
+        feature = "brotli",
+        feature = "deflate",
+        feature = "zstd"
+    ));
+
+    // a limit of n counts the first URL, as `redirect::Policy::limited` does
+    for (preset, limit, accepts) in [
+        (rquest::Preset::Crawler, 10, decompresses),
+        (rquest::Preset::ApiClient, 5, decompresses),
+        (rquest::Preset::Downloader, 10, false),
+    ] {
+        let client = Client::builder().preset(preset).no_proxy().build().unwrap();
+
+        let res = client
+            .get(format!("http://{}/{}", server.addr(), limit - 1))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.url().path(), "/0");
+        assert_eq!(res.text().await.unwrap(), accepts.to_string(), "{preset:?}");
+
+        let err = client
+            .get(format!("http://{}/{}", server.addr(), limit))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(err.is_redirect(), "{preset:?}");
+    }
+}
+
+#[tokio::test]
+async fn doh_resolution() {
+    use http_body_util::BodyExt;
+
+    let server = server::http(move |req| async move {
+        if req.uri() != "/dns-query" {
+            assert_eq!(req.headers()["host"], "doh.test");
+            assert_eq!(req.headers()["x-custom"], "value");
+            return http::Response::new("Hello".into());
+        }
+
+        assert_eq!(req.headers()["content-type"], "application/dns-message");
+        assert!(!req.headers().contains_key("x-custom"));
+        let mut msg = req.into_body().collect().await.unwrap().to_bytes().to_vec();
+        let qtype = &msg[msg.len() - 4..msg.len() - 2];
+        msg[2] = 0x81;
+        msg[3] = 0x80;
+        if qtype == [0, 1] {
+            // A doh.test -> 127.0.0.1
+            msg[7] = 1;
+            msg.extend_from_slice(&[0xc0, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 127, 0, 0, 1]);
+        }
+        http::Response::new(msg.into())
+    });
+
+    let mut headers = http::HeaderMap::new();
+    headers.insert("x-custom", http::HeaderValue::from_static("value"));
+    let client = Client::builder()
+        .no_proxy()
+        .default_headers(headers)
+        .doh(format!("http://{}/dns-query", server.addr()))
+        .build()
+        .unwrap();
+
+    let res = client
+        .get(format!("http://doh.test:{}/", server.addr().port()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "Hello");
+}
+
+#[tokio::test]
+async fn doh_with_default_pool() {
+    use http_body_util::BodyExt;
+
+    let server = server::http(move |req| async move {
+        if req.uri() != "/dns-query" {
+            return http::Response::new("Hello".into());
+        }
+
+        let mut msg = req.into_body().collect().await.unwrap().to_bytes().to_vec();
+        let qtype = &msg[msg.len() - 4..msg.len() - 2];
+        msg[2] = 0x81;
+        msg[3] = 0x80;
+        if qtype == [0, 1] {
+            // A *.test -> 127.0.0.1
+            msg[7] = 1;
+            msg.extend_from_slice(&[0xc0, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 127, 0, 0, 1]);
+        }
+        http::Response::new(msg.into())
+    });
+
+    // the DoH client pools its connections apart, with the unbounded
+    // default pool of the client
+    let client = Client::builder()
+        .doh(format!("http://{}/dns-query", server.addr()))
+        .no_proxy()
+        .build()
+        .unwrap();
+
+    for host in ["a.test", "b.test"] {
+        let res = client
+            .get(format!("http://{host}:{}/", server.addr().port()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.text().await.unwrap(), "Hello");
+    }
+}
+
+/// A self-signed certificate for `localhost`, and its key.
+fn self_signed() -> (
+    boring2::x509::X509,
+    boring2::pkey::PKey<boring2::pkey::Private>,
+) {
+    use boring2::asn1::Asn1Time;
+    use boring2::bn::BigNum;
+    use boring2::ec::{EcGroup, EcKey};
+    use boring2::hash::MessageDigest;
+    use boring2::nid::Nid;
+    use boring2::pkey::PKey;
+    use boring2::x509::extension::SubjectAlternativeName;
+    use boring2::x509::{X509NameBuilder, X509};
+
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "localhost").unwrap();
+    let name = name.build();
+
+    let mut cert = X509::builder().unwrap();
+    cert.set_version(2).unwrap();
+    let serial = BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap();
+    cert.set_serial_number(&serial).unwrap();
+    cert.set_subject_name(&name).unwrap();
+    cert.set_issuer_name(&name).unwrap();
+    cert.set_pubkey(&key).unwrap();
+    cert.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    cert.set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+    let san = SubjectAlternativeName::new()
+        .dns("localhost")
+        .build(&cert.x509v3_context(None, None))
+        .unwrap();
+    cert.append_extension(san).unwrap();
+    cert.sign(&key, MessageDigest::sha256()).unwrap();
+    (cert.build(), key)
+}
+
+/// A DoT server for `localhost` answering `127.0.0.1` to A queries, in the
+/// reverse order of the queries, or never answering at all, and its
+/// certificate.
+async fn dot_server(answer: bool) -> (std::net::SocketAddr, boring2::x509::X509) {
+    use boring2::ssl::{SslAcceptor, SslMethod};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn answer_queries<S>(mut tls: S, answer: bool) -> std::io::Result<()>
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    {
+        let mut queries = Vec::new();
+        for _ in 0..2 {
+            let len = tls.read_u16().await? as usize;
+            let mut msg = vec![0; len];
+            tls.read_exact(&mut msg).await?;
+            queries.push(msg);
+        }
+        assert_eq!(
+            u16::from_be_bytes([queries[1][0], queries[1][1]]),
+            u16::from_be_bytes([queries[0][0], queries[0][1]]).wrapping_add(1)
+        );
+        if !answer {
+            return std::future::pending().await;
+        }
+
+        for mut msg in queries.into_iter().rev() {
+            let qtype = [msg[msg.len() - 4], msg[msg.len() - 3]];
+            msg[2] = 0x81;
+            msg[3] = 0x80;
+            if qtype == [0, 1] {
+                // A localhost -> 127.0.0.1
+                msg[7] = 1;
+                msg.extend_from_slice(&[0xc0, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 127, 0, 0, 1]);
+            }
+            tls.write_all(&(msg.len() as u16).to_be_bytes()).await?;
+            tls.write_all(&msg).await?;
+        }
+        tls.flush().await
+    }
+
+    let (cert, key) = self_signed();
+    let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    acceptor.set_certificate(&cert).unwrap();
+    acceptor.set_private_key(&key).unwrap();
+    let acceptor = acceptor.build();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                if let Ok(tls) = tokio_boring2::accept(&acceptor, socket).await {
+                    let _ = answer_queries(tls, answer).await;
+                }
+            });
+        }
+    });
+    (addr, cert)
+}
+
+/// A DoT upstream trusting the certificate of the server at `addr`.
+fn dot_upstream(addr: std::net::SocketAddr, cert: &boring2::x509::X509) -> rquest::dns::DotConfig {
+    let roots = rquest::RootCertStore::from_der_certs([cert.to_der().unwrap()]).unwrap();
+    rquest::dns::DotConfig::new("localhost", [addr.ip()])
+        .port(addr.port())
+        .root_cert_store(roots)
+}
+
+#[tokio::test]
+async fn dot_resolution() {
+    use boring2::hash::{hash, MessageDigest};
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["host"], "dot.test");
+        http::Response::new("Hello".into())
+    });
+    let (dot, cert) = dot_server(true).await;
+    let spki = cert.public_key().unwrap().public_key_to_der().unwrap();
+    let spki = hash(MessageDigest::sha256(), &spki).unwrap();
+    let pin = <[u8; 32]>::try_from(&spki[..]).unwrap();
+
+    let client = Client::builder()
+        .no_proxy()
+        .dot(dot_upstream(dot, &cert).pin_spki_sha256(pin))
+        .build()
+        .unwrap();
+    let res = client
+        .get(format!("http://dot.test:{}/", server.addr().port()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "Hello");
+
+    // a server whose key matches no pin is refused
+    let client = Client::builder()
+        .no_proxy()
+        .dot(dot_upstream(dot, &cert).pin_spki_sha256([0; 32]))
+        .build()
+        .unwrap();
+    let err = client
+        .get(format!("http://dot.test:{}/", server.addr().port()))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_connect());
+}
+
+#[tokio::test]
+async fn dot_timeout() {
+    let (dot, cert) = dot_server(false).await;
+
+    let client = Client::builder()
+        .no_proxy()
+        .dot(dot_upstream(dot, &cert).timeout(std::time::Duration::from_millis(100)))
+        .build()
+        .unwrap();
+    let start = std::time::Instant::now();
+    let err = client.get("http://dot.test/").send().await.unwrap_err();
+    assert!(err.is_connect());
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn dns_cache() {
+    use http_body_util::BodyExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let queries = Arc::new(AtomicUsize::new(0));
+    let counter = queries.clone();
+    let server = server::http(move |req| {
+        let counter = counter.clone();
+        async move {
+            if req.uri() != "/dns-query" {
+                return http::Response::new("Hello".into());
+            }
+
+            counter.fetch_add(1, Ordering::SeqCst);
+            let mut msg = req.into_body().collect().await.unwrap().to_bytes().to_vec();
+            let qtype = &msg[msg.len() - 4..msg.len() - 2];
+            msg[2] = 0x81;
+            msg[3] = 0x80;
+            if qtype == [0, 1] {
+                // A cache.test -> 127.0.0.1
+                msg[7] = 1;
+                msg.extend_from_slice(&[0xc0, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 127, 0, 0, 1]);
+            }
+            http::Response::new(msg.into())
+        }
+    });
+
+    let client = Client::builder()
+        .no_proxy()
+        .pool_max_idle_per_host(0)
+        .doh(format!("http://{}/dns-query", server.addr()))
+        .dns_cache(rquest::dns::DnsCacheConfig::new())
+        .build()
+        .unwrap();
+
+    let url = format!("http://cache.test:{}/", server.addr().port());
+    for _ in 0..2 {
+        let res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.text().await.unwrap(), "Hello");
+    }
+    // one A and one AAAA query
+    assert_eq!(queries.load(Ordering::SeqCst), 2);
+
+    client.flush_dns_cache();
+    client.get(&url).send().await.unwrap();
+    assert_eq!(queries.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn https_rr_hints_and_port() {
+    use http_body_util::BodyExt;
+
+    let server = server::http(move |req| async move {
+        if req.uri() != "/dns-query" {
+            return http::Response::new("Hello".into());
+        }
+
+        // the DoH endpoint is the server itself
+        let host = req.headers()["host"].to_str().unwrap();
+        let port: u16 = host.rsplit(':').next().unwrap().parse().unwrap();
+        let mut msg = req.into_body().collect().await.unwrap().to_bytes().to_vec();
+        let qtype = &msg[msg.len() - 4..msg.len() - 2];
+        msg[2] = 0x81;
+        msg[3] = 0x80;
+        if qtype == [0, 65] {
+            // HTTPS svc.test: alpn h3, port and ipv4hint of the server
+            let port = port.to_be_bytes();
+            let rdata = [
+                &[0, 1, 0][..],
+                &[0, 1, 0, 3, 2, b'h', b'3'],
+                &[0, 3, 0, 2, port[0], port[1]],
+                &[0, 4, 0, 4, 127, 0, 0, 1],
+            ]
+            .concat();
+            msg[7] = 1;
+            msg.extend_from_slice(&[0xc0, 12, 0, 65, 0, 1, 0, 0, 0, 60, 0, rdata.len() as u8]);
+            msg.extend_from_slice(&rdata);
+        }
+        http::Response::new(msg.into())
+    });
+
+    let mut headers = http::HeaderMap::new();
+    headers.insert("x-custom", http::HeaderValue::from_static("value"));
+    let client = Client::builder()
+        .no_proxy()
+        .default_headers(headers)
+        .doh(format!("http://{}/dns-query", server.addr()))
+        .https_rr(true)
+        .build()
+        .unwrap();
+
+    let res = client.get("http://svc.test/").send().await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "Hello");
+
+    let record = client.https_record("svc.test").unwrap();
+    assert!(record.supports_h3());
+    assert_eq!(record.port(), Some(server.addr().port()));
+}
+
+/// An HTTPS server for `localhost` answering every request with `body` and
+/// `alt_svc`, if any, closing the connection after each response.
+async fn alt_svc_server(
+    cert: &boring2::x509::X509,
+    key: &boring2::pkey::PKey<boring2::pkey::Private>,
+    body: &'static str,
+    alt_svc: Option<String>,
+) -> std::net::SocketAddr {
+    use boring2::ssl::{SslAcceptor, SslMethod};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    acceptor.set_certificate(cert).unwrap();
+    acceptor.set_private_key(key).unwrap();
+    let acceptor = acceptor.build();
+
+    let alt_svc = alt_svc.map_or(String::new(), |value| format!("alt-svc: {value}\r\n"));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            let acceptor = acceptor.clone();
+            let alt_svc = alt_svc.clone();
+            tokio::spawn(async move {
+                let Ok(mut tls) = tokio_boring2::accept(&acceptor, socket).await else {
+                    return;
+                };
+                let mut req = Vec::new();
+                while !req.ends_with(b"\r\n\r\n") {
+                    let Ok(byte) = tls.read_u8().await else {
+                        return;
+                    };
+                    req.push(byte);
+                }
+                let res = format!(
+                    "HTTP/1.1 200 OK\r\n{alt_svc}connection: close\r\ncontent-length: {}\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = tls.write_all(res.as_bytes()).await;
+                let _ = tls.shutdown().await;
+            });
+        }
+    });
+    addr
+}
+
+#[tokio::test]
+async fn alt_svc_connects_to_alternative() {
+    let (cert, key) = self_signed();
+    let alternative = alt_svc_server(&cert, &key, "alternative", None).await;
+    let origin = alt_svc_server(
+        &cert,
+        &key,
+        "origin",
+        Some(format!("http%2F1.1=\":{}\"", alternative.port())),
+    )
+    .await;
+
+    let client = Client::builder()
+        .no_proxy()
+        .root_cert_store(rquest::RootCertStore::from_der_certs([cert.to_der().unwrap()]).unwrap())
+        .alt_svc(true)
+        .build()
+        .unwrap();
+    let url = format!("https://localhost:{}/", origin.port());
+
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "origin");
+
+    // the next connection goes to the alternative, verified for the origin
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.url().as_str(), url);
+    assert_eq!(res.text().await.unwrap(), "alternative");
+}
+
+#[tokio::test]
+async fn alt_svc_falls_back_to_origin() {
+    // nothing listens on the port of the alternative
+    let unused = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = unused.local_addr().unwrap().port();
+    drop(unused);
+
+    let (cert, key) = self_signed();
+    let origin = alt_svc_server(
+        &cert,
+        &key,
+        "origin",
+        Some(format!("http%2F1.1=\":{port}\"")),
+    )
+    .await;
+
+    let client = Client::builder()
+        .no_proxy()
+        .root_cert_store(rquest::RootCertStore::from_der_certs([cert.to_der().unwrap()]).unwrap())
+        .alt_svc(true)
+        .build()
+        .unwrap();
+    let url = format!("https://localhost:{}/", origin.port());
+
+    for _ in 0..2 {
+        let res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.text().await.unwrap(), "origin");
+    }
+}
+
+#[tokio::test]
+async fn download_throttle() {
+    let server =
+        server::http(move |_req| async move { http::Response::new(vec![0u8; 4096].into()) });
+
+    let client = Client::builder()
+        .no_proxy()
+        .download_throttle(rquest::Throttle::new(8192).burst(1))
+        .build()
+        .unwrap();
+
+    let start = std::time::Instant::now();
+    let body = client
+        .get(format!("http://{}/throttled", server.addr()))
+        .send()
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap();
+
+    assert_eq!(body.len(), 4096);
+    assert!(start.elapsed() >= std::time::Duration::from_millis(400));
+}
+
+#[tokio::test]
+async fn upload_throttle() {
+    use http_body_util::BodyExt;
+
+    let server = server::http(move |req| async move {
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        http::Response::new(body.len().to_string().into())
+    });
+
+    let client = Client::builder().no_proxy().build().unwrap();
+
+    let start = std::time::Instant::now();
+    let res = client
+        .post(format!("http://{}/throttled", server.addr()))
+        .upload_throttle(rquest::Throttle::new(8192).burst(1))
+        .body(vec![0u8; 4096])
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.text().await.unwrap(), "4096");
+    assert!(start.elapsed() >= std::time::Duration::from_millis(400));
+}
+
+#[tokio::test]
+async fn progress_callbacks() {
+    use http_body_util::BodyExt;
+    use std::sync::{Arc, Mutex};
+
+    let server = server::http(move |req| async move {
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        http::Response::new(vec![0u8; body.len() * 2].into())
+    });
+
+    let uploaded = Arc::new(Mutex::new(Vec::new()));
+    let downloaded = Arc::new(Mutex::new(Vec::new()));
+
+    let client = Client::builder().no_proxy().build().unwrap();
+    let res = client
+        .post(format!("http://{}/progress", server.addr()))
+        .body(vec![0u8; 1024])
+        .on_upload_progress({
+            let uploaded = uploaded.clone();
+            move |sent, total| uploaded.lock().unwrap().push((sent, total))
+        })
+        .on_download_progress({
+            let downloaded = downloaded.clone();
+            move |received, total| downloaded.lock().unwrap().push((received, total))
+        })
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.bytes().await.unwrap().len(), 2048);
+    assert_eq!(uploaded.lock().unwrap().last(), Some(&(1024, Some(1024))));
+    assert_eq!(downloaded.lock().unwrap().last(), Some(&(2048, Some(2048))));
+}
+
+#[tokio::test]
+async fn max_response_size() {
+    let server =
+        server::http(move |_req| async move { http::Response::new(vec![0u8; 2048].into()) });
+
+    let client = Client::builder()
+        .no_proxy()
+        .max_response_size(1024)
+        .build()
+        .unwrap();
+
+    let err = client
+        .get(format!("http://{}/large", server.addr()))
+        .send()
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap_err();
+
+    assert!(err.is_body());
+    assert!(err.is_body_too_large());
+}
+
+#[tokio::test]
+async fn download_resumes_with_range() {
+    use futures_util::TryStreamExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/file", listener.local_addr().unwrap());
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    tokio::spawn(async move {
+        let responses: [&[u8]; 2] = [
+            // cut off after half the body
+            b"HTTP/1.1 200 OK\r\ncontent-length: 10\r\netag: \"v1\"\r\n\r\nhello",
+            b"HTTP/1.1 206 Partial Content\r\ncontent-length: 5\r\n\
+              content-range: bytes 5-9/10\r\n\r\nworld",
+        ];
+        for response in responses {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut req = Vec::new();
+            let mut buf = [0; 1024];
+            while !req.ends_with(b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                req.extend_from_slice(&buf[..n]);
+            }
+            tx.send(String::from_utf8(req).unwrap().to_lowercase())
+                .unwrap();
+            socket.write_all(response).await.unwrap();
+        }
+    });
+
+    let client = Client::builder().no_proxy().build().unwrap();
+    let chunks: Vec<_> = client
+        .get(&url)
+        .download()
+        .retry_delay(std::time::Duration::ZERO)
+        .stream()
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(chunks.concat(), b"helloworld");
+
+    let first = rx.try_recv().unwrap();
+    assert!(first.contains("\r\naccept-encoding: identity\r\n"));
+    assert!(!first.contains("\r\nrange:"));
+    let second = rx.try_recv().unwrap();
+    assert!(second.contains("\r\nrange: bytes=5-\r\n"));
+    assert!(second.contains("\r\nif-range: \"v1\"\r\n"));
+}
+
+#[tokio::test]
+async fn download_segments() {
+    use futures_util::TryStreamExt;
+
+    let content: Vec<u8> = (0..100u8).collect();
+    let server = server::http(move |req| {
+        let content = content.clone();
+        async move {
+            let range = match req.headers().get("range") {
+                Some(range) => range.to_str().unwrap().to_owned(),
+                None => return http::Response::new(content.into()),
+            };
+            let (start, end) = range
+                .strip_prefix("bytes=")
+                .unwrap()
+                .split_once('-')
+                .unwrap();
+            let (start, end): (usize, usize) = (start.parse().unwrap(), end.parse().unwrap());
+            http::Response::builder()
+                .status(http::StatusCode::PARTIAL_CONTENT)
+                .header("content-range", format!("bytes {start}-{end}/100"))
+                .header("etag", "\"v1\"")
+                .body(content[start..=end].to_vec().into())
+                .unwrap()
+        }
+    });
+
+    let client = Client::builder().no_proxy().build().unwrap();
+    let chunks: Vec<_> = client
+        .get(format!("http://{}/file", server.addr()))
+        .download()
+        .segments(4)
+        .stream()
+        .try_collect()
+        .await
+        .unwrap();
+
+    // the first byte to learn the size, then the four ranges
+    assert_eq!(chunks.len(), 4);
+    assert_eq!(chunks.concat(), (0..100u8).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn download_segments_without_ranges() {
+    use futures_util::TryStreamExt;
+
+    let server = server::http(move |_req| async move { http::Response::new("whole body".into()) });
+
+    let client = Client::builder().no_proxy().build().unwrap();
+    let chunks: Vec<_> = client
+        .get(format!("http://{}/file", server.addr()))
+        .download()
+        .segments(4)
+        .stream()
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(chunks.concat(), b"whole body");
+}
+
+#[tokio::test]
+async fn download_segments_of_unknown_size() {
+    use futures_util::TryStreamExt;
+
+    // ranges are answered, but without the size of the resource
+    let server = server::http(move |req| async move {
+        match req.headers().get("range") {
+            Some(range) => {
+                assert_eq!(range, "bytes=0-0");
+                http::Response::builder()
+                    .status(http::StatusCode::PARTIAL_CONTENT)
+                    .header("content-range", "bytes 0-0/*")
+                    .body("w".into())
+                    .unwrap()
+            }
+            None => http::Response::new("whole body".into()),
+        }
+    });
+
+    let client = Client::builder().no_proxy().build().unwrap();
+    let chunks: Vec<_> = client
+        .get(format!("http://{}/file", server.addr()))
+        .download()
+        .segments(4)
+        .stream()
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(chunks.concat(), b"whole body");
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn save_to_file() {
+    let server = server::http(move |_req| async move { http::Response::new("test".into()) });
+
+    let dir = std::env::temp_dir().join(format!("rquest-save-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("saved.txt");
+    let url = format!("http://{}/file", server.addr());
+
+    let client = Client::builder().no_proxy().build().unwrap();
+
+    // sha256("test")
+    let len = client
+        .get(&url)
+        .send()
+        .await
+        .unwrap()
+        .save_to(&path)
+        .sha256("9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08")
+        .await
+        .unwrap();
+    assert_eq!(len, 4);
+    assert_eq!(std::fs::read(&path).unwrap(), b"test");
+
+    let bad = dir.join("bad.txt");
+    let err = client
+        .get(&url)
+        .send()
+        .await
+        .unwrap()
+        .save_to(&bad)
+        .expected_len(5)
+        .await
+        .unwrap_err();
+    assert!(err.is_body());
+    assert!(!bad.exists());
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn event_source_reconnects_with_last_event_id() {
+    use futures_util::TryStreamExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let server = server::http({
+        let calls = calls.clone();
+        move |req| {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                assert_eq!(req.headers()["accept"], "text/event-stream");
+                let body = match call {
+                    0 => {
+                        assert!(req.headers().get("last-event-id").is_none());
+                        ": hello\r\nid: 1\r\nretry: 0\r\nevent: update\r\ndata: a\r\ndata: b\r\n\r\n"
+                    }
+                    1 => {
+                        assert_eq!(req.headers()["last-event-id"], "1");
+                        "data: c\n\ndata: incomplete\n"
+                    }
+                    _ => {
+                        return http::Response::builder()
+                            .status(204)
+                            .body(Default::default())
+                            .unwrap()
+                    }
+                };
+                http::Response::builder()
+                    .header(CONTENT_TYPE, "text/event-stream")
+                    .body(body.into())
+                    .unwrap()
+            }
+        }
+    });
+
+    let events: Vec<_> = Client::new()
+        .get(format!("http://{}/events", server.addr()))
+        .event_source()
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].event(), "update");
+    assert_eq!(events[0].data(), "a\nb");
+    assert_eq!(events[0].retry(), Some(std::time::Duration::ZERO));
+    assert_eq!(events[1].event(), "message");
+    assert_eq!(events[1].data(), "c");
+    assert_eq!(events[1].id(), Some("1"));
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn query_and_form_styles() {
+    use http_body_util::BodyExt;
+    use rquest::{ArrayStyle, NestedStyle, QueryStyle};
+
+    #[derive(serde::Serialize)]
+    struct Search {
+        ids: Vec<u32>,
+        page: Page,
+    }
+
+    #[derive(serde::Serialize)]
+    struct Page {
+        size: u32,
+    }
+
+    let server = server::http(move |req| async move {
+        assert_eq!(
+            req.uri().query(),
+            Some("ids%5B%5D=1&ids%5B%5D=2&page%5Bsize%5D=5")
+        );
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "ids=1%2C2&page.size=5");
+        http::Response::default()
+    });
+
+    let search = Search {
+        ids: vec![1, 2],
+        page: Page { size: 5 },
+    };
+    let client = Client::builder()
+        .query_style(QueryStyle::new().arrays(ArrayStyle::Brackets))
+        .build()
+        .unwrap();
+    let res = client
+        .post(format!("http://{}/search", server.addr()))
+        .query(&search)
+        .form_with(
+            &search,
+            QueryStyle::new()
+                .arrays(ArrayStyle::Comma)
+                .nested(NestedStyle::Dots),
+        )
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn base_url_and_default_query() {
+    let server = server::http(move |req| async move {
+        match req.uri().path() {
+            "/v1/users" => assert_eq!(req.uri().query(), Some("key=secret&page=2")),
+            "/health" => assert_eq!(req.uri().query(), Some("key=secret")),
+            path => panic!("unexpected path {path}"),
+        }
+        http::Response::default()
+    });
+
+    let client = Client::builder()
+        .base_url(format!("http://{}/v1", server.addr()))
+        .default_query(&[("key", "secret")])
+        .build()
+        .unwrap();
+
+    let res = client
+        .get("users")
+        .query(&[("page", "2")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.url().path(), "/v1/users");
+
+    let res = client.get("/health").send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+
+    // other origins do not get the default parameters
+    let req = client.get("https://example.com/path?a=1").build().unwrap();
+    assert_eq!(req.url().query(), Some("a=1"));
+}
+
+#[tokio::test]
+async fn default_auth_refreshes_refused_token() {
+    use http_body_util::BodyExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let server = server::http(move |req| async move {
+        let status = match req.headers()["authorization"].to_str().unwrap() {
+            "Bearer token-1" => http::StatusCode::UNAUTHORIZED,
+            "Bearer token-2" => http::StatusCode::OK,
+            "Basic explicit" => http::StatusCode::NO_CONTENT,
+            value => panic!("unexpected authorization {value}"),
+        };
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        let mut res = http::Response::new(body.into());
+        *res.status_mut() = status;
+        res
+    });
+
+    let fetched = Arc::new(AtomicUsize::new(0));
+    let counter = fetched.clone();
+    let provider = rquest::AuthProvider::new(move || {
+        let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+        async move { rquest::AuthToken::bearer(&format!("token-{n}")) }
+    });
+    let client = Client::builder().default_auth(provider).build().unwrap();
+
+    let url = format!("http://{}/", server.addr());
+    let res = client.post(&url).body("payload").send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+    assert_eq!(res.text().await.unwrap(), "payload");
+    assert_eq!(fetched.load(Ordering::SeqCst), 2);
+
+    // the new token is kept for the next requests
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+    assert_eq!(fetched.load(Ordering::SeqCst), 2);
+
+    // an explicit header wins
+    let res = client
+        .get(&url)
+        .header("authorization", "Basic explicit")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::NO_CONTENT);
+
+    // other origins than the first request's get no token
+    let other = server::http(move |req| async move {
+        assert!(!req.headers().contains_key("authorization"));
+        http::Response::default()
+    });
+    let res = client
+        .get(format!("http://{}/", other.addr()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+    assert_eq!(fetched.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn request_template() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["x-template"], "yes");
+        http::Response::new(req.uri().to_string().into())
+    });
+
+    let client = Client::new();
+    let template = client
+        .get(format!("http://{}/items/{{id}}?tag={{tag}}", server.addr()))
+        .header("x-template", "yes")
+        .template()
+        .unwrap();
+    assert_eq!(template.params().collect::<Vec<_>>(), ["id", "tag"]);
+
+    for (id, tag) in [("1", "a b"), ("2/3", "c&d")] {
+        let res = template
+            .request(&[("id", id), ("tag", tag)])
+            .send()
+            .await
+            .unwrap();
+        let expected = format!(
+            "/items/{}?tag={}",
+            id.replace('/', "%2F"),
+            tag.replace(' ', "%20").replace('&', "%26")
+        );
+        assert_eq!(res.text().await.unwrap(), expected);
+    }
+
+    let err = template.request(&[("id", "1")]).send().await.unwrap_err();
+    assert!(err.is_builder());
+}
+
+#[tokio::test]
+async fn client_with_changed_settings() {
+    use std::time::Duration;
+
+    let server = server::http(move |_req| async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        http::Response::default()
+    });
+
+    let client = Client::new();
+    let impatient = client
+        .with(|c| {
+            c.timeout(Duration::from_millis(50))
+                .base_url(format!("http://{}/api", server.addr()))
+        })
+        .unwrap();
+
+    let err = impatient.get("items").send().await.unwrap_err();
+    assert!(err.is_timeout());
+    assert_eq!(err.url().unwrap().path(), "/api/items");
+
+    // the original client is left as it was
+    let url = format!("http://{}/api/items", server.addr());
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[test]
+fn request_to_curl() {
+    let client = Client::builder()
+        .default_headers({
+            let mut headers = http::HeaderMap::new();
+            headers.insert("x-default", "1".parse().unwrap());
+            headers
+        })
+        .proxy(rquest::Proxy::http("http://proxy.test:3128").unwrap())
+        .no_gzip()
+        .no_brotli()
+        .no_deflate()
+        .no_zstd()
+        .build()
+        .unwrap();
+
+    let curl = client
+        .put("http://example.test/it's")
+        .header("x-request", "2")
+        .body("hello")
+        .to_curl()
+        .unwrap();
+    assert_eq!(
+        curl,
+        "curl \\\n  -X 'PUT' \\\n  'http://example.test/it'\\''s' \\\n  \
+         -H 'x-request: 2' \\\n  -H 'x-default: 1' \\\n  \
+         --proxy 'http://proxy.test:3128' \\\n  --data-binary 'hello'"
+    );
+
+    let req = client.get("http://example.test/").build().unwrap();
+    assert_eq!(req.as_curl(), "curl \\\n  'http://example.test/'");
+}
+
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn har_recorder() {
+    let server = server::http(move |req| async move {
+        if req.uri().path() == "/start" {
+            http::Response::builder()
+                .status(302)
+                .header("location", "/end?x=1")
+                .body(rquest::Body::default())
+                .unwrap()
+        } else {
+            http::Response::builder()
+                .header("content-type", "text/plain")
+                .body("recorded".into())
+                .unwrap()
+        }
+    });
+
+    let har = rquest::HarRecorder::new();
+    let client = Client::builder().har_recorder(har.clone()).build().unwrap();
+    let res = client
+        .get(format!("http://{}/start", server.addr()))
+        .header("x-test", "1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "recorded");
+    assert_eq!(har.len(), 2);
+
+    let archive: serde_json::Value = serde_json::from_str(&har.to_json()).unwrap();
+    assert_eq!(archive["log"]["version"], "1.2");
+    let entries = archive["log"]["entries"].as_array().unwrap();
+    assert_eq!(entries[0]["response"]["status"], 302);
+    assert_eq!(entries[0]["response"]["redirectURL"], "/end?x=1");
+    assert_eq!(entries[1]["request"]["method"], "GET");
+    assert_eq!(entries[1]["request"]["queryString"][0]["name"], "x");
+    assert_eq!(entries[1]["response"]["content"]["text"], "recorded");
+    assert_eq!(entries[1]["response"]["content"]["mimeType"], "text/plain");
+    assert!(entries[1]["request"]["headers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|header| header["name"] == "x-test"));
+    assert_eq!(entries[1]["timings"]["send"], 0.0);
+}
+
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn cassette_records_then_replays() {
+    use rquest::{Cassette, CassetteMode};
+
+    let server = server::http(move |req| async move {
+        http::Response::builder()
+            .header("x-path", req.uri().path())
+            .body("live".into())
+            .unwrap()
+    });
+    let url = format!("http://{}/page", server.addr());
+    let path = std::env::temp_dir().join(format!("rquest-cassette-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let client = Client::builder()
+        .cassette(Cassette::open(&path).unwrap())
+        .build()
+        .unwrap();
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.headers()["x-path"], "/page");
+    assert_eq!(res.text().await.unwrap(), "live");
+    drop(server);
+
+    let cassette = Cassette::with_mode(&path, CassetteMode::Replay).unwrap();
+    assert_eq!(cassette.len(), 1);
+    let client = Client::builder().cassette(cassette).build().unwrap();
+    for _ in 0..2 {
+        let res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.url().as_str(), url);
+        assert_eq!(res.text().await.unwrap(), "live");
+    }
+
+    let err = client.post(&url).body("other").send().await.unwrap_err();
+    assert!(err.is_request());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn cassette_keeps_credentials_out() {
+    use rquest::Cassette;
+
+    let server = server::http(move |_req| async move { http::Response::new("live".into()) });
+    let url = format!("http://{}/private", server.addr());
+    let path = std::env::temp_dir().join(format!(
+        "rquest-cassette-credentials-{}.json",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+
+    // configuring a cassette already shared is fine
+    let cassette = Cassette::open(&path).unwrap();
+    let shared = cassette.clone();
+    let cassette = cassette.match_headers(["x-tenant"]).match_body(false);
+    let client = Client::builder().cassette(cassette).build().unwrap();
+    let res = client
+        .get(&url)
+        .header("authorization", "Bearer s3cr3t-token")
+        .header("cookie", "session=s3cr3t-cookie")
+        .header("proxy-authorization", "Basic s3cr3t-proxy")
+        .header("x-tenant", "acme")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "live");
+
+    let recorded = std::fs::read_to_string(&path).unwrap();
+    assert!(!recorded.contains("s3cr3t"), "{}", recorded);
+    assert!(recorded.contains("x-tenant"));
+    assert!(recorded.contains("acme"));
+    assert_eq!(shared.len(), 1);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn mock_transport() {
+    let mock = rquest::MockTransport::new().route(http::Method::GET, "/items", |req| {
+        let page = req.url().query().unwrap_or_default().to_owned();
+        http::Response::builder().status(203).body(page).unwrap()
+    });
+    let client = Client::builder().mock(mock.clone()).build().unwrap();
+
+    let res = client
+        .get("http://mock.test/items?page=2")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 203);
+    assert_eq!(res.url().as_str(), "http://mock.test/items?page=2");
+    assert_eq!(res.text().await.unwrap(), "page=2");
+
+    let err = client
+        .get("http://mock.test/other")
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_request());
+    assert_eq!(mock.calls(), 1);
+}
+
+#[cfg(feature = "charset")]
+#[tokio::test]
+async fn text_sniffs_meta_charset() {
+    use futures_util::TryStreamExt;
+
+    let server = server::http(move |_req| async move {
+        // "café" in windows-1252, declared only by the document
+        let mut page = b"<html><head><meta charset=\"windows-1252\"></head><body>caf".to_vec();
+        page.push(0xe9);
+        page.extend_from_slice(b"</body></html>");
+        http::Response::builder()
+            .header("content-type", "text/html")
+            .body(page.into())
+            .unwrap()
+    });
+    let url = format!("http://{}/", server.addr());
+
+    let text = rquest::get(&url).await.unwrap().text().await.unwrap();
+    assert!(text.contains("<body>café</body>"));
+
+    let chunks: Vec<String> = rquest::get(&url)
+        .await
+        .unwrap()
+        .text_lossy_stream()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(chunks.concat(), text);
+}
+
+#[tokio::test]
+async fn digest_auth_answers_challenge() {
+    let server = server::http(move |req| async move {
+        match req.headers().get("authorization") {
+            None => http::Response::builder()
+                .status(401)
+                .header(
+                    "www-authenticate",
+                    r#"Digest realm="device", qop="auth", nonce="abc", opaque="xyz""#,
+                )
+                .body(rquest::Body::default())
+                .unwrap(),
+            Some(auth) => {
+                let auth = auth.to_str().unwrap();
+                assert!(auth.starts_with(r#"Digest username="admin", realm="device""#));
+                assert!(auth.contains(r#"uri="/status?x=1""#));
+                assert!(auth.contains(r#"nonce="abc""#));
+                assert!(auth.contains("qop=auth"));
+                assert!(auth.contains(r#"opaque="xyz""#));
+                http::Response::new("ok".into())
+            }
+        }
+    });
+
+    let res = Client::new()
+        .get(format!("http://{}/status?x=1", server.addr()))
+        .digest_auth("admin", "secret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+    assert_eq!(res.text().await.unwrap(), "ok");
+}
+
+#[cfg(feature = "aws-sigv4")]
+#[tokio::test]
+async fn sign_aws_v4_adds_signature_last() {
+    let server = server::http(move |req| async move {
+        let names = req
+            .headers()
+            .keys()
+            .map(|name| name.as_str().to_owned())
+            .collect::<Vec<_>>();
+        let position = |name: &str| names.iter().position(|n| n == name);
+        assert!(position("x-custom") < position("authorization"));
+        assert_eq!(
+            req.headers()["x-amz-content-sha256"],
+            // sha256 of "hello"
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        let auth = req.headers()["authorization"].to_str().unwrap();
+        assert!(auth.starts_with("AWS4-HMAC-SHA256 Credential=AKID/"));
+        assert!(auth.contains("/us-east-1/s3/aws4_request, "));
+        assert!(auth.contains("SignedHeaders=host;x-amz-content-sha256;x-amz-date, "));
+        http::Response::default()
+    });
+
+    let res = Client::new()
+        .put(format!("http://{}/bucket/key", server.addr()))
+        .header("x-custom", "1")
+        .body("hello")
+        .sign_aws_v4(
+            rquest::AwsCredentials::new("AKID", "secret"),
+            "us-east-1",
+            "s3",
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[cfg(feature = "aws-sigv4")]
+#[tokio::test]
+async fn sign_aws_v4_on_redirects() {
+    let other = server::http(move |req| async move {
+        assert_eq!(req.headers().get("authorization"), None);
+        assert_eq!(req.headers().get("x-amz-date"), None);
+        assert_eq!(req.headers().get("x-amz-content-sha256"), None);
+        http::Response::default()
+    });
+    let other = format!("http://{}/elsewhere", other.addr());
+
+    let server = server::http(move |req| {
+        let other = other.clone();
+        async move {
+            // signed for each path, once
+            assert_eq!(req.headers().get_all("authorization").iter().count(), 1);
+            match req.uri().path() {
+                "/bucket/old" => http::Response::builder()
+                    .status(307)
+                    .header("location", "/bucket/new")
+                    .body(Default::default())
+                    .unwrap(),
+                "/bucket/new" => http::Response::builder()
+                    .status(307)
+                    .header("location", other)
+                    .body(Default::default())
+                    .unwrap(),
+                path => panic!("unexpected path {path}"),
+            }
+        }
+    });
+
+    let res = Client::new()
+        .put(format!("http://{}/bucket/old", server.addr()))
+        .body("hello")
+        .sign_aws_v4(
+            rquest::AwsCredentials::new("AKID", "secret"),
+            "us-east-1",
+            "s3",
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+    assert!(res.url().as_str().ends_with("/elsewhere"));
+}
+
+#[tokio::test]
+async fn signer_sees_final_request() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["x-signature"], "POST /orders?id=1 5 gzip");
+        http::Response::default()
+    });
+
+    let client = Client::builder()
+        .signer(|req: &mut rquest::SigningRequest<'_>| {
+            let signature = format!(
+                "{} {} {} {}",
+                req.method(),
+                req.uri().path_and_query().unwrap(),
+                req.body().unwrap().len(),
+                req.headers()["accept-encoding"].to_str().unwrap(),
+            );
+            req.headers_mut()
+                .insert("x-signature", signature.parse().unwrap());
+            Ok(())
+        })
+        .build()
+        .unwrap();
+
+    let res = client
+        .post(format!("http://{}/orders?id=1", server.addr()))
+        .header("accept-encoding", "gzip")
+        .body("hello")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn signer_signs_redirects_again() {
+    let server = server::http(move |req| async move {
+        let signatures = req
+            .headers()
+            .get_all("x-signature")
+            .iter()
+            .map(|value| value.to_str().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        match req.uri().path() {
+            "/old" => {
+                assert_eq!(signatures, ["POST /old 5"]);
+                http::Response::builder()
+                    .status(307)
+                    .header("location", "/new")
+                    .body(Default::default())
+                    .unwrap()
+            }
+            "/new" => {
+                assert_eq!(signatures, ["POST /new 5"]);
+                http::Response::default()
+            }
+            path => panic!("unexpected path {path}"),
+        }
+    });
+
+    let res = Client::new()
+        .post(format!("http://{}/old", server.addr()))
+        .body("hello")
+        .signer(|req: &mut rquest::SigningRequest<'_>| {
+            let signature = format!(
+                "{} {} {}",
+                req.method(),
+                req.uri().path(),
+                req.body().unwrap().len(),
+            );
+            req.headers_mut()
+                .append("x-signature", signature.parse().unwrap());
+            Ok(())
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn oauth2_refresh_token_rotates() {
+    use http_body_util::BodyExt;
+
+    let server = server::http(move |req| async move {
+        if req.uri().path() == "/token" {
+            let body = req.into_body().collect().await.unwrap().to_bytes();
+            let token = match &body[..] {
+                b"grant_type=refresh_token&refresh_token=r1&client_id=app" => {
+                    r#"{"access_token":"a1","token_type":"Bearer","refresh_token":"r2"}"#
+                }
+                b"grant_type=refresh_token&refresh_token=r2&client_id=app" => {
+                    r#"{"access_token":"a2","token_type":"Bearer","expires_in":3600}"#
+                }
+                body => panic!("unexpected token request {body:?}"),
+            };
+            return http::Response::new(token.into());
+        }
+        let status = match req.headers()["authorization"].to_str().unwrap() {
+            "Bearer a1" => http::StatusCode::UNAUTHORIZED,
+            "Bearer a2" => http::StatusCode::OK,
+            value => panic!("unexpected authorization {value}"),
+        };
+        let mut res = http::Response::default();
+        *res.status_mut() = status;
+        res
+    });
+
+    let oauth =
+        rquest::OAuth2::refresh_token(format!("http://{}/token", server.addr()), "app", "r1");
+    let client = Client::builder()
+        .host_auth(format!("http://{}", server.addr()), oauth.into())
+        .build()
+        .unwrap();
+
+    let res = client
+        .get(format!("http://{}/api", server.addr()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+
+    // the same host on another port is another origin
+    let other = server::http(move |req| async move {
+        assert_eq!(req.headers().get("authorization"), None);
+        http::Response::default()
+    });
+    let res = client
+        .get(format!("http://{}/api", other.addr()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[cfg(feature = "ntlm")]
+#[tokio::test]
+async fn ntlm_auth_answers_challenge() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.version(), http::Version::HTTP_11);
+        let auth = req.headers()["authorization"].to_str().unwrap().to_owned();
+        if auth.starts_with("NTLM TlRMTVNTUAABAAAA") {
+            return http::Response::builder()
+                .status(401)
+                .header(
+                    "www-authenticate",
+                    "NTLM TlRMTVNTUAACAAAAAAAAADAAAAAHgggAASNFZ4mrze8AAAAAAAAAAAAAAAAwAAAA",
+                )
+                .body(rquest::Body::default())
+                .unwrap();
+        }
+        assert!(auth.starts_with("NTLM TlRMTVNTUAADAAAA"), "{auth}");
+        http::Response::new("ok".into())
+    });
+
+    let res = Client::new()
+        .get(format!("http://{}/", server.addr()))
+        .ntlm_auth("CORP\\alice", "secret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+    assert_eq!(res.text().await.unwrap(), "ok");
+}
+
+#[cfg(feature = "negotiate")]
+#[tokio::test]
+async fn negotiate_auth_waits_for_a_challenge() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers().get("authorization"), None);
+        http::Response::new("ok".into())
+    });
+
+    let res = Client::new()
+        .get(format!("http://{}/", server.addr()))
+        .negotiate_auth()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+    assert_eq!(res.text().await.unwrap(), "ok");
+}
+
+#[tokio::test]
+async fn conditional_revalidates_with_etag() {
+    let server = server::http(move |req| async move {
+        match req.headers().get("if-none-match") {
+            Some(etag) => {
+                assert_eq!(etag, "\"v1\"");
+                http::Response::builder()
+                    .status(304)
+                    .body(rquest::Body::default())
+                    .unwrap()
+            }
+            None => http::Response::builder()
+                .header("etag", "\"v1\"")
+                .body("hello".into())
+                .unwrap(),
+        }
+    });
+
+    let client = Client::new();
+    let url = format!("http://{}/feed", server.addr());
+
+    let first = client.get(&url).conditional().send().await.unwrap();
+    assert!(first.is_modified());
+    assert_eq!(first.into_response().text().await.unwrap(), "hello");
+
+    let second = client.get(&url).conditional().send().await.unwrap();
+    assert!(matches!(second, rquest::Revalidation::NotModified(_)));
+}
+
+#[tokio::test]
+async fn http_cache_answers_fresh_and_revalidates_stale() {
+    use rquest::cache::{CacheStatus, HttpCache};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let server = server::http({
+        let calls = calls.clone();
+        move |req| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if req.uri().path() == "/fresh" {
+                    return http::Response::builder()
+                        .header("cache-control", "max-age=60")
+                        .body("fresh".into())
+                        .unwrap();
+                }
+                if req.headers().get("if-none-match").is_some() {
+                    assert_eq!(req.headers()["if-none-match"], "\"v1\"");
+                    return http::Response::builder()
+                        .status(304)
+                        .body(Default::default())
+                        .unwrap();
+                }
+                http::Response::builder()
+                    .header("cache-control", "no-cache")
+                    .header("etag", "\"v1\"")
+                    .body("stale".into())
+                    .unwrap()
+            }
+        }
+    });
+
+    let client = Client::builder()
+        .http_cache(HttpCache::new())
+        .build()
+        .unwrap();
+
+    let url = format!("http://{}/fresh", server.addr());
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.cache_status(), Some(CacheStatus::Miss));
+    assert_eq!(res.text().await.unwrap(), "fresh");
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.cache_status(), Some(CacheStatus::Hit));
+    assert_eq!(res.text().await.unwrap(), "fresh");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let url = format!("http://{}/stale", server.addr());
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.cache_status(), Some(CacheStatus::Miss));
+    assert_eq!(res.text().await.unwrap(), "stale");
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.cache_status(), Some(CacheStatus::Revalidated));
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+    assert_eq!(res.text().await.unwrap(), "stale");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn http_cache_disk_store_survives_clients() {
+    use rquest::cache::{CacheStatus, DiskStore, HttpCache};
+
+    let server = server::http(move |_req| async move {
+        http::Response::builder()
+            .header("cache-control", "max-age=60")
+            .body("cached".into())
+            .unwrap()
+    });
+
+    let dir = std::env::temp_dir().join(format!("rquest-cache-{}", server.addr().port()));
+    let url = format!("http://{}/", server.addr());
+    for status in [CacheStatus::Miss, CacheStatus::Hit] {
+        let store = DiskStore::open(&dir).unwrap();
+        let client = Client::builder()
+            .http_cache(HttpCache::with_store(store))
+            .build()
+            .unwrap();
+        let res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.cache_status(), Some(status));
+        assert_eq!(res.text().await.unwrap(), "cached");
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn opaque_header_values_are_echoed_exactly() {
+    use rquest::header::HeaderValue;
+    use rquest::HeaderValueExt;
+
+    let server = server::http(move |req| async move {
+        if let Some(token) = req.headers().get("x-token") {
+            assert_eq!(token.as_bytes(), b"caf\xe9 \xff");
+        }
+        http::Response::builder()
+            .header("x-token", HeaderValue::from_bytes(b"caf\xe9 \xff").unwrap())
+            .body(Default::default())
+            .unwrap()
+    });
+
+    let client = Client::new();
+    let url = format!("http://{}/", server.addr());
+    let res = client.get(&url).send().await.unwrap();
+    let token = res.headers()["x-token"].clone();
+    assert!(token.to_str().is_err());
+    assert_eq!(token.to_latin1(), "café \u{ff}");
+
+    let res = client
+        .get(&url)
+        .header("x-token", token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn lenient_http1_accepts_sloppy_responses() {
+    use tokio::io::AsyncWriteExt;
+
+    let server = server::low_level_with_response(|_raw_request, client_socket| {
+        Box::new(async move {
+            let response = "HTTP/1.1 200\nX-Folded: a\n b\nnot a header\nContent-Length: 2\n\nok";
+            client_socket
+                .write_all(response.as_bytes())
+                .await
+                .expect("response write_all failed");
+        })
+    });
+    let url = format!("http://{}/", server.addr());
+
+    assert!(Client::new().get(&url).send().await.is_err());
+
+    let client = Client::builder().lenient_http1(true).build().unwrap();
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+    let folded = res.headers()["x-folded"].to_str().unwrap();
+    assert!(folded.starts_with('a') && folded.ends_with('b'), "{folded}");
+    assert_eq!(res.text().await.unwrap(), "ok");
+}
+
+#[tokio::test]
+async fn raw_method_and_absolute_request_target() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.method().as_str(), "PURGE");
+        let target = req.uri().to_string();
+        http::Response::new(target.into())
+    });
+    let url = format!("http://{}/assets/app.js?v=2", server.addr());
+
+    let client = Client::new();
+    let res = client.request_raw("PURGE", &url).send().await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "/assets/app.js?v=2");
+
+    let res = client
+        .request_raw("PURGE", &url)
+        .request_target(rquest::RequestTarget::Absolute)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), url);
+
+    assert!(client.request_raw("BAD METHOD", &url).build().is_err());
+}
+
+#[tokio::test]
+async fn connect_to_keeps_host_of_the_url() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["host"], "origin.example.test:8080");
+        http::Response::default()
+    });
+
+    let client = Client::builder()
+        .connect_to("origin.example.test", 8080, server.addr())
+        .build()
+        .unwrap();
+    let res = client
+        .get("http://origin.example.test:8080/")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+
+    // another port of the host is not mapped
+    assert!(client
+        .get("http://origin.example.test:8081/")
+        .send()
+        .await
+        .is_err());
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn response_into_async_read() {
+    use tokio::io::AsyncReadExt;
+
+    let server = server::http(move |_req| async move {
+        let chunks: Vec<Result<_, std::convert::Infallible>> =
+            vec![Ok("first "), Ok("second "), Ok("third")];
+        let stream = futures_util::stream::iter(chunks);
+        http::Response::new(rquest::Body::wrap_stream(stream))
+    });
+    let url = format!("http://{}/", server.addr());
+
+    let mut reader = Client::new()
+        .get(&url)
+        .send()
+        .await
+        .unwrap()
+        .into_async_read();
+    let mut body = String::new();
+    reader.read_to_string(&mut body).await.unwrap();
+    assert_eq!(body, "first second third");
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn file_body_is_reopened_on_redirect() {
+    use http_body_util::BodyExt;
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["content-length"], "11");
+        let path = req.uri().path().to_owned();
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "hello world");
+        if path == "/upload" {
+            http::Response::builder()
+                .status(307)
+                .header("location", "/moved")
+                .body(Default::default())
+                .unwrap()
+        } else {
+            http::Response::new(path.into())
+        }
+    });
+
+    let path = std::env::temp_dir().join(format!("rquest-body-{}.txt", std::process::id()));
+    std::fs::write(&path, "hello world").unwrap();
+
+    let client = Client::new();
+    let res = client
+        .post(format!("http://{}/upload", server.addr()))
+        .body(rquest::Body::from_file(&path).unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "/moved");
+
+    let reader = tokio::fs::File::open(&path).await.unwrap();
+    let res = client
+        .post(format!("http://{}/reader", server.addr()))
+        .body(rquest::Body::from_async_read(reader, Some(11)))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "/reader");
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(rquest::Body::from_file(&path).is_err());
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn channel_body_streams_sent_chunks() {
+    use http_body_util::BodyExt;
+
+    let server = server::http(move |req| async move {
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        http::Response::new(body.into())
+    });
+    let url = format!("http://{}/", server.addr());
+    let client = Client::new();
+
+    let (mut sender, body) = rquest::Body::channel();
+    let response = tokio::spawn(client.post(&url).body(body).send());
+    for line in ["{\"n\":0}\n", "{\"n\":1}\n"] {
+        sender.send_data(line).await.unwrap();
+    }
+    drop(sender);
+    let res = response.await.unwrap().unwrap();
+    assert_eq!(res.text().await.unwrap(), "{\"n\":0}\n{\"n\":1}\n");
+
+    let (mut sender, body) = rquest::Body::channel();
+    let response = tokio::spawn(client.post(&url).body(body).send());
+    sender.send_data("partial").await.unwrap();
+    sender.abort();
+    assert!(response.await.unwrap().is_err());
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn abort_handle_cancels_response_and_body() {
+    use futures_util::StreamExt;
+
+    let server = server::http(move |req| async move {
+        if req.uri().path() == "/slow" {
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+        }
+        let first =
+            futures_util::stream::once(async { Ok::<_, std::convert::Infallible>("first chunk") });
+        let stream = first.chain(futures_util::stream::pending());
+        http::Response::new(rquest::Body::wrap_stream(stream))
+    });
+    let client = Client::new();
+
+    let handle = rquest::AbortHandle::new();
+    let aborter = handle.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        aborter.abort();
+    });
+    let err = client
+        .get(format!("http://{}/slow", server.addr()))
+        .abort_handle(&handle)
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_canceled());
+    assert!(err.is_request());
+
+    let handle = rquest::AbortHandle::new();
+    let mut res = client
+        .get(format!("http://{}/body", server.addr()))
+        .abort_handle(&handle)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.chunk().await.unwrap().unwrap(), "first chunk");
+    let aborter = handle.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        aborter.abort();
+    });
+    let err = res.chunk().await.unwrap_err();
+    assert!(err.is_canceled());
+    assert!(err.is_body());
+}
+
+#[tokio::test]
+async fn headers_for_host() {
+    use http::header::{HeaderMap, HeaderValue, ACCEPT};
+
+    let server = server::http(move |req| async move {
+        if req.uri().path() == "/redirect" {
+            let host = req.headers()["host"].to_str().unwrap();
+            let location = format!("http://{}/", host.replace("127.0.0.1", "localhost"));
+            return http::Response::builder()
+                .status(302)
+                .header("location", location)
+                .body(Default::default())
+                .unwrap();
+        }
+        let api_key = req.headers().get("x-api-key").is_some();
+        let accept = req
+            .headers()
+            .get("accept")
+            .map_or("none", |accept| accept.to_str().unwrap())
+            .to_owned();
+        http::Response::new(format!("{api_key} {accept}").into())
+    });
+
+    let mut defaults = HeaderMap::new();
+    defaults.insert(ACCEPT, HeaderValue::from_static("text/plain"));
+    let mut local = HeaderMap::new();
+    local.insert(ACCEPT, HeaderValue::from_static("application/json"));
+    local.insert("x-api-key", HeaderValue::from_static("secret"));
+    let client = Client::builder()
+        .default_headers(defaults)
+        .headers_for_host("127.0.0.1", local)
+        .build()
+        .unwrap();
+
+    let ip = format!("http://127.0.0.1:{}", server.addr().port());
+    let name = format!("http://localhost:{}", server.addr().port());
+
+    let res = client.get(&ip).send().await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "true application/json");
+
+    let res = client.get(&name).send().await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "false text/plain");
+
+    let res = client
+        .get(&ip)
+        .header(ACCEPT, "text/html")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "true text/html");
+
+    // the headers of the first host are not sent to the second one
+    let res = client.get(format!("{ip}/redirect")).send().await.unwrap();
+    assert_eq!(res.url().host_str(), Some("localhost"));
+    assert_eq!(res.text().await.unwrap(), "false text/plain");
+}
+
+#[tokio::test]
+async fn impersonate_for_host() {
+    let server = server::http(move |req| async move {
+        let user_agent = req.headers()["user-agent"].to_str().unwrap().to_owned();
+        http::Response::new(user_agent.into())
+    });
+
+    let client = Client::builder()
+        .impersonate(Impersonate::Chrome131)
+        .impersonate_for_host("127.0.0.1", Impersonate::OkHttp5)
+        .build()
+        .unwrap();
+
+    let ip = format!("http://127.0.0.1:{}/", server.addr().port());
+    let res = client.get(&ip).send().await.unwrap();
+    assert!(res.text().await.unwrap().contains("OkHttp/5"));
+
+    let name = format!("http://localhost:{}/", server.addr().port());
+    let res = client.get(&name).send().await.unwrap();
+    assert!(res.text().await.unwrap().contains("Chrome/131"));
+}
+
+#[tokio::test]
+async fn hsts_preload_upgrades_to_https() {
+    let server = server::http(move |_req| async move { http::Response::default() });
+
+    let client = Client::builder()
+        .hsts_preload(["localhost"])
+        .build()
+        .unwrap();
+
+    // the plain HTTP server can't complete a TLS handshake
+    let url = format!("http://localhost:{}/", server.addr().port());
+    let err = client.get(&url).send().await.unwrap_err();
+    assert_eq!(err.url().map(|url| url.scheme()), Some("https"));
+
+    // IP addresses are never upgraded
+    let url = format!("http://{}/", server.addr());
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.url().scheme(), "http");
+}
+
+#[tokio::test]
+async fn error_kind_connect_refused() {
+    // find a port nothing listens on
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+
+    let err = Client::new()
+        .get(format!("http://{}/", addr))
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(err.is_connect());
+    assert_eq!(err.kind(), rquest::ErrorKind::Connect);
+    assert_eq!(err.remote_addr(), Some(addr));
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn transfer_stats() {
+    let server = server::http(move |_req| async move { http::Response::new("hello".into()) });
+
+    let client = Client::new();
+    let url = format!("http://{}/", server.addr());
+
+    let res = client.post(&url).body("abc").send().await.unwrap();
+    let stats = res.stats();
+    assert_eq!(stats.requests(), 1);
+    assert!(stats.request_header_bytes() > 0);
+    assert_eq!(stats.request_body_bytes(), 3);
+    assert!(stats.response_header_bytes() > 0);
+
+    assert_eq!(res.text().await.unwrap(), "hello");
+    assert_eq!(stats.response_body_bytes(), 5);
+    assert_eq!(stats.response_decoded_bytes(), 5);
+
+    client.get(&url).send().await.unwrap();
+    let totals = client.stats();
+    assert_eq!(totals.requests(), 2);
+    assert_eq!(totals.request_body_bytes(), 3);
+    assert!(totals.received_bytes() > stats.received_bytes());
+}
+
+#[tokio::test]
+async fn debug_capture_http1_head() {
+    let server = server::http(move |_req| async move { http::Response::default() });
+
+    let client = Client::builder().debug_capture(true).build().unwrap();
+    let url = format!("http://{}/capture", server.addr());
+
+    let res = client
+        .get(&url)
+        .header("x-first", "1")
+        .header("x-second", "2")
+        .send()
+        .await
+        .unwrap();
+
+    let capture = res.debug_capture().expect("captured");
+    assert_eq!(capture.client_hello(), None);
+    let head = std::str::from_utf8(capture.request_head().unwrap()).unwrap();
+    assert!(head.starts_with("GET /capture HTTP/1.1\r\n"));
+    assert!(head.ends_with("\r\n\r\n"));
+    let first = head.find("x-first: 1\r\n").unwrap();
+    let second = head.find("x-second: 2\r\n").unwrap();
+    assert!(first < second);
+    assert!(capture.headers().contains_key("x-first"));
+
+    let res = Client::new().get(&url).send().await.unwrap();
+    assert!(res.debug_capture().is_none());
+}
+
+#[tokio::test]
+async fn fresh_connection_is_closed_after_use() {
+    let mut server = server::http(move |_| async move { http::Response::default() });
+
+    let client = Client::new();
+    let url = format!("http://{}", server.addr());
+
+    let res = client.get(&url).send().await.unwrap();
+    res.text().await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert!(server.events().is_empty());
+
+    let res = client
+        .get(&url)
+        .fresh_connection(true)
+        .send()
+        .await
+        .unwrap();
+    res.text().await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert!(server
+        .events()
+        .iter()
+        .any(|e| matches!(e, server::Event::ConnectionClosed)));
+}
+
+#[tokio::test]
+async fn pool_max_connection_requests_retires_connections() {
+    let mut server = server::http(move |_| async move { http::Response::default() });
+
+    let client = Client::builder()
+        .pool_max_connection_requests(2)
+        .build()
+        .unwrap();
+    let url = format!("http://{}", server.addr());
+
+    client.get(&url).send().await.unwrap().text().await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert!(server.events().is_empty());
+
+    client.get(&url).send().await.unwrap().text().await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert!(server
+        .events()
+        .iter()
+        .any(|e| matches!(e, server::Event::ConnectionClosed)));
+}
+
+#[tokio::test]
+async fn resolve_to_overrides_one_request() {
+    let server = server::http(move |_req| async { http::Response::new("Hello".into()) });
+
+    let url = format!("http://rust-lang.org:{}/resolve_to", server.addr().port());
+    let client = rquest::Client::builder().no_proxy().build().unwrap();
+
+    let res = client
+        .get(&url)
+        .resolve_to(server.addr().ip())
+        .send()
+        .await
+        .expect("request");
+    assert_eq!(res.remote_addr(), Some(server.addr()));
+    assert_eq!(res.text().await.unwrap(), "Hello");
+}
+
+#[tokio::test]
+async fn fetch_cors_and_redirect_modes() {
+    let server = server::http(move |req| async move {
+        match req.uri().path() {
+            "/redirect" => http::Response::builder()
+                .status(302)
+                .header("location", "/allowed")
+                .body(Default::default())
+                .unwrap(),
+            path => {
+                assert_eq!(req.headers()["origin"], "http://localhost");
+                assert_eq!(req.headers()["referer"], "http://localhost/");
+                let mut res = http::Response::new("Hello".into());
+                if path == "/allowed" {
+                    res.headers_mut()
+                        .insert("access-control-allow-origin", "*".parse().unwrap());
+                }
+                res
+            }
+        }
+    });
+
+    let client = rquest::Client::builder().no_proxy().build().unwrap();
+    let url =
+        |path: &str| rquest::Url::parse(&format!("http://{}{}", server.addr(), path)).unwrap();
+    let fetch = |path: &str| rquest::FetchRequest {
+        referrer: Some(rquest::Url::parse("http://localhost/page").unwrap()),
+        ..rquest::FetchRequest::new(rquest::Method::GET, url(path))
+    };
+
+    let res = client.fetch(fetch("/allowed")).await.expect("allowed");
+    assert_eq!(res.text().await.unwrap(), "Hello");
+
+    let err = client.fetch(fetch("/denied")).await.unwrap_err();
+    assert!(err.is_request());
+
+    let err = client
+        .fetch(rquest::FetchRequest {
+            mode: rquest::RequestMode::SameOrigin,
+            ..fetch("/allowed")
+        })
+        .await
+        .unwrap_err();
+    assert!(err.is_request());
+
+    let res = client
+        .fetch(rquest::FetchRequest {
+            redirect: rquest::RequestRedirect::Manual,
+            ..fetch("/redirect")
+        })
+        .await
+        .expect("manual redirect");
+    assert_eq!(res.status(), rquest::StatusCode::FOUND);
+
+    let err = client
+        .fetch(rquest::FetchRequest {
+            redirect: rquest::RequestRedirect::Error,
+            ..fetch("/redirect")
+        })
+        .await
+        .unwrap_err();
+    assert!(err.is_redirect());
+}
+
+#[tokio::test]
+async fn page_navigation_then_xhr_headers() {
+    let server = server::http(move |req| async move {
+        let header = |name: &str| {
+            req.headers()
+                .get(name)
+                .map(|value| value.to_str().unwrap().to_owned())
+        };
+        match req.uri().path() {
+            "/" => {
+                assert_eq!(header("sec-fetch-site").as_deref(), Some("none"));
+                assert_eq!(header("sec-fetch-mode").as_deref(), Some("navigate"));
+                assert_eq!(header("sec-fetch-dest").as_deref(), Some("document"));
+                assert_eq!(header("sec-fetch-user").as_deref(), Some("?1"));
+                assert_eq!(header("referer"), None);
+                assert_eq!(header("sec-ch-ua-arch"), None);
+                http::Response::builder()
+                    .header("accept-ch", "Sec-CH-UA-Arch")
+                    .body(Default::default())
+                    .unwrap()
+            }
+            _ => {
+                assert_eq!(header("sec-fetch-site").as_deref(), Some("same-origin"));
+                assert_eq!(header("sec-fetch-mode").as_deref(), Some("cors"));
+                assert_eq!(header("sec-fetch-dest").as_deref(), Some("empty"));
+                assert_eq!(header("sec-fetch-user"), None);
+                assert!(header("referer").unwrap().ends_with('/'));
+                assert_eq!(header("sec-ch-ua-arch").as_deref(), Some("\"x86\""));
+                http::Response::new("Hello".into())
+            }
+        }
+    });
+
+    let client = rquest::Client::builder().no_proxy().build().unwrap();
+    let mut page = client.page().client_hint(
+        rquest::header::HeaderName::from_static("sec-ch-ua-arch"),
+        rquest::header::HeaderValue::from_static("\"x86\""),
+    );
+
+    let url = format!("http://{}/", server.addr());
+    page.navigate(&url).await.expect("navigate");
+    assert_eq!(page.url().map(|url| url.as_str()), Some(url.as_str()));
+
+    let xhr = rquest::Url::parse(&format!("{}api", url)).unwrap();
+    let res = page
+        .fetch(rquest::FetchRequest::new(rquest::Method::GET, xhr))
+        .await
+        .expect("xhr");
+    assert_eq!(res.text().await.unwrap(), "Hello");
+}
+
+#[tokio::test]
+async fn rate_limit_pacing_waits_for_retry_after() {
+    let requests = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = requests.clone();
+    let server = server::http(move |_req| {
+        let n = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        async move {
+            if n == 0 {
+                http::Response::builder()
+                    .status(429)
+                    .header("retry-after", "1")
+                    .body(Default::default())
+                    .unwrap()
+            } else {
+                http::Response::new("Hello".into())
+            }
+        }
+    });
+
+    let url = format!("http://{}/pacing", server.addr());
+    let client = rquest::Client::builder()
+        .no_proxy()
+        .rate_limit_pacing(true)
+        .build()
+        .unwrap();
+
+    let res = client.get(&url).send().await.expect("first");
+    assert_eq!(res.status(), rquest::StatusCode::TOO_MANY_REQUESTS);
+
+    let start = std::time::Instant::now();
+    let res = client.get(&url).send().await.expect("second");
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+    assert!(start.elapsed() >= std::time::Duration::from_millis(900));
+    assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn client_hints_sent_once_accepted() {
+    let server = server::http(move |req| async move {
+        let platform_version = req
+            .headers()
+            .get("sec-ch-ua-platform-version")
+            .map(|value| value.to_str().unwrap().to_owned());
+        match req.uri().path() {
+            "/first" => {
+                assert_eq!(platform_version, None);
+                http::Response::builder()
+                    .header("accept-ch", "Sec-CH-UA-Platform-Version")
+                    .body(Default::default())
+                    .unwrap()
+            }
+            _ => {
+                assert_eq!(platform_version.as_deref(), Some("\"15.0.0\""));
+                http::Response::default()
+            }
+        }
+    });
+
+    let client = rquest::Client::builder()
+        .no_proxy()
+        .client_hints(rquest::ClientHints::new().platform_version("15.0.0"))
+        .build()
+        .unwrap();
+
+    let res = client
+        .get(format!("http://{}/first", server.addr()))
+        .send()
+        .await
+        .expect("first");
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+
+    let res = client
+        .get(format!("http://{}/second", server.addr()))
+        .send()
+        .await
+        .expect("second");
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
@@ -201,3 +201,41 @@ async fn cookie_store_path() {
     let url = format!("http://{}/subpath", server.addr());
     client.get(&url).send().await.unwrap();
 }
+
+#[tokio::test]
+async fn session_export_resumes_cookies() {
+    let server = server::http(move |req| async move {
+        if req.uri() == "/login" {
+            return http::Response::builder()
+                .header("Set-Cookie", "sid=abc; Max-Age=3600")
+                .header("Set-Cookie", "pref=dark; Path=/account")
+                .body(Default::default())
+                .unwrap();
+        }
+        let mut cookies = req.headers()["cookie"]
+            .to_str()
+            .unwrap()
+            .split("; ")
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        cookies.sort();
+        assert_eq!(cookies, ["pref=dark", "sid=abc"]);
+        http::Response::default()
+    });
+
+    let client = rquest::Client::builder()
+        .cookie_store(true)
+        .build()
+        .unwrap();
+    let url = format!("http://{}", server.addr());
+    client.get(format!("{url}/login")).send().await.unwrap();
+    let state = client.export_session();
+    assert!(!state.is_empty());
+
+    let resumed = rquest::Client::builder()
+        .import_session(state)
+        .build()
+        .unwrap();
+    let res = resumed.get(format!("{url}/account")).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+}
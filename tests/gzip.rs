@@ -362,3 +362,147 @@ async fn test_chunked_fragmented_response_with_extra_bytes() {
     assert!(err.is_decode());
     assert!(start.elapsed() >= DELAY_BETWEEN_RESPONSE_PARTS - DELAY_MARGIN);
 }
+
+#[tokio::test]
+async fn gzip_max_response_size() {
+    let mut encoder = libflate::gzip::Encoder::new(Vec::new()).unwrap();
+    encoder.write_all(&[0u8; 100_000]).unwrap();
+    let gzipped_content = encoder.finish().into_result().unwrap();
+
+    let server = server::http(move |_req| {
+        let gzipped = gzipped_content.clone();
+        async move {
+            http::Response::builder()
+                .header("content-encoding", "gzip")
+                .body(gzipped.into())
+                .unwrap()
+        }
+    });
+
+    let client = rquest::Client::builder()
+        .max_response_size(10_000)
+        .build()
+        .unwrap();
+
+    let err = client
+        .get(format!("http://{}/gzip", server.addr()))
+        .send()
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap_err();
+    assert!(err.is_body_too_large());
+
+    let body = client
+        .get(format!("http://{}/gzip", server.addr()))
+        .max_response_size(100_000)
+        .send()
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap();
+    assert_eq!(body.len(), 100_000);
+}
+
+#[tokio::test]
+async fn gzip_max_decompression_ratio() {
+    let mut encoder = libflate::gzip::Encoder::new(Vec::new()).unwrap();
+    encoder.write_all(&vec![0u8; 1024 * 1024]).unwrap();
+    let gzipped_content = encoder.finish().into_result().unwrap();
+
+    let server = server::http(move |_req| {
+        let gzipped = gzipped_content.clone();
+        async move {
+            http::Response::builder()
+                .header("content-encoding", "gzip")
+                .body(gzipped.into())
+                .unwrap()
+        }
+    });
+
+    let client = rquest::Client::builder()
+        .max_decompression_ratio(100)
+        .build()
+        .unwrap();
+
+    let err = client
+        .get(format!("http://{}/gzip", server.addr()))
+        .send()
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap_err();
+    assert!(err.is_decode());
+    assert!(err.is_decompression_limit());
+}
+
+#[tokio::test]
+async fn gzip_request_body() {
+    use http_body_util::BodyExt;
+    use std::io::Read;
+
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["content-encoding"], "gzip");
+        let len: usize = req.headers()["content-length"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body.len(), len);
+
+        let mut decoded = String::new();
+        libflate::gzip::Decoder::new(&body[..])
+            .unwrap()
+            .read_to_string(&mut decoded)
+            .unwrap();
+        http::Response::new(decoded.into())
+    });
+
+    let content = "test request body ".repeat(100);
+    let res = rquest::Client::new()
+        .post(format!("http://{}/gzip", server.addr()))
+        .body(content.clone())
+        .compress_body(rquest::Encoding::Gzip)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.text().await.unwrap(), content);
+}
+
+#[tokio::test]
+async fn gzip_decompress_disabled_per_request() {
+    let mut encoder = libflate::gzip::Encoder::new(Vec::new()).unwrap();
+    encoder.write_all(b"test response").unwrap();
+    let gzipped_content = encoder.finish().into_result().unwrap();
+
+    let server = server::http({
+        let gzipped_content = gzipped_content.clone();
+        move |_req| {
+            let gzipped = gzipped_content.clone();
+            async move {
+                http::Response::builder()
+                    .header("content-encoding", "gzip")
+                    .header("content-length", gzipped.len())
+                    .body(gzipped.into())
+                    .unwrap()
+            }
+        }
+    });
+
+    let res = rquest::Client::new()
+        .get(format!("http://{}/gzip", server.addr()))
+        .decompress(false)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.headers()["content-encoding"], "gzip");
+    assert_eq!(res.content_length(), Some(gzipped_content.len() as u64));
+    assert_eq!(res.bytes().await.unwrap(), gzipped_content);
+}
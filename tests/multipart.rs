@@ -176,3 +176,119 @@ async fn async_impl_file_part() {
     assert_eq!(res.url().as_str(), &url);
     assert_eq!(res.status(), rquest::StatusCode::OK);
 }
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn reader_part_with_length() {
+    let _ = env_logger::try_init();
+
+    let reader = std::io::Cursor::new(b"hello world, and more".to_vec());
+    let part = rquest::multipart::Part::reader_with_length(reader, 11).file_name("hello.txt");
+    let form = rquest::multipart::Form::new().part("foo", part);
+
+    let expected_body = format!(
+        "\
+         --{0}\r\n\
+         Content-Disposition: form-data; name=\"foo\"; filename=\"hello.txt\"\r\n\r\n\
+         hello world\r\n\
+         --{0}--\r\n\
+         ",
+        form.boundary()
+    );
+
+    let server = server::http(move |req| {
+        let expected_body = expected_body.clone();
+        async move {
+            assert_eq!(
+                req.headers()["content-length"],
+                expected_body.len().to_string()
+            );
+            let full = req.collect().await.unwrap().to_bytes();
+
+            assert_eq!(full, expected_body.as_bytes());
+
+            http::Response::default()
+        }
+    });
+
+    let url = format!("http://{}/multipart/reader", server.addr());
+
+    let res = rquest::Client::new()
+        .post(&url)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn impersonated_multipart_style() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |req| async move {
+        let ct = req.headers()["content-type"].to_str().unwrap().to_owned();
+        let boundary = ct
+            .strip_prefix("multipart/form-data; boundary=")
+            .unwrap()
+            .to_owned();
+        assert!(boundary.starts_with("----WebKitFormBoundary"));
+
+        let full = req.collect().await.unwrap().to_bytes();
+        let expected_body = format!(
+            "\
+             --{0}\r\n\
+             Content-Disposition: form-data; name=\"caf\u{e9}\"\r\n\r\n\
+             latte\r\n\
+             --{0}--\r\n\
+             ",
+            boundary
+        );
+        assert_eq!(full, expected_body.as_bytes());
+
+        http::Response::default()
+    });
+
+    let client = rquest::Client::builder()
+        .impersonate(rquest::Impersonate::Chrome131)
+        .build()
+        .unwrap();
+    let form = rquest::multipart::Form::new().text("caf\u{e9}", "latte");
+
+    let res = client
+        .post(format!("http://{}/multipart/style", server.addr()))
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[test]
+fn deterministic_boundary() {
+    let boundaries = || {
+        let client = rquest::Client::builder()
+            .impersonate(rquest::Impersonate::Chrome131)
+            .deterministic(7)
+            .build()
+            .unwrap();
+        (0..2)
+            .map(|_| {
+                let form = rquest::multipart::Form::new().text("q", "rust");
+                let req = client
+                    .post("http://example.test/")
+                    .multipart(form)
+                    .build()
+                    .unwrap();
+                req.headers()["content-type"].to_str().unwrap().to_owned()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let first = boundaries();
+    assert!(first[0].contains("boundary=----WebKitFormBoundary"));
+    assert_ne!(first[0], first[1]);
+    assert_eq!(first, boundaries());
+}
@@ -120,6 +120,31 @@ async fn test_no_proxy() {
     assert_eq!(res.status(), rquest::StatusCode::OK);
 }
 
+#[tokio::test]
+async fn no_proxy_local_hostnames() {
+    let server = server::http(move |req| {
+        assert_eq!(req.uri(), "/local");
+
+        async { http::Response::default() }
+    });
+    let url = format!("http://localhost:{}/local", server.addr().port());
+
+    // the proxy does not exist, so only a direct request succeeds.
+    let proxy = rquest::Proxy::http("http://127.0.0.1:1")
+        .unwrap()
+        .no_proxy(rquest::NoProxy::from_string("example.com, <local>"));
+    let res = rquest::Client::builder()
+        .proxy(proxy)
+        .build()
+        .unwrap()
+        .get(&url)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
 #[tokio::test]
 async fn test_using_system_proxy() {
     let url = "http://not.a.real.sub.hyper.rs/prox";
@@ -175,3 +200,474 @@ async fn http_over_http() {
     assert_eq!(res.url().as_str(), url);
     assert_eq!(res.status(), rquest::StatusCode::OK);
 }
+
+#[cfg(feature = "socks")]
+#[tokio::test]
+async fn socks4a_proxy_user_id() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let server = server::http(move |req| {
+        assert_eq!(req.headers()["host"], "hyper.rs");
+
+        async { http::Response::default() }
+    });
+    let upstream = server.addr();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy = format!("socks4a://Aladdin@{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+
+        async fn read_cstr(socket: &mut tokio::net::TcpStream) -> String {
+            let mut buf = Vec::new();
+            loop {
+                match socket.read_u8().await.unwrap() {
+                    0 => return String::from_utf8(buf).unwrap(),
+                    b => buf.push(b),
+                }
+            }
+        }
+
+        let mut head = [0; 8];
+        socket.read_exact(&mut head).await.unwrap();
+        // version 4, CONNECT, port 80, and the 0.0.0.x address of SOCKS4A
+        assert_eq!(&head[..4], [4, 1, 0, 80]);
+        assert_eq!(&head[4..7], [0, 0, 0]);
+        assert_eq!(read_cstr(&mut socket).await, "Aladdin");
+        assert_eq!(read_cstr(&mut socket).await, "hyper.rs");
+
+        socket
+            .write_all(&[0, 0x5a, 0, 0, 0, 0, 0, 0])
+            .await
+            .unwrap();
+        let mut upstream = tokio::net::TcpStream::connect(upstream).await.unwrap();
+        tokio::io::copy_bidirectional(&mut socket, &mut upstream)
+            .await
+            .ok();
+    });
+
+    let res = rquest::Client::builder()
+        .proxy(rquest::Proxy::http(&proxy).unwrap())
+        .build()
+        .unwrap()
+        .get("http://hyper.rs/prox")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn proxy_pool_quarantines_failing_proxy() {
+    let url = "http://hyper.rs/prox";
+    let limited = server::http(move |req| {
+        assert_eq!(req.uri(), url);
+
+        async {
+            http::Response::builder()
+                .status(429)
+                .body(Default::default())
+                .unwrap()
+        }
+    });
+    let healthy = server::http(move |req| {
+        assert_eq!(req.uri(), url);
+        assert_eq!(
+            req.headers()["proxy-authorization"],
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+        );
+
+        async { http::Response::default() }
+    });
+
+    let pool = rquest::ProxyPool::builder()
+        .proxy(format!("http://{}", limited.addr()))
+        .proxy(format!("http://Aladdin:open%20sesame@{}", healthy.addr()))
+        .build()
+        .unwrap();
+
+    let client = rquest::Client::builder()
+        .proxy(rquest::Proxy::pool(pool.clone()))
+        .build()
+        .unwrap();
+
+    let res = client.get(url).send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::TOO_MANY_REQUESTS);
+
+    for _ in 0..3 {
+        let res = client.get(url).send().await.unwrap();
+        assert_eq!(res.status(), rquest::StatusCode::OK);
+    }
+
+    let stats = pool.stats();
+    assert_eq!(stats[0].failures(), 1);
+    assert!(stats[0].is_quarantined());
+    assert_eq!(stats[1].successes(), 3);
+    assert_eq!(stats[1].success_rate(), Some(1.0));
+}
+
+#[tokio::test]
+async fn proxy_pool_rotates_once_per_request() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let url = "http://hyper.rs/prox";
+    let hits = Arc::new([AtomicUsize::new(0), AtomicUsize::new(0)]);
+    let servers = [0, 1].map(|i| {
+        let hits = hits.clone();
+        server::http(move |req| {
+            assert_eq!(req.uri(), url);
+            hits[i].fetch_add(1, Ordering::SeqCst);
+
+            async { http::Response::default() }
+        })
+    });
+
+    let pool = rquest::ProxyPool::builder()
+        .proxy(format!("http://{}", servers[0].addr()))
+        .proxy(format!("http://{}", servers[1].addr()))
+        .build()
+        .unwrap();
+
+    // the https-only proxy matches nothing, so the pool is the last match
+    let client = rquest::Client::builder()
+        .proxy(rquest::Proxy::pool(pool))
+        .proxy(rquest::Proxy::https("http://127.0.0.1:1").unwrap())
+        .build()
+        .unwrap();
+
+    for _ in 0..4 {
+        let res = client.get(url).send().await.unwrap();
+        assert_eq!(res.status(), rquest::StatusCode::OK);
+    }
+
+    assert_eq!(hits[0].load(Ordering::SeqCst), 2);
+    assert_eq!(hits[1].load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn http_over_https_proxy() {
+    use boring2::ssl::{select_next_proto, AlpnError, SslAcceptor, SslFiletype, SslMethod};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    acceptor
+        .set_certificate_file("tests/support/server.cert", SslFiletype::ASN1)
+        .unwrap();
+    acceptor
+        .set_private_key_file("tests/support/server.key", SslFiletype::ASN1)
+        .unwrap();
+    // the proxy would pick h2 if it was offered
+    acceptor.set_alpn_select_callback(|_, client| {
+        select_next_proto(b"\x02h2\x08http/1.1", client).ok_or(AlpnError::NOACK)
+    });
+    let acceptor = acceptor.build();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy = format!(
+        "https://localhost:{}",
+        listener.local_addr().unwrap().port()
+    );
+
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut tls = tokio_boring2::accept(&acceptor, socket).await.unwrap();
+        assert_eq!(tls.ssl().selected_alpn_protocol(), Some(&b"http/1.1"[..]));
+
+        let mut req = Vec::new();
+        while !req.ends_with(b"\r\n\r\n") {
+            req.push(tls.read_u8().await.unwrap());
+        }
+        assert!(req.starts_with(b"GET http://hyper.rs/prox HTTP/1.1\r\n"));
+
+        tls.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+            .await
+            .unwrap();
+    });
+
+    let res = rquest::Client::builder()
+        .proxy(rquest::Proxy::http(&proxy).unwrap())
+        .proxy_tls_config(
+            rquest::TlsConfig::builder()
+                .certs_verification(false)
+                .build(),
+        )
+        .build()
+        .unwrap()
+        .get("http://hyper.rs/prox")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[cfg(feature = "socks")]
+#[tokio::test]
+async fn proxy_credentials_not_sent_through_socks() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // the origin asks for proxy credentials, which must not make the client
+    // hand them over
+    let server = server::http(move |req| {
+        assert_eq!(req.headers().get("proxy-authorization"), None);
+
+        async {
+            http::Response::builder()
+                .status(http::StatusCode::PROXY_AUTHENTICATION_REQUIRED)
+                .body(Default::default())
+                .unwrap()
+        }
+    });
+    let upstream = server.addr();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy = format!("socks5h://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+
+        // no authentication
+        let mut greeting = [0; 3];
+        socket.read_exact(&mut greeting).await.unwrap();
+        assert_eq!(greeting, [5, 1, 0]);
+        socket.write_all(&[5, 0]).await.unwrap();
+
+        // CONNECT to a domain name
+        let mut head = [0; 5];
+        socket.read_exact(&mut head).await.unwrap();
+        assert_eq!(&head[..4], [5, 1, 0, 3]);
+        let mut host = vec![0; head[4] as usize + 2];
+        socket.read_exact(&mut host).await.unwrap();
+        assert_eq!(&host[..host.len() - 2], b"hyper.rs");
+
+        socket
+            .write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0])
+            .await
+            .unwrap();
+        let mut upstream = tokio::net::TcpStream::connect(upstream).await.unwrap();
+        tokio::io::copy_bidirectional(&mut socket, &mut upstream)
+            .await
+            .ok();
+    });
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let proxy = rquest::Proxy::all(&proxy)
+        .unwrap()
+        .credentials_provider(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Ok(rquest::ProxyCredentials::basic("Aladdin", "opensesame")) }
+        });
+
+    let res = rquest::Client::builder()
+        .proxy(proxy)
+        .build()
+        .unwrap()
+        .get("http://hyper.rs/prox")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        res.status(),
+        rquest::StatusCode::PROXY_AUTHENTICATION_REQUIRED
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn proxy_credentials_provider_refreshes_on_407() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let url = "http://hyper.rs.local/prox";
+    let server = server::http(move |req| {
+        assert_eq!(req.uri(), url);
+
+        let authorized = req.headers().get("proxy-authorization")
+            == Some(&http::HeaderValue::from_static("Bearer token-2"));
+        async move {
+            let status = if authorized {
+                http::StatusCode::OK
+            } else {
+                http::StatusCode::PROXY_AUTHENTICATION_REQUIRED
+            };
+            http::Response::builder()
+                .status(status)
+                .body(Default::default())
+                .unwrap()
+        }
+    });
+
+    // the first token is refused, the second one is accepted.
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let proxy = rquest::Proxy::http(format!("http://{}", server.addr()))
+        .unwrap()
+        .credentials_provider(move || {
+            let call = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            async move {
+                let token = format!("Bearer token-{}", call);
+                Ok(rquest::ProxyCredentials::header(token.parse().unwrap()))
+            }
+        });
+
+    let client = rquest::Client::builder().proxy(proxy).build().unwrap();
+
+    let res = client.get(url).send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    // the accepted token is kept for the next requests.
+    let res = client.get(url).send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn custom_connect_headers() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy = format!("http://{}", listener.local_addr().unwrap());
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut req = Vec::new();
+        let mut buf = [0; 1024];
+        while !req.ends_with(b"\r\n\r\n") {
+            let n = socket.read(&mut buf).await.unwrap();
+            req.extend_from_slice(&buf[..n]);
+        }
+        tx.send(String::from_utf8(req).unwrap()).unwrap();
+
+        // refuse the tunnel, only the CONNECT request matters
+        socket
+            .write_all(b"HTTP/1.1 403 Forbidden\r\n\r\n")
+            .await
+            .unwrap();
+    });
+
+    let mut headers = rquest::header::HeaderMap::new();
+    headers.insert("x-proxy-session", "a1b2c3".parse().unwrap());
+    let client = rquest::Client::builder()
+        .proxy(
+            rquest::Proxy::https(&proxy)
+                .unwrap()
+                .custom_connect_headers(headers),
+        )
+        .build()
+        .unwrap();
+
+    let err = client
+        .get("https://hyper.rs.local/prox")
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_connect());
+
+    let req = rx.try_recv().unwrap();
+    assert!(req.starts_with("CONNECT hyper.rs.local:443 HTTP/1.1\r\n"));
+    assert!(req.contains("\r\nx-proxy-session: a1b2c3\r\n"));
+}
+
+#[tokio::test]
+async fn custom_connect_headers_follow_redirects() {
+    let server = server::http(move |req| async move {
+        assert_eq!(req.headers()["x-proxy-session"], "a1b2c3");
+        if req.uri() == "http://hyper.rs/start" {
+            http::Response::builder()
+                .status(302)
+                .header("location", "http://other.rs/end")
+                .body(Default::default())
+                .unwrap()
+        } else {
+            assert_eq!(req.uri(), "http://other.rs/end");
+            http::Response::default()
+        }
+    });
+
+    let mut headers = rquest::header::HeaderMap::new();
+    headers.insert("x-proxy-session", "a1b2c3".parse().unwrap());
+    let client = rquest::Client::builder()
+        .proxy(
+            rquest::Proxy::http(format!("http://{}", server.addr()))
+                .unwrap()
+                .custom_connect_headers(headers),
+        )
+        .build()
+        .unwrap();
+
+    let res = client.get("http://hyper.rs/start").send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+    assert_eq!(res.url().as_str(), "http://other.rs/end");
+}
+
+#[tokio::test]
+async fn no_proxy_ports_and_predicates() {
+    let server = server::http(move |_req| async { http::Response::default() });
+    let port = server.addr().port();
+
+    // the proxy does not exist, so only direct requests succeed.
+    let client = |no_proxy: rquest::NoProxy| {
+        let proxy = rquest::Proxy::http("http://127.0.0.1:1")
+            .unwrap()
+            .no_proxy(Some(no_proxy));
+        rquest::Client::builder().proxy(proxy).build().unwrap()
+    };
+    let url = format!("http://127.0.0.1:{}/", port);
+
+    let no_proxy = rquest::NoProxy::from_string(&format!("127.0.0.1:{}", port)).unwrap();
+    let res = client(no_proxy).get(&url).send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+
+    let no_proxy = rquest::NoProxy::from_string("127.0.0.0/8").unwrap();
+    let res = client(no_proxy).get(&url).send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+
+    // another port goes through the proxy
+    let no_proxy = rquest::NoProxy::from_string(&format!("127.0.0.1:{}", port + 1)).unwrap();
+    let err = client(no_proxy).get(&url).send().await.unwrap_err();
+    assert!(err.is_connect());
+
+    let no_proxy = rquest::NoProxy::default().predicate(move |url| url.port() == Some(port));
+    let res = client(no_proxy).get(&url).send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn tunnel_through_http_proxy() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let server = server::low_level_with_response(|raw_request, client_socket| {
+        Box::new(async move {
+            assert!(raw_request.starts_with(b"CONNECT mail.example.test:25 HTTP/1.1\r\n"));
+            client_socket
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                .await
+                .unwrap();
+
+            let mut buf = [0; 4];
+            client_socket.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"ping");
+            client_socket.write_all(b"pong").await.unwrap();
+            client_socket.shutdown().await.unwrap();
+        })
+    });
+
+    let client = rquest::Client::builder()
+        .proxy(rquest::Proxy::all(format!("http://{}", server.addr())).unwrap())
+        .build()
+        .unwrap();
+    let mut tunnel = client.tunnel("mail.example.test", 25).await.unwrap();
+    tunnel.write_all(b"ping").await.unwrap();
+    let mut buf = Vec::new();
+    tunnel.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, b"pong");
+}
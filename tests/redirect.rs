@@ -46,6 +46,55 @@ async fn test_redirect_301_and_302_and_303_changes_post_to_get() {
     }
 }
 
+#[tokio::test]
+async fn test_redirect_301_and_302_strict_keeps_post() {
+    use rquest::redirect::MethodRewrite;
+
+    let client = rquest::Client::new();
+    let codes = [301u16, 302];
+
+    for &code in &codes {
+        let redirect = server::http(move |req| async move {
+            assert_eq!(req.method(), "POST");
+            assert_eq!(req.headers()["content-length"], "5");
+
+            let is_redirect = req.uri() == &*format!("/{code}");
+            let data = req.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(&*data, b"Hello");
+
+            if is_redirect {
+                http::Response::builder()
+                    .status(code)
+                    .header("location", "/dst")
+                    .header("server", "test-redirect")
+                    .body(Body::default())
+                    .unwrap()
+            } else {
+                http::Response::builder()
+                    .header("server", "test-dst")
+                    .body(Body::default())
+                    .unwrap()
+            }
+        });
+
+        let url = format!("http://{}/{}", redirect.addr(), code);
+        let dst = format!("http://{}/{}", redirect.addr(), "dst");
+        let res = client
+            .post(&url)
+            .body("Hello")
+            .redirect(Policy::default().method_rewrite(MethodRewrite::Strict))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.url().as_str(), dst);
+        assert_eq!(res.status(), rquest::StatusCode::OK);
+        assert_eq!(
+            res.headers().get(rquest::header::SERVER).unwrap(),
+            &"test-dst"
+        );
+    }
+}
+
 #[tokio::test]
 async fn test_redirect_307_and_308_tries_to_get_again() {
     let client = rquest::Client::new();
@@ -142,6 +191,42 @@ async fn test_redirect_307_and_308_tries_to_post_again() {
     }
 }
 
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn test_redirect_307_sends_body_factory_again() {
+    let redirect = server::http(move |req| async move {
+        assert_eq!(req.method(), "POST");
+        let uri = req.uri().clone();
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&*body, b"Hello");
+
+        if uri == "/307" {
+            http::Response::builder()
+                .status(307)
+                .header("location", "/dst")
+                .body(Body::default())
+                .unwrap()
+        } else {
+            assert_eq!(uri, "/dst");
+            http::Response::default()
+        }
+    });
+
+    let body = Body::from_fn(|| {
+        let chunks: Vec<Result<_, std::io::Error>> = vec![Ok("Hel"), Ok("lo")];
+        futures_util::stream::iter(chunks)
+    });
+    let req = rquest::Client::new()
+        .post(format!("http://{}/307", redirect.addr()))
+        .redirect(Policy::default())
+        .body(body);
+    assert!(req.try_clone().is_some());
+
+    let res = req.send().await.unwrap();
+    assert_eq!(res.url().path(), "/dst");
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
 #[tokio::test]
 async fn test_redirect_removes_sensitive_headers() {
     use tokio::sync::watch;
@@ -335,3 +420,36 @@ async fn test_redirect_302_with_set_cookies() {
     assert_eq!(res.url().as_str(), dst);
     assert_eq!(res.status(), rquest::StatusCode::OK);
 }
+
+#[tokio::test]
+async fn test_redirect_preserve_location() {
+    let server = server::http(move |req| async move {
+        if req.uri() == "/start" {
+            http::Response::builder()
+                .status(302)
+                .header("location", "/dst/./a%2Fb/../c?x=%41#frag")
+                .body(Body::default())
+                .unwrap()
+        } else {
+            assert_eq!(req.uri(), "/dst/./a%2Fb/../c?x=%41");
+            http::Response::default()
+        }
+    });
+
+    let url = format!("http://{}/start", server.addr());
+    let client = rquest::Client::builder()
+        .redirect(Policy::default())
+        .redirect_preserve_location(true)
+        .build()
+        .unwrap();
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+
+    let history = res.history();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].status(), rquest::StatusCode::FOUND);
+    assert_eq!(history[0].previous().as_str(), url);
+    assert_eq!(history[0].location(), "/dst/./a%2Fb/../c?x=%41#frag");
+    assert_eq!(history[0].url().path(), "/dst/c");
+    assert_eq!(res.url(), history[0].url());
+}
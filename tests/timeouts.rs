@@ -88,6 +88,35 @@ async fn connect_timeout() {
     assert!(err.is_timeout());
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::test]
+async fn tls_handshake_timeout() {
+    let _ = env_logger::try_init();
+
+    // Accepts the TCP connection, but never answers the ClientHello.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        let _conns = listener.incoming().collect::<Vec<_>>();
+    });
+
+    let client = rquest::Client::builder()
+        .tls_handshake_timeout(Duration::from_millis(100))
+        .no_proxy()
+        .build()
+        .unwrap();
+
+    let url = format!("https://127.0.0.1:{port}/slow");
+
+    let res = tokio::time::timeout(Duration::from_secs(5), client.get(url).send())
+        .await
+        .expect("handshake timeout should fire before the test timeout");
+
+    let err = res.unwrap_err();
+
+    assert!(err.is_timeout());
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[tokio::test]
 async fn connect_many_timeout_succeeds() {
@@ -204,6 +233,37 @@ async fn read_timeout_applies_to_headers() {
     assert_eq!(err.url().map(|u| u.as_str()), Some(url.as_str()));
 }
 
+#[tokio::test]
+async fn read_timeout_restarts_on_redirect() {
+    let _ = env_logger::try_init();
+
+    let server = server::http(move |req| async move {
+        // each response is slow, but faster than the read timeout
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        if req.uri() == "/redirect" {
+            http::Response::builder()
+                .status(302)
+                .header("location", "/slow")
+                .body(Default::default())
+                .unwrap()
+        } else {
+            http::Response::default()
+        }
+    });
+
+    let client = rquest::Client::builder()
+        .read_timeout(Duration::from_millis(250))
+        .no_proxy()
+        .build()
+        .unwrap();
+
+    let url = format!("http://{}/redirect", server.addr());
+    let res = client.get(&url).send().await.expect("Failed to get");
+
+    assert_eq!(res.url().path(), "/slow");
+    assert_eq!(res.status(), rquest::StatusCode::OK);
+}
+
 #[cfg(feature = "stream")]
 #[tokio::test]
 async fn read_timeout_applies_to_body() {
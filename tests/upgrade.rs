@@ -100,3 +100,55 @@ async fn http2_upgrade() {
     upgraded.read_to_end(&mut buf).await.unwrap();
     assert_eq!(buf, b"bar=foo");
 }
+
+#[tokio::test]
+async fn request_builder_upgrade() {
+    let server = server::http(move |req| {
+        assert_eq!(req.version(), http::Version::HTTP_11);
+        assert_eq!(req.headers()["connection"], "upgrade");
+        let refused = req.uri().path() == "/refused";
+
+        if !refused {
+            tokio::spawn(async move {
+                let mut upgraded =
+                    hyper_util::rt::TokioIo::new(hyper::upgrade::on(req).await.unwrap());
+                upgraded.write_all(b"hello").await.unwrap();
+            });
+        }
+
+        async move {
+            let status = if refused {
+                http::StatusCode::FORBIDDEN
+            } else {
+                http::StatusCode::SWITCHING_PROTOCOLS
+            };
+            http::Response::builder()
+                .status(status)
+                .header(http::header::CONNECTION, "upgrade")
+                .header(http::header::UPGRADE, "custom-proto/1")
+                .body(rquest::Body::default())
+                .unwrap()
+        }
+    });
+
+    let client = rquest::Client::new();
+    let res = client
+        .get(format!("http://{}/tunnel", server.addr()))
+        .upgrade("custom-proto/1")
+        .send()
+        .await
+        .unwrap();
+    let mut upgraded = res.upgrade().await.unwrap();
+    let mut buf = vec![];
+    upgraded.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, b"hello");
+
+    let res = client
+        .get(format!("http://{}/refused", server.addr()))
+        .upgrade("custom-proto/1")
+        .send()
+        .await
+        .unwrap();
+    let err = res.upgrade().await.unwrap_err();
+    assert!(err.is_upgrade());
+}